@@ -1,9 +1,10 @@
 #![no_main]
-use std::{borrow::Borrow, convert::TryInto, str::FromStr};
+use std::{borrow::Borrow, convert::TryInto};
 
 use libfuzzer_sys::fuzz_target;
-use rebuild_rs::{
-    instruction::{create, unlock, Schedule, VestingInstruction},
+use token_vesting_program::{
+    ids,
+    instruction::{create, unlock, CreateOptions, Schedule, VestingInstruction},
     processor::Processor,
     state::VestingSchedule,
 };
@@ -49,9 +50,9 @@ fuzz_target!(|fuzz_instruction: FuzzInstruction| {
 // ----------------------------------------------------------------------------- helpers
 
 async fn setup_test_env() -> (BanksClient, Keypair, Hash, Pubkey) {
-    let program_id = Pubkey::from_str("SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM").unwrap();
+    let program_id = ids::devnet();
     let (mut banks_client, payer, recent_blockhash) = ProgramTest::new(
-        "rebuild_rs", //must match crate name or cargo test-bpf won't work
+        "token_vesting_program", //must match crate name or cargo test-bpf won't work
         program_id,
         processor!(Processor::process_instruction),
     )
@@ -291,7 +292,7 @@ async fn test_init_create_unlock_flow() {
         &mint_keypair.pubkey(),
     );
 
-    let s = rebuild_rs::instruction::Schedule {
+    let s = token_vesting_program::instruction::Schedule {
         release_time: 1,
         amount: 111,
     };
@@ -311,6 +312,10 @@ async fn test_init_create_unlock_flow() {
         &dest_token_acc_key,
         &mint_keypair.pubkey(),
         schedules,
+        CreateOptions {
+            allow_immediate: true,
+            ..CreateOptions::default()
+        },
         seeds,
     )
     .unwrap();
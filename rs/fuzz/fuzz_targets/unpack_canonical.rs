@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use token_vesting_program::instruction::VestingInstruction;
+
+// Any byte string that unpacks must be the one and only encoding of that instruction,
+// ie packing it back has to give the exact same bytes - no ignored trailing data, no alternative layouts
+fuzz_target!(|data: &[u8]| {
+    if let Ok(instruction) = VestingInstruction::unpack(data) {
+        assert_eq!(instruction.pack(), data, "{:?}", instruction);
+    }
+});
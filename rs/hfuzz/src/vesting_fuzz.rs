@@ -1,12 +1,19 @@
-use std::{collections::HashMap, convert::TryInto, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+};
 
 use honggfuzz::fuzz;
-use rebuild_rs::{
-    instruction::{create, init, Schedule, VestingInstruction},
+use token_vesting_program::{
+    ids,
+    instruction::{create, init, unlock, CreateOptions, Schedule, VestingInstruction},
+    pda::find_vesting_address_for_seeds,
     processor::Processor,
+    state::VestingScheduleHeader,
 };
 use solana_program::{
-    instruction::{Instruction, InstructionError},
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction, system_program,
@@ -14,14 +21,16 @@ use solana_program::{
 };
 use solana_program_test::*;
 use solana_sdk::{
-    account::Account,
-    hash::Hash,
+    account::{Account, AccountSharedData},
     signature::{Keypair, Signer},
     transaction::{Transaction, TransactionError},
     transport::TransportError,
 };
 use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
-use spl_token::instruction::{initialize_mint, mint_to};
+use spl_token::{
+    instruction::{initialize_mint, mint_to, transfer},
+    state::Account as TokenAccount,
+};
 
 // ----------------------------------------------------------------------------- structs / consts
 
@@ -55,6 +64,120 @@ pub struct FuzzInstruction {
     // This flag decides wether the instruction will be executed with inputs that should
     // not provoke any errors. (The accounts and contracts will be set up before if needed)
     correct_inputs: bool,
+    // Only used together with correct_inputs on a Create. If set, the freshly created vesting account's bytes get
+    // overwritten before an Unlock is attempted against it - see run_corrupted_account_ix
+    corruption: Option<AccountCorruption>,
+    // Only used together with correct_inputs on a Create, when there's no corruption. Sends the contract's Init,
+    // Create and Unlock as transactions of their own, with unrelated ops in between - see run_interleaved_ix
+    interleaving: Option<Interleaving>,
+    // Only used together with correct_inputs on a Create, when there's no corruption or interleaving. Applied in order
+    // to the account list of an otherwise correct Unlock - see run_permuted_accounts_ix
+    account_permutations: Vec<AccountPermutation>,
+}
+
+/// Ways of messing up a vesting account's data behind the program's back
+#[derive(Debug, arbitrary::Arbitrary, Clone)]
+pub enum AccountCorruption {
+    FlipInitialized,
+    CorruptSchedules { offset: u16, bytes: Vec<u8> },
+    Truncate { len: u16 },
+}
+
+impl AccountCorruption {
+    fn apply(&self, data: &mut Vec<u8>) {
+        match self {
+            Self::FlipInitialized => {
+                if let Some(is_initialized) = data.get_mut(VestingScheduleHeader::LEN - 1) {
+                    *is_initialized ^= 1;
+                }
+            }
+            Self::CorruptSchedules { offset, bytes } => {
+                // only ever touch the bytes after the header
+                let schedules_len = data.len().saturating_sub(VestingScheduleHeader::LEN);
+                if schedules_len == 0 {
+                    return;
+                }
+                for (i, b) in bytes.iter().enumerate() {
+                    let pos = VestingScheduleHeader::LEN + (*offset as usize + i) % schedules_len;
+                    data[pos] = *b;
+                }
+            }
+            Self::Truncate { len } => {
+                let new_len = *len as usize % (data.len() + 1);
+                data.truncate(new_len);
+            }
+        }
+    }
+}
+
+/// Ways of messing up an instruction's account list. Indexes wrap around the list's length
+#[derive(Debug, arbitrary::Arbitrary, Clone)]
+pub enum AccountPermutation {
+    Swap { a: u8, b: u8 },
+    Duplicate { index: u8, at: u8 },
+    Remove { index: u8 },
+    FlipSigner { index: u8 },
+    FlipWritable { index: u8 },
+}
+
+impl AccountPermutation {
+    fn apply(&self, accounts: &mut Vec<AccountMeta>) {
+        if accounts.is_empty() {
+            return;
+        }
+        let wrap = |i: &u8, len: usize| *i as usize % len;
+        match self {
+            Self::Swap { a, b } => {
+                let len = accounts.len();
+                accounts.swap(wrap(a, len), wrap(b, len));
+            }
+            Self::Duplicate { index, at } => {
+                let duplicate = accounts[wrap(index, accounts.len())].clone();
+                let at = wrap(at, accounts.len() + 1);
+                accounts.insert(at, duplicate);
+            }
+            Self::Remove { index } => {
+                accounts.remove(wrap(index, accounts.len()));
+            }
+            Self::FlipSigner { index } => {
+                let i = wrap(index, accounts.len());
+                accounts[i].is_signer ^= true;
+            }
+            Self::FlipWritable { index } => {
+                let i = wrap(index, accounts.len());
+                accounts[i].is_writable ^= true;
+            }
+        }
+    }
+}
+
+/// A contract's lifecycle split across transactions, in any order and with other transactions landing in between
+#[derive(Debug, arbitrary::Arbitrary, Clone)]
+pub struct Interleaving {
+    // sent one transaction each, in this order. Only the first MAX_INTERLEAVED_STEPS are
+    steps: Vec<ContractStep>,
+    // one is sent after every step, wrapping around. Nothing is when empty
+    unrelated_ops: Vec<UnrelatedOp>,
+}
+
+const MAX_INTERLEAVED_STEPS: usize = 8;
+
+#[derive(Debug, arbitrary::Arbitrary, Clone, Copy, PartialEq)]
+pub enum ContractStep {
+    Init,
+    Create,
+    Unlock,
+}
+
+/// What anyone else could do to a contract's accounts between its transactions
+#[derive(Debug, arbitrary::Arbitrary, Clone)]
+pub enum UnrelatedOp {
+    // lamports sent to the vesting PDA, before or after Init created it
+    FundVestingAccount { lamports: u32 },
+    MintToVestingTokenAccount { amount: u64 },
+    // the granter moving tokens out of the source, to the destination
+    DrainSource { amount: u64 },
+    Nothing,
 }
 
 /// Use u8 as an account id to simplify the address space and re-use accounts
@@ -71,8 +194,7 @@ fn main() {
         token_program_id: spl_token::id(),
         clock_program_id: sysvar::clock::id(),
         rent_program_id: sysvar::rent::id(),
-        vesting_program_id: Pubkey::from_str("SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM")
-            .unwrap(),
+        vesting_program_id: ids::devnet(),
         mint_authority_keypair: Keypair::new(),
     };
 
@@ -85,7 +207,7 @@ fn main() {
             // }
 
             let mut program_test = ProgramTest::new(
-                "rebuild_rs",
+                "token_vesting_program",
                 token_vesting_testenv.vesting_program_id,
                 processor!(Processor::process_instruction),
             );
@@ -102,9 +224,7 @@ fn main() {
 
             rt.block_on(run_fuzz_instructions(
                 &token_vesting_testenv,
-                &mut test_state.banks_client,
-                test_state.payer,
-                test_state.last_blockhash,
+                &mut test_state,
                 fuzz_instructions,
             ));
         });
@@ -113,11 +233,13 @@ fn main() {
 
 async fn run_fuzz_instructions(
     token_vesting_testenv: &TokenVestingEnv,
-    banks_client: &mut BanksClient,
-    correct_payer: Keypair,
-    recent_blockhash: Hash,
+    test_state: &mut ProgramTestContext,
     fuzz_instructions: Vec<FuzzInstruction>,
 ) {
+    let correct_payer = clone_keypair(&test_state.payer);
+    let recent_blockhash = test_state.last_blockhash;
+    let vesting_pdas = derived_vesting_pdas(token_vesting_testenv, &fuzz_instructions);
+
     // the reason we need a HashMap is because the fuzzer is generating u8 values - and we need Pubkeys/Keypairs
     // so we have to convert u8s -> into pubkeys/keypairs and store them
     let mut vesting_account_keys: HashMap<AccountId, Pubkey> = HashMap::new();
@@ -158,6 +280,89 @@ async fn run_fuzz_instructions(
             .entry(ix.payer_key)
             .or_insert_with(|| Keypair::new()); //this will be empty, no sol in it
 
+        // corrupted account runs need their own transactions, so they don't go into the global one
+        if let FuzzInstruction {
+            instruction: VestingInstruction::Create { .. },
+            correct_inputs: true,
+            corruption: Some(corruption),
+            ..
+        } = &ix
+        {
+            run_corrupted_account_ix(
+                token_vesting_testenv,
+                test_state,
+                &ix,
+                corruption,
+                &vesting_pdas,
+                &correct_payer,
+                mint_keys.get(&ix.mint_key).unwrap(),
+                source_token_account_owner_keys
+                    .get(&ix.source_token_account_owner_key)
+                    .unwrap(),
+                destination_token_owner_keys
+                    .get(&ix.destination_token_owner_key)
+                    .unwrap(),
+            )
+            .await;
+            continue;
+        }
+
+        // and for contracts whose lifecycle is split across transactions
+        if let FuzzInstruction {
+            instruction: VestingInstruction::Create { .. },
+            correct_inputs: true,
+            corruption: None,
+            interleaving: Some(interleaving),
+            ..
+        } = &ix
+        {
+            run_interleaved_ix(
+                token_vesting_testenv,
+                test_state,
+                &ix,
+                interleaving,
+                &vesting_pdas,
+                &correct_payer,
+                mint_keys.get(&ix.mint_key).unwrap(),
+                source_token_account_owner_keys
+                    .get(&ix.source_token_account_owner_key)
+                    .unwrap(),
+                destination_token_owner_keys
+                    .get(&ix.destination_token_owner_key)
+                    .unwrap(),
+            )
+            .await;
+            continue;
+        }
+
+        // same for permuted account lists
+        if let FuzzInstruction {
+            instruction: VestingInstruction::Create { .. },
+            correct_inputs: true,
+            corruption: None,
+            ..
+        } = &ix
+        {
+            if !ix.account_permutations.is_empty() {
+                run_permuted_accounts_ix(
+                    token_vesting_testenv,
+                    test_state,
+                    &ix,
+                    &vesting_pdas,
+                    &correct_payer,
+                    mint_keys.get(&ix.mint_key).unwrap(),
+                    source_token_account_owner_keys
+                        .get(&ix.source_token_account_owner_key)
+                        .unwrap(),
+                    destination_token_owner_keys
+                        .get(&ix.destination_token_owner_key)
+                        .unwrap(),
+                )
+                .await;
+                continue;
+            }
+        }
+
         let (mut output_ix, mut signer_keys) = run_fuzz_ix(
             &token_vesting_testenv,
             &ix,
@@ -192,38 +397,562 @@ async fn run_fuzz_instructions(
         .chain(global_signer_keys.iter())
         .collect::<Vec<&Keypair>>();
     tx.partial_sign(&signers, recent_blockhash);
-    banks_client
-        .process_transaction(tx)
+    process_transaction_checked(test_state, tx, &vesting_pdas)
         .await
-        .unwrap_or_else(|e| {
-            if let TransportError::TransactionError(te) = e {
-                match te {
-                    TransactionError::InstructionError(_, ie) => match ie {
-                        InstructionError::InvalidArgument
-                        | InstructionError::InvalidInstructionData
-                        | InstructionError::InvalidAccountData
-                        | InstructionError::InsufficientFunds
-                        | InstructionError::AccountAlreadyInitialized
-                        | InstructionError::InvalidSeeds
-                        | InstructionError::Custom(0) => {}
-                        _ => {
-                            print!("{:?}", ie);
-                            Err(ie).unwrap()
-                        }
-                    },
-                    TransactionError::SignatureFailure
-                    | TransactionError::InvalidAccountForFee
-                    | TransactionError::InsufficientFundsForFee => {}
-                    _ => {
-                        print!("{:?}", te);
-                        panic!()
-                    }
+        .unwrap_or_else(check_transport_error);
+}
+
+// Submits a transaction and asserts that every token account which lost tokens in it was either a source whose owner
+// signed the transaction, or a vesting token account owned by one of the correctly derived vesting PDAs. Anything else
+// means the program signed for an account it has no authority over
+async fn process_transaction_checked(
+    test_state: &mut ProgramTestContext,
+    tx: Transaction,
+    vesting_pdas: &HashSet<Pubkey>,
+) -> Result<(), TransportError> {
+    let message = tx.message.clone();
+    let before = token_snapshot(&mut test_state.banks_client, &message.account_keys).await;
+    let result = test_state.banks_client.process_transaction(tx).await;
+    let after = token_snapshot(&mut test_state.banks_client, &message.account_keys).await;
+
+    let signers = &message.account_keys[..message.header.num_required_signatures as usize];
+    for (key, (owner, amount_before)) in before.iter() {
+        // a closed account counts as emptied
+        let amount_after = after.get(key).map_or(0, |(_, amount)| *amount);
+        if amount_after >= *amount_before {
+            continue;
+        }
+        assert!(
+            signers.contains(owner) || vesting_pdas.contains(owner),
+            "token account {} lost {} tokens, but its owner {} neither signed nor is a vesting PDA",
+            key,
+            amount_before - amount_after,
+            owner
+        );
+    }
+    result
+}
+
+// Owner and balance of every spl-token account among the keys
+async fn token_snapshot(
+    banks_client: &mut BanksClient,
+    keys: &[Pubkey],
+) -> HashMap<Pubkey, (Pubkey, u64)> {
+    let mut snapshot = HashMap::new();
+    for key in keys {
+        let account = match banks_client.get_account(*key).await.unwrap() {
+            Some(account) if account.owner == spl_token::id() => account,
+            _ => continue,
+        };
+        if let Ok(token_account) = TokenAccount::unpack(&account.data) {
+            snapshot.insert(*key, (token_account.owner, token_account.amount));
+        }
+    }
+    snapshot
+}
+
+/// Panics on any error the program isn't supposed to produce - those are the bugs we're fuzzing for
+fn check_transport_error(e: TransportError) {
+    if let TransportError::TransactionError(te) = e {
+        match te {
+            TransactionError::InstructionError(_, ie) => match ie {
+                InstructionError::InvalidArgument
+                | InstructionError::InvalidInstructionData
+                | InstructionError::InvalidAccountData
+                | InstructionError::InsufficientFunds
+                | InstructionError::AccountAlreadyInitialized
+                | InstructionError::InvalidSeeds
+                | InstructionError::UninitializedAccount //header with is_initialized flipped
+                // permuted account lists
+                | InstructionError::NotEnoughAccountKeys
+                | InstructionError::MissingRequiredSignature
+                | InstructionError::IncorrectProgramId
+                | InstructionError::PrivilegeEscalation
+                | InstructionError::ReadonlyLamportChange
+                | InstructionError::ReadonlyDataModified
+                | InstructionError::ExternalAccountDataModified
+                | InstructionError::ExternalAccountLamportSpend
+                | InstructionError::Custom(4) //spl-token's OwnerMismatch
+                | InstructionError::Custom(0)
+                | InstructionError::Custom(1) => {} //spl-token's InsufficientFunds, when corrupted schedules claim more than the vesting account holds
+                _ => {
+                    print!("{:?}", ie);
+                    Err(ie).unwrap()
                 }
-            } else {
-                print!("{:?}", e);
+            },
+            TransactionError::SignatureFailure
+            | TransactionError::InvalidAccountForFee
+            | TransactionError::InsufficientFundsForFee => {}
+            _ => {
+                print!("{:?}", te);
                 panic!()
             }
-        });
+        }
+    } else {
+        print!("{:?}", e);
+        panic!()
+    }
+}
+
+/// Keys of a contract set up by setup_contract
+struct ContractKeys {
+    seeds: [u8; 32],
+    vesting_account_key: Pubkey,
+    vesting_token_key: Pubkey,
+    destination_token_key: Pubkey,
+}
+
+// Inits and creates a healthy contract in its own transaction. Returns None if the fuzzed inputs don't make for one,
+// eg the schedules add up to more than the source account holds
+async fn setup_contract(
+    token_vesting_testenv: &TokenVestingEnv,
+    test_state: &mut ProgramTestContext,
+    ix: &FuzzInstruction,
+    vesting_pdas: &HashSet<Pubkey>,
+    correct_payer: &Keypair,
+    mint_key: &Keypair,
+    source_token_account_owner_key: &Keypair,
+    destination_token_owner_key: &Keypair,
+) -> Option<ContractKeys> {
+    let (correct_seeds, correct_vesting_account_key, correct_vesting_token_key) =
+        correct_vesting_keys(token_vesting_testenv, ix.seeds, mint_key);
+    let correct_source_token_account_key =
+        get_associated_token_address(&source_token_account_owner_key.pubkey(), &mint_key.pubkey());
+    // unlike the Create branch in run_fuzz_ix, the destination has to be a real token account for unlock to get anywhere
+    let correct_destination_token_key =
+        get_associated_token_address(&destination_token_owner_key.pubkey(), &mint_key.pubkey());
+
+    let mut setup_ixs = vec![init(
+        &token_vesting_testenv.system_program_id,
+        &token_vesting_testenv.rent_program_id,
+        &token_vesting_testenv.vesting_program_id,
+        &correct_payer.pubkey(),
+        &correct_vesting_account_key,
+        correct_seeds,
+        ix.number_of_schedules as u32,
+    )
+    .unwrap()];
+    setup_ixs.append(&mut create_fuzzinstruction(
+        token_vesting_testenv,
+        ix,
+        correct_payer,
+        &correct_source_token_account_key,
+        source_token_account_owner_key,
+        &correct_destination_token_key,
+        &destination_token_owner_key.pubkey(),
+        &correct_vesting_account_key,
+        &correct_vesting_token_key,
+        correct_seeds,
+        mint_key,
+        ix.source_token_amount,
+    ));
+
+    let mut setup_tx = Transaction::new_with_payer(&setup_ixs, Some(&correct_payer.pubkey()));
+    let setup_signers = vec![
+        correct_payer,
+        mint_key,
+        &token_vesting_testenv.mint_authority_keypair,
+        source_token_account_owner_key,
+    ];
+    setup_tx.partial_sign(&setup_signers, test_state.last_blockhash);
+    if let Err(e) = process_transaction_checked(test_state, setup_tx, vesting_pdas).await {
+        check_transport_error(e);
+        return None;
+    }
+
+    Some(ContractKeys {
+        seeds: correct_seeds,
+        vesting_account_key: correct_vesting_account_key,
+        vesting_token_key: correct_vesting_token_key,
+        destination_token_key: correct_destination_token_key,
+    })
+}
+
+fn unlock_contract_ix(token_vesting_testenv: &TokenVestingEnv, keys: &ContractKeys) -> Instruction {
+    unlock(
+        &token_vesting_testenv.vesting_program_id,
+        &token_vesting_testenv.token_program_id,
+        &token_vesting_testenv.clock_program_id,
+        &keys.vesting_account_key,
+        &keys.vesting_token_key,
+        &keys.destination_token_key,
+        keys.seeds,
+    )
+    .unwrap()
+}
+
+// Submits an Unlock and checks that tokens only ever moved from the vesting token account to the destination,
+// and that a failed Unlock moved nothing at all
+async fn unlock_and_check_balances(
+    test_state: &mut ProgramTestContext,
+    vesting_pdas: &HashSet<Pubkey>,
+    correct_payer: &Keypair,
+    keys: &ContractKeys,
+    unlock_ix: Instruction,
+) {
+    let mut unlock_tx = Transaction::new_with_payer(&[unlock_ix], Some(&correct_payer.pubkey()));
+    // a permutation may have asked for a signature we don't hold - nothing to submit then
+    if unlock_tx
+        .try_sign(&[correct_payer], test_state.last_blockhash)
+        .is_err()
+    {
+        return;
+    }
+
+    let token_keys = [keys.vesting_token_key, keys.destination_token_key];
+    let balances_before = token_balances(&mut test_state.banks_client, &token_keys).await;
+    let result = process_transaction_checked(test_state, unlock_tx, vesting_pdas).await;
+    let balances_after = token_balances(&mut test_state.banks_client, &token_keys).await;
+
+    assert_eq!(
+        balances_before.iter().map(|&b| b as u128).sum::<u128>(),
+        balances_after.iter().map(|&b| b as u128).sum::<u128>(),
+        "tokens leaked out of the vesting/destination pair"
+    );
+    match result {
+        Ok(()) => assert!(balances_after[1] >= balances_before[1]),
+        Err(e) => {
+            check_transport_error(e);
+            assert_eq!(balances_before, balances_after, "failed unlock moved funds");
+        }
+    }
+}
+
+// Sets up a healthy contract, overwrites its bytes with set_account and then tries to unlock it.
+// The program must fail cleanly (or succeed) without panicking, and no tokens may appear or disappear
+async fn run_corrupted_account_ix(
+    token_vesting_testenv: &TokenVestingEnv,
+    test_state: &mut ProgramTestContext,
+    ix: &FuzzInstruction,
+    corruption: &AccountCorruption,
+    vesting_pdas: &HashSet<Pubkey>,
+    correct_payer: &Keypair,
+    mint_key: &Keypair,
+    source_token_account_owner_key: &Keypair,
+    destination_token_owner_key: &Keypair,
+) {
+    let keys = match setup_contract(
+        token_vesting_testenv,
+        test_state,
+        ix,
+        vesting_pdas,
+        correct_payer,
+        mint_key,
+        source_token_account_owner_key,
+        destination_token_owner_key,
+    )
+    .await
+    {
+        Some(keys) => keys,
+        None => return,
+    };
+
+    let mut vesting_account = test_state
+        .banks_client
+        .get_account(keys.vesting_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    corruption.apply(&mut vesting_account.data);
+    test_state.set_account(
+        &keys.vesting_account_key,
+        &AccountSharedData::from(vesting_account),
+    );
+
+    let unlock_ix = unlock_contract_ix(token_vesting_testenv, &keys);
+    unlock_and_check_balances(test_state, vesting_pdas, correct_payer, &keys, unlock_ix).await;
+}
+
+// Sets up a healthy contract and tries to unlock it with a shuffled, duplicated, shortened or re-flagged account
+// list. Whatever the list, the program must fail cleanly (or succeed) and funds may only go to the destination
+async fn run_permuted_accounts_ix(
+    token_vesting_testenv: &TokenVestingEnv,
+    test_state: &mut ProgramTestContext,
+    ix: &FuzzInstruction,
+    vesting_pdas: &HashSet<Pubkey>,
+    correct_payer: &Keypair,
+    mint_key: &Keypair,
+    source_token_account_owner_key: &Keypair,
+    destination_token_owner_key: &Keypair,
+) {
+    let keys = match setup_contract(
+        token_vesting_testenv,
+        test_state,
+        ix,
+        vesting_pdas,
+        correct_payer,
+        mint_key,
+        source_token_account_owner_key,
+        destination_token_owner_key,
+    )
+    .await
+    {
+        Some(keys) => keys,
+        None => return,
+    };
+
+    let mut unlock_ix = unlock_contract_ix(token_vesting_testenv, &keys);
+    for permutation in ix.account_permutations.iter() {
+        permutation.apply(&mut unlock_ix.accounts);
+    }
+    unlock_and_check_balances(test_state, vesting_pdas, correct_payer, &keys, unlock_ix).await;
+}
+
+// Sets up a contract's mint and token accounts, then sends its Init, Create and Unlock one transaction each in the
+// fuzzed order, with unrelated ops landing in between. Whatever the interleaving, Create may only fund an account Init
+// has written the header of, and Unlock may only go through once Create has
+async fn run_interleaved_ix(
+    token_vesting_testenv: &TokenVestingEnv,
+    test_state: &mut ProgramTestContext,
+    ix: &FuzzInstruction,
+    interleaving: &Interleaving,
+    vesting_pdas: &HashSet<Pubkey>,
+    correct_payer: &Keypair,
+    mint_key: &Keypair,
+    source_token_account_owner_key: &Keypair,
+    destination_token_owner_key: &Keypair,
+) {
+    let (correct_seeds, correct_vesting_account_key, correct_vesting_token_key) =
+        correct_vesting_keys(token_vesting_testenv, ix.seeds, mint_key);
+    let correct_source_token_account_key =
+        get_associated_token_address(&source_token_account_owner_key.pubkey(), &mint_key.pubkey());
+    let keys = ContractKeys {
+        seeds: correct_seeds,
+        vesting_account_key: correct_vesting_account_key,
+        vesting_token_key: correct_vesting_token_key,
+        destination_token_key: get_associated_token_address(
+            &destination_token_owner_key.pubkey(),
+            &mint_key.pubkey(),
+        ),
+    };
+
+    // the mint and the token accounts go first, the Create closing the list waits for its turn
+    let mut setup_ixs = create_fuzzinstruction(
+        token_vesting_testenv,
+        ix,
+        correct_payer,
+        &correct_source_token_account_key,
+        source_token_account_owner_key,
+        &keys.destination_token_key,
+        &destination_token_owner_key.pubkey(),
+        &keys.vesting_account_key,
+        &keys.vesting_token_key,
+        keys.seeds,
+        mint_key,
+        ix.source_token_amount,
+    );
+    let create_ix = setup_ixs.pop().unwrap();
+    let setup_signers = vec![
+        correct_payer,
+        mint_key,
+        &token_vesting_testenv.mint_authority_keypair,
+    ];
+    if let Err(e) = send_unique(
+        test_state,
+        vesting_pdas,
+        correct_payer,
+        setup_ixs,
+        setup_signers,
+    )
+    .await
+    {
+        check_transport_error(e);
+        return;
+    }
+
+    let init_ix = init(
+        &token_vesting_testenv.system_program_id,
+        &token_vesting_testenv.rent_program_id,
+        &token_vesting_testenv.vesting_program_id,
+        &correct_payer.pubkey(),
+        &keys.vesting_account_key,
+        keys.seeds,
+        ix.number_of_schedules as u32,
+    )
+    .unwrap();
+    let unlock_ix = unlock_contract_ix(token_vesting_testenv, &keys);
+
+    let mut initialized = false;
+    let mut created = false;
+    let mut unrelated_ops = interleaving.unrelated_ops.iter().cycle();
+    for step in interleaving.steps.iter().take(MAX_INTERLEAVED_STEPS) {
+        let (step_ix, signers) = match step {
+            ContractStep::Init => (init_ix.clone(), vec![correct_payer]),
+            ContractStep::Create => (
+                create_ix.clone(),
+                vec![correct_payer, source_token_account_owner_key],
+            ),
+            ContractStep::Unlock => (unlock_ix.clone(), vec![correct_payer]),
+        };
+        let landed = match send_unique(
+            test_state,
+            vesting_pdas,
+            correct_payer,
+            vec![step_ix],
+            signers,
+        )
+        .await
+        {
+            Ok(()) => true,
+            Err(e) => {
+                check_transport_error(e);
+                false
+            }
+        };
+
+        match step {
+            ContractStep::Init => initialized |= landed,
+            ContractStep::Create if landed => {
+                assert!(
+                    initialized,
+                    "Create funded vesting account {} before Init wrote its header",
+                    keys.vesting_account_key
+                );
+                let vesting_account = test_state
+                    .banks_client
+                    .get_account(keys.vesting_account_key)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let header = VestingScheduleHeader::unpack_account(&vesting_account.data).unwrap();
+                assert_eq!(header.destination_address, keys.destination_token_key);
+                assert_eq!(header.mint_address, mint_key.pubkey());
+                created = true;
+            }
+            ContractStep::Unlock if landed => assert!(
+                created,
+                "Unlock went through on vesting account {} before Create completed",
+                keys.vesting_account_key
+            ),
+            _ => {}
+        }
+
+        if let Some(op) = unrelated_ops.next() {
+            // these aren't what's being fuzzed, failing is fine as long as nobody lost tokens they shouldn't have
+            let (op_ixs, op_signers) = unrelated_op_ixs(
+                op,
+                token_vesting_testenv,
+                &keys,
+                correct_payer,
+                mint_key,
+                source_token_account_owner_key,
+                &correct_source_token_account_key,
+            );
+            let _ = send_unique(test_state, vesting_pdas, correct_payer, op_ixs, op_signers).await;
+        }
+    }
+}
+
+fn unrelated_op_ixs<'a>(
+    op: &UnrelatedOp,
+    token_vesting_testenv: &'a TokenVestingEnv,
+    keys: &ContractKeys,
+    correct_payer: &'a Keypair,
+    mint_key: &Keypair,
+    source_token_account_owner_key: &'a Keypair,
+    source_token_account_key: &Pubkey,
+) -> (Vec<Instruction>, Vec<&'a Keypair>) {
+    match op {
+        UnrelatedOp::FundVestingAccount { lamports } => (
+            vec![system_instruction::transfer(
+                &correct_payer.pubkey(),
+                &keys.vesting_account_key,
+                *lamports as u64,
+            )],
+            vec![correct_payer],
+        ),
+        UnrelatedOp::MintToVestingTokenAccount { amount } => (
+            vec![mint_to(
+                &spl_token::id(),
+                &mint_key.pubkey(),
+                &keys.vesting_token_key,
+                &token_vesting_testenv.mint_authority_keypair.pubkey(),
+                &[],
+                *amount,
+            )
+            .unwrap()],
+            vec![correct_payer, &token_vesting_testenv.mint_authority_keypair],
+        ),
+        UnrelatedOp::DrainSource { amount } => (
+            vec![transfer(
+                &spl_token::id(),
+                source_token_account_key,
+                &keys.destination_token_key,
+                &source_token_account_owner_key.pubkey(),
+                &[],
+                *amount,
+            )
+            .unwrap()],
+            vec![correct_payer, source_token_account_owner_key],
+        ),
+        UnrelatedOp::Nothing => (vec![], vec![correct_payer]),
+    }
+}
+
+// Sends `instructions` in a transaction of their own. A zero-lamport transfer to a fresh key tells it apart from an
+// identical one sent earlier under the same blockhash, which the bank would turn away as already processed
+async fn send_unique(
+    test_state: &mut ProgramTestContext,
+    vesting_pdas: &HashSet<Pubkey>,
+    correct_payer: &Keypair,
+    mut instructions: Vec<Instruction>,
+    signers: Vec<&Keypair>,
+) -> Result<(), TransportError> {
+    instructions.push(system_instruction::transfer(
+        &correct_payer.pubkey(),
+        &Pubkey::new_unique(),
+        0,
+    ));
+    let mut tx = Transaction::new_with_payer(&instructions, Some(&correct_payer.pubkey()));
+    tx.partial_sign(&signers, test_state.last_blockhash);
+    process_transaction_checked(test_state, tx, vesting_pdas).await
+}
+
+async fn token_balances(banks_client: &mut BanksClient, keys: &[Pubkey]) -> Vec<u64> {
+    let mut balances = vec![];
+    for key in keys {
+        let account = banks_client.get_account(*key).await.unwrap().unwrap();
+        balances.push(TokenAccount::unpack(&account.data).unwrap().amount);
+    }
+    balances
+}
+
+// we use the seeds to derive a real PDA account. Init finds the same bump and keeps it in the header, so the seeds
+// stay as they are
+fn correct_vesting_account_key(
+    token_vesting_testenv: &TokenVestingEnv,
+    seeds: [u8; 32],
+) -> ([u8; 32], Pubkey) {
+    let (correct_vesting_account_key, _) =
+        find_vesting_address_for_seeds(&seeds, &token_vesting_testenv.vesting_program_id);
+    (seeds, correct_vesting_account_key)
+}
+
+// The vesting accounts a run may legitimately move tokens out of - one per fuzzed seed
+fn derived_vesting_pdas(
+    token_vesting_testenv: &TokenVestingEnv,
+    fuzz_instructions: &[FuzzInstruction],
+) -> HashSet<Pubkey> {
+    fuzz_instructions
+        .iter()
+        .map(|ix| correct_vesting_account_key(token_vesting_testenv, ix.seeds).1)
+        .collect()
+}
+
+fn correct_vesting_keys(
+    token_vesting_testenv: &TokenVestingEnv,
+    seeds: [u8; 32],
+    mint_key: &Keypair,
+) -> ([u8; 32], Pubkey, Pubkey) {
+    let (correct_seeds, correct_vesting_account_key) =
+        correct_vesting_account_key(token_vesting_testenv, seeds);
+    let correct_vesting_token_key =
+        get_associated_token_address(&correct_vesting_account_key, &mint_key.pubkey());
+    (
+        correct_seeds,
+        correct_vesting_account_key,
+        correct_vesting_token_key,
+    )
 }
 
 fn run_fuzz_ix(
@@ -242,16 +971,8 @@ fn run_fuzz_ix(
     // basically, depending on the boolean generated by the fuzzer, we can decide to try to run an tx with correct inputs or with wrong inputs
     if ix.correct_inputs {
         //if we decide to run a correct tx, we first need to fix some inputs
-        //we use the seeds to derive a real PDA account, and then update the seeds to it captures the bump
-        let mut correct_seeds = ix.seeds;
-        let (correct_vesting_account_key, bump) = Pubkey::find_program_address(
-            &[&correct_seeds[..31]], //take 31 out of 32 bytes to generate the bump
-            &token_vesting_testenv.vesting_program_id,
-        );
-        correct_seeds[31] = bump; //assign that bump as 32nd byte into the array. now this array represents the entire seed used to derive the vesting account
-                                  // from vesting account generate vesting token account
-        let correct_vesting_token_key =
-            get_associated_token_address(&correct_vesting_account_key, &mint_key.pubkey());
+        let (correct_seeds, correct_vesting_account_key, correct_vesting_token_key) =
+            correct_vesting_keys(token_vesting_testenv, ix.seeds, mint_key);
         // also separately generate the source token account
         let correct_source_token_account_key = get_associated_token_address(
             &source_token_account_owner_key.pubkey(),
@@ -443,6 +1164,10 @@ fn create_fuzzinstruction(
         &destination_token_key,
         &mint_key.pubkey(),
         fuzz_instruction.schedules.clone()[..used_number_of_schedules.into()].into(),
+        CreateOptions {
+            allow_immediate: true,
+            ..CreateOptions::default()
+        },
         correct_seeds,
     )
     .unwrap();
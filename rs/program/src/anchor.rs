@@ -0,0 +1,248 @@
+//! Anchor bindings, so that Anchor programs can take our accounts in their `Accounts` structs instead of slicing
+//! bytes out of an `UncheckedAccount`.
+//!
+//! `Account<'info, T>` checks the owner against a program id known at compile time, and this program doesn't have
+//! one - every deployment has its own. So the types here only deserialize, and `declare_program!`, invoked in the
+//! Anchor crate with the id of the deployment it talks to, wraps them into account types that also check the owner:
+//!
+//! ```ignore
+//! token_vesting_program::declare_program!("SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM");
+//!
+//! #[derive(Accounts)]
+//! pub struct Stake<'info> {
+//!     pub vesting_account: Account<'info, token_vesting::VestingContract>,
+//!     pub vesting_program: Program<'info, token_vesting::TokenVesting>,
+//! }
+//! ```
+//!
+//! The accounts are read-only from Anchor's side: AccountSerialize is a no-op, and only this program can write them
+//! anyway.
+
+pub use anchor_lang;
+use anchor_lang::{error::ErrorCode, AccountDeserialize, AccountSerialize};
+use solana_program::program_pack::Pack;
+
+pub use crate::state::{
+    CompressedVestingTree, DestinationApprovers, DestinationIndex, EmissionCurve, ProgramConfig,
+    VestingScheduleHeader,
+};
+use crate::state::{ProgramAccount, ScheduleCategory, VestingSchedule};
+
+/// A whole vesting account - the header, its schedules, and their categories (all Uncategorized for a Plain
+/// contract). Contracts exported to a new deployment don't deserialize
+#[derive(Clone, Debug, PartialEq)]
+pub struct VestingContract {
+    pub header: VestingScheduleHeader,
+    pub schedules: Vec<VestingSchedule>,
+    pub categories: Vec<ScheduleCategory>,
+}
+
+// the type byte every account starts with doubles as the discriminator, so there's no unchecked path
+
+impl AccountDeserialize for VestingContract {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        match ProgramAccount::unpack_any(buf) {
+            Ok(ProgramAccount::Vesting {
+                header,
+                schedules,
+                categories,
+            }) => Ok(Self {
+                header,
+                schedules,
+                categories,
+            }),
+            _ => Err(ErrorCode::AccountDidNotDeserialize.into()),
+        }
+    }
+}
+
+/// Only the header, for when the schedules aren't needed - skips decoding them
+impl AccountDeserialize for VestingScheduleHeader {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::unpack_account(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl AccountDeserialize for CompressedVestingTree {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::unpack(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl AccountDeserialize for ProgramConfig {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::unpack(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl AccountDeserialize for DestinationIndex {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::unpack(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl AccountDeserialize for DestinationApprovers {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::unpack(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl AccountDeserialize for EmissionCurve {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::unpack(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl AccountSerialize for VestingContract {}
+
+impl AccountSerialize for VestingScheduleHeader {}
+
+impl AccountSerialize for CompressedVestingTree {}
+
+impl AccountSerialize for ProgramConfig {}
+
+impl AccountSerialize for DestinationIndex {}
+
+impl AccountSerialize for DestinationApprovers {}
+
+impl AccountSerialize for EmissionCurve {}
+
+/// Declares a `token_vesting` module for the deployment at `$id`, holding:
+/// - `ID` / `id()`, like Anchor's `declare_id!`
+/// - `TokenVesting`, for `Program<'info, token_vesting::TokenVesting>`
+/// - `VestingContract`, `VestingScheduleHeader`, `CompressedVestingTree`, `ProgramConfig`, `DestinationIndex`,
+///   `DestinationApprovers` and `EmissionCurve`, which deref to the types of the same name in this module and check that the account is owned by `$id`
+#[macro_export]
+macro_rules! declare_program {
+    ($id:literal) => {
+        pub mod token_vesting {
+            anchor_lang::declare_id!($id);
+
+            /// The token vesting program, for `Program<'info, TokenVesting>`
+            #[derive(Clone)]
+            pub struct TokenVesting;
+
+            impl $crate::anchor::anchor_lang::Id for TokenVesting {
+                fn id() -> $crate::anchor::anchor_lang::prelude::Pubkey {
+                    ID
+                }
+            }
+
+            $crate::__anchor_owned_account!(VestingContract);
+            $crate::__anchor_owned_account!(VestingScheduleHeader);
+            $crate::__anchor_owned_account!(CompressedVestingTree);
+            $crate::__anchor_owned_account!(ProgramConfig);
+            $crate::__anchor_owned_account!(DestinationIndex);
+            $crate::__anchor_owned_account!(DestinationApprovers);
+            $crate::__anchor_owned_account!(EmissionCurve);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __anchor_owned_account {
+    ($name:ident) => {
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name(pub $crate::anchor::$name);
+
+        impl $crate::anchor::anchor_lang::AccountDeserialize for $name {
+            fn try_deserialize_unchecked(
+                buf: &mut &[u8],
+            ) -> $crate::anchor::anchor_lang::Result<Self> {
+                <$crate::anchor::$name as $crate::anchor::anchor_lang::AccountDeserialize>::try_deserialize_unchecked(buf)
+                    .map(Self)
+            }
+        }
+
+        impl $crate::anchor::anchor_lang::AccountSerialize for $name {}
+
+        impl $crate::anchor::anchor_lang::Owner for $name {
+            fn owner() -> $crate::anchor::anchor_lang::prelude::Pubkey {
+                ID
+            }
+        }
+
+        impl ::std::ops::Deref for $name {
+            type Target = $crate::anchor::$name;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+    };
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::{Account, Pubkey};
+
+    use super::*;
+    use crate::test_utils::{vesting_schedule, VestingFixture};
+
+    crate::declare_program!("SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM");
+
+    #[test]
+    fn test_deserialize_vesting_contract() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let account = f.vesting_account(schedules);
+
+        let contract = VestingContract::try_deserialize(&mut &account.data[..]).unwrap();
+        assert_eq!(
+            contract.schedules,
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 50)]
+        );
+        assert_eq!(
+            contract.categories,
+            vec![ScheduleCategory::Uncategorized; 2]
+        );
+        assert_eq!(
+            VestingScheduleHeader::try_deserialize(&mut &account.data[..]).unwrap(),
+            contract.header
+        );
+
+        // a vesting account is nothing else
+        assert!(ProgramConfig::try_deserialize(&mut &account.data[..]).is_err());
+        assert!(CompressedVestingTree::try_deserialize(&mut &account.data[..]).is_err());
+        assert!(VestingContract::try_deserialize(&mut &account.data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_declared_accounts_check_owner() {
+        let f = VestingFixture::new();
+        let mut account = f.vesting_account(vec![vesting_schedule(10, 100)]);
+
+        // VestingFixture deploys under a random program id, not the declared one
+        assert!(
+            Account::<token_vesting::VestingContract>::try_from(&account.account_info()).is_err()
+        );
+
+        account.owner = token_vesting::ID;
+        let info = account.account_info();
+        let contract = Account::<token_vesting::VestingContract>::try_from(&info).unwrap();
+        assert_eq!(
+            contract.header.destination_address,
+            f.destination_token_account_key
+        );
+        assert_eq!(
+            Account::<token_vesting::VestingScheduleHeader>::try_from(&info)
+                .unwrap()
+                .schedule_count,
+            1
+        );
+        assert!(Account::<token_vesting::CompressedVestingTree>::try_from(&info).is_err());
+        assert!(Account::<token_vesting::ProgramConfig>::try_from(&info).is_err());
+        assert!(Account::<token_vesting::DestinationIndex>::try_from(&info).is_err());
+        assert!(Account::<token_vesting::DestinationApprovers>::try_from(&info).is_err());
+        assert!(Account::<token_vesting::EmissionCurve>::try_from(&info).is_err());
+        assert_eq!(
+            <token_vesting::TokenVesting as anchor_lang::Id>::id(),
+            "SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM"
+                .parse::<Pubkey>()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Wire format of Bonfida's token-vesting program, the one this program was rebuilt from. Only compiled with the
+//! bonfida-compat feature, which also makes the program accept Create instructions encoded for the original - so
+//! one crate can serve clients of either deployment.
+//!
+//! Init, Unlock and ChangeDestination are byte for byte the same in both programs, and so are their account lists.
+//! What differs:
+//! - Create has no allow_immediate, gas or minimum claim fields. The original never checked release times, so an
+//!   upstream Create maps to allow_immediate = true
+//! - the vesting account header only holds the destination, the mint and is_initialized - no account type byte
+//! - Create needs the mint and the destination spl-token account after the original's five accounts. The original
+//!   ignores them, so `create` below passes them for either deployment
+//!
+//! Schedules are laid out the same way after either header.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    instruction::Instruction,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::VestingError::InvalidInstruction,
+    instruction::{
+        account_metas, Schedule, Seeds, VestingInstruction, CREATE_ACCOUNTS, SCHEDULE_SIZE,
+    },
+    state::{RevokePolicy, ScheduleLayout},
+};
+
+/// Length of an upstream Create payload (after the tag) without its schedules
+pub const CREATE_FIXED_LEN: usize = 96;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BonfidaInstruction {
+    /// Same accounts as VestingInstruction::Init
+    Init {
+        seeds: Seeds,
+        number_of_schedules: u32,
+    },
+    /// Same accounts as VestingInstruction::Create, without the optional ones
+    Create {
+        seeds: Seeds,
+        mint_address: Pubkey,
+        destination_token_address: Pubkey,
+        schedules: Vec<Schedule>,
+    },
+    /// Same accounts as VestingInstruction::Unlock, without the optional rebate receiver
+    Unlock { seeds: Seeds },
+    /// Same accounts as VestingInstruction::ChangeDestination
+    ChangeDestination { seeds: Seeds },
+}
+
+impl BonfidaInstruction {
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
+        Ok(match tag {
+            0 => {
+                VestingInstruction::expect_len(rest, 36)?;
+                Self::Init {
+                    seeds: VestingInstruction::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    number_of_schedules: VestingInstruction::unpack_u32(rest, 32)?,
+                }
+            }
+            1 => {
+                VestingInstruction::expect_len_with_tail(rest, CREATE_FIXED_LEN, SCHEDULE_SIZE)?;
+                let mut schedules = vec![];
+                for offset in (CREATE_FIXED_LEN..rest.len()).step_by(SCHEDULE_SIZE) {
+                    schedules.push(Schedule {
+                        release_time: VestingInstruction::unpack_u64(rest, offset)?,
+                        amount: VestingInstruction::unpack_u64(rest, offset + 8)?,
+                    });
+                }
+                Self::Create {
+                    seeds: VestingInstruction::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    mint_address: VestingInstruction::unpack_addr(rest, 32)?,
+                    destination_token_address: VestingInstruction::unpack_addr(rest, 64)?,
+                    schedules,
+                }
+            }
+            2 | 3 => {
+                VestingInstruction::expect_len(rest, 32)?;
+                let seeds = VestingInstruction::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                match tag {
+                    2 => Self::Unlock { seeds },
+                    _ => Self::ChangeDestination { seeds },
+                }
+            }
+            _ => {
+                msg!("unsupported upstream instruction! passed tag: {:?}", tag);
+                return Err(InvalidInstruction.into());
+            }
+        })
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        match self {
+            Self::Init {
+                seeds,
+                number_of_schedules,
+            } => {
+                buf.push(0);
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(&number_of_schedules.to_le_bytes());
+            }
+            Self::Create {
+                seeds,
+                mint_address,
+                destination_token_address,
+                schedules,
+            } => {
+                buf.push(1);
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(&mint_address.to_bytes());
+                buf.extend_from_slice(&destination_token_address.to_bytes());
+                for s in schedules.iter() {
+                    buf.extend_from_slice(&s.release_time.to_le_bytes());
+                    buf.extend_from_slice(&s.amount.to_le_bytes());
+                }
+            }
+            Self::Unlock { seeds } => {
+                buf.push(2);
+                buf.extend_from_slice(seeds);
+            }
+            Self::ChangeDestination { seeds } => {
+                buf.push(3);
+                buf.extend_from_slice(seeds);
+            }
+        }
+        buf
+    }
+}
+
+impl From<BonfidaInstruction> for VestingInstruction {
+    fn from(instruction: BonfidaInstruction) -> Self {
+        match instruction {
+            BonfidaInstruction::Init {
+                seeds,
+                number_of_schedules,
+            } => Self::Init {
+                seeds,
+                number_of_schedules,
+                schedule_layout: ScheduleLayout::Plain,
+            },
+            BonfidaInstruction::Create {
+                seeds,
+                mint_address,
+                destination_token_address,
+                schedules,
+            } => Self::Create {
+                seeds,
+                token_mint_addr: mint_address,
+                token_dest_addr: destination_token_address,
+                allow_immediate: true,
+                gas_rebate: 0,
+                gas_deposit: 0,
+                min_claim_amount: 0,
+                destination_locked: false,
+                forbid_self_dealing: false,
+                revoke_policy: RevokePolicy::ReturnToGranter,
+                relative: false,
+                emergency_authority: Pubkey::default(),
+                revocable: true,
+                schedules,
+                categories: vec![],
+            },
+            BonfidaInstruction::Unlock { seeds } => Self::Unlock {
+                seeds,
+                max_schedules_per_call: 0,
+                max_amount: 0,
+                start_index: None,
+            },
+            BonfidaInstruction::ChangeDestination { seeds } => Self::ChangeDestination {
+                seeds,
+                expected_revision: None,
+            },
+        }
+    }
+}
+
+/// Whether a Create payload (after the tag) is laid out for the upstream program. The two layouts never have the
+/// same length modulo SCHEDULE_SIZE, so there's no ambiguity
+pub fn is_create_payload(rest: &[u8]) -> bool {
+    rest.len() >= CREATE_FIXED_LEN && rest.len() % SCHEDULE_SIZE == CREATE_FIXED_LEN % SCHEDULE_SIZE
+}
+
+// Creates a `Create` instruction for the upstream program
+pub fn create(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    source_token_account_owner_key: &Pubkey,
+    source_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    mint_address: &Pubkey,
+    schedules: Vec<Schedule>,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = BonfidaInstruction::Create {
+        seeds,
+        mint_address: *mint_address,
+        destination_token_address: *destination_token_account_key,
+        schedules,
+    }
+    .pack();
+    let accounts = account_metas(
+        CREATE_ACCOUNTS,
+        &[
+            token_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            source_token_account_owner_key,
+            source_token_account_key,
+            mint_address,
+            destination_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// ----------------------------------------------------------------------------- state
+
+/// Header of a vesting account created by the upstream program
+#[derive(Debug, PartialEq)]
+pub struct BonfidaScheduleHeader {
+    pub destination_address: Pubkey,
+    pub mint_address: Pubkey,
+    pub is_initialized: bool,
+}
+
+impl Sealed for BonfidaScheduleHeader {}
+
+impl IsInitialized for BonfidaScheduleHeader {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for BonfidaScheduleHeader {
+    //pubkey x2 + bool
+    const LEN: usize = 65;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref!(dst, 0, BonfidaScheduleHeader::LEN);
+        let (dst_destination_address, dst_mint_address, dst_is_initialized) =
+            mut_array_refs![dst, 32, 32, 1];
+        dst_destination_address.copy_from_slice(self.destination_address.as_ref());
+        dst_mint_address.copy_from_slice(self.mint_address.as_ref());
+        dst_is_initialized[0] = self.is_initialized as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < BonfidaScheduleHeader::LEN {
+            msg!("passed slice is shorter than 65 bytes");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = array_ref!(src, 0, BonfidaScheduleHeader::LEN);
+        let (src_destination_address, src_mint_address, src_is_initialized) =
+            array_refs![src, 32, 32, 1];
+        let is_initialized = match src_is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Self {
+            destination_address: Pubkey::new_from_array(*src_destination_address),
+            mint_address: Pubkey::new_from_array(*src_mint_address),
+            is_initialized,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // byte strings as produced by the upstream program's own pack functions
+    fn golden(parts: &[(u8, usize)]) -> Vec<u8> {
+        parts.iter().flat_map(|&(byte, n)| vec![byte; n]).collect()
+    }
+
+    fn create_instruction() -> BonfidaInstruction {
+        BonfidaInstruction::Create {
+            seeds: [1; 32],
+            mint_address: Pubkey::new_from_array([2; 32]),
+            destination_token_address: Pubkey::new_from_array([3; 32]),
+            schedules: vec![Schedule {
+                release_time: 10,
+                amount: 20,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_golden_instructions() {
+        let create = golden(&[
+            (1, 1),
+            (1, 32),
+            (2, 32),
+            (3, 32),
+            (10, 1),
+            (0, 7),
+            (20, 1),
+            (0, 7),
+        ]);
+        let vectors = vec![
+            (
+                BonfidaInstruction::Init {
+                    seeds: [1; 32],
+                    number_of_schedules: 2,
+                },
+                golden(&[(0, 1), (1, 32), (2, 1), (0, 3)]),
+            ),
+            (create_instruction(), create),
+            (
+                BonfidaInstruction::Unlock { seeds: [1; 32] },
+                golden(&[(2, 1), (1, 32)]),
+            ),
+            (
+                BonfidaInstruction::ChangeDestination { seeds: [1; 32] },
+                golden(&[(3, 1), (1, 32)]),
+            ),
+        ];
+        for (instruction, bytes) in vectors {
+            assert_eq!(instruction.pack(), bytes);
+            assert_eq!(BonfidaInstruction::unpack(&bytes).unwrap(), instruction);
+            // the program itself accepts every upstream encoding
+            assert_eq!(
+                VestingInstruction::unpack(&bytes).unwrap(),
+                VestingInstruction::from(instruction)
+            );
+        }
+    }
+
+    #[test]
+    fn test_shared_instructions_are_byte_identical() {
+        assert_eq!(
+            BonfidaInstruction::Unlock { seeds: [1; 32] }.pack(),
+            VestingInstruction::Unlock {
+                seeds: [1; 32],
+                max_schedules_per_call: 0,
+                max_amount: 0,
+                start_index: None,
+            }
+            .pack()
+        );
+        assert_eq!(
+            BonfidaInstruction::Init {
+                seeds: [1; 32],
+                number_of_schedules: 2
+            }
+            .pack(),
+            VestingInstruction::Init {
+                seeds: [1; 32],
+                number_of_schedules: 2,
+                schedule_layout: ScheduleLayout::Plain,
+            }
+            .pack()
+        );
+        // while the redesigned Create still unpacks as such
+        let create = VestingInstruction::from(create_instruction());
+        assert_eq!(VestingInstruction::unpack(&create.pack()).unwrap(), create);
+        assert!(!is_create_payload(&create.pack()[1..]));
+    }
+
+    #[test]
+    fn test_golden_header() {
+        let bytes = golden(&[(3, 32), (2, 32), (1, 1)]);
+        let header = BonfidaScheduleHeader {
+            destination_address: Pubkey::new_from_array([3; 32]),
+            mint_address: Pubkey::new_from_array([2; 32]),
+            is_initialized: true,
+        };
+        let mut packed = vec![0; BonfidaScheduleHeader::LEN];
+        header.pack_into_slice(&mut packed);
+        assert_eq!(packed, bytes);
+        assert_eq!(BonfidaScheduleHeader::unpack(&bytes).unwrap(), header);
+    }
+}
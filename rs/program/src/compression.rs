@@ -0,0 +1,389 @@
+//! Compressed vesting: thousands of (destination, release_time, amount) leaves in an spl-account-compression merkle
+//! tree, claimed one at a time by proving the leaf against the tree. What's in here is the leaf hashing, the
+//! account-compression instructions the program calls and an off-chain copy of the tree to reconstruct proofs from
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    keccak::hashv,
+    pubkey::Pubkey,
+};
+
+/// A node in the compressed vesting tree - either a leaf hash or an inner node hash
+pub type Node = [u8; 32];
+
+/// Claimed (or never used) leaves are replaced with this value
+pub const EMPTY_LEAF: Node = [0; 32];
+
+/// Deepest tree we allow - 2^20 leaves is ~1M grants and keeps the proof at 20 accounts
+pub const MAX_TREE_DEPTH: u32 = 20;
+
+// inner nodes are hashed the way spl-account-compression does, without a prefix. Leaves get one, so that an inner
+// node can never be passed off as a leaf
+const LEAF_PREFIX: &[u8] = &[0];
+
+// ----------------------------------------------------------------------------- hashing
+
+/// Hashes a single compressed vesting entry. This is what gets stored in the tree instead of a whole vesting account
+pub fn hash_leaf(destination_address: &Pubkey, release_time: u64, amount: u64) -> Node {
+    hashv(&[
+        LEAF_PREFIX,
+        destination_address.as_ref(),
+        &release_time.to_le_bytes(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+pub fn hash_node(left: &Node, right: &Node) -> Node {
+    hashv(&[left, right]).to_bytes()
+}
+
+/// Walks up from the leaf to the root using the proof. The bits of the index tell us on which side the leaf sits at each level
+pub fn compute_root(leaf: Node, index: u32, proof: &[Node]) -> Node {
+    let mut node = leaf;
+    for (level, sibling) in proof.iter().enumerate() {
+        node = if (index >> level) & 1 == 0 {
+            hash_node(&node, sibling)
+        } else {
+            hash_node(sibling, &node)
+        };
+    }
+    node
+}
+
+pub fn verify_proof(root: &Node, leaf: Node, index: u32, proof: &[Node]) -> bool {
+    compute_root(leaf, index, proof) == *root
+}
+
+// ----------------------------------------------------------------------------- spl-account-compression
+// the leaves live in a concurrent merkle tree of spl-account-compression, with the compressed vesting account as its
+// authority. Its instructions are encoded by hand rather than pulling in the crate, which pins its own solana and
+// anchor versions. Like any anchor program, each starts with the first 8 bytes of sha256("global:<instruction>")
+
+/// The spl-account-compression program, the only one a compressed contract's tree may belong to
+pub mod spl_account_compression {
+    solana_program::declare_id!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+}
+
+/// The noop program spl-account-compression logs tree changes through
+pub mod spl_noop {
+    solana_program::declare_id!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+}
+
+const INIT_EMPTY_MERKLE_TREE: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+const APPEND: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+const REPLACE_LEAF: [u8; 8] = [204, 165, 76, 100, 73, 147, 0, 128];
+
+/// (max depth, max buffer size) pairs spl-account-compression has a tree for, up to MAX_TREE_DEPTH. The buffer is how
+/// many changes a proof can be behind on and still go through, so that concurrent claims don't fail each other
+pub const TREE_SIZES: &[(u32, u32)] = &[
+    (3, 8),
+    (5, 8),
+    (6, 16),
+    (7, 16),
+    (8, 16),
+    (9, 16),
+    (10, 32),
+    (11, 32),
+    (12, 32),
+    (13, 32),
+    (14, 64),
+    (14, 256),
+    (14, 1024),
+    (14, 2048),
+    (15, 64),
+    (16, 64),
+    (17, 64),
+    (18, 64),
+    (19, 64),
+    (20, 64),
+    (20, 256),
+    (20, 1024),
+    (20, 2048),
+];
+
+/// Bytes the account of a tree with no canopy takes: a 56 byte header, then the sequence number, active index and
+/// buffer size, the changelog buffer and the rightmost path - each change and the path being a path's nodes plus a
+/// node, an index and padding
+pub fn merkle_tree_account_size(max_depth: u32, max_buffer_size: u32) -> usize {
+    let path = 32 * max_depth as usize + 32 + 8;
+    56 + 24 + max_buffer_size as usize * path + path
+}
+
+/// Sets up the zeroed `merkle_tree` account, owned by the account-compression program, with `authority` as the only
+/// one allowed to change it
+pub fn init_empty_merkle_tree(
+    merkle_tree: &Pubkey,
+    authority: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    let mut data = INIT_EMPTY_MERKLE_TREE.to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+    tree_instruction(merkle_tree, authority, data, &[])
+}
+
+/// Puts `leaf` at the tree's next free index
+pub fn append(merkle_tree: &Pubkey, authority: &Pubkey, leaf: Node) -> Instruction {
+    let mut data = APPEND.to_vec();
+    data.extend_from_slice(&leaf);
+    tree_instruction(merkle_tree, authority, data, &[])
+}
+
+/// Replaces the leaf at `index`, which has to be `previous_leaf` - proven against `root`, which can be any of the
+/// roots still in the tree's changelog buffer. Fails on anything else, which is what stops a leaf from being
+/// claimed twice
+pub fn replace_leaf(
+    merkle_tree: &Pubkey,
+    authority: &Pubkey,
+    root: Node,
+    previous_leaf: Node,
+    new_leaf: Node,
+    index: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    let mut data = REPLACE_LEAF.to_vec();
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&previous_leaf);
+    data.extend_from_slice(&new_leaf);
+    data.extend_from_slice(&index.to_le_bytes());
+    tree_instruction(merkle_tree, authority, data, proof)
+}
+
+// every instruction above takes the tree, its authority and the noop program it logs changes through for indexers,
+// followed by the proof nodes as readonly accounts
+fn tree_instruction(
+    merkle_tree: &Pubkey,
+    authority: &Pubkey,
+    data: Vec<u8>,
+    proof: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(spl_noop::id(), false),
+    ];
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false)),
+    );
+    Instruction {
+        program_id: spl_account_compression::id(),
+        accounts,
+        data,
+    }
+}
+
+// ----------------------------------------------------------------------------- indexer helper
+// everything below is meant to be used off-chain, by whoever creates the tree and serves proofs to beneficiaries
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedSchedule {
+    pub destination_address: Pubkey,
+    pub release_time: u64,
+    pub amount: u64,
+}
+
+impl CompressedSchedule {
+    pub fn leaf(&self) -> Node {
+        hash_leaf(&self.destination_address, self.release_time, self.amount)
+    }
+}
+
+/// Full in-memory copy of the tree, used to compute the root at creation time and to reconstruct proofs for claims
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    depth: u32,
+    // levels[0] are the leaves, levels[depth] is the root
+    levels: Vec<Vec<Node>>,
+}
+
+impl MerkleTree {
+    /// Builds the smallest tree of TREE_SIZES that fits all the schedules. Unused leaves are padded with EMPTY_LEAF,
+    /// which is what an spl-account-compression tree starts out with too. None for more schedules than a tree of
+    /// MAX_TREE_DEPTH holds
+    pub fn new(schedules: &[CompressedSchedule]) -> Option<Self> {
+        let depth = TREE_SIZES
+            .iter()
+            .map(|&(depth, _)| depth)
+            .find(|depth| (1_usize << depth) >= schedules.len())?;
+        let leaves = schedules.iter().map(|s| s.leaf()).collect::<Vec<Node>>();
+        Self::from_leaves(leaves, depth)
+    }
+
+    /// None for a depth past MAX_TREE_DEPTH, or more leaves than the depth holds
+    pub fn from_leaves(mut leaves: Vec<Node>, depth: u32) -> Option<Self> {
+        if depth > MAX_TREE_DEPTH || leaves.len() > 1 << depth {
+            return None;
+        }
+        leaves.resize(1 << depth, EMPTY_LEAF);
+
+        let mut levels = vec![leaves];
+        for level in 0..depth as usize {
+            let next = levels[level]
+                .chunks(2)
+                .map(|pair| hash_node(&pair[0], &pair[1]))
+                .collect();
+            levels.push(next);
+        }
+        Some(Self { depth, levels })
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// The smallest changelog buffer spl-account-compression has for this depth, None for a depth it has no tree for
+    pub fn max_buffer_size(&self) -> Option<u32> {
+        TREE_SIZES
+            .iter()
+            .find(|&&(depth, _)| depth == self.depth)
+            .map(|&(_, max_buffer_size)| max_buffer_size)
+    }
+
+    pub fn root(&self) -> Node {
+        self.levels[self.depth as usize][0]
+    }
+
+    pub fn leaf(&self, index: u32) -> Node {
+        self.levels[0][index as usize]
+    }
+
+    /// Sibling hashes from the leaf up to (but excluding) the root
+    pub fn proof(&self, index: u32) -> Vec<Node> {
+        let mut position = index as usize;
+        let mut proof = Vec::with_capacity(self.depth as usize);
+        for level in 0..self.depth as usize {
+            proof.push(self.levels[level][position ^ 1]);
+            position >>= 1;
+        }
+        proof
+    }
+
+    /// Sibling hashes as the readonly accounts ClaimCompressed takes them in
+    pub fn proof_accounts(&self, index: u32) -> Vec<Pubkey> {
+        self.proof(index)
+            .into_iter()
+            .map(Pubkey::new_from_array)
+            .collect()
+    }
+
+    /// Mirrors what ClaimCompressed has the tree do - replaces the leaf and rehashes the path up to the root
+    pub fn replace_leaf(&mut self, index: u32, leaf: Node) {
+        let mut position = index as usize;
+        self.levels[0][position] = leaf;
+        for level in 0..self.depth as usize {
+            let left = self.levels[level][position & !1];
+            let right = self.levels[level][position | 1];
+            position >>= 1;
+            self.levels[level + 1][position] = hash_node(&left, &right);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_proofs() {
+        let schedules = (0..5)
+            .map(|i| CompressedSchedule {
+                destination_address: Pubkey::new_unique(),
+                release_time: 100 + i,
+                amount: 1000 * i,
+            })
+            .collect::<Vec<CompressedSchedule>>();
+        let mut tree = MerkleTree::new(&schedules).unwrap();
+        assert_eq!(tree.depth(), 3);
+
+        // every leaf verifies against the root, and only at its own index
+        let root = tree.root();
+        for (i, s) in schedules.iter().enumerate() {
+            let proof = tree.proof(i as u32);
+            assert!(verify_proof(&root, s.leaf(), i as u32, &proof));
+            assert!(!verify_proof(&root, s.leaf(), (i as u32) ^ 1, &proof));
+        }
+
+        // claiming a leaf changes the root in the same way the program computes it
+        let proof = tree.proof(2);
+        let expected_root = compute_root(EMPTY_LEAF, 2, &proof);
+        tree.replace_leaf(2, EMPTY_LEAF);
+        assert_eq!(tree.root(), expected_root);
+        assert!(!verify_proof(&tree.root(), schedules[2].leaf(), 2, &proof));
+
+        // other proofs are still valid once refreshed
+        assert!(verify_proof(
+            &tree.root(),
+            schedules[3].leaf(),
+            3,
+            &tree.proof(3)
+        ));
+    }
+
+    #[test]
+    fn test_merkle_tree_sizes() {
+        let schedules = |n| {
+            (0..n)
+                .map(|i| CompressedSchedule {
+                    destination_address: Pubkey::new_unique(),
+                    release_time: i,
+                    amount: 1,
+                })
+                .collect::<Vec<CompressedSchedule>>()
+        };
+        // depth 4 has no spl-account-compression tree
+        for (n, depth, max_buffer_size) in [(1, 3, 8), (9, 5, 8), (40, 6, 16), (10_000, 14, 64)] {
+            let tree = MerkleTree::new(&schedules(n)).unwrap();
+            assert_eq!(tree.depth(), depth);
+            assert_eq!(tree.max_buffer_size(), Some(max_buffer_size));
+        }
+        assert_eq!(
+            MerkleTree::from_leaves(vec![], 4)
+                .unwrap()
+                .max_buffer_size(),
+            None
+        );
+        assert!(MerkleTree::from_leaves(vec![EMPTY_LEAF; 9], 3).is_none());
+        assert!(MerkleTree::from_leaves(vec![], MAX_TREE_DEPTH + 1).is_none());
+
+        // the sizes spl-account-compression's own clients come up with
+        assert_eq!(merkle_tree_account_size(3, 8), 1_304);
+        assert_eq!(merkle_tree_account_size(14, 64), 31_800);
+    }
+
+    #[test]
+    fn test_account_compression_instructions() {
+        let tree = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let proof = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let ix = replace_leaf(&tree, &authority, [1; 32], [2; 32], EMPTY_LEAF, 3, &proof);
+        assert_eq!(ix.program_id, spl_account_compression::id());
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(tree, false),
+                AccountMeta::new_readonly(authority, true),
+                AccountMeta::new_readonly(spl_noop::id(), false),
+                AccountMeta::new_readonly(proof[0], false),
+                AccountMeta::new_readonly(proof[1], false),
+            ]
+        );
+        assert_eq!(ix.data.len(), 8 + 32 * 3 + 4);
+        assert_eq!(ix.data[..8], REPLACE_LEAF);
+        assert_eq!(ix.data[8 + 32 * 3..], 3u32.to_le_bytes());
+
+        let ix = init_empty_merkle_tree(&tree, &authority, 14, 64);
+        assert_eq!(
+            ix.data,
+            [&INIT_EMPTY_MERKLE_TREE[..], &[14, 0, 0, 0, 64, 0, 0, 0]].concat()
+        );
+        let ix = append(&tree, &authority, [5; 32]);
+        assert_eq!(ix.data, [&APPEND[..], &[5; 32]].concat());
+    }
+}
@@ -0,0 +1,485 @@
+//! The byte layouts and the vesting maths of the program, without std or solana-program - only `core` and `alloc`.
+//! Embedded signers and HSM plugins can build this file on its own, eg `#[path]`-included into a `#![no_std]` crate
+//! with `extern crate alloc`, to make sense of instruction and account bytes. The rest of the crate re-exports what
+//! it uses from here.
+//!
+//! Keys are plain `Key` newtypes here. The conversions from and to solana-program's Pubkey are behind the
+//! core-shims feature, so that a standalone build doesn't need the dependency.
+#![deny(
+    clippy::std_instead_of_core,
+    clippy::std_instead_of_alloc,
+    clippy::alloc_instead_of_core
+)]
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+// ----------------------------------------------------------------------------- keys
+
+/// The 32 bytes of a Pubkey
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Key(pub [u8; 32]);
+
+impl Key {
+    pub const LEN: usize = 32;
+}
+
+impl From<[u8; 32]> for Key {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Key {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "core-shims")]
+impl From<solana_program::pubkey::Pubkey> for Key {
+    fn from(pubkey: solana_program::pubkey::Pubkey) -> Self {
+        Self(pubkey.to_bytes())
+    }
+}
+
+#[cfg(feature = "core-shims")]
+impl From<Key> for solana_program::pubkey::Pubkey {
+    fn from(key: Key) -> Self {
+        Self::new_from_array(key.0)
+    }
+}
+
+// ----------------------------------------------------------------------------- layout
+// everything is little endian. The readers return None past the end of `src` instead of panicking
+
+pub fn read_u32(src: &[u8], offset: usize) -> Option<u32> {
+    let bytes = src.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+pub fn read_u64(src: &[u8], offset: usize) -> Option<u64> {
+    let bytes = src.get(offset..offset.checked_add(8)?)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+pub fn read_key(src: &[u8], offset: usize) -> Option<Key> {
+    let bytes = src.get(offset..offset.checked_add(Key::LEN)?)?;
+    Some(Key(bytes.try_into().ok()?))
+}
+
+/// Stored as next_release_time once every schedule has been claimed (or revoked), so a "< now" check never matches
+pub const NO_PENDING_RELEASE: u64 = u64::MAX;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VestingSchedule {
+    pub release_time: u64,
+    pub amount: u64,
+}
+
+impl VestingSchedule {
+    pub const LEN: usize = 16;
+
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.release_time.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.amount.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(src: &[u8]) -> Option<Self> {
+        Some(Self {
+            release_time: read_u64(src, 0)?,
+            amount: read_u64(src, 8)?,
+        })
+    }
+}
+
+/// Schedules laid out back to back, the way both Create's payload and a vesting account store them. None unless
+/// `src` holds a whole number of them
+pub fn schedules_from_bytes(src: &[u8]) -> Option<Vec<VestingSchedule>> {
+    let chunks = src.chunks_exact(VestingSchedule::LEN);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    chunks.map(VestingSchedule::from_bytes).collect()
+}
+
+/// Follows the only schedule of a ScheduleLayout::Linear contract: cliff_amount is released all at once at
+/// cliff_time, and total_amount continuously over [start_time, end_time) - eg a year's cliff, then monthly vesting
+/// without a schedule per month. The schedule's release_time is the first of the two, and cliff_amount plus
+/// total_amount less the schedule's amount is what has been claimed so far
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearVesting {
+    pub cliff_time: u64,
+    pub cliff_amount: u64, //0 = no cliff
+    pub start_time: u64,
+    pub end_time: u64,     //everything has been released from here on
+    pub total_amount: u64, //only lowered by Revoke, which folds the cliff into it
+}
+
+impl LinearVesting {
+    pub const LEN: usize = 40;
+
+    /// When the first tokens are released - the release_time of the contract's schedule
+    pub fn first_release_time(&self) -> u64 {
+        if self.cliff_amount > 0 {
+            self.cliff_time.min(self.start_time)
+        } else {
+            self.start_time
+        }
+    }
+
+    /// Everything the contract releases, cliff included. None if it doesn't fit in a u64
+    pub fn grant_amount(&self) -> Option<u64> {
+        self.cliff_amount.checked_add(self.total_amount)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 40] {
+        let mut bytes = [0; 40];
+        for (i, field) in [
+            self.cliff_time,
+            self.cliff_amount,
+            self.start_time,
+            self.end_time,
+            self.total_amount,
+        ]
+        .iter()
+        .enumerate()
+        {
+            bytes[i * 8..(i + 1) * 8].copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(src: &[u8]) -> Option<Self> {
+        Some(Self {
+            cliff_time: read_u64(src, 0)?,
+            cliff_amount: read_u64(src, 8)?,
+            start_time: read_u64(src, 16)?,
+            end_time: read_u64(src, 24)?,
+            total_amount: read_u64(src, 32)?,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------- maths
+
+/// Earliest release time among the schedules that haven't been paid out yet, or NO_PENDING_RELEASE if there are none
+pub fn next_release_time(schedules: &[VestingSchedule]) -> u64 {
+    schedules
+        .iter()
+        .filter(|s| s.amount > 0)
+        .map(|s| s.release_time)
+        .min()
+        .unwrap_or(NO_PENDING_RELEASE)
+}
+
+/// The schedule releasing last - the one everything rolls into when roll_into_final is set.
+/// Ties go to the later index, so the result doesn't depend on how equal schedules were ordered at Create
+pub fn final_schedule_index(schedules: &[VestingSchedule]) -> Option<usize> {
+    schedules
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, s)| s.release_time)
+        .map(|(i, _)| i)
+}
+
+/// next_release_time for a contract with roll_into_final set: nothing can be claimed before the final schedule
+pub fn rolled_next_release_time(schedules: &[VestingSchedule]) -> u64 {
+    match final_schedule_index(schedules) {
+        Some(i) if schedules.iter().any(|s| s.amount > 0) => schedules[i].release_time,
+        _ => NO_PENDING_RELEASE,
+    }
+}
+
+/// How much of a linear contract has been released at `now`: its cliff from cliff_time on, plus nothing of the
+/// linear part before its start, all of it from its end on and a share proportional to the time elapsed in between,
+/// rounded down
+pub fn linear_released(linear: &LinearVesting, now: u64) -> u64 {
+    let cliff = if now >= linear.cliff_time {
+        linear.cliff_amount
+    } else {
+        0
+    };
+    let linear_part = if now >= linear.end_time {
+        linear.total_amount
+    } else if now < linear.start_time {
+        0
+    } else {
+        // end_time > now >= start_time, and the result is below total_amount, so none of this overflows
+        (linear.total_amount as u128 * (now - linear.start_time) as u128
+            / (linear.end_time - linear.start_time) as u128) as u64
+    };
+    cliff.saturating_add(linear_part)
+}
+
+/// What a linear contract has paid out so far, given its schedule
+fn linear_claimed(schedule: &VestingSchedule, linear: &LinearVesting) -> u64 {
+    linear
+        .cliff_amount
+        .saturating_add(linear.total_amount)
+        .saturating_sub(schedule.amount)
+}
+
+/// What Unlock pays out of a linear contract at `now`: released so far, less what has already been claimed
+pub fn linear_claimable(schedule: &VestingSchedule, linear: &LinearVesting, now: u64) -> u64 {
+    linear_released(linear, now)
+        .saturating_sub(linear_claimed(schedule, linear))
+        .min(schedule.amount)
+}
+
+/// Shrinks what an Unlock takes off each schedule to `max_amount` in total, every schedule giving up the same share of
+/// its release. The rounding dust goes to the earliest schedules that rounded down, so the total comes out exact.
+/// Amounts already within `max_amount` are left alone
+pub fn pro_rata_claim(amounts: &mut [u64], max_amount: u64) {
+    let total: u128 = amounts.iter().map(|a| *a as u128).sum();
+    if total <= max_amount as u128 {
+        return;
+    }
+
+    let released = amounts.to_vec();
+    let mut claimed: u64 = 0;
+    for a in amounts.iter_mut() {
+        // below the amount itself, since max_amount < total
+        *a = (*a as u128 * max_amount as u128 / total) as u64;
+        claimed += *a;
+    }
+    // each amount lost less than 1 to rounding, so there are at least as many that rounded down as there is dust
+    for (a, released) in amounts.iter_mut().zip(released) {
+        if claimed == max_amount {
+            break;
+        }
+        if *a < released {
+            *a += 1;
+            claimed += 1;
+        }
+    }
+}
+
+/// Ends a linear contract at `now`: what has been released stays claimable, the rest is taken off the contract and
+/// returned. Claims made ahead of the clock (within the early claim tolerance) are kept as released
+pub fn truncate_linear(
+    schedule: &mut VestingSchedule,
+    linear: &mut LinearVesting,
+    now: u64,
+) -> u64 {
+    let claimed = linear_claimed(schedule, linear);
+    let kept = linear_released(linear, now).max(claimed);
+    // kept never exceeds cliff_amount + total_amount, which is claimed + schedule.amount
+    let revoked = schedule.amount - (kept - claimed);
+    if revoked == 0 {
+        return 0;
+    }
+
+    schedule.amount -= revoked;
+    // from here on everything kept counts as released
+    *linear = LinearVesting {
+        cliff_time: 0,
+        cliff_amount: 0,
+        start_time: linear.start_time.min(now),
+        end_time: now,
+        total_amount: kept,
+    };
+    revoked
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(release_time: u64, amount: u64) -> VestingSchedule {
+        VestingSchedule {
+            release_time,
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let s = schedule(1_700_000_000, 42);
+        assert_eq!(VestingSchedule::from_bytes(&s.to_bytes()), Some(s.clone()));
+        assert_eq!(VestingSchedule::from_bytes(&s.to_bytes()[..15]), None);
+
+        let mut bytes = s.to_bytes().to_vec();
+        bytes.extend_from_slice(&schedule(1, 2).to_bytes());
+        assert_eq!(schedules_from_bytes(&bytes), Some(vec![s, schedule(1, 2)]));
+        assert_eq!(schedules_from_bytes(&bytes[1..]), None);
+        assert_eq!(schedules_from_bytes(&[]), Some(vec![]));
+
+        let l = linear(1, 2, 3, 4, 5);
+        assert_eq!(LinearVesting::from_bytes(&l.to_bytes()), Some(l));
+
+        assert_eq!(read_u32(&[1, 0, 0, 0, 9], 0), Some(1));
+        assert_eq!(read_u32(&[1, 0, 0, 0], 1), None);
+        assert_eq!(read_u64(&[0; 8], usize::MAX), None);
+        assert_eq!(read_key(&[7; 33], 1), Some(Key([7; 32])));
+    }
+
+    #[cfg(feature = "core-shims")]
+    #[test]
+    fn test_pubkey_shims() {
+        let pubkey = solana_program::pubkey::Pubkey::new_unique();
+        let key = Key::from(pubkey);
+        assert_eq!(key.as_ref(), pubkey.as_ref());
+        assert_eq!(solana_program::pubkey::Pubkey::from(key), pubkey);
+    }
+
+    #[test]
+    fn test_next_release_time() {
+        assert_eq!(
+            next_release_time(&[schedule(30, 1), schedule(10, 0), schedule(20, 1)]),
+            20
+        );
+        assert_eq!(next_release_time(&[schedule(10, 0)]), NO_PENDING_RELEASE);
+    }
+
+    #[test]
+    fn test_final_schedule_index() {
+        assert_eq!(final_schedule_index(&[]), None);
+        assert_eq!(
+            final_schedule_index(&[schedule(30, 1), schedule(10, 1), schedule(20, 1)]),
+            Some(0)
+        );
+        assert_eq!(
+            final_schedule_index(&[schedule(30, 1), schedule(30, 1)]),
+            Some(1)
+        );
+    }
+
+    fn linear(
+        cliff_time: u64,
+        cliff_amount: u64,
+        start_time: u64,
+        end_time: u64,
+        total_amount: u64,
+    ) -> LinearVesting {
+        LinearVesting {
+            cliff_time,
+            cliff_amount,
+            start_time,
+            end_time,
+            total_amount,
+        }
+    }
+
+    #[test]
+    fn test_linear_released() {
+        let l = linear(0, 0, 10, 110, 1000);
+        assert_eq!(linear_released(&l, 0), 0);
+        assert_eq!(linear_released(&l, 10), 0);
+        assert_eq!(linear_released(&l, 35), 250);
+        assert_eq!(linear_released(&l, 109), 990);
+        assert_eq!(linear_released(&l, 110), 1000);
+        assert_eq!(linear_released(&l, u64::MAX), 1000);
+
+        // rounds down, and doesn't overflow on large amounts
+        let l = linear(0, 0, 0, 3, u64::MAX);
+        assert_eq!(linear_released(&l, 1), u64::MAX / 3);
+    }
+
+    #[test]
+    fn test_linear_released_with_cliff() {
+        // a cliff of 400 at 60, then 600 over the second half
+        let l = linear(60, 400, 60, 110, 600);
+        assert_eq!(linear_released(&l, 59), 0);
+        assert_eq!(linear_released(&l, 60), 400);
+        assert_eq!(linear_released(&l, 85), 700);
+        assert_eq!(linear_released(&l, 110), 1000);
+
+        // the linear part can start before the cliff, which then releases what accrued so far
+        let l = linear(60, 0, 10, 110, 1000);
+        assert_eq!(linear_released(&l, 35), 250);
+    }
+
+    #[test]
+    fn test_linear_claimable() {
+        let l = linear(0, 0, 10, 110, 1000);
+        assert_eq!(linear_claimable(&schedule(10, 1000), &l, 35), 250);
+        // 250 claimed already
+        assert_eq!(linear_claimable(&schedule(10, 750), &l, 35), 0);
+        assert_eq!(linear_claimable(&schedule(10, 750), &l, 60), 250);
+        assert_eq!(linear_claimable(&schedule(10, 750), &l, 200), 750);
+        // claimed ahead of the clock
+        assert_eq!(linear_claimable(&schedule(10, 700), &l, 35), 0);
+
+        let l = linear(60, 400, 60, 110, 600);
+        assert_eq!(linear_claimable(&schedule(60, 1000), &l, 60), 400);
+        assert_eq!(linear_claimable(&schedule(60, 600), &l, 85), 300);
+    }
+
+    #[test]
+    fn test_truncate_linear() {
+        let mut s = schedule(10, 750);
+        let mut l = linear(0, 0, 10, 110, 1000);
+        assert_eq!(truncate_linear(&mut s, &mut l, 60), 500);
+        assert_eq!(s, schedule(10, 250));
+        assert_eq!(l, linear(0, 0, 10, 60, 500));
+        // everything left has been released
+        assert_eq!(linear_claimable(&s, &l, 60), 250);
+        assert_eq!(truncate_linear(&mut s, &mut l, 60), 0);
+
+        // before the start nothing is kept
+        let mut s = schedule(10, 1000);
+        let mut l = linear(0, 0, 10, 110, 1000);
+        assert_eq!(truncate_linear(&mut s, &mut l, 5), 1000);
+        assert_eq!(s.amount, 0);
+        assert_eq!(l.total_amount, 0);
+    }
+
+    #[test]
+    fn test_truncate_linear_with_cliff() {
+        // before the cliff it all goes
+        let mut s = schedule(60, 1000);
+        let mut l = linear(60, 400, 60, 110, 600);
+        assert_eq!(truncate_linear(&mut s, &mut l, 30), 1000);
+        assert_eq!(s.amount, 0);
+
+        // past it the cliff and what accrued since stay
+        let mut s = schedule(60, 1000);
+        let mut l = linear(60, 400, 60, 110, 600);
+        assert_eq!(truncate_linear(&mut s, &mut l, 85), 300);
+        assert_eq!(s, schedule(60, 700));
+        assert_eq!(linear_claimable(&s, &l, 200), 700);
+    }
+
+    #[test]
+    fn test_pro_rata_claim() {
+        let mut amounts = vec![300, 100, 0];
+        pro_rata_claim(&mut amounts, 200);
+        assert_eq!(amounts, vec![150, 50, 0]);
+
+        // dust goes to the earliest schedules
+        let mut amounts = vec![1, 1, 1];
+        pro_rata_claim(&mut amounts, 2);
+        assert_eq!(amounts, vec![1, 1, 0]);
+        let mut amounts = vec![10, 10, 10];
+        pro_rata_claim(&mut amounts, 10);
+        assert_eq!(amounts, vec![4, 3, 3]);
+
+        // nothing to cut
+        let mut amounts = vec![10, 20];
+        pro_rata_claim(&mut amounts, 30);
+        assert_eq!(amounts, vec![10, 20]);
+
+        // totals past u64 don't overflow
+        let mut amounts = vec![u64::MAX, u64::MAX];
+        pro_rata_claim(&mut amounts, u64::MAX);
+        assert_eq!(amounts, vec![u64::MAX / 2 + 1, u64::MAX / 2]);
+    }
+
+    #[test]
+    fn test_rolled_next_release_time() {
+        assert_eq!(
+            rolled_next_release_time(&[schedule(10, 100), schedule(20, 0)]),
+            20
+        );
+        assert_eq!(
+            rolled_next_release_time(&[schedule(10, 0), schedule(20, 0)]),
+            NO_PENDING_RELEASE
+        );
+        assert_eq!(rolled_next_release_time(&[]), NO_PENDING_RELEASE);
+    }
+}
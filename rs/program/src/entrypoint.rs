@@ -26,3 +26,5 @@ pub fn process_instruction(
 
 // Deploy the program with the following id:
 // solana_program::declare_id!("SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM");
+// and devnet-tools builds with this one, see ids.rs:
+// solana_program::declare_id!("4zSnaqctZixkbvBFB3fvqPC33wazL98jR2LAaD6Qb9PG");
@@ -0,0 +1,208 @@
+use num_traits::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+
+/// Errors that may be returned by the Token vesting program.
+/// thiserror::Error covers std::error::Error trait
+#[derive(Clone, Debug, Eq, thiserror::Error, num_derive::FromPrimitive, PartialEq)]
+pub enum VestingError {
+    #[error("Invalid Instruction")]
+    InvalidInstruction,
+    #[error("Some other error")]
+    SomeOther,
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof,
+    #[error("Contract has nothing left to vest")]
+    ContractExhausted,
+    #[error("Release time is in the past")]
+    ReleaseTimeInPast,
+    #[error("Invalid claim permit")]
+    InvalidPermit,
+    #[error("Claim permit has expired")]
+    PermitExpired,
+    #[error("Account data doesn't match its header")]
+    CorruptState,
+    #[error("Claim is below the contract's minimum")]
+    ClaimBelowMinimum,
+    #[error("Contract destination is locked")]
+    DestinationLocked,
+    #[error("Early claim tolerance is too large")]
+    EarlyClaimToleranceTooLarge,
+    #[error("Withholding is above 100%")]
+    WithholdingTooLarge,
+    #[error("Contract still has tokens to pay out")]
+    ContractNotFinished,
+    #[error("Unlock is rate limited")]
+    UnlockRateLimited,
+    #[error("Invalid destination approvers")]
+    InvalidApprovers,
+    #[error("Account predates the current layout")]
+    LegacyAccountLayout,
+    #[error("Granter owns the destination")]
+    SelfDealing,
+    #[error("Contract is in the wrong state")]
+    InvalidContractState,
+    #[error("A token CPI moved a different amount than intended")]
+    UnexpectedTokenDelta,
+    #[error("Minting the grant would overflow the mint's supply")]
+    MintSupplyOverflow,
+    #[error("Source token account isn't owned by the granter")]
+    SourceOwnerMismatch,
+    #[error("New destination is for another mint")]
+    DestinationMintMismatch,
+    #[error("Contract hasn't been activated yet")]
+    AwaitingActivation,
+    #[error("Contract is paused")]
+    ContractPaused,
+    #[error("Contract can't be revoked")]
+    Irrevocable,
+    #[error("Invalid splits")]
+    InvalidSplits,
+    #[error("Release time beyond the emission curve")]
+    BeyondEmissionCurve,
+    #[error("Token account has an extension contracts can't work with")]
+    IncompatibleTokenExtension,
+    #[error("Contract has changed since the expected revision")]
+    StaleRevision,
+    #[error("Minting the grant would take the mint's supply past the cap")]
+    MintSupplyCapExceeded,
+    #[error("Destination index points at a contract still paying that destination")]
+    DestinationIndexTaken,
+}
+
+// ----------------------------------------------------------------------------- VestingError -> ProgramError
+impl From<VestingError> for ProgramError {
+    // ok interesting, so you can convert enums into u32 numbers. First enum = 0, Second = 1, etc
+    fn from(e: VestingError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+// ----------------------------------------------------------------------------- ProgramError -> VestingError
+/// so that we can do error.print::<VestingError>
+
+impl PrintProgramError for VestingError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        match self {
+            VestingError::InvalidInstruction => msg!("Error: Invalid instruction!"),
+            VestingError::SomeOther => msg!("some other error occured!"),
+            VestingError::InvalidMerkleProof => {
+                msg!("Error: Merkle proof does not match the stored root!")
+            }
+            VestingError::ContractExhausted => {
+                msg!("Error: Every schedule of this contract has already been claimed!")
+            }
+            VestingError::ReleaseTimeInPast => {
+                msg!("Error: Release time is in the past and immediate release wasn't allowed!")
+            }
+            VestingError::InvalidPermit => {
+                msg!("Error: Claim permit is missing or wasn't signed by the destination owner!")
+            }
+            VestingError::PermitExpired => msg!("Error: Claim permit has expired!"),
+            VestingError::CorruptState => {
+                msg!("Error: Vesting account size doesn't match the schedule count in its header!")
+            }
+            VestingError::ClaimBelowMinimum => {
+                msg!("Error: Amount released so far is below the contract's minimum claim!")
+            }
+            VestingError::DestinationLocked => {
+                msg!("Error: The contract's destination can't be changed!")
+            }
+            VestingError::EarlyClaimToleranceTooLarge => {
+                msg!("Error: Early claim tolerance is above MAX_EARLY_CLAIM_TOLERANCE_SECONDS!")
+            }
+            VestingError::WithholdingTooLarge => {
+                msg!("Error: Withholding is above MAX_WITHHOLDING_BPS!")
+            }
+            VestingError::ContractNotFinished => {
+                msg!("Error: The contract still has unclaimed or unreleased schedules!")
+            }
+            VestingError::UnlockRateLimited => {
+                msg!("Error: The previous unlock was less than unlock_interval ago!")
+            }
+            VestingError::InvalidApprovers => {
+                msg!("Error: Approvers must be 1 to MAX_DESTINATION_APPROVERS distinct keys, at least threshold of them!")
+            }
+            VestingError::LegacyAccountLayout => {
+                msg!("Error: The account is on the baseline layout, run MigrateLayout on it first!")
+            }
+            VestingError::SelfDealing => {
+                msg!("Error: The granter can revoke the contract, so it can't also own the destination!")
+            }
+            VestingError::InvalidContractState => {
+                msg!("Error: The contract's state doesn't allow this, see ContractState::apply!")
+            }
+            VestingError::UnexpectedTokenDelta => {
+                msg!("Error: A token account's balance didn't change by what the instruction meant to move!")
+            }
+            VestingError::MintSupplyOverflow => {
+                msg!("Error: Minting the grant would take the mint's supply past u64::MAX!")
+            }
+            VestingError::SourceOwnerMismatch => {
+                msg!("Error: The signing granter isn't the token-level owner of the source token account!")
+            }
+            VestingError::DestinationMintMismatch => {
+                msg!("Error: The new destination token account isn't of the contract's mint, Unlock could never pay it!")
+            }
+            VestingError::AwaitingActivation => {
+                msg!("Error: The contract's release times are relative until the granter activates it!")
+            }
+            VestingError::ContractPaused => {
+                msg!("Error: The granter has paused the contract, nothing is paid out until it's resumed!")
+            }
+            VestingError::Irrevocable => {
+                msg!("Error: The contract was created irrevocable, nothing can ever be revoked from it!")
+            }
+            VestingError::InvalidSplits => {
+                msg!("Error: Splits must be at most MAX_SPLIT_DESTINATIONS distinct accounts other than the destination, each taking a share, adding up to at most MAX_SPLIT_BPS!")
+            }
+            VestingError::BeyondEmissionCurve => {
+                msg!("Error: A contract tracked by its mint's emission curve can't release later than EMISSION_CURVE_WEEKS from now!")
+            }
+            VestingError::IncompatibleTokenExtension => {
+                msg!("Error: A vesting token account can't carry Token-2022 extensions other than ImmutableOwner and TransferFeeAmount!")
+            }
+            VestingError::StaleRevision => {
+                msg!("Error: The contract was modified after the revision the client built its transaction against!")
+            }
+            VestingError::MintSupplyCapExceeded => {
+                msg!("Error: Minting the grant would take the mint's supply past the max_supply CreateFromMint was given!")
+            }
+            VestingError::DestinationIndexTaken => {
+                msg!("Error: The destination index points at another contract still paying that destination, pass that contract once it's finished to take the index over!")
+            }
+        }
+    }
+}
+
+impl<T> DecodeError<T> for VestingError {
+    fn type_of() -> &'static str {
+        "VestingError"
+    }
+}
+
+// ----------------------------------------------------------------------------- error context
+/// What the handlers and their helpers return
+pub type VestingResult<T = ()> = Result<T, ProgramError>;
+
+/// Names the check that failed, so that an on-chain failure can be told apart from the other checks returning the
+/// same error from a single log line
+pub trait ErrorContext<T> {
+    fn ctx(self, check: &str) -> VestingResult<T>;
+}
+
+impl<T, E: Into<ProgramError>> ErrorContext<T> for Result<T, E> {
+    fn ctx(self, check: &str) -> VestingResult<T> {
+        self.map_err(|e| {
+            let e = e.into();
+            msg!("{} failed: {:?}", check, e);
+            e
+        })
+    }
+}
@@ -0,0 +1,51 @@
+//! Where the program is deployed, for clients that would otherwise hard-code the base58 id. Devnet runs the
+//! devnet-tools build, so it goes out under an id of its own (see entrypoint.rs): a program at the mainnet id is never
+//! a QA build, and a devnet-tools build refuses the debug instructions at the mainnet id all the same.
+
+use solana_program::pubkey::Pubkey;
+
+/// SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    6, 155, 234, 178, 173, 163, 242, 156, 244, 23, 31, 68, 62, 6, 167, 88, 67, 91, 187, 67, 11, 89,
+    96, 112, 145, 111, 56, 88, 133, 52, 131, 80,
+]);
+
+/// 4zSnaqctZixkbvBFB3fvqPC33wazL98jR2LAaD6Qb9PG
+const DEVNET_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    59, 74, 82, 184, 168, 144, 89, 58, 40, 171, 61, 12, 134, 193, 205, 11, 6, 62, 13, 49, 28, 199,
+    46, 238, 15, 115, 241, 165, 202, 229, 160, 139,
+]);
+
+pub fn mainnet() -> Pubkey {
+    PROGRAM_ID
+}
+
+pub fn devnet() -> Pubkey {
+    DEVNET_PROGRAM_ID
+}
+
+/// The deployments ImportContract takes contracts from. Whoever owns a migrated vesting account decides what it says,
+/// so one owned by any other program could make up its destination, granter and schedules
+pub fn migration_sources() -> [Pubkey; 2] {
+    [mainnet(), devnet()]
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids() {
+        assert_eq!(
+            mainnet().to_string(),
+            "SoLi39YzAM2zEXcecy77VGbxLB5yHryNckY9Jx7yBKM"
+        );
+        assert_eq!(
+            devnet().to_string(),
+            "4zSnaqctZixkbvBFB3fvqPC33wazL98jR2LAaD6Qb9PG"
+        );
+        assert_ne!(devnet(), mainnet());
+    }
+}
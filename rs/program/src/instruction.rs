@@ -0,0 +1,6640 @@
+// use borsh::{BorshDeserialize, BorshSerialize};
+// use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+use num_traits::FromPrimitive;
+use solana_program::{
+    bpf_loader_upgradeable, ed25519_program,
+    hash::hashv,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_compute_units,
+    msg,
+    program::MAX_RETURN_DATA,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+
+use crate::{
+    compression::{spl_account_compression, spl_noop, Node},
+    error::{VestingError, VestingError::InvalidInstruction},
+    pda,
+    state::{
+        DustPolicy, RentPayer, RevokePolicy, ScheduleCategory, ScheduleLayout, SplitDestination,
+        UnlockPolicy, VestingScheduleHeader,
+    },
+};
+
+pub type Seeds = [u8; 32];
+
+// #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+// #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum VestingInstruction {
+    /// Initializes an empty program account for the token_vesting program
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[]` The sysvar Rent account
+    ///   2. `[signer, writable]` The fee payer account, recorded so that CloseUninitialized can refund it if Create
+    ///      never comes
+    ///   3. `[writable]` The vesting account, at pda::find_vesting_address_for_seeds. Or for legacy seeds ending in
+    ///      their own bump, at create_program_address of them alone
+    Init {
+        // The seed used to derive the vesting accounts address. Init keeps the bump in the header, for every other
+        // instruction to derive the account and sign with
+        seeds: Seeds,
+        // The number of release schedules for this contract to hold
+        number_of_schedules: u32,
+        // Categorized leaves room for a category per schedule, and the contract then has to be created with them.
+        // Linear is for CreateLinear. Left off the wire when Plain, which keeps Init byte for byte the same as upstream
+        schedule_layout: ScheduleLayout,
+    },
+    /// Creates a new vesting schedule contract
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[writable]` The vesting account
+    ///   2. `[writable]` The vesting spl-token account
+    ///   3. `[signer]` The source spl-token account owner (writable if gas_deposit > 0 or the index is passed)
+    ///   4. `[writable]` The source spl-token account
+    ///   5. `[]` The contract's mint
+    ///   6. `[]` The destination spl-token account, of the contract's mint
+    ///   7. `[]` (Optional) The system program account, needed if gas_deposit > 0 or the index is passed
+    ///   8. `[writable]` (Optional) The destination index of (mint, destination), see find_destination_index. Created
+    ///      on first use, at the source spl-token account owner's expense
+    ///   9. `[]` (Optional) The program config, see find_program_config
+    ///   10. `[writable]` (Optional) The mint's emission curve, see find_emission_curve. Tracks the contract on it from
+    ///       then on. Created on first use at the source spl-token account owner's expense, with 7 then needed too
+    ///   11. `[]` (Optional) The contract the index points at, if it points at another one. Needed to show that contract
+    ///       no longer pays the destination, the index is never taken over from one that still does
+    ///
+    ///   9 and 10 are recognized by their key (the config by its owner and type), so they can go anywhere after 6.
+    ///   7, 8 and 11 are, in that order, whatever is left
+    Create {
+        seeds: Seeds,
+        token_mint_addr: Pubkey,
+        token_dest_addr: Pubkey,
+        // Unless set, schedules with a release time in the past are rejected - usually a sign of milliseconds
+        // being passed as seconds (or the other way round), or a date that ended up as 1970
+        allow_immediate: bool,
+        // Lamports paid out of the gas escrow to whoever submits a successful Unlock, so that beneficiaries without
+        // SOL can have a relayer claim for them
+        gas_rebate: u64,
+        // Lamports moved from the source token account owner into the gas escrow
+        gas_deposit: u64,
+        // Unlock refuses to transfer less than this, unless it's all the claimant has left. 0 = no minimum
+        min_claim_amount: u64,
+        // Set for grants that can't be re-assigned - ChangeDestination and ChangeFutureDestination then always fail
+        destination_locked: bool,
+        // The granter can revoke a revocable contract, so with this set Create fails for one if the granter also owns
+        // the destination spl-token account. ProgramConfig::forbid_self_dealing turns it on for the whole deployment
+        forbid_self_dealing: bool,
+        // What Revoke does with the unvested remainder - Burn needs the mint passed in place of the granter's token account
+        revoke_policy: RevokePolicy,
+        // For grants starting at a date that isn't known yet, eg an employee's first day: the release times are offsets
+        // from it, and nothing is released until the granter sends Activate, which adds its clock to each of them
+        relative: bool,
+        // Can sweep the vesting token account to a recovery account of its choosing with EmergencyWithdraw, eg when the
+        // destination's wallet is compromised. Pubkey::default() = none
+        emergency_authority: Pubkey,
+        // Unless set, Revoke and RevokeBatch always fail on the contract, so the destination can check on-chain that
+        // the grant can never be clawed back
+        revocable: bool,
+        schedules: Vec<Schedule>,
+        // One per schedule, for reporting emissions by bucket - or empty for a plain contract. Non-empty categories
+        // go out under their own tag (18), with a byte per schedule after the schedules
+        categories: Vec<ScheduleCategory>,
+    },
+    /// Pays out whatever has been released to the destination stored in the header. Anyone can submit it, eg a crank
+    /// going through due contracts: the caller picks none of where the tokens go. Contracts that opted out with
+    /// SetUnlockPolicy need the destination owner's signature or a permit instead, or the signature of the
+    /// destination spl-token account's delegate under UnlockPolicy::DelegateOnly
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[writable]` The vesting account
+    ///   3. `[writable]` The vesting spl-token account
+    ///   4. `[writable]` The destination spl-token account
+    ///   5. `[signer, writable]` (Optional) The account receiving the gas rebate, usually the fee payer. Required when
+    ///      the unlock policy isn't Permissionless or max_amount is set, and then has to be the destination
+    ///      spl-token account owner, or its delegate under UnlockPolicy::DelegateOnly
+    ///   6. `[]` (Optional) The program config, see find_program_config. Without it there is no early claim
+    ///      tolerance. Recognized as the config, so it can go anywhere after the destination spl-token account
+    ///   7. `[writable]` The withholding spl-token account, required while the contract withholds (see
+    ///      SetWithholding). Recognized by its key, so it can go anywhere after the destination spl-token account
+    ///   8. `[writable]` The spl-token account of each split, required while the contract splits its unlocks (see
+    ///      SetSplits). Recognized by their keys like the withholding account
+    ///   9. `[writable]` The mint's emission curve, required while the contract is tracked by it (see
+    ///      EmissionCurve). Recognized by its key too
+    ///   10. `[]` The mint, required when it charges Token-2022 transfer fees. Recognized by its key too
+    ///
+    ///   Any other account after the destination spl-token account is taken for the rebate receiver, and there can
+    ///   only be one
+    Unlock {
+        seeds: Seeds,
+        // Only looks at this many schedules, starting from the header's unlock_cursor, so that contracts too big for
+        // one transaction can be drained over several. 0 = every schedule. Left off the wire when 0, which keeps
+        // Unlock byte for byte the same as upstream
+        max_schedules_per_call: u32,
+        // Claims at most this much, taking the same share off every released schedule instead of all of it, eg to
+        // spread a claim over tax years. 0 = no cap. Only the destination owner can cap a claim, and not in a bounded
+        // Unlock. Left off the wire when 0 too, and puts max_schedules_per_call on it when it isn't
+        max_amount: u64,
+        // Starts a bounded Unlock at this schedule instead of the header's unlock_cursor, so that clients can pick
+        // the ranges themselves, eg to unlock a big contract from several transactions at once. Only moves the
+        // cursor when it starts right at it. Goes on the wire last, when set
+        start_index: Option<u32>,
+    },
+
+    /// Change the destination account of a given simple vesting contract (SVC)
+    /// - can only be invoked by the present destination address of the contract.
+    ///
+    /// The owner doesn't have to be a wallet: a program owning the destination through a PDA signs with invoke_signed.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[]` The current destination token account
+    ///   2. `[signer]` The destination spl-token account owner (writable if the indexes are passed)
+    ///   3. `[]` The new destination spl-token account, of the contract's mint
+    ///   4. `[]` (Optional) The system program account, needed to pass the indexes
+    ///   5. `[writable]` (Optional) The destination index of the current destination, cleared if it points here
+    ///   6. `[writable]` (Optional) The destination index of the new destination. Created on first use, at the
+    ///      destination owner's expense
+    ///   7. `[]` (Optional) The contract the new destination's index points at, if it points at another one - see
+    ///      Create
+    ChangeDestination {
+        seeds: Seeds,
+        // Fails with StaleRevision unless the contract is still at this revision, for a change proposed (eg to a
+        // multisig) before someone else changed the contract. Goes on the wire after the seeds, when set
+        expected_revision: Option<u32>,
+    },
+    Empty {
+        number: u32,
+    },
+    /// Creates a compressed vesting contract - a single account that is the authority of an spl-account-compression
+    /// merkle tree over many (destination, release_time, amount) leaves, so that thousands of small grants share
+    /// one tree's rent. The tree starts out empty, the granter fills it with AppendCompressed
+    ///
+    /// The program can't check that the leaves add up to total_amount - that's up to whoever builds the tree.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[]` The sysvar Rent account
+    ///   2. `[signer, writable]` The fee payer account
+    ///   3. `[]` The spl-token program account
+    ///   4. `[writable]` The compressed vesting account
+    ///   5. `[writable]` The vesting spl-token account
+    ///   6. `[signer]` The source spl-token account owner
+    ///   7. `[writable]` The source spl-token account
+    ///   8. `[writable]` The merkle tree account, allocated for max_depth and max_buffer_size (see
+    ///      compression::merkle_tree_account_size) and owned by the account-compression program
+    ///   9. `[]` The account-compression program account
+    ///   10. `[]` The noop program account the account-compression program logs changes through
+    ///   11. `[]` The mint, required when it charges Token-2022 transfer fees, which the granter pays on top so
+    ///       that the vesting token account receives total_amount. Recognized by its key
+    CreateCompressed {
+        seeds: Seeds,
+        token_mint_addr: Pubkey,
+        max_depth: u32,
+        max_buffer_size: u32,
+        total_amount: u64,
+    },
+    /// Appends leaves to the merkle tree of a compressed vesting contract, in order. A tree holds 2^max_depth of
+    /// them, so a big one takes a few of these
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The compressed vesting account
+    ///   1. `[signer]` The granter account
+    ///   2. `[writable]` The merkle tree account
+    ///   3. `[]` The account-compression program account
+    ///   4. `[]` The noop program account
+    AppendCompressed {
+        seeds: Seeds,
+        leaves: Vec<Node>,
+    },
+    /// Claims a single matured leaf of a compressed vesting contract. The leaf is replaced with an empty one,
+    /// so the same proof can't be used twice. The proof can be against any root still in the tree's changelog
+    /// buffer: claims landing in between are accounted for, so that beneficiaries claiming at the same time don't
+    /// fail each other
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[]` The compressed vesting account
+    ///   3. `[writable]` The vesting spl-token account
+    ///   4. `[writable]` The destination spl-token account, of the contract's mint
+    ///   5. `[writable]` The merkle tree account
+    ///   6. `[]` The account-compression program account
+    ///   7. `[]` The noop program account
+    ///   8. ..8 + max_depth `[]` The proof, an account per sibling node from the leaf up, the node as its key
+    ///   9. `[]` The mint, required when it charges Token-2022 transfer fees, which the destination receives the
+    ///      tokens short of. Recognized by its key
+    ClaimCompressed {
+        seeds: Seeds,
+        root: Node,
+        index: u32,
+        release_time: u64,
+        amount: u64,
+    },
+    /// Revokes many vesting contracts of the same granter in one go. Every schedule that hasn't been released yet
+    /// is transferred back to the granter (or burned, see RevokePolicy), already released amounts stay claimable by
+    /// the destination.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[signer]` The granter account (source token account owner at Create)
+    ///   * Then for each contract, in the same order as the seeds:
+    ///   3. `[writable]` The vesting account
+    ///   4. `[writable]` The vesting spl-token account
+    ///   5. `[writable]` The granter spl-token account receiving the unvested tokens, or the mint if the contract
+    ///      burns them
+    ///   * Then, after every contract's:
+    ///   6. `[writable]` The emission curve of each mint tracking one of the contracts (see EmissionCurve)
+    ///   7. `[]` (Optional) The program config, for its early claim tolerance like in Revoke
+    RevokeBatch {
+        seeds: Vec<Seeds>,
+    },
+    /// Moves a contract to a new deployment of the program, step 1 of 2 - runs on the OLD deployment.
+    /// Everything that hasn't been paid out yet goes to an escrow token account owned by the new deployment's
+    /// migration authority (see find_migration_authority), and the vesting account is marked as migrated so that
+    /// it can't be unlocked here anymore. Both parties to the contract have to sign.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[writable]` The vesting account
+    ///   2. `[writable]` The vesting spl-token account
+    ///   3. `[writable]` The escrow spl-token account
+    ///   4. `[signer]` The granter account
+    ///   5. `[]` The destination spl-token account
+    ///   6. `[signer]` The destination spl-token account owner
+    ///   7. `[writable]` The mint's emission curve, required while the contract is tracked by it (see
+    ///      EmissionCurve)
+    ///   8. `[]` The mint, required when it charges Token-2022 transfer fees. The escrow then receives the tokens
+    ///      short of them, and has to be made up for before ImportContract. Recognized by its key
+    ExportContract {
+        seeds: Seeds,
+        new_program_id: Pubkey,
+    },
+    /// Step 2 of 2 - runs on the NEW deployment. Recreates the exported contract (same destination, granter and
+    /// schedules) under new seeds, funded from the escrow.
+    ///
+    /// The old vesting account has to be owned by one of ids::migration_sources and laid out the way ExportContract
+    /// leaves it. Beyond that it's taken at its word: whatever it says, the new contract can only ever pay out tokens
+    /// that are sitting in the escrow, which only the new deployment can move.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[]` The sysvar Rent account
+    ///   2. `[signer, writable]` The fee payer account
+    ///   3. `[]` The spl-token program account
+    ///   4. `[]` The migrated vesting account, owned by the old deployment
+    ///   5. `[]` The migration authority
+    ///   6. `[writable]` The escrow spl-token account
+    ///   7. `[writable]` The new vesting account
+    ///   8. `[writable]` The new vesting spl-token account
+    ///   9. `[]` The mint, required when it charges Token-2022 transfer fees, which the escrow pays on top so that
+    ///      the new vesting spl-token account receives the full amount
+    ImportContract {
+        seeds: Seeds,
+    },
+    /// Same as Init, but for the `index`-th child of `base_seed` (see child_seeds) - eg one contract per month per
+    /// employee, all derived from a single base seed. Afterwards the contract is used with the derived seeds like any other.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[]` The sysvar Rent account
+    ///   2. `[signer, writable]` The fee payer account
+    ///   3. `[writable]` The vesting account
+    InitChild {
+        base_seed: Seeds,
+        index: u32,
+        number_of_schedules: u32,
+    },
+    /// Adds lamports to the gas escrow of a contract. Anyone can top it up
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[signer, writable]` The funding account
+    ///   2. `[writable]` The vesting account
+    TopUpGas {
+        seeds: Seeds,
+        amount: u64,
+    },
+    /// Turns permissionless unlock on or off. It's on for every new contract. While it's off, Unlock has to be signed
+    /// by the destination spl-token account owner, or submitted as UnlockWithPermit. Same as SetUnlockPolicy with
+    /// Permissionless or DestinationOwnerOnly
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[]` The destination spl-token account
+    ///   2. `[signer]` The destination spl-token account owner
+    SetPermissionlessUnlock {
+        seeds: Seeds,
+        enabled: bool,
+    },
+    /// Same as Unlock, but authorized by a claim permit instead of the destination owner's signature, so that a
+    /// relayer can submit it. The instruction right before this one has to be an ed25519 program instruction
+    /// verifying the destination owner's signature over claim_permit_message(). The runtime has already checked the
+    /// signature by the time this runs - all that's left is checking who signed what.
+    ///
+    /// The message covers the contract's permit_nonce, which every UnlockWithPermit bumps, so a permit is good for a
+    /// single unlock - a captured one can't be replayed. It only ever pays out to the contract's destination anyway.
+    /// A PDA owner can't sign one - its program signs a plain Unlock with invoke_signed instead.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[writable]` The vesting account
+    ///   3. `[writable]` The vesting spl-token account
+    ///   4. `[writable]` The destination spl-token account
+    ///   5. `[]` The instructions sysvar account
+    ///   6. `[signer, writable]` (Optional) The account receiving the gas rebate, usually the relayer
+    ///
+    ///   Followed by Unlock's withholding, split and emission curve accounts when the contract needs them
+    UnlockWithPermit {
+        seeds: Seeds,
+        // Unix timestamp after which the permit is no longer accepted
+        expiry: i64,
+    },
+    /// Same as ChangeDestination, but only for schedules releasing at or after `cutoff`. Earlier ones - matured or
+    /// not - stay with the current destination, which from then on claims them with Unlock as the "legacy"
+    /// destination. Only one split can be pending at a time.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[]` The current destination token account
+    ///   2. `[signer]` The destination spl-token account owner (writable if the indexes are passed)
+    ///   3. `[]` The new destination spl-token account
+    ///   4. `[]` (Optional) The system program account, needed to pass the indexes
+    ///   5. `[writable]` (Optional) The destination index of the current destination. Left alone while it's the
+    ///      legacy destination, cleared like in ChangeDestination otherwise
+    ///   6. `[writable]` (Optional) The destination index of the new destination
+    ///   7. `[]` (Optional) The contract the new destination's index points at, if it points at another one - see
+    ///      Create
+    ChangeFutureDestination {
+        seeds: Seeds,
+        cutoff: u64,
+    },
+    /// Checks that the vesting spl-token account holds enough to pay out every remaining schedule, and records the
+    /// result in the header's insolvent flag. A shortfall can come from transfer-fee mints or an external burn.
+    /// Anyone can call it.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[]` The vesting spl-token account
+    VerifySolvency {
+        seeds: Seeds,
+    },
+    /// Turns rolling into the final schedule on or off. While on, Unlock moves every matured schedule other than
+    /// the last-releasing one into it instead of paying it out, so nothing can be claimed before the final release
+    /// time - eg for a lockup extension both parties agreed to. Rolled amounts stay in the final schedule after
+    /// the flag is turned off, and count as unreleased for Revoke like the rest of it.
+    ///
+    /// Both the granter and the destination owner have to sign. Can't be turned on while a ChangeFutureDestination
+    /// split is pending.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The granter
+    ///   2. `[]` The destination spl-token account
+    ///   3. `[signer]` The destination spl-token account owner
+    SetRollover {
+        seeds: Seeds,
+        enabled: bool,
+    },
+    /// Sets the smallest amount Unlock transfers, for destinations where every incoming transfer costs something
+    /// to account for (eg exchange deposit addresses). A claim that empties everything left for the claimant is
+    /// always allowed, so a contract can't get stuck below the minimum. 0 turns the minimum off.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[]` The destination spl-token account
+    ///   2. `[signer]` The destination spl-token account owner
+    SetMinClaimAmount {
+        seeds: Seeds,
+        min_claim_amount: u64,
+    },
+    /// Creates the program config (see find_program_config) and hands it to `admin`. Only the program's upgrade
+    /// authority can send it, and only once per deployment
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[]` The sysvar Rent account
+    ///   2. `[signer, writable]` The fee payer account
+    ///   3. `[writable]` The program config account
+    ///   4. `[]` The program data account of this program
+    ///   5. `[signer]` The program's upgrade authority
+    InitConfig {
+        admin: Pubkey,
+        early_claim_tolerance_seconds: u32,
+    },
+    /// Sets how long before a schedule's release time Unlock already pays it out, to absorb validator clock skew
+    /// around the boundary. Capped at MAX_EARLY_CLAIM_TOLERANCE_SECONDS, 0 turns it off
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The program config account
+    ///   1. `[signer]` The config admin
+    SetEarlyClaimTolerance {
+        early_claim_tolerance_seconds: u32,
+    },
+    /// Sets the share of every Unlock that goes to a withholding token account instead of the destination, eg for
+    /// tax withheld at source. Both transfers are made by the same Unlock, the withheld share rounded down. Capped at
+    /// MAX_WITHHOLDING_BPS, 0 turns it off.
+    ///
+    /// Both the granter and the destination owner have to sign.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The granter
+    ///   2. `[]` The destination spl-token account
+    ///   3. `[signer]` The destination spl-token account owner
+    ///   4. `[]` The withholding spl-token account, of the contract's mint. Only needed if withholding_bps > 0
+    SetWithholding {
+        seeds: Seeds,
+        withholding_bps: u16,
+    },
+    /// Sets the wallet whose token accounts Close can donate dust to. Pubkey::default() turns donations off
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The program config account
+    ///   1. `[signer]` The config admin
+    SetCommunityTreasury {
+        community_treasury: Pubkey,
+    },
+    /// Closes a contract with nothing left to pay out - every schedule claimed or revoked. Whatever is still in the
+    /// vesting token account is dealt with according to `dust_policy`, then both the vesting token account and the
+    /// vesting account are closed and their lamports (including any gas escrow left) go to the granter.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[writable]` The vesting account
+    ///   2. `[writable]` The vesting spl-token account
+    ///   3. `[signer, writable]` The granter account
+    ///   4. `[writable]` Where the dust goes: a granter spl-token account for ReturnToGranter, a community treasury
+    ///      spl-token account for CommunityTreasury, or the mint for Burn
+    ///   5. `[]` The program config, see find_program_config. Only for CommunityTreasury
+    ///   6. `[writable]` The mint, required when it charges Token-2022 transfer fees: the dust goes out short of
+    ///      them, and the fees withheld on the vesting spl-token account are harvested to the mint before it's
+    ///      closed. Recognized by its key
+    Close {
+        seeds: Seeds,
+        dust_policy: DustPolicy,
+    },
+    /// Caps how much a single Unlock transfers, as a circuit breaker on very large claims. Whatever is released
+    /// beyond the cap stays claimable, and the next Unlock can only go through `unlock_interval` seconds after the
+    /// previous one. Can't be lower than the contract's min_claim_amount, 0 turns the cap off. Bounded unlocks don't
+    /// work on a capped contract.
+    ///
+    /// Both the granter and the destination owner have to sign.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The granter
+    ///   2. `[]` The destination spl-token account
+    ///   3. `[signer]` The destination spl-token account owner
+    SetTransferLimit {
+        seeds: Seeds,
+        max_transfer_per_unlock: u64,
+        unlock_interval: u32,
+    },
+    /// Puts a contract's destination changes behind `threshold` of `approvers` (m-of-n), for foundation-held
+    /// grants that shouldn't be re-assigned by a single key. The approvers live in a PDA next to the vesting account
+    /// (see find_destination_approvers) and the destination gets locked, so ChangeDestination fails from then on
+    /// and the destination only moves through ApproveDestinationChange.
+    ///
+    /// Both the granter and the destination owner have to sign. Can't be undone.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[signer, writable]` The fee payer account
+    ///   2. `[writable]` The vesting account
+    ///   3. `[writable]` The destination approvers account, see find_destination_approvers
+    ///   4. `[signer]` The granter
+    ///   5. `[]` The destination spl-token account
+    ///   6. `[signer]` The destination spl-token account owner
+    InitDestinationApprovers {
+        seeds: Seeds,
+        threshold: u8,
+        approvers: Vec<Pubkey>,
+    },
+    /// One approver's vote for moving the contract to `new_destination`, replacing whatever that approver voted for
+    /// before. The vote bringing `threshold` approvers onto the same destination changes it, and clears every vote.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[writable]` The destination approvers account, see find_destination_approvers
+    ///   2. `[signer]` The approver (writable if the indexes are passed)
+    ///   3. `[]` The new destination spl-token account
+    ///   4. `[]` (Optional) The system program account, needed to pass the indexes
+    ///   5. `[writable]` (Optional) The destination index of the current destination, cleared if it points here
+    ///   6. `[writable]` (Optional) The destination index of the new destination. Created on first use, at the
+    ///      approver's expense
+    ///   7. `[]` (Optional) The contract the new destination's index points at, if it points at another one - see
+    ///      Create
+    ///
+    /// The indexes are only touched by the vote that changes the destination.
+    ApproveDestinationChange {
+        seeds: Seeds,
+        new_destination: Pubkey,
+    },
+    /// Moves a contract created on the baseline layout (a 65-byte header of destination, mint and is_initialized,
+    /// then the schedules) onto the current header version: grows the account to fit the header and rewrites it,
+    /// the schedules moving up behind it. Until then the contract can't be used by any other instruction. Anyone can
+    /// send it, as there's nothing to choose - the payer covers the rent of the extra bytes
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[signer, writable]` The fee payer account
+    ///   2. `[writable]` The baseline vesting account
+    MigrateLayout,
+    /// Drops the schedules that have been paid out from a contract, moving the rest down, and shrinks the vesting
+    /// account to match. The rent it no longer needs goes to the rent receiver, any gas escrow stays. Fails if there's
+    /// nothing to drop, or nothing would be left - Close is for finished contracts
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The sysvar Rent account
+    ///   1. `[writable]` The vesting account
+    ///   2. `[signer]` The granter
+    ///   3. `[writable]` The rent receiver
+    Compact {
+        seeds: Seeds,
+    },
+    /// Turns the self-dealing check of Create on or off for the whole deployment, see Create's forbid_self_dealing.
+    /// Create only sees it when it's passed the program config
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The program config account
+    ///   1. `[signer]` The config admin
+    SetForbidSelfDealing {
+        forbid_self_dealing: bool,
+    },
+    /// RevokeBatch for a single contract: everything that hasn't been released yet is transferred back to the
+    /// granter recorded at Create (or burned, see RevokePolicy), already released amounts stay claimable by the
+    /// destination.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The clock sysvar account
+    ///   2. `[signer]` The granter account (source token account owner at Create)
+    ///   3. `[writable]` The vesting account
+    ///   4. `[writable]` The vesting spl-token account
+    ///   5. `[writable]` The granter spl-token account receiving the unvested tokens, or the mint if the contract
+    ///      burns them
+    ///   6. `[writable]` The mint's emission curve, required while the contract is tracked by it (see
+    ///      EmissionCurve)
+    ///   7. `[]` The mint, required when it charges Token-2022 transfer fees, which the granter receives the
+    ///      tokens short of. Recognized by its key, like the emission curve
+    ///   8. `[]` (Optional) The program config, see find_program_config. Schedules within its early claim tolerance
+    ///      count as released, as they do for Unlock. Recognized as the config wherever it goes after 5
+    Revoke {
+        seeds: Seeds,
+        // Same as ChangeDestination's
+        expected_revision: Option<u32>,
+    },
+    /// Unlock for a contract from InitGrant. Takes what the seeds are derived from (see vesting_seeds) instead of the
+    /// seeds themselves, so the beneficiary can claim from any wallet that knows who granted what to whom.
+    /// `beneficiary` is the one the granter picked at InitGrant - a later TransferBeneficiary doesn't move the
+    /// contract, so its seeds stay the same
+    ///
+    /// Accounts expected by this instruction: same as Unlock
+    UnlockGrant {
+        granter: Pubkey,
+        beneficiary: Pubkey,
+        mint: Pubkey,
+        nonce: u64,
+    },
+    /// Create, with the tokens minted straight into the vesting spl-token account instead of coming out of a
+    /// pre-funded source account. The mint authority signs and is recorded as the granter. Fails with
+    /// MintSupplyCapExceeded if the mint's supply would end up above `max_supply`. Takes none of Create's options - no
+    /// gas escrow, no minimum claim, destination not locked
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[writable]` The vesting account
+    ///   2. `[writable]` The vesting spl-token account
+    ///   3. `[signer]` The mint authority (writable if the index is passed)
+    ///   4. `[writable]` The mint
+    ///   5. `[]` The destination spl-token account, of the contract's mint
+    ///   6. `[]` (Optional) The system program account, needed if the index is passed
+    ///   7. `[writable]` (Optional) The destination index of (mint, destination), see find_destination_index
+    ///   8. `[]` (Optional) The program config, see find_program_config
+    ///   9. `[writable]` (Optional) The mint's emission curve, see find_emission_curve
+    ///
+    ///   Found the same way as Create's
+    CreateFromMint {
+        seeds: Seeds,
+        token_mint_addr: Pubkey,
+        token_dest_addr: Pubkey,
+        allow_immediate: bool,
+        // The most the mint's supply may reach with the grant minted, u64::MAX for no cap beyond spl-token's own
+        max_supply: u64,
+        schedules: Vec<Schedule>,
+    },
+    /// Create, for a contract releasing `cliff_amount` at `cliff_time` and `total_amount` continuously between
+    /// `start_time` and `end_time`, instead of in tranches. Every Unlock pays out whatever has been released since the
+    /// previous one, and Revoke keeps what has been released so far. Needs an account from an Init with
+    /// ScheduleLayout::Linear, and takes none of Create's options
+    ///
+    /// Accounts expected by this instruction: same as Create
+    CreateLinear {
+        seeds: Seeds,
+        token_mint_addr: Pubkey,
+        token_dest_addr: Pubkey,
+        allow_immediate: bool,
+        // Ignored when cliff_amount is 0
+        cliff_time: u64,
+        cliff_amount: u64,
+        start_time: u64,
+        // Has to be after start_time
+        end_time: u64,
+        total_amount: u64,
+    },
+    /// Adds schedules to an existing contract instead of creating a new one for every grant adjustment. The vesting
+    /// account grows by SCHEDULE_SIZE bytes per schedule, at the granter's expense, and the schedules' total is
+    /// transferred from the granter's source spl-token account. Only for plain contracts that still have something
+    /// to pay out, and only the granter recorded at Create can send it
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The system program account
+    ///   2. `[writable]` The vesting account
+    ///   3. `[writable]` The vesting spl-token account
+    ///   4. `[signer, writable]` The granter account
+    ///   5. `[writable]` The source spl-token account
+    ///   6. `[writable]` The mint's emission curve, required while the contract is tracked by it (see
+    ///      EmissionCurve)
+    ///   7. `[]` The mint, required when it charges Token-2022 transfer fees, which the granter pays on top as with
+    ///      Create. Recognized by its key, like the emission curve
+    TopUp {
+        seeds: Seeds,
+        // Same as Create's
+        allow_immediate: bool,
+        schedules: Vec<Schedule>,
+    },
+    /// Sets the header's has_claimable hint from the clock, for contracts that became claimable by time passing since
+    /// their last Create or Unlock. Anyone can call it, eg a crank refreshing contracts ahead of a memcmp scan on the
+    /// hint. The hint is only as precise as next_release_time
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The clock sysvar account
+    ///   1. `[writable]` The vesting account
+    RefreshClaimable {
+        seeds: Seeds,
+    },
+    /// Sets who can submit an Unlock, see UnlockPolicy. Custodians that don't want a crank triggering claims (and
+    /// the taxable events that come with them) can keep it to the destination owner, or to the delegate they
+    /// approve on the destination spl-token account
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[]` The destination spl-token account
+    ///   2. `[signer]` The destination spl-token account owner
+    SetUnlockPolicy {
+        seeds: Seeds,
+        policy: UnlockPolicy,
+    },
+    /// Releases everything the contract still holds right away, eg for an acqui-hire. Every schedule releasing after
+    /// now is moved to now (a linear contract ends now), so the next Unlock pays out the whole balance. There's
+    /// nothing left for a Revoke afterwards. Refused while a ChangeFutureDestination split is pending, as moving release
+    /// times would move schedules between the two destinations
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The clock sysvar account
+    ///   1. `[signer]` The granter account
+    ///   2. `[writable]` The vesting account
+    ///   3. `[writable]` The mint's emission curve, required while the contract is tracked by it (see
+    ///      EmissionCurve)
+    Accelerate {
+        seeds: Seeds,
+    },
+    /// Hands the granter's side of the contract - Revoke, Accelerate, Close and the settings the granter signs for -
+    /// over to another key, eg when a treasury rotates its keys. Only the current granter can sign it
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The current granter account
+    ChangeGranter {
+        seeds: Seeds,
+        new_granter: Pubkey,
+    },
+    /// Starts a contract created with `relative` set: the clock is added to each of its release times and stored as
+    /// the header's activation_time, and from then on it's like any other contract. Until then Unlock refuses it, and
+    /// Revoke takes back all of it
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The clock sysvar account
+    ///   1. `[signer]` The granter account
+    ///   2. `[writable]` The vesting account
+    Activate {
+        seeds: Seeds,
+    },
+    /// Holds back the contract's payouts, eg during a dispute or an off-boarding, without revoking anything: Unlock
+    /// refuses it until `Resume`, while its schedules keep vesting by the clock as before
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The granter account
+    Pause {
+        seeds: Seeds,
+    },
+    /// Lets Unlock pay out a paused contract again, including whatever vested while it was paused
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The granter account
+    Resume {
+        seeds: Seeds,
+    },
+    /// Sweeps everything in the vesting spl-token account, released or not, to a recovery account and closes out the
+    /// schedules, eg when the destination's wallet is compromised. Only the emergency_authority set at Create can sign
+    /// it, and contracts created without one can't be swept at all
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[writable]` The vesting account
+    ///   2. `[writable]` The vesting spl-token account
+    ///   3. `[signer]` The emergency authority account
+    ///   4. `[writable]` The recovery spl-token account, of the contract's mint
+    ///   5. `[writable]` The mint's emission curve, required while the contract is tracked by it (see
+    ///      EmissionCurve)
+    ///   6. `[]` The mint, required when it charges Token-2022 transfer fees, which the recovery account receives
+    ///      the tokens short of. Recognized by its key, like the emission curve
+    EmergencyWithdraw {
+        seeds: Seeds,
+    },
+    /// Moves every release time of the contract `offset` seconds earlier, so that QA can fast-forward a contract on
+    /// devnet instead of waiting for it. Only exists in builds with the devnet-tools feature, which can't be built
+    /// with the default (mainnet) features
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The granter account
+    ///   2. `[writable]` The mint's emission curve, required while the contract is tracked by it (see
+    ///      EmissionCurve)
+    #[cfg(feature = "devnet-tools")]
+    DebugShiftSchedules {
+        seeds: Seeds,
+        offset: u64,
+    },
+    /// Splits every Unlock between the destination and up to MAX_SPLIT_DESTINATIONS other spl-token accounts, eg
+    /// 70/20/10 between a founder, an advisor and a treasury as a destination keeping 7_000 bps next to splits of 2_000
+    /// and 1_000. Each split takes its bps of what the destination would have been paid, rounded down, and the
+    /// destination keeps the rest. Withholding comes off first, and schedules claimed by a legacy destination aren't
+    /// split. Replaces any splits already set, an empty list turns splitting off.
+    ///
+    /// Both the granter and the destination owner have to sign.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer]` The granter
+    ///   2. `[]` The destination spl-token account
+    ///   3. `[signer]` The destination spl-token account owner
+    ///   4. `[]` The spl-token account of each split, of the contract's mint, in the same order as `splits`
+    SetSplits {
+        seeds: Seeds,
+        // 34 bytes each on the wire: the spl-token account, then its bps
+        splits: Vec<SplitDestination>,
+    },
+    /// Puts what the tracked contracts of a mint (see EmissionCurve) release in each of the next `weeks` weeks on the
+    /// return data, starting with the current week, for on-chain consumers to read with get_return_data after
+    /// invoking it. Anyone can call it, and it changes nothing. See unpack_emissions for the layout
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The clock sysvar account
+    ///   1. `[]` The mint's emission curve, see find_emission_curve
+    ReadEmissions {
+        mint_address: Pubkey,
+        // At most MAX_READ_EMISSION_WEEKS, what fits in the return data
+        weeks: u8,
+    },
+    /// Consolidates two grants of the same granter to the same destination, in the same mint: appends what the
+    /// merged contract still has to pay out to the schedules of the one kept, moves the whole balance of its vesting
+    /// token account over and closes it. The kept contract's terms then apply to everything, so they have to be the
+    /// merged one's already. Its vesting account grows by SCHEDULE_SIZE bytes per schedule moved, paid for by the rent
+    /// of the merged one; the rest of that rent goes to the granter, and the merged gas escrow moves along.
+    ///
+    /// Both contracts have to be plain and active, and either both or neither tracked on the mint's emission curve -
+    /// which then has nothing to update. Neither can have a legacy destination left, and they have to agree on
+    /// withholding, splits, emergency authority, unlock policy, transfer cap and revocability. Both the granter and
+    /// the destination owner have to sign.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The spl-token program account
+    ///   1. `[]` The system program account
+    ///   2. `[writable]` The vesting account kept
+    ///   3. `[writable]` Its vesting spl-token account
+    ///   4. `[writable]` The vesting account merged into it, closed
+    ///   5. `[writable]` Its vesting spl-token account, closed
+    ///   6. `[signer, writable]` The granter of both
+    ///   7. `[]` The destination spl-token account of both
+    ///   8. `[signer]` The destination spl-token account owner
+    ///   9. `[writable]` (Optional) The destination index of (mint, destination), see find_destination_index. Moved
+    ///      over to the kept contract if it points at the merged one
+    ///   10. `[writable]` The mint, required when it charges Token-2022 transfer fees: the kept contract receives
+    ///       the merged balance short of them, and the fees withheld on the merged vesting spl-token account are
+    ///       harvested to the mint before it's closed. Recognized by its key, so it can take the place of 9
+    Merge {
+        seeds: Seeds,
+        merged_seeds: Seeds,
+    },
+    /// ChangeDestination for a destination owner moving wallets: the new destination is the associated token account
+    /// of `new owner` for the contract's mint, derived on-chain and created through the associated token account
+    /// program if it doesn't exist yet. `rent_payer` picks who pays for it: the current owner, a cranker, or the gas
+    /// escrow, which takes the granter's signature and fails with InsufficientFunds if it can't cover the rent
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[]` The current destination token account
+    ///   2. `[signer, writable]` The destination spl-token account owner
+    ///   3. `[writable]` The new owner's associated token account for the contract's mint
+    ///   4. `[]` The new owner
+    ///   5. `[]` The contract's mint
+    ///   6. `[]` The system program account
+    ///   7. `[]` The spl-token program account
+    ///   8. `[]` The associated token account program account
+    ///   9. `[writable]` (Optional) The destination index of the current destination, cleared if it points here
+    ///   10. `[writable]` (Optional) The destination index of the new destination, created on first use. Passed
+    ///       along with 9 or not at all
+    ///   11. `[]` (Optional) The contract the new destination's index points at, if it points at another one - see
+    ///       Create. Only with 9 and 10
+    ///   12. `[signer, writable]` The cranker paying the rent for RentPayer::Cranker, the granter for
+    ///       RentPayer::GranterEscrow, not passed for RentPayer::Beneficiary. Always the last account, whether or not
+    ///       9 to 11 are passed
+    TransferBeneficiary {
+        seeds: Seeds,
+        rent_payer: RentPayer,
+    },
+    /// Fails with StaleRevision unless the contract is at `expected_revision`. Changes nothing: a client puts it in
+    /// front of a change built against what it last read, so that the change doesn't land on top of another one made
+    /// in the meantime (see VestingScheduleHeader::revision)
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The vesting account
+    CheckRevision {
+        seeds: Seeds,
+        expected_revision: u32,
+    },
+    /// Grows an account Init sized for too few schedules, so that it fits `number_of_schedules` of them (and the
+    /// categories or the LinearVesting of `schedule_layout`) after all. Only before Create: a created contract's
+    /// account holds exactly its schedules, and TopUp grows it along with them. The fee payer covers the extra rent
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[]` The system program account
+    ///   1. `[signer, writable]` The fee payer account
+    ///   2. `[writable]` The vesting account
+    Resize {
+        seeds: Seeds,
+        number_of_schedules: u32,
+        // Same as Init's, left off the wire when Plain
+        schedule_layout: ScheduleLayout,
+    },
+    /// Gives back the rent of an account Init-ed but never Created. Every lamport on it goes to whoever paid for
+    /// Init, as recorded in the account, and only with their signature. An account Init-ed before the payer was
+    /// recorded is still all zeroes and can't be closed this way
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The vesting account
+    ///   1. `[signer, writable]` The account that paid for Init
+    CloseUninitialized {
+        seeds: Seeds,
+    },
+    /// Init for a contract the parties find by who they are, see vesting_seeds. The fee payer is the granter: the
+    /// seeds are hashed from its key, and the account goes at pda::find_granted_vesting_address of them, where nothing
+    /// else creates accounts. Create then only takes the contract from the granter, so no one else can get in first
+    /// at the address the parties derive
+    ///
+    /// Accounts expected by this instruction: same as Init
+    InitGrant {
+        beneficiary: Pubkey,
+        mint: Pubkey,
+        nonce: u64,
+        number_of_schedules: u32,
+        // Same as Init's
+        schedule_layout: ScheduleLayout,
+    },
+}
+
+/// One contract to be revoked by `revoke_batch`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RevokeBatchEntry {
+    pub vesting_account_key: Pubkey,
+    pub vesting_token_account_key: Pubkey,
+    /// The mint instead, for a contract created with RevokePolicy::Burn
+    pub granter_token_account_key: Pubkey,
+    pub seeds: Seeds,
+}
+
+pub const SCHEDULE_SIZE: usize = 16;
+
+/// Domain separator for child_seeds, so a child can never collide with a hash used elsewhere
+pub const CHILD_SEED_PREFIX: &[u8] = b"child";
+
+/// Domain separator for vesting_seeds
+pub const VESTING_SEED_PREFIX: &[u8] = b"vesting";
+
+/// Seed of the PDA (of the new deployment) that owns migration escrows, next to the old vesting account's key
+pub const MIGRATION_SEED: &[u8] = b"migration";
+
+/// Seed of the program config PDA. There's only one per deployment
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seed of the destination index PDAs, next to the mint and the destination token account
+pub const DESTINATION_INDEX_SEED: &[u8] = b"destination";
+
+/// Seed of the destination approvers PDAs, next to the vesting account's key
+pub const DESTINATION_APPROVERS_SEED: &[u8] = b"approvers";
+
+/// Seed of the emission curve PDAs, next to the mint
+pub const EMISSION_CURVE_SEED: &[u8] = b"emissions";
+
+/// Most weeks a ReadEmissions can ask for: the week and the weekly amounts have to fit in MAX_RETURN_DATA
+pub const MAX_READ_EMISSION_WEEKS: u8 = ((MAX_RETURN_DATA - 8) / 8) as u8;
+
+/// Domain separator for claim permits, so the destination owner's signature can't be lifted from another message
+pub const CLAIM_PERMIT_PREFIX: &[u8] = b"claim permit";
+
+/// Layout of an ed25519 program instruction holding a single signature, with everything inside the instruction:
+/// count (1) + padding (1) + 7 u16 offsets, followed by the public key, the signature and the message
+pub const ED25519_OFFSETS_START: usize = 2;
+pub const ED25519_DATA_START: usize = 16;
+pub const ED25519_PUBKEY_SIZE: usize = 32;
+pub const ED25519_SIGNATURE_SIZE: usize = 64;
+
+// ----------------------------------------------------------------------------- account specs
+
+/// An account an instruction expects. The builders make their AccountMetas out of these, and the processor checks
+/// what it's passed against the same ones before running the instruction - see VestingInstruction::account_specs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AccountSpec {
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+    /// Optional accounts come after the required ones and are up to the instruction to find (mostly by key) and
+    /// check. Builders pass them whenever they have them
+    pub optional: bool,
+}
+
+impl AccountSpec {
+    pub const fn readonly(name: &'static str) -> Self {
+        Self {
+            name,
+            writable: false,
+            signer: false,
+            optional: false,
+        }
+    }
+
+    pub const fn writable(name: &'static str) -> Self {
+        Self {
+            writable: true,
+            ..Self::readonly(name)
+        }
+    }
+
+    pub const fn signer(name: &'static str) -> Self {
+        Self {
+            signer: true,
+            ..Self::readonly(name)
+        }
+    }
+
+    pub const fn writable_signer(name: &'static str) -> Self {
+        Self {
+            signer: true,
+            ..Self::writable(name)
+        }
+    }
+
+    pub const fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+
+    pub fn meta(&self, key: &Pubkey) -> AccountMeta {
+        AccountMeta {
+            pubkey: *key,
+            is_signer: self.signer,
+            is_writable: self.writable,
+        }
+    }
+}
+
+/// AccountMetas for the first `keys.len()` accounts of `specs` - every required one, and as many optional ones as
+/// there are keys for
+pub fn account_metas(specs: &[AccountSpec], keys: &[&Pubkey]) -> Vec<AccountMeta> {
+    assert!(keys.len() <= specs.len() && specs[keys.len()..].iter().all(|s| s.optional));
+    specs
+        .iter()
+        .zip(keys)
+        .map(|(spec, key)| spec.meta(key))
+        .collect()
+}
+
+pub const INIT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::readonly("rent sysvar"),
+    AccountSpec::writable_signer("fee payer"),
+    AccountSpec::writable("vesting account"),
+];
+
+/// Create and CreateLinear. The system program and the destination index are taken in that order from whatever is
+/// left after the program config and the emission curve, which are recognized by key
+pub const CREATE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::signer("source token account owner"),
+    AccountSpec::writable("source token account"),
+    AccountSpec::readonly("mint"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::readonly("system program").optional(),
+    AccountSpec::writable("destination index").optional(),
+    AccountSpec::readonly("program config").optional(),
+];
+
+/// Create with a gas deposit, or with the source token account owner paying for a destination index or an emission
+/// curve. The owner pays with the system program, so it's writable and the system program is required
+pub const PAYING_CREATE_ACCOUNTS: &[AccountSpec] = &[
+    CREATE_ACCOUNTS[0],
+    CREATE_ACCOUNTS[1],
+    CREATE_ACCOUNTS[2],
+    AccountSpec::writable_signer("source token account owner"),
+    CREATE_ACCOUNTS[4],
+    CREATE_ACCOUNTS[5],
+    CREATE_ACCOUNTS[6],
+    AccountSpec::readonly("system program"),
+    CREATE_ACCOUNTS[8],
+    CREATE_ACCOUNTS[9],
+];
+
+pub const CREATE_FROM_MINT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::signer("mint authority"),
+    AccountSpec::writable("mint"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::readonly("system program").optional(),
+    AccountSpec::writable("destination index").optional(),
+    AccountSpec::readonly("program config").optional(),
+];
+
+/// Unlock and UnlockGrant. The optional accounts after the rebate receiver are recognized by their keys, in any order
+pub const UNLOCK_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("clock sysvar"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable("destination token account"),
+    AccountSpec::writable_signer("rebate receiver").optional(),
+    AccountSpec::readonly("program config").optional(),
+    AccountSpec::writable("withholding token account").optional(),
+    AccountSpec::writable("split token account").optional(),
+    EMISSION_CURVE_ACCOUNT,
+    AccountSpec::readonly("mint").optional(),
+];
+
+pub const UNLOCK_WITH_PERMIT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("clock sysvar"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable("destination token account"),
+    AccountSpec::readonly("instructions sysvar"),
+    AccountSpec::writable_signer("rebate receiver").optional(),
+];
+
+/// ChangeDestination and ChangeFutureDestination
+pub const CHANGE_DESTINATION_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::readonly("current destination token account"),
+    AccountSpec::signer("destination token account owner"),
+    AccountSpec::readonly("new destination token account"),
+    AccountSpec::readonly("system program").optional(),
+    AccountSpec::writable("current destination index").optional(),
+    AccountSpec::writable("new destination index").optional(),
+    AccountSpec::readonly("new destination index's contract").optional(),
+];
+
+pub const CREATE_COMPRESSED_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::readonly("rent sysvar"),
+    AccountSpec::writable_signer("fee payer"),
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::writable("compressed vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::signer("source token account owner"),
+    AccountSpec::writable("source token account"),
+    AccountSpec::writable("merkle tree"),
+    AccountSpec::readonly("account-compression program"),
+    AccountSpec::readonly("noop program"),
+];
+
+pub const APPEND_COMPRESSED_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("compressed vesting account"),
+    AccountSpec::signer("granter"),
+    AccountSpec::writable("merkle tree"),
+    AccountSpec::readonly("account-compression program"),
+    AccountSpec::readonly("noop program"),
+];
+
+/// ClaimCompressed's accounts in front of the proof
+pub const CLAIM_COMPRESSED_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("clock sysvar"),
+    AccountSpec::readonly("compressed vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable("destination token account"),
+    AccountSpec::writable("merkle tree"),
+    AccountSpec::readonly("account-compression program"),
+    AccountSpec::readonly("noop program"),
+];
+
+/// Revoke, and the accounts of RevokeBatch before the ones of its contracts
+pub const REVOKE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("clock sysvar"),
+    AccountSpec::signer("granter"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable("granter token account"),
+];
+
+/// Granter-only changes that go by the clock: Accelerate and Activate
+pub const GRANTER_CLOCK_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("clock sysvar"),
+    AccountSpec::signer("granter"),
+    AccountSpec::writable("vesting account"),
+];
+
+/// Granter-only settings: ChangeGranter, Pause, Resume and DebugShiftSchedules
+pub const GRANTER_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::signer("granter"),
+];
+
+pub const EMERGENCY_WITHDRAW_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::signer("emergency authority"),
+    AccountSpec::writable("recovery token account"),
+];
+
+pub const EXPORT_CONTRACT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable("escrow token account"),
+    AccountSpec::signer("granter"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::signer("destination token account owner"),
+];
+
+pub const IMPORT_CONTRACT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::readonly("rent sysvar"),
+    AccountSpec::writable_signer("fee payer"),
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("migrated vesting account"),
+    AccountSpec::readonly("migration authority"),
+    AccountSpec::writable("escrow token account"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+];
+
+pub const TOP_UP_GAS_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::writable_signer("funder"),
+    AccountSpec::writable("vesting account"),
+];
+
+/// Settings only the destination owner signs for: SetPermissionlessUnlock, SetUnlockPolicy and SetMinClaimAmount
+pub const DESTINATION_OWNER_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::signer("destination token account owner"),
+];
+
+pub const VERIFY_SOLVENCY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::readonly("vesting token account"),
+];
+
+/// Settings both parties sign for: SetRollover, SetTransferLimit and SetSplits, which is followed by the spl-token
+/// account of every split
+pub const BOTH_PARTIES_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::signer("granter"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::signer("destination token account owner"),
+];
+
+pub const SET_WITHHOLDING_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::signer("granter"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::signer("destination token account owner"),
+    AccountSpec::readonly("withholding token account").optional(),
+];
+pub const INIT_CONFIG_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::readonly("rent sysvar"),
+    AccountSpec::writable_signer("fee payer"),
+    AccountSpec::writable("program config"),
+    AccountSpec::readonly("program data"),
+    AccountSpec::signer("upgrade authority"),
+];
+
+/// Settings of the program config: SetEarlyClaimTolerance, SetCommunityTreasury and SetForbidSelfDealing
+pub const CONFIG_ADMIN_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("program config"),
+    AccountSpec::signer("config admin"),
+];
+
+pub const CLOSE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable_signer("granter"),
+    AccountSpec::writable("dust receiver"),
+    AccountSpec::readonly("program config").optional(),
+];
+
+pub const INIT_DESTINATION_APPROVERS_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::writable_signer("fee payer"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("destination approvers"),
+    AccountSpec::signer("granter"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::signer("destination token account owner"),
+];
+
+pub const APPROVE_DESTINATION_CHANGE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("destination approvers"),
+    AccountSpec::signer("approver"),
+    AccountSpec::readonly("new destination token account"),
+    AccountSpec::readonly("system program").optional(),
+    AccountSpec::writable("current destination index").optional(),
+    AccountSpec::writable("new destination index").optional(),
+    AccountSpec::readonly("new destination index's contract").optional(),
+];
+
+pub const MIGRATE_LAYOUT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::writable_signer("fee payer"),
+    AccountSpec::writable("baseline vesting account"),
+];
+
+pub const TOP_UP_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("system program"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable_signer("granter"),
+    AccountSpec::writable("source token account"),
+];
+
+pub const MERGE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("system program"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable("vesting token account"),
+    AccountSpec::writable("merged vesting account"),
+    AccountSpec::writable("merged vesting token account"),
+    AccountSpec::writable_signer("granter"),
+    AccountSpec::readonly("destination token account"),
+    AccountSpec::signer("destination token account owner"),
+    AccountSpec::writable("destination index").optional(),
+];
+
+pub const TRANSFER_BENEFICIARY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::readonly("current destination token account"),
+    AccountSpec::writable_signer("destination token account owner"),
+    AccountSpec::writable("new destination token account"),
+    AccountSpec::readonly("new owner"),
+    AccountSpec::readonly("mint"),
+    AccountSpec::readonly("system program"),
+    AccountSpec::readonly("spl-token program"),
+    AccountSpec::readonly("associated token account program"),
+    AccountSpec::writable("current destination index").optional(),
+    AccountSpec::writable("new destination index").optional(),
+    AccountSpec::readonly("new destination index's contract").optional(),
+    AccountSpec::writable_signer("rent payer").optional(),
+];
+
+pub const CHECK_REVISION_ACCOUNTS: &[AccountSpec] = &[AccountSpec::readonly("vesting account")];
+
+pub const RESIZE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("system program"),
+    AccountSpec::writable_signer("fee payer"),
+    AccountSpec::writable("vesting account"),
+];
+
+pub const CLOSE_UNINITIALIZED_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable("vesting account"),
+    AccountSpec::writable_signer("init payer"),
+];
+
+pub const READ_EMISSIONS_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("clock sysvar"),
+    AccountSpec::readonly("emission curve"),
+];
+
+/// Appended to every instruction changing a tracked contract's schedules, see with_emission_curve
+pub const EMISSION_CURVE_ACCOUNT: AccountSpec = AccountSpec::writable("emission curve").optional();
+
+pub const REFRESH_CLAIMABLE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("clock sysvar"),
+    AccountSpec::writable("vesting account"),
+];
+
+pub const COMPACT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("rent sysvar"),
+    AccountSpec::writable("vesting account"),
+    AccountSpec::signer("granter"),
+    AccountSpec::writable("rent receiver"),
+];
+
+// #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+// #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Schedule {
+    pub release_time: u64, //in SECONDS, not milliseconds
+    pub amount: u64,
+}
+
+impl Schedule {
+    /// Builds a schedule releasing at the given date/time, in whatever time zone it's expressed in.
+    /// Dates before 1970 can't be represented on-chain and return None
+    #[cfg(feature = "chrono")]
+    pub fn at<Tz: chrono::TimeZone>(datetime: chrono::DateTime<Tz>, amount: u64) -> Option<Self> {
+        use std::convert::TryFrom;
+        let release_time = u64::try_from(datetime.timestamp()).ok()?;
+        Some(Self {
+            release_time,
+            amount,
+        })
+    }
+}
+
+impl VestingInstruction {
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        msg!("input is {:?}", input);
+
+        // Below are listed 3 different ways of deserializing the incoming byte array.
+        // Uncomment the appropriate one.
+        // you might have to derive Serialize, Deserialize / BorshSerialize, BorshDeserialize on a few structs/enums to make the code compile
+
+        // ----------------------------------------------------------------------------- 1 manual
+        let (&tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
+        // every payload has exactly one valid encoding - trailing bytes are rejected rather than ignored
+        let result = match tag {
+            0 if rest.len() == 37 => Self::Init {
+                seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                number_of_schedules: Self::unpack_u32(rest, 32)?,
+                // Plain has the shorter encoding
+                schedule_layout: match ScheduleLayout::from_u8(rest[36]) {
+                    Some(layout @ (ScheduleLayout::Categorized | ScheduleLayout::Linear)) => layout,
+                    _ => return Err(InvalidInstruction.into()),
+                },
+            },
+            0 => {
+                Self::expect_len(rest, 36)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let number_of_schedules = Self::unpack_u32(rest, 32)?;
+                Self::Init {
+                    seeds,
+                    number_of_schedules,
+                    schedule_layout: ScheduleLayout::Plain,
+                }
+            }
+            #[cfg(feature = "bonfida-compat")]
+            1 if crate::bonfida::is_create_payload(rest) => {
+                crate::bonfida::BonfidaInstruction::unpack(input)?.into()
+            }
+            1 | 18 => {
+                // a categorized schedule takes a category byte on top, stored after all the schedules
+                let item_len = if tag == 18 {
+                    SCHEDULE_SIZE + 1
+                } else {
+                    SCHEDULE_SIZE
+                };
+                Self::expect_len_with_tail(rest, 158, item_len)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let token_mint_addr = Self::unpack_addr(rest, 32)?;
+                let token_dest_addr = Self::unpack_addr(rest, 64)?;
+                let allow_immediate = Self::unpack_bool(rest, 96)?;
+                let gas_rebate = Self::unpack_u64(rest, 97)?;
+                let gas_deposit = Self::unpack_u64(rest, 105)?;
+                let min_claim_amount = Self::unpack_u64(rest, 113)?;
+                let destination_locked = Self::unpack_bool(rest, 121)?;
+                let forbid_self_dealing = Self::unpack_bool(rest, 122)?;
+                let revoke_policy = RevokePolicy::from_u8(rest[123]).ok_or(InvalidInstruction)?;
+                let relative = Self::unpack_bool(rest, 124)?;
+                let emergency_authority = Self::unpack_addr(rest, 125)?;
+                let revocable = Self::unpack_bool(rest, 157)?;
+
+                let number_of_schedules = rest[158..].len() / item_len;
+                let mut schedules: Vec<Schedule> = Vec::with_capacity(number_of_schedules);
+                let mut offset = 158;
+
+                for _ in 0..number_of_schedules {
+                    let release_time = Self::unpack_u64(rest, offset)?;
+                    let amount = Self::unpack_u64(rest, offset + 8)?;
+                    offset += SCHEDULE_SIZE;
+                    schedules.push(Schedule {
+                        release_time,
+                        amount,
+                    })
+                }
+
+                let categories = if tag == 18 {
+                    if number_of_schedules == 0 {
+                        msg!("a categorized contract needs at least one schedule");
+                        return Err(InvalidInstruction.into());
+                    }
+                    rest[offset..]
+                        .iter()
+                        .map(|c| ScheduleCategory::from_u8(*c).ok_or(InvalidInstruction))
+                        .collect::<Result<Vec<ScheduleCategory>, VestingError>>()?
+                } else {
+                    vec![]
+                };
+
+                Self::Create {
+                    seeds,
+                    token_mint_addr,
+                    token_dest_addr,
+                    allow_immediate,
+                    gas_rebate,
+                    gas_deposit,
+                    min_claim_amount,
+                    destination_locked,
+                    forbid_self_dealing,
+                    revoke_policy,
+                    relative,
+                    emergency_authority,
+                    revocable,
+                    schedules,
+                    categories,
+                }
+            }
+            // after max_schedules_per_call: max_amount (8 bytes) and/or start_index (4 bytes)
+            2 if matches!(rest.len(), 36 | 40 | 44 | 48) => {
+                let max_schedules_per_call = Self::unpack_u32(rest, 32)?;
+                // an unbounded Unlock with nothing else on it is the 32 byte one
+                if rest.len() == 36 && max_schedules_per_call == 0 {
+                    return Err(InvalidInstruction.into());
+                }
+                let max_amount = match rest.len() {
+                    44 | 48 => Self::unpack_u64(rest, 36)?,
+                    _ => 0,
+                };
+                // and a max_amount of 0 is always left off, so that 44 and 48 bytes don't decode like 36 and 40
+                if matches!(rest.len(), 44 | 48) && max_amount == 0 {
+                    return Err(InvalidInstruction.into());
+                }
+                Self::Unlock {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    max_schedules_per_call,
+                    max_amount,
+                    start_index: match rest.len() {
+                        40 | 48 => Some(Self::unpack_u32(rest, rest.len() - 4)?),
+                        _ => None,
+                    },
+                }
+            }
+            // after the seeds: expected_revision (4 bytes)
+            3 | 30 if rest.len() == 36 => {
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let expected_revision = Some(Self::unpack_u32(rest, 32)?);
+                match tag {
+                    3 => Self::ChangeDestination {
+                        seeds,
+                        expected_revision,
+                    },
+                    _ => Self::Revoke {
+                        seeds,
+                        expected_revision,
+                    },
+                }
+            }
+            2 | 3 | 15 => {
+                Self::expect_len(rest, 32)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                match tag {
+                    2 => Self::Unlock {
+                        seeds,
+                        max_schedules_per_call: 0,
+                        max_amount: 0,
+                        start_index: None,
+                    },
+                    3 => Self::ChangeDestination {
+                        seeds,
+                        expected_revision: None,
+                    },
+                    _ => Self::VerifySolvency { seeds },
+                }
+            }
+            4 => {
+                Self::expect_len(rest, 4)?;
+                let number = Self::unpack_u32(rest, 0)?;
+                Self::Empty { number }
+            }
+            5 => {
+                Self::expect_len(rest, 80)?;
+                Self::CreateCompressed {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    token_mint_addr: Self::unpack_addr(rest, 32)?,
+                    max_depth: Self::unpack_u32(rest, 64)?,
+                    max_buffer_size: Self::unpack_u32(rest, 68)?,
+                    total_amount: Self::unpack_u64(rest, 72)?,
+                }
+            }
+            6 => {
+                Self::expect_len(rest, 84)?;
+                Self::ClaimCompressed {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    root: Self::unpack_seeds(rest, 32).ok_or(InvalidInstruction)?,
+                    index: Self::unpack_u32(rest, 64)?,
+                    release_time: Self::unpack_u64(rest, 68)?,
+                    amount: Self::unpack_u64(rest, 76)?,
+                }
+            }
+            7 => {
+                Self::expect_len_with_tail(rest, 0, 32)?;
+                let number_of_contracts = rest.len() / 32;
+                if number_of_contracts == 0 {
+                    return Err(InvalidInstruction.into());
+                }
+                let mut seeds: Vec<Seeds> = Vec::with_capacity(number_of_contracts);
+                for i in 0..number_of_contracts {
+                    seeds.push(Self::unpack_seeds(rest, i * 32).ok_or(InvalidInstruction)?);
+                }
+                Self::RevokeBatch { seeds }
+            }
+            8 => {
+                Self::expect_len(rest, 64)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let new_program_id = Self::unpack_addr(rest, 32)?;
+                Self::ExportContract {
+                    seeds,
+                    new_program_id,
+                }
+            }
+            9 => {
+                Self::expect_len(rest, 32)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                Self::ImportContract { seeds }
+            }
+            10 => {
+                Self::expect_len(rest, 40)?;
+                let base_seed = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let index = Self::unpack_u32(rest, 32)?;
+                let number_of_schedules = Self::unpack_u32(rest, 36)?;
+                Self::InitChild {
+                    base_seed,
+                    index,
+                    number_of_schedules,
+                }
+            }
+            11 => {
+                Self::expect_len(rest, 40)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let amount = Self::unpack_u64(rest, 32)?;
+                Self::TopUpGas { seeds, amount }
+            }
+            12 => {
+                Self::expect_len(rest, 33)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let enabled = Self::unpack_bool(rest, 32)?;
+                Self::SetPermissionlessUnlock { seeds, enabled }
+            }
+            13 => {
+                Self::expect_len(rest, 40)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let expiry = Self::unpack_u64(rest, 32)? as i64;
+                Self::UnlockWithPermit { seeds, expiry }
+            }
+            14 => {
+                Self::expect_len(rest, 40)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let cutoff = Self::unpack_u64(rest, 32)?;
+                Self::ChangeFutureDestination { seeds, cutoff }
+            }
+            16 => {
+                Self::expect_len(rest, 33)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let enabled = Self::unpack_bool(rest, 32)?;
+                Self::SetRollover { seeds, enabled }
+            }
+            17 => {
+                Self::expect_len(rest, 40)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let min_claim_amount = Self::unpack_u64(rest, 32)?;
+                Self::SetMinClaimAmount {
+                    seeds,
+                    min_claim_amount,
+                }
+            }
+            19 => {
+                Self::expect_len(rest, 36)?;
+                let admin = Self::unpack_addr(rest, 0)?;
+                let early_claim_tolerance_seconds = Self::unpack_u32(rest, 32)?;
+                Self::InitConfig {
+                    admin,
+                    early_claim_tolerance_seconds,
+                }
+            }
+            20 => {
+                Self::expect_len(rest, 4)?;
+                let early_claim_tolerance_seconds = Self::unpack_u32(rest, 0)?;
+                Self::SetEarlyClaimTolerance {
+                    early_claim_tolerance_seconds,
+                }
+            }
+            21 => {
+                Self::expect_len(rest, 34)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let withholding_bps = Self::unpack_u16(rest, 32)?;
+                Self::SetWithholding {
+                    seeds,
+                    withholding_bps,
+                }
+            }
+            22 => {
+                Self::expect_len(rest, 32)?;
+                let community_treasury = Self::unpack_addr(rest, 0)?;
+                Self::SetCommunityTreasury { community_treasury }
+            }
+            23 => {
+                Self::expect_len(rest, 33)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let dust_policy = DustPolicy::from_u8(rest[32]).ok_or(InvalidInstruction)?;
+                Self::Close { seeds, dust_policy }
+            }
+            24 => {
+                Self::expect_len(rest, 44)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let max_transfer_per_unlock = Self::unpack_u64(rest, 32)?;
+                let unlock_interval = Self::unpack_u32(rest, 40)?;
+                Self::SetTransferLimit {
+                    seeds,
+                    max_transfer_per_unlock,
+                    unlock_interval,
+                }
+            }
+            25 => {
+                Self::expect_len_with_tail(rest, 33, 32)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let threshold = rest[32];
+                let number_of_approvers = (rest.len() - 33) / 32;
+                let mut approvers: Vec<Pubkey> = Vec::with_capacity(number_of_approvers);
+                for i in 0..number_of_approvers {
+                    approvers.push(Self::unpack_addr(rest, 33 + i * 32)?);
+                }
+                Self::InitDestinationApprovers {
+                    seeds,
+                    threshold,
+                    approvers,
+                }
+            }
+            26 => {
+                Self::expect_len(rest, 64)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let new_destination = Self::unpack_addr(rest, 32)?;
+                Self::ApproveDestinationChange {
+                    seeds,
+                    new_destination,
+                }
+            }
+            27 => {
+                Self::expect_len(rest, 0)?;
+                Self::MigrateLayout
+            }
+            28 => {
+                Self::expect_len(rest, 32)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                Self::Compact { seeds }
+            }
+            29 => {
+                Self::expect_len(rest, 1)?;
+                let forbid_self_dealing = Self::unpack_bool(rest, 0)?;
+                Self::SetForbidSelfDealing {
+                    forbid_self_dealing,
+                }
+            }
+            30 => {
+                Self::expect_len(rest, 32)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                Self::Revoke {
+                    seeds,
+                    expected_revision: None,
+                }
+            }
+            31 => {
+                Self::expect_len(rest, 104)?;
+                Self::UnlockGrant {
+                    granter: Self::unpack_addr(rest, 0)?,
+                    beneficiary: Self::unpack_addr(rest, 32)?,
+                    mint: Self::unpack_addr(rest, 64)?,
+                    nonce: Self::unpack_u64(rest, 96)?,
+                }
+            }
+            32 => {
+                Self::expect_len_with_tail(rest, 105, SCHEDULE_SIZE)?;
+                let schedules = rest[105..]
+                    .chunks(SCHEDULE_SIZE)
+                    .map(|s| {
+                        Ok(Schedule {
+                            release_time: Self::unpack_u64(s, 0)?,
+                            amount: Self::unpack_u64(s, 8)?,
+                        })
+                    })
+                    .collect::<Result<Vec<Schedule>, VestingError>>()?;
+                Self::CreateFromMint {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    token_mint_addr: Self::unpack_addr(rest, 32)?,
+                    token_dest_addr: Self::unpack_addr(rest, 64)?,
+                    allow_immediate: Self::unpack_bool(rest, 96)?,
+                    max_supply: Self::unpack_u64(rest, 97)?,
+                    schedules,
+                }
+            }
+            33 => {
+                Self::expect_len(rest, 137)?;
+                Self::CreateLinear {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    token_mint_addr: Self::unpack_addr(rest, 32)?,
+                    token_dest_addr: Self::unpack_addr(rest, 64)?,
+                    allow_immediate: Self::unpack_bool(rest, 96)?,
+                    cliff_time: Self::unpack_u64(rest, 97)?,
+                    cliff_amount: Self::unpack_u64(rest, 105)?,
+                    start_time: Self::unpack_u64(rest, 113)?,
+                    end_time: Self::unpack_u64(rest, 121)?,
+                    total_amount: Self::unpack_u64(rest, 129)?,
+                }
+            }
+            34 => {
+                Self::expect_len_with_tail(rest, 33, SCHEDULE_SIZE)?;
+                let schedules = rest[33..]
+                    .chunks(SCHEDULE_SIZE)
+                    .map(|s| {
+                        Ok(Schedule {
+                            release_time: Self::unpack_u64(s, 0)?,
+                            amount: Self::unpack_u64(s, 8)?,
+                        })
+                    })
+                    .collect::<Result<Vec<Schedule>, VestingError>>()?;
+                Self::TopUp {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    allow_immediate: Self::unpack_bool(rest, 32)?,
+                    schedules,
+                }
+            }
+            35 => {
+                Self::expect_len(rest, 32)?;
+                Self::RefreshClaimable {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                }
+            }
+            36 => {
+                Self::expect_len(rest, 33)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let policy = UnlockPolicy::from_u8(rest[32]).ok_or(InvalidInstruction)?;
+                Self::SetUnlockPolicy { seeds, policy }
+            }
+            37 => {
+                Self::expect_len(rest, 32)?;
+                Self::Accelerate {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                }
+            }
+            38 => {
+                Self::expect_len(rest, 64)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let new_granter = Self::unpack_addr(rest, 32)?;
+                Self::ChangeGranter { seeds, new_granter }
+            }
+            39 => {
+                Self::expect_len(rest, 32)?;
+                Self::Activate {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                }
+            }
+            40 => {
+                Self::expect_len(rest, 32)?;
+                Self::Pause {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                }
+            }
+            41 => {
+                Self::expect_len(rest, 32)?;
+                Self::Resume {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                }
+            }
+            42 => {
+                Self::expect_len(rest, 32)?;
+                Self::EmergencyWithdraw {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                }
+            }
+            #[cfg(feature = "devnet-tools")]
+            43 => {
+                Self::expect_len(rest, 40)?;
+                Self::DebugShiftSchedules {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    offset: Self::unpack_u64(rest, 32)?,
+                }
+            }
+            44 => {
+                Self::expect_len_with_tail(rest, 32, 34)?;
+                let seeds = Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?;
+                let number_of_splits = (rest.len() - 32) / 34;
+                let mut splits: Vec<SplitDestination> = Vec::with_capacity(number_of_splits);
+                for i in 0..number_of_splits {
+                    splits.push(SplitDestination {
+                        destination_address: Self::unpack_addr(rest, 32 + i * 34)?,
+                        bps: Self::unpack_u16(rest, 64 + i * 34)?,
+                    });
+                }
+                Self::SetSplits { seeds, splits }
+            }
+            45 => {
+                Self::expect_len(rest, 33)?;
+                Self::ReadEmissions {
+                    mint_address: Self::unpack_addr(rest, 0)?,
+                    weeks: rest[32],
+                }
+            }
+            46 => {
+                Self::expect_len(rest, 64)?;
+                Self::Merge {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    merged_seeds: Self::unpack_seeds(rest, 32).ok_or(InvalidInstruction)?,
+                }
+            }
+            47 if rest.len() == 33 => Self::TransferBeneficiary {
+                seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                // the current owner paying has the shorter encoding, the one from before rent_payer
+                rent_payer: match RentPayer::from_u8(rest[32]) {
+                    Some(payer @ (RentPayer::Cranker | RentPayer::GranterEscrow)) => payer,
+                    _ => return Err(InvalidInstruction.into()),
+                },
+            },
+            47 => {
+                Self::expect_len(rest, 32)?;
+                Self::TransferBeneficiary {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    rent_payer: RentPayer::Beneficiary,
+                }
+            }
+            48 => {
+                Self::expect_len(rest, 36)?;
+                Self::CheckRevision {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    expected_revision: Self::unpack_u32(rest, 32)?,
+                }
+            }
+            49 if rest.len() == 37 => Self::Resize {
+                seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                number_of_schedules: Self::unpack_u32(rest, 32)?,
+                // Plain has the shorter encoding, same as Init
+                schedule_layout: match ScheduleLayout::from_u8(rest[36]) {
+                    Some(layout @ (ScheduleLayout::Categorized | ScheduleLayout::Linear)) => layout,
+                    _ => return Err(InvalidInstruction.into()),
+                },
+            },
+            49 => {
+                Self::expect_len(rest, 36)?;
+                Self::Resize {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    number_of_schedules: Self::unpack_u32(rest, 32)?,
+                    schedule_layout: ScheduleLayout::Plain,
+                }
+            }
+            50 => {
+                Self::expect_len(rest, 32)?;
+                Self::CloseUninitialized {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                }
+            }
+            51 if rest.len() == 77 => Self::InitGrant {
+                beneficiary: Self::unpack_addr(rest, 0)?,
+                mint: Self::unpack_addr(rest, 32)?,
+                nonce: Self::unpack_u64(rest, 64)?,
+                number_of_schedules: Self::unpack_u32(rest, 72)?,
+                // same as Init, Plain has the shorter encoding
+                schedule_layout: match ScheduleLayout::from_u8(rest[76]) {
+                    Some(layout @ (ScheduleLayout::Categorized | ScheduleLayout::Linear)) => layout,
+                    _ => return Err(InvalidInstruction.into()),
+                },
+            },
+            51 => {
+                Self::expect_len(rest, 76)?;
+                Self::InitGrant {
+                    beneficiary: Self::unpack_addr(rest, 0)?,
+                    mint: Self::unpack_addr(rest, 32)?,
+                    nonce: Self::unpack_u64(rest, 64)?,
+                    number_of_schedules: Self::unpack_u32(rest, 72)?,
+                    schedule_layout: ScheduleLayout::Plain,
+                }
+            }
+            52 => {
+                // same idea as schedules in Create - whatever follows the seeds are 32 byte leaves
+                Self::expect_len_with_tail(rest, 32, 32)?;
+                let leaf_count = rest.len() / 32 - 1;
+                if leaf_count == 0 {
+                    return Err(InvalidInstruction.into());
+                }
+                let mut leaves: Vec<Node> = Vec::with_capacity(leaf_count);
+                for i in 1..=leaf_count {
+                    leaves.push(Self::unpack_seeds(rest, i * 32).ok_or(InvalidInstruction)?);
+                }
+                Self::AppendCompressed {
+                    seeds: Self::unpack_seeds(rest, 0).ok_or(InvalidInstruction)?,
+                    leaves,
+                }
+            }
+            _ => {
+                msg!("unsupported instruction! passed tag: {:?}", tag);
+                return Err(InvalidInstruction.into());
+            }
+        };
+
+        // ----------------------------------------------------------------------------- 2 bincode
+        // let result: Self = bincode::deserialize(input).unwrap();
+
+        // ----------------------------------------------------------------------------- 3 borsh
+        // let result: Self = Self::try_from_slice(input).unwrap();
+
+        // -----------------------------------------------------------------------------
+        msg!("result is {:?}", result);
+        sol_log_compute_units();
+        Ok(result)
+    }
+
+    pub(crate) fn expect_len(rest: &[u8], len: usize) -> Result<(), VestingError> {
+        if rest.len() != len {
+            msg!(
+                "expected {} bytes of instruction data, got {}",
+                len,
+                rest.len()
+            );
+            return Err(InvalidInstruction);
+        }
+        Ok(())
+    }
+
+    /// A fixed part followed by any number of whole items (schedules, proof nodes...)
+    pub(crate) fn expect_len_with_tail(
+        rest: &[u8],
+        fixed_len: usize,
+        item_len: usize,
+    ) -> Result<(), VestingError> {
+        let items = rest.len().saturating_sub(fixed_len) / item_len;
+        if rest.len() != fixed_len + items * item_len {
+            msg!(
+                "expected {} bytes of instruction data plus a multiple of {}, got {}",
+                fixed_len,
+                item_len,
+                rest.len()
+            );
+            return Err(InvalidInstruction);
+        }
+        Ok(())
+    }
+
+    /// assumes 32 bytes long
+    pub(crate) fn unpack_seeds(rest: &[u8], start: usize) -> Option<Seeds> {
+        rest.get(start..start + 32) //32 bytes of seeds
+            .and_then(|slice| slice.try_into().ok())
+    }
+
+    pub(crate) fn unpack_u16(rest: &[u8], start: usize) -> Result<u16, VestingError> {
+        rest.get(start..start + 2) //2 bytes int
+            .and_then(|slice| slice.try_into().ok())
+            .map(u16::from_le_bytes)
+            .ok_or(InvalidInstruction)
+    }
+
+    pub(crate) fn unpack_u32(rest: &[u8], start: usize) -> Result<u32, VestingError> {
+        rest.get(start..start + 4) //4 bytes int
+            .and_then(|slice| slice.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(InvalidInstruction)
+    }
+
+    pub fn unpack_u64(rest: &[u8], start: usize) -> Result<u64, VestingError> {
+        // return Err(VestingError::SomeOther);
+        rest.get(start..start + 8) //8 bytes int
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(InvalidInstruction)
+    }
+
+    fn unpack_bool(rest: &[u8], start: usize) -> Result<bool, VestingError> {
+        match rest.get(start) {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => Err(InvalidInstruction),
+        }
+    }
+
+    pub(crate) fn unpack_addr(rest: &[u8], start: usize) -> Result<Pubkey, VestingError> {
+        rest.get(start..start + 32)
+            .and_then(|slice| slice.try_into().ok())
+            .map(Pubkey::new)
+            .ok_or(InvalidInstruction)
+    }
+
+    /// Position of the (first) vesting account among the accounts this instruction expects, if it takes one
+    pub fn vesting_account_index(&self) -> Option<usize> {
+        match self {
+            Self::Init { .. } | Self::InitChild { .. } | Self::InitGrant { .. } => Some(3),
+            Self::Create { .. }
+            | Self::CreateFromMint { .. }
+            | Self::CreateLinear { .. }
+            | Self::ExportContract { .. }
+            | Self::Close { .. }
+            | Self::Compact { .. }
+            | Self::RefreshClaimable { .. }
+            | Self::EmergencyWithdraw { .. } => Some(1),
+            Self::Unlock { .. }
+            | Self::UnlockWithPermit { .. }
+            | Self::UnlockGrant { .. }
+            | Self::TopUpGas { .. }
+            | Self::TopUp { .. }
+            | Self::InitDestinationApprovers { .. }
+            | Self::Accelerate { .. }
+            | Self::Activate { .. }
+            | Self::Merge { .. }
+            | Self::Resize { .. } => Some(2),
+            Self::ChangeDestination { .. }
+            | Self::ChangeFutureDestination { .. }
+            | Self::SetPermissionlessUnlock { .. }
+            | Self::SetUnlockPolicy { .. }
+            | Self::VerifySolvency { .. }
+            | Self::SetRollover { .. }
+            | Self::SetMinClaimAmount { .. }
+            | Self::SetWithholding { .. }
+            | Self::SetTransferLimit { .. }
+            | Self::ApproveDestinationChange { .. }
+            | Self::ChangeGranter { .. }
+            | Self::Pause { .. }
+            | Self::Resume { .. }
+            | Self::SetSplits { .. }
+            | Self::TransferBeneficiary { .. }
+            | Self::CheckRevision { .. }
+            | Self::CloseUninitialized { .. } => Some(0),
+            #[cfg(feature = "devnet-tools")]
+            Self::DebugShiftSchedules { .. } => Some(0),
+            Self::RevokeBatch { .. } | Self::Revoke { .. } => Some(3),
+            Self::ImportContract { .. } => Some(7),
+            Self::Empty { .. }
+            | Self::CreateCompressed { .. }
+            | Self::AppendCompressed { .. }
+            | Self::ClaimCompressed { .. }
+            | Self::InitConfig { .. }
+            | Self::SetEarlyClaimTolerance { .. }
+            | Self::SetCommunityTreasury { .. }
+            | Self::SetForbidSelfDealing { .. }
+            | Self::MigrateLayout
+            | Self::ReadEmissions { .. } => None,
+        }
+    }
+
+    /// The accounts this instruction expects, the ones its builders pass and the processor checks. RevokeBatch's are
+    /// only the ones in front of its contracts'
+    pub fn account_specs(&self) -> &'static [AccountSpec] {
+        match self {
+            Self::Empty { .. } => &[],
+            Self::Init { .. } | Self::InitChild { .. } | Self::InitGrant { .. } => INIT_ACCOUNTS,
+            Self::Create { gas_deposit, .. } if *gas_deposit > 0 => PAYING_CREATE_ACCOUNTS,
+            Self::Create { .. } | Self::CreateLinear { .. } => CREATE_ACCOUNTS,
+            Self::CreateFromMint { .. } => CREATE_FROM_MINT_ACCOUNTS,
+            Self::Unlock { .. } | Self::UnlockGrant { .. } => UNLOCK_ACCOUNTS,
+            Self::UnlockWithPermit { .. } => UNLOCK_WITH_PERMIT_ACCOUNTS,
+            Self::ChangeDestination { .. } | Self::ChangeFutureDestination { .. } => {
+                CHANGE_DESTINATION_ACCOUNTS
+            }
+            Self::CreateCompressed { .. } => CREATE_COMPRESSED_ACCOUNTS,
+            Self::AppendCompressed { .. } => APPEND_COMPRESSED_ACCOUNTS,
+            Self::ClaimCompressed { .. } => CLAIM_COMPRESSED_ACCOUNTS,
+            Self::Revoke { .. } => REVOKE_ACCOUNTS,
+            Self::Accelerate { .. } | Self::Activate { .. } => GRANTER_CLOCK_ACCOUNTS,
+            Self::ChangeGranter { .. } | Self::Pause { .. } | Self::Resume { .. } => {
+                GRANTER_ACCOUNTS
+            }
+            Self::RevokeBatch { .. } => &REVOKE_ACCOUNTS[..3],
+            Self::ExportContract { .. } => EXPORT_CONTRACT_ACCOUNTS,
+            Self::ImportContract { .. } => IMPORT_CONTRACT_ACCOUNTS,
+            Self::TopUpGas { .. } => TOP_UP_GAS_ACCOUNTS,
+            Self::SetPermissionlessUnlock { .. }
+            | Self::SetUnlockPolicy { .. }
+            | Self::SetMinClaimAmount { .. } => DESTINATION_OWNER_ACCOUNTS,
+            Self::VerifySolvency { .. } => VERIFY_SOLVENCY_ACCOUNTS,
+            Self::SetRollover { .. } | Self::SetTransferLimit { .. } | Self::SetSplits { .. } => {
+                BOTH_PARTIES_ACCOUNTS
+            }
+            Self::SetWithholding { .. } => SET_WITHHOLDING_ACCOUNTS,
+            Self::InitConfig { .. } => INIT_CONFIG_ACCOUNTS,
+            Self::SetEarlyClaimTolerance { .. }
+            | Self::SetCommunityTreasury { .. }
+            | Self::SetForbidSelfDealing { .. } => CONFIG_ADMIN_ACCOUNTS,
+            Self::Close { .. } => CLOSE_ACCOUNTS,
+            Self::InitDestinationApprovers { .. } => INIT_DESTINATION_APPROVERS_ACCOUNTS,
+            Self::ApproveDestinationChange { .. } => APPROVE_DESTINATION_CHANGE_ACCOUNTS,
+            Self::MigrateLayout => MIGRATE_LAYOUT_ACCOUNTS,
+            Self::Compact { .. } => COMPACT_ACCOUNTS,
+            Self::TopUp { .. } => TOP_UP_ACCOUNTS,
+            Self::RefreshClaimable { .. } => REFRESH_CLAIMABLE_ACCOUNTS,
+            Self::EmergencyWithdraw { .. } => EMERGENCY_WITHDRAW_ACCOUNTS,
+            #[cfg(feature = "devnet-tools")]
+            Self::DebugShiftSchedules { .. } => GRANTER_ACCOUNTS,
+            Self::ReadEmissions { .. } => READ_EMISSIONS_ACCOUNTS,
+            Self::Merge { .. } => MERGE_ACCOUNTS,
+            Self::TransferBeneficiary { .. } => TRANSFER_BENEFICIARY_ACCOUNTS,
+            Self::CheckRevision { .. } => CHECK_REVISION_ACCOUNTS,
+            Self::Resize { .. } => RESIZE_ACCOUNTS,
+            Self::CloseUninitialized { .. } => CLOSE_UNINITIALIZED_ACCOUNTS,
+        }
+    }
+
+    // the reverse of above - packs an instruction into a vector of bytes
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.packed_len());
+        self.pack_to(&mut buf);
+        buf
+    }
+
+    /// How many bytes pack produces, without packing anything
+    pub fn packed_len(&self) -> usize {
+        let mut len = PackedLen(0);
+        self.pack_to(&mut len);
+        len.0
+    }
+
+    /// Packs into the front of `dst` without allocating, for CPI builders with a buffer of their own. Returns the
+    /// number of bytes written, or AccountDataTooSmall if they don't fit
+    pub fn pack_into(&self, dst: &mut [u8]) -> Result<usize, ProgramError> {
+        let len = self.packed_len();
+        if dst.len() < len {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        self.pack_to(&mut SliceWriter { dst, pos: 0 });
+        Ok(len)
+    }
+
+    // the single encoding behind pack, packed_len and pack_into
+    fn pack_to<B: PackBuffer>(&self, buf: &mut B) {
+        match self {
+            &Self::Empty { number } => {
+                buf.push(4);
+                buf.extend_from_slice(&number.to_le_bytes());
+            }
+            &Self::Init {
+                seeds,
+                number_of_schedules,
+                schedule_layout,
+            } => {
+                buf.push(0);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&number_of_schedules.to_le_bytes());
+                if schedule_layout != ScheduleLayout::Plain {
+                    buf.push(schedule_layout as u8);
+                }
+            }
+            Self::Create {
+                seeds,
+                token_mint_addr,
+                token_dest_addr,
+                allow_immediate,
+                gas_rebate,
+                gas_deposit,
+                min_claim_amount,
+                destination_locked,
+                forbid_self_dealing,
+                revoke_policy,
+                relative,
+                emergency_authority,
+                revocable,
+                schedules,
+                categories,
+            } => {
+                buf.push(if categories.is_empty() { 1 } else { 18 });
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(&token_mint_addr.to_bytes());
+                buf.extend_from_slice(&token_dest_addr.to_bytes());
+                buf.push(*allow_immediate as u8);
+                buf.extend_from_slice(&gas_rebate.to_le_bytes());
+                buf.extend_from_slice(&gas_deposit.to_le_bytes());
+                buf.extend_from_slice(&min_claim_amount.to_le_bytes());
+                buf.push(*destination_locked as u8);
+                buf.push(*forbid_self_dealing as u8);
+                buf.push(*revoke_policy as u8);
+                buf.push(*relative as u8);
+                buf.extend_from_slice(&emergency_authority.to_bytes());
+                buf.push(*revocable as u8);
+                for s in schedules.iter() {
+                    buf.extend_from_slice(&s.release_time.to_le_bytes());
+                    buf.extend_from_slice(&s.amount.to_le_bytes());
+                }
+                for c in categories {
+                    buf.push(*c as u8);
+                }
+            }
+            &Self::Unlock {
+                seeds,
+                max_schedules_per_call,
+                max_amount,
+                start_index,
+            } => {
+                buf.push(2);
+                buf.extend_from_slice(&seeds);
+                if max_schedules_per_call > 0 || max_amount > 0 || start_index.is_some() {
+                    buf.extend_from_slice(&max_schedules_per_call.to_le_bytes());
+                }
+                if max_amount > 0 {
+                    buf.extend_from_slice(&max_amount.to_le_bytes());
+                }
+                if let Some(start_index) = start_index {
+                    buf.extend_from_slice(&start_index.to_le_bytes());
+                }
+            }
+            &Self::ChangeDestination {
+                seeds,
+                expected_revision,
+            } => {
+                buf.push(3);
+                buf.extend_from_slice(&seeds);
+                if let Some(expected_revision) = expected_revision {
+                    buf.extend_from_slice(&expected_revision.to_le_bytes());
+                }
+            }
+            Self::CreateCompressed {
+                seeds,
+                token_mint_addr,
+                max_depth,
+                max_buffer_size,
+                total_amount,
+            } => {
+                buf.push(5);
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(&token_mint_addr.to_bytes());
+                buf.extend_from_slice(&max_depth.to_le_bytes());
+                buf.extend_from_slice(&max_buffer_size.to_le_bytes());
+                buf.extend_from_slice(&total_amount.to_le_bytes());
+            }
+            Self::AppendCompressed { seeds, leaves } => {
+                buf.push(52);
+                buf.extend_from_slice(seeds);
+                for leaf in leaves.iter() {
+                    buf.extend_from_slice(leaf);
+                }
+            }
+            Self::ClaimCompressed {
+                seeds,
+                root,
+                index,
+                release_time,
+                amount,
+            } => {
+                buf.push(6);
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(root);
+                buf.extend_from_slice(&index.to_le_bytes());
+                buf.extend_from_slice(&release_time.to_le_bytes());
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::RevokeBatch { seeds } => {
+                buf.push(7);
+                for s in seeds.iter() {
+                    buf.extend_from_slice(s);
+                }
+            }
+            Self::ExportContract {
+                seeds,
+                new_program_id,
+            } => {
+                buf.push(8);
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(&new_program_id.to_bytes());
+            }
+            Self::ImportContract { seeds } => {
+                buf.push(9);
+                buf.extend_from_slice(seeds);
+            }
+            &Self::InitChild {
+                base_seed,
+                index,
+                number_of_schedules,
+            } => {
+                buf.push(10);
+                buf.extend_from_slice(&base_seed);
+                buf.extend_from_slice(&index.to_le_bytes());
+                buf.extend_from_slice(&number_of_schedules.to_le_bytes());
+            }
+            &Self::TopUpGas { seeds, amount } => {
+                buf.push(11);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            &Self::SetPermissionlessUnlock { seeds, enabled } => {
+                buf.push(12);
+                buf.extend_from_slice(&seeds);
+                buf.push(enabled as u8);
+            }
+            &Self::UnlockWithPermit { seeds, expiry } => {
+                buf.push(13);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&expiry.to_le_bytes());
+            }
+            &Self::ChangeFutureDestination { seeds, cutoff } => {
+                buf.push(14);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&cutoff.to_le_bytes());
+            }
+            &Self::VerifySolvency { seeds } => {
+                buf.push(15);
+                buf.extend_from_slice(&seeds);
+            }
+            &Self::SetRollover { seeds, enabled } => {
+                buf.push(16);
+                buf.extend_from_slice(&seeds);
+                buf.push(enabled as u8);
+            }
+            &Self::SetMinClaimAmount {
+                seeds,
+                min_claim_amount,
+            } => {
+                buf.push(17);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&min_claim_amount.to_le_bytes());
+            }
+            &Self::InitConfig {
+                admin,
+                early_claim_tolerance_seconds,
+            } => {
+                buf.push(19);
+                buf.extend_from_slice(&admin.to_bytes());
+                buf.extend_from_slice(&early_claim_tolerance_seconds.to_le_bytes());
+            }
+            &Self::SetEarlyClaimTolerance {
+                early_claim_tolerance_seconds,
+            } => {
+                buf.push(20);
+                buf.extend_from_slice(&early_claim_tolerance_seconds.to_le_bytes());
+            }
+            &Self::SetWithholding {
+                seeds,
+                withholding_bps,
+            } => {
+                buf.push(21);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&withholding_bps.to_le_bytes());
+            }
+            &Self::SetCommunityTreasury { community_treasury } => {
+                buf.push(22);
+                buf.extend_from_slice(&community_treasury.to_bytes());
+            }
+            &Self::Close { seeds, dust_policy } => {
+                buf.push(23);
+                buf.extend_from_slice(&seeds);
+                buf.push(dust_policy as u8);
+            }
+            &Self::SetTransferLimit {
+                seeds,
+                max_transfer_per_unlock,
+                unlock_interval,
+            } => {
+                buf.push(24);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&max_transfer_per_unlock.to_le_bytes());
+                buf.extend_from_slice(&unlock_interval.to_le_bytes());
+            }
+            Self::InitDestinationApprovers {
+                seeds,
+                threshold,
+                approvers,
+            } => {
+                buf.push(25);
+                buf.extend_from_slice(seeds);
+                buf.push(*threshold);
+                for approver in approvers.iter() {
+                    buf.extend_from_slice(&approver.to_bytes());
+                }
+            }
+            &Self::ApproveDestinationChange {
+                seeds,
+                new_destination,
+            } => {
+                buf.push(26);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&new_destination.to_bytes());
+            }
+            Self::MigrateLayout => buf.push(27),
+            &Self::Compact { seeds } => {
+                buf.push(28);
+                buf.extend_from_slice(&seeds);
+            }
+            &Self::SetForbidSelfDealing {
+                forbid_self_dealing,
+            } => {
+                buf.push(29);
+                buf.push(forbid_self_dealing as u8);
+            }
+            &Self::Revoke {
+                seeds,
+                expected_revision,
+            } => {
+                buf.push(30);
+                buf.extend_from_slice(&seeds);
+                if let Some(expected_revision) = expected_revision {
+                    buf.extend_from_slice(&expected_revision.to_le_bytes());
+                }
+            }
+            &Self::UnlockGrant {
+                granter,
+                beneficiary,
+                mint,
+                nonce,
+            } => {
+                buf.push(31);
+                buf.extend_from_slice(&granter.to_bytes());
+                buf.extend_from_slice(&beneficiary.to_bytes());
+                buf.extend_from_slice(&mint.to_bytes());
+                buf.extend_from_slice(&nonce.to_le_bytes());
+            }
+            Self::CreateFromMint {
+                seeds,
+                token_mint_addr,
+                token_dest_addr,
+                allow_immediate,
+                max_supply,
+                schedules,
+            } => {
+                buf.push(32);
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(&token_mint_addr.to_bytes());
+                buf.extend_from_slice(&token_dest_addr.to_bytes());
+                buf.push(*allow_immediate as u8);
+                buf.extend_from_slice(&max_supply.to_le_bytes());
+                for s in schedules.iter() {
+                    buf.extend_from_slice(&s.release_time.to_le_bytes());
+                    buf.extend_from_slice(&s.amount.to_le_bytes());
+                }
+            }
+            &Self::CreateLinear {
+                seeds,
+                token_mint_addr,
+                token_dest_addr,
+                allow_immediate,
+                cliff_time,
+                cliff_amount,
+                start_time,
+                end_time,
+                total_amount,
+            } => {
+                buf.push(33);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&token_mint_addr.to_bytes());
+                buf.extend_from_slice(&token_dest_addr.to_bytes());
+                buf.push(allow_immediate as u8);
+                buf.extend_from_slice(&cliff_time.to_le_bytes());
+                buf.extend_from_slice(&cliff_amount.to_le_bytes());
+                buf.extend_from_slice(&start_time.to_le_bytes());
+                buf.extend_from_slice(&end_time.to_le_bytes());
+                buf.extend_from_slice(&total_amount.to_le_bytes());
+            }
+            Self::TopUp {
+                seeds,
+                allow_immediate,
+                schedules,
+            } => {
+                buf.push(34);
+                buf.extend_from_slice(seeds);
+                buf.push(*allow_immediate as u8);
+                for s in schedules.iter() {
+                    buf.extend_from_slice(&s.release_time.to_le_bytes());
+                    buf.extend_from_slice(&s.amount.to_le_bytes());
+                }
+            }
+            Self::RefreshClaimable { seeds } => {
+                buf.push(35);
+                buf.extend_from_slice(seeds);
+            }
+            &Self::SetUnlockPolicy { seeds, policy } => {
+                buf.push(36);
+                buf.extend_from_slice(&seeds);
+                buf.push(policy as u8);
+            }
+            Self::Accelerate { seeds } => {
+                buf.push(37);
+                buf.extend_from_slice(seeds);
+            }
+            &Self::ChangeGranter { seeds, new_granter } => {
+                buf.push(38);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&new_granter.to_bytes());
+            }
+            Self::Activate { seeds } => {
+                buf.push(39);
+                buf.extend_from_slice(seeds);
+            }
+            Self::Pause { seeds } => {
+                buf.push(40);
+                buf.extend_from_slice(seeds);
+            }
+            Self::Resume { seeds } => {
+                buf.push(41);
+                buf.extend_from_slice(seeds);
+            }
+            Self::EmergencyWithdraw { seeds } => {
+                buf.push(42);
+                buf.extend_from_slice(seeds);
+            }
+            #[cfg(feature = "devnet-tools")]
+            &Self::DebugShiftSchedules { seeds, offset } => {
+                buf.push(43);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&offset.to_le_bytes());
+            }
+            Self::SetSplits { seeds, splits } => {
+                buf.push(44);
+                buf.extend_from_slice(seeds);
+                for split in splits.iter() {
+                    buf.extend_from_slice(&split.destination_address.to_bytes());
+                    buf.extend_from_slice(&split.bps.to_le_bytes());
+                }
+            }
+            &Self::ReadEmissions {
+                mint_address,
+                weeks,
+            } => {
+                buf.push(45);
+                buf.extend_from_slice(&mint_address.to_bytes());
+                buf.push(weeks);
+            }
+            Self::Merge {
+                seeds,
+                merged_seeds,
+            } => {
+                buf.push(46);
+                buf.extend_from_slice(seeds);
+                buf.extend_from_slice(merged_seeds);
+            }
+            &Self::TransferBeneficiary { seeds, rent_payer } => {
+                buf.push(47);
+                buf.extend_from_slice(&seeds);
+                if rent_payer != RentPayer::Beneficiary {
+                    buf.push(rent_payer as u8);
+                }
+            }
+            &Self::CheckRevision {
+                seeds,
+                expected_revision,
+            } => {
+                buf.push(48);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&expected_revision.to_le_bytes());
+            }
+            &Self::Resize {
+                seeds,
+                number_of_schedules,
+                schedule_layout,
+            } => {
+                buf.push(49);
+                buf.extend_from_slice(&seeds);
+                buf.extend_from_slice(&number_of_schedules.to_le_bytes());
+                if schedule_layout != ScheduleLayout::Plain {
+                    buf.push(schedule_layout as u8);
+                }
+            }
+            Self::CloseUninitialized { seeds } => {
+                buf.push(50);
+                buf.extend_from_slice(seeds);
+            }
+            &Self::InitGrant {
+                beneficiary,
+                mint,
+                nonce,
+                number_of_schedules,
+                schedule_layout,
+            } => {
+                buf.push(51);
+                buf.extend_from_slice(beneficiary.as_ref());
+                buf.extend_from_slice(mint.as_ref());
+                buf.extend_from_slice(&nonce.to_le_bytes());
+                buf.extend_from_slice(&number_of_schedules.to_le_bytes());
+                if schedule_layout != ScheduleLayout::Plain {
+                    buf.push(schedule_layout as u8);
+                }
+            }
+        };
+    }
+}
+
+/// Where pack_to writes to
+trait PackBuffer {
+    fn push(&mut self, byte: u8);
+    fn extend_from_slice(&mut self, bytes: &[u8]);
+}
+
+impl PackBuffer for Vec<u8> {
+    fn push(&mut self, byte: u8) {
+        Vec::push(self, byte)
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        Vec::extend_from_slice(self, bytes)
+    }
+}
+
+/// Only counts
+struct PackedLen(usize);
+
+impl PackBuffer for PackedLen {
+    fn push(&mut self, _: u8) {
+        self.0 += 1;
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.0 += bytes.len();
+    }
+}
+
+/// Writes into a slice pack_into has checked is long enough
+struct SliceWriter<'a> {
+    dst: &'a mut [u8],
+    pos: usize,
+}
+
+impl PackBuffer for SliceWriter<'_> {
+    fn push(&mut self, byte: u8) {
+        self.dst[self.pos] = byte;
+        self.pos += 1;
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.dst[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+}
+
+// ----------------------------------------------------------------------------- helper fns to be called from tests / other rust code
+
+/// Lamports an `init` (or `init_categorized`) takes from the payer: the rent exemption of a vesting account holding
+/// `number_of_schedules` schedules. Transaction fees come on top
+pub fn required_lamports_for(
+    number_of_schedules: u32,
+    schedule_layout: ScheduleLayout,
+    rent: &Rent,
+) -> u64 {
+    rent.minimum_balance(VestingScheduleHeader::account_len(
+        number_of_schedules as usize,
+        schedule_layout,
+    ))
+}
+
+// Creates a `Init` instruction
+pub fn init(
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    vesting_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    vesting_account: &Pubkey,
+    seeds: Seeds,
+    number_of_schedules: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Init {
+        seeds,
+        number_of_schedules,
+        schedule_layout: ScheduleLayout::Plain,
+    }
+    .pack();
+    let accounts = account_metas(
+        INIT_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            payer_key,
+            vesting_account,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `init`, but leaves room for a category per schedule, for a contract created with categories
+pub fn init_categorized(
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    vesting_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    vesting_account: &Pubkey,
+    seeds: Seeds,
+    number_of_schedules: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Init {
+        seeds,
+        number_of_schedules,
+        schedule_layout: ScheduleLayout::Categorized,
+    }
+    .pack();
+    let accounts = account_metas(
+        INIT_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            payer_key,
+            vesting_account,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `init`, but for the single schedule and the LinearVesting of a contract created with `create_linear`
+pub fn init_linear(
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    vesting_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    vesting_account: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Init {
+        seeds,
+        number_of_schedules: 1,
+        schedule_layout: ScheduleLayout::Linear,
+    }
+    .pack();
+    let accounts = account_metas(
+        INIT_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            payer_key,
+            vesting_account,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `InitChild` instruction
+pub fn init_child(
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    vesting_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    base_seed: Seeds,
+    index: u32,
+    number_of_schedules: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::InitChild {
+        base_seed,
+        index,
+        number_of_schedules,
+    }
+    .pack();
+    let (vesting_account, _) = find_child_vesting_account(vesting_program_id, &base_seed, index);
+    let accounts = account_metas(
+        INIT_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            payer_key,
+            &vesting_account,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Seeds of the `index`-th child of `base_seed`: 31 bytes of hash plus a bump, same layout as hand-picked seeds.
+/// Deterministic, so payroll-style systems can recompute every address from the base seed alone
+pub fn child_seeds(program_id: &Pubkey, base_seed: &Seeds, index: u32) -> Seeds {
+    let hash = hashv(&[CHILD_SEED_PREFIX, base_seed, &index.to_le_bytes()]).to_bytes();
+    let mut seeds = hash;
+    let (_, bump) = Pubkey::find_program_address(&[&hash[..31]], program_id);
+    seeds[31] = bump;
+    seeds
+}
+
+/// Vesting account address and seeds of the `index`-th child of `base_seed`
+pub fn find_child_vesting_account(
+    program_id: &Pubkey,
+    base_seed: &Seeds,
+    index: u32,
+) -> (Pubkey, Seeds) {
+    let seeds = child_seeds(program_id, base_seed, index);
+    // can't fail - the bump was picked by find_program_address
+    let key = Pubkey::create_program_address(&[&seeds], program_id).unwrap();
+    (key, seeds)
+}
+
+/// Seeds of the `nonce`-th contract `granter` sets up for `beneficiary` (the destination owner's wallet) in `mint`:
+/// the hash of `["vesting", granter, beneficiary, mint, nonce]`, all 32 bytes of it, the bump left to InitGrant, which
+/// keeps it in the header. Anyone who knows the parties finds the contract without an index, and can claim from it
+/// with UnlockGrant. The hash on its own proves nothing about who made it, so the contract has to come from
+/// InitGrant, which hashes the granter's key itself - the same seeds passed to Init end up at an address no one looks
+/// at
+pub fn vesting_seeds(granter: &Pubkey, beneficiary: &Pubkey, mint: &Pubkey, nonce: u64) -> Seeds {
+    hashv(&[
+        VESTING_SEED_PREFIX,
+        granter.as_ref(),
+        beneficiary.as_ref(),
+        mint.as_ref(),
+        &nonce.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Vesting account address and seeds of the `nonce`-th contract `granter` set up for `beneficiary` in `mint` with
+/// InitGrant, see vesting_seeds
+pub fn find_vesting_address(
+    program_id: &Pubkey,
+    granter: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    nonce: u64,
+) -> (Pubkey, Seeds) {
+    let seeds = vesting_seeds(granter, beneficiary, mint, nonce);
+    let (key, _) = pda::find_granted_vesting_address(&seeds, program_id);
+    (key, seeds)
+}
+
+/// Everything Create can set besides the contract's parties and schedules, for the `create` builder and the
+/// processor alike. The default is a plain contract: release times in the future, no gas escrow, no minimum claim,
+/// revocable back to the granter
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateOptions {
+    pub allow_immediate: bool,
+    /// Lamports paid out of the gas escrow to whoever submits a successful Unlock
+    pub gas_rebate: u64,
+    /// Lamports moved from the source token account owner into the gas escrow
+    pub gas_deposit: u64,
+    /// Unlock refuses to transfer less than this at a time. 0 = no minimum
+    pub min_claim_amount: u64,
+    /// The destination can never be changed, eg for grants that legally can't be re-assigned
+    pub destination_locked: bool,
+    /// Create fails for a revocable contract if the granter also owns the destination spl-token account
+    pub forbid_self_dealing: bool,
+    /// What Revoke does with the unvested remainder, eg burn it for a deflationary token
+    pub revoke_policy: RevokePolicy,
+    /// Release times are offsets from a start that isn't known yet. Nothing is released until the granter sends
+    /// Activate, which anchors them to its clock. allow_immediate doesn't matter then
+    pub relative: bool,
+    /// Can later sweep the contract with `emergency_withdraw`, eg when the destination's wallet is compromised.
+    /// Pubkey::default() = none
+    pub emergency_authority: Pubkey,
+    /// Unless set, Revoke and RevokeBatch always fail, for grants that can never be clawed back
+    pub revocable: bool,
+    /// One per schedule, for reporting emissions by bucket, or none for a plain contract. The vesting account of a
+    /// categorized contract has to come from `init_categorized`
+    pub categories: Vec<ScheduleCategory>,
+    /// `create_from_mint` only, which `create` refuses: the most the mint's supply may reach with the grant minted.
+    /// None = no cap beyond spl-token's own. The processor mints the grant when it's set
+    pub max_supply: Option<u64>,
+}
+
+impl Default for CreateOptions {
+    fn default() -> Self {
+        Self {
+            allow_immediate: false,
+            gas_rebate: 0,
+            gas_deposit: 0,
+            min_claim_amount: 0,
+            destination_locked: false,
+            forbid_self_dealing: false,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            relative: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            categories: vec![],
+            max_supply: None,
+        }
+    }
+}
+
+// Creates a `Create` instruction
+pub fn create(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    source_token_account_owner_key: &Pubkey,
+    source_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    mint_address: &Pubkey,
+    schedules: Vec<Schedule>,
+    options: CreateOptions,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let CreateOptions {
+        allow_immediate,
+        gas_rebate,
+        gas_deposit,
+        min_claim_amount,
+        destination_locked,
+        forbid_self_dealing,
+        revoke_policy,
+        relative,
+        emergency_authority,
+        revocable,
+        categories,
+        max_supply,
+    } = options;
+    if (!categories.is_empty() && categories.len() != schedules.len()) || max_supply.is_some() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let system_program_id = solana_program::system_program::id();
+    let data = VestingInstruction::Create {
+        token_mint_addr: *mint_address,
+        seeds,
+        token_dest_addr: *destination_token_account_key,
+        allow_immediate,
+        gas_rebate,
+        gas_deposit,
+        min_claim_amount,
+        destination_locked,
+        forbid_self_dealing,
+        revoke_policy,
+        relative,
+        emergency_authority,
+        revocable,
+        schedules,
+        categories,
+    }
+    .pack();
+    let mut keys = vec![
+        token_program_id,
+        vesting_account_key,
+        vesting_token_account_key,
+        source_token_account_owner_key,
+        source_token_account_key,
+        mint_address,
+        destination_token_account_key,
+    ];
+    // the source owner pays the gas deposit
+    let specs = if gas_deposit > 0 {
+        keys.push(&system_program_id);
+        PAYING_CREATE_ACCOUNTS
+    } else {
+        CREATE_ACCOUNTS
+    };
+    let accounts = account_metas(specs, &keys);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CreateFromMint` instruction. The mint authority signs in place of the source token account owner.
+// CreateFromMint only takes allow_immediate and max_supply out of the options
+pub fn create_from_mint(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    mint_authority_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    mint_address: &Pubkey,
+    schedules: Vec<Schedule>,
+    options: CreateOptions,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let CreateOptions {
+        allow_immediate,
+        max_supply,
+        ..
+    } = options;
+    let plain = CreateOptions {
+        allow_immediate,
+        max_supply,
+        ..CreateOptions::default()
+    };
+    if options != plain {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let data = VestingInstruction::CreateFromMint {
+        seeds,
+        token_mint_addr: *mint_address,
+        token_dest_addr: *destination_token_account_key,
+        allow_immediate,
+        max_supply: max_supply.unwrap_or(u64::MAX),
+        schedules,
+    }
+    .pack();
+    let accounts = account_metas(
+        CREATE_FROM_MINT_ACCOUNTS,
+        &[
+            token_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            mint_authority_key,
+            mint_address,
+            destination_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CreateLinear` instruction. With a cliff, its amount is released all at once at its release time -
+// typically a year's worth at the one year mark, with the linear part starting there
+pub fn create_linear(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    source_token_account_owner_key: &Pubkey,
+    source_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    mint_address: &Pubkey,
+    cliff: Option<Schedule>,
+    start_time: u64,
+    end_time: u64,
+    total_amount: u64,
+    allow_immediate: bool,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let cliff = cliff.unwrap_or(Schedule {
+        release_time: 0,
+        amount: 0,
+    });
+    let data = VestingInstruction::CreateLinear {
+        seeds,
+        token_mint_addr: *mint_address,
+        token_dest_addr: *destination_token_account_key,
+        allow_immediate,
+        cliff_time: cliff.release_time,
+        cliff_amount: cliff.amount,
+        start_time,
+        end_time,
+        total_amount,
+    }
+    .pack();
+    let accounts = account_metas(
+        CREATE_ACCOUNTS,
+        &[
+            token_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            source_token_account_owner_key,
+            source_token_account_key,
+            mint_address,
+            destination_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `Unlock` instruction. Needs no signer, so anyone can crank a permissionless contract
+pub fn unlock(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Unlock {
+        seeds,
+        max_schedules_per_call: 0,
+        max_amount: 0,
+        start_index: None,
+    }
+    .pack();
+    let accounts = account_metas(
+        UNLOCK_ACCOUNTS,
+        &[
+            token_program_id,
+            clock_sysvar_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            destination_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `UnlockGrant` instruction. The vesting account is at find_vesting_address
+pub fn unlock_grant(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    granter: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    nonce: u64,
+) -> Result<Instruction, ProgramError> {
+    let (vesting_account_key, _) =
+        find_vesting_address(vesting_program_id, granter, beneficiary, mint, nonce);
+    let data = VestingInstruction::UnlockGrant {
+        granter: *granter,
+        beneficiary: *beneficiary,
+        mint: *mint,
+        nonce,
+    }
+    .pack();
+    let accounts = account_metas(
+        UNLOCK_ACCOUNTS,
+        &[
+            token_program_id,
+            clock_sysvar_id,
+            &vesting_account_key,
+            vesting_token_account_key,
+            destination_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `unlock`, with the gas rebate (if the contract has one) going to `rebate_receiver_key`
+pub fn unlock_with_rebate(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    rebate_receiver_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = unlock(
+        vesting_program_id,
+        token_program_id,
+        clock_sysvar_id,
+        vesting_account_key,
+        vesting_token_account_key,
+        destination_token_account_key,
+        seeds,
+    )?;
+    instruction
+        .accounts
+        .push(UNLOCK_ACCOUNTS[5].meta(rebate_receiver_key));
+    Ok(instruction)
+}
+
+/// Same as `unlock`, but only looks at `max_schedules_per_call` schedules, picking up where the previous bounded
+/// Unlock left off. Contracts too big for a single Unlock are drained by sending this until next_release_time is in
+/// the future
+pub fn unlock_bounded(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    max_schedules_per_call: u32,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = unlock(
+        vesting_program_id,
+        token_program_id,
+        clock_sysvar_id,
+        vesting_account_key,
+        vesting_token_account_key,
+        destination_token_account_key,
+        seeds,
+    )?;
+    instruction.data = VestingInstruction::Unlock {
+        seeds,
+        max_schedules_per_call,
+        max_amount: 0,
+        start_index: None,
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Same as `unlock_bounded`, but over the schedules from `start_index` on instead of from the header's unlock_cursor.
+/// Lets a client split a big contract into ranges up front; ranges that don't start at the cursor leave
+/// next_release_time as it was, so it can be early until a full or cursor-driven Unlock catches up
+pub fn unlock_range(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    start_index: u32,
+    count: u32,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = unlock(
+        vesting_program_id,
+        token_program_id,
+        clock_sysvar_id,
+        vesting_account_key,
+        vesting_token_account_key,
+        destination_token_account_key,
+        seeds,
+    )?;
+    instruction.data = VestingInstruction::Unlock {
+        seeds,
+        max_schedules_per_call: count,
+        max_amount: 0,
+        start_index: Some(start_index),
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Same as `unlock`, but claims at most `max_amount` of what has been released, leaving the rest claimable. The
+/// destination owner has to sign, and gets the gas rebate if there is one
+pub fn unlock_partial(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    max_amount: u64,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = unlock_with_rebate(
+        vesting_program_id,
+        token_program_id,
+        clock_sysvar_id,
+        vesting_account_key,
+        vesting_token_account_key,
+        destination_token_account_key,
+        destination_token_account_owner_key,
+        seeds,
+    )?;
+    instruction.data = VestingInstruction::Unlock {
+        seeds,
+        max_schedules_per_call: 0,
+        max_amount,
+        start_index: None,
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Same as any of the `unlock` or `revoke` builders, with the program config appended so that its early claim
+/// tolerance applies
+pub fn with_program_config(
+    vesting_program_id: &Pubkey,
+    mut instruction: Instruction,
+) -> Instruction {
+    let (config_key, _) = find_program_config(vesting_program_id);
+    instruction
+        .accounts
+        .push(UNLOCK_ACCOUNTS[6].meta(&config_key));
+    instruction
+}
+
+/// Same as any of the `unlock` builders, with the withholding token account appended - needed while the contract
+/// withholds, see `set_withholding`
+pub fn with_withholding(
+    mut instruction: Instruction,
+    withholding_token_account_key: &Pubkey,
+) -> Instruction {
+    instruction
+        .accounts
+        .push(UNLOCK_ACCOUNTS[7].meta(withholding_token_account_key));
+    instruction
+}
+
+/// Same as any of the `unlock` builders, with the split token accounts appended - needed while the contract splits
+/// its unlocks, see `set_splits`
+pub fn with_splits(mut instruction: Instruction, splits: &[SplitDestination]) -> Instruction {
+    instruction.accounts.extend(
+        splits
+            .iter()
+            .map(|split| UNLOCK_ACCOUNTS[8].meta(&split.destination_address)),
+    );
+    instruction
+}
+
+/// The message the destination owner signs off-chain to let anyone submit a single UnlockWithPermit until `expiry`.
+/// `nonce` is the contract's current permit_nonce, read from its header
+pub fn claim_permit_message(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    expiry: i64,
+    nonce: u64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(CLAIM_PERMIT_PREFIX.len() + 32 + 32 + 8 + 8);
+    message.extend_from_slice(CLAIM_PERMIT_PREFIX);
+    message.extend_from_slice(vesting_program_id.as_ref());
+    message.extend_from_slice(vesting_account_key.as_ref());
+    message.extend_from_slice(&expiry.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+/// An ed25519 program instruction verifying `signature` by `signer_key` over `message`. Goes right before
+/// UnlockWithPermit. Built by hand since the on-chain crate doesn't pull in an ed25519 implementation
+pub fn ed25519_verify(
+    signer_key: &Pubkey,
+    signature: &[u8; ED25519_SIGNATURE_SIZE],
+    message: &[u8],
+) -> Instruction {
+    let pubkey_offset = ED25519_DATA_START;
+    let signature_offset = pubkey_offset + ED25519_PUBKEY_SIZE;
+    let message_offset = signature_offset + ED25519_SIGNATURE_SIZE;
+
+    let mut data = Vec::with_capacity(message_offset + message.len());
+    data.push(1); //number of signatures
+    data.push(0); //padding
+                  // u16::MAX as the instruction index means "this instruction"
+    for offset in [
+        signature_offset as u16,
+        u16::MAX,
+        pubkey_offset as u16,
+        u16::MAX,
+        message_offset as u16,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(signer_key.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+// Creates an `UnlockWithPermit` instruction
+pub fn unlock_with_permit(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    instructions_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    rebate_receiver_key: Option<&Pubkey>,
+    expiry: i64,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::UnlockWithPermit { seeds, expiry }.pack();
+    let mut accounts = account_metas(
+        UNLOCK_WITH_PERMIT_ACCOUNTS,
+        &[
+            token_program_id,
+            clock_sysvar_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            destination_token_account_key,
+            instructions_sysvar_id,
+        ],
+    );
+    if let Some(rebate_receiver_key) = rebate_receiver_key {
+        accounts.push(UNLOCK_WITH_PERMIT_ACCOUNTS[6].meta(rebate_receiver_key));
+    }
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetPermissionlessUnlock` instruction
+pub fn set_permissionless_unlock(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    enabled: bool,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetPermissionlessUnlock { seeds, enabled }.pack();
+    let accounts = account_metas(
+        DESTINATION_OWNER_ACCOUNTS,
+        &[
+            vesting_account_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `set_permissionless_unlock`, but for any UnlockPolicy
+pub fn set_unlock_policy(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    policy: UnlockPolicy,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetUnlockPolicy { seeds, policy }.pack();
+    let accounts = account_metas(
+        DESTINATION_OWNER_ACCOUNTS,
+        &[
+            vesting_account_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `TopUp` instruction, adding `schedules` to the contract
+pub fn top_up(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    source_token_account_key: &Pubkey,
+    schedules: Vec<Schedule>,
+    allow_immediate: bool,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::TopUp {
+        seeds,
+        allow_immediate,
+        schedules,
+    }
+    .pack();
+    let accounts = account_metas(
+        TOP_UP_ACCOUNTS,
+        &[
+            token_program_id,
+            system_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            granter_key,
+            source_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `RefreshClaimable` instruction
+pub fn refresh_claimable(
+    vesting_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::RefreshClaimable { seeds }.pack();
+    let accounts = account_metas(
+        REFRESH_CLAIMABLE_ACCOUNTS,
+        &[clock_sysvar_id, vesting_account_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `Accelerate` instruction
+pub fn accelerate(
+    vesting_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    granter_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Accelerate { seeds }.pack();
+    let accounts = account_metas(
+        GRANTER_CLOCK_ACCOUNTS,
+        &[clock_sysvar_id, granter_key, vesting_account_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `Activate` instruction
+pub fn activate(
+    vesting_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    granter_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Activate { seeds }.pack();
+    let accounts = account_metas(
+        GRANTER_CLOCK_ACCOUNTS,
+        &[clock_sysvar_id, granter_key, vesting_account_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `ChangeGranter` instruction
+pub fn change_granter(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    new_granter_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::ChangeGranter {
+        seeds,
+        new_granter: *new_granter_key,
+    }
+    .pack();
+    let accounts = account_metas(GRANTER_ACCOUNTS, &[vesting_account_key, granter_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Pause` instruction
+pub fn pause(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Pause { seeds }.pack();
+    let accounts = account_metas(GRANTER_ACCOUNTS, &[vesting_account_key, granter_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Resume` instruction
+pub fn resume(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Resume { seeds }.pack();
+    let accounts = account_metas(GRANTER_ACCOUNTS, &[vesting_account_key, granter_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `EmergencyWithdraw` instruction
+pub fn emergency_withdraw(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    emergency_authority_key: &Pubkey,
+    recovery_token_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::EmergencyWithdraw { seeds }.pack();
+    let accounts = account_metas(
+        EMERGENCY_WITHDRAW_ACCOUNTS,
+        &[
+            token_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            emergency_authority_key,
+            recovery_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `DebugShiftSchedules` instruction
+#[cfg(feature = "devnet-tools")]
+pub fn debug_shift_schedules(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    offset: u64,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::DebugShiftSchedules { seeds, offset }.pack();
+    let accounts = account_metas(GRANTER_ACCOUNTS, &[vesting_account_key, granter_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetSplits` instruction, passing the token account of every split
+pub fn set_splits(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    splits: Vec<SplitDestination>,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let mut accounts = account_metas(
+        BOTH_PARTIES_ACCOUNTS,
+        &[
+            vesting_account_key,
+            granter_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    accounts.extend(
+        splits
+            .iter()
+            .map(|split| AccountMeta::new_readonly(split.destination_address, false)),
+    );
+    let data = VestingInstruction::SetSplits { seeds, splits }.pack();
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Merge` instruction, passing the destination index of (mint, destination) so that it follows the
+// kept contract
+pub fn merge(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    merged_vesting_account_key: &Pubkey,
+    merged_vesting_token_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    mint_address: &Pubkey,
+    seeds: Seeds,
+    merged_seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let (index_key, _) = find_destination_index(
+        vesting_program_id,
+        mint_address,
+        destination_token_account_key,
+    );
+    let data = VestingInstruction::Merge {
+        seeds,
+        merged_seeds,
+    }
+    .pack();
+    let accounts = account_metas(
+        MERGE_ACCOUNTS,
+        &[
+            token_program_id,
+            system_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            merged_vesting_account_key,
+            merged_vesting_token_account_key,
+            granter_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+            &index_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `ReadEmissions` instruction
+pub fn read_emissions(
+    vesting_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    mint_address: &Pubkey,
+    weeks: u8,
+) -> Result<Instruction, ProgramError> {
+    let (curve_key, _) = find_emission_curve(vesting_program_id, mint_address);
+    let data = VestingInstruction::ReadEmissions {
+        mint_address: *mint_address,
+        weeks,
+    }
+    .pack();
+    let accounts = account_metas(READ_EMISSIONS_ACCOUNTS, &[clock_sysvar_id, &curve_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// The return data of ReadEmissions: the week (see EmissionCurve::week) of the first amount, then the amount
+/// released in that week and each of the following ones, all little-endian u64s
+pub fn pack_emissions(first_week: u64, weekly_amounts: &[u64]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + weekly_amounts.len() * 8);
+    data.extend_from_slice(&first_week.to_le_bytes());
+    for amount in weekly_amounts {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+    data
+}
+
+/// The reverse of pack_emissions, for consumers reading what ReadEmissions returned
+pub fn unpack_emissions(data: &[u8]) -> Option<(u64, Vec<u64>)> {
+    let words = data.chunks_exact(8);
+    if !words.remainder().is_empty() {
+        return None;
+    }
+    let mut words = words.map(|word| u64::from_le_bytes(word.try_into().unwrap()));
+    let first_week = words.next()?;
+    Some((first_week, words.collect()))
+}
+
+// Creates a `TopUpGas` instruction
+pub fn top_up_gas(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    funder_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    amount: u64,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::TopUpGas { seeds, amount }.pack();
+    let accounts = account_metas(
+        TOP_UP_GAS_ACCOUNTS,
+        &[system_program_id, funder_key, vesting_account_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn change_destination(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    current_destination_token_account_owner: &Pubkey,
+    current_destination_token_account: &Pubkey,
+    target_destination_token_account: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::ChangeDestination {
+        seeds,
+        expected_revision: None,
+    }
+    .pack();
+    let accounts = account_metas(
+        CHANGE_DESTINATION_ACCOUNTS,
+        &[
+            vesting_account_key,
+            current_destination_token_account,
+            current_destination_token_account_owner,
+            target_destination_token_account,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `change_destination`, but fails with StaleRevision once the contract has moved past `expected_revision`
+pub fn change_destination_at_revision(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    current_destination_token_account_owner: &Pubkey,
+    current_destination_token_account: &Pubkey,
+    target_destination_token_account: &Pubkey,
+    seeds: Seeds,
+    expected_revision: u32,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = change_destination(
+        vesting_program_id,
+        vesting_account_key,
+        current_destination_token_account_owner,
+        current_destination_token_account,
+        target_destination_token_account,
+        seeds,
+    )?;
+    instruction.data = VestingInstruction::ChangeDestination {
+        seeds,
+        expected_revision: Some(expected_revision),
+    }
+    .pack();
+    Ok(instruction)
+}
+
+// Creates a `TransferBeneficiary` instruction, passing both destination indexes so that the contract moves over to
+// the new destination's
+pub fn transfer_beneficiary(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    associated_token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    current_destination_token_account_owner: &Pubkey,
+    current_destination_token_account: &Pubkey,
+    new_owner: &Pubkey,
+    mint_address: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let (new_destination_token_account, _) = Pubkey::find_program_address(
+        &[
+            new_owner.as_ref(),
+            token_program_id.as_ref(),
+            mint_address.as_ref(),
+        ],
+        associated_token_program_id,
+    );
+    let (current_index_key, _) = find_destination_index(
+        vesting_program_id,
+        mint_address,
+        current_destination_token_account,
+    );
+    let (new_index_key, _) = find_destination_index(
+        vesting_program_id,
+        mint_address,
+        &new_destination_token_account,
+    );
+    let data = VestingInstruction::TransferBeneficiary {
+        seeds,
+        rent_payer: RentPayer::Beneficiary,
+    }
+    .pack();
+    let accounts = account_metas(
+        TRANSFER_BENEFICIARY_ACCOUNTS,
+        &[
+            vesting_account_key,
+            current_destination_token_account,
+            current_destination_token_account_owner,
+            &new_destination_token_account,
+            new_owner,
+            mint_address,
+            system_program_id,
+            token_program_id,
+            associated_token_program_id,
+            &current_index_key,
+            &new_index_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `TransferBeneficiary` instruction like transfer_beneficiary, with `rent_payer` paying for the new owner's
+// associated token account. `rent_payer_key` is the cranker for RentPayer::Cranker and the granter for
+// RentPayer::GranterEscrow, and isn't used for RentPayer::Beneficiary
+pub fn transfer_beneficiary_paid_by(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    associated_token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    current_destination_token_account_owner: &Pubkey,
+    current_destination_token_account: &Pubkey,
+    new_owner: &Pubkey,
+    mint_address: &Pubkey,
+    seeds: Seeds,
+    rent_payer: RentPayer,
+    rent_payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = transfer_beneficiary(
+        vesting_program_id,
+        token_program_id,
+        system_program_id,
+        associated_token_program_id,
+        vesting_account_key,
+        current_destination_token_account_owner,
+        current_destination_token_account,
+        new_owner,
+        mint_address,
+        seeds,
+    )?;
+    if rent_payer != RentPayer::Beneficiary {
+        instruction.data = VestingInstruction::TransferBeneficiary { seeds, rent_payer }.pack();
+        instruction
+            .accounts
+            .push(TRANSFER_BENEFICIARY_ACCOUNTS[12].meta(rent_payer_key));
+    }
+    Ok(instruction)
+}
+
+// Creates a `CheckRevision` instruction
+pub fn check_revision(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    seeds: Seeds,
+    expected_revision: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::CheckRevision {
+        seeds,
+        expected_revision,
+    }
+    .pack();
+    let accounts = account_metas(CHECK_REVISION_ACCOUNTS, &[vesting_account_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Resize` instruction
+pub fn resize(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    seeds: Seeds,
+    number_of_schedules: u32,
+    schedule_layout: ScheduleLayout,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Resize {
+        seeds,
+        number_of_schedules,
+        schedule_layout,
+    }
+    .pack();
+    let accounts = account_metas(
+        RESIZE_ACCOUNTS,
+        &[system_program_id, payer_key, vesting_account_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `InitGrant` instruction. The granter pays, and the vesting account is at find_vesting_address
+pub fn init_grant(
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    vesting_program_id: &Pubkey,
+    granter: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    nonce: u64,
+    number_of_schedules: u32,
+) -> Result<Instruction, ProgramError> {
+    let (vesting_account_key, _) =
+        find_vesting_address(vesting_program_id, granter, beneficiary, mint, nonce);
+    let data = VestingInstruction::InitGrant {
+        beneficiary: *beneficiary,
+        mint: *mint,
+        nonce,
+        number_of_schedules,
+        schedule_layout: ScheduleLayout::Plain,
+    }
+    .pack();
+    let accounts = account_metas(
+        INIT_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            granter,
+            &vesting_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `CloseUninitialized` instruction
+pub fn close_uninitialized(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    payer_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::CloseUninitialized { seeds }.pack();
+    let accounts = account_metas(
+        CLOSE_UNINITIALIZED_ACCOUNTS,
+        &[vesting_account_key, payer_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// The destination index PDA of a (mint, destination token account) pair
+pub fn find_destination_index(
+    vesting_program_id: &Pubkey,
+    mint_address: &Pubkey,
+    destination_token_account_key: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            DESTINATION_INDEX_SEED,
+            mint_address.as_ref(),
+            destination_token_account_key.as_ref(),
+        ],
+        vesting_program_id,
+    )
+}
+
+/// The destination approvers PDA of a vesting account
+pub fn find_destination_approvers(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DESTINATION_APPROVERS_SEED, vesting_account_key.as_ref()],
+        vesting_program_id,
+    )
+}
+
+/// The emission curve PDA of a mint
+pub fn find_emission_curve(vesting_program_id: &Pubkey, mint_address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[EMISSION_CURVE_SEED, mint_address.as_ref()],
+        vesting_program_id,
+    )
+}
+
+/// Same as any of the `create` builders but `create_linear`, but also tracks the new contract on the emission curve
+/// of its mint
+pub fn with_new_emission_curve(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    instruction: Instruction,
+    mint_address: &Pubkey,
+) -> Instruction {
+    // the source owner pays for the curve if it's the mint's first tracked contract
+    let instruction = paying_create(system_program_id, instruction);
+    with_emission_curve(vesting_program_id, instruction, mint_address)
+}
+
+/// Same as the builder of any instruction changing a contract's schedules - `unlock`, `revoke`, `revoke_batch`,
+/// `top_up`, `accelerate`, `emergency_withdraw`, `export_contract` and their variants - with the emission curve of
+/// the contract's mint appended. Needed for every one of them once the contract is tracked, see
+/// `with_new_emission_curve`
+pub fn with_emission_curve(
+    vesting_program_id: &Pubkey,
+    mut instruction: Instruction,
+    mint_address: &Pubkey,
+) -> Instruction {
+    let (curve_key, _) = find_emission_curve(vesting_program_id, mint_address);
+    instruction
+        .accounts
+        .push(EMISSION_CURVE_ACCOUNT.meta(&curve_key));
+    instruction
+}
+
+/// Same as any of the `create` builders, but also points the destination index of (mint, destination) at the new
+/// contract. `destination_token_account_key` has to be the one the contract is created for
+pub fn with_destination_index(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    instruction: Instruction,
+    mint_address: &Pubkey,
+    destination_token_account_key: &Pubkey,
+) -> Instruction {
+    let (index_key, _) = find_destination_index(
+        vesting_program_id,
+        mint_address,
+        destination_token_account_key,
+    );
+    // the source owner pays for the index
+    let mut instruction = paying_create(system_program_id, instruction);
+    instruction
+        .accounts
+        .push(PAYING_CREATE_ACCOUNTS[8].meta(&index_key));
+    instruction
+}
+
+/// A Create instruction with the source token account owner paying, see PAYING_CREATE_ACCOUNTS. `create` already
+/// makes one with a gas deposit
+fn paying_create(system_program_id: &Pubkey, mut instruction: Instruction) -> Instruction {
+    let owner = instruction.accounts[3].pubkey;
+    instruction.accounts[3] = PAYING_CREATE_ACCOUNTS[3].meta(&owner);
+    if !instruction
+        .accounts
+        .iter()
+        .any(|a| a.pubkey == *system_program_id)
+    {
+        instruction
+            .accounts
+            .push(PAYING_CREATE_ACCOUNTS[7].meta(system_program_id));
+    }
+    instruction
+}
+
+/// Same as `with_destination_index`, `with_moved_destination_index`, `with_moved_destination_index_on_approval` or
+/// `transfer_beneficiary`, for a destination whose index still points at `indexed_contract`, an earlier contract that
+/// has since stopped paying it. The program checks that it has before taking the index over, see find_destination_index
+pub fn replacing_indexed_contract(
+    mut instruction: Instruction,
+    indexed_contract: &Pubkey,
+) -> Instruction {
+    let meta = AccountMeta::new_readonly(*indexed_contract, false);
+    // TransferBeneficiary's rent payer stays the last account
+    match VestingInstruction::unpack(&instruction.data) {
+        Ok(VestingInstruction::TransferBeneficiary { .. }) => instruction.accounts.insert(11, meta),
+        _ => instruction.accounts.push(meta),
+    }
+    instruction
+}
+
+/// Same as `approve_destination_change`, but also moves the contract from the index of `current_destination` to the
+/// new destination's should this vote change the destination
+pub fn with_moved_destination_index_on_approval(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    mut instruction: Instruction,
+    mint_address: &Pubkey,
+    current_destination: &Pubkey,
+) -> Instruction {
+    let (current_index_key, _) =
+        find_destination_index(vesting_program_id, mint_address, current_destination);
+    let (new_index_key, _) = find_destination_index(
+        vesting_program_id,
+        mint_address,
+        &instruction.accounts[3].pubkey,
+    );
+    // the approver pays for the new index
+    instruction.accounts[2].is_writable = true;
+    instruction.accounts.extend(account_metas(
+        &APPROVE_DESTINATION_CHANGE_ACCOUNTS[4..],
+        &[system_program_id, &current_index_key, &new_index_key],
+    ));
+    instruction
+}
+
+/// Same as `change_destination` or `change_future_destination`, but also moves the contract from the current
+/// destination's index to the new destination's
+pub fn with_moved_destination_index(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    mut instruction: Instruction,
+    mint_address: &Pubkey,
+) -> Instruction {
+    let (current_index_key, _) = find_destination_index(
+        vesting_program_id,
+        mint_address,
+        &instruction.accounts[1].pubkey,
+    );
+    let (new_index_key, _) = find_destination_index(
+        vesting_program_id,
+        mint_address,
+        &instruction.accounts[3].pubkey,
+    );
+    // the destination owner pays for the new index
+    instruction.accounts[2].is_writable = true;
+    instruction.accounts.extend(account_metas(
+        &CHANGE_DESTINATION_ACCOUNTS[4..],
+        &[system_program_id, &current_index_key, &new_index_key],
+    ));
+    instruction
+}
+
+// Creates a `ChangeFutureDestination` instruction
+pub fn change_future_destination(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    current_destination_token_account_owner: &Pubkey,
+    current_destination_token_account: &Pubkey,
+    target_destination_token_account: &Pubkey,
+    cutoff: u64,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = change_destination(
+        vesting_program_id,
+        vesting_account_key,
+        current_destination_token_account_owner,
+        current_destination_token_account,
+        target_destination_token_account,
+        seeds,
+    )?;
+    instruction.data = VestingInstruction::ChangeFutureDestination { seeds, cutoff }.pack();
+    Ok(instruction)
+}
+
+// Creates a `VerifySolvency` instruction
+pub fn verify_solvency(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::VerifySolvency { seeds }.pack();
+    let accounts = account_metas(
+        VERIFY_SOLVENCY_ACCOUNTS,
+        &[vesting_account_key, vesting_token_account_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetRollover` instruction
+pub fn set_rollover(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    enabled: bool,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetRollover { seeds, enabled }.pack();
+    let accounts = account_metas(
+        BOTH_PARTIES_ACCOUNTS,
+        &[
+            vesting_account_key,
+            granter_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetMinClaimAmount` instruction
+pub fn set_min_claim_amount(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    min_claim_amount: u64,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetMinClaimAmount {
+        seeds,
+        min_claim_amount,
+    }
+    .pack();
+    let accounts = account_metas(
+        DESTINATION_OWNER_ACCOUNTS,
+        &[
+            vesting_account_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// The program config PDA of a deployment
+pub fn find_program_config(vesting_program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], vesting_program_id)
+}
+
+// Creates an `InitConfig` instruction, to be signed by the program's upgrade authority
+pub fn init_config(
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    vesting_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    upgrade_authority_key: &Pubkey,
+    admin: &Pubkey,
+    early_claim_tolerance_seconds: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::InitConfig {
+        admin: *admin,
+        early_claim_tolerance_seconds,
+    }
+    .pack();
+    let (config_key, _) = find_program_config(vesting_program_id);
+    let (program_data_key, _) = Pubkey::find_program_address(
+        &[vesting_program_id.as_ref()],
+        &bpf_loader_upgradeable::id(),
+    );
+    let accounts = account_metas(
+        INIT_CONFIG_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            payer_key,
+            &config_key,
+            &program_data_key,
+            upgrade_authority_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetEarlyClaimTolerance` instruction
+pub fn set_early_claim_tolerance(
+    vesting_program_id: &Pubkey,
+    admin_key: &Pubkey,
+    early_claim_tolerance_seconds: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetEarlyClaimTolerance {
+        early_claim_tolerance_seconds,
+    }
+    .pack();
+    let (config_key, _) = find_program_config(vesting_program_id);
+    let accounts = account_metas(CONFIG_ADMIN_ACCOUNTS, &[&config_key, admin_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetWithholding` instruction. The withholding token account is ignored when withholding_bps is 0
+pub fn set_withholding(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    withholding_token_account_key: &Pubkey,
+    withholding_bps: u16,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetWithholding {
+        seeds,
+        withholding_bps,
+    }
+    .pack();
+    let accounts = account_metas(
+        SET_WITHHOLDING_ACCOUNTS,
+        &[
+            vesting_account_key,
+            granter_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+            withholding_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetTransferLimit` instruction
+pub fn set_transfer_limit(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    max_transfer_per_unlock: u64,
+    unlock_interval: u32,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetTransferLimit {
+        seeds,
+        max_transfer_per_unlock,
+        unlock_interval,
+    }
+    .pack();
+    let accounts = account_metas(
+        BOTH_PARTIES_ACCOUNTS,
+        &[
+            vesting_account_key,
+            granter_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `InitDestinationApprovers` instruction
+pub fn init_destination_approvers(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    threshold: u8,
+    approvers: Vec<Pubkey>,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::InitDestinationApprovers {
+        seeds,
+        threshold,
+        approvers,
+    }
+    .pack();
+    let (approvers_key, _) = find_destination_approvers(vesting_program_id, vesting_account_key);
+    let accounts = account_metas(
+        INIT_DESTINATION_APPROVERS_ACCOUNTS,
+        &[
+            system_program_id,
+            payer_key,
+            vesting_account_key,
+            &approvers_key,
+            granter_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `ApproveDestinationChange` instruction
+pub fn approve_destination_change(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    approver_key: &Pubkey,
+    new_destination_token_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::ApproveDestinationChange {
+        seeds,
+        new_destination: *new_destination_token_account_key,
+    }
+    .pack();
+    let (approvers_key, _) = find_destination_approvers(vesting_program_id, vesting_account_key);
+    let accounts = account_metas(
+        APPROVE_DESTINATION_CHANGE_ACCOUNTS,
+        &[
+            vesting_account_key,
+            &approvers_key,
+            approver_key,
+            new_destination_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `MigrateLayout` instruction
+pub fn migrate_layout(
+    vesting_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    account_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::MigrateLayout.pack();
+    let accounts = account_metas(
+        MIGRATE_LAYOUT_ACCOUNTS,
+        &[system_program_id, payer_key, account_key],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Compact` instruction
+pub fn compact(
+    vesting_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    rent_receiver_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Compact { seeds }.pack();
+    let accounts = account_metas(
+        COMPACT_ACCOUNTS,
+        &[
+            rent_program_id,
+            vesting_account_key,
+            granter_key,
+            rent_receiver_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetCommunityTreasury` instruction
+pub fn set_community_treasury(
+    vesting_program_id: &Pubkey,
+    admin_key: &Pubkey,
+    community_treasury: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetCommunityTreasury {
+        community_treasury: *community_treasury,
+    }
+    .pack();
+    let (config_key, _) = find_program_config(vesting_program_id);
+    let accounts = account_metas(CONFIG_ADMIN_ACCOUNTS, &[&config_key, admin_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `SetForbidSelfDealing` instruction
+pub fn set_forbid_self_dealing(
+    vesting_program_id: &Pubkey,
+    admin_key: &Pubkey,
+    forbid_self_dealing: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::SetForbidSelfDealing {
+        forbid_self_dealing,
+    }
+    .pack();
+    let (config_key, _) = find_program_config(vesting_program_id);
+    let accounts = account_metas(CONFIG_ADMIN_ACCOUNTS, &[&config_key, admin_key]);
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Close` instruction. `dust_receiver_key` depends on the policy, see Close - the program config is added
+// for CommunityTreasury
+pub fn close(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    dust_receiver_key: &Pubkey,
+    dust_policy: DustPolicy,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Close { seeds, dust_policy }.pack();
+    let mut accounts = account_metas(
+        CLOSE_ACCOUNTS,
+        &[
+            token_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            granter_key,
+            dust_receiver_key,
+        ],
+    );
+    if dust_policy == DustPolicy::CommunityTreasury {
+        let (config_key, _) = find_program_config(vesting_program_id);
+        accounts.push(CLOSE_ACCOUNTS[5].meta(&config_key));
+    }
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// The tree CreateCompressed sets up and the tokens it holds, for the `create_compressed` builder
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateCompressedParams {
+    /// One of compression::TREE_SIZES, along with max_buffer_size
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+    /// Moved from the source token account into the vesting token account, to pay out every leaf appended later
+    pub total_amount: u64,
+}
+
+// Creates a `CreateCompressed` instruction
+pub fn create_compressed(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    source_token_account_owner_key: &Pubkey,
+    source_token_account_key: &Pubkey,
+    merkle_tree_key: &Pubkey,
+    mint_address: &Pubkey,
+    params: CreateCompressedParams,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let CreateCompressedParams {
+        max_depth,
+        max_buffer_size,
+        total_amount,
+    } = params;
+    let data = VestingInstruction::CreateCompressed {
+        seeds,
+        token_mint_addr: *mint_address,
+        max_depth,
+        max_buffer_size,
+        total_amount,
+    }
+    .pack();
+    let accounts = account_metas(
+        CREATE_COMPRESSED_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            payer_key,
+            token_program_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            source_token_account_owner_key,
+            source_token_account_key,
+            merkle_tree_key,
+            &spl_account_compression::id(),
+            &spl_noop::id(),
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `AppendCompressed` instruction
+pub fn append_compressed(
+    vesting_program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    merkle_tree_key: &Pubkey,
+    leaves: Vec<Node>,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::AppendCompressed { seeds, leaves }.pack();
+    let accounts = account_metas(
+        APPEND_COMPRESSED_ACCOUNTS,
+        &[
+            vesting_account_key,
+            granter_key,
+            merkle_tree_key,
+            &spl_account_compression::id(),
+            &spl_noop::id(),
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// The leaf ClaimCompressed pays out and its proof, for the `claim_compressed` builder
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClaimCompressedParams {
+    /// Any root still in the tree's changelog buffer
+    pub root: Node,
+    pub index: u32,
+    pub release_time: u64,
+    pub amount: u64,
+    /// The sibling nodes from the leaf up, see compression::MerkleTree::proof_accounts
+    pub proof: Vec<Pubkey>,
+}
+
+// Creates a `ClaimCompressed` instruction
+pub fn claim_compressed(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    merkle_tree_key: &Pubkey,
+    params: ClaimCompressedParams,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let ClaimCompressedParams {
+        root,
+        index,
+        release_time,
+        amount,
+        proof,
+    } = params;
+    let data = VestingInstruction::ClaimCompressed {
+        seeds,
+        root,
+        index,
+        release_time,
+        amount,
+    }
+    .pack();
+    let mut accounts = account_metas(
+        CLAIM_COMPRESSED_ACCOUNTS,
+        &[
+            token_program_id,
+            clock_sysvar_id,
+            vesting_account_key,
+            vesting_token_account_key,
+            destination_token_account_key,
+            merkle_tree_key,
+            &spl_account_compression::id(),
+            &spl_noop::id(),
+        ],
+    );
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false)),
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `RevokeBatch` instruction
+pub fn revoke_batch(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    granter_key: &Pubkey,
+    contracts: &[RevokeBatchEntry],
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::RevokeBatch {
+        seeds: contracts.iter().map(|c| c.seeds).collect(),
+    }
+    .pack();
+    let mut accounts = account_metas(
+        &REVOKE_ACCOUNTS[..3],
+        &[token_program_id, clock_sysvar_id, granter_key],
+    );
+    for c in contracts.iter() {
+        accounts.extend(account_metas(
+            &REVOKE_ACCOUNTS[3..],
+            &[
+                &c.vesting_account_key,
+                &c.vesting_token_account_key,
+                &c.granter_token_account_key,
+            ],
+        ));
+    }
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates a `Revoke` instruction
+pub fn revoke(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    granter_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    granter_token_account_key: &Pubkey,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::Revoke {
+        seeds,
+        expected_revision: None,
+    }
+    .pack();
+    let accounts = account_metas(
+        REVOKE_ACCOUNTS,
+        &[
+            token_program_id,
+            clock_sysvar_id,
+            granter_key,
+            vesting_account_key,
+            vesting_token_account_key,
+            granter_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `revoke`, but fails with StaleRevision once the contract has moved past `expected_revision`
+pub fn revoke_at_revision(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    granter_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    granter_token_account_key: &Pubkey,
+    seeds: Seeds,
+    expected_revision: u32,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = revoke(
+        vesting_program_id,
+        token_program_id,
+        clock_sysvar_id,
+        granter_key,
+        vesting_account_key,
+        vesting_token_account_key,
+        granter_token_account_key,
+        seeds,
+    )?;
+    instruction.data = VestingInstruction::Revoke {
+        seeds,
+        expected_revision: Some(expected_revision),
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// The PDA of the new deployment that owns the escrow a given vesting account is exported to
+pub fn find_migration_authority(
+    new_program_id: &Pubkey,
+    old_vesting_account_key: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MIGRATION_SEED, old_vesting_account_key.as_ref()],
+        new_program_id,
+    )
+}
+
+/// The contract ExportContract hands over and ImportContract picks up, for both builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractMigration {
+    /// The deployment ImportContract runs on
+    pub new_program_id: Pubkey,
+    /// The exported vesting account, on the old deployment
+    pub old_vesting_account_key: Pubkey,
+    /// Owned by the new deployment's migration authority, see find_migration_authority
+    pub escrow_token_account_key: Pubkey,
+}
+
+// Creates an `ExportContract` instruction, to be sent to the old deployment
+pub fn export_contract(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    granter_key: &Pubkey,
+    destination_token_account_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    migration: &ContractMigration,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::ExportContract {
+        seeds,
+        new_program_id: migration.new_program_id,
+    }
+    .pack();
+    let accounts = account_metas(
+        EXPORT_CONTRACT_ACCOUNTS,
+        &[
+            token_program_id,
+            &migration.old_vesting_account_key,
+            vesting_token_account_key,
+            &migration.escrow_token_account_key,
+            granter_key,
+            destination_token_account_key,
+            destination_token_account_owner_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: *vesting_program_id,
+        accounts,
+        data,
+    })
+}
+
+// Creates an `ImportContract` instruction, to be sent to the new deployment
+pub fn import_contract(
+    token_program_id: &Pubkey,
+    system_program_id: &Pubkey,
+    rent_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    vesting_account_key: &Pubkey,
+    vesting_token_account_key: &Pubkey,
+    migration: &ContractMigration,
+    seeds: Seeds,
+) -> Result<Instruction, ProgramError> {
+    let data = VestingInstruction::ImportContract { seeds }.pack();
+    let (migration_authority_key, _) = find_migration_authority(
+        &migration.new_program_id,
+        &migration.old_vesting_account_key,
+    );
+    let accounts = account_metas(
+        IMPORT_CONTRACT_ACCOUNTS,
+        &[
+            system_program_id,
+            rent_program_id,
+            payer_key,
+            token_program_id,
+            &migration.old_vesting_account_key,
+            &migration_authority_key,
+            &migration.escrow_token_account_key,
+            vesting_account_key,
+            vesting_token_account_key,
+        ],
+    );
+    Ok(Instruction {
+        program_id: migration.new_program_id,
+        accounts,
+        data,
+    })
+}
+
+// ----------------------------------------------------------------------------- needed for fuzzing
+
+#[cfg(feature = "fuzz")]
+impl arbitrary::Arbitrary<'_> for VestingInstruction {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        let seeds: [u8; 32] = u.arbitrary()?;
+        let choice = u.choose(&[0, 1, 2, 3, 4])?;
+        match choice {
+            0 => {
+                let number_of_schedules = u.arbitrary()?;
+                return Ok(Self::Init {
+                    seeds,
+                    number_of_schedules,
+                    schedule_layout: ScheduleLayout::Plain,
+                });
+            }
+            _ => {
+                let schedules: [Schedule; 10] = u.arbitrary()?;
+                let key_bytes: [u8; 32] = u.arbitrary()?;
+                let token_mint_addr: Pubkey = Pubkey::new(&key_bytes);
+                let key_bytes: [u8; 32] = u.arbitrary()?;
+                let token_dest_addr: Pubkey = Pubkey::new(&key_bytes);
+                return Ok(Self::Create {
+                    seeds,
+                    token_mint_addr,
+                    token_dest_addr,
+                    allow_immediate: u.arbitrary()?,
+                    gas_rebate: 0,
+                    gas_deposit: 0,
+                    min_claim_amount: u.arbitrary()?,
+                    destination_locked: u.arbitrary()?,
+                    forbid_self_dealing: u.arbitrary()?,
+                    revoke_policy: RevokePolicy::ReturnToGranter,
+                    relative: false,
+                    emergency_authority: Pubkey::default(),
+                    revocable: true,
+                    schedules: schedules.to_vec(),
+                    categories: vec![],
+                });
+            } // todo didn't bother implementing..
+              // 2 => return Ok(Self::Unlock { seeds }),
+              // 3 => return Ok(Self::ChangeDestination { seeds }),
+              // _ => {
+              //     return Ok(Self::Empty {
+              //         number: u.arbitrary()?,
+              //     })
+              // }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------- test
+
+#[cfg(test)]
+mod test {
+    use solana_program::program_pack::Pack;
+
+    use super::*;
+
+    #[test]
+    fn test_instruction_packing() {
+        let token_mint_addr = Pubkey::new_unique();
+        let token_dest_addr = Pubkey::new_unique();
+
+        let original_create = VestingInstruction::Create {
+            seeds: [50u8; 32],
+            schedules: vec![Schedule {
+                amount: 42,
+                release_time: 250,
+            }],
+            token_mint_addr: token_mint_addr.clone(),
+            token_dest_addr,
+            allow_immediate: false,
+            gas_rebate: 5000,
+            gas_deposit: 50000,
+            min_claim_amount: 1000,
+            destination_locked: true,
+            forbid_self_dealing: false,
+            revoke_policy: RevokePolicy::Burn,
+            relative: true,
+            emergency_authority: Pubkey::new_unique(),
+            revocable: false,
+            categories: vec![],
+        };
+        let packed_create = original_create.pack();
+        let unpacked_create = VestingInstruction::unpack(&packed_create).unwrap();
+        assert_eq!(original_create, unpacked_create);
+        // there's no third policy
+        let mut unknown_policy = packed_create.clone();
+        unknown_policy[1 + 123] = 2;
+        assert!(VestingInstruction::unpack(&unknown_policy).is_err());
+
+        let categorized_create = VestingInstruction::Create {
+            schedules: vec![
+                Schedule {
+                    amount: 42,
+                    release_time: 250,
+                },
+                Schedule {
+                    amount: 7,
+                    release_time: 500,
+                },
+            ],
+            categories: vec![ScheduleCategory::Salary, ScheduleCategory::Advisor],
+            seeds: [50u8; 32],
+            token_mint_addr,
+            token_dest_addr,
+            allow_immediate: false,
+            gas_rebate: 0,
+            gas_deposit: 0,
+            min_claim_amount: 0,
+            destination_locked: false,
+            forbid_self_dealing: false,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            relative: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+        };
+        let packed_categorized = categorized_create.pack();
+        assert_eq!(packed_categorized[0], 18);
+        assert_eq!(packed_categorized.len(), 1 + 158 + 2 * (SCHEDULE_SIZE + 1));
+        assert_eq!(&packed_categorized[packed_categorized.len() - 2..], &[1, 3]);
+        assert_eq!(
+            categorized_create,
+            VestingInstruction::unpack(&packed_categorized).unwrap()
+        );
+
+        let original_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 0,
+            max_amount: 0,
+            start_index: None,
+        };
+        assert_eq!(original_unlock.pack().len(), 33);
+        assert_eq!(
+            original_unlock,
+            VestingInstruction::unpack(&original_unlock.pack()).unwrap()
+        );
+        let bounded_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 64,
+            max_amount: 0,
+            start_index: None,
+        };
+        assert_eq!(bounded_unlock.pack().len(), 37);
+        assert_eq!(
+            bounded_unlock,
+            VestingInstruction::unpack(&bounded_unlock.pack()).unwrap()
+        );
+        let partial_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 0,
+            max_amount: 1_000,
+            start_index: None,
+        };
+        assert_eq!(partial_unlock.pack().len(), 45);
+        assert_eq!(
+            partial_unlock,
+            VestingInstruction::unpack(&partial_unlock.pack()).unwrap()
+        );
+        let range_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 64,
+            max_amount: 0,
+            start_index: Some(128),
+        };
+        assert_eq!(range_unlock.pack().len(), 41);
+        assert_eq!(
+            range_unlock,
+            VestingInstruction::unpack(&range_unlock.pack()).unwrap()
+        );
+        // rejected by the processor, but still round trips
+        let capped_range_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 64,
+            max_amount: 1_000,
+            start_index: Some(128),
+        };
+        assert_eq!(
+            capped_range_unlock,
+            VestingInstruction::unpack(&capped_range_unlock.pack()).unwrap()
+        );
+
+        let original_init = VestingInstruction::Init {
+            number_of_schedules: 42,
+            seeds: [50u8; 32],
+            schedule_layout: ScheduleLayout::Plain,
+        };
+        assert_eq!(original_init.pack().len(), 37);
+        assert_eq!(
+            original_init,
+            VestingInstruction::unpack(&original_init.pack()).unwrap()
+        );
+        let categorized_init = VestingInstruction::Init {
+            number_of_schedules: 42,
+            seeds: [50u8; 32],
+            schedule_layout: ScheduleLayout::Categorized,
+        };
+        assert_eq!(
+            categorized_init,
+            VestingInstruction::unpack(&categorized_init.pack()).unwrap()
+        );
+        let linear_init = VestingInstruction::Init {
+            number_of_schedules: 1,
+            seeds: [50u8; 32],
+            schedule_layout: ScheduleLayout::Linear,
+        };
+        assert_eq!(
+            linear_init,
+            VestingInstruction::unpack(&linear_init.pack()).unwrap()
+        );
+
+        let original_change = VestingInstruction::ChangeDestination {
+            seeds: [50u8; 32],
+            expected_revision: None,
+        };
+        assert_eq!(original_change.pack().len(), 33);
+        assert_eq!(
+            original_change,
+            VestingInstruction::unpack(&original_change.pack()).unwrap()
+        );
+        let checked_change = VestingInstruction::ChangeDestination {
+            seeds: [50u8; 32],
+            expected_revision: Some(7),
+        };
+        assert_eq!(checked_change.pack().len(), 37);
+        assert_eq!(
+            checked_change,
+            VestingInstruction::unpack(&checked_change.pack()).unwrap()
+        );
+
+        let original_create_compressed = VestingInstruction::CreateCompressed {
+            seeds: [50u8; 32],
+            token_mint_addr,
+            max_depth: 14,
+            max_buffer_size: 64,
+            total_amount: 1_000_000,
+        };
+        assert_eq!(
+            original_create_compressed,
+            VestingInstruction::unpack(&original_create_compressed.pack()).unwrap()
+        );
+
+        let original_append_compressed = VestingInstruction::AppendCompressed {
+            seeds: [50u8; 32],
+            leaves: vec![[1u8; 32], [2u8; 32]],
+        };
+        assert_eq!(
+            original_append_compressed,
+            VestingInstruction::unpack(&original_append_compressed.pack()).unwrap()
+        );
+        // nothing to append
+        assert!(VestingInstruction::unpack(&[&[52][..], &[50u8; 32]].concat()).is_err());
+
+        let original_claim_compressed = VestingInstruction::ClaimCompressed {
+            seeds: [50u8; 32],
+            root: [7u8; 32],
+            index: 3,
+            release_time: 250,
+            amount: 42,
+        };
+        assert_eq!(
+            original_claim_compressed,
+            VestingInstruction::unpack(&original_claim_compressed.pack()).unwrap()
+        );
+
+        let original_revoke_batch = VestingInstruction::RevokeBatch {
+            seeds: vec![[50u8; 32], [51u8; 32], [52u8; 32]],
+        };
+        assert_eq!(
+            original_revoke_batch,
+            VestingInstruction::unpack(&original_revoke_batch.pack()).unwrap()
+        );
+
+        let original_export = VestingInstruction::ExportContract {
+            seeds: [50u8; 32],
+            new_program_id: Pubkey::new_unique(),
+        };
+        assert_eq!(
+            original_export,
+            VestingInstruction::unpack(&original_export.pack()).unwrap()
+        );
+
+        let original_import = VestingInstruction::ImportContract { seeds: [50u8; 32] };
+        assert_eq!(
+            original_import,
+            VestingInstruction::unpack(&original_import.pack()).unwrap()
+        );
+
+        let original_init_child = VestingInstruction::InitChild {
+            base_seed: [50u8; 32],
+            index: 7,
+            number_of_schedules: 12,
+        };
+        assert_eq!(
+            original_init_child,
+            VestingInstruction::unpack(&original_init_child.pack()).unwrap()
+        );
+
+        let original_top_up = VestingInstruction::TopUpGas {
+            seeds: [50u8; 32],
+            amount: 10_000,
+        };
+        assert_eq!(
+            original_top_up,
+            VestingInstruction::unpack(&original_top_up.pack()).unwrap()
+        );
+
+        let original_set_permissionless = VestingInstruction::SetPermissionlessUnlock {
+            seeds: [50u8; 32],
+            enabled: false,
+        };
+        assert_eq!(
+            original_set_permissionless,
+            VestingInstruction::unpack(&original_set_permissionless.pack()).unwrap()
+        );
+
+        let original_set_policy = VestingInstruction::SetUnlockPolicy {
+            seeds: [50u8; 32],
+            policy: UnlockPolicy::DelegateOnly,
+        };
+        assert_eq!(
+            original_set_policy,
+            VestingInstruction::unpack(&original_set_policy.pack()).unwrap()
+        );
+        let mut unknown_policy = original_set_policy.pack();
+        unknown_policy[33] = 3;
+        assert_eq!(
+            VestingInstruction::unpack(&unknown_policy),
+            Err(InvalidInstruction.into())
+        );
+
+        let original_unlock_with_permit = VestingInstruction::UnlockWithPermit {
+            seeds: [50u8; 32],
+            expiry: 1_700_000_000,
+        };
+        assert_eq!(
+            original_unlock_with_permit,
+            VestingInstruction::unpack(&original_unlock_with_permit.pack()).unwrap()
+        );
+
+        let original_verify = VestingInstruction::VerifySolvency { seeds: [50u8; 32] };
+        assert_eq!(
+            original_verify,
+            VestingInstruction::unpack(&original_verify.pack()).unwrap()
+        );
+
+        let original_accelerate = VestingInstruction::Accelerate { seeds: [50u8; 32] };
+        assert_eq!(
+            original_accelerate,
+            VestingInstruction::unpack(&original_accelerate.pack()).unwrap()
+        );
+
+        let original_activate = VestingInstruction::Activate { seeds: [50u8; 32] };
+        assert_eq!(
+            original_activate,
+            VestingInstruction::unpack(&original_activate.pack()).unwrap()
+        );
+
+        let original_set_splits = VestingInstruction::SetSplits {
+            seeds: [50u8; 32],
+            splits: vec![
+                SplitDestination {
+                    destination_address: Pubkey::new_unique(),
+                    bps: 2_000,
+                },
+                SplitDestination {
+                    destination_address: Pubkey::new_unique(),
+                    bps: 1_000,
+                },
+            ],
+        };
+        let packed_set_splits = original_set_splits.pack();
+        assert_eq!(packed_set_splits.len(), 1 + 32 + 2 * 34);
+        assert_eq!(
+            original_set_splits,
+            VestingInstruction::unpack(&packed_set_splits).unwrap()
+        );
+        assert!(
+            VestingInstruction::unpack(&packed_set_splits[..packed_set_splits.len() - 1]).is_err()
+        );
+
+        let original_read_emissions = VestingInstruction::ReadEmissions {
+            mint_address: Pubkey::new_unique(),
+            weeks: MAX_READ_EMISSION_WEEKS,
+        };
+        let packed_read_emissions = original_read_emissions.pack();
+        assert_eq!(packed_read_emissions.len(), 1 + 32 + 1);
+        assert_eq!(
+            original_read_emissions,
+            VestingInstruction::unpack(&packed_read_emissions).unwrap()
+        );
+
+        let original_merge = VestingInstruction::Merge {
+            seeds: [50u8; 32],
+            merged_seeds: [51u8; 32],
+        };
+        let packed_merge = original_merge.pack();
+        assert_eq!(packed_merge.len(), 1 + 32 + 32);
+        assert_eq!(
+            original_merge,
+            VestingInstruction::unpack(&packed_merge).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&packed_merge[..64]).is_err());
+
+        for rent_payer in [
+            RentPayer::Beneficiary,
+            RentPayer::Cranker,
+            RentPayer::GranterEscrow,
+        ] {
+            let original_transfer_beneficiary = VestingInstruction::TransferBeneficiary {
+                seeds: [50u8; 32],
+                rent_payer,
+            };
+            assert_eq!(
+                original_transfer_beneficiary,
+                VestingInstruction::unpack(&original_transfer_beneficiary.pack()).unwrap()
+            );
+        }
+        // the current owner paying only has the short encoding
+        let mut packed_transfer_beneficiary = VestingInstruction::TransferBeneficiary {
+            seeds: [50u8; 32],
+            rent_payer: RentPayer::Beneficiary,
+        }
+        .pack();
+        assert_eq!(packed_transfer_beneficiary.len(), 33);
+        packed_transfer_beneficiary.push(RentPayer::Beneficiary as u8);
+        assert!(VestingInstruction::unpack(&packed_transfer_beneficiary).is_err());
+
+        let original_check_revision = VestingInstruction::CheckRevision {
+            seeds: [50u8; 32],
+            expected_revision: 7,
+        };
+        assert_eq!(
+            original_check_revision,
+            VestingInstruction::unpack(&original_check_revision.pack()).unwrap()
+        );
+
+        let original_resize = VestingInstruction::Resize {
+            seeds: [50u8; 32],
+            number_of_schedules: 12,
+            schedule_layout: ScheduleLayout::Plain,
+        };
+        assert_eq!(original_resize.pack().len(), 37);
+        assert_eq!(
+            original_resize,
+            VestingInstruction::unpack(&original_resize.pack()).unwrap()
+        );
+        let categorized_resize = VestingInstruction::Resize {
+            seeds: [50u8; 32],
+            number_of_schedules: 12,
+            schedule_layout: ScheduleLayout::Categorized,
+        };
+        assert_eq!(
+            categorized_resize,
+            VestingInstruction::unpack(&categorized_resize.pack()).unwrap()
+        );
+        // Plain only has the short encoding
+        let mut long_plain = original_resize.pack();
+        long_plain.push(ScheduleLayout::Plain as u8);
+        assert!(VestingInstruction::unpack(&long_plain).is_err());
+
+        let original_close_uninitialized =
+            VestingInstruction::CloseUninitialized { seeds: [50u8; 32] };
+        assert_eq!(
+            original_close_uninitialized,
+            VestingInstruction::unpack(&original_close_uninitialized.pack()).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&original_close_uninitialized.pack()[..32]).is_err());
+
+        let original_init_grant = VestingInstruction::InitGrant {
+            beneficiary: token_dest_addr,
+            mint: token_mint_addr,
+            nonce: 3,
+            number_of_schedules: 2,
+            schedule_layout: ScheduleLayout::Plain,
+        };
+        assert_eq!(original_init_grant.pack().len(), 77);
+        assert_eq!(
+            original_init_grant,
+            VestingInstruction::unpack(&original_init_grant.pack()).unwrap()
+        );
+        let categorized_init_grant = VestingInstruction::InitGrant {
+            beneficiary: token_dest_addr,
+            mint: token_mint_addr,
+            nonce: 3,
+            number_of_schedules: 2,
+            schedule_layout: ScheduleLayout::Categorized,
+        };
+        assert_eq!(categorized_init_grant.pack().len(), 78);
+        assert_eq!(
+            categorized_init_grant,
+            VestingInstruction::unpack(&categorized_init_grant.pack()).unwrap()
+        );
+        let mut plain_spelled_out = original_init_grant.pack();
+        plain_spelled_out.push(ScheduleLayout::Plain as u8);
+        assert!(VestingInstruction::unpack(&plain_spelled_out).is_err());
+
+        let original_emergency_withdraw =
+            VestingInstruction::EmergencyWithdraw { seeds: [50u8; 32] };
+        assert_eq!(
+            original_emergency_withdraw,
+            VestingInstruction::unpack(&original_emergency_withdraw.pack()).unwrap()
+        );
+
+        #[cfg(feature = "devnet-tools")]
+        {
+            let original_shift = VestingInstruction::DebugShiftSchedules {
+                seeds: [50u8; 32],
+                offset: 86_400,
+            };
+            assert_eq!(
+                original_shift,
+                VestingInstruction::unpack(&original_shift.pack()).unwrap()
+            );
+        }
+
+        let original_pause = VestingInstruction::Pause { seeds: [50u8; 32] };
+        assert_eq!(
+            original_pause,
+            VestingInstruction::unpack(&original_pause.pack()).unwrap()
+        );
+
+        let original_resume = VestingInstruction::Resume { seeds: [50u8; 32] };
+        assert_eq!(
+            original_resume,
+            VestingInstruction::unpack(&original_resume.pack()).unwrap()
+        );
+
+        let original_change_granter = VestingInstruction::ChangeGranter {
+            seeds: [50u8; 32],
+            new_granter: Pubkey::new_unique(),
+        };
+        assert_eq!(
+            original_change_granter,
+            VestingInstruction::unpack(&original_change_granter.pack()).unwrap()
+        );
+
+        let original_refresh = VestingInstruction::RefreshClaimable { seeds: [50u8; 32] };
+        assert_eq!(
+            original_refresh,
+            VestingInstruction::unpack(&original_refresh.pack()).unwrap()
+        );
+
+        let original_change_future = VestingInstruction::ChangeFutureDestination {
+            seeds: [50u8; 32],
+            cutoff: 1_700_000_000,
+        };
+        assert_eq!(
+            original_change_future,
+            VestingInstruction::unpack(&original_change_future.pack()).unwrap()
+        );
+
+        let original_rollover = VestingInstruction::SetRollover {
+            seeds: [50u8; 32],
+            enabled: true,
+        };
+        assert_eq!(
+            original_rollover,
+            VestingInstruction::unpack(&original_rollover.pack()).unwrap()
+        );
+
+        let original_min_claim = VestingInstruction::SetMinClaimAmount {
+            seeds: [50u8; 32],
+            min_claim_amount: 1000,
+        };
+        assert_eq!(
+            original_min_claim,
+            VestingInstruction::unpack(&original_min_claim.pack()).unwrap()
+        );
+
+        let original_init_config = VestingInstruction::InitConfig {
+            admin: Pubkey::new_unique(),
+            early_claim_tolerance_seconds: 30,
+        };
+        assert_eq!(
+            original_init_config,
+            VestingInstruction::unpack(&original_init_config.pack()).unwrap()
+        );
+
+        let original_tolerance = VestingInstruction::SetEarlyClaimTolerance {
+            early_claim_tolerance_seconds: 30,
+        };
+        assert_eq!(
+            original_tolerance,
+            VestingInstruction::unpack(&original_tolerance.pack()).unwrap()
+        );
+
+        let original_withholding = VestingInstruction::SetWithholding {
+            seeds: [50u8; 32],
+            withholding_bps: 2_500,
+        };
+        let packed_withholding = original_withholding.pack();
+        assert_eq!(packed_withholding.len(), 35);
+        assert_eq!(
+            original_withholding,
+            VestingInstruction::unpack(&packed_withholding).unwrap()
+        );
+
+        let original_treasury = VestingInstruction::SetCommunityTreasury {
+            community_treasury: Pubkey::new_unique(),
+        };
+        assert_eq!(
+            original_treasury,
+            VestingInstruction::unpack(&original_treasury.pack()).unwrap()
+        );
+
+        for dust_policy in [
+            DustPolicy::ReturnToGranter,
+            DustPolicy::CommunityTreasury,
+            DustPolicy::Burn,
+        ] {
+            let original_close = VestingInstruction::Close {
+                seeds: [50u8; 32],
+                dust_policy,
+            };
+            assert_eq!(
+                original_close,
+                VestingInstruction::unpack(&original_close.pack()).unwrap()
+            );
+        }
+        let mut unknown_policy = VestingInstruction::Close {
+            seeds: [50u8; 32],
+            dust_policy: DustPolicy::Burn,
+        }
+        .pack();
+        unknown_policy[33] = 3;
+        assert!(VestingInstruction::unpack(&unknown_policy).is_err());
+
+        let original_transfer_limit = VestingInstruction::SetTransferLimit {
+            seeds: [50u8; 32],
+            max_transfer_per_unlock: 1_000,
+            unlock_interval: 3_600,
+        };
+        assert_eq!(
+            original_transfer_limit,
+            VestingInstruction::unpack(&original_transfer_limit.pack()).unwrap()
+        );
+
+        let original_init_approvers = VestingInstruction::InitDestinationApprovers {
+            seeds: [50u8; 32],
+            threshold: 2,
+            approvers: vec![
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ],
+        };
+        let packed_init_approvers = original_init_approvers.pack();
+        assert_eq!(packed_init_approvers.len(), 1 + 33 + 3 * 32);
+        assert_eq!(
+            original_init_approvers,
+            VestingInstruction::unpack(&packed_init_approvers).unwrap()
+        );
+        assert!(VestingInstruction::unpack(
+            &packed_init_approvers[..packed_init_approvers.len() - 1]
+        )
+        .is_err());
+
+        let original_approve = VestingInstruction::ApproveDestinationChange {
+            seeds: [50u8; 32],
+            new_destination: Pubkey::new_unique(),
+        };
+        assert_eq!(
+            original_approve,
+            VestingInstruction::unpack(&original_approve.pack()).unwrap()
+        );
+
+        assert_eq!(VestingInstruction::MigrateLayout.pack(), vec![27]);
+        let original_compact = VestingInstruction::Compact { seeds: [50u8; 32] };
+        assert_eq!(
+            original_compact,
+            VestingInstruction::unpack(&original_compact.pack()).unwrap()
+        );
+        assert_eq!(
+            VestingInstruction::MigrateLayout,
+            VestingInstruction::unpack(&[27]).unwrap()
+        );
+        let original_forbid = VestingInstruction::SetForbidSelfDealing {
+            forbid_self_dealing: true,
+        };
+        assert_eq!(original_forbid.pack(), vec![29, 1]);
+        assert_eq!(
+            original_forbid,
+            VestingInstruction::unpack(&original_forbid.pack()).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&[29, 2]).is_err());
+        let original_revoke = VestingInstruction::Revoke {
+            seeds: [50u8; 32],
+            expected_revision: None,
+        };
+        assert_eq!(
+            original_revoke,
+            VestingInstruction::unpack(&original_revoke.pack()).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&original_revoke.pack()[..32]).is_err());
+        let checked_revoke = VestingInstruction::Revoke {
+            seeds: [50u8; 32],
+            expected_revision: Some(u32::MAX),
+        };
+        assert_eq!(
+            checked_revoke,
+            VestingInstruction::unpack(&checked_revoke.pack()).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&checked_revoke.pack()[..35]).is_err());
+        let original_unlock_grant = VestingInstruction::UnlockGrant {
+            granter: Pubkey::new_unique(),
+            beneficiary: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            nonce: 3,
+        };
+        assert_eq!(
+            original_unlock_grant,
+            VestingInstruction::unpack(&original_unlock_grant.pack()).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&original_unlock_grant.pack()[..104]).is_err());
+        let original_create_from_mint = VestingInstruction::CreateFromMint {
+            seeds: [50u8; 32],
+            token_mint_addr: Pubkey::new_unique(),
+            token_dest_addr: Pubkey::new_unique(),
+            allow_immediate: true,
+            max_supply: 1_000_000,
+            schedules: vec![
+                Schedule {
+                    release_time: 1,
+                    amount: 10,
+                },
+                Schedule {
+                    release_time: 2,
+                    amount: 20,
+                },
+            ],
+        };
+        let packed = original_create_from_mint.pack();
+        assert_eq!(packed.len(), 1 + 105 + 2 * SCHEDULE_SIZE);
+        assert_eq!(
+            original_create_from_mint,
+            VestingInstruction::unpack(&packed).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&packed[..packed.len() - 1]).is_err());
+
+        let original_create_linear = VestingInstruction::CreateLinear {
+            seeds: [50u8; 32],
+            token_mint_addr: Pubkey::new_unique(),
+            token_dest_addr: Pubkey::new_unique(),
+            allow_immediate: false,
+            cliff_time: 100,
+            cliff_amount: 250,
+            start_time: 100,
+            end_time: 200,
+            total_amount: 750,
+        };
+        let packed = original_create_linear.pack();
+        assert_eq!(packed.len(), 1 + 137);
+        assert_eq!(
+            original_create_linear,
+            VestingInstruction::unpack(&packed).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&packed[..packed.len() - 1]).is_err());
+
+        let original_top_up = VestingInstruction::TopUp {
+            seeds: [50u8; 32],
+            allow_immediate: true,
+            schedules: vec![Schedule {
+                release_time: 3,
+                amount: 30,
+            }],
+        };
+        let packed = original_top_up.pack();
+        assert_eq!(packed.len(), 1 + 33 + SCHEDULE_SIZE);
+        assert_eq!(
+            original_top_up,
+            VestingInstruction::unpack(&packed).unwrap()
+        );
+        assert!(VestingInstruction::unpack(&packed[..packed.len() - 1]).is_err());
+
+        let original_empty = VestingInstruction::Empty { number: 5 };
+        assert_eq!(
+            original_empty,
+            VestingInstruction::unpack(&original_empty.pack()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_packed_len_and_pack_into() {
+        let create = VestingInstruction::Create {
+            seeds: [50u8; 32],
+            token_mint_addr: Pubkey::new_unique(),
+            token_dest_addr: Pubkey::new_unique(),
+            allow_immediate: false,
+            gas_rebate: 0,
+            gas_deposit: 0,
+            min_claim_amount: 0,
+            destination_locked: false,
+            forbid_self_dealing: false,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            relative: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            schedules: (0..40)
+                .map(|i| Schedule {
+                    release_time: i,
+                    amount: 100,
+                })
+                .collect(),
+            categories: vec![ScheduleCategory::Salary; 40],
+        };
+        let packed = create.pack();
+        assert_eq!(create.packed_len(), packed.len());
+        // no reallocations along the way
+        assert_eq!(packed.capacity(), packed.len());
+
+        let mut dst = [0xff; 1232];
+        assert_eq!(create.pack_into(&mut dst), Ok(packed.len()));
+        assert_eq!(&dst[..packed.len()], &packed[..]);
+        // nothing past what was written
+        assert!(dst[packed.len()..].iter().all(|b| *b == 0xff));
+
+        assert_eq!(
+            create.pack_into(&mut dst[..packed.len() - 1]),
+            Err(ProgramError::AccountDataTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_unpack_rejects_non_canonical_lengths() {
+        let create = VestingInstruction::Create {
+            seeds: [50u8; 32],
+            token_mint_addr: Pubkey::new_unique(),
+            token_dest_addr: Pubkey::new_unique(),
+            allow_immediate: false,
+            gas_rebate: 0,
+            gas_deposit: 0,
+            min_claim_amount: 0,
+            destination_locked: false,
+            forbid_self_dealing: false,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            relative: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            schedules: vec![Schedule {
+                amount: 42,
+                release_time: 250,
+            }],
+            categories: vec![],
+        };
+        let categorized_create = VestingInstruction::Create {
+            seeds: [50u8; 32],
+            token_mint_addr: Pubkey::new_unique(),
+            token_dest_addr: Pubkey::new_unique(),
+            allow_immediate: false,
+            gas_rebate: 0,
+            gas_deposit: 0,
+            min_claim_amount: 0,
+            destination_locked: false,
+            forbid_self_dealing: false,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            relative: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            schedules: vec![Schedule {
+                amount: 42,
+                release_time: 250,
+            }],
+            categories: vec![ScheduleCategory::Bonus],
+        };
+        let claim_compressed = VestingInstruction::ClaimCompressed {
+            seeds: [50u8; 32],
+            root: [7u8; 32],
+            index: 3,
+            release_time: 250,
+            amount: 42,
+        };
+        let fixtures = vec![
+            VestingInstruction::Init {
+                number_of_schedules: 1,
+                seeds: [50u8; 32],
+                schedule_layout: ScheduleLayout::Plain,
+            },
+            create.clone(),
+            categorized_create.clone(),
+            VestingInstruction::Unlock {
+                seeds: [50u8; 32],
+                max_schedules_per_call: 0,
+                max_amount: 0,
+                start_index: None,
+            },
+            VestingInstruction::Unlock {
+                seeds: [50u8; 32],
+                max_schedules_per_call: 64,
+                max_amount: 0,
+                start_index: None,
+            },
+            VestingInstruction::Unlock {
+                seeds: [50u8; 32],
+                max_schedules_per_call: 0,
+                max_amount: 1_000,
+                start_index: None,
+            },
+            VestingInstruction::Unlock {
+                seeds: [50u8; 32],
+                max_schedules_per_call: 64,
+                max_amount: 0,
+                start_index: Some(128),
+            },
+            VestingInstruction::ChangeDestination {
+                seeds: [50u8; 32],
+                expected_revision: None,
+            },
+            VestingInstruction::Empty { number: 5 },
+            VestingInstruction::CreateCompressed {
+                seeds: [50u8; 32],
+                token_mint_addr: Pubkey::new_unique(),
+                max_depth: 3,
+                max_buffer_size: 8,
+                total_amount: 42,
+            },
+            VestingInstruction::AppendCompressed {
+                seeds: [50u8; 32],
+                leaves: vec![[1u8; 32]],
+            },
+            claim_compressed,
+            VestingInstruction::RevokeBatch {
+                seeds: vec![[50u8; 32]],
+            },
+            VestingInstruction::ExportContract {
+                seeds: [50u8; 32],
+                new_program_id: Pubkey::new_unique(),
+            },
+            VestingInstruction::ImportContract { seeds: [50u8; 32] },
+            VestingInstruction::InitChild {
+                base_seed: [50u8; 32],
+                index: 7,
+                number_of_schedules: 12,
+            },
+            VestingInstruction::TopUpGas {
+                seeds: [50u8; 32],
+                amount: 10_000,
+            },
+        ];
+
+        for fixture in fixtures {
+            let packed = fixture.pack();
+            // one trailing byte, and a trailing byte short of one more schedule / leaf / seed
+            let mut extra_byte = packed.clone();
+            extra_byte.push(0);
+            assert!(
+                VestingInstruction::unpack(&extra_byte).is_err(),
+                "{:?}",
+                fixture
+            );
+            let mut partial_item = packed.clone();
+            partial_item.extend_from_slice(&[0; 15]);
+            assert!(
+                VestingInstruction::unpack(&partial_item).is_err(),
+                "{:?}",
+                fixture
+            );
+            // and one byte short
+            assert!(
+                VestingInstruction::unpack(&packed[..packed.len() - 1]).is_err(),
+                "{:?}",
+                fixture
+            );
+        }
+
+        // RevokeBatch needs at least one contract
+        assert!(VestingInstruction::unpack(&[7]).is_err());
+
+        // Unlock leaves off max_schedules_per_call when it's 0 and nothing follows it
+        let mut unbounded_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 0,
+            max_amount: 0,
+            start_index: None,
+        }
+        .pack();
+        unbounded_unlock.extend_from_slice(&0u32.to_le_bytes());
+        assert!(VestingInstruction::unpack(&unbounded_unlock).is_err());
+        // and max_amount when it's 0
+        let mut uncapped_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 64,
+            max_amount: 0,
+            start_index: None,
+        }
+        .pack();
+        uncapped_unlock.extend_from_slice(&0u64.to_le_bytes());
+        assert!(VestingInstruction::unpack(&uncapped_unlock).is_err());
+
+        // Plain has a single encoding, categorized contracts have at least one schedule, and categories are known ones
+        let mut init = VestingInstruction::Init {
+            number_of_schedules: 1,
+            seeds: [50u8; 32],
+            schedule_layout: ScheduleLayout::Plain,
+        }
+        .pack();
+        init.push(ScheduleLayout::Plain as u8);
+        assert!(VestingInstruction::unpack(&init).is_err());
+        let mut empty_categorized = create.pack();
+        empty_categorized[0] = 18;
+        empty_categorized.truncate(1 + 158);
+        assert!(VestingInstruction::unpack(&empty_categorized).is_err());
+        let mut unknown_category = categorized_create.pack();
+        *unknown_category.last_mut().unwrap() = 42;
+        assert!(VestingInstruction::unpack(&unknown_category).is_err());
+    }
+
+    #[test]
+    fn test_unlock_has_one_encoding() {
+        // every combination of the optional fields, 0 or not, at every length Unlock takes: whatever decodes packs
+        // back to the same bytes
+        let mut decoded = vec![];
+        for max_amount in [None, Some(0u64), Some(1_000)] {
+            for start_index in [None, Some(0u32), Some(128)] {
+                for max_schedules_per_call in [0u32, 64] {
+                    let mut input = vec![2];
+                    input.extend_from_slice(&[50u8; 32]);
+                    input.extend_from_slice(&max_schedules_per_call.to_le_bytes());
+                    if let Some(max_amount) = max_amount {
+                        input.extend_from_slice(&max_amount.to_le_bytes());
+                    }
+                    if let Some(start_index) = start_index {
+                        input.extend_from_slice(&start_index.to_le_bytes());
+                    }
+                    if let Ok(unlock) = VestingInstruction::unpack(&input) {
+                        assert_eq!(unlock.pack(), input, "{:?}", unlock);
+                        decoded.push(unlock);
+                    }
+                }
+            }
+        }
+        // only a max_amount of 0 and an unbounded 36 byte Unlock are turned down
+        assert_eq!(decoded.len(), 3 * 3 * 2 - 6 - 1);
+
+        // the start_index forms with a max_amount of 0 are the ones without it
+        let range_unlock = VestingInstruction::Unlock {
+            seeds: [50u8; 32],
+            max_schedules_per_call: 64,
+            max_amount: 0,
+            start_index: Some(128),
+        }
+        .pack();
+        assert_eq!(range_unlock.len(), 1 + 40);
+        let mut zero_capped = range_unlock[..1 + 36].to_vec();
+        zero_capped.extend_from_slice(&0u64.to_le_bytes());
+        zero_capped.extend_from_slice(&range_unlock[1 + 36..]);
+        assert_eq!(
+            VestingInstruction::unpack(&zero_capped),
+            Err(InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn test_destination_index_builders() {
+        let program_id = Pubkey::new_unique();
+        let system_program_id = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let new_destination = Pubkey::new_unique();
+
+        let create_ix = create(
+            &program_id,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &destination,
+            &mint,
+            vec![],
+            CreateOptions::default(),
+            [1; 32],
+        )
+        .unwrap();
+        let create_ix = with_destination_index(
+            &program_id,
+            &system_program_id,
+            create_ix,
+            &mint,
+            &destination,
+        );
+        assert!(create_ix.accounts[3].is_writable);
+        assert_eq!(create_ix.accounts[7].pubkey, system_program_id);
+        assert_eq!(
+            create_ix.accounts[8].pubkey,
+            find_destination_index(&program_id, &mint, &destination).0
+        );
+
+        let change_ix = change_destination(
+            &program_id,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &destination,
+            &new_destination,
+            [1; 32],
+        )
+        .unwrap();
+        let change_ix =
+            with_moved_destination_index(&program_id, &system_program_id, change_ix, &mint);
+        assert!(change_ix.accounts[2].is_writable);
+        assert_eq!(
+            change_ix
+                .accounts
+                .iter()
+                .skip(4)
+                .map(|meta| meta.pubkey)
+                .collect::<Vec<_>>(),
+            vec![
+                system_program_id,
+                find_destination_index(&program_id, &mint, &destination).0,
+                find_destination_index(&program_id, &mint, &new_destination).0,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emission_curve_builders() {
+        let program_id = Pubkey::new_unique();
+        let system_program_id = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (curve_key, _) = find_emission_curve(&program_id, &mint);
+
+        let create_ix = create(
+            &program_id,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &mint,
+            vec![],
+            CreateOptions::default(),
+            [1; 32],
+        )
+        .unwrap();
+        let create_ix = with_new_emission_curve(&program_id, &system_program_id, create_ix, &mint);
+        assert!(create_ix.accounts[3].is_writable);
+        assert_eq!(create_ix.accounts[7].pubkey, system_program_id);
+        assert_eq!(create_ix.accounts[8].pubkey, curve_key);
+        assert!(create_ix.accounts[8].is_writable);
+
+        let read_ix = read_emissions(&program_id, &Pubkey::new_unique(), &mint, 4).unwrap();
+        assert_eq!(read_ix.accounts[1].pubkey, curve_key);
+        assert!(!read_ix.accounts[1].is_writable);
+
+        let data = pack_emissions(2_800, &[1, 0, u64::MAX]);
+        assert_eq!(data.len(), 8 * 4);
+        assert_eq!(unpack_emissions(&data), Some((2_800, vec![1, 0, u64::MAX])));
+        assert_eq!(unpack_emissions(&data[..8]), Some((2_800, vec![])));
+        assert_eq!(unpack_emissions(&data[1..]), None);
+        assert_eq!(unpack_emissions(&[]), None);
+        assert_eq!(
+            pack_emissions(0, &[0; MAX_READ_EMISSION_WEEKS as usize]).len(),
+            MAX_RETURN_DATA
+        );
+    }
+
+    #[test]
+    fn test_builders_follow_account_specs() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let schedules = vec![Schedule {
+            release_time: 1,
+            amount: 1,
+        }];
+
+        let create_ix = create(
+            &program_id,
+            &key,
+            &key,
+            &key,
+            &key,
+            &key,
+            &destination,
+            &mint,
+            schedules.clone(),
+            CreateOptions::default(),
+            [1; 32],
+        )
+        .unwrap();
+        assert_eq!(create_ix.accounts[5].pubkey, mint);
+        assert_eq!(create_ix.accounts[6].pubkey, destination);
+
+        let instructions = vec![
+            init(&key, &key, &program_id, &key, &key, [1; 32], 1).unwrap(),
+            create_ix,
+            create(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &destination,
+                &mint,
+                schedules.clone(),
+                CreateOptions {
+                    relative: true,
+                    ..CreateOptions::default()
+                },
+                [1; 32],
+            )
+            .unwrap(),
+            create(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &destination,
+                &mint,
+                schedules.clone(),
+                CreateOptions {
+                    revocable: false,
+                    ..CreateOptions::default()
+                },
+                [1; 32],
+            )
+            .unwrap(),
+            create(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &destination,
+                &mint,
+                schedules.clone(),
+                CreateOptions {
+                    emergency_authority: key,
+                    ..CreateOptions::default()
+                },
+                [1; 32],
+            )
+            .unwrap(),
+            create_from_mint(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &destination,
+                &mint,
+                schedules.clone(),
+                CreateOptions::default(),
+                [1; 32],
+            )
+            .unwrap(),
+            create(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &destination,
+                &mint,
+                schedules,
+                CreateOptions {
+                    gas_rebate: 1,
+                    gas_deposit: 1,
+                    ..CreateOptions::default()
+                },
+                [1; 32],
+            )
+            .unwrap(),
+            unlock_with_rebate(&program_id, &key, &key, &key, &key, &key, &key, [1; 32]).unwrap(),
+            unlock_partial(&program_id, &key, &key, &key, &key, &key, &key, 1, [1; 32]).unwrap(),
+            unlock_range(&program_id, &key, &key, &key, &key, &key, 2, 1, [1; 32]).unwrap(),
+            accelerate(&program_id, &key, &key, &key, [1; 32]).unwrap(),
+            change_granter(&program_id, &key, &key, &key, [1; 32]).unwrap(),
+            activate(&program_id, &key, &key, &key, [1; 32]).unwrap(),
+            pause(&program_id, &key, &key, [1; 32]).unwrap(),
+            resume(&program_id, &key, &key, [1; 32]).unwrap(),
+            emergency_withdraw(&program_id, &key, &key, &key, &key, &key, [1; 32]).unwrap(),
+            unlock_with_permit(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                Some(&key),
+                0,
+                [1; 32],
+            )
+            .unwrap(),
+            change_destination(&program_id, &key, &key, &key, &key, [1; 32]).unwrap(),
+            set_withholding(&program_id, &key, &key, &key, &key, &key, 1, [1; 32]).unwrap(),
+            close(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                DustPolicy::CommunityTreasury,
+                [1; 32],
+            )
+            .unwrap(),
+            revoke(&program_id, &key, &key, &key, &key, &key, &key, [1; 32]).unwrap(),
+            read_emissions(&program_id, &key, &mint, 1).unwrap(),
+            merge(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &mint,
+                [1; 32],
+                [2; 32],
+            )
+            .unwrap(),
+            transfer_beneficiary(
+                &program_id,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &key,
+                &mint,
+                [1; 32],
+            )
+            .unwrap(),
+        ];
+        #[cfg(feature = "devnet-tools")]
+        let instructions = [
+            instructions,
+            vec![debug_shift_schedules(&program_id, &key, &key, 60, [1; 32]).unwrap()],
+        ]
+        .concat();
+        for ix in instructions {
+            let specs = VestingInstruction::unpack(&ix.data)
+                .unwrap()
+                .account_specs();
+            assert!(ix.accounts.len() <= specs.len());
+            for (meta, spec) in ix.accounts.iter().zip(specs) {
+                // builders only ever ask for more than the spec, eg a writable payer
+                assert!(meta.is_signer >= spec.signer, "{}", spec.name);
+                assert!(meta.is_writable >= spec.writable, "{}", spec.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_transfer_beneficiary_derives_associated_token_account() {
+        let program_id = Pubkey::new_unique();
+        let (new_owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = transfer_beneficiary(
+            &program_id,
+            &spl_token::id(),
+            &solana_program::system_program::id(),
+            &spl_associated_token_account::id(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &new_owner,
+            &mint,
+            [1; 32],
+        )
+        .unwrap();
+        let new_destination =
+            spl_associated_token_account::get_associated_token_address(&new_owner, &mint);
+        assert_eq!(ix.accounts[3].pubkey, new_destination);
+        assert_eq!(
+            ix.accounts[10].pubkey,
+            find_destination_index(&program_id, &mint, &new_destination).0
+        );
+    }
+
+    #[test]
+    fn test_transfer_beneficiary_paid_by() {
+        let program_id = Pubkey::new_unique();
+        let cranker = Pubkey::new_unique();
+        let transfer_beneficiary = |rent_payer| {
+            transfer_beneficiary_paid_by(
+                &program_id,
+                &spl_token::id(),
+                &solana_program::system_program::id(),
+                &spl_associated_token_account::id(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                [1; 32],
+                rent_payer,
+                &cranker,
+            )
+            .unwrap()
+        };
+
+        let ix = transfer_beneficiary(RentPayer::Cranker);
+        assert_eq!(ix.accounts.len(), 12);
+        assert_eq!(ix.accounts[11], AccountMeta::new(cranker, true));
+        assert_eq!(
+            VestingInstruction::unpack(&ix.data).unwrap(),
+            VestingInstruction::TransferBeneficiary {
+                seeds: [1; 32],
+                rent_payer: RentPayer::Cranker,
+            }
+        );
+
+        // the rent payer stays last
+        let indexed_contract = Pubkey::new_unique();
+        let ix = replacing_indexed_contract(ix, &indexed_contract);
+        assert_eq!(
+            ix.accounts[11],
+            AccountMeta::new_readonly(indexed_contract, false)
+        );
+        assert_eq!(ix.accounts[12], AccountMeta::new(cranker, true));
+
+        // the current owner pays, as with transfer_beneficiary
+        let ix = transfer_beneficiary(RentPayer::Beneficiary);
+        assert_eq!(ix.accounts.len(), 11);
+        assert_eq!(ix.data.len(), 33);
+    }
+
+    #[test]
+    fn test_required_lamports_for() {
+        let rent = Rent::default();
+        assert_eq!(
+            required_lamports_for(2, ScheduleLayout::Plain, &rent),
+            rent.minimum_balance(VestingScheduleHeader::LEN + 2 * SCHEDULE_SIZE)
+        );
+        assert!(
+            required_lamports_for(2, ScheduleLayout::Categorized, &rent)
+                > required_lamports_for(2, ScheduleLayout::Plain, &rent)
+        );
+        // the header alone costs rent too
+        assert!(required_lamports_for(0, ScheduleLayout::Plain, &rent) > 0);
+    }
+
+    #[test]
+    fn test_child_seeds() {
+        let program_id = Pubkey::new_unique();
+        let base_seed = [9u8; 32];
+
+        let (key, seeds) = find_child_vesting_account(&program_id, &base_seed, 0);
+        assert_eq!(seeds, child_seeds(&program_id, &base_seed, 0));
+        assert_eq!(
+            Pubkey::create_program_address(&[&seeds], &program_id).unwrap(),
+            key
+        );
+
+        // every month gets its own address, and other base seeds don't collide
+        let (next_key, _) = find_child_vesting_account(&program_id, &base_seed, 1);
+        let (other_key, _) = find_child_vesting_account(&program_id, &[8u8; 32], 0);
+        assert_ne!(key, next_key);
+        assert_ne!(key, other_key);
+    }
+
+    #[test]
+    fn test_vesting_seeds() {
+        let program_id = Pubkey::new_unique();
+        let (granter, beneficiary, mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let (key, seeds) = find_vesting_address(&program_id, &granter, &beneficiary, &mint, 0);
+        assert_eq!(seeds, vesting_seeds(&granter, &beneficiary, &mint, 0));
+        let (_, bump) = pda::find_granted_vesting_address(&seeds, &program_id);
+        assert_eq!(
+            pda::VestingSigner::new(seeds, bump)
+                .granted()
+                .key(&program_id),
+            Ok(key)
+        );
+        // Init-ed under the same seeds, an account isn't where the parties look
+        assert_ne!(
+            pda::find_vesting_address_for_seeds(&seeds, &program_id).0,
+            key
+        );
+        // another granter for the same beneficiary, another grant, or the parties swapped around
+        for (other_key, _) in [
+            find_vesting_address(&program_id, &Pubkey::new_unique(), &beneficiary, &mint, 0),
+            find_vesting_address(&program_id, &granter, &beneficiary, &mint, 1),
+            find_vesting_address(&program_id, &beneficiary, &granter, &mint, 0),
+        ] {
+            assert_ne!(key, other_key);
+        }
+
+        // InitGrant and UnlockGrant both land on it
+        let ix = init_grant(
+            &solana_program::system_program::id(),
+            &solana_program::sysvar::rent::id(),
+            &program_id,
+            &granter,
+            &beneficiary,
+            &mint,
+            0,
+            1,
+        )
+        .unwrap();
+        assert_eq!(ix.accounts[3].pubkey, key);
+        let ix = unlock_grant(
+            &program_id,
+            &spl_token::id(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &granter,
+            &beneficiary,
+            &mint,
+            0,
+        )
+        .unwrap();
+        assert_eq!(ix.accounts[2].pubkey, key);
+    }
+}
@@ -0,0 +1,26 @@
+extern crate alloc;
+
+// a build with DebugShiftSchedules lets granters move release times at will, it must never be the one on mainnet
+#[cfg(all(feature = "mainnet", feature = "devnet-tools"))]
+compile_error!("devnet-tools can't be built with the default features, pass --no-default-features");
+
+#[cfg(not(feature = "no-entrypoint"))]
+pub mod entrypoint;
+
+#[cfg(feature = "anchor")]
+pub mod anchor;
+#[cfg(feature = "bonfida-compat")]
+pub mod bonfida;
+pub mod compression;
+pub mod core;
+pub mod error;
+pub mod ids;
+pub mod instruction;
+pub mod math;
+#[cfg(any(test, feature = "paranoid"))]
+mod paranoid;
+pub mod pda;
+pub mod processor;
+pub mod state;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
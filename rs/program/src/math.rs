@@ -0,0 +1,111 @@
+//! Arithmetic over a contract's schedules that doesn't care how they're stored. The parts that don't need
+//! solana-program live in `core`, and are re-exported from here.
+
+use solana_program::program_error::ProgramError;
+
+pub use crate::core::{
+    final_schedule_index, linear_claimable, linear_released, pro_rata_claim,
+    rolled_next_release_time, truncate_linear,
+};
+use crate::state::{SplitDestination, VestingSchedule, MAX_SPLIT_BPS};
+
+/// Moves the amount of every schedule that has matured at `now`, other than the final one, into the final one.
+/// Returns the amount moved
+pub fn roll_matured_into_final(
+    schedules: &mut [VestingSchedule],
+    now: u64,
+) -> Result<u64, ProgramError> {
+    let final_index = match final_schedule_index(schedules) {
+        Some(i) => i,
+        None => return Ok(0),
+    };
+
+    let mut rolled: u64 = 0;
+    for (i, s) in schedules.iter_mut().enumerate() {
+        if i != final_index && now >= s.release_time {
+            rolled = rolled
+                .checked_add(s.amount)
+                .ok_or(ProgramError::InvalidAccountData)?;
+            s.amount = 0;
+        }
+    }
+
+    let final_schedule = &mut schedules[final_index];
+    final_schedule.amount = final_schedule
+        .amount
+        .checked_add(rolled)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(rolled)
+}
+
+/// What each split takes of `amount`, rounded down. Splits adding up to at most MAX_SPLIT_BPS never take more than
+/// `amount` between them, the destination keeps the rest
+pub fn split_amounts(amount: u64, splits: &[SplitDestination]) -> Vec<u64> {
+    splits
+        .iter()
+        .map(|s| (amount as u128 * s.bps as u128 / MAX_SPLIT_BPS as u128) as u64)
+        .collect()
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(release_time: u64, amount: u64) -> VestingSchedule {
+        VestingSchedule {
+            release_time,
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_roll_matured_into_final() {
+        let mut schedules = vec![schedule(10, 100), schedule(20, 50), schedule(30, 25)];
+        assert_eq!(roll_matured_into_final(&mut schedules, 15).unwrap(), 100);
+        assert_eq!(
+            schedules,
+            vec![schedule(10, 0), schedule(20, 50), schedule(30, 125)]
+        );
+
+        // rolling twice doesn't move anything twice
+        assert_eq!(roll_matured_into_final(&mut schedules, 15).unwrap(), 0);
+
+        // the final schedule itself stays put once it has matured
+        assert_eq!(roll_matured_into_final(&mut schedules, 30).unwrap(), 50);
+        assert_eq!(
+            schedules,
+            vec![schedule(10, 0), schedule(20, 0), schedule(30, 175)]
+        );
+    }
+
+    #[test]
+    fn test_split_amounts() {
+        let split = |bps| SplitDestination {
+            destination_address: Default::default(),
+            bps,
+        };
+        // 70/20/10, the destination keeping its 70% and the dust
+        assert_eq!(
+            split_amounts(1_000, &[split(2_000), split(1_000)]),
+            vec![200, 100]
+        );
+        assert_eq!(
+            split_amounts(999, &[split(2_000), split(1_000)]),
+            vec![199, 99]
+        );
+        assert_eq!(
+            split_amounts(u64::MAX, &[split(MAX_SPLIT_BPS)]),
+            vec![u64::MAX]
+        );
+        assert_eq!(split_amounts(1, &[split(5_000), split(5_000)]), vec![0, 0]);
+        assert!(split_amounts(1_000, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_roll_matured_into_final_overflow() {
+        let mut schedules = vec![schedule(10, u64::MAX), schedule(20, 1)];
+        assert!(roll_matured_into_final(&mut schedules, 15).is_err());
+    }
+}
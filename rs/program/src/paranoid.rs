@@ -0,0 +1,111 @@
+//! Double checks the token CPIs (paranoid feature). The balances a CPI is about to move are read before it, and
+//! afterwards each of them has to have changed by exactly what the handler meant to move - so a CPI wired to the
+//! wrong account or amount by some future change aborts the instruction instead of going through.
+//!
+//! Meant for testnet deployments and the fuzz build: every token account checked costs an extra unpack.
+
+use solana_program::{account_info::AccountInfo, msg};
+use spl_token_2022::{extension::StateWithExtensions, state::Account};
+
+use crate::error::VestingError;
+
+/// Balances read before a CPI, next to the change each of them is expected to see
+pub struct TokenAudit<'a, 'b> {
+    expected: Vec<(&'a AccountInfo<'b>, u64, i128)>,
+}
+
+impl<'a, 'b> TokenAudit<'a, 'b> {
+    /// An account listed more than once is expected to see the sum of its deltas. Accounts that can't be read as
+    /// token accounts are left out, the CPI fails on them anyway
+    pub fn before(deltas: &[(&'a AccountInfo<'b>, i128)]) -> Self {
+        let mut expected: Vec<(&'a AccountInfo<'b>, u64, i128)> = Vec::with_capacity(deltas.len());
+        for (account, delta) in deltas.iter() {
+            if let Some(entry) = expected.iter_mut().find(|(a, _, _)| a.key == account.key) {
+                entry.2 += delta;
+                continue;
+            }
+            if let Some(balance) = balance(account) {
+                expected.push((account, balance, *delta));
+            }
+        }
+        Self { expected }
+    }
+
+    pub fn check(&self) -> Result<(), VestingError> {
+        for (account, before, delta) in self.expected.iter() {
+            let after = balance(account);
+            if after.map(|after| after as i128 - *before as i128) != Some(*delta) {
+                msg!(
+                    "{:?} went from {:?} to {:?}, expected a change of {:?}",
+                    account.key,
+                    before,
+                    after,
+                    delta
+                );
+                return Err(VestingError::UnexpectedTokenDelta);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn balance(account: &AccountInfo) -> Option<u64> {
+    StateWithExtensions::<Account>::unpack(&account.data.borrow())
+        .ok()
+        .map(|a| a.base.amount)
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestAccount;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+
+    fn set_balance(account: &AccountInfo, amount: u64) {
+        let mut data = account.data.borrow_mut();
+        let mut token_account = Account::unpack(&data).unwrap();
+        token_account.amount = amount;
+        Account::pack(token_account, &mut data).unwrap();
+    }
+
+    #[test]
+    fn test_token_audit() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut source = TestAccount::token_account(Pubkey::new_unique(), mint, owner, 100);
+        let mut destination = TestAccount::token_account(Pubkey::new_unique(), mint, owner, 5);
+        let mut wallet = TestAccount::wallet(Pubkey::new_unique());
+        let source = source.account_info();
+        let destination = destination.account_info();
+        let wallet = wallet.account_info();
+
+        let audit = TokenAudit::before(&[(&source, -30), (&destination, 30), (&wallet, 1)]);
+        // nothing moved yet
+        assert_eq!(audit.check(), Err(VestingError::UnexpectedTokenDelta));
+
+        set_balance(&source, 70);
+        set_balance(&destination, 35);
+        assert_eq!(audit.check(), Ok(()));
+
+        // the right amount, to the wrong account
+        set_balance(&destination, 5);
+        assert_eq!(audit.check(), Err(VestingError::UnexpectedTokenDelta));
+    }
+
+    #[test]
+    fn test_token_audit_adds_up_deltas_of_the_same_account() {
+        let mut account = TestAccount::token_account(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            10,
+        );
+        let account = account.account_info();
+
+        // a transfer to itself
+        let audit = TokenAudit::before(&[(&account, -10), (&account, 10)]);
+        assert_eq!(audit.check(), Ok(()));
+    }
+}
@@ -0,0 +1,166 @@
+//! The addresses the program derives, all of them under its own id unless said otherwise:
+//!
+//! - vesting account: `[seeds, bump]`, 32 bytes of seeds that mean something on their own and the bump
+//!   find_program_address picks for them, which Init stores in the header - see find_vesting_address_for_seeds.
+//!   Legacy vesting accounts are at `[seeds]`, a single 32-byte seed whose last byte is the bump - see
+//!   vesting_address. Seeds from instruction::child_seeds have the legacy layout, with the prefix hashed from what
+//!   they're derived from
+//! - granted vesting account: `[VESTING_SEED_PREFIX, seeds, bump]`, for seeds from instruction::vesting_seeds, which
+//!   are hashed from the granter, the beneficiary, the mint and a nonce - see find_granted_vesting_address. Only
+//!   InitGrant creates accounts there, with the seeds hashed from its own signer, so no one but the granter can take
+//!   the address the parties point at
+//! - program config: `[CONFIG_SEED]`, see instruction::find_program_config
+//! - destination index: `[DESTINATION_INDEX_SEED, mint, destination spl-token account]`, see
+//!   instruction::find_destination_index
+//! - destination approvers: `[DESTINATION_APPROVERS_SEED, vesting account]`, see
+//!   instruction::find_destination_approvers
+//! - emission curve: `[EMISSION_CURVE_SEED, mint]`, see instruction::find_emission_curve
+//! - migration authority: `[MIGRATION_SEED, old vesting account]` under the new deployment's id, see
+//!   instruction::find_migration_authority
+//!
+//! Whichever the namespace, it's the 32 bytes of seeds that instructions carry. The processor reads the bump from the
+//! vesting account's header and checks the account with create_program_address, see VestingSigner.
+
+use solana_program::pubkey::{Pubkey, PubkeyError};
+
+use crate::{
+    instruction::{Seeds, VESTING_SEED_PREFIX},
+    state::{account_type, is_baseline_layout, AccountType, BUMP_OFFSET},
+};
+
+/// Header bump of a legacy vesting account, whose seeds end in their own bump. find_program_address never picks it
+pub const LEGACY_BUMP: u8 = 0;
+
+/// Vesting account address and bump for `seeds` as they are, none of their bytes given up to the bump. Not to be
+/// confused with instruction::find_vesting_address, which goes from the parties of an InitGrant to their seeds
+pub fn find_vesting_address_for_seeds(seeds: &Seeds, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds], program_id)
+}
+
+/// Granted vesting account address and bump for `seeds`, under VESTING_SEED_PREFIX
+pub fn find_granted_vesting_address(seeds: &Seeds, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VESTING_SEED_PREFIX, seeds], program_id)
+}
+
+/// A vesting account's seeds along with the bump from its header, which is what it's derived from and signs with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VestingSigner {
+    seeds: Seeds,
+    bump: [u8; 1],
+    granted: bool,
+}
+
+impl VestingSigner {
+    pub fn new(seeds: Seeds, bump: u8) -> Self {
+        Self {
+            seeds,
+            bump: [bump],
+            granted: false,
+        }
+    }
+
+    /// The same seeds and bump, for a granted vesting account
+    pub fn granted(self) -> Self {
+        Self {
+            granted: true,
+            ..self
+        }
+    }
+
+    pub fn is_granted(&self) -> bool {
+        self.granted
+    }
+
+    /// The signer for the vesting account holding `data`. Data too short to hold a header, as before Init, reads as
+    /// LEGACY_BUMP, and so do a baseline contract and a compressed one, which keep no bump
+    pub fn for_account(seeds: Seeds, data: &[u8]) -> Self {
+        if is_baseline_layout(data)
+            || account_type(data) == Some(AccountType::CompressedVestingTree)
+        {
+            return Self::new(seeds, LEGACY_BUMP);
+        }
+        Self::new(seeds, data.get(BUMP_OFFSET).copied().unwrap_or(LEGACY_BUMP))
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+
+    /// What invoke_signed takes for the vesting account: the seeds, then the bump unless it's a legacy one. A granted
+    /// account's go behind VESTING_SEED_PREFIX
+    pub fn signer_seeds(&self) -> Vec<&[u8]> {
+        if self.granted {
+            vec![VESTING_SEED_PREFIX, &self.seeds, &self.bump]
+        } else if self.bump() == LEGACY_BUMP {
+            vec![&self.seeds]
+        } else {
+            vec![&self.seeds, &self.bump]
+        }
+    }
+
+    pub fn key(&self, program_id: &Pubkey) -> Result<Pubkey, PubkeyError> {
+        Pubkey::create_program_address(&self.signer_seeds(), program_id)
+    }
+}
+
+/// Vesting account address and full seeds for a 31-byte `seed_prefix`, the bump going into the last byte
+pub fn vesting_address(seed_prefix: &[u8; 31], program_id: &Pubkey) -> (Pubkey, Seeds) {
+    let (key, bump) = Pubkey::find_program_address(&[seed_prefix], program_id);
+    let mut seeds = [0; 32];
+    seeds[..31].copy_from_slice(seed_prefix);
+    seeds[31] = bump;
+    (key, seeds)
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vesting_address() {
+        let program_id = Pubkey::new_unique();
+        let (key, seeds) = vesting_address(&[7; 31], &program_id);
+        assert_eq!(seeds[..31], [7; 31]);
+        assert_eq!(
+            Pubkey::create_program_address(&[&seeds], &program_id),
+            Ok(key)
+        );
+        assert_eq!(
+            VestingSigner::new(seeds, LEGACY_BUMP).key(&program_id),
+            Ok(key)
+        );
+    }
+
+    #[test]
+    fn test_find_vesting_address_for_seeds() {
+        let program_id = Pubkey::new_unique();
+        let (key, bump) = find_vesting_address_for_seeds(&[7; 32], &program_id);
+        assert_ne!(bump, LEGACY_BUMP);
+        let signer = VestingSigner::new([7; 32], bump);
+        assert_eq!(signer.key(&program_id), Ok(key));
+        assert_eq!(signer.signer_seeds(), vec![&[7; 32][..], &[bump][..]]);
+
+        // the bump comes from the header, and the account isn't there before Init
+        let mut data = vec![0; BUMP_OFFSET + 1];
+        data[BUMP_OFFSET] = bump;
+        assert_eq!(VestingSigner::for_account([7; 32], &data), signer);
+        assert_eq!(VestingSigner::for_account([7; 32], &[]).bump(), LEGACY_BUMP);
+    }
+
+    #[test]
+    fn test_find_granted_vesting_address() {
+        let program_id = Pubkey::new_unique();
+        let (key, bump) = find_granted_vesting_address(&[7; 32], &program_id);
+        let signer = VestingSigner::new([7; 32], bump).granted();
+        assert!(signer.is_granted());
+        assert_eq!(signer.key(&program_id), Ok(key));
+        assert_eq!(
+            signer.signer_seeds(),
+            vec![VESTING_SEED_PREFIX, &[7; 32][..], &[bump][..]]
+        );
+        // nothing Init takes the same seeds to lands there
+        assert_ne!(find_vesting_address_for_seeds(&[7; 32], &program_id).0, key);
+    }
+}
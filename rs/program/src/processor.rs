@@ -0,0 +1,11769 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::{Clock, Epoch},
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    log::sol_log_data,
+    msg,
+    program::{invoke, invoke_signed, set_return_data},
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{self, create_account},
+    system_program,
+    sysvar::{self, Sysvar},
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{
+            instruction::{harvest_withheld_tokens_to_mint, transfer_checked_with_fee},
+            TransferFeeConfig,
+        },
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction::{burn, close_account, mint_to},
+    state::{Account, Mint},
+};
+
+use crate::{
+    compression::{
+        append, hash_leaf, init_empty_merkle_tree, replace_leaf, spl_account_compression, spl_noop,
+        Node, EMPTY_LEAF, MAX_TREE_DEPTH,
+    },
+    error::{ErrorContext, VestingError, VestingResult},
+    ids,
+    instruction::{
+        child_seeds, claim_permit_message, find_destination_approvers, find_destination_index,
+        find_emission_curve, find_migration_authority, pack_emissions, vesting_seeds, AccountSpec,
+        CreateOptions, Schedule, Seeds, VestingInstruction, CONFIG_SEED,
+        DESTINATION_APPROVERS_SEED, DESTINATION_INDEX_SEED, ED25519_DATA_START,
+        ED25519_OFFSETS_START, ED25519_PUBKEY_SIZE, EMISSION_CURVE_SEED, MAX_READ_EMISSION_WEEKS,
+        MIGRATION_SEED, SCHEDULE_SIZE,
+    },
+    math::{
+        linear_claimable, pro_rata_claim, roll_matured_into_final, split_amounts, truncate_linear,
+    },
+    pda::{
+        find_granted_vesting_address, find_vesting_address_for_seeds, VestingSigner, LEGACY_BUMP,
+    },
+    state::{
+        account_type, bump_revision, compact_schedules, init_payer, is_baseline_layout,
+        next_release_time, pack_categories_into_slice, pack_schedules_into_slice,
+        unpack_categories, unpack_linear, unpack_schedules, upgrade_baseline_layout,
+        upgraded_baseline_len, write_destination, write_init_payer, write_state, AccountType,
+        CompressedVestingTree, ContractEvent, ContractState, DestinationApprovers,
+        DestinationIndex, DustPolicy, EmissionCurve, LinearVesting, ProgramConfig, RentPayer,
+        RevokePolicy, ScheduleCategory, ScheduleLayout, SplitDestination, UnlockPolicy,
+        VestingSchedule, VestingScheduleHeader, ACCELERATE_EVENT, ACCOUNT_MAGIC,
+        ACCOUNT_TYPE_OFFSET, BUMP_OFFSET, CONTRACT_STATE_OFFSET, EMISSION_CURVE_WEEKS,
+        HEADER_VERSION, HEADER_VERSION_OFFSET, MAX_DESTINATION_APPROVERS,
+        MAX_EARLY_CLAIM_TOLERANCE_SECONDS, MAX_SPLIT_BPS, MAX_SPLIT_DESTINATIONS,
+        MAX_WITHHOLDING_BPS, NO_PENDING_RELEASE, REVISION_EVENT, REVOKE_EVENT,
+    },
+};
+
+/// The Token-2022 extensions a vesting token account may carry. The others change whether a transfer out of it goes
+/// through at all - required memos, CPI guards, confidential balances. TransferFeeAmount comes with mints charging
+/// transfer fees, which every instruction moving the contract's tokens pays through TransferCheckedWithFee (see
+/// Processor::transfer_tokens)
+pub const VESTING_TOKEN_ACCOUNT_EXTENSIONS: [ExtensionType; 2] = [
+    ExtensionType::ImmutableOwner,
+    ExtensionType::TransferFeeAmount,
+];
+
+/// A Token-2022 mint charging transfer fees. Every transfer of its tokens has to name it, its decimals and the exact
+/// fee the transfer is charged in the current epoch
+struct FeeMint<'a, 'b> {
+    account: &'a AccountInfo<'b>,
+    decimals: u8,
+    transfer_fee_config: TransferFeeConfig,
+    epoch: Epoch,
+}
+
+impl FeeMint<'_, '_> {
+    /// What a transfer of `amount` is charged, and the recipient goes without
+    fn fee(&self, amount: u64) -> VestingResult<u64> {
+        self.transfer_fee_config
+            .calculate_epoch_fee(self.epoch, amount)
+            .ok_or(ProgramError::InvalidArgument)
+    }
+
+    /// The transfer that leaves the recipient exactly `net` after its fee
+    fn gross_up(&self, net: u64) -> VestingResult<u64> {
+        let transfer_fee = self.transfer_fee_config.get_epoch_fee(self.epoch);
+        transfer_fee
+            .calculate_pre_fee_amount(net)
+            .filter(|gross| transfer_fee.calculate_post_fee_amount(*gross) == Some(net))
+            .ok_or_else(|| {
+                msg!("no transfer leaves exactly {:?} after the mint's fee", net);
+                ProgramError::InvalidArgument
+            })
+    }
+}
+
+/// Where Create gets the tokens of a new contract from
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Funding {
+    /// Transferred out of the source spl-token account, signed by its owner
+    Transfer,
+    /// Minted into the vesting spl-token account, signed by the mint authority - see CreateFromMint
+    MintTo { max_supply: u64 },
+}
+
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        msg!("begin processing ix");
+        // decode the instruction from bytes
+        let instruction =
+            VestingInstruction::unpack(instruction_data).ctx("unpacking instruction")?;
+        #[cfg(feature = "debug-logs")]
+        let vesting_account_index = instruction.vesting_account_index();
+        Self::check_accounts(instruction.account_specs(), accounts)?;
+
+        // match the decoded instruction
+        let result = match instruction {
+            VestingInstruction::Empty { number } => {
+                msg!("it worked, number is {}", number);
+                return Ok(());
+            }
+            VestingInstruction::Init {
+                seeds,
+                number_of_schedules,
+                schedule_layout,
+            } => {
+                msg!("Instruction: Init");
+                Self::process_init(
+                    program_id,
+                    accounts,
+                    seeds,
+                    number_of_schedules,
+                    schedule_layout,
+                )
+            }
+            VestingInstruction::Create {
+                seeds,
+                token_mint_addr,
+                token_dest_addr,
+                allow_immediate,
+                gas_rebate,
+                gas_deposit,
+                min_claim_amount,
+                destination_locked,
+                forbid_self_dealing,
+                revoke_policy,
+                relative,
+                emergency_authority,
+                revocable,
+                schedules,
+                categories,
+            } => {
+                msg!("Instruction: Create");
+                Self::process_create(
+                    program_id,
+                    accounts,
+                    seeds,
+                    &token_mint_addr,
+                    &token_dest_addr,
+                    schedules,
+                    None,
+                    CreateOptions {
+                        allow_immediate,
+                        gas_rebate,
+                        gas_deposit,
+                        min_claim_amount,
+                        destination_locked,
+                        forbid_self_dealing,
+                        revoke_policy,
+                        relative,
+                        emergency_authority,
+                        revocable,
+                        categories,
+                        max_supply: None,
+                    },
+                )
+            }
+            VestingInstruction::CreateFromMint {
+                seeds,
+                token_mint_addr,
+                token_dest_addr,
+                allow_immediate,
+                max_supply,
+                schedules,
+            } => {
+                msg!("Instruction: Create From Mint");
+                Self::process_create(
+                    program_id,
+                    accounts,
+                    seeds,
+                    &token_mint_addr,
+                    &token_dest_addr,
+                    schedules,
+                    None,
+                    CreateOptions {
+                        allow_immediate,
+                        max_supply: Some(max_supply),
+                        ..CreateOptions::default()
+                    },
+                )
+            }
+            VestingInstruction::CreateLinear {
+                seeds,
+                token_mint_addr,
+                token_dest_addr,
+                allow_immediate,
+                cliff_time,
+                cliff_amount,
+                start_time,
+                end_time,
+                total_amount,
+            } => {
+                msg!("Instruction: Create Linear");
+                Self::process_create(
+                    program_id,
+                    accounts,
+                    seeds,
+                    &token_mint_addr,
+                    &token_dest_addr,
+                    vec![],
+                    Some(LinearVesting {
+                        cliff_time,
+                        cliff_amount,
+                        start_time,
+                        end_time,
+                        total_amount,
+                    }),
+                    CreateOptions {
+                        allow_immediate,
+                        ..CreateOptions::default()
+                    },
+                )
+            }
+            VestingInstruction::Unlock {
+                seeds,
+                max_schedules_per_call,
+                max_amount,
+                start_index,
+            } => {
+                msg!("Instruction: Unlock");
+                Self::process_unlock(
+                    program_id,
+                    accounts,
+                    seeds,
+                    None,
+                    max_schedules_per_call,
+                    max_amount,
+                    start_index,
+                )
+            }
+            VestingInstruction::ChangeDestination {
+                seeds,
+                expected_revision,
+            } => {
+                msg!("Instruction: Change Destination");
+                Self::process_change_destination(
+                    program_id,
+                    accounts,
+                    seeds,
+                    None,
+                    expected_revision,
+                )
+            }
+            VestingInstruction::CreateCompressed {
+                seeds,
+                token_mint_addr,
+                max_depth,
+                max_buffer_size,
+                total_amount,
+            } => {
+                msg!("Instruction: Create Compressed");
+                Self::process_create_compressed(
+                    program_id,
+                    accounts,
+                    seeds,
+                    &token_mint_addr,
+                    max_depth,
+                    max_buffer_size,
+                    total_amount,
+                )
+            }
+            VestingInstruction::AppendCompressed { seeds, leaves } => {
+                msg!("Instruction: Append Compressed");
+                Self::process_append_compressed(program_id, accounts, seeds, &leaves)
+            }
+            VestingInstruction::ClaimCompressed {
+                seeds,
+                root,
+                index,
+                release_time,
+                amount,
+            } => {
+                msg!("Instruction: Claim Compressed");
+                Self::process_claim_compressed(
+                    program_id,
+                    accounts,
+                    seeds,
+                    root,
+                    index,
+                    release_time,
+                    amount,
+                )
+            }
+            VestingInstruction::RevokeBatch { seeds } => {
+                msg!("Instruction: Revoke Batch");
+                Self::process_revoke_batch(program_id, accounts, seeds)
+            }
+            VestingInstruction::ExportContract {
+                seeds,
+                new_program_id,
+            } => {
+                msg!("Instruction: Export Contract");
+                Self::process_export_contract(program_id, accounts, seeds, &new_program_id)
+            }
+            VestingInstruction::ImportContract { seeds } => {
+                msg!("Instruction: Import Contract");
+                Self::process_import_contract(program_id, accounts, seeds)
+            }
+            VestingInstruction::InitChild {
+                base_seed,
+                index,
+                number_of_schedules,
+            } => {
+                msg!("Instruction: Init Child");
+                // find_program_address costs some compute, but it's only paid once per contract
+                let seeds = child_seeds(program_id, &base_seed, index);
+                Self::process_init(
+                    program_id,
+                    accounts,
+                    seeds,
+                    number_of_schedules,
+                    ScheduleLayout::Plain,
+                )
+            }
+            VestingInstruction::TopUpGas { seeds, amount } => {
+                msg!("Instruction: Top Up Gas");
+                Self::process_top_up_gas(program_id, accounts, seeds, amount)
+            }
+            VestingInstruction::SetPermissionlessUnlock { seeds, enabled } => {
+                msg!("Instruction: Set Permissionless Unlock");
+                let policy = if enabled {
+                    UnlockPolicy::Permissionless
+                } else {
+                    UnlockPolicy::DestinationOwnerOnly
+                };
+                Self::process_set_unlock_policy(program_id, accounts, seeds, policy)
+            }
+            VestingInstruction::SetUnlockPolicy { seeds, policy } => {
+                msg!("Instruction: Set Unlock Policy");
+                Self::process_set_unlock_policy(program_id, accounts, seeds, policy)
+            }
+            VestingInstruction::Accelerate { seeds } => {
+                msg!("Instruction: Accelerate");
+                Self::process_accelerate(program_id, accounts, seeds)
+            }
+            VestingInstruction::Activate { seeds } => {
+                msg!("Instruction: Activate");
+                Self::process_activate(program_id, accounts, seeds)
+            }
+            VestingInstruction::ChangeGranter { seeds, new_granter } => {
+                msg!("Instruction: ChangeGranter");
+                Self::process_change_granter(program_id, accounts, seeds, &new_granter)
+            }
+            VestingInstruction::Pause { seeds } => {
+                msg!("Instruction: Pause");
+                Self::process_set_paused(program_id, accounts, seeds, true)
+            }
+            VestingInstruction::Resume { seeds } => {
+                msg!("Instruction: Resume");
+                Self::process_set_paused(program_id, accounts, seeds, false)
+            }
+            VestingInstruction::EmergencyWithdraw { seeds } => {
+                msg!("Instruction: Emergency Withdraw");
+                Self::process_emergency_withdraw(program_id, accounts, seeds)
+            }
+            #[cfg(feature = "devnet-tools")]
+            VestingInstruction::DebugShiftSchedules { seeds, offset } => {
+                msg!("Instruction: Debug Shift Schedules");
+                Self::process_debug_shift_schedules(program_id, accounts, seeds, offset)
+            }
+            VestingInstruction::UnlockWithPermit { seeds, expiry } => {
+                msg!("Instruction: Unlock With Permit");
+                Self::process_unlock(program_id, accounts, seeds, Some(expiry), 0, 0, None)
+            }
+            VestingInstruction::ChangeFutureDestination { seeds, cutoff } => {
+                msg!("Instruction: Change Future Destination");
+                Self::process_change_destination(program_id, accounts, seeds, Some(cutoff), None)
+            }
+            VestingInstruction::VerifySolvency { seeds } => {
+                msg!("Instruction: Verify Solvency");
+                Self::process_verify_solvency(program_id, accounts, seeds)
+            }
+            VestingInstruction::SetRollover { seeds, enabled } => {
+                msg!("Instruction: Set Rollover");
+                Self::process_set_rollover(program_id, accounts, seeds, enabled)
+            }
+            VestingInstruction::SetMinClaimAmount {
+                seeds,
+                min_claim_amount,
+            } => {
+                msg!("Instruction: Set Min Claim Amount");
+                Self::process_set_min_claim_amount(program_id, accounts, seeds, min_claim_amount)
+            }
+            VestingInstruction::SetWithholding {
+                seeds,
+                withholding_bps,
+            } => {
+                msg!("Instruction: Set Withholding");
+                Self::process_set_withholding(program_id, accounts, seeds, withholding_bps)
+            }
+            VestingInstruction::SetSplits { seeds, splits } => {
+                msg!("Instruction: Set Splits");
+                Self::process_set_splits(program_id, accounts, seeds, splits)
+            }
+            VestingInstruction::ReadEmissions {
+                mint_address,
+                weeks,
+            } => {
+                msg!("Instruction: Read Emissions");
+                Self::process_read_emissions(program_id, accounts, &mint_address, weeks)
+            }
+            VestingInstruction::Merge {
+                seeds,
+                merged_seeds,
+            } => {
+                msg!("Instruction: Merge");
+                Self::process_merge(program_id, accounts, seeds, merged_seeds)
+            }
+            VestingInstruction::TransferBeneficiary { seeds, rent_payer } => {
+                msg!("Instruction: Transfer Beneficiary");
+                Self::process_transfer_beneficiary(program_id, accounts, seeds, rent_payer)
+            }
+            VestingInstruction::CheckRevision {
+                seeds,
+                expected_revision,
+            } => {
+                msg!("Instruction: Check Revision");
+                Self::process_check_revision(program_id, accounts, seeds, expected_revision)
+            }
+            VestingInstruction::Resize {
+                seeds,
+                number_of_schedules,
+                schedule_layout,
+            } => {
+                msg!("Instruction: Resize");
+                Self::process_resize(
+                    program_id,
+                    accounts,
+                    seeds,
+                    number_of_schedules,
+                    schedule_layout,
+                )
+            }
+            VestingInstruction::CloseUninitialized { seeds } => {
+                msg!("Instruction: Close Uninitialized");
+                Self::process_close_uninitialized(program_id, accounts, seeds)
+            }
+            VestingInstruction::InitGrant {
+                beneficiary,
+                mint,
+                nonce,
+                number_of_schedules,
+                schedule_layout,
+            } => {
+                msg!("Instruction: Init Grant");
+                Self::process_init_grant(
+                    program_id,
+                    accounts,
+                    &beneficiary,
+                    &mint,
+                    nonce,
+                    number_of_schedules,
+                    schedule_layout,
+                )
+            }
+            VestingInstruction::InitConfig {
+                admin,
+                early_claim_tolerance_seconds,
+            } => {
+                msg!("Instruction: Init Config");
+                Self::process_init_config(
+                    program_id,
+                    accounts,
+                    &admin,
+                    early_claim_tolerance_seconds,
+                )
+            }
+            VestingInstruction::SetEarlyClaimTolerance {
+                early_claim_tolerance_seconds,
+            } => {
+                msg!("Instruction: Set Early Claim Tolerance");
+                Self::process_set_early_claim_tolerance(
+                    program_id,
+                    accounts,
+                    early_claim_tolerance_seconds,
+                )
+            }
+            VestingInstruction::SetCommunityTreasury { community_treasury } => {
+                msg!("Instruction: Set Community Treasury");
+                Self::process_set_community_treasury(program_id, accounts, &community_treasury)
+            }
+            VestingInstruction::Close { seeds, dust_policy } => {
+                msg!("Instruction: Close");
+                Self::process_close(program_id, accounts, seeds, dust_policy)
+            }
+            VestingInstruction::SetTransferLimit {
+                seeds,
+                max_transfer_per_unlock,
+                unlock_interval,
+            } => {
+                msg!("Instruction: Set Transfer Limit");
+                Self::process_set_transfer_limit(
+                    program_id,
+                    accounts,
+                    seeds,
+                    max_transfer_per_unlock,
+                    unlock_interval,
+                )
+            }
+            VestingInstruction::InitDestinationApprovers {
+                seeds,
+                threshold,
+                approvers,
+            } => {
+                msg!("Instruction: Init Destination Approvers");
+                Self::process_init_destination_approvers(
+                    program_id, accounts, seeds, threshold, approvers,
+                )
+            }
+            VestingInstruction::ApproveDestinationChange {
+                seeds,
+                new_destination,
+            } => {
+                msg!("Instruction: Approve Destination Change");
+                Self::process_approve_destination_change(
+                    program_id,
+                    accounts,
+                    seeds,
+                    &new_destination,
+                )
+            }
+            VestingInstruction::MigrateLayout => {
+                msg!("Instruction: Migrate Layout");
+                Self::process_migrate_layout(program_id, accounts)
+            }
+            VestingInstruction::Compact { seeds } => {
+                msg!("Instruction: Compact");
+                Self::process_compact(program_id, accounts, seeds)
+            }
+            VestingInstruction::TopUp {
+                seeds,
+                allow_immediate,
+                schedules,
+            } => {
+                msg!("Instruction: Top Up");
+                Self::process_top_up(program_id, accounts, seeds, allow_immediate, schedules)
+            }
+            VestingInstruction::RefreshClaimable { seeds } => {
+                msg!("Instruction: Refresh Claimable");
+                Self::process_refresh_claimable(program_id, accounts, seeds)
+            }
+            VestingInstruction::SetForbidSelfDealing {
+                forbid_self_dealing,
+            } => {
+                msg!("Instruction: Set Forbid Self Dealing");
+                Self::process_set_forbid_self_dealing(program_id, accounts, forbid_self_dealing)
+            }
+            VestingInstruction::Revoke {
+                seeds,
+                expected_revision,
+            } => {
+                msg!("Instruction: Revoke");
+                Self::process_revoke(program_id, accounts, seeds, expected_revision)
+            }
+            VestingInstruction::UnlockGrant {
+                granter,
+                beneficiary,
+                mint,
+                nonce,
+            } => {
+                msg!("Instruction: Unlock Grant");
+                // a hash and no find_program_address - the bump is in the header, and process_unlock checks the
+                // vesting account against the seeds
+                let seeds = vesting_seeds(&granter, &beneficiary, &mint, nonce);
+                Self::process_unlock(program_id, accounts, seeds, None, 0, 0, None)
+            }
+        };
+
+        // the handlers only log which check failed - this shows what the contract looked like at the time
+        #[cfg(feature = "debug-logs")]
+        if result.is_err() {
+            if let Some(vesting_account) = vesting_account_index.and_then(|i| accounts.get(i)) {
+                let data = vesting_account.data.borrow();
+                if let Ok(header) = VestingScheduleHeader::unpack_account(&data) {
+                    crate::state::log_contract_summary(
+                        &header,
+                        &data[VestingScheduleHeader::LEN..header.schedules_end()],
+                    );
+                }
+            }
+        }
+
+        result
+    }
+
+    fn process_init(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        number_of_schedules: u32,
+        schedule_layout: ScheduleLayout,
+    ) -> ProgramResult {
+        Self::init_vesting_account(
+            program_id,
+            accounts,
+            number_of_schedules,
+            schedule_layout,
+            |_, vesting_account| Self::new_vesting_signer(program_id, vesting_account, seeds),
+        )
+    }
+
+    fn process_init_grant(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        beneficiary: &Pubkey,
+        mint: &Pubkey,
+        nonce: u64,
+        number_of_schedules: u32,
+        schedule_layout: ScheduleLayout,
+    ) -> ProgramResult {
+        Self::init_vesting_account(
+            program_id,
+            accounts,
+            number_of_schedules,
+            schedule_layout,
+            |payer, vesting_account| {
+                // the seeds are the payer's to take, being hashed from its key
+                if !payer.is_signer {
+                    msg!("The granter should be a signer");
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                let seeds = vesting_seeds(payer.key, beneficiary, mint, nonce);
+                let (key, bump) = find_granted_vesting_address(&seeds, program_id);
+                if key != *vesting_account.key {
+                    msg!("Provided vesting account is invalid");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                Ok(VestingSigner::new(seeds, bump).granted())
+            },
+        )
+    }
+
+    /// Init and InitGrant, the vesting account signing with what `signer` makes of the payer and the vesting account
+    fn init_vesting_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        number_of_schedules: u32,
+        schedule_layout: ScheduleLayout,
+        signer: impl FnOnce(&AccountInfo, &AccountInfo) -> Result<VestingSigner, ProgramError>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- size & rent
+        let state_size =
+            VestingScheduleHeader::account_len(number_of_schedules as usize, schedule_layout);
+        let rent = Rent::from_account_info(rent_sysvar_account).ctx("reading rent sysvar")?;
+        let rent_size = rent.minimum_balance(state_size);
+
+        // ----------------------------------------------------------------------------- vesting account key
+        // find the non reversible public key for the vesting contract via the seed + check against the one that was passed
+        // in other words, vesting_account = PDA of the vesting program
+        let signer = signer(payer, vesting_account)?;
+
+        // ----------------------------------------------------------------------------- create
+        // ask system_program to create the actual account with the right space and rent
+        let init_vesting_account = create_account(
+            &payer.key,
+            vesting_account.key,
+            rent_size,
+            state_size as u64,
+            &program_id,
+        );
+
+        invoke_signed(
+            //note how we're using _signed coz it's a PDA
+            &init_vesting_account,
+            &[
+                system_program_account.clone(),
+                payer.clone(),
+                vesting_account.clone(),
+            ],
+            &[&signer.signer_seeds()], //signing with seeds
+        )
+        .ctx("creating vesting account")?;
+
+        // everything else stays zero until Create, which writes the granter over the payer
+        let mut data = vesting_account.data.borrow_mut();
+        write_state(
+            &mut data,
+            ContractState::Uninitialized.apply(ContractEvent::Init)?,
+        );
+        data[BUMP_OFFSET] = signer.bump();
+        write_init_payer(&mut data, payer.key);
+        Ok(())
+    }
+
+    pub fn process_close_uninitialized(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if !payer.is_signer {
+            msg!("The Init payer should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut data = vesting_account.try_borrow_mut_data()?;
+        // Init used to leave the account zeroed, with no record of who paid for it - nobody can claim that rent back
+        if data.iter().all(|b| *b == 0) {
+            msg!("An account Init-ed before the payer was recorded has no payer to refund");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if data.len() < VestingScheduleHeader::LEN {
+            msg!("The vesting account is too short for a header");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let state = ContractState::from_byte(data[CONTRACT_STATE_OFFSET], NO_PENDING_RELEASE)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .apply(ContractEvent::CloseUninitialized)?;
+
+        if init_payer(&data) != *payer.key {
+            msg!("Only whoever paid for Init gets the rent back");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- close
+        // the runtime drops the account once it has no lamports. Until then it's marked Closed, like Close leaves a
+        // contract, so that nothing can Create over it in the meantime
+        let lamports = vesting_account.lamports();
+        **vesting_account.try_borrow_mut_lamports()? = 0;
+        **payer.try_borrow_mut_lamports()? += lamports;
+        data.fill(0);
+        data[..ACCOUNT_TYPE_OFFSET].copy_from_slice(&ACCOUNT_MAGIC);
+        data[HEADER_VERSION_OFFSET] = HEADER_VERSION;
+        write_state(&mut data, state);
+
+        Ok(())
+    }
+
+    pub fn process_resize(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        number_of_schedules: u32,
+        schedule_layout: ScheduleLayout,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !payer.is_signer {
+            msg!("The fee payer should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Create checks the account against its schedules, so until then nobody relies on its size. After, the
+        // header's schedule_count has to match it
+        let state = ContractState::from_byte(
+            *vesting_account
+                .try_borrow_data()?
+                .get(CONTRACT_STATE_OFFSET)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            NO_PENDING_RELEASE,
+        )
+        .ok_or(ProgramError::InvalidAccountData)?;
+        if state.is_created() {
+            msg!(
+                "Only an account waiting for Create can be resized, TopUp grows a created contract"
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let new_len =
+            VestingScheduleHeader::account_len(number_of_schedules as usize, schedule_layout);
+        if new_len <= vesting_account.data_len() {
+            msg!(
+                "the account already holds {:?} bytes, {:?} schedules take {:?}",
+                vesting_account.data_len(),
+                number_of_schedules,
+                new_len
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- grow the account
+        let rent = Rent::get().ctx("reading rent")?;
+        let missing_lamports = rent
+            .minimum_balance(new_len)
+            .saturating_sub(vesting_account.lamports());
+        if missing_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, vesting_account.key, missing_lamports),
+                &[
+                    payer.clone(),
+                    vesting_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )
+            .ctx("topping up rent")?;
+        }
+        vesting_account
+            .realloc(new_len, true)
+            .ctx("growing the vesting account")?;
+        Ok(())
+    }
+
+    pub fn process_create(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        token_mint_addr: &Pubkey,
+        token_dest_addr: &Pubkey,
+        schedules: Vec<Schedule>,
+        linear: Option<LinearVesting>,
+        options: CreateOptions,
+    ) -> ProgramResult {
+        let CreateOptions {
+            allow_immediate,
+            gas_rebate,
+            gas_deposit,
+            min_claim_amount,
+            destination_locked,
+            forbid_self_dealing,
+            revoke_policy,
+            relative,
+            emergency_authority,
+            revocable,
+            categories,
+            max_supply,
+        } = options;
+        let funding = match max_supply {
+            Some(max_supply) => Funding::MintTo { max_supply },
+            None => Funding::Transfer,
+        };
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?; //the one that holds the info
+        let vesting_token_account = next_account_info(accounts_iter)?; //the one that will hold the tokens
+        let source_token_account_owner = next_account_info(accounts_iter)?; //the mint authority with Funding::MintTo
+        let source_token_account = next_account_info(accounts_iter)?; //the mint with Funding::MintTo
+        let mint = match funding {
+            Funding::Transfer => next_account_info(accounts_iter)?,
+            Funding::MintTo { .. } => source_token_account,
+        };
+        let destination_token_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        // check passed in vesting account's addr matches derived PDA addr
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("bad provided vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !source_token_account_owner.is_signer {
+            msg!("source token account owner should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // only an account fresh out of Init can take a contract. A legacy contract's next_release_time doesn't matter,
+        // Active and Exhausted are both turned away
+        let state = ContractState::from_byte(
+            vesting_account.try_borrow_data()?[CONTRACT_STATE_OFFSET],
+            NO_PENDING_RELEASE,
+        )
+        .ok_or(ProgramError::InvalidAccountData)?;
+        let event = if relative {
+            ContractEvent::CreateRelative
+        } else {
+            ContractEvent::Create
+        };
+        let state = match state.apply(event) {
+            Ok(state) => state,
+            Err(_) => {
+                msg!("cannot overwrite an existing vesting contract");
+                return Err(ProgramError::InvalidArgument);
+            }
+        };
+
+        // InitGrant hashed the seeds from whoever paid for it, and only that granter can put a contract at them
+        if signer.is_granted()
+            && init_payer(&vesting_account.try_borrow_data()?) != *source_token_account_owner.key
+        {
+            msg!("Only the granter the seeds were derived from can create a contract at them");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // because this is an instance of TokenAccount, we can unpack it with a predefined function
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+
+        // so what we want is:
+        // (vesting) program_id -> owns vesting_account
+        // vesting_account -> owns vesting_token_account
+        if vesting_token_account_data.owner != *vesting_account.key {
+            msg!("vesting token account should be owned by vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if vesting_token_account_data.delegate.is_some() {
+            msg!("vesting account should NOT have a delegate");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if vesting_token_account_data.close_authority.is_some() {
+            msg!("vesting account should NOT have a close authority");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::check_token_account_extensions(vesting_token_account)?;
+
+        if vesting_token_account_data.mint != *token_mint_addr {
+            msg!("vesting token account should be of the contract's mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if mint.key != token_mint_addr {
+            msg!("mint account doesn't match the contract's mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !is_token_program(mint.owner) {
+            msg!("mint should be owned by the spl-token or Token-2022 program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mint_data = Self::unpack_mint(mint).ctx("unpacking mint")?;
+
+        if destination_token_account.key != token_dest_addr {
+            msg!("destination token account doesn't match the contract's destination");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.mint != *token_mint_addr {
+            msg!("destination token account should be of the contract's mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // spl-token would turn away a transfer by someone else than the source's owner too, just with a vaguer error
+        if let Funding::Transfer = funding {
+            let source_token_account_data =
+                Self::unpack_token_account(program_id, source_token_account)
+                    .ctx("unpacking source token account")?;
+            if source_token_account_data.owner != *source_token_account_owner.key {
+                msg!(
+                    "source token account is owned by {:?}, not the granter",
+                    source_token_account_data.owner
+                );
+                return Err(VestingError::SourceOwnerMismatch.into());
+            }
+        }
+
+        // the granter can revoke whatever hasn't been released yet, so a granter that also owns the destination
+        // decides on its own what the contract pays out. An irrevocable contract pays out as set up whoever owns it
+        let forbid_self_dealing = revocable
+            && (forbid_self_dealing
+                || match accounts
+                    .iter()
+                    .find(|a| Self::is_program_config(program_id, a))
+                {
+                    Some(config_account) => {
+                        ProgramConfig::unpack(&config_account.data.borrow())
+                            .ctx("unpacking program config")?
+                            .forbid_self_dealing
+                    }
+                    None => false,
+                });
+        if forbid_self_dealing
+            && destination_token_account_data.owner == *source_token_account_owner.key
+        {
+            msg!("The granter can't own the destination of a contract it can revoke");
+            return Err(VestingError::SelfDealing.into());
+        }
+
+        // a linear contract's one schedule covers its whole grant, from the first release on
+        let schedules = match &linear {
+            Some(linear) => {
+                if linear.end_time <= linear.start_time || !categories.is_empty() {
+                    msg!(
+                        "a linear contract needs its release to end after it starts ({:?} - {:?}) and no categories",
+                        linear.start_time,
+                        linear.end_time
+                    );
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                vec![Schedule {
+                    release_time: linear.first_release_time(),
+                    amount: linear
+                        .grant_amount()
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                }]
+            }
+            None => schedules,
+        };
+
+        // a release time in the past unlocks immediately, which is almost never what the granter meant. Relative ones
+        // aren't times yet
+        if !allow_immediate && !relative {
+            let now = Clock::get().ctx("reading clock")?.unix_timestamp;
+            if schedules.iter().any(|s| (s.release_time as i64) < now) {
+                msg!("schedule release time is in the past, pass allow_immediate if intended");
+                return Err(VestingError::ReleaseTimeInPast.into());
+            }
+        }
+
+        // the emission curve is found by its key as well. Linear contracts release continuously and relative ones
+        // have no release times yet, so neither has weeks to go on it
+        let (emission_curve_key, _) = find_emission_curve(program_id, token_mint_addr);
+        let emission_curve_account = accounts.iter().find(|a| *a.key == emission_curve_key);
+        if emission_curve_account.is_some() && (linear.is_some() || relative) {
+            msg!("Only contracts releasing in tranches at absolute times can be tracked on an emission curve");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // categories are all or nothing - either every schedule has one or it's a plain contract
+        let schedule_layout = if linear.is_some() {
+            ScheduleLayout::Linear
+        } else if categories.is_empty() {
+            ScheduleLayout::Plain
+        } else if categories.len() == schedules.len() {
+            ScheduleLayout::Categorized
+        } else {
+            msg!(
+                "got {:?} categories for {:?} schedules",
+                categories.len(),
+                schedules.len()
+            );
+            return Err(ProgramError::InvalidInstructionData);
+        };
+
+        // ----------------------------------------------------------------------------- update state
+        //the reason we're creating a new one instead of deserializing existing one is because THERE IS NO EXISTING ONE
+        //one of the checks above makes sure that (the one that checks the contract state)
+        let state_schedules = schedules
+            .iter()
+            .map(|s| VestingSchedule {
+                release_time: s.release_time,
+                amount: s.amount,
+            })
+            .collect::<Vec<VestingSchedule>>();
+        let mut state_header = VestingScheduleHeader {
+            destination_address: *token_dest_addr,
+            mint_address: *token_mint_addr,
+            granter_address: *source_token_account_owner.key,
+            next_release_time: if relative {
+                NO_PENDING_RELEASE //nothing is released before Activate
+            } else {
+                next_release_time(&state_schedules)
+            },
+            gas_rebate,
+            gas_escrow: gas_deposit,
+            schedule_count: state_schedules.len() as u32,
+            unlock_policy: UnlockPolicy::Permissionless, //the destination owner can change it with SetUnlockPolicy
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount,
+            destination_locked,
+            unlock_cursor: 0,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority,
+            revocable,
+            splits: vec![],
+            emission_tracked: emission_curve_account.is_some(),
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: signer.bump(),
+            schedule_layout,
+            state,
+        };
+        state_header.settle_state();
+        // only a hint, so a clock that can't be read isn't worth failing Create over
+        if let Ok(clock) = Clock::get() {
+            state_header.refresh_has_claimable(clock.unix_timestamp as u64);
+        }
+
+        //get a mutable reference to vesting_account's data
+        //a categorized (or linear) contract needs an account from an Init with the same layout, and the other way around
+        let mut data = vesting_account.data.borrow_mut();
+        let expected_len = VestingScheduleHeader::account_len(schedules.len(), schedule_layout);
+        if data.len() != expected_len {
+            msg!(
+                "data len not right: l = {:?}, r = {:?}",
+                data.len(),
+                expected_len
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        //pack the newly created header into that reference
+        state_header.pack_into_slice(&mut data);
+        pack_categories_into_slice(&categories, &mut data[state_header.schedules_end()..]);
+        if let Some(linear) = &linear {
+            linear.pack_into_slice(&mut data[state_header.schedules_end()..]);
+        }
+
+        // ----------------------------------------------------------------------------- build up amount
+
+        let mut offset = VestingScheduleHeader::LEN; //needed to pack schedule into data
+        let mut total_amount: u64 = 0; //needed to keep track of total amount
+
+        for s in state_schedules.iter() {
+            //we're packing the schedule at a specific offset
+            s.pack_into_slice(&mut data[offset..]);
+
+            let delta = total_amount.checked_add(s.amount);
+            match delta {
+                Some(n) => total_amount = n, //not +=n, we're doing checked_add above
+                None => return Err(ProgramError::InvalidInstructionData),
+            }
+            offset += SCHEDULE_SIZE;
+        }
+
+        // ----------------------------------------------------------------------------- send funds
+
+        match funding {
+            Funding::Transfer => {
+                // a mint charging transfer fees has the granter pay them on top, so that the vesting token account
+                // still receives the total
+                let fee_mint =
+                    Self::contract_fee_mint(vesting_token_account, token_mint_addr, accounts)?;
+                let gross_amount = match &fee_mint {
+                    Some(fee_mint) => fee_mint.gross_up(total_amount)?,
+                    None => total_amount,
+                };
+
+                //if existing amount in source token below total amount, we can't do it
+                if Self::unpack_token_account(program_id, source_token_account)
+                    .ctx("unpacking source token account")?
+                    .amount
+                    < gross_amount
+                {
+                    msg!("source token account has insufficient funds");
+                    return Err(ProgramError::InsufficientFunds);
+                }
+
+                //no signer seeds because it's alice who's signing and not a PDA
+                Self::transfer_tokens(
+                    spl_token_account,
+                    source_token_account,
+                    vesting_token_account,
+                    source_token_account_owner,
+                    fee_mint.as_ref(),
+                    &[],
+                    gross_amount,
+                )
+                .ctx("transferring tokens to the vesting token account")?;
+            }
+            Funding::MintTo { max_supply } => {
+                if mint_data.mint_authority != COption::Some(*source_token_account_owner.key) {
+                    msg!("the granter has to be the mint authority");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                // spl-token has no cap of its own, the supply just has to stay within a u64. Checked up front so the
+                // failure is ours rather than a bare overflow out of the CPI
+                let supply = match mint_data.supply.checked_add(total_amount) {
+                    Some(supply) => supply,
+                    None => {
+                        msg!(
+                            "minting {} on top of a supply of {} overflows",
+                            total_amount,
+                            mint_data.supply
+                        );
+                        return Err(VestingError::MintSupplyOverflow.into());
+                    }
+                };
+                // the cap is the granter's, so that a grant built against a stale supply doesn't inflate past it
+                if supply > max_supply {
+                    msg!(
+                        "minting {} on top of a supply of {} goes past the cap of {}",
+                        total_amount,
+                        mint_data.supply,
+                        max_supply
+                    );
+                    return Err(VestingError::MintSupplyCapExceeded.into());
+                }
+
+                Self::invoke_token(
+                    &mint_to(
+                        spl_token_account.key,
+                        mint.key,
+                        vesting_token_account.key,
+                        source_token_account_owner.key,
+                        &[],
+                        total_amount,
+                    )?,
+                    &[
+                        mint.clone(),
+                        vesting_token_account.clone(),
+                        spl_token_account.clone(),
+                        source_token_account_owner.clone(),
+                    ],
+                    &[],
+                    &[(vesting_token_account, total_amount as i128)],
+                )
+                .ctx("minting tokens to the vesting token account")?;
+            }
+        }
+
+        // ----------------------------------------------------------------------------- gas escrow
+        drop(data); //the system program needs to borrow the vesting account
+
+        // the program config and the emission curve were found by key, the rest are positional
+        let mut optional_accounts = accounts_iter
+            .filter(|a| *a.key != emission_curve_key && !Self::is_program_config(program_id, a));
+        let system_program_account = optional_accounts.next();
+        let destination_index_account = optional_accounts.next();
+        let indexed_contract = optional_accounts.next();
+        if gas_deposit > 0 {
+            let system_program_account =
+                system_program_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if *system_program_account.key != system_program::id() {
+                msg!("The provided system program account is invalid");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            invoke(
+                &system_instruction::transfer(
+                    source_token_account_owner.key,
+                    vesting_account.key,
+                    gas_deposit,
+                ),
+                &[
+                    source_token_account_owner.clone(),
+                    vesting_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )
+            .ctx("depositing gas")?;
+        }
+
+        // ----------------------------------------------------------------------------- destination index
+        if let Some(destination_index_account) = destination_index_account {
+            Self::check_destination_index_free(
+                program_id,
+                destination_index_account,
+                token_mint_addr,
+                token_dest_addr,
+                &vesting_account_key,
+                indexed_contract,
+            )?;
+            Self::write_destination_index(
+                program_id,
+                destination_index_account,
+                token_mint_addr,
+                token_dest_addr,
+                &vesting_account_key,
+                source_token_account_owner,
+                system_program_account.ok_or(ProgramError::NotEnoughAccountKeys)?,
+            )?;
+        }
+
+        // ----------------------------------------------------------------------------- emission curve
+        if let Some(emission_curve_account) = emission_curve_account {
+            // a clock that can't be read only keeps the curve from moving up to the current week
+            let now = Clock::get().map_or(0, |clock| clock.unix_timestamp as u64);
+            if emission_curve_account.owner != program_id {
+                Self::init_emission_curve(
+                    program_id,
+                    emission_curve_account,
+                    token_mint_addr,
+                    source_token_account_owner,
+                    system_program_account.ok_or(ProgramError::NotEnoughAccountKeys)?,
+                    now,
+                )?;
+            }
+            Self::update_emission_curve(
+                program_id,
+                Some(emission_curve_account),
+                &[],
+                &state_schedules,
+                now,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Fails with DestinationIndexTaken unless the destination index of (mint, destination) can be pointed at
+    /// `vesting_account_key`: it hasn't been created yet, points nowhere or already there, or points at a contract
+    /// that no longer pays the destination. That contract has to be passed as `indexed_contract` to show it, so that
+    /// a new contract can't take the index over from one still paying out
+    fn check_destination_index_free(
+        program_id: &Pubkey,
+        destination_index_account: &AccountInfo,
+        mint_address: &Pubkey,
+        destination_token_account_key: &Pubkey,
+        vesting_account_key: &Pubkey,
+        indexed_contract: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        if destination_index_account.owner != program_id {
+            return Ok(());
+        }
+        let index = DestinationIndex::unpack(&destination_index_account.data.borrow())
+            .ctx("unpacking destination index")?;
+        if index.vesting_account == Pubkey::default()
+            || index.vesting_account == *vesting_account_key
+        {
+            return Ok(());
+        }
+
+        let still_paying = match indexed_contract {
+            Some(contract) if *contract.key == index.vesting_account => Self::pays_destination(
+                program_id,
+                contract,
+                mint_address,
+                destination_token_account_key,
+            ),
+            _ => true,
+        };
+        if still_paying {
+            msg!(
+                "The destination index points at {}, which still pays the destination",
+                index.vesting_account
+            );
+            return Err(VestingError::DestinationIndexTaken.into());
+        }
+        Ok(())
+    }
+
+    /// Whether `contract` still has something to pay (mint, destination), as its destination or its legacy one.
+    /// A contract on the baseline layout can't be read until MigrateLayout, so it's taken to
+    /// still pay
+    fn pays_destination(
+        program_id: &Pubkey,
+        contract: &AccountInfo,
+        mint_address: &Pubkey,
+        destination_token_account_key: &Pubkey,
+    ) -> bool {
+        if contract.owner != program_id || contract.lamports() == 0 {
+            return false;
+        }
+        let header = match VestingScheduleHeader::unpack_account(&contract.data.borrow()) {
+            Ok(header) => header,
+            Err(e) => return e == VestingError::LegacyAccountLayout.into(),
+        };
+        let destination = header.destination_address == *destination_token_account_key
+            || (header.legacy_cutoff > 0
+                && header.legacy_destination_address == *destination_token_account_key);
+        header.mint_address == *mint_address
+            && destination
+            && !matches!(
+                header.state,
+                ContractState::Exhausted | ContractState::Closed
+            )
+    }
+
+    /// Points the destination index of (mint, destination) at `vesting_account_key`, creating it first if needed
+    fn write_destination_index<'a>(
+        program_id: &Pubkey,
+        destination_index_account: &AccountInfo<'a>,
+        mint_address: &Pubkey,
+        destination_token_account_key: &Pubkey,
+        vesting_account_key: &Pubkey,
+        payer: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let (index_key, bump) =
+            find_destination_index(program_id, mint_address, destination_token_account_key);
+        if index_key != *destination_index_account.key {
+            msg!("Provided destination index account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if destination_index_account.owner != program_id {
+            let rent = Rent::get().ctx("reading rent")?;
+            invoke_signed(
+                &create_account(
+                    payer.key,
+                    &index_key,
+                    rent.minimum_balance(DestinationIndex::LEN),
+                    DestinationIndex::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    system_program_account.clone(),
+                    payer.clone(),
+                    destination_index_account.clone(),
+                ],
+                &[&[
+                    DESTINATION_INDEX_SEED,
+                    mint_address.as_ref(),
+                    destination_token_account_key.as_ref(),
+                    &[bump],
+                ]],
+            )
+            .ctx("creating destination index")?;
+        }
+
+        let index = DestinationIndex {
+            vesting_account: *vesting_account_key,
+            is_initialized: true,
+        };
+        index.pack_into_slice(&mut destination_index_account.data.borrow_mut());
+        Ok(())
+    }
+
+    /// Clears the destination index of (mint, destination) if it still points at `vesting_account_key`. An index
+    /// that was never created is left alone
+    fn clear_destination_index(
+        program_id: &Pubkey,
+        destination_index_account: &AccountInfo,
+        mint_address: &Pubkey,
+        destination_token_account_key: &Pubkey,
+        vesting_account_key: &Pubkey,
+    ) -> ProgramResult {
+        let (index_key, _) =
+            find_destination_index(program_id, mint_address, destination_token_account_key);
+        if index_key != *destination_index_account.key {
+            msg!("Provided destination index account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if destination_index_account.owner != program_id {
+            return Ok(());
+        }
+
+        let mut index = DestinationIndex::unpack(&destination_index_account.data.borrow())
+            .ctx("unpacking destination index")?;
+        if index.vesting_account == *vesting_account_key {
+            index.vesting_account = Pubkey::default();
+            index.pack_into_slice(&mut destination_index_account.data.borrow_mut());
+        }
+        Ok(())
+    }
+
+    /// Points the destination index of (mint, destination) at `to` if it points at `from`. An index that was never
+    /// created is left alone
+    fn move_destination_index(
+        program_id: &Pubkey,
+        destination_index_account: &AccountInfo,
+        mint_address: &Pubkey,
+        destination_token_account_key: &Pubkey,
+        from: &Pubkey,
+        to: &Pubkey,
+    ) -> ProgramResult {
+        let (index_key, _) =
+            find_destination_index(program_id, mint_address, destination_token_account_key);
+        if index_key != *destination_index_account.key {
+            msg!("Provided destination index account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if destination_index_account.owner != program_id {
+            return Ok(());
+        }
+
+        let mut index = DestinationIndex::unpack(&destination_index_account.data.borrow())
+            .ctx("unpacking destination index")?;
+        if index.vesting_account == *from {
+            index.vesting_account = *to;
+            index.pack_into_slice(&mut destination_index_account.data.borrow_mut());
+        }
+        Ok(())
+    }
+
+    /// Creates the emission curve of a mint for its first tracked contract, starting with the week of `now`
+    fn init_emission_curve<'a>(
+        program_id: &Pubkey,
+        emission_curve_account: &AccountInfo<'a>,
+        mint_address: &Pubkey,
+        payer: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        now: u64,
+    ) -> ProgramResult {
+        let (curve_key, bump) = find_emission_curve(program_id, mint_address);
+        if curve_key != *emission_curve_account.key {
+            msg!("Provided emission curve account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let rent = Rent::get().ctx("reading rent")?;
+        invoke_signed(
+            &create_account(
+                payer.key,
+                &curve_key,
+                rent.minimum_balance(EmissionCurve::LEN),
+                EmissionCurve::LEN as u64,
+                program_id,
+            ),
+            &[
+                system_program_account.clone(),
+                payer.clone(),
+                emission_curve_account.clone(),
+            ],
+            &[&[EMISSION_CURVE_SEED, mint_address.as_ref(), &[bump]]],
+        )
+        .ctx("creating emission curve")?;
+
+        EmissionCurve::new(*mint_address, now)
+            .pack_into_slice(&mut emission_curve_account.data.borrow_mut());
+        Ok(())
+    }
+
+    /// The emission curve among `accounts` of a contract tracked on one, None for a contract that isn't
+    fn emission_curve_account<'b, 'a>(
+        program_id: &Pubkey,
+        header: &VestingScheduleHeader,
+        accounts: &'b [AccountInfo<'a>],
+    ) -> Result<Option<&'b AccountInfo<'a>>, ProgramError> {
+        if !header.emission_tracked {
+            return Ok(None);
+        }
+        let (curve_key, _) = find_emission_curve(program_id, &header.mint_address);
+        match accounts.iter().find(|a| *a.key == curve_key) {
+            Some(emission_curve_account) => Ok(Some(emission_curve_account)),
+            None => {
+                msg!(
+                    "The contract is tracked on its mint's emission curve, which has to be passed"
+                );
+                Err(ProgramError::NotEnoughAccountKeys)
+            }
+        }
+    }
+
+    /// Moves a tracked contract from its `before` schedules to its `after` ones on the emission curve, after moving
+    /// the curve up to `now`. Taking schedules off doesn't need the curve to be current, so a `now` of 0 will do there
+    fn update_emission_curve(
+        program_id: &Pubkey,
+        emission_curve_account: Option<&AccountInfo>,
+        before: &[VestingSchedule],
+        after: &[VestingSchedule],
+        now: u64,
+    ) -> ProgramResult {
+        let emission_curve_account = match emission_curve_account {
+            Some(emission_curve_account) => emission_curve_account,
+            None => return Ok(()),
+        };
+        if emission_curve_account.owner != program_id {
+            msg!("The emission curve should be owned by the program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let mut curve = EmissionCurve::unpack(&emission_curve_account.data.borrow())
+            .ctx("unpacking emission curve")?;
+        curve.roll(now);
+        if curve.replace(before, after).is_none() {
+            msg!(
+                "a tracked contract can't release more than {} weeks out",
+                EMISSION_CURVE_WEEKS
+            );
+            return Err(VestingError::BeyondEmissionCurve.into());
+        }
+        curve.pack_into_slice(&mut emission_curve_account.data.borrow_mut());
+        Ok(())
+    }
+
+    pub fn process_read_emissions(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mint_address: &Pubkey,
+        weeks: u8,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let emission_curve_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (curve_key, _) = find_emission_curve(program_id, mint_address);
+        if curve_key != *emission_curve_account.key {
+            msg!("Provided emission curve account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if weeks == 0 || weeks > MAX_READ_EMISSION_WEEKS {
+            msg!(
+                "can read 1 to {} weeks, not {}",
+                MAX_READ_EMISSION_WEEKS,
+                weeks
+            );
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let now = Clock::from_account_info(clock_sysvar_account)
+            .ctx("reading clock sysvar")?
+            .unix_timestamp as u64;
+
+        // ----------------------------------------------------------------------------- core
+        // a mint that never had a tracked contract has no curve, and nothing coming up either
+        let (first_week, weekly_amounts) = if emission_curve_account.owner == program_id {
+            EmissionCurve::unpack(&emission_curve_account.data.borrow())
+                .ctx("unpacking emission curve")?
+                .upcoming(now, weeks as usize)
+        } else {
+            (EmissionCurve::week(now), vec![0; weeks as usize])
+        };
+        set_return_data(&pack_emissions(first_week, &weekly_amounts));
+
+        Ok(())
+    }
+
+    /// Handles both Unlock and UnlockWithPermit - the latter passes the permit's expiry
+    pub fn process_unlock(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        permit_expiry: Option<i64>,
+        max_schedules_per_call: u32,
+        max_amount: u64,
+        start_index: Option<u32>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?; //this is the one with the headers and schedules
+        let vesting_token_account = next_account_info(accounts_iter)?; //this is the one with the tokens
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let instructions_sysvar_account = match permit_expiry {
+            Some(_) => Some(next_account_info(accounts_iter)?),
+            None => None,
+        };
+        let trailing_accounts = accounts_iter.as_slice();
+
+        // ----------------------------------------------------------------------------- checks
+        //check passed vesting account matches derived vesting account
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        //check provided spl_token program is one of the real ones
+        Self::check_token_program(spl_token_account)?;
+
+        // unpack header
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        // relative release times would all look long past
+        if header_state.state == ContractState::AwaitingActivation {
+            msg!("The granter has to activate the contract first");
+            return Err(VestingError::AwaitingActivation.into());
+        }
+
+        if header_state.paused {
+            msg!("The granter has paused the contract's payouts");
+            return Err(VestingError::ContractPaused.into());
+        }
+
+        // the withholding account is picked out by its key, so it can come anywhere among the trailing accounts
+        let withholding_token_account = trailing_accounts.iter().find(|a| {
+            header_state.withholding_bps > 0 && *a.key == header_state.withholding_destination
+        });
+        // so are the split accounts
+        let split_token_accounts: Vec<Option<&AccountInfo>> = header_state
+            .splits
+            .iter()
+            .map(|s| {
+                trailing_accounts
+                    .iter()
+                    .find(|a| *a.key == s.destination_address)
+            })
+            .collect();
+        // and so is the emission curve of a tracked contract, and the mint of one charging transfer fees. The config,
+        // only passed for its early claim tolerance, is recognized as the config
+        let emission_curve_account =
+            Self::emission_curve_account(program_id, &header_state, trailing_accounts)?;
+        let config_account = trailing_accounts
+            .iter()
+            .find(|a| Self::is_program_config(program_id, a));
+        let trailing_accounts: Vec<&AccountInfo> = trailing_accounts
+            .iter()
+            .filter(|a| withholding_token_account.map_or(true, |w| w.key != a.key))
+            .filter(|a| emission_curve_account.map_or(true, |c| c.key != a.key))
+            .filter(|a| config_account.map_or(true, |c| c.key != a.key))
+            .filter(|a| *a.key != header_state.mint_address)
+            .filter(|a| {
+                !header_state
+                    .splits
+                    .iter()
+                    .any(|s| s.destination_address == *a.key)
+            })
+            .collect();
+        // which leaves the rebate receiver, only used if the contract pays a gas rebate or the caller has to sign
+        let rebate_receiver = match trailing_accounts.as_slice() {
+            [] => None,
+            [rebate_receiver] => Some(*rebate_receiver),
+            _ => {
+                msg!("Unexpected account: only the rebate receiver goes by its position");
+                return Err(ProgramError::InvalidArgument);
+            }
+        };
+
+        // check that header's dest addr matches provided dest addr. After a ChangeFutureDestination the previous
+        // destination claims the schedules released before the cutoff, and the current one everything else
+        let claims_legacy = header_state.legacy_cutoff > 0
+            && header_state.legacy_destination_address == *destination_token_account.key;
+        if header_state.destination_address != *destination_token_account.key && !claims_legacy {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // unpack vesting token account
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+
+        // check the owner of that account is the vesting_account
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("The vesting token account should be owned by the vesting account.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_account).ctx("reading clock sysvar")?;
+
+        // every transfer of a mint charging fees has to name the fee, which the recipients go without
+        let fee_mint = if Self::has_transfer_fees(vesting_token_account)? {
+            Some(Self::fee_mint(
+                &header_state.mint_address,
+                accounts,
+                clock.epoch,
+            )?)
+        } else {
+            None
+        };
+
+        // schedules releasing within the tolerance count as released, in case the cluster clock lags behind
+        let now = Self::unlock_time(program_id, &clock, accounts)?;
+
+        // a capped contract pays out large claims over several unlocks, spaced by the interval. The first one since
+        // the cap was set doesn't wait
+        let capped = header_state.max_transfer_per_unlock > 0;
+        let next_unlock_time = header_state
+            .last_unlock_time
+            .saturating_add(header_state.unlock_interval as u64);
+        if capped
+            && header_state.last_unlock_time > 0
+            && (clock.unix_timestamp as u64) < next_unlock_time
+        {
+            msg!("the next unlock can go through at {:?}", next_unlock_time);
+            return Err(VestingError::UnlockRateLimited.into());
+        }
+
+        // a permit is checked whenever one is passed, even if the contract doesn't need it
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        let destination_owner = destination_token_account_data.owner;
+        let owner_authorized = match (permit_expiry, instructions_sysvar_account) {
+            (Some(expiry), Some(instructions_sysvar_account)) => {
+                Self::check_claim_permit(
+                    program_id,
+                    instructions_sysvar_account,
+                    &vesting_account_key,
+                    &destination_owner,
+                    expiry,
+                    header_state.permit_nonce,
+                    clock.unix_timestamp,
+                )?;
+                // spends the permit - the same signature won't match the next nonce
+                header_state.permit_nonce = header_state.permit_nonce.wrapping_add(1);
+                true
+            }
+            _ => rebate_receiver.is_some_and(|r| r.is_signer && *r.key == destination_owner),
+        };
+        // under DelegateOnly the delegate stands in for the owner, for the checks below as well
+        let authorized = match header_state.unlock_policy {
+            UnlockPolicy::Permissionless | UnlockPolicy::DestinationOwnerOnly => owner_authorized,
+            UnlockPolicy::DelegateOnly => rebate_receiver.is_some_and(|r| {
+                r.is_signer && destination_token_account_data.delegate == COption::Some(*r.key)
+            }),
+        };
+        if header_state.unlock_policy != UnlockPolicy::Permissionless && !authorized {
+            msg!(
+                "Unlock policy is {} - the caller isn't allowed to unlock",
+                header_state.unlock_policy.as_str()
+            );
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        // a crank capping claims would leave the destination owner paying for a string of small unlocks
+        if max_amount > 0 && !authorized {
+            msg!("Only the destination owner can cap what an Unlock claims");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // a bounded unlock only loads the schedules it looks at, and carries on from there next time
+        let bounded = max_schedules_per_call > 0;
+        let (window_start, window_end) = if bounded {
+            if header_state.roll_into_final
+                || header_state.legacy_cutoff > 0
+                || header_state.max_transfer_per_unlock > 0
+            {
+                msg!("A bounded unlock can't be used while the contract rolls over, has a legacy destination or caps transfers");
+                return Err(ProgramError::InvalidArgument);
+            }
+            if max_amount > 0 {
+                msg!("A bounded unlock can't also cap what it claims");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let start = match start_index {
+                Some(start_index) if start_index >= header_state.schedule_count => {
+                    msg!(
+                        "start index {:?} is past the contract's {:?} schedules",
+                        start_index,
+                        header_state.schedule_count
+                    );
+                    return Err(ProgramError::InvalidArgument);
+                }
+                Some(start_index) => start_index,
+                None => header_state.unlock_cursor.min(header_state.schedule_count),
+            };
+            let end = start
+                .saturating_add(max_schedules_per_call)
+                .min(header_state.schedule_count);
+            (start, end)
+        } else {
+            if start_index.is_some() {
+                msg!("A start index needs a bound on the schedules to unlock");
+                return Err(ProgramError::InvalidArgument);
+            }
+            (0, header_state.schedule_count)
+        };
+        // a range further on than the cursor leaves it where it is, so nothing before it gets skipped
+        let advances_cursor = bounded && window_start == header_state.unlock_cursor;
+        let window = VestingScheduleHeader::LEN + window_start as usize * VestingSchedule::LEN
+            ..VestingScheduleHeader::LEN + window_end as usize * VestingSchedule::LEN;
+
+        // ----------------------------------------------------------------------------- core
+        // figure out how much has vested and can be transferred
+        let mut total_amount_to_transfer: u64 = 0;
+        let mut schedules =
+            unpack_schedules(&packed_state.borrow()[window.clone()]).ctx("unpacking schedules")?;
+        let tracked_schedules = match emission_curve_account {
+            Some(_) => schedules.clone(),
+            None => vec![],
+        };
+
+        // matured tranches move into the final schedule instead of being paid out
+        if header_state.roll_into_final {
+            let rolled = roll_matured_into_final(&mut schedules, now)
+                .ctx("rolling into the final schedule")?;
+            msg!("rolled {:?} into the final schedule", rolled);
+        }
+
+        // a linear contract's only schedule releases bit by bit from its release time on, instead of all at once
+        let linear =
+            unpack_linear(&header_state, &packed_state.borrow()).ctx("unpacking linear vesting")?;
+
+        let mut claimed = vec![0; schedules.len()];
+        for (s, claimed) in schedules.iter().zip(claimed.iter_mut()) {
+            msg!(
+                "unix timestamp: {:?}, schedule's release time: {:?}",
+                now,
+                s.release_time
+            );
+            let released = match &linear {
+                Some(linear) => linear_claimable(s, linear, now),
+                None if now >= s.release_time => s.amount,
+                None => 0,
+            };
+            if released > 0 && header_state.pays_legacy_destination(s) == claims_legacy {
+                // past the cap, the rest stays released for the next unlock
+                let amount = if capped {
+                    released.min(header_state.max_transfer_per_unlock - total_amount_to_transfer)
+                } else {
+                    released
+                };
+                total_amount_to_transfer = total_amount_to_transfer
+                    .checked_add(amount)
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                *claimed = amount;
+            }
+        }
+        // a partial claim takes the same share off every released schedule, the rest stays released
+        if max_amount > 0 && total_amount_to_transfer > max_amount {
+            pro_rata_claim(&mut claimed, max_amount);
+            total_amount_to_transfer = max_amount;
+        }
+        for (s, claimed) in schedules.iter_mut().zip(claimed) {
+            s.amount -= claimed; //note we're also taking the amount off the schedule. we will update state below. this is so that once an amount has vested, it only transfers out of the vesting contract ONCE
+        }
+        // a bounded unlock moves the cursor on even when its schedules haven't been released yet
+        if total_amount_to_transfer == 0 && !advances_cursor {
+            msg!("Vesting contract has not yet reached release time");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // the last claim goes through whatever its size, or the remainder would be stuck for good. A bounded unlock
+        // can't tell what's left outside of its schedules, so there only the destination owner can take a small claim
+        let claims_everything_left = if bounded {
+            authorized
+        } else {
+            !schedules
+                .iter()
+                .any(|s| s.amount > 0 && header_state.pays_legacy_destination(s) == claims_legacy)
+        };
+        if total_amount_to_transfer > 0
+            && total_amount_to_transfer < header_state.min_claim_amount
+            && !claims_everything_left
+        {
+            msg!(
+                "released amount {:?} is below the minimum claim of {:?}",
+                total_amount_to_transfer,
+                header_state.min_claim_amount
+            );
+            return Err(VestingError::ClaimBelowMinimum.into());
+        }
+
+        msg!(
+            "vesting contract balance is {:?}",
+            vesting_token_account_data.amount
+        );
+        msg!("total amount to transfer is {:?}", total_amount_to_transfer);
+
+        // the withheld share is rounded down, any dust goes to the destination
+        let withheld_amount = (total_amount_to_transfer as u128
+            * header_state.withholding_bps as u128
+            / MAX_WITHHOLDING_BPS as u128) as u64;
+        let destination_amount = total_amount_to_transfer - withheld_amount;
+
+        // each split takes its share of the rest, rounded down too. What a legacy destination claims isn't split
+        let split_amounts = if claims_legacy {
+            vec![0; header_state.splits.len()]
+        } else {
+            split_amounts(destination_amount, &header_state.splits)
+        };
+        let destination_amount = destination_amount - split_amounts.iter().sum::<u64>();
+
+        // ----------------------------------------------------------------------------- transfer
+        let mut transfer_fees = 0;
+        if withheld_amount > 0 {
+            let withholding_token_account = withholding_token_account.ok_or_else(|| {
+                msg!("The contract withholds - its withholding token account has to be passed");
+                ProgramError::NotEnoughAccountKeys
+            })?;
+            msg!("withholding {:?}", withheld_amount);
+
+            transfer_fees += Self::transfer_tokens(
+                spl_token_account,
+                vesting_token_account,
+                withholding_token_account,
+                vesting_account,
+                fee_mint.as_ref(),
+                &[&signer.signer_seeds()],
+                withheld_amount,
+            )
+            .ctx("transferring withheld tokens")?;
+        }
+
+        for (split_token_account, split_amount) in
+            split_token_accounts.into_iter().zip(split_amounts)
+        {
+            if split_amount == 0 {
+                continue;
+            }
+            let split_token_account = split_token_account.ok_or_else(|| {
+                msg!("The contract splits its unlocks - the token account of every split has to be passed");
+                ProgramError::NotEnoughAccountKeys
+            })?;
+            msg!("paying {:?} to a split", split_amount);
+
+            transfer_fees += Self::transfer_tokens(
+                spl_token_account,
+                vesting_token_account,
+                split_token_account,
+                vesting_account,
+                fee_mint.as_ref(),
+                &[&signer.signer_seeds()],
+                split_amount,
+            )
+            .ctx("transferring split tokens")?;
+        }
+
+        if destination_amount > 0 {
+            //sign with a pda coz token_vesting_account is a pda
+            transfer_fees += Self::transfer_tokens(
+                spl_token_account,
+                vesting_token_account,
+                destination_token_account,
+                vesting_account,
+                fee_mint.as_ref(),
+                &[&signer.signer_seeds()],
+                destination_amount,
+            )
+            .ctx("transferring released tokens")?;
+        }
+
+        // no fee is ever above what it's charged on, so this can't underflow
+        let net_amount = total_amount_to_transfer - transfer_fees;
+        msg!(
+            "unlocked {:?} gross, {:?} net of {:?} in transfer fees",
+            total_amount_to_transfer,
+            net_amount,
+            transfer_fees
+        );
+
+        // ----------------------------------------------------------------------------- update state
+        if capped && total_amount_to_transfer > 0 {
+            header_state.last_unlock_time = clock.unix_timestamp as u64;
+        }
+        if total_amount_to_transfer > 0 {
+            header_state.unlock_count = header_state.unlock_count.saturating_add(1);
+        }
+        header_state.unlocked_gross = header_state
+            .unlocked_gross
+            .saturating_add(total_amount_to_transfer);
+        header_state.unlocked_net = header_state.unlocked_net.saturating_add(net_amount);
+
+        // Reset released amounts to 0. This makes the simple unlock safe with complex scheduling contracts
+        if advances_cursor {
+            header_state.advance_unlock_cursor(&schedules, window_end);
+        } else if !bounded {
+            header_state.update_next_release_time(&schedules);
+            header_state.settle_legacy_destination(&schedules);
+        }
+        // whatever the cap, the minimum claim or the rollover left behind is still claimable
+        header_state.refresh_has_claimable(now);
+
+        // pay the relayer back, as long as the escrow can cover it. An empty escrow never blocks the unlock itself.
+        // Bounded unlocks that only moved the cursor don't earn one, or they could drain the escrow.
+        // The signer can also be a PDA destination owner signing through invoke_signed. Those often don't exist as
+        // accounts, and topping one up with less than rent would fail the whole transaction - so no rebate for them
+        if let Some(rebate_receiver) = rebate_receiver.filter(|_| total_amount_to_transfer > 0) {
+            let rebate = header_state.gas_rebate;
+            if rebate > 0
+                && rebate_receiver.is_signer
+                && rebate_receiver.lamports() > 0
+                && header_state.gas_escrow >= rebate
+            {
+                **vesting_account.try_borrow_mut_lamports()? -= rebate;
+                **rebate_receiver.try_borrow_mut_lamports()? += rebate;
+                header_state.gas_escrow -= rebate;
+                msg!("paid {:?} lamports of gas rebate", rebate);
+            }
+        }
+
+        Self::update_emission_curve(
+            program_id,
+            emission_curve_account,
+            &tracked_schedules,
+            &schedules,
+            clock.unix_timestamp as u64,
+        )?;
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(schedules, &mut packed_state.borrow_mut()[window]);
+
+        Ok(())
+    }
+
+    pub fn process_set_transfer_limit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        max_transfer_per_unlock: u64,
+        unlock_interval: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        // the cap slows down what the beneficiary can claim, so both sign
+        if header_state.granter_address != *granter_account.key {
+            msg!("Only the granter can set a transfer limit");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Self::check_transfer_limit(max_transfer_per_unlock, header_state.min_claim_amount)?;
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.max_transfer_per_unlock = max_transfer_per_unlock;
+        header_state.unlock_interval = unlock_interval;
+        if max_transfer_per_unlock == 0 {
+            header_state.last_unlock_time = 0;
+        }
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    /// A cap below the minimum claim would leave every capped unlock too small to go through
+    fn check_transfer_limit(max_transfer_per_unlock: u64, min_claim_amount: u64) -> ProgramResult {
+        if max_transfer_per_unlock > 0 && max_transfer_per_unlock < min_claim_amount {
+            msg!(
+                "max transfer per unlock {:?} is below the minimum claim of {:?}",
+                max_transfer_per_unlock,
+                min_claim_amount
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    pub fn process_set_withholding(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        withholding_bps: u16,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+        let withholding_token_account = next_account_info(accounts_iter).ok();
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if withholding_bps > MAX_WITHHOLDING_BPS {
+            return Err(VestingError::WithholdingTooLarge.into());
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        // the withheld share comes out of what the beneficiary gets, so both sign
+        if header_state.granter_address != *granter_account.key {
+            msg!("Only the granter can set withholding");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let withholding_destination = if withholding_bps > 0 {
+            let withholding_token_account = withholding_token_account.ok_or_else(|| {
+                msg!("The withholding token account has to be passed");
+                ProgramError::NotEnoughAccountKeys
+            })?;
+            // Unlock tells it apart from the destination by its key
+            if withholding_token_account.key == destination_token_account.key {
+                msg!("The withholding token account should differ from the destination");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let withholding_token_account_data =
+                Self::unpack_token_account(program_id, withholding_token_account)
+                    .ctx("unpacking withholding token account")?;
+            if withholding_token_account_data.mint != header_state.mint_address {
+                msg!("The withholding token account should hold the contract's mint");
+                return Err(ProgramError::InvalidArgument);
+            }
+            *withholding_token_account.key
+        } else {
+            Pubkey::default()
+        };
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.withholding_bps = withholding_bps;
+        header_state.withholding_destination = withholding_destination;
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_set_splits(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        splits: Vec<SplitDestination>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+        let split_token_accounts = accounts_iter.as_slice();
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        // the splits come out of what the beneficiary gets, so both sign
+        if header_state.granter_address != *granter_account.key {
+            msg!("Only the granter can set splits");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Unlock tells the split accounts apart from the destination and each other by their keys
+        let total_bps = splits.iter().map(|s| s.bps as u32).sum::<u32>();
+        if splits.len() > MAX_SPLIT_DESTINATIONS
+            || total_bps > MAX_SPLIT_BPS as u32
+            || splits.iter().enumerate().any(|(i, split)| {
+                split.bps == 0
+                    || split.destination_address == header_state.destination_address
+                    || splits[..i]
+                        .iter()
+                        .any(|s| s.destination_address == split.destination_address)
+            })
+        {
+            msg!(
+                "{:?} splits adding up to {:?} bps are not valid",
+                splits.len(),
+                total_bps
+            );
+            return Err(VestingError::InvalidSplits.into());
+        }
+
+        if split_token_accounts.len() < splits.len() {
+            msg!("The token account of every split has to be passed");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        for (split, split_token_account) in splits.iter().zip(split_token_accounts) {
+            if split.destination_address != *split_token_account.key {
+                msg!("Split token accounts should come in the order of the splits");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let split_token_account_data =
+                Self::unpack_token_account(program_id, split_token_account)
+                    .ctx("unpacking split token account")?;
+            if split_token_account_data.mint != header_state.mint_address {
+                msg!("Split token accounts should hold the contract's mint");
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.splits = splits;
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_close(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        dust_policy: DustPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+        let dust_receiver = next_account_info(accounts_iter)?;
+        let config_account = match dust_policy {
+            DustPolicy::CommunityTreasury => Some(next_account_info(accounts_iter)?),
+            DustPolicy::ReturnToGranter | DustPolicy::Burn => None,
+        };
+
+        // ----------------------------------------------------------------------------- checks
+        Self::check_token_program(spl_token_account)?;
+
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter.key {
+            msg!("Only the granter can close the contract");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("The vesting token account should be owned by the vesting account.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // released but unclaimed amounts still belong to the destination
+        header_state.apply(ContractEvent::Close)?;
+
+        // whoever receives the dust has to match the policy the granter picked
+        match dust_policy {
+            DustPolicy::ReturnToGranter | DustPolicy::CommunityTreasury => {
+                let expected_owner = match config_account {
+                    Some(config_account) => {
+                        if config_account.owner != program_id {
+                            msg!("The program config should be owned by the program");
+                            return Err(ProgramError::IncorrectProgramId);
+                        }
+                        let community_treasury =
+                            ProgramConfig::unpack(&config_account.data.borrow())
+                                .ctx("unpacking program config")?
+                                .community_treasury;
+                        if community_treasury == Pubkey::default() {
+                            msg!("No community treasury is configured");
+                            return Err(ProgramError::InvalidArgument);
+                        }
+                        community_treasury
+                    }
+                    None => *granter.key,
+                };
+                let dust_receiver_data = Self::unpack_token_account(program_id, dust_receiver)
+                    .ctx("unpacking dust receiver token account")?;
+                if dust_receiver_data.owner != expected_owner {
+                    msg!("The dust receiver isn't owned by {:?}", expected_owner);
+                    return Err(ProgramError::InvalidArgument);
+                }
+                if dust_receiver_data.mint != header_state.mint_address {
+                    msg!("The dust receiver should hold the vested mint");
+                    return Err(ProgramError::InvalidArgument);
+                }
+            }
+            DustPolicy::Burn => {
+                if *dust_receiver.key != header_state.mint_address {
+                    msg!("Burning needs the contract's mint");
+                    return Err(ProgramError::InvalidArgument);
+                }
+            }
+        }
+
+        let fee_mint =
+            Self::contract_fee_mint(vesting_token_account, &header_state.mint_address, accounts)?;
+
+        // ----------------------------------------------------------------------------- transfer
+        let dust = vesting_token_account_data.amount;
+        if dust > 0 {
+            msg!("{:?} left in the vesting token account", dust);
+            match dust_policy {
+                DustPolicy::ReturnToGranter | DustPolicy::CommunityTreasury => {
+                    Self::transfer_tokens(
+                        spl_token_account,
+                        vesting_token_account,
+                        dust_receiver,
+                        vesting_account,
+                        fee_mint.as_ref(),
+                        &[&signer.signer_seeds()],
+                        dust,
+                    )
+                    .ctx("moving the dust")?;
+                }
+                // burning moves the dust nowhere - the receiver is the mint then
+                DustPolicy::Burn => {
+                    Self::invoke_token(
+                        &burn(
+                            spl_token_account.key,
+                            vesting_token_account.key,
+                            dust_receiver.key,
+                            &vesting_account_key,
+                            &[],
+                            dust,
+                        )?,
+                        &[
+                            spl_token_account.clone(),
+                            vesting_token_account.clone(),
+                            dust_receiver.clone(),
+                            vesting_account.clone(),
+                        ],
+                        &[&signer.signer_seeds()],
+                        &[(vesting_token_account, -(dust as i128)), (dust_receiver, 0)],
+                    )
+                    .ctx("burning the dust")?;
+                }
+            }
+        }
+        if let Some(fee_mint) = &fee_mint {
+            Self::harvest_withheld_fees(spl_token_account, fee_mint, vesting_token_account)
+                .ctx("harvesting withheld transfer fees")?;
+        }
+
+        let close_vesting_token_account = close_account(
+            spl_token_account.key,
+            vesting_token_account.key,
+            granter.key,
+            &vesting_account_key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_vesting_token_account,
+            &[
+                spl_token_account.clone(),
+                vesting_token_account.clone(),
+                granter.clone(),
+                vesting_account.clone(),
+            ],
+            &[&signer.signer_seeds()],
+        )
+        .ctx("closing the vesting token account")?;
+
+        // ----------------------------------------------------------------------------- update state
+        // the runtime drops the account once it has no lamports. Until then only a Closed header is left, which
+        // Create won't write over
+        let lamports = vesting_account.lamports();
+        **vesting_account.try_borrow_mut_lamports()? = 0;
+        **granter.try_borrow_mut_lamports()? += lamports;
+        let mut data = vesting_account.data.borrow_mut();
+        data.fill(0);
+        header_state.pack_into_slice(&mut data);
+
+        Ok(())
+    }
+
+    /// invoke_signed for the CPIs that move tokens, `deltas` being the change each token account's balance is meant
+    /// to see. With the paranoid feature, any other change aborts the instruction (see paranoid.rs)
+    #[cfg_attr(not(all(feature = "paranoid", not(test))), allow(unused_variables))]
+    fn invoke_token<'a>(
+        instruction: &Instruction,
+        account_infos: &[AccountInfo<'a>],
+        signers_seeds: &[&[&[u8]]],
+        deltas: &[(&AccountInfo<'a>, i128)],
+    ) -> ProgramResult {
+        // the CPIs of off-chain unit tests don't move anything, so there would be nothing to check
+        #[cfg(all(feature = "paranoid", not(test)))]
+        let audit = crate::paranoid::TokenAudit::before(deltas);
+        invoke_signed(instruction, account_infos, signers_seeds)?;
+        #[cfg(all(feature = "paranoid", not(test)))]
+        audit.check()?;
+        Ok(())
+    }
+
+    /// Unpacks an account expected to be a token account. Any account could happen to be a token account's size - a
+    /// vesting account or config, or whatever another program lays out - so anything not owned by spl-token or
+    /// Token-2022 is turned away before its data is read as one
+    fn unpack_token_account(program_id: &Pubkey, account: &AccountInfo) -> VestingResult<Account> {
+        if !is_token_program(account.owner) {
+            if account.owner == program_id {
+                let account_type = account_type(&account.data.borrow());
+                msg!(
+                    "Expected a token account, got this program's {:?} account",
+                    account_type
+                );
+            } else {
+                msg!(
+                    "Expected a token account, got one owned by {}",
+                    account.owner
+                );
+            }
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(StateWithExtensions::<Account>::unpack(&account.data.borrow())?.base)
+    }
+
+    fn unpack_mint(account: &AccountInfo) -> VestingResult<Mint> {
+        Ok(StateWithExtensions::<Mint>::unpack(&account.data.borrow())?.base)
+    }
+
+    /// Derives the vesting account at `seeds` under the bump in `vesting_account`'s header, along with what it signs
+    /// with. Nothing but a vesting account this program created can be at the derived address, so whoever passed
+    /// `vesting_account` only gets a match for the real one - which is for the caller to check. A granted account is
+    /// only looked for once the plain derivation misses
+    fn vesting_signer(
+        program_id: &Pubkey,
+        vesting_account: &AccountInfo,
+        seeds: Seeds,
+    ) -> Result<(Pubkey, VestingSigner), ProgramError> {
+        let signer = VestingSigner::for_account(seeds, &vesting_account.try_borrow_data()?);
+        let key = signer.key(program_id);
+        if key.as_ref() != Ok(vesting_account.key) {
+            let granted = signer.granted();
+            if granted.key(program_id).as_ref() == Ok(vesting_account.key) {
+                return Ok((*vesting_account.key, granted));
+            }
+        }
+        Ok((key.ctx("deriving vesting account")?, signer))
+    }
+
+    /// vesting_signer for an account that doesn't exist yet, so has no header to take the bump from. Seeds that end in
+    /// their own bump are a legacy contract's, anything else gets the bump find_program_address picks
+    fn new_vesting_signer(
+        program_id: &Pubkey,
+        vesting_account: &AccountInfo,
+        seeds: Seeds,
+    ) -> Result<VestingSigner, ProgramError> {
+        if Pubkey::create_program_address(&[&seeds], program_id).as_ref() == Ok(vesting_account.key)
+        {
+            return Ok(VestingSigner::new(seeds, LEGACY_BUMP));
+        }
+        let (key, bump) = find_vesting_address_for_seeds(&seeds, program_id);
+        if key != *vesting_account.key {
+            msg!("Provided vesting account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(VestingSigner::new(seeds, bump))
+    }
+
+    fn check_token_program(spl_token_account: &AccountInfo) -> ProgramResult {
+        if !is_token_program(spl_token_account.key) {
+            msg!("The provided spl token program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// Turns away a Token-2022 vesting token account carrying anything but VESTING_TOKEN_ACCOUNT_EXTENSIONS. Only
+    /// checked when the contract takes the account over: the extensions an owner could add later need the vesting
+    /// account's signature, which this program never gives for that
+    fn check_token_account_extensions(vesting_token_account: &AccountInfo) -> ProgramResult {
+        let data = vesting_token_account.data.borrow();
+        let state = StateWithExtensions::<Account>::unpack(&data)?;
+        for extension in state.get_extension_types()? {
+            if !VESTING_TOKEN_ACCOUNT_EXTENSIONS.contains(&extension) {
+                msg!(
+                    "vesting token account has the {:?} extension, which contracts can't hold tokens under",
+                    extension
+                );
+                return Err(VestingError::IncompatibleTokenExtension.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the mint of `token_account` charges transfer fees - Token-2022 then gives each of its token accounts
+    /// a TransferFeeAmount
+    fn has_transfer_fees(token_account: &AccountInfo) -> VestingResult<bool> {
+        let data = token_account.data.borrow();
+        Ok(StateWithExtensions::<Account>::unpack(&data)?
+            .get_extension_types()?
+            .contains(&ExtensionType::TransferFeeAmount))
+    }
+
+    /// Finds the mint at `mint_key` among `accounts` and reads its transfer fee, for a contract whose vesting token
+    /// account has_transfer_fees
+    fn fee_mint<'a, 'b>(
+        mint_key: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        epoch: Epoch,
+    ) -> VestingResult<FeeMint<'a, 'b>> {
+        let account = accounts.iter().find(|a| a.key == mint_key).ok_or_else(|| {
+            msg!("The mint charges transfer fees, so it has to be passed");
+            ProgramError::NotEnoughAccountKeys
+        })?;
+        if *account.owner != spl_token_2022::id() {
+            msg!("mint should be owned by the Token-2022 program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let data = account.data.borrow();
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        Ok(FeeMint {
+            account,
+            decimals: mint.base.decimals,
+            transfer_fee_config: *mint.get_extension::<TransferFeeConfig>()?,
+            epoch,
+        })
+    }
+
+    /// fee_mint for the contract whose vesting token account is `vesting_token_account`, None unless its mint charges
+    /// transfer fees. The epoch is read from the clock sysvar if it's among `accounts`, from the Clock syscall if not
+    fn contract_fee_mint<'a, 'b>(
+        vesting_token_account: &AccountInfo,
+        mint_key: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> VestingResult<Option<FeeMint<'a, 'b>>> {
+        if !Self::has_transfer_fees(vesting_token_account)? {
+            return Ok(None);
+        }
+        let clock = match accounts.iter().find(|a| sysvar::clock::check_id(a.key)) {
+            Some(clock_sysvar_account) => Clock::from_account_info(clock_sysvar_account),
+            None => Clock::get(),
+        }
+        .ctx("reading clock")?;
+        Self::fee_mint(mint_key, accounts, clock.epoch).map(Some)
+    }
+
+    /// Moves the fees withheld on `token_account` (from the transfers into it) to the mint. Token-2022 won't close
+    /// an account still holding some, and anyone may harvest them
+    fn harvest_withheld_fees<'a>(
+        spl_token_account: &AccountInfo<'a>,
+        fee_mint: &FeeMint<'_, 'a>,
+        token_account: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        invoke(
+            &harvest_withheld_tokens_to_mint(
+                spl_token_account.key,
+                fee_mint.account.key,
+                &[token_account.key],
+            )?,
+            &[
+                spl_token_account.clone(),
+                fee_mint.account.clone(),
+                token_account.clone(),
+            ],
+        )
+    }
+
+    /// Transfers `amount` out of `source`, signed by `authority` - through TransferCheckedWithFee when there's a
+    /// `fee_mint`. Returns the fee, which `destination` receives that much short of `amount`
+    fn transfer_tokens<'a>(
+        spl_token_account: &AccountInfo<'a>,
+        source: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        fee_mint: Option<&FeeMint<'_, 'a>>,
+        signers_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> VestingResult<u64> {
+        let mut account_infos = vec![
+            spl_token_account.clone(),
+            source.clone(),
+            destination.clone(),
+            authority.clone(),
+        ];
+        let (instruction, fee) = match fee_mint {
+            Some(fee_mint) => {
+                let fee = fee_mint.fee(amount)?;
+                account_infos.push(fee_mint.account.clone());
+                let instruction = transfer_checked_with_fee(
+                    spl_token_account.key,
+                    source.key,
+                    fee_mint.account.key,
+                    destination.key,
+                    authority.key,
+                    &[],
+                    amount,
+                    fee_mint.decimals,
+                    fee,
+                )?;
+                (instruction, fee)
+            }
+            None => {
+                let instruction = transfer(
+                    spl_token_account.key,
+                    source.key,
+                    destination.key,
+                    authority.key,
+                    &[],
+                    amount,
+                )?;
+                (instruction, 0)
+            }
+        };
+
+        Self::invoke_token(
+            &instruction,
+            &account_infos,
+            signers_seeds,
+            &[
+                (source, -(amount as i128)),
+                (destination, (amount - fee) as i128),
+            ],
+        )?;
+        Ok(fee)
+    }
+
+    /// Checks the required accounts of an instruction against its spec - the optional ones are up to the instruction
+    fn check_accounts(specs: &[AccountSpec], accounts: &[AccountInfo]) -> ProgramResult {
+        let required = specs.iter().take_while(|s| !s.optional).count();
+        if accounts.len() < required {
+            msg!(
+                "expected at least {} accounts, got {}",
+                required,
+                accounts.len()
+            );
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        for (spec, account) in specs[..required].iter().zip(accounts) {
+            if spec.signer && !account.is_signer {
+                msg!("{} should be a signer", spec.name);
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if spec.writable && !account.is_writable {
+                msg!("{} should be writable", spec.name);
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+        Ok(())
+    }
+
+    /// Only ProgramConfig ever writes its account type, and only to the config PDA - so owner and type are enough
+    fn is_program_config(program_id: &Pubkey, account: &AccountInfo) -> bool {
+        account.owner == program_id
+            && account_type(&account.data.borrow()) == Some(AccountType::ProgramConfig)
+    }
+
+    /// The clock as Unlock sees it, ahead by the early claim tolerance of the program config when it's among
+    /// `accounts`. Revoke goes by it too, so that it never takes back what Unlock would already pay out
+    fn unlock_time(
+        program_id: &Pubkey,
+        clock: &Clock,
+        accounts: &[AccountInfo],
+    ) -> VestingResult<u64> {
+        let early_claim_tolerance_seconds = match accounts
+            .iter()
+            .find(|a| Self::is_program_config(program_id, a))
+        {
+            Some(config_account) => {
+                ProgramConfig::unpack(&config_account.data.borrow())
+                    .ctx("unpacking program config")?
+                    .early_claim_tolerance_seconds
+            }
+            None => 0,
+        };
+        Ok((clock.unix_timestamp as u64).saturating_add(early_claim_tolerance_seconds as u64))
+    }
+
+    pub fn process_init_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        admin: &Pubkey,
+        early_claim_tolerance_seconds: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let config_account = next_account_info(accounts_iter)?;
+        let program_data_account = next_account_info(accounts_iter)?;
+        let upgrade_authority = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (config_key, bump) = Pubkey::find_program_address(&[CONFIG_SEED], program_id);
+        if config_key != *config_account.key {
+            msg!("Provided program config account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // whoever can upgrade the program decides who the admin is
+        let (program_data_key, _) =
+            Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        if program_data_key != *program_data_account.key
+            || program_data_account.owner != &bpf_loader_upgradeable::id()
+        {
+            msg!("Provided program data account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let upgrade_authority_address =
+            match bincode::deserialize(&program_data_account.data.borrow()) {
+                Ok(UpgradeableLoaderState::ProgramData {
+                    upgrade_authority_address,
+                    ..
+                }) => upgrade_authority_address,
+                _ => {
+                    msg!("Provided program data account is invalid");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+            };
+        if upgrade_authority_address != Some(*upgrade_authority.key) || !upgrade_authority.is_signer
+        {
+            msg!("The program's upgrade authority has to sign");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if early_claim_tolerance_seconds > MAX_EARLY_CLAIM_TOLERANCE_SECONDS {
+            msg!(
+                "early claim tolerance can't be more than {:?} seconds",
+                MAX_EARLY_CLAIM_TOLERANCE_SECONDS
+            );
+            return Err(VestingError::EarlyClaimToleranceTooLarge.into());
+        }
+
+        // ----------------------------------------------------------------------------- create
+        let rent = Rent::from_account_info(rent_sysvar_account).ctx("reading rent sysvar")?;
+        invoke_signed(
+            &create_account(
+                payer.key,
+                &config_key,
+                rent.minimum_balance(ProgramConfig::LEN),
+                ProgramConfig::LEN as u64,
+                program_id,
+            ),
+            &[
+                system_program_account.clone(),
+                payer.clone(),
+                config_account.clone(),
+            ],
+            &[&[CONFIG_SEED, &[bump]]],
+        )
+        .ctx("creating program config")?;
+
+        let config = ProgramConfig {
+            admin: *admin,
+            early_claim_tolerance_seconds,
+            community_treasury: Pubkey::default(),
+            forbid_self_dealing: false,
+            is_initialized: true,
+        };
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+        Ok(())
+    }
+
+    pub fn process_set_early_claim_tolerance(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        early_claim_tolerance_seconds: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let config_account = next_account_info(accounts_iter)?;
+        let admin = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if config_account.owner != program_id {
+            msg!("The program config should be owned by the program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut config =
+            ProgramConfig::unpack(&config_account.data.borrow()).ctx("unpacking program config")?;
+
+        if config.admin != *admin.key || !admin.is_signer {
+            msg!("The config admin has to sign");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if early_claim_tolerance_seconds > MAX_EARLY_CLAIM_TOLERANCE_SECONDS {
+            msg!(
+                "early claim tolerance can't be more than {:?} seconds",
+                MAX_EARLY_CLAIM_TOLERANCE_SECONDS
+            );
+            return Err(VestingError::EarlyClaimToleranceTooLarge.into());
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        config.early_claim_tolerance_seconds = early_claim_tolerance_seconds;
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+        Ok(())
+    }
+
+    pub fn process_set_community_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        community_treasury: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let config_account = next_account_info(accounts_iter)?;
+        let admin = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if config_account.owner != program_id {
+            msg!("The program config should be owned by the program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut config =
+            ProgramConfig::unpack(&config_account.data.borrow()).ctx("unpacking program config")?;
+
+        if config.admin != *admin.key || !admin.is_signer {
+            msg!("The config admin has to sign");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        config.community_treasury = *community_treasury;
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+        Ok(())
+    }
+
+    pub fn process_set_forbid_self_dealing(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        forbid_self_dealing: bool,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let config_account = next_account_info(accounts_iter)?;
+        let admin = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if config_account.owner != program_id {
+            msg!("The program config should be owned by the program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut config =
+            ProgramConfig::unpack(&config_account.data.borrow()).ctx("unpacking program config")?;
+
+        if config.admin != *admin.key || !admin.is_signer {
+            msg!("The config admin has to sign");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        config.forbid_self_dealing = forbid_self_dealing;
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+        Ok(())
+    }
+
+    /// Handles both ChangeDestination and ChangeFutureDestination - the latter passes the cutoff
+    pub fn process_change_destination(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        cutoff: Option<u64>,
+        expected_revision: Option<u32>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+        let new_destination_token_account = next_account_info(accounts_iter)?;
+        // optional, all or nothing
+        let destination_indexes = match (
+            next_account_info(accounts_iter),
+            next_account_info(accounts_iter),
+            next_account_info(accounts_iter),
+        ) {
+            (Ok(system_program_account), Ok(current_index), Ok(new_index)) => {
+                Some((system_program_account, current_index, new_index))
+            }
+            _ => None,
+        };
+        let indexed_contract = accounts_iter.next();
+
+        // ----------------------------------------------------------------------------- checks
+        // check vesting account matches
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // check destination account matches
+        let state = VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if state.destination_locked {
+            msg!("This contract's destination is locked");
+            return Err(VestingError::DestinationLocked.into());
+        }
+        Self::check_revision(&state, expected_revision)?;
+
+        if state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // check signer (dest acc) present
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.owner != *destination_token_account_owner.key {
+            msg!("The current destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // tokens sent to an account of another mint would be stuck for good, as every Unlock to it would fail
+        let new_destination_token_account_data =
+            Self::unpack_token_account(program_id, new_destination_token_account)
+                .ctx("unpacking new destination token account")?;
+        if new_destination_token_account_data.mint != state.mint_address {
+            msg!("The new destination token account is for another mint");
+            return Err(VestingError::DestinationMintMismatch.into());
+        }
+
+        let schedules = unpack_schedules(
+            &vesting_account.data.borrow()[VestingScheduleHeader::LEN..state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+
+        // ----------------------------------------------------------------------------- core
+        //get a mutable copy of state
+        let mut new_state = state;
+        // once everything has been claimed there's nothing left to redirect
+        new_state.apply(ContractEvent::Modify)?;
+        if let Some(cutoff) = cutoff {
+            if new_state.legacy_cutoff > 0 {
+                msg!("The previous destination should claim everything before its cutoff first");
+                return Err(ProgramError::InvalidArgument);
+            }
+            // rolling would move the previous destination's schedules past the cutoff
+            if new_state.roll_into_final {
+                msg!("Can't split the destination of a contract rolling into its final schedule");
+                return Err(ProgramError::InvalidArgument);
+            }
+            // the cutoff splits schedules by release time, and a linear contract's only one releases over a range
+            if new_state.schedule_layout == ScheduleLayout::Linear {
+                msg!("Can't split the destination of a linear contract");
+                return Err(ProgramError::InvalidArgument);
+            }
+            if new_state.destination_address == *new_destination_token_account.key {
+                msg!("The new destination should differ from the current one");
+                return Err(ProgramError::InvalidArgument);
+            }
+            // only worth a split if the current destination still has something coming before the cutoff
+            new_state.legacy_destination_address = new_state.destination_address;
+            new_state.legacy_cutoff = cutoff;
+            new_state.settle_legacy_destination(&schedules);
+            new_state.pack_into_slice(
+                &mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN],
+            );
+        }
+        //update the address
+        write_destination(
+            &mut vesting_account.data.borrow_mut(),
+            new_destination_token_account.key,
+        );
+        Self::record_revision(vesting_account)?;
+
+        // ----------------------------------------------------------------------------- destination indexes
+        if let Some((system_program_account, current_index, new_index)) = destination_indexes {
+            Self::move_destination_indexes(
+                program_id,
+                &new_state,
+                destination_token_account.key,
+                new_destination_token_account.key,
+                &vesting_account_key,
+                destination_token_account_owner,
+                system_program_account,
+                (current_index, new_index, indexed_contract),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the contract from the destination index of `destination` to the one of `new_destination`, once its
+    /// header `state` points at the new one. The index of a legacy destination that still has something to claim
+    /// keeps pointing here. `payer` covers the new index if it has to be created
+    fn move_destination_indexes<'a>(
+        program_id: &Pubkey,
+        state: &VestingScheduleHeader,
+        destination: &Pubkey,
+        new_destination: &Pubkey,
+        vesting_account_key: &Pubkey,
+        payer: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        (current_index, new_index, indexed_contract): (
+            &AccountInfo<'a>,
+            &AccountInfo<'a>,
+            Option<&AccountInfo<'a>>,
+        ),
+    ) -> ProgramResult {
+        // a legacy destination still has something to claim, so it keeps finding the contract
+        let still_legacy =
+            state.legacy_cutoff > 0 && state.legacy_destination_address == *destination;
+        if !still_legacy {
+            Self::clear_destination_index(
+                program_id,
+                current_index,
+                &state.mint_address,
+                destination,
+                vesting_account_key,
+            )?;
+        }
+        Self::check_destination_index_free(
+            program_id,
+            new_index,
+            &state.mint_address,
+            new_destination,
+            vesting_account_key,
+            indexed_contract,
+        )?;
+        Self::write_destination_index(
+            program_id,
+            new_index,
+            &state.mint_address,
+            new_destination,
+            vesting_account_key,
+            payer,
+            system_program_account,
+        )
+    }
+
+    pub fn process_transfer_beneficiary(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        rent_payer: RentPayer,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+        let new_destination_token_account = next_account_info(accounts_iter)?;
+        let new_owner = next_account_info(accounts_iter)?;
+        let mint_account = next_account_info(accounts_iter)?;
+        let system_program_account = next_account_info(accounts_iter)?;
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let associated_token_program_account = next_account_info(accounts_iter)?;
+        // the rent payer comes last, so that it can't be mistaken for an index
+        let mut remaining_accounts: Vec<&AccountInfo> = accounts_iter.collect();
+        let rent_payer_account = match rent_payer {
+            RentPayer::Beneficiary => None,
+            RentPayer::Cranker | RentPayer::GranterEscrow => Some(
+                remaining_accounts
+                    .pop()
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?,
+            ),
+        };
+        // optional, both or neither
+        let destination_indexes = match remaining_accounts[..] {
+            [current_index, new_index, ref rest @ ..] => {
+                Some((current_index, new_index, rest.first().copied()))
+            }
+            _ => None,
+        };
+
+        // ----------------------------------------------------------------------------- checks
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::check_token_program(spl_token_account)?;
+        if *associated_token_program_account.key != spl_associated_token_account::id() {
+            msg!("The provided associated token account program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut state = VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+            .ctx("unpacking vesting account header")?;
+        if state.mint_address != *mint_account.key {
+            msg!("The provided mint isn't the contract's");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let new_destination_key = get_associated_token_address(new_owner.key, mint_account.key);
+        if new_destination_key != *new_destination_token_account.key {
+            msg!("The new destination should be the new owner's associated token account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if new_destination_key == state.destination_address {
+            msg!("The new owner's associated token account is already the destination");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // only the owner gets to have the new account created, whoever pays for it
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if let Some(rent_payer_account) = rent_payer_account {
+            if rent_payer == RentPayer::GranterEscrow
+                && *rent_payer_account.key != state.granter_address
+            {
+                msg!("Only the granter can spend the gas escrow");
+                return Err(ProgramError::InvalidArgument);
+            }
+            if !rent_payer_account.is_signer {
+                msg!("The rent payer should be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+
+        // ----------------------------------------------------------------------------- new destination
+        let (funder, signer) = match (rent_payer, rent_payer_account) {
+            (RentPayer::Cranker, Some(cranker)) => (cranker, None),
+            (RentPayer::GranterEscrow, Some(_)) => {
+                let (vesting_account_key, signer) =
+                    Self::vesting_signer(program_id, vesting_account, seeds)?;
+                if vesting_account_key != *vesting_account.key {
+                    msg!("Invalid vesting account key");
+                    return Err(ProgramError::InvalidArgument);
+                }
+
+                // the vesting account carries data, so the system program won't take lamports out of it. Paying for
+                // the new account up front leaves the associated token account program nothing to transfer
+                if new_destination_token_account.data_is_empty() {
+                    let rent = Rent::get().ctx("reading rent")?;
+                    let account_len =
+                        associated_token_account_len(spl_token_account, mint_account)?;
+                    let required_lamports = rent
+                        .minimum_balance(account_len)
+                        .max(1)
+                        .saturating_sub(new_destination_token_account.lamports());
+                    if required_lamports > state.gas_escrow {
+                        msg!(
+                            "The gas escrow holds {:?} lamports, the new destination needs {:?}",
+                            state.gas_escrow,
+                            required_lamports
+                        );
+                        return Err(ProgramError::InsufficientFunds);
+                    }
+                    **vesting_account.try_borrow_mut_lamports()? -= required_lamports;
+                    **new_destination_token_account.try_borrow_mut_lamports()? += required_lamports;
+                    state.gas_escrow -= required_lamports;
+                    state.pack_into_slice(
+                        &mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN],
+                    );
+                }
+                (vesting_account, Some(signer))
+            }
+            _ => (destination_token_account_owner, None),
+        };
+
+        // idempotent, so a new owner who already has the account is no different
+        let instruction = create_associated_token_account_idempotent(
+            funder.key,
+            new_owner.key,
+            mint_account.key,
+            spl_token_account.key,
+        );
+        let account_infos = [
+            funder.clone(),
+            new_destination_token_account.clone(),
+            new_owner.clone(),
+            mint_account.clone(),
+            system_program_account.clone(),
+            spl_token_account.clone(),
+            associated_token_program_account.clone(),
+        ];
+        match signer {
+            Some(signer) => invoke_signed(&instruction, &account_infos, &[&signer.signer_seeds()]),
+            None => invoke(&instruction, &account_infos),
+        }
+        .ctx("creating the new owner's associated token account")?;
+
+        // ----------------------------------------------------------------------------- change destination
+        // from here on it's a plain ChangeDestination, locked destinations, its checks and the indexes included
+        let mut change_destination_accounts = vec![
+            vesting_account.clone(),
+            destination_token_account.clone(),
+            destination_token_account_owner.clone(),
+            new_destination_token_account.clone(),
+        ];
+        if let Some((current_index, new_index, indexed_contract)) = destination_indexes {
+            change_destination_accounts.extend([
+                system_program_account.clone(),
+                current_index.clone(),
+                new_index.clone(),
+            ]);
+            change_destination_accounts.extend(indexed_contract.cloned());
+        }
+        Self::process_change_destination(
+            program_id,
+            &change_destination_accounts,
+            seeds,
+            None,
+            None,
+        )
+    }
+
+    pub fn process_init_destination_approvers(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        threshold: u8,
+        approvers: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let approvers_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (approvers_key, bump) = find_destination_approvers(program_id, &vesting_account_key);
+        if approvers_key != *approvers_account.key {
+            msg!("Provided destination approvers account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        // approvers take the destination away from its owner, so both sign
+        if header_state.granter_address != *granter_account.key {
+            msg!("Only the granter can set destination approvers");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // covers a second Init too, since approvers lock the destination
+        if header_state.destination_locked {
+            msg!("This contract's destination is already locked");
+            return Err(VestingError::DestinationLocked.into());
+        }
+
+        if approvers.is_empty()
+            || approvers.len() > MAX_DESTINATION_APPROVERS
+            || threshold == 0
+            || threshold as usize > approvers.len()
+        {
+            msg!(
+                "{:?} of {:?} approvers is not a valid threshold",
+                threshold,
+                approvers.len()
+            );
+            return Err(VestingError::InvalidApprovers.into());
+        }
+        // a duplicate would count twice towards the threshold
+        for (i, approver) in approvers.iter().enumerate() {
+            if approvers[..i].contains(approver) {
+                msg!("Approver {:?} is listed twice", approver);
+                return Err(VestingError::InvalidApprovers.into());
+            }
+        }
+
+        // ----------------------------------------------------------------------------- core
+        if approvers_account.owner != program_id {
+            let rent = Rent::get().ctx("reading rent")?;
+            invoke_signed(
+                &create_account(
+                    payer.key,
+                    &approvers_key,
+                    rent.minimum_balance(DestinationApprovers::LEN),
+                    DestinationApprovers::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    system_program_account.clone(),
+                    payer.clone(),
+                    approvers_account.clone(),
+                ],
+                &[&[
+                    DESTINATION_APPROVERS_SEED,
+                    vesting_account_key.as_ref(),
+                    &[bump],
+                ]],
+            )
+            .ctx("creating destination approvers")?;
+        }
+
+        let approvers_state = DestinationApprovers {
+            vesting_account: vesting_account_key,
+            threshold,
+            votes: vec![Pubkey::default(); approvers.len()],
+            approvers,
+            is_initialized: true,
+        };
+        approvers_state.pack_into_slice(&mut approvers_account.data.borrow_mut());
+
+        header_state.destination_locked = true;
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_approve_destination_change(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        new_destination: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let approvers_account = next_account_info(accounts_iter)?;
+        let approver = next_account_info(accounts_iter)?;
+        let new_destination_token_account = next_account_info(accounts_iter)?;
+        // optional, all or nothing - same as ChangeDestination's
+        let destination_indexes = match (
+            next_account_info(accounts_iter),
+            next_account_info(accounts_iter),
+            next_account_info(accounts_iter),
+        ) {
+            (Ok(system_program_account), Ok(current_index), Ok(new_index)) => {
+                Some((system_program_account, current_index, new_index))
+            }
+            _ => None,
+        };
+        let indexed_contract = accounts_iter.next();
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (approvers_key, _) = find_destination_approvers(program_id, &vesting_account_key);
+        if approvers_key != *approvers_account.key || *approvers_account.owner != *program_id {
+            msg!("Provided destination approvers account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut approvers_state = DestinationApprovers::unpack(&approvers_account.data.borrow())
+            .ctx("unpacking destination approvers")?;
+
+        let approver_index = match approvers_state
+            .approvers
+            .iter()
+            .position(|a| a == approver.key)
+        {
+            Some(i) => i,
+            None => {
+                msg!("{:?} is not an approver of this contract", approver.key);
+                return Err(ProgramError::InvalidArgument);
+            }
+        };
+        if !approver.is_signer {
+            msg!("The approver should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let mut state = VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if *new_destination_token_account.key != *new_destination {
+            msg!("The new destination account does not match the approved one");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if state.destination_address == *new_destination {
+            msg!("The new destination should differ from the current one");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let new_destination_token_account_data =
+            Self::unpack_token_account(program_id, new_destination_token_account)
+                .ctx("unpacking new destination token account")?;
+        if new_destination_token_account_data.mint != state.mint_address {
+            msg!("The new destination token account is for another mint");
+            return Err(VestingError::DestinationMintMismatch.into());
+        }
+
+        // once everything has been claimed there's nothing left to redirect
+        state.apply(ContractEvent::Modify)?;
+
+        // ----------------------------------------------------------------------------- core
+        // every approver only moves its own vote, so none can hold back the others
+        if approvers_state.vote(approver_index, *new_destination) {
+            msg!(
+                "Threshold reached, moving the destination to {:?}",
+                new_destination
+            );
+            write_destination(&mut vesting_account.data.borrow_mut(), new_destination);
+            Self::record_revision(vesting_account)?;
+
+            if let Some((system_program_account, current_index, new_index)) = destination_indexes {
+                Self::move_destination_indexes(
+                    program_id,
+                    &state,
+                    &state.destination_address,
+                    new_destination,
+                    &vesting_account_key,
+                    approver,
+                    system_program_account,
+                    (current_index, new_index, indexed_contract),
+                )?;
+            }
+        }
+        approvers_state.pack_into_slice(&mut approvers_account.data.borrow_mut());
+
+        Ok(())
+    }
+
+    pub fn process_migrate_layout(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let legacy_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *legacy_account.owner != *program_id {
+            msg!("Only the program's own accounts can be migrated");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !is_baseline_layout(&legacy_account.data.borrow()) {
+            msg!("The account isn't a contract on the baseline layout");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- core
+        let new_len = upgraded_baseline_len(legacy_account.data_len());
+        let rent = Rent::get().ctx("reading rent")?;
+        let missing_lamports = rent
+            .minimum_balance(new_len)
+            .saturating_sub(legacy_account.lamports());
+        if missing_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, legacy_account.key, missing_lamports),
+                &[
+                    payer.clone(),
+                    legacy_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )
+            .ctx("topping up rent")?;
+        }
+
+        legacy_account
+            .realloc(new_len, false)
+            .ctx("growing the account")?;
+        upgrade_baseline_layout(&mut legacy_account.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    pub fn process_compact(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let rent_receiver = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if rent_receiver.key == vesting_account.key {
+            msg!("The rent receiver can't be the vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        // the granter paid for the account, so they decide where the rent goes
+        if header_state.granter_address != *granter_account.key {
+            msg!("Only the granter can compact a contract");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let schedules = unpack_schedules(
+            &vesting_account.data.borrow()
+                [VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        // nothing would be left of an exhausted contract, Close is for those
+        header_state.apply(ContractEvent::Modify)?;
+        // the LinearVesting only makes sense next to its one schedule
+        if header_state.schedule_layout == ScheduleLayout::Linear {
+            msg!("A linear contract has nothing to compact");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if schedules.iter().all(|s| s.amount > 0) {
+            msg!("No paid out schedules to drop");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_account).ctx("reading rent sysvar")?;
+
+        // ----------------------------------------------------------------------------- core
+        let new_len = compact_schedules(&mut header_state, &mut vesting_account.data.borrow_mut())
+            .ctx("compacting schedules")?;
+
+        // the gas escrow is in lamports too, and stays with the contract
+        let keep = rent
+            .minimum_balance(new_len)
+            .saturating_add(header_state.gas_escrow);
+        let refund = vesting_account.lamports().saturating_sub(keep);
+        msg!(
+            "{:?} schedules left, refunding {:?} lamports",
+            header_state.schedule_count,
+            refund
+        );
+        **vesting_account.try_borrow_mut_lamports()? -= refund;
+        **rent_receiver.try_borrow_mut_lamports()? += refund;
+
+        vesting_account
+            .realloc(new_len, false)
+            .ctx("shrinking the vesting account")?;
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    /// Checks that the instruction before the current one is an ed25519 program instruction verifying
+    /// `destination_owner`'s signature over the claim permit for this contract and its current `nonce`
+    fn check_claim_permit(
+        program_id: &Pubkey,
+        instructions_sysvar_account: &AccountInfo,
+        vesting_account_key: &Pubkey,
+        destination_owner: &Pubkey,
+        expiry: i64,
+        nonce: u64,
+        now: i64,
+    ) -> VestingResult {
+        if *instructions_sysvar_account.key != sysvar::instructions::id() {
+            msg!("The provided instructions sysvar account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if now >= expiry {
+            msg!("claim permit expired at {:?}", expiry);
+            return Err(VestingError::PermitExpired.into());
+        }
+
+        let current_index =
+            sysvar::instructions::load_current_index_checked(instructions_sysvar_account)
+                .ctx("reading instructions sysvar")?;
+        if current_index == 0 {
+            msg!("the ed25519 instruction should come right before this one");
+            return Err(VestingError::InvalidPermit.into());
+        }
+        let verify_ix = sysvar::instructions::load_instruction_at_checked(
+            current_index as usize - 1,
+            instructions_sysvar_account,
+        )
+        .ctx("loading the ed25519 instruction")?;
+        if verify_ix.program_id != solana_program::ed25519_program::id() {
+            msg!("the instruction before this one isn't an ed25519 program instruction");
+            return Err(VestingError::InvalidPermit.into());
+        }
+
+        let (signer, message) =
+            parse_ed25519_instruction(&verify_ix.data).ok_or(VestingError::InvalidPermit)?;
+        if signer != destination_owner.as_ref()
+            || message != claim_permit_message(program_id, vesting_account_key, expiry, nonce)
+        {
+            // PDAs have no private key, so a PDA owner always ends up here
+            msg!("claim permit wasn't signed by the destination owner for this contract");
+            return Err(VestingError::InvalidPermit.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn process_set_unlock_policy(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        policy: UnlockPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let destination_token_account =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.unlock_policy = policy;
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_verify_solvency(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+        if vesting_token_account_data.owner != vesting_account_key
+            || vesting_token_account_data.mint != header_state.mint_address
+        {
+            msg!("The vesting token account should be the vesting account's, for the contract's mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- core
+        let schedules = unpack_schedules(
+            &vesting_account.data.borrow()
+                [VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        let mut remaining: u64 = 0;
+        for s in schedules.iter() {
+            remaining = remaining
+                .checked_add(s.amount)
+                .ok_or(ProgramError::InvalidAccountData)?;
+        }
+
+        // more than enough is fine - anyone can send tokens to the vesting token account
+        let insolvent = vesting_token_account_data.amount < remaining;
+        msg!(
+            "solvency: vesting account {}, balance {}, remaining {}, insolvent {}",
+            vesting_account_key,
+            vesting_token_account_data.amount,
+            remaining,
+            insolvent
+        );
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.insolvent = insolvent;
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+
+        Ok(())
+    }
+
+    pub fn process_refresh_claimable(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+        let clock = Clock::from_account_info(clock_sysvar_account).ctx("reading clock sysvar")?;
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.refresh_has_claimable(clock.unix_timestamp as u64);
+        msg!("has claimable: {:?}", header_state.has_claimable);
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+
+        Ok(())
+    }
+
+    pub fn process_check_revision(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        expected_revision: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let header_state = VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+            .ctx("unpacking vesting account header")?;
+        Self::check_revision(&header_state, Some(expected_revision))
+    }
+
+    /// Fails with StaleRevision unless the contract is still at `expected_revision`, if there is one
+    fn check_revision(
+        header: &VestingScheduleHeader,
+        expected_revision: Option<u32>,
+    ) -> ProgramResult {
+        match expected_revision {
+            Some(expected_revision) if expected_revision != header.revision => {
+                msg!(
+                    "contract is at revision {:?}, expected {:?}",
+                    header.revision,
+                    expected_revision
+                );
+                Err(VestingError::StaleRevision.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a change the granter or the destination owner just made to the contract, once its header is written.
+    /// Unlock, RefreshClaimable and the other instructions anyone can send leave the revision alone, so a crank
+    /// doesn't turn every client's CheckRevision stale
+    fn record_revision(vesting_account: &AccountInfo) -> ProgramResult {
+        let revision = bump_revision(&mut vesting_account.data.borrow_mut())?;
+        sol_log_data(&[
+            REVISION_EVENT,
+            vesting_account.key.as_ref(),
+            &revision.to_le_bytes(),
+        ]);
+        Ok(())
+    }
+
+    pub fn process_accelerate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if !granter.is_signer {
+            msg!("granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter.key {
+            msg!("Only the granter of the contract can accelerate it");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // schedules releasing before the cutoff go to the previous destination, so moving release times moves tokens
+        if header_state.legacy_cutoff > 0 {
+            msg!("The previous destination should claim everything before its cutoff first");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // once everything has been claimed there's nothing left to release
+        header_state.apply(ContractEvent::Modify)?;
+
+        let emission_curve_account =
+            Self::emission_curve_account(program_id, &header_state, accounts)?;
+
+        // ----------------------------------------------------------------------------- core
+        let now = Clock::from_account_info(clock_sysvar_account)
+            .ctx("reading clock sysvar")?
+            .unix_timestamp as u64;
+        let mut schedules = unpack_schedules(
+            &packed_state.borrow()[VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        let tracked_schedules = match emission_curve_account {
+            Some(_) => schedules.clone(),
+            None => vec![],
+        };
+        let mut linear =
+            unpack_linear(&header_state, &packed_state.borrow()).ctx("unpacking linear vesting")?;
+
+        let mut accelerated: u64 = 0;
+        match linear.as_mut() {
+            // a linear contract ends now, having released its whole grant
+            Some(linear) => {
+                accelerated = schedules[0].amount - linear_claimable(&schedules[0], linear, now);
+                let grant_amount = linear
+                    .grant_amount()
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                *linear = LinearVesting {
+                    cliff_time: 0,
+                    cliff_amount: 0,
+                    start_time: linear.start_time.min(now),
+                    end_time: now,
+                    total_amount: grant_amount,
+                };
+                schedules[0].release_time = schedules[0].release_time.min(now);
+            }
+            None => {
+                for s in schedules.iter_mut().filter(|s| s.release_time > now) {
+                    accelerated = accelerated
+                        .checked_add(s.amount)
+                        .ok_or(ProgramError::InvalidAccountData)?;
+                    s.release_time = now;
+                }
+            }
+        }
+        if accelerated == 0 {
+            msg!("Nothing left to accelerate");
+            return Ok(());
+        }
+        msg!("released {:?} early", accelerated);
+        sol_log_data(&[
+            ACCELERATE_EVENT,
+            vesting_account.key.as_ref(),
+            &accelerated.to_le_bytes(),
+        ]);
+
+        // ----------------------------------------------------------------------------- update state
+        // also ends any sweep of bounded Unlocks, which may have gone past schedules that are released now
+        header_state.update_next_release_time(&schedules);
+        header_state.refresh_has_claimable(now);
+        Self::update_emission_curve(
+            program_id,
+            emission_curve_account,
+            &tracked_schedules,
+            &schedules,
+            now,
+        )?;
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(
+            schedules,
+            &mut packed_state.borrow_mut()[VestingScheduleHeader::LEN..],
+        );
+        if let Some(linear) = linear {
+            linear.pack_into_slice(&mut packed_state.borrow_mut()[header_state.schedules_end()..]);
+        }
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_activate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if !granter.is_signer {
+            msg!("granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter.key {
+            msg!("Only the granter of the contract can activate it");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // only contracts created with relative release times, and only once
+        header_state.apply(ContractEvent::Activate)?;
+
+        // ----------------------------------------------------------------------------- core
+        let now = Clock::from_account_info(clock_sysvar_account)
+            .ctx("reading clock sysvar")?
+            .unix_timestamp as u64;
+        let mut schedules = unpack_schedules(
+            &packed_state.borrow()[VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        for s in schedules.iter_mut() {
+            s.release_time = s.release_time.checked_add(now).ok_or_else(|| {
+                msg!("release time {:?} is too far out to anchor", s.release_time);
+                ProgramError::InvalidAccountData
+            })?;
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.activation_time = now;
+        header_state.update_next_release_time(&schedules);
+        header_state.refresh_has_claimable(now);
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(
+            schedules,
+            &mut packed_state.borrow_mut()[VestingScheduleHeader::LEN..],
+        );
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_change_granter(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        new_granter: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if !granter.is_signer {
+            msg!("granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter.key {
+            msg!("Only the granter of the contract can hand it over");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // nobody can sign for the default key, so the contract's lamports would be stuck at Close
+        if *new_granter == Pubkey::default() {
+            msg!("The new granter should be a real key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        // allowed on Exhausted contracts too, whose granter still gets the lamports back at Close
+        msg!(
+            "granter {} -> {}",
+            header_state.granter_address,
+            new_granter
+        );
+        header_state.granter_address = *new_granter;
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    /// Handles both Pause and Resume
+    pub fn process_set_paused(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        paused: bool,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if !granter.is_signer {
+            msg!("granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter.key {
+            msg!("Only the granter of the contract can pause or resume it");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // nothing is left to hold back in an exhausted contract
+        header_state.apply(ContractEvent::Modify)?;
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.paused = paused;
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_emergency_withdraw(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+        let emergency_authority = next_account_info(accounts_iter)?;
+        let recovery_token_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if !emergency_authority.is_signer {
+            msg!("emergency authority should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        // nobody can sign for the default key, but a contract without an authority shouldn't even get that far
+        if header_state.emergency_authority == Pubkey::default() {
+            msg!("The contract was created without an emergency authority");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if header_state.emergency_authority != *emergency_authority.key {
+            msg!("Only the contract's emergency authority can sweep it");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("The vesting token account should be owned by the vesting account.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let recovery_token_account_data =
+            Self::unpack_token_account(program_id, recovery_token_account)
+                .ctx("unpacking recovery token account")?;
+        if recovery_token_account_data.mint != header_state.mint_address {
+            msg!("The recovery token account should hold the vested mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let emission_curve_account =
+            Self::emission_curve_account(program_id, &header_state, accounts)?;
+        let fee_mint =
+            Self::contract_fee_mint(vesting_token_account, &header_state.mint_address, accounts)?;
+
+        // ----------------------------------------------------------------------------- transfer
+        // everything on the account, including whatever was released but not claimed yet
+        let amount = vesting_token_account_data.amount;
+        if amount > 0 {
+            Self::transfer_tokens(
+                spl_token_account,
+                vesting_token_account,
+                recovery_token_account,
+                vesting_account,
+                fee_mint.as_ref(),
+                &[&signer.signer_seeds()],
+                amount,
+            )
+            .ctx("sweeping the vesting token account")?;
+        }
+        msg!("swept {} to {}", amount, recovery_token_account.key);
+
+        // ----------------------------------------------------------------------------- update state
+        let mut schedules = unpack_schedules(
+            &packed_state.borrow()[VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        let tracked_schedules = match emission_curve_account {
+            Some(_) => schedules.clone(),
+            None => vec![],
+        };
+        for s in schedules.iter_mut() {
+            s.amount = 0;
+        }
+        Self::update_emission_curve(
+            program_id,
+            emission_curve_account,
+            &tracked_schedules,
+            &schedules,
+            0,
+        )?;
+        // an Exhausted contract can still be swept, eg of tokens sent to it after the fact
+        if header_state.state == ContractState::AwaitingActivation {
+            header_state.apply(ContractEvent::Exhaust)?;
+        }
+        header_state.update_next_release_time(&schedules);
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(
+            schedules,
+            &mut packed_state.borrow_mut()[VestingScheduleHeader::LEN..],
+        );
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    /// Only in devnet-tools builds, see lib.rs
+    #[cfg(feature = "devnet-tools")]
+    pub fn process_debug_shift_schedules(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        offset: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        // a QA build that made it to the mainnet id anyway still doesn't move anyone's release times
+        if *program_id == ids::mainnet() {
+            msg!("DebugShiftSchedules isn't available at the mainnet program id");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if !granter.is_signer {
+            msg!("granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter.key {
+            msg!("Only the granter of the contract can shift its schedules");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        header_state.apply(ContractEvent::Modify)?;
+
+        let emission_curve_account =
+            Self::emission_curve_account(program_id, &header_state, accounts)?;
+
+        // ----------------------------------------------------------------------------- core
+        let mut schedules = unpack_schedules(
+            &packed_state.borrow()[VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        let tracked_schedules = match emission_curve_account {
+            Some(_) => schedules.clone(),
+            None => vec![],
+        };
+        let mut linear =
+            unpack_linear(&header_state, &packed_state.borrow()).ctx("unpacking linear vesting")?;
+
+        for s in schedules.iter_mut() {
+            s.release_time = s.release_time.saturating_sub(offset);
+        }
+        if let Some(linear) = linear.as_mut() {
+            linear.cliff_time = linear.cliff_time.saturating_sub(offset);
+            linear.start_time = linear.start_time.saturating_sub(offset);
+            linear.end_time = linear.end_time.saturating_sub(offset);
+        }
+        // the split between the two destinations moves along with the schedules, 0 would mean no split at all
+        if header_state.legacy_cutoff > 0 {
+            header_state.legacy_cutoff = header_state.legacy_cutoff.saturating_sub(offset).max(1);
+        }
+        msg!("shifted release times {} seconds earlier", offset);
+
+        // ----------------------------------------------------------------------------- update state
+        // earlier is never past the end of the curve, so it doesn't have to be current
+        Self::update_emission_curve(
+            program_id,
+            emission_curve_account,
+            &tracked_schedules,
+            &schedules,
+            0,
+        )?;
+        header_state.update_next_release_time(&schedules);
+        if let Ok(clock) = Clock::get() {
+            header_state.refresh_has_claimable(clock.unix_timestamp as u64);
+        }
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(
+            schedules,
+            &mut packed_state.borrow_mut()[VestingScheduleHeader::LEN..],
+        );
+        if let Some(linear) = linear {
+            linear.pack_into_slice(&mut packed_state.borrow_mut()[header_state.schedules_end()..]);
+        }
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+    pub fn process_set_min_claim_amount(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        min_claim_amount: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let destination_token_account =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Self::check_transfer_limit(header_state.max_transfer_per_unlock, min_claim_amount)?;
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.min_claim_amount = min_claim_amount;
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_set_rollover(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        enabled: bool,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        // a lockup extension changes what both sides agreed to at Create, so both sign
+        if header_state.granter_address != *granter_account.key {
+            msg!("Only the granter can set rollover");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let destination_token_account =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if enabled && header_state.legacy_cutoff > 0 {
+            msg!("The previous destination should claim everything before its cutoff first");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if enabled && header_state.schedule_layout == ScheduleLayout::Linear {
+            msg!("A linear contract has no tranches to roll over");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        let schedules = unpack_schedules(
+            &vesting_account.data.borrow()
+                [VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        header_state.roll_into_final = enabled;
+        header_state.update_next_release_time(&schedules);
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+        Self::record_revision(vesting_account)?;
+
+        Ok(())
+    }
+
+    pub fn process_top_up_gas(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        amount: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let funder = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        // ----------------------------------------------------------------------------- transfer
+        invoke(
+            &system_instruction::transfer(funder.key, vesting_account.key, amount),
+            &[
+                funder.clone(),
+                vesting_account.clone(),
+                system_program_account.clone(),
+            ],
+        )
+        .ctx("depositing gas")?;
+
+        // ----------------------------------------------------------------------------- update state
+        header_state.gas_escrow = header_state
+            .gas_escrow
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        header_state
+            .pack_into_slice(&mut vesting_account.data.borrow_mut()[..VestingScheduleHeader::LEN]);
+
+        Ok(())
+    }
+
+    pub fn process_top_up(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        allow_immediate: bool,
+        schedules: Vec<Schedule>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let system_program_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let source_token_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter_account.key {
+            msg!("Only the granter can top up a contract");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // an exhausted contract is finished, new schedules go into a new one
+        header_state.apply(ContractEvent::Modify)?;
+        // categories and the LinearVesting sit right after the schedules
+        if header_state.schedule_layout != ScheduleLayout::Plain {
+            msg!("Only plain contracts can be topped up");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if Self::unpack_token_account(program_id, vesting_token_account)
+            .ctx("unpacking vesting token account")?
+            .owner
+            != vesting_account_key
+        {
+            msg!("vesting token account should be owned by vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if schedules.is_empty() {
+            msg!("No schedules to add");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        if !allow_immediate {
+            let now = Clock::get().ctx("reading clock")?.unix_timestamp;
+            if schedules.iter().any(|s| (s.release_time as i64) < now) {
+                msg!("schedule release time is in the past, pass allow_immediate if intended");
+                return Err(VestingError::ReleaseTimeInPast.into());
+            }
+        }
+
+        // whatever the contract still has to pay out has to stay summable
+        let existing_schedules = unpack_schedules(
+            &vesting_account.data.borrow()
+                [VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        let total_amount = schedules
+            .iter()
+            .try_fold(0u64, |total, s| total.checked_add(s.amount))
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        existing_schedules
+            .iter()
+            .try_fold(total_amount, |total, s| total.checked_add(s.amount))
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        // same as Create, the granter pays a mint's transfer fees on top
+        let fee_mint =
+            Self::contract_fee_mint(vesting_token_account, &header_state.mint_address, accounts)?;
+        let gross_amount = match &fee_mint {
+            Some(fee_mint) => fee_mint.gross_up(total_amount)?,
+            None => total_amount,
+        };
+
+        if Self::unpack_token_account(program_id, source_token_account)
+            .ctx("unpacking source token account")?
+            .amount
+            < gross_amount
+        {
+            msg!("source token account has insufficient funds");
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        // ----------------------------------------------------------------------------- grow the account
+        let schedule_count = header_state.schedule_count as usize + schedules.len();
+        let new_len = VestingScheduleHeader::account_len(schedule_count, ScheduleLayout::Plain);
+        let rent = Rent::get().ctx("reading rent")?;
+        // the gas escrow is in lamports too, and isn't there to pay rent
+        let missing_lamports = rent
+            .minimum_balance(new_len)
+            .saturating_add(header_state.gas_escrow)
+            .saturating_sub(vesting_account.lamports());
+        if missing_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    granter_account.key,
+                    vesting_account.key,
+                    missing_lamports,
+                ),
+                &[
+                    granter_account.clone(),
+                    vesting_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )
+            .ctx("topping up rent")?;
+        }
+        vesting_account
+            .realloc(new_len, false)
+            .ctx("growing the vesting account")?;
+
+        // ----------------------------------------------------------------------------- update state
+        let new_schedules = schedules
+            .iter()
+            .map(|s| VestingSchedule {
+                release_time: s.release_time,
+                amount: s.amount,
+            })
+            .collect::<Vec<VestingSchedule>>();
+        // a bounded Unlock halfway through a sweep gets to the new schedules at the end of it, until then
+        // next_release_time can be too early but never too late
+        header_state.next_release_time = header_state
+            .next_release_time
+            .min(next_release_time(&new_schedules));
+        // a clock that can't be read only keeps the curve from moving up to the current week
+        Self::update_emission_curve(
+            program_id,
+            Self::emission_curve_account(program_id, &header_state, accounts)?,
+            &[],
+            &new_schedules,
+            Clock::get().map_or(0, |clock| clock.unix_timestamp as u64),
+        )?;
+        let schedules_end = header_state.schedules_end();
+        header_state.schedule_count = schedule_count as u32;
+        {
+            let mut data = vesting_account.data.borrow_mut();
+            pack_schedules_into_slice(new_schedules, &mut data[schedules_end..]);
+            header_state.pack_into_slice(&mut data);
+        }
+        Self::record_revision(vesting_account)?;
+
+        // ----------------------------------------------------------------------------- send funds
+        Self::transfer_tokens(
+            spl_token_account,
+            source_token_account,
+            vesting_token_account,
+            granter_account,
+            fee_mint.as_ref(),
+            &[],
+            gross_amount,
+        )
+        .ctx("transferring tokens to the vesting token account")?;
+
+        Ok(())
+    }
+
+    pub fn process_merge(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        merged_seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let system_program_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+        let merged_vesting_account = next_account_info(accounts_iter)?;
+        let merged_vesting_token_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+        let optional_account = accounts_iter.next();
+
+        // ----------------------------------------------------------------------------- checks
+        Self::check_token_program(spl_token_account)?;
+        if *system_program_account.key != system_program::id() {
+            msg!("The provided system program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (vesting_account_key, _) = Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let (merged_vesting_account_key, merged_signer) =
+            Self::vesting_signer(program_id, merged_vesting_account, merged_seeds)?;
+        if merged_vesting_account_key != *merged_vesting_account.key {
+            msg!("Invalid merged vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if vesting_account_key == merged_vesting_account_key {
+            msg!("A contract can't be merged into itself");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id || *merged_vesting_account.owner != *program_id {
+            msg!("vesting accounts should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut header_state =
+            VestingScheduleHeader::unpack_account(&vesting_account.data.borrow())
+                .ctx("unpacking vesting account header")?;
+        let mut merged_header_state =
+            VestingScheduleHeader::unpack_account(&merged_vesting_account.data.borrow())
+                .ctx("unpacking merged vesting account header")?;
+
+        // the merged grant ends up under the kept contract's terms, so both parties sign
+        if header_state.granter_address != *granter_account.key
+            || merged_header_state.granter_address != *granter_account.key
+        {
+            msg!("Only contracts of the same granter can be merged, by that granter");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !granter_account.is_signer {
+            msg!("The granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if header_state.destination_address != *destination_token_account.key
+            || merged_header_state.destination_address != *destination_token_account.key
+        {
+            msg!("Both contracts should pay out to the provided destination account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !destination_token_account_owner.is_signer {
+            msg!("Destination token account owner should be a signer.");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.owner != *destination_token_account_owner.key {
+            msg!("The destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if header_state.mint_address != merged_header_state.mint_address {
+            msg!("Only contracts vesting the same mint can be merged");
+            return Err(ProgramError::InvalidArgument);
+        }
+        // the mint can go where the index does when there's no index
+        let destination_index_account =
+            optional_account.filter(|a| *a.key != header_state.mint_address);
+
+        header_state.apply(ContractEvent::Modify)?;
+        merged_header_state.apply(ContractEvent::Modify)?;
+        // categories and the LinearVesting sit right after the schedules
+        if header_state.schedule_layout != ScheduleLayout::Plain
+            || merged_header_state.schedule_layout != ScheduleLayout::Plain
+        {
+            msg!("Only plain contracts can be merged");
+            return Err(ProgramError::InvalidArgument);
+        }
+        // the schedules stay on the curve as they are, as long as they were on it to begin with
+        if header_state.emission_tracked != merged_header_state.emission_tracked {
+            msg!("Either both contracts or neither should be tracked on the emission curve");
+            return Err(ProgramError::InvalidArgument);
+        }
+        // the merged schedules get paid out under the kept contract's terms, so those have to be the ones they were
+        // granted under - Merge is no way around a withholding, a split, a cap, an authority, a pause or a lock
+        if header_state.legacy_cutoff != 0 || merged_header_state.legacy_cutoff != 0 {
+            msg!("Contracts still paying their legacy destination can't be merged");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let payout_terms = |header: &VestingScheduleHeader| {
+            (
+                (
+                    header.withholding_bps,
+                    header.withholding_destination,
+                    header.splits.clone(),
+                ),
+                (header.emergency_authority, header.paused),
+                (
+                    header.unlock_policy,
+                    header.max_transfer_per_unlock,
+                    header.unlock_interval,
+                    header.min_claim_amount,
+                    header.roll_into_final,
+                ),
+                (header.revocable, header.revoke_policy),
+                header.destination_locked,
+            )
+        };
+        if payout_terms(&header_state) != payout_terms(&merged_header_state) {
+            msg!("Only contracts paying out under the same terms can be merged");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if Self::unpack_token_account(program_id, vesting_token_account)
+            .ctx("unpacking vesting token account")?
+            .owner
+            != vesting_account_key
+        {
+            msg!("vesting token account should be owned by vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let merged_vesting_token_account_data =
+            Self::unpack_token_account(program_id, merged_vesting_token_account)
+                .ctx("unpacking merged vesting token account")?;
+        if merged_vesting_token_account_data.owner != merged_vesting_account_key {
+            msg!("merged vesting token account should be owned by the merged vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // paid out schedules aren't worth the space
+        let merged_schedules: Vec<VestingSchedule> = unpack_schedules(
+            &merged_vesting_account.data.borrow()
+                [VestingScheduleHeader::LEN..merged_header_state.schedules_end()],
+        )
+        .ctx("unpacking merged schedules")?
+        .into_iter()
+        .filter(|s| s.amount > 0)
+        .collect();
+        let merged_amount = merged_schedules
+            .iter()
+            .try_fold(0u64, |total, s| total.checked_add(s.amount))
+            .ok_or(ProgramError::InvalidAccountData)?;
+        // an insolvent contract would make the kept one insolvent too - counting what a mint's transfer fee takes off
+        // the way over
+        let fee_mint = Self::contract_fee_mint(
+            merged_vesting_token_account,
+            &header_state.mint_address,
+            accounts,
+        )?;
+        let merged_balance = merged_vesting_token_account_data.amount;
+        let merged_fee = match &fee_mint {
+            Some(fee_mint) => fee_mint.fee(merged_balance)?,
+            None => 0,
+        };
+        if merged_balance - merged_fee < merged_amount {
+            msg!("The merged contract holds less than it still has to pay out");
+            return Err(ProgramError::InsufficientFunds);
+        }
+        // whatever the kept contract has to pay out has to stay summable
+        unpack_schedules(
+            &vesting_account.data.borrow()
+                [VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?
+        .iter()
+        .try_fold(merged_amount, |total, s| total.checked_add(s.amount))
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+        // ----------------------------------------------------------------------------- grow the account
+        // the merged contract's lamports, its gas escrow included, all move over. Its rent more than pays for the
+        // schedules it brings along, unless the kept contract was short of rent to begin with
+        let merged_lamports = merged_vesting_account.lamports();
+        **merged_vesting_account.try_borrow_mut_lamports()? = 0;
+        **vesting_account.try_borrow_mut_lamports()? += merged_lamports;
+        header_state.gas_escrow = header_state
+            .gas_escrow
+            .saturating_add(merged_header_state.gas_escrow);
+
+        let schedule_count = header_state.schedule_count as usize + merged_schedules.len();
+        let new_len = VestingScheduleHeader::account_len(schedule_count, ScheduleLayout::Plain);
+        let rent = Rent::get().ctx("reading rent")?;
+        let required_lamports = rent
+            .minimum_balance(new_len)
+            .saturating_add(header_state.gas_escrow);
+        let lamports = vesting_account.lamports();
+        if lamports < required_lamports {
+            invoke(
+                &system_instruction::transfer(
+                    granter_account.key,
+                    vesting_account.key,
+                    required_lamports - lamports,
+                ),
+                &[
+                    granter_account.clone(),
+                    vesting_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )
+            .ctx("topping up rent")?;
+        } else {
+            **vesting_account.try_borrow_mut_lamports()? = required_lamports;
+            **granter_account.try_borrow_mut_lamports()? += lamports - required_lamports;
+        }
+        vesting_account
+            .realloc(new_len, false)
+            .ctx("growing the vesting account")?;
+
+        // ----------------------------------------------------------------------------- transfer
+        if merged_balance > 0 {
+            Self::transfer_tokens(
+                spl_token_account,
+                merged_vesting_token_account,
+                vesting_token_account,
+                merged_vesting_account,
+                fee_mint.as_ref(),
+                &[&merged_signer.signer_seeds()],
+                merged_balance,
+            )
+            .ctx("transferring tokens to the kept vesting token account")?;
+        }
+        if let Some(fee_mint) = &fee_mint {
+            Self::harvest_withheld_fees(spl_token_account, fee_mint, merged_vesting_token_account)
+                .ctx("harvesting withheld transfer fees")?;
+        }
+
+        invoke_signed(
+            &close_account(
+                spl_token_account.key,
+                merged_vesting_token_account.key,
+                granter_account.key,
+                &merged_vesting_account_key,
+                &[],
+            )?,
+            &[
+                spl_token_account.clone(),
+                merged_vesting_token_account.clone(),
+                granter_account.clone(),
+                merged_vesting_account.clone(),
+            ],
+            &[&merged_signer.signer_seeds()],
+        )
+        .ctx("closing the merged vesting token account")?;
+
+        // ----------------------------------------------------------------------------- update state
+        // as with TopUp, a bounded Unlock halfway through a sweep gets to the new schedules at the end of it
+        header_state.next_release_time = header_state
+            .next_release_time
+            .min(next_release_time(&merged_schedules));
+        header_state.has_claimable |= merged_header_state.has_claimable;
+        let schedules_end = header_state.schedules_end();
+        header_state.schedule_count = schedule_count as u32;
+        {
+            let mut data = vesting_account.data.borrow_mut();
+            pack_schedules_into_slice(merged_schedules, &mut data[schedules_end..]);
+            header_state.pack_into_slice(&mut data);
+        }
+        Self::record_revision(vesting_account)?;
+
+        if let Some(destination_index_account) = destination_index_account {
+            Self::move_destination_index(
+                program_id,
+                destination_index_account,
+                &header_state.mint_address,
+                destination_token_account.key,
+                &merged_vesting_account_key,
+                &vesting_account_key,
+            )?;
+        }
+
+        // the runtime drops the merged account now that it has no lamports. Until then only a Closed header is
+        // left, which Create won't write over
+        merged_header_state.next_release_time = NO_PENDING_RELEASE;
+        merged_header_state.gas_escrow = 0;
+        merged_header_state.apply(ContractEvent::Exhaust)?;
+        merged_header_state.apply(ContractEvent::Close)?;
+        let mut merged_data = merged_vesting_account.data.borrow_mut();
+        merged_data.fill(0);
+        merged_header_state.pack_into_slice(&mut merged_data);
+
+        Ok(())
+    }
+
+    pub fn process_create_compressed(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        token_mint_addr: &Pubkey,
+        max_depth: u32,
+        max_buffer_size: u32,
+        total_amount: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?; //the one that is the merkle tree's authority
+        let vesting_token_account = next_account_info(accounts_iter)?; //the one that will hold the tokens
+        let source_token_account_owner = next_account_info(accounts_iter)?;
+        let source_token_account = next_account_info(accounts_iter)?;
+        let merkle_tree_account = next_account_info(accounts_iter)?;
+        let account_compression_program = next_account_info(accounts_iter)?;
+        let noop_program = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        // a compressed account keeps no bump, so its seeds are always legacy ones ending in their own
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("bad provided vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !source_token_account_owner.is_signer {
+            msg!("source token account owner should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Self::check_token_program(spl_token_account)?;
+
+        if max_depth > MAX_TREE_DEPTH {
+            msg!("max depth can't be larger than {:?}", MAX_TREE_DEPTH);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Self::check_compression_programs(account_compression_program, noop_program)?;
+
+        // a tree that isn't zeroed fails the init below
+        if merkle_tree_account.owner != account_compression_program.key {
+            msg!("The merkle tree should be owned by the account-compression program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("vesting token account should be owned by vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if vesting_token_account_data.mint != *token_mint_addr {
+            msg!("vesting token account should hold the vested mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if vesting_token_account_data.delegate.is_some() {
+            msg!("vesting account should NOT have a delegate");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if vesting_token_account_data.close_authority.is_some() {
+            msg!("vesting account should NOT have a close authority");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::check_token_account_extensions(vesting_token_account)?;
+
+        // ----------------------------------------------------------------------------- create
+        // unlike normal contracts there's no separate Init step - the account is always the same size
+        let rent = Rent::from_account_info(rent_sysvar_account).ctx("reading rent sysvar")?;
+        let init_vesting_account = create_account(
+            payer.key,
+            &vesting_account_key,
+            rent.minimum_balance(CompressedVestingTree::LEN),
+            CompressedVestingTree::LEN as u64,
+            program_id,
+        );
+
+        //fails if the account already exists, which is what stops anyone from overwriting an existing root
+        invoke_signed(
+            &init_vesting_account,
+            &[
+                system_program_account.clone(),
+                payer.clone(),
+                vesting_account.clone(),
+            ],
+            &[&signer.signer_seeds()],
+        )
+        .ctx("creating vesting account")?;
+
+        // ----------------------------------------------------------------------------- update state
+        let state = CompressedVestingTree {
+            mint_address: *token_mint_addr,
+            merkle_tree: *merkle_tree_account.key,
+            granter: *source_token_account_owner.key,
+            max_depth,
+            leaf_count: 0,
+            is_initialized: true,
+        };
+        state.pack_into_slice(&mut vesting_account.data.borrow_mut());
+
+        // fails unless the tree account is a zeroed one of the right size, so nobody else can be its authority already
+        invoke_signed(
+            &init_empty_merkle_tree(
+                merkle_tree_account.key,
+                &vesting_account_key,
+                max_depth,
+                max_buffer_size,
+            ),
+            &[
+                merkle_tree_account.clone(),
+                vesting_account.clone(),
+                noop_program.clone(),
+                account_compression_program.clone(),
+            ],
+            &[&signer.signer_seeds()],
+        )
+        .ctx("initializing merkle tree")?;
+
+        // ----------------------------------------------------------------------------- send funds
+        // the granter pays transfer fees on top, like on Create
+        let fee_mint = Self::contract_fee_mint(vesting_token_account, token_mint_addr, accounts)?;
+        let gross_amount = match &fee_mint {
+            Some(fee_mint) => fee_mint.gross_up(total_amount)?,
+            None => total_amount,
+        };
+        if Self::unpack_token_account(program_id, source_token_account)
+            .ctx("unpacking source token account")?
+            .amount
+            < gross_amount
+        {
+            msg!("source token account has insufficient funds");
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        Self::transfer_tokens(
+            spl_token_account,
+            source_token_account,
+            vesting_token_account,
+            source_token_account_owner,
+            fee_mint.as_ref(),
+            &[],
+            gross_amount,
+        )
+        .ctx("transferring tokens to the vesting token account")?;
+
+        Ok(())
+    }
+
+    pub fn process_append_compressed(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        leaves: &[Node],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let vesting_account = next_account_info(accounts_iter)?;
+        let granter_account = next_account_info(accounts_iter)?;
+        let merkle_tree_account = next_account_info(accounts_iter)?;
+        let account_compression_program = next_account_info(accounts_iter)?;
+        let noop_program = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut state = CompressedVestingTree::unpack(&vesting_account.data.borrow())
+            .ctx("unpacking compressed vesting tree")?;
+
+        if state.granter != *granter_account.key {
+            msg!("Only the granter can append leaves");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Self::check_compression_programs(account_compression_program, noop_program)?;
+        Self::check_merkle_tree(&state, merkle_tree_account, account_compression_program)?;
+
+        let leaf_count = state.leaf_count as u64 + leaves.len() as u64;
+        if leaf_count > 1 << state.max_depth {
+            msg!("The tree only holds {:?} leaves", 1u64 << state.max_depth);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        state.leaf_count = leaf_count as u32;
+        state.pack_into_slice(&mut vesting_account.data.borrow_mut());
+
+        // ----------------------------------------------------------------------------- append
+        for leaf in leaves {
+            invoke_signed(
+                &append(merkle_tree_account.key, &vesting_account_key, *leaf),
+                &[
+                    merkle_tree_account.clone(),
+                    vesting_account.clone(),
+                    noop_program.clone(),
+                    account_compression_program.clone(),
+                ],
+                &[&signer.signer_seeds()],
+            )
+            .ctx("appending leaf")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn process_claim_compressed(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        root: Node,
+        index: u32,
+        release_time: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let merkle_tree_account = next_account_info(accounts_iter)?;
+        let account_compression_program = next_account_info(accounts_iter)?;
+        let noop_program = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Self::check_token_program(spl_token_account)?;
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let state = CompressedVestingTree::unpack(&vesting_account.data.borrow())
+            .ctx("unpacking compressed vesting tree")?;
+
+        Self::check_compression_programs(account_compression_program, noop_program)?;
+        Self::check_merkle_tree(&state, merkle_tree_account, account_compression_program)?;
+
+        // the tree keeps no canopy, so the proof goes all the way up. Whatever follows it is left to contract_fee_mint
+        let proof = match accounts_iter.as_slice().get(..state.max_depth as usize) {
+            Some(proof) if index < state.leaf_count => proof,
+            _ => {
+                msg!(
+                    "proof should be exactly {:?} nodes long, for one of the {:?} leaves",
+                    state.max_depth,
+                    state.leaf_count
+                );
+                return Err(VestingError::InvalidMerkleProof.into());
+            }
+        };
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("The vesting token account should be owned by the vesting account.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // the destination is part of the leaf, but the tree can't tell which mint it holds
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if vesting_token_account_data.mint != state.mint_address
+            || destination_token_account_data.mint != state.mint_address
+        {
+            msg!("The vesting and destination token accounts should hold the vested mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_account).ctx("reading clock sysvar")?;
+        if (clock.unix_timestamp as u64) < release_time {
+            msg!("Vesting contract has not yet reached release time");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ----------------------------------------------------------------------------- update state
+        // the destination is part of the leaf, so tokens can only go to whoever the grantor put in the tree. The leaf is
+        // emptied before transferring, and the tree only empties a leaf that is still there - so the same proof can't
+        // be used twice
+        let leaf = hash_leaf(destination_token_account.key, release_time, amount);
+        let proof_keys = proof.iter().map(|node| *node.key).collect::<Vec<Pubkey>>();
+        let mut tree_accounts = vec![
+            merkle_tree_account.clone(),
+            vesting_account.clone(),
+            noop_program.clone(),
+            account_compression_program.clone(),
+        ];
+        tree_accounts.extend(proof.iter().cloned());
+        invoke_signed(
+            &replace_leaf(
+                merkle_tree_account.key,
+                &vesting_account_key,
+                root,
+                leaf,
+                EMPTY_LEAF,
+                index,
+                &proof_keys,
+            ),
+            &tree_accounts,
+            &[&signer.signer_seeds()],
+        )
+        .ctx("emptying the claimed leaf")?;
+
+        // ----------------------------------------------------------------------------- transfer
+        let fee_mint =
+            Self::contract_fee_mint(vesting_token_account, &state.mint_address, accounts)?;
+        Self::transfer_tokens(
+            spl_token_account,
+            vesting_token_account,
+            destination_token_account,
+            vesting_account,
+            fee_mint.as_ref(),
+            &[&signer.signer_seeds()],
+            amount,
+        )
+        .ctx("transferring released tokens")?;
+
+        Ok(())
+    }
+
+    /// The programs a compressed contract calls into. Anything else could fake the tree and accept any proof
+    fn check_compression_programs(
+        account_compression_program: &AccountInfo,
+        noop_program: &AccountInfo,
+    ) -> ProgramResult {
+        if *account_compression_program.key != spl_account_compression::id() {
+            msg!("The provided account-compression program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *noop_program.key != spl_noop::id() {
+            msg!("The provided noop program account is invalid");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// The merkle tree of a compressed vesting contract, and the account-compression program that owns it
+    fn check_merkle_tree(
+        state: &CompressedVestingTree,
+        merkle_tree_account: &AccountInfo,
+        account_compression_program: &AccountInfo,
+    ) -> ProgramResult {
+        if *merkle_tree_account.key != state.merkle_tree {
+            msg!("The merkle tree doesn't belong to this contract");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if merkle_tree_account.owner != account_compression_program.key {
+            msg!("The merkle tree should be owned by the account-compression program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+
+    pub fn process_export_contract(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        new_program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+        let escrow_token_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+        let destination_token_account = next_account_info(accounts_iter)?;
+        let destination_token_account_owner = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        Self::check_token_program(spl_token_account)?;
+
+        if new_program_id == program_id {
+            msg!("Can't export a contract to the deployment it already lives on");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        //fails for contracts that have already been exported
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        // both parties have to agree to the move
+        if !granter.is_signer || !destination_token_account_owner.is_signer {
+            msg!("Both the granter and the destination owner should sign an export");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if header_state.granter_address != *granter.key {
+            msg!("Provided granter isn't the granter of the contract");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if header_state.destination_address != *destination_token_account.key {
+            msg!("Contract destination account does not matched provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // ImportContract drops paid out schedules and knows nothing of the LinearVesting after them
+        if header_state.schedule_layout == ScheduleLayout::Linear {
+            msg!("Linear contracts can't be exported");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let destination_token_account_data =
+            Self::unpack_token_account(program_id, destination_token_account)
+                .ctx("unpacking destination token account")?;
+        if destination_token_account_data.owner != *destination_token_account_owner.key {
+            msg!("The current destination token account isn't owned by the provided owner");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("The vesting token account should be owned by the vesting account.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let (migration_authority_key, _) =
+            find_migration_authority(new_program_id, &vesting_account_key);
+        let escrow_token_account_data =
+            Self::unpack_token_account(program_id, escrow_token_account)
+                .ctx("unpacking escrow token account")?;
+        if escrow_token_account_data.owner != migration_authority_key {
+            msg!("The escrow token account should be owned by the new deployment's migration authority");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if escrow_token_account_data.mint != header_state.mint_address {
+            msg!("The escrow token account should hold the vested mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if escrow_token_account_data.delegate.is_some()
+            || escrow_token_account_data.close_authority.is_some()
+        {
+            msg!("escrow account should NOT have a delegate or a close authority");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let emission_curve_account =
+            Self::emission_curve_account(program_id, &header_state, accounts)?;
+
+        // ----------------------------------------------------------------------------- core
+        // released but unclaimed amounts go along too - the destination can unlock them on the new deployment
+        let schedules = unpack_schedules(
+            &packed_state.borrow()[VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        let mut total_amount_to_export: u64 = 0;
+        for s in schedules.iter() {
+            total_amount_to_export = total_amount_to_export
+                .checked_add(s.amount)
+                .ok_or(ProgramError::InvalidAccountData)?;
+        }
+        header_state.apply(ContractEvent::Modify)?;
+        // the new deployment doesn't track the contract, whatever it recreates is released there
+        Self::update_emission_curve(program_id, emission_curve_account, &schedules, &[], 0)?;
+
+        // ----------------------------------------------------------------------------- transfer
+        // a mint's transfer fee leaves the escrow short, ImportContract doesn't go through until it's made up for
+        let fee_mint =
+            Self::contract_fee_mint(vesting_token_account, &header_state.mint_address, accounts)?;
+        Self::transfer_tokens(
+            spl_token_account,
+            vesting_token_account,
+            escrow_token_account,
+            vesting_account,
+            fee_mint.as_ref(),
+            &[&signer.signer_seeds()],
+            total_amount_to_export,
+        )
+        .ctx("transferring tokens to the escrow")?;
+
+        // ----------------------------------------------------------------------------- update state
+        // schedules are left as they were, so the new deployment knows what to recreate
+        header_state
+            .pack_migrated_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+
+        Ok(())
+    }
+
+    pub fn process_import_contract(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let system_program_account = next_account_info(accounts_iter)?;
+        let rent_sysvar_account = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let old_vesting_account = next_account_info(accounts_iter)?;
+        let migration_authority = next_account_info(accounts_iter)?;
+        let escrow_token_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        Self::check_token_program(spl_token_account)?;
+
+        if *old_vesting_account.owner == *program_id {
+            msg!("Contracts can only be imported from another deployment");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !ids::migration_sources().contains(old_vesting_account.owner) {
+            msg!("Contracts can only be imported from the deployments in ids::migration_sources");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let old_data = old_vesting_account.data.borrow();
+        if old_data.len() < VestingScheduleHeader::LEN {
+            msg!("vesting account's data should  never be shorter than the header");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let old_header = VestingScheduleHeader::unpack_migrated_account(&old_data)
+            .ctx("unpacking vesting account header")?;
+        // ExportContract never lets a linear contract go, and its LinearVesting wouldn't be carried over
+        if old_header.schedule_layout == ScheduleLayout::Linear {
+            msg!("Linear contracts can't be imported");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        //fully claimed schedules aren't worth the rent. Their categories go with them
+        let old_schedules =
+            unpack_schedules(&old_data[VestingScheduleHeader::LEN..old_header.schedules_end()])
+                .ctx("unpacking schedules")?;
+        let old_categories =
+            unpack_categories(&old_header, &old_data).ctx("unpacking categories")?;
+        let (schedules, categories): (Vec<VestingSchedule>, Vec<ScheduleCategory>) = old_schedules
+            .into_iter()
+            .zip(old_categories)
+            .filter(|(s, _)| s.amount > 0)
+            .unzip();
+
+        let (migration_authority_key, bump) =
+            find_migration_authority(program_id, old_vesting_account.key);
+        if migration_authority_key != *migration_authority.key {
+            msg!("Invalid migration authority");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let escrow_token_account_data =
+            Self::unpack_token_account(program_id, escrow_token_account)
+                .ctx("unpacking escrow token account")?;
+        if escrow_token_account_data.owner != migration_authority_key {
+            msg!("The escrow token account should be owned by the migration authority");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let signer = Self::new_vesting_signer(program_id, vesting_account, seeds)?;
+        let vesting_account_key = *vesting_account.key;
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("vesting token account should be owned by vesting account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if vesting_token_account_data.mint != old_header.mint_address
+            || escrow_token_account_data.mint != old_header.mint_address
+        {
+            msg!("escrow and vesting token accounts should hold the vested mint");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if vesting_token_account_data.delegate.is_some() {
+            msg!("vesting account should NOT have a delegate");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if vesting_token_account_data.close_authority.is_some() {
+            msg!("vesting account should NOT have a close authority");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::check_token_account_extensions(vesting_token_account)?;
+
+        let mut total_amount: u64 = 0;
+        for s in schedules.iter() {
+            total_amount = total_amount
+                .checked_add(s.amount)
+                .ok_or(ProgramError::InvalidAccountData)?;
+        }
+        // the escrow pays a mint's transfer fee on top, so that the vesting token account receives the total
+        let fee_mint =
+            Self::contract_fee_mint(vesting_token_account, &old_header.mint_address, accounts)?;
+        let gross_amount = match &fee_mint {
+            Some(fee_mint) => fee_mint.gross_up(total_amount)?,
+            None => total_amount,
+        };
+        if escrow_token_account_data.amount < gross_amount {
+            msg!("escrow token account has insufficient funds");
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        // ----------------------------------------------------------------------------- create
+        let state_size =
+            VestingScheduleHeader::account_len(schedules.len(), old_header.schedule_layout);
+        let rent = Rent::from_account_info(rent_sysvar_account).ctx("reading rent sysvar")?;
+        let init_vesting_account = create_account(
+            payer.key,
+            &vesting_account_key,
+            rent.minimum_balance(state_size),
+            state_size as u64,
+            program_id,
+        );
+
+        //fails if the account already exists
+        invoke_signed(
+            &init_vesting_account,
+            &[
+                system_program_account.clone(),
+                payer.clone(),
+                vesting_account.clone(),
+            ],
+            &[&signer.signer_seeds()],
+        )
+        .ctx("creating vesting account")?;
+
+        // ----------------------------------------------------------------------------- update state
+        let mut state_header = VestingScheduleHeader {
+            destination_address: old_header.destination_address,
+            mint_address: old_header.mint_address,
+            granter_address: old_header.granter_address,
+            next_release_time: NO_PENDING_RELEASE,
+            gas_rebate: 0,
+            gas_escrow: 0,
+            schedule_count: schedules.len() as u32,
+            unlock_policy: old_header.unlock_policy,
+            legacy_destination_address: old_header.legacy_destination_address,
+            legacy_cutoff: old_header.legacy_cutoff,
+            insolvent: old_header.insolvent,
+            roll_into_final: old_header.roll_into_final,
+            min_claim_amount: old_header.min_claim_amount,
+            destination_locked: old_header.destination_locked,
+            unlock_cursor: 0,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: old_header.permit_nonce,
+            withholding_bps: old_header.withholding_bps,
+            withholding_destination: old_header.withholding_destination,
+            max_transfer_per_unlock: old_header.max_transfer_per_unlock,
+            unlock_interval: old_header.unlock_interval,
+            last_unlock_time: old_header.last_unlock_time,
+            revoke_policy: old_header.revoke_policy,
+            unlock_count: old_header.unlock_count,
+            destination_change_count: old_header.destination_change_count,
+            revoke_attempt_count: old_header.revoke_attempt_count,
+            has_claimable: old_header.has_claimable,
+            activation_time: old_header.activation_time,
+            paused: old_header.paused,
+            emergency_authority: old_header.emergency_authority,
+            revocable: old_header.revocable,
+            splits: old_header.splits.clone(),
+            emission_tracked: false, //the curves of the old deployment stay there
+            unlocked_gross: old_header.unlocked_gross,
+            unlocked_net: old_header.unlocked_net,
+            revision: old_header.revision,
+            bump: signer.bump(),
+            schedule_layout: old_header.schedule_layout,
+            state: ContractState::Active,
+        };
+        state_header.update_next_release_time(&schedules);
+        let mut data = vesting_account.data.borrow_mut();
+        state_header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(schedules, &mut data[VestingScheduleHeader::LEN..]);
+        pack_categories_into_slice(&categories, &mut data[state_header.schedules_end()..]);
+
+        // ----------------------------------------------------------------------------- transfer
+        Self::transfer_tokens(
+            spl_token_account,
+            escrow_token_account,
+            vesting_token_account,
+            migration_authority,
+            fee_mint.as_ref(),
+            &[&[MIGRATION_SEED, old_vesting_account.key.as_ref(), &[bump]]],
+            gross_amount,
+        )
+        .ctx("transferring tokens from the escrow")?;
+
+        Ok(())
+    }
+
+    pub fn process_revoke_batch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Vec<Seeds>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        Self::check_token_program(spl_token_account)?;
+
+        if !granter.is_signer {
+            msg!("granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // ----------------------------------------------------------------------------- core
+        let clock = Clock::from_account_info(clock_sysvar_account).ctx("reading clock sysvar")?;
+        let now = Self::unlock_time(program_id, &clock, accounts)?;
+
+        //each contract comes with its own triple of accounts, in the same order as the seeds
+        for contract_seeds in seeds.iter() {
+            let vesting_account = next_account_info(accounts_iter)?;
+            let vesting_token_account = next_account_info(accounts_iter)?;
+            let granter_token_account = next_account_info(accounts_iter)?;
+
+            let revoked = Self::revoke_contract(
+                program_id,
+                spl_token_account,
+                granter,
+                vesting_account,
+                vesting_token_account,
+                granter_token_account,
+                accounts,
+                now,
+                *contract_seeds,
+                None,
+            )?;
+            msg!("revoked {:?} from {:?}", revoked, vesting_account.key);
+        }
+
+        Ok(())
+    }
+
+    pub fn process_revoke(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seeds: Seeds,
+        expected_revision: Option<u32>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let spl_token_account = next_account_info(accounts_iter)?;
+        let clock_sysvar_account = next_account_info(accounts_iter)?;
+        let granter = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_token_account = next_account_info(accounts_iter)?;
+        let granter_token_account = next_account_info(accounts_iter)?;
+
+        // ----------------------------------------------------------------------------- checks
+        Self::check_token_program(spl_token_account)?;
+
+        if !granter.is_signer {
+            msg!("granter should be a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // ----------------------------------------------------------------------------- core
+        let clock = Clock::from_account_info(clock_sysvar_account).ctx("reading clock sysvar")?;
+        let revoked = Self::revoke_contract(
+            program_id,
+            spl_token_account,
+            granter,
+            vesting_account,
+            vesting_token_account,
+            granter_token_account,
+            accounts,
+            Self::unlock_time(program_id, &clock, accounts)?,
+            seeds,
+            expected_revision,
+        )?;
+        msg!("revoked {:?} from {:?}", revoked, vesting_account.key);
+
+        Ok(())
+    }
+
+    /// Sends every schedule that hasn't been released yet back to the granter and returns the amount revoked. A linear
+    /// contract is cut short at `now` instead, keeping what has been released so far. Contracts with nothing left to revoke are left untouched, so one of them doesn't fail a whole batch
+    fn revoke_contract<'a>(
+        program_id: &Pubkey,
+        spl_token_account: &AccountInfo<'a>,
+        granter: &AccountInfo<'a>,
+        vesting_account: &AccountInfo<'a>,
+        vesting_token_account: &AccountInfo<'a>,
+        receiver: &AccountInfo<'a>, //the granter's token account, or the mint with RevokePolicy::Burn
+        accounts: &[AccountInfo<'a>], //all of the instruction's, the emission curve of a tracked contract among them
+        now: u64,
+        seeds: Seeds,
+        expected_revision: Option<u32>, //None from RevokeBatch
+    ) -> VestingResult<u64> {
+        // ----------------------------------------------------------------------------- checks
+        let (vesting_account_key, signer) =
+            Self::vesting_signer(program_id, vesting_account, seeds)?;
+        if vesting_account_key != *vesting_account.key {
+            msg!("Invalid vesting account key");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if *vesting_account.owner != *program_id {
+            msg!("vesting account should be owned by the vesting program");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let packed_state = &vesting_account.data;
+        let mut header_state = VestingScheduleHeader::unpack_account(&packed_state.borrow())
+            .ctx("unpacking vesting account header")?;
+
+        if header_state.granter_address != *granter.key {
+            msg!("Only the granter of the contract can revoke it");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::check_revision(&header_state, expected_revision)?;
+
+        // no attempt is counted either, the contract is as if Revoke didn't exist
+        if !header_state.revocable {
+            msg!("The contract was created irrevocable");
+            return Err(VestingError::Irrevocable.into());
+        }
+
+        let vesting_token_account_data =
+            Self::unpack_token_account(program_id, vesting_token_account)
+                .ctx("unpacking vesting token account")?;
+        if vesting_token_account_data.owner != vesting_account_key {
+            msg!("The vesting token account should be owned by the vesting account.");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        match header_state.revoke_policy {
+            RevokePolicy::ReturnToGranter => {
+                let granter_token_account_data = Self::unpack_token_account(program_id, receiver)
+                    .ctx("unpacking granter token account")?;
+                if granter_token_account_data.owner != *granter.key {
+                    msg!("Revoked tokens can only go to a token account owned by the granter");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                if granter_token_account_data.mint != header_state.mint_address {
+                    msg!("The granter token account should hold the vested mint");
+                    return Err(ProgramError::InvalidArgument);
+                }
+            }
+            RevokePolicy::Burn => {
+                if *receiver.key != header_state.mint_address {
+                    msg!("Burning needs the contract's mint");
+                    return Err(ProgramError::InvalidArgument);
+                }
+            }
+        }
+
+        let emission_curve_account =
+            Self::emission_curve_account(program_id, &header_state, accounts)?;
+
+        // ----------------------------------------------------------------------------- core
+        let mut total_amount_to_revoke: u64 = 0;
+        let mut schedules = unpack_schedules(
+            &packed_state.borrow()[VestingScheduleHeader::LEN..header_state.schedules_end()],
+        )
+        .ctx("unpacking schedules")?;
+        let tracked_schedules = match emission_curve_account {
+            Some(_) => schedules.clone(),
+            None => vec![],
+        };
+
+        //already released amounts belong to the destination, even if they haven't been claimed yet
+        let mut linear =
+            unpack_linear(&header_state, &packed_state.borrow()).ctx("unpacking linear vesting")?;
+        match linear.as_mut() {
+            Some(linear) => {
+                total_amount_to_revoke = truncate_linear(&mut schedules[0], linear, now);
+            }
+            None => {
+                // before Activate nothing has been released
+                let awaiting_activation = header_state.state == ContractState::AwaitingActivation;
+                for s in schedules.iter_mut() {
+                    if awaiting_activation || now < s.release_time {
+                        total_amount_to_revoke = total_amount_to_revoke
+                            .checked_add(s.amount)
+                            .ok_or(ProgramError::InvalidAccountData)?;
+                        s.amount = 0;
+                    }
+                }
+            }
+        }
+        // a granter revoking a contract with nothing left is worth knowing about too
+        header_state.revoke_attempt_count = header_state.revoke_attempt_count.saturating_add(1);
+        if total_amount_to_revoke == 0 {
+            msg!("Nothing left to revoke");
+            header_state
+                .pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+            return Ok(0);
+        }
+
+        // ----------------------------------------------------------------------------- transfer
+        match header_state.revoke_policy {
+            RevokePolicy::ReturnToGranter => {
+                let fee_mint = Self::contract_fee_mint(
+                    vesting_token_account,
+                    &header_state.mint_address,
+                    accounts,
+                )?;
+                Self::transfer_tokens(
+                    spl_token_account,
+                    vesting_token_account,
+                    receiver,
+                    vesting_account,
+                    fee_mint.as_ref(),
+                    &[&signer.signer_seeds()],
+                    total_amount_to_revoke,
+                )
+                .ctx("moving revoked tokens")?;
+            }
+            // burning moves the tokens nowhere - the receiver is the mint then
+            RevokePolicy::Burn => {
+                Self::invoke_token(
+                    &burn(
+                        spl_token_account.key,
+                        vesting_token_account.key,
+                        receiver.key,
+                        &vesting_account_key,
+                        &[],
+                        total_amount_to_revoke,
+                    )?,
+                    &[
+                        spl_token_account.clone(),
+                        vesting_token_account.clone(),
+                        receiver.clone(),
+                        vesting_account.clone(),
+                    ],
+                    &[&signer.signer_seeds()],
+                    &[
+                        (vesting_token_account, -(total_amount_to_revoke as i128)),
+                        (receiver, 0),
+                    ],
+                )
+                .ctx("burning revoked tokens")?;
+            }
+        }
+        sol_log_data(&[
+            REVOKE_EVENT,
+            vesting_account.key.as_ref(),
+            &total_amount_to_revoke.to_le_bytes(),
+            &[header_state.revoke_policy as u8],
+        ]);
+
+        // ----------------------------------------------------------------------------- update state
+        if header_state.state == ContractState::AwaitingActivation {
+            header_state.apply(ContractEvent::Exhaust)?;
+        }
+        header_state.update_next_release_time(&schedules);
+        Self::update_emission_curve(
+            program_id,
+            emission_curve_account,
+            &tracked_schedules,
+            &schedules,
+            now,
+        )?;
+        header_state.pack_into_slice(&mut packed_state.borrow_mut()[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(
+            schedules,
+            &mut packed_state.borrow_mut()[VestingScheduleHeader::LEN..],
+        );
+        if let Some(linear) = linear {
+            linear.pack_into_slice(&mut packed_state.borrow_mut()[header_state.schedules_end()..]);
+        }
+
+        Self::record_revision(vesting_account)?;
+
+        Ok(total_amount_to_revoke)
+    }
+}
+
+/// Signer and message of an ed25519 program instruction holding exactly one signature, with the key and message
+/// inside the instruction itself (as built by instruction::ed25519_verify). Anything else is rejected
+fn parse_ed25519_instruction(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    if data.len() < ED25519_DATA_START || data[0] != 1 {
+        return None;
+    }
+    let offset = |i: usize| {
+        let start = ED25519_OFFSETS_START + i * 2;
+        u16::from_le_bytes([data[start], data[start + 1]])
+    };
+    // signature (0, 1), public key (2, 3), message (4, 5 = size, 6)
+    if offset(1) != u16::MAX || offset(3) != u16::MAX || offset(6) != u16::MAX {
+        return None;
+    }
+
+    let pubkey_offset = offset(2) as usize;
+    let message_offset = offset(4) as usize;
+    let signer = data.get(pubkey_offset..pubkey_offset + ED25519_PUBKEY_SIZE)?;
+    let message = data.get(message_offset..message_offset + offset(5) as usize)?;
+    Some((signer, message))
+}
+
+/// Either spl-token or Token-2022 - their instructions and base account layouts are the same
+fn is_token_program(key: &Pubkey) -> bool {
+    *key == spl_token::id() || *key == spl_token_2022::id()
+}
+
+/// The size of the associated token account the associated token account program creates for `mint_account`: the
+/// base layout for spl-token, plus ImmutableOwner and whatever the mint's extensions require for Token-2022
+fn associated_token_account_len(
+    spl_token_account: &AccountInfo,
+    mint_account: &AccountInfo,
+) -> Result<usize, ProgramError> {
+    if *spl_token_account.key != spl_token_2022::id() {
+        return Ok(Account::LEN);
+    }
+    let data = mint_account.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(&data).ctx("unpacking mint")?;
+    let mut extensions =
+        ExtensionType::get_required_init_account_extensions(&mint.get_extension_types()?);
+    extensions.push(ExtensionType::ImmutableOwner);
+    Ok(ExtensionType::get_account_len::<Account>(&extensions))
+}
+
+/// Token-2022 deprecates plain Transfer in favour of TransferChecked, but still takes it for mints without transfer
+/// fees. Those with fees go through Processor::transfer_tokens instead
+#[allow(deprecated)]
+fn transfer(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::transfer(
+        token_program_id,
+        source_pubkey,
+        destination_pubkey,
+        authority_pubkey,
+        signer_pubkeys,
+        amount,
+    )
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::{self, ed25519_verify, find_child_vesting_account, find_program_config},
+        pda::vesting_address,
+        state::{AccountType, ACCOUNT_TYPE_OFFSET, BASELINE_HEADER_LEN, SECONDS_PER_WEEK},
+        test_utils::{
+            account_infos, process_checked, schedule, vesting_schedule, TestAccount, VestingFixture,
+        },
+    };
+
+    #[test]
+    fn test_ctx_keeps_the_error() {
+        let result: Result<(), VestingError> = Err(VestingError::CorruptState);
+        assert_eq!(
+            result.ctx("unpacking vesting account header"),
+            Err(VestingError::CorruptState.into())
+        );
+    }
+
+    // ----------------------------------------------------------------------------- init
+
+    #[test]
+    fn test_init_rejects_wrong_vesting_account() {
+        let f = VestingFixture::new();
+        let mut accounts = vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::rent(),
+            TestAccount::wallet(Pubkey::new_unique()).signer(),
+            TestAccount::wallet(Pubkey::new_unique()),
+        ];
+        assert_eq!(
+            Processor::process_init(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                f.seeds,
+                1,
+                ScheduleLayout::Plain
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_init_stores_the_bump() {
+        let mut f = VestingFixture::new();
+        let (vesting_account_key, bump) = find_vesting_address_for_seeds(&[9; 32], &f.program_id);
+        f.seeds = [9; 32];
+        f.vesting_account_key = vesting_account_key;
+        let mut accounts = vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::rent(),
+            TestAccount::wallet(Pubkey::new_unique()).signer(),
+            f.empty_vesting_account(1),
+        ];
+        Processor::process_init(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            f.seeds,
+            1,
+            ScheduleLayout::Plain,
+        )
+        .unwrap();
+        let initialized = accounts[3].data.clone();
+        assert_eq!(initialized[BUMP_OFFSET], bump);
+
+        // Create keeps it, and every instruction after derives the account with it
+        let mut accounts = f.create_accounts(100);
+        accounts[1].data = initialized;
+        f.create(&mut accounts, vec![schedule(10, 100)]).unwrap();
+        let created = accounts[1].data.clone();
+        let mut header = f.header(&[vesting_schedule(10, 100)]);
+        header.bump = bump;
+        assert_eq!(
+            VestingScheduleHeader::unpack(&created[..VestingScheduleHeader::LEN]).unwrap(),
+            header
+        );
+
+        let mut accounts = f.unlock_accounts(20, vec![vesting_schedule(10, 100)]);
+        accounts[2].data = created.clone();
+        f.unlock(&mut accounts).unwrap();
+
+        // under another bump the seeds point somewhere else, if anywhere
+        let mut accounts = f.unlock_accounts(20, vec![vesting_schedule(10, 100)]);
+        accounts[2].data = created;
+        accounts[2].data[BUMP_OFFSET] = LEGACY_BUMP;
+        assert!(f.unlock(&mut accounts).is_err());
+    }
+
+    #[test]
+    fn test_init_grant_only_lets_the_granter_create() {
+        let mut f = VestingFixture::new();
+        f.seeds = vesting_seeds(&f.granter, &f.destination_owner, &f.mint, 0);
+        let (vesting_account_key, bump) = find_granted_vesting_address(&f.seeds, &f.program_id);
+        f.vesting_account_key = vesting_account_key;
+        let init_grant = |payer: TestAccount, vesting_account: TestAccount| {
+            let mut accounts = vec![
+                TestAccount::program(solana_program::system_program::id()),
+                TestAccount::rent(),
+                payer,
+                vesting_account,
+            ];
+            Processor::process_init_grant(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                &f.destination_owner,
+                &f.mint,
+                0,
+                1,
+                ScheduleLayout::Plain,
+            )
+            .map(|_| accounts.remove(3))
+        };
+
+        assert_eq!(
+            init_grant(TestAccount::wallet(f.granter), f.empty_vesting_account(1)).unwrap_err(),
+            ProgramError::MissingRequiredSignature
+        );
+        // someone else paying derives other seeds, and the same seeds Init-ed are somewhere else
+        let squatter = Pubkey::new_unique();
+        assert_eq!(
+            init_grant(
+                TestAccount::wallet(squatter).signer(),
+                f.empty_vesting_account(1)
+            )
+            .unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        assert_ne!(
+            find_vesting_address_for_seeds(&f.seeds, &f.program_id).0,
+            vesting_account_key
+        );
+
+        let initialized = init_grant(
+            TestAccount::wallet(f.granter).signer(),
+            f.empty_vesting_account(1),
+        )
+        .unwrap();
+        assert_eq!(initialized.data[BUMP_OFFSET], bump);
+        assert_eq!(init_payer(&initialized.data), f.granter);
+
+        // the seeds hold no one to the granter, the recorded payer does
+        let other = VestingFixture {
+            granter: squatter,
+            ..f
+        };
+        let mut accounts = other.create_accounts(100);
+        accounts[1].data = initialized.data.clone();
+        assert_eq!(
+            other.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.create_accounts(100);
+        accounts[1].data = initialized.data;
+        f.create(&mut accounts, vec![schedule(10, 100)]).unwrap();
+        let created = accounts[1].data.clone();
+
+        // every instruction after finds it under the prefix
+        let mut accounts = f.unlock_accounts(20, vec![vesting_schedule(10, 100)]);
+        accounts[2].data = created;
+        f.unlock(&mut accounts).unwrap();
+    }
+
+    #[test]
+    fn test_close_uninitialized_refunds_the_init_payer() {
+        let f = VestingFixture::new();
+        let payer = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::rent(),
+            TestAccount::wallet(payer).signer().writable(),
+            f.empty_vesting_account(1),
+        ];
+        Processor::process_init(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            f.seeds,
+            1,
+            ScheduleLayout::Plain,
+        )
+        .unwrap();
+        let initialized = accounts.remove(3);
+        let close_uninitialized = |accounts: &mut [TestAccount]| {
+            Processor::process_close_uninitialized(&f.program_id, &account_infos(accounts), f.seeds)
+        };
+
+        let mut not_signed = vec![initialized.clone(), TestAccount::wallet(payer).writable()];
+        assert_eq!(
+            close_uninitialized(&mut not_signed),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // only the payer gets the rent
+        let mut someone_else = vec![
+            initialized.clone(),
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+        ];
+        assert_eq!(
+            close_uninitialized(&mut someone_else),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // an Initialized account always has its payer recorded
+        let mut unrecorded = vec![
+            initialized.clone(),
+            TestAccount::wallet(payer).signer().writable(),
+        ];
+        write_init_payer(&mut unrecorded[0].data, &Pubkey::default());
+        assert_eq!(
+            close_uninitialized(&mut unrecorded),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // a created contract goes through Close
+        let mut created = vec![
+            f.vesting_account(vec![vesting_schedule(10, 100)]),
+            TestAccount::wallet(f.granter).signer().writable(),
+        ];
+        assert_eq!(
+            close_uninitialized(&mut created),
+            Err(VestingError::InvalidContractState.into())
+        );
+
+        let mut accounts = vec![initialized, TestAccount::wallet(payer).signer().writable()];
+        let lamports = accounts[0].lamports + accounts[1].lamports;
+        close_uninitialized(&mut accounts).unwrap();
+        assert_eq!(accounts[0].lamports, 0);
+        assert_eq!(accounts[1].lamports, lamports);
+        assert_eq!(
+            accounts[0].data[CONTRACT_STATE_OFFSET],
+            ContractState::Closed as u8
+        );
+
+        // nothing can Create over it before the runtime drops it
+        let closed_data = accounts[0].data.clone();
+        let mut accounts = f.create_accounts(100);
+        accounts[1].data = closed_data;
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_close_uninitialized_rejects_a_zeroed_account() {
+        // Init-ed before the payer was recorded: legacy seeds, and a 65 byte header and one schedule of zeroes
+        let f = VestingFixture::new();
+        let (vesting_account_key, seeds) = vesting_address(&[7; 31], &f.program_id);
+        let zeroed =
+            TestAccount::new(vesting_account_key, f.program_id, vec![0; 65 + 16]).writable();
+        let payer = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let close_uninitialized = |accounts: &mut [TestAccount]| {
+            Processor::process_close_uninitialized(&f.program_id, &account_infos(accounts), seeds)
+        };
+
+        // nothing on the account says who paid for it, and nobody can vouch for them either
+        let mut payer_only = vec![
+            zeroed.clone(),
+            TestAccount::wallet(payer).signer().writable(),
+        ];
+        assert_eq!(
+            close_uninitialized(&mut payer_only),
+            Err(ProgramError::InvalidAccountData)
+        );
+        let mut with_the_admin = vec![
+            zeroed,
+            TestAccount::wallet(payer).signer().writable(),
+            config_account(&f.program_id, &admin, 0),
+            TestAccount::wallet(admin).signer(),
+        ];
+        let before = with_the_admin.clone();
+        assert_eq!(
+            close_uninitialized(&mut with_the_admin),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(with_the_admin[0].lamports, before[0].lamports);
+        assert_eq!(with_the_admin[1].lamports, before[1].lamports);
+    }
+
+    #[test]
+    fn test_resize_checks() {
+        let f = VestingFixture::new();
+        let resize = |accounts: &mut [TestAccount], number_of_schedules: u32| {
+            Processor::process_resize(
+                &f.program_id,
+                &account_infos(accounts),
+                f.seeds,
+                number_of_schedules,
+                ScheduleLayout::Plain,
+            )
+        };
+        let resize_accounts = |vesting_account: TestAccount| {
+            vec![
+                TestAccount::program(solana_program::system_program::id()),
+                TestAccount::wallet(Pubkey::new_unique())
+                    .signer()
+                    .writable(),
+                vesting_account,
+            ]
+        };
+
+        let mut accounts = resize_accounts(f.empty_vesting_account(1));
+        accounts[1].is_signer = false;
+        assert_eq!(
+            resize(&mut accounts, 3),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let mut accounts = resize_accounts(f.empty_vesting_account(1));
+        accounts[2].owner = Pubkey::new_unique();
+        assert_eq!(resize(&mut accounts, 3), Err(ProgramError::InvalidArgument));
+
+        // only grows
+        let mut accounts = resize_accounts(f.empty_vesting_account(3));
+        assert_eq!(resize(&mut accounts, 3), Err(ProgramError::InvalidArgument));
+        assert_eq!(resize(&mut accounts, 1), Err(ProgramError::InvalidArgument));
+
+        // after Create the schedules are checked against the size, TopUp takes over
+        let mut accounts = resize_accounts(f.vesting_account(vec![vesting_schedule(10, 100)]));
+        assert_eq!(resize(&mut accounts, 3), Err(ProgramError::InvalidArgument));
+        assert_eq!(
+            accounts[2].data.len(),
+            VestingScheduleHeader::account_len(1, ScheduleLayout::Plain)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- create
+
+    #[test]
+    fn test_create_writes_header_and_schedules() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        f.create(&mut accounts, vec![schedule(10, 100)]).unwrap();
+
+        let data = &accounts[1].data;
+        assert_eq!(
+            VestingScheduleHeader::unpack(&data[..VestingScheduleHeader::LEN]).unwrap(),
+            f.header(&[vesting_schedule(10, 100)])
+        );
+        assert_eq!(
+            unpack_schedules(&data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 100)]
+        );
+    }
+
+    #[test]
+    fn test_create_categorized() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        let create_categorized = |accounts: &mut [TestAccount], categories| {
+            Processor::process_create(
+                &f.program_id,
+                &account_infos(accounts),
+                f.seeds,
+                &f.mint,
+                &f.destination_token_account_key,
+                vec![schedule(10, 100)],
+                None,
+                CreateOptions {
+                    allow_immediate: true,
+                    categories,
+                    ..CreateOptions::default()
+                },
+            )
+        };
+
+        // every schedule needs a category, and the account needs room for them
+        assert_eq!(
+            create_categorized(
+                &mut accounts,
+                vec![ScheduleCategory::Salary, ScheduleCategory::Bonus]
+            ),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            create_categorized(&mut accounts, vec![ScheduleCategory::Salary]),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        accounts[1] = TestAccount::new(
+            f.vesting_account_key,
+            f.program_id,
+            vec![0; VestingScheduleHeader::account_len(1, ScheduleLayout::Categorized)],
+        )
+        .writable();
+        create_categorized(&mut accounts, vec![ScheduleCategory::Salary]).unwrap();
+        let header = VestingScheduleHeader::unpack_account(&accounts[1].data).unwrap();
+        assert_eq!(header.schedule_layout, ScheduleLayout::Categorized);
+        assert_eq!(
+            unpack_categories(&header, &accounts[1].data).unwrap(),
+            vec![ScheduleCategory::Salary]
+        );
+
+        // the category table stays put while the schedules are paid out
+        let mut unlock_accounts = f.unlock_accounts(20, vec![]);
+        unlock_accounts[2] = accounts[1].clone();
+        unlock_accounts[3] = f.vesting_token_account(100);
+        f.unlock(&mut unlock_accounts).unwrap();
+        let data = &unlock_accounts[2].data;
+        let header = VestingScheduleHeader::unpack_account(data).unwrap();
+        assert_eq!(
+            unpack_schedules(&data[VestingScheduleHeader::LEN..header.schedules_end()]).unwrap(),
+            vec![vesting_schedule(10, 0)]
+        );
+        assert_eq!(
+            unpack_categories(&header, data).unwrap(),
+            vec![ScheduleCategory::Salary]
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_wrong_vesting_account() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts[1].key = Pubkey::new_unique();
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_create_requires_granter_signature() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts[3].is_signer = false;
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_foreign_vesting_account() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts[1].owner = Pubkey::new_unique();
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_existing_contract() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts[1] = f.vesting_account(vec![vesting_schedule(10, 100)]);
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_delegated_vesting_token_account() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts[2] = accounts[2].clone().with_token_state(|a| {
+            a.delegate = COption::Some(Pubkey::new_unique());
+        });
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_create_with_token_2022() {
+        let f = VestingFixture::new();
+        let token_2022 = |accounts: &mut Vec<TestAccount>, extensions: &[ExtensionType]| {
+            accounts[0] = TestAccount::program(spl_token_2022::id());
+            accounts[2] = accounts[2].clone().with_token_extensions(extensions);
+            let mut mint = TestAccount::mint(f.mint, Pubkey::new_unique(), 0);
+            mint.owner = spl_token_2022::id();
+            accounts.push(mint);
+        };
+
+        let mut accounts = f.create_accounts(100);
+        token_2022(&mut accounts, &[]);
+        f.create(&mut accounts, vec![schedule(10, 100)]).unwrap();
+
+        // associated token accounts of Token-2022 are always ImmutableOwner
+        let mut accounts = f.create_accounts(100);
+        token_2022(&mut accounts, &[ExtensionType::ImmutableOwner]);
+        f.create(&mut accounts, vec![schedule(10, 100)]).unwrap();
+
+        for extension in [
+            ExtensionType::MemoTransfer,
+            ExtensionType::CpiGuard,
+            ExtensionType::NonTransferableAccount,
+        ] {
+            let mut accounts = f.create_accounts(100);
+            token_2022(&mut accounts, &[ExtensionType::ImmutableOwner, extension]);
+            assert_eq!(
+                f.create(&mut accounts, vec![schedule(10, 100)]),
+                Err(VestingError::IncompatibleTokenExtension.into()),
+                "{:?}",
+                extension
+            );
+        }
+    }
+
+    #[test]
+    fn test_create_checks_mint_and_destination() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts[2] = TestAccount::token_account(
+            accounts[2].key,
+            Pubkey::new_unique(),
+            f.vesting_account_key,
+            0,
+        );
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // both are required, in their place
+        let mut accounts = f.create_accounts(100);
+        accounts.truncate(6);
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        let mut accounts = f.create_accounts(100);
+        accounts.swap(5, 6);
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.create_accounts(100);
+        accounts[5].owner = Pubkey::new_unique();
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        let mut accounts = f.create_accounts(100);
+        accounts[6] = TestAccount::token_account(
+            f.destination_token_account_key,
+            Pubkey::new_unique(),
+            f.destination_owner,
+            0,
+        );
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // and the destination is the one in the instruction
+        let mut accounts = f.create_accounts(100);
+        accounts[6].key = Pubkey::new_unique();
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_process_instruction_checks_account_specs() {
+        let f = VestingFixture::new();
+        let ix = VestingInstruction::Create {
+            seeds: f.seeds,
+            token_mint_addr: f.mint,
+            token_dest_addr: f.destination_token_account_key,
+            allow_immediate: true,
+            gas_rebate: 0,
+            gas_deposit: 0,
+            min_claim_amount: 0,
+            destination_locked: false,
+            forbid_self_dealing: false,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            relative: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            schedules: vec![schedule(10, 100)],
+            categories: vec![],
+        };
+        let mut accounts = f.create_accounts(100);
+        assert_eq!(
+            Processor::process_instruction(
+                &f.program_id,
+                &account_infos(&mut accounts[..4]),
+                &ix.pack()
+            ),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        accounts[1].is_writable = false;
+        assert_eq!(
+            Processor::process_instruction(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                &ix.pack()
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+        accounts[1].is_writable = true;
+        Processor::process_instruction(&f.program_id, &account_infos(&mut accounts), &ix.pack())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_create_rejects_foreign_source_token_account() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts[4] = accounts[4]
+            .clone()
+            .with_token_state(|a| a.owner = Pubkey::new_unique());
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(VestingError::SourceOwnerMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_insufficient_funds() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(99);
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_amount_overflow() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(u64::MAX);
+        accounts[1] = f.empty_vesting_account(2);
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, u64::MAX), schedule(20, 1)]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn test_create_forbids_self_dealing() {
+        let f = VestingFixture::new();
+        let create_with =
+            |accounts: &mut [TestAccount], forbid_self_dealing: bool, revocable: bool| {
+                Processor::process_create(
+                    &f.program_id,
+                    &account_infos(accounts),
+                    f.seeds,
+                    &f.mint,
+                    &f.destination_token_account_key,
+                    vec![schedule(10, 100)],
+                    None,
+                    CreateOptions {
+                        allow_immediate: true,
+                        forbid_self_dealing,
+                        revocable,
+                        ..CreateOptions::default()
+                    },
+                )
+            };
+        let create_forbidding = |accounts: &mut [TestAccount], forbid_self_dealing: bool| {
+            create_with(accounts, forbid_self_dealing, true)
+        };
+        let granter_owned_destination =
+            TestAccount::token_account(f.destination_token_account_key, f.mint, f.granter, 0);
+
+        let self_dealing_accounts = || {
+            let mut accounts = f.create_accounts(100);
+            accounts[6] = granter_owned_destination.clone();
+            accounts
+        };
+
+        let mut accounts = self_dealing_accounts();
+        assert_eq!(
+            create_forbidding(&mut accounts, true),
+            Err(VestingError::SelfDealing.into())
+        );
+        // off unless asked for
+        let mut accounts = self_dealing_accounts();
+        create_forbidding(&mut accounts, false).unwrap();
+
+        let mut accounts = f.create_accounts(100);
+        create_forbidding(&mut accounts, true).unwrap();
+
+        // or turned on for the whole deployment
+        let mut config = config_account(&f.program_id, &Pubkey::new_unique(), 0);
+        let mut config_state = ProgramConfig::unpack(&config.data).unwrap();
+        config_state.forbid_self_dealing = true;
+        config_state.pack_into_slice(&mut config.data);
+        let mut accounts = self_dealing_accounts();
+        accounts.push(config.clone());
+        assert_eq!(
+            create_forbidding(&mut accounts, false),
+            Err(VestingError::SelfDealing.into())
+        );
+
+        // an irrevocable contract pays out as set up, so the granter may own its destination either way
+        let mut accounts = self_dealing_accounts();
+        create_with(&mut accounts, true, false).unwrap();
+        let mut accounts = self_dealing_accounts();
+        accounts.push(config);
+        create_with(&mut accounts, false, false).unwrap();
+    }
+
+    #[test]
+    fn test_create_from_mint() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_from_mint_accounts(0);
+        f.create_from_mint(&mut accounts, u64::MAX).unwrap();
+
+        // same contract as a funded Create, the mint authority being the granter
+        let data = &accounts[1].data;
+        assert_eq!(
+            VestingScheduleHeader::unpack(&data[..VestingScheduleHeader::LEN]).unwrap(),
+            f.header(&[vesting_schedule(10, 100)])
+        );
+    }
+
+    #[test]
+    fn test_create_from_mint_checks_the_mint() {
+        let f = VestingFixture::new();
+
+        let mut accounts = f.create_from_mint_accounts(0);
+        accounts[4] = TestAccount::mint(f.mint, Pubkey::new_unique(), 0);
+        assert_eq!(
+            f.create_from_mint(&mut accounts, u64::MAX),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.create_from_mint_accounts(0);
+        accounts[4] = TestAccount::mint(Pubkey::new_unique(), f.granter, 0);
+        assert_eq!(
+            f.create_from_mint(&mut accounts, u64::MAX),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.create_from_mint_accounts(u64::MAX - 99);
+        assert_eq!(
+            f.create_from_mint(&mut accounts, u64::MAX),
+            Err(VestingError::MintSupplyOverflow.into())
+        );
+        let mut accounts = f.create_from_mint_accounts(u64::MAX - 100);
+        f.create_from_mint(&mut accounts, u64::MAX).unwrap();
+    }
+
+    #[test]
+    fn test_create_from_mint_checks_the_supply_cap() {
+        let f = VestingFixture::new();
+
+        // the grant is 100
+        let mut accounts = f.create_from_mint_accounts(901);
+        assert_eq!(
+            f.create_from_mint(&mut accounts, 1_000),
+            Err(VestingError::MintSupplyCapExceeded.into())
+        );
+        let mut accounts = f.create_from_mint_accounts(900);
+        f.create_from_mint(&mut accounts, 1_000).unwrap();
+    }
+
+    // ----------------------------------------------------------------------------- unlock
+
+    #[test]
+    fn test_unlock_zeroes_released_schedules_only() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(
+            15,
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 50)],
+        );
+        f.unlock(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 50)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.next_release_time, 20);
+        assert_eq!(header.unlock_count, 1);
+    }
+
+    #[test]
+    fn test_unlock_checks_the_token_program() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[0] = TestAccount::program(Pubkey::new_unique());
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[0] = TestAccount::program(spl_token_2022::id());
+        accounts[3] = accounts[3]
+            .clone()
+            .with_token_extensions(&[ExtensionType::ImmutableOwner]);
+        f.unlock(&mut accounts).unwrap();
+    }
+
+    #[test]
+    fn test_unlock_with_transfer_fees() {
+        let f = VestingFixture::new();
+        let withholding_key = Pubkey::new_unique();
+        let fee_accounts = || {
+            let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 1_000)]);
+            accounts[0] = TestAccount::program(spl_token_2022::id());
+            let mut header =
+                VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN])
+                    .unwrap();
+            header.withholding_bps = 2_000;
+            header.withholding_destination = withholding_key;
+            header.pack_into_slice(&mut accounts[2].data[..VestingScheduleHeader::LEN]);
+            accounts[3] = accounts[3].clone().with_token_extensions(&[
+                ExtensionType::ImmutableOwner,
+                ExtensionType::TransferFeeAmount,
+            ]);
+            accounts.push(TestAccount::token_account(
+                withholding_key,
+                f.mint,
+                Pubkey::new_unique(),
+                0,
+            ));
+            accounts
+        };
+
+        // every transfer has to name the fee, which takes the mint
+        let mut accounts = fee_accounts();
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        let mut accounts = fee_accounts();
+        let mut mint = TestAccount::fee_mint(f.mint, 100, 5);
+        mint.owner = spl_token::id();
+        accounts.push(mint);
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        // 1% up to 5 a transfer: 2 off the 200 withheld, 5 off the 800 paid out
+        let mut accounts = fee_accounts();
+        accounts.push(TestAccount::fee_mint(f.mint, 100, 5));
+        f.unlock(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!((header.unlocked_gross, header.unlocked_net), (1_000, 993));
+
+        // without fees the two stay the same
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 1_000)]);
+        f.unlock(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!((header.unlocked_gross, header.unlocked_net), (1_000, 1_000));
+    }
+
+    #[test]
+    fn test_fee_mint() {
+        let mint_key = Pubkey::new_unique();
+        let mut mints = [TestAccount::fee_mint(mint_key, 100, 5)];
+        let mints = account_infos(&mut mints);
+        assert_eq!(
+            Processor::fee_mint(&Pubkey::new_unique(), &mints, 0).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+        let fee_mint = Processor::fee_mint(&mint_key, &mints, 0).unwrap();
+        assert_eq!(fee_mint.decimals, 6);
+        assert_eq!(fee_mint.fee(100), Ok(1));
+        assert_eq!(fee_mint.fee(1_000), Ok(5));
+        // what Create sends for the vesting token account to receive the total
+        assert_eq!(fee_mint.gross_up(99), Ok(100));
+        assert_eq!(fee_mint.gross_up(10_000), Ok(10_005));
+
+        // nothing gets through a 100% fee
+        let mut mints = [TestAccount::fee_mint(mint_key, 10_000, u64::MAX)];
+        let mints = account_infos(&mut mints);
+        let fee_mint = Processor::fee_mint(&mint_key, &mints, 0).unwrap();
+        assert_eq!(fee_mint.gross_up(1), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_unlock_by_anyone_pays_the_stored_destination() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        // a crank signing as the rebate receiver is no different from nobody signing at all
+        accounts.push(TestAccount::wallet(Pubkey::new_unique()).signer());
+        f.unlock(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.destination_address, f.destination_token_account_key);
+        assert_eq!(header.unlock_count, 1);
+    }
+
+    #[test]
+    fn test_partial_unlock_takes_a_share_of_every_release() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(
+            25,
+            vec![
+                vesting_schedule(10, 300),
+                vesting_schedule(20, 100),
+                vesting_schedule(30, 50),
+            ],
+        );
+        accounts.push(TestAccount::wallet(f.destination_owner).signer());
+        f.unlock_partial(&mut accounts, 200).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![
+                vesting_schedule(10, 150),
+                vesting_schedule(20, 50),
+                vesting_schedule(30, 50)
+            ]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.next_release_time, 10);
+        assert!(header.has_claimable);
+
+        // a cap above what's left takes all of it
+        f.unlock_partial(&mut accounts, 1_000).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![
+                vesting_schedule(10, 0),
+                vesting_schedule(20, 0),
+                vesting_schedule(30, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_unlock_requires_destination_owner() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        assert_eq!(
+            f.unlock_partial(&mut accounts, 50),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        accounts.push(TestAccount::wallet(Pubkey::new_unique()).signer());
+        assert_eq!(
+            f.unlock_partial(&mut accounts, 50),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[5] = TestAccount::wallet(f.destination_owner).signer();
+        assert_eq!(
+            Processor::process_unlock(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                f.seeds,
+                None,
+                1,
+                50,
+                None,
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+        f.unlock_partial(&mut accounts, 50).unwrap();
+    }
+
+    #[test]
+    fn test_unlock_grant_derives_the_seeds() {
+        let mut f = VestingFixture::new();
+        f.seeds = vesting_seeds(&f.granter, &f.destination_owner, &f.mint, 2);
+        let (vesting_account_key, bump) = find_granted_vesting_address(&f.seeds, &f.program_id);
+        f.vesting_account_key = vesting_account_key;
+        let grant = |nonce| VestingInstruction::UnlockGrant {
+            granter: f.granter,
+            beneficiary: f.destination_owner,
+            mint: f.mint,
+            nonce,
+        };
+
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[2].data[BUMP_OFFSET] = bump;
+        // under the contract's bump, another nonce's seeds derive some other address or none at all
+        assert!(Processor::process_instruction(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            &grant(1).pack()
+        )
+        .is_err());
+        Processor::process_instruction(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            &grant(2).pack(),
+        )
+        .unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0)]
+        );
+    }
+
+    #[test]
+    fn test_unlock_pays_gas_rebate() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts_with_rebate(7000);
+        let (vesting_lamports, relayer_lamports) = (accounts[2].lamports, accounts[5].lamports);
+        f.unlock(&mut accounts).unwrap();
+
+        assert_eq!(accounts[2].lamports, vesting_lamports - 5000);
+        assert_eq!(accounts[5].lamports, relayer_lamports + 5000);
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.gas_escrow, 2000);
+    }
+
+    #[test]
+    fn test_unlock_without_enough_gas_escrow_still_unlocks() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts_with_rebate(4999);
+        let vesting_lamports = accounts[2].lamports;
+        f.unlock(&mut accounts).unwrap();
+
+        assert_eq!(accounts[2].lamports, vesting_lamports);
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0)]
+        );
+    }
+
+    #[test]
+    fn test_unlock_skips_rebate_for_accountless_signer() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts_with_rebate(7000);
+        // eg a PDA destination owner signing through invoke_signed
+        accounts[5].lamports = 0;
+        let vesting_lamports = accounts[2].lamports;
+        f.unlock(&mut accounts).unwrap();
+
+        assert_eq!(accounts[2].lamports, vesting_lamports);
+        assert_eq!(accounts[5].lamports, 0);
+    }
+
+    #[test]
+    fn test_permissioned_unlock_requires_destination_owner() {
+        let f = VestingFixture::new();
+        let mut accounts = f.permissioned_unlock_accounts();
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // someone else signing doesn't help
+        accounts.push(TestAccount::wallet(Pubkey::new_unique()).signer());
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[5] = TestAccount::wallet(f.destination_owner).signer();
+        f.unlock(&mut accounts).unwrap();
+    }
+
+    #[test]
+    fn test_delegate_only_unlock_requires_delegate() {
+        let f = VestingFixture::new();
+        let delegate = Pubkey::new_unique();
+        let mut accounts = f.permissioned_unlock_accounts();
+        let mut header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        header.unlock_policy = UnlockPolicy::DelegateOnly;
+        header.pack_into_slice(&mut accounts[2].data[..VestingScheduleHeader::LEN]);
+        accounts[4] = accounts[4]
+            .clone()
+            .with_token_state(|a| a.delegate = COption::Some(delegate));
+
+        // not even the destination owner, it has to approve itself as the delegate if it wants to unlock
+        accounts.push(TestAccount::wallet(f.destination_owner).signer());
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        accounts[5] = TestAccount::wallet(delegate);
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[5] = TestAccount::wallet(delegate).signer();
+        f.unlock(&mut accounts).unwrap();
+    }
+
+    #[test]
+    fn test_unlock_with_permit() {
+        let f = VestingFixture::new();
+        let mut accounts = f.permit_unlock_accounts(&f.destination_owner, 0);
+        f.permit_unlock(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0)]
+        );
+    }
+
+    #[test]
+    fn test_unlock_with_permit_rejects_other_signer() {
+        let f = VestingFixture::new();
+        let mut accounts = f.permit_unlock_accounts(&Pubkey::new_unique(), 0);
+        assert_eq!(
+            f.permit_unlock(&mut accounts),
+            Err(VestingError::InvalidPermit.into())
+        );
+    }
+
+    #[test]
+    fn test_unlock_with_permit_cannot_be_replayed() {
+        let f = VestingFixture::new();
+        let mut accounts = f.permit_unlock_accounts(&f.destination_owner, 0);
+        f.permit_unlock(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.permit_nonce, 1);
+
+        // the same permit and signature, submitted again
+        assert_eq!(
+            f.permit_unlock(&mut accounts),
+            Err(VestingError::InvalidPermit.into())
+        );
+    }
+
+    #[test]
+    fn test_unlock_with_permit_rejects_wrong_nonce() {
+        let f = VestingFixture::new();
+        let mut accounts = f.permit_unlock_accounts(&f.destination_owner, 1);
+        assert_eq!(
+            f.permit_unlock(&mut accounts),
+            Err(VestingError::InvalidPermit.into())
+        );
+    }
+
+    #[test]
+    fn test_unlock_with_permit_rejects_expired_permit() {
+        let f = VestingFixture::new();
+        let mut accounts = f.permit_unlock_accounts(&f.destination_owner, 0);
+        accounts[1] = TestAccount::clock(20);
+        assert_eq!(
+            f.permit_unlock(&mut accounts),
+            Err(VestingError::PermitExpired.into())
+        );
+    }
+
+    #[test]
+    fn test_unlock_with_permit_requires_ed25519_instruction() {
+        let f = VestingFixture::new();
+        let mut accounts = f.permit_unlock_accounts(&f.destination_owner, 0);
+        let mut forged = ed25519_verify(
+            &f.destination_owner,
+            &[0; 64],
+            &claim_permit_message(&f.program_id, &f.vesting_account_key, 20, 0),
+        );
+        forged.program_id = Pubkey::new_unique();
+        accounts[5] = TestAccount::instructions(&[forged.clone(), forged], 1);
+        assert_eq!(
+            f.permit_unlock(&mut accounts),
+            Err(VestingError::InvalidPermit.into())
+        );
+    }
+
+    #[test]
+    fn test_set_unlock_policy() {
+        let f = VestingFixture::new();
+        let mut accounts = vec![
+            f.vesting_account(vec![vesting_schedule(10, 100)]),
+            f.destination_token_account(),
+            TestAccount::wallet(f.destination_owner),
+        ];
+        let set = |accounts: &mut [TestAccount], ix: VestingInstruction| {
+            Processor::process_instruction(&f.program_id, &account_infos(accounts), &ix.pack())
+        };
+        let policy = |accounts: &[TestAccount]| {
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN])
+                .unwrap()
+                .unlock_policy
+        };
+        let set_permissionless = |enabled| VestingInstruction::SetPermissionlessUnlock {
+            seeds: f.seeds,
+            enabled,
+        };
+        assert_eq!(
+            set(&mut accounts, set_permissionless(false)),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[2].is_signer = true;
+        set(&mut accounts, set_permissionless(false)).unwrap();
+        assert_eq!(policy(&accounts), UnlockPolicy::DestinationOwnerOnly);
+
+        set(
+            &mut accounts,
+            VestingInstruction::SetUnlockPolicy {
+                seeds: f.seeds,
+                policy: UnlockPolicy::DelegateOnly,
+            },
+        )
+        .unwrap();
+        assert_eq!(policy(&accounts), UnlockPolicy::DelegateOnly);
+
+        set(&mut accounts, set_permissionless(true)).unwrap();
+        assert_eq!(policy(&accounts), UnlockPolicy::Permissionless);
+    }
+
+    #[test]
+    fn test_set_rollover_requires_both_parties() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        for signer in [1, 3] {
+            let mut accounts = f.set_rollover_accounts(schedules());
+            accounts[signer].is_signer = false;
+            assert_eq!(
+                f.set_rollover(&mut accounts, true),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+
+        let mut accounts = f.set_rollover_accounts(schedules());
+        f.set_rollover(&mut accounts, true).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert!(header.roll_into_final);
+        // nothing can be claimed before the final schedule anymore
+        assert_eq!(header.next_release_time, 20);
+    }
+
+    #[test]
+    fn test_unlock_rolls_into_final_schedule() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let mut accounts = f.set_rollover_accounts(schedules());
+        f.set_rollover(&mut accounts, true).unwrap();
+
+        // the first schedule has matured, but is locked up until the final one
+        let mut unlock_accounts = f.unlock_accounts(15, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut unlock_accounts = f.unlock_accounts(25, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 0)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&unlock_accounts[2].data[..VestingScheduleHeader::LEN])
+                .unwrap();
+        assert_eq!(header.next_release_time, NO_PENDING_RELEASE);
+    }
+
+    #[test]
+    fn test_rollover_and_future_destination_exclude_each_other() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let mut accounts = f.set_rollover_accounts(schedules());
+        f.set_rollover(&mut accounts, true).unwrap();
+        let mut change_accounts = f.change_destination_accounts(schedules());
+        change_accounts[0].data = accounts[0].data.clone();
+        assert_eq!(
+            f.change_future_destination(&mut change_accounts, 20),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut change_accounts = f.change_destination_accounts(schedules());
+        f.change_future_destination(&mut change_accounts, 20)
+            .unwrap();
+        let mut accounts = f.set_rollover_accounts(schedules());
+        accounts[0].data = change_accounts[0].data.clone();
+        accounts[2] =
+            TestAccount::token_account(change_accounts[3].key, f.mint, f.destination_owner, 0);
+        assert_eq!(
+            f.set_rollover(&mut accounts, true),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_unlock_respects_min_claim_amount() {
+        let f = VestingFixture::new();
+        let schedules = || {
+            vec![
+                vesting_schedule(10, 5),
+                vesting_schedule(20, 5),
+                vesting_schedule(30, 50),
+            ]
+        };
+        let mut accounts = vec![
+            f.vesting_account(schedules()),
+            f.destination_token_account(),
+            TestAccount::wallet(f.destination_owner).signer(),
+        ];
+        Processor::process_set_min_claim_amount(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            f.seeds,
+            10,
+        )
+        .unwrap();
+
+        let mut unlock_accounts = f.unlock_accounts(15, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(VestingError::ClaimBelowMinimum.into())
+        );
+
+        // dust adds up until it's worth a transfer
+        let mut unlock_accounts = f.unlock_accounts(25, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![
+                vesting_schedule(10, 0),
+                vesting_schedule(20, 0),
+                vesting_schedule(30, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unlock_allows_last_claim_below_minimum() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 5)]);
+        let mut header = f.header(&[vesting_schedule(10, 5)]);
+        header.min_claim_amount = 10;
+        header.pack_into_slice(&mut accounts[2].data[..VestingScheduleHeader::LEN]);
+        f.unlock(&mut accounts).unwrap();
+    }
+
+    #[test]
+    fn test_bounded_unlock_drains_across_calls() {
+        let f = VestingFixture::new();
+        let schedules = || {
+            vec![
+                vesting_schedule(10, 100),
+                vesting_schedule(40, 10),
+                vesting_schedule(20, 50),
+                vesting_schedule(30, 25),
+                vesting_schedule(10, 5),
+            ]
+        };
+        let mut accounts = f.unlock_accounts(35, schedules());
+        let header = |accounts: &[TestAccount]| {
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap()
+        };
+
+        // halfway through, next_release_time hasn't moved yet
+        f.unlock_bounded(&mut accounts, 2).unwrap();
+        assert_eq!(header(&accounts).unlock_cursor, 2);
+        assert_eq!(header(&accounts).sweep_next_release_time, 40);
+        assert_eq!(header(&accounts).next_release_time, 10);
+
+        f.unlock_bounded(&mut accounts, 2).unwrap();
+        assert_eq!(header(&accounts).unlock_cursor, 4);
+
+        // the last call wraps around and sets next_release_time from the whole sweep
+        f.unlock_bounded(&mut accounts, 2).unwrap();
+        assert_eq!(header(&accounts).unlock_cursor, 0);
+        assert_eq!(header(&accounts).next_release_time, 40);
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![
+                vesting_schedule(10, 0),
+                vesting_schedule(40, 10),
+                vesting_schedule(20, 0),
+                vesting_schedule(30, 0),
+                vesting_schedule(10, 0)
+            ]
+        );
+
+        // the next sweep finds nothing released, and still only moves the cursor
+        f.unlock_bounded(&mut accounts, 2).unwrap();
+        assert_eq!(header(&accounts).unlock_cursor, 2);
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_full_unlock_ends_bounded_sweep() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(
+            15,
+            vec![vesting_schedule(10, 100), vesting_schedule(10, 50)],
+        );
+        f.unlock_bounded(&mut accounts, 1).unwrap();
+        f.unlock(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.unlock_cursor, 0);
+        assert_eq!(header.next_release_time, NO_PENDING_RELEASE);
+    }
+
+    #[test]
+    fn test_bounded_unlock_rejects_rollover() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let mut accounts = f.unlock_accounts(15, schedules);
+        let mut header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        header.roll_into_final = true;
+        header.pack_into_slice(&mut accounts[2].data[..VestingScheduleHeader::LEN]);
+        assert_eq!(
+            f.unlock_bounded(&mut accounts, 1),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_range_unlock_leaves_the_cursor_behind() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(
+            35,
+            vec![
+                vesting_schedule(10, 100),
+                vesting_schedule(20, 50),
+                vesting_schedule(30, 25),
+                vesting_schedule(40, 10),
+            ],
+        );
+        let header = |accounts: &[TestAccount]| {
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap()
+        };
+
+        // only the range's schedules are claimed, and the sweep still starts from the first one
+        f.unlock_range(&mut accounts, Some(1), 2).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![
+                vesting_schedule(10, 100),
+                vesting_schedule(20, 0),
+                vesting_schedule(30, 0),
+                vesting_schedule(40, 10),
+            ]
+        );
+        assert_eq!(header(&accounts).unlock_cursor, 0);
+        assert_eq!(header(&accounts).next_release_time, 10);
+
+        // a range with nothing released in it that doesn't move the cursor is an error
+        assert_eq!(
+            f.unlock_range(&mut accounts, Some(1), 3),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // a range starting at the cursor carries the sweep on like a plain bounded unlock
+        f.unlock_range(&mut accounts, Some(0), 1).unwrap();
+        assert_eq!(header(&accounts).unlock_cursor, 1);
+        assert_eq!(
+            header(&accounts).sweep_next_release_time,
+            NO_PENDING_RELEASE
+        );
+    }
+
+    #[test]
+    fn test_range_unlock_rejects_bad_start_index() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(
+            15,
+            vec![vesting_schedule(10, 100), vesting_schedule(10, 50)],
+        );
+        assert_eq!(
+            f.unlock_range(&mut accounts, Some(2), 1),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            f.unlock_range(&mut accounts, Some(1), 0),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- revoke
+
+    #[test]
+    fn test_revoke_takes_back_unreleased_schedules() {
+        let f = VestingFixture::new();
+        let mut accounts = f.revoke_accounts(
+            15,
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 50)],
+        );
+        f.revoke(&mut accounts).unwrap();
+
+        // the released schedule stays claimable by the destination
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 0)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&accounts[3].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.next_release_time, 10);
+
+        // nothing left to revoke the second time around, but the attempt is counted
+        f.revoke(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[3].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.revoke_attempt_count, 2);
+    }
+
+    #[test]
+    fn test_revoke_burns_with_burn_policy() {
+        let f = VestingFixture::new();
+        let mut accounts = f.revoke_accounts(
+            15,
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 50)],
+        );
+        let mut header =
+            VestingScheduleHeader::unpack(&accounts[3].data[..VestingScheduleHeader::LEN]).unwrap();
+        header.revoke_policy = RevokePolicy::Burn;
+        header.pack_into_slice(&mut accounts[3].data[..VestingScheduleHeader::LEN]);
+
+        // nothing goes back to the granter, the mint takes the place of its token account
+        assert_eq!(f.revoke(&mut accounts), Err(ProgramError::InvalidArgument));
+        accounts[5] = TestAccount::mint(Pubkey::new_unique(), f.granter, 150);
+        assert_eq!(f.revoke(&mut accounts), Err(ProgramError::InvalidArgument));
+        accounts[5] = TestAccount::mint(f.mint, f.granter, 150);
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 0)]
+        );
+    }
+
+    #[test]
+    fn test_revoke_with_transfer_fees() {
+        let f = VestingFixture::new();
+        let fee_accounts = || {
+            let mut accounts = f.revoke_accounts(
+                15,
+                vec![vesting_schedule(10, 100), vesting_schedule(20, 50)],
+            );
+            accounts[0] = TestAccount::program(spl_token_2022::id());
+            accounts[4] = accounts[4].clone().with_token_extensions(&[
+                ExtensionType::ImmutableOwner,
+                ExtensionType::TransferFeeAmount,
+            ]);
+            accounts
+        };
+
+        // the transfer back to the granter has to name the fee, which takes the mint
+        let mut accounts = fee_accounts();
+        assert_eq!(
+            f.revoke(&mut accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        let mut accounts = fee_accounts();
+        accounts.push(TestAccount::fee_mint(f.mint, 100, 5));
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 0)]
+        );
+    }
+
+    #[test]
+    fn test_revoke_is_granter_only() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(20, 50)];
+
+        let mut accounts = f.revoke_accounts(15, schedules.clone());
+        accounts[2].is_signer = false;
+        assert_eq!(
+            f.revoke(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let mut accounts = f.revoke_accounts(15, schedules.clone());
+        accounts[2] = TestAccount::wallet(Pubkey::new_unique()).signer();
+        assert_eq!(f.revoke(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        // the tokens can only go back to the granter
+        let mut accounts = f.revoke_accounts(15, schedules);
+        accounts[5] =
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 0);
+        assert_eq!(f.revoke(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_irrevocable_contract_rejects_revoke() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(50);
+        Processor::process_create(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            f.seeds,
+            &f.mint,
+            &f.destination_token_account_key,
+            vec![schedule(20, 50)],
+            None,
+            CreateOptions {
+                allow_immediate: true,
+                revocable: false,
+                ..CreateOptions::default()
+            },
+        )
+        .unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[1].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert!(!header.revocable);
+
+        let mut revoke_accounts = f.revoke_accounts(15, vec![vesting_schedule(20, 50)]);
+        revoke_accounts[3].data = accounts[1].data.clone();
+        assert_eq!(
+            f.revoke(&mut revoke_accounts),
+            Err(VestingError::Irrevocable.into())
+        );
+        assert_eq!(revoke_accounts[3].data, accounts[1].data);
+    }
+
+    // ----------------------------------------------------------------------------- linear
+
+    /// Linear without a cliff
+    fn linear(start_time: u64, end_time: u64, total_amount: u64) -> LinearVesting {
+        LinearVesting {
+            cliff_time: 0,
+            cliff_amount: 0,
+            start_time,
+            end_time,
+            total_amount,
+        }
+    }
+
+    fn linear_state(
+        account: &TestAccount,
+    ) -> (VestingScheduleHeader, VestingSchedule, LinearVesting) {
+        let header = VestingScheduleHeader::unpack_account(&account.data).unwrap();
+        let schedules =
+            unpack_schedules(&account.data[VestingScheduleHeader::LEN..header.schedules_end()])
+                .unwrap();
+        let linear = unpack_linear(&header, &account.data).unwrap().unwrap();
+        (header, schedules[0].clone(), linear)
+    }
+
+    #[test]
+    fn test_create_linear() {
+        let f = VestingFixture::new();
+        let linear_account = || {
+            TestAccount::new(
+                f.vesting_account_key,
+                f.program_id,
+                vec![0; VestingScheduleHeader::account_len(1, ScheduleLayout::Linear)],
+            )
+            .writable()
+        };
+
+        // a plain account has no room for the LinearVesting
+        let mut accounts = f.create_accounts(1_000);
+        assert_eq!(
+            f.create_linear(&mut accounts, linear(100, 200, 1_000)),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // the release has to take some time
+        accounts[1] = linear_account();
+        assert_eq!(
+            f.create_linear(&mut accounts, linear(100, 100, 1_000)),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        f.create_linear(&mut accounts, linear(100, 200, 1_000))
+            .unwrap();
+        let (header, schedule, linear_vesting) = linear_state(&accounts[1]);
+        assert_eq!(header.schedule_layout, ScheduleLayout::Linear);
+        assert_eq!(header.next_release_time, 100);
+        assert_eq!(schedule, vesting_schedule(100, 1_000));
+        assert_eq!(linear_vesting, linear(100, 200, 1_000));
+
+        // the cliff is part of the grant, and can come first
+        let with_cliff = LinearVesting {
+            cliff_time: 50,
+            cliff_amount: 400,
+            ..linear(100, 200, 1_000)
+        };
+        let mut accounts = f.create_accounts(1_400);
+        accounts[1] = linear_account();
+        f.create_linear(&mut accounts, with_cliff.clone()).unwrap();
+        let (header, schedule, linear_vesting) = linear_state(&accounts[1]);
+        assert_eq!(header.next_release_time, 50);
+        assert_eq!(schedule, vesting_schedule(50, 1_400));
+        assert_eq!(linear_vesting, with_cliff);
+    }
+
+    #[test]
+    fn test_unlock_linear_releases_the_cliff_first() {
+        let f = VestingFixture::new();
+        // 400 at the cliff, then 600 over the next 100 seconds
+        let with_cliff = LinearVesting {
+            cliff_time: 150,
+            cliff_amount: 400,
+            ..linear(150, 250, 600)
+        };
+        let mut accounts = f.unlock_accounts(100, vec![]);
+        accounts[2] = f.linear_vesting_account(vesting_schedule(150, 1_000), with_cliff);
+        accounts[3] = f.vesting_token_account(1_000);
+
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        accounts[1] = TestAccount::clock(150);
+        f.unlock(&mut accounts).unwrap();
+        assert_eq!(linear_state(&accounts[2]).1, vesting_schedule(150, 600));
+
+        accounts[1] = TestAccount::clock(200);
+        f.unlock(&mut accounts).unwrap();
+        assert_eq!(linear_state(&accounts[2]).1, vesting_schedule(150, 300));
+    }
+
+    #[test]
+    fn test_unlock_linear_pays_what_accrued_since_the_last_unlock() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(50, vec![]);
+        accounts[2] =
+            f.linear_vesting_account(vesting_schedule(100, 1_000), linear(100, 200, 1_000));
+        accounts[3] = f.vesting_token_account(1_000);
+
+        // nothing before the start
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        accounts[1] = TestAccount::clock(150);
+        f.unlock(&mut accounts).unwrap();
+        assert_eq!(linear_state(&accounts[2]).1, vesting_schedule(100, 500));
+        // the same instant again has nothing new
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        accounts[1] = TestAccount::clock(175);
+        f.unlock(&mut accounts).unwrap();
+        assert_eq!(linear_state(&accounts[2]).1, vesting_schedule(100, 250));
+
+        accounts[1] = TestAccount::clock(300);
+        f.unlock(&mut accounts).unwrap();
+        let (header, schedule, linear_vesting) = linear_state(&accounts[2]);
+        assert_eq!(schedule, vesting_schedule(100, 0));
+        assert_eq!(linear_vesting, linear(100, 200, 1_000));
+        assert_eq!(header.state, ContractState::Exhausted);
+    }
+
+    #[test]
+    fn test_revoke_linear_keeps_what_accrued() {
+        let f = VestingFixture::new();
+        let mut accounts = f.revoke_accounts(150, vec![]);
+        // 250 out of 1_000 claimed so far
+        accounts[3] = f.linear_vesting_account(vesting_schedule(100, 750), linear(100, 200, 1_000));
+        accounts[4] = f.vesting_token_account(750);
+
+        f.revoke(&mut accounts).unwrap();
+        let (header, schedule, linear_vesting) = linear_state(&accounts[3]);
+        assert_eq!(schedule, vesting_schedule(100, 250));
+        assert_eq!(linear_vesting, linear(100, 150, 500));
+        assert_eq!(header.state, ContractState::Active);
+
+        // nothing accrues from here on
+        accounts[1] = TestAccount::clock(175);
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(linear_state(&accounts[3]).1, vesting_schedule(100, 250));
+    }
+
+    #[test]
+    fn test_linear_rejects_a_future_destination() {
+        let f = VestingFixture::new();
+        let mut accounts = f.change_destination_accounts(vec![]);
+        accounts[0] =
+            f.linear_vesting_account(vesting_schedule(100, 1_000), linear(100, 200, 1_000));
+        assert_eq!(
+            Processor::process_change_destination(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                f.seeds,
+                Some(150),
+                None,
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- accelerate
+
+    #[test]
+    fn test_accelerate_releases_everything_now() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        // Accelerate takes the clock, granter and vesting account of Revoke
+        let mut accounts = f.revoke_accounts(15, schedules());
+        f.accelerate(&mut accounts[1..4]).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 100), vesting_schedule(15, 50)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&accounts[3].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.next_release_time, 10);
+        assert!(header.has_claimable);
+
+        // nothing is left to accelerate, nor to revoke
+        let data = accounts[3].data.clone();
+        f.accelerate(&mut accounts[1..4]).unwrap();
+        assert_eq!(accounts[3].data, data);
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 100), vesting_schedule(15, 50)]
+        );
+
+        // and the destination can claim it all
+        let mut unlock_accounts = f.unlock_accounts(15, schedules());
+        unlock_accounts[2].data = accounts[3].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(15, 0)]
+        );
+    }
+
+    #[test]
+    fn test_accelerate_is_granter_only() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(20, 50)];
+
+        let mut accounts = f.revoke_accounts(15, schedules.clone());
+        accounts[2].is_signer = false;
+        assert_eq!(
+            f.accelerate(&mut accounts[1..4]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let mut accounts = f.revoke_accounts(15, schedules);
+        accounts[2] = TestAccount::wallet(Pubkey::new_unique()).signer();
+        assert_eq!(
+            f.accelerate(&mut accounts[1..4]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_accelerate_waits_for_the_legacy_cutoff() {
+        let f = VestingFixture::new();
+        let mut accounts = f.revoke_accounts(15, vec![vesting_schedule(20, 50)]);
+        let mut header =
+            VestingScheduleHeader::unpack(&accounts[3].data[..VestingScheduleHeader::LEN]).unwrap();
+        header.legacy_cutoff = 20;
+        header.pack_into_slice(&mut accounts[3].data[..VestingScheduleHeader::LEN]);
+        assert_eq!(
+            f.accelerate(&mut accounts[1..4]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_accelerate_linear() {
+        let f = VestingFixture::new();
+        let mut accounts = f.revoke_accounts(150, vec![]);
+        // 250 out of 1_000 claimed so far, 250 more claimable
+        accounts[3] = f.linear_vesting_account(vesting_schedule(100, 750), linear(100, 200, 1_000));
+        f.accelerate(&mut accounts[1..4]).unwrap();
+
+        let (header, schedule, linear_vesting) = linear_state(&accounts[3]);
+        assert_eq!(schedule, vesting_schedule(100, 750));
+        assert_eq!(linear_vesting, linear(100, 150, 1_000));
+        assert_eq!(linear_claimable(&schedule, &linear_vesting, 150), 750);
+        assert!(header.has_claimable);
+    }
+
+    // ----------------------------------------------------------------------------- change granter
+
+    #[test]
+    fn test_change_granter_hands_the_contract_over() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(20, 50)];
+        // ChangeGranter takes the first two accounts of SetRollover
+        let mut accounts = f.set_rollover_accounts(schedules());
+        let new_granter = Pubkey::new_unique();
+        f.change_granter(&mut accounts[..2], &new_granter).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.granter_address, new_granter);
+
+        // the previous granter is out
+        assert_eq!(
+            f.change_granter(&mut accounts[..2], &f.granter),
+            Err(ProgramError::InvalidArgument)
+        );
+        let mut revoke_accounts = f.revoke_accounts(15, schedules());
+        revoke_accounts[3].data = accounts[0].data.clone();
+        assert_eq!(
+            f.accelerate(&mut revoke_accounts[1..4]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // and the new one is in
+        revoke_accounts[2] = TestAccount::wallet(new_granter).signer();
+        f.accelerate(&mut revoke_accounts[1..4]).unwrap();
+    }
+
+    #[test]
+    fn test_change_granter_checks() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(20, 50)];
+
+        let mut accounts = f.set_rollover_accounts(schedules.clone());
+        accounts[1].is_signer = false;
+        assert_eq!(
+            f.change_granter(&mut accounts[..2], &Pubkey::new_unique()),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let mut accounts = f.set_rollover_accounts(schedules.clone());
+        accounts[1] = TestAccount::wallet(f.destination_owner).signer();
+        assert_eq!(
+            f.change_granter(&mut accounts[..2], &Pubkey::new_unique()),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.set_rollover_accounts(schedules);
+        assert_eq!(
+            f.change_granter(&mut accounts[..2], &Pubkey::default()),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- revision
+
+    #[test]
+    fn test_revision() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(20, 50)];
+        let mut accounts = f.set_rollover_accounts(schedules());
+        f.check_revision(&mut accounts[..1], 0).unwrap();
+
+        // every change one of the parties makes counts
+        let new_granter = Pubkey::new_unique();
+        f.change_granter(&mut accounts[..2], &new_granter).unwrap();
+        accounts[1] = TestAccount::wallet(new_granter).signer();
+        f.change_granter(&mut accounts[..2], &f.granter).unwrap();
+        f.check_revision(&mut accounts[..1], 2).unwrap();
+        assert_eq!(
+            f.check_revision(&mut accounts[..1], 1),
+            Err(VestingError::StaleRevision.into())
+        );
+
+        // a crank's doesn't
+        let mut unlock_accounts = f.unlock_accounts(25, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        f.check_revision(&mut unlock_accounts[2..3], 2).unwrap();
+
+        // a change that fails leaves it alone too
+        accounts[1] = TestAccount::wallet(f.destination_owner).signer();
+        assert!(f.change_granter(&mut accounts[..2], &new_granter).is_err());
+        f.check_revision(&mut accounts[..1], 2).unwrap();
+
+        let mut other_program = f.set_rollover_accounts(schedules());
+        other_program[0].owner = Pubkey::new_unique();
+        assert_eq!(
+            f.check_revision(&mut other_program[..1], 0),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_expected_revision() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let change_destination_at = |accounts: &mut [TestAccount], expected_revision| {
+            Processor::process_change_destination(
+                &f.program_id,
+                &account_infos(accounts),
+                f.seeds,
+                None,
+                Some(expected_revision),
+            )
+        };
+        let revoke_at = |accounts: &mut [TestAccount], expected_revision| {
+            Processor::process_revoke(
+                &f.program_id,
+                &account_infos(accounts),
+                f.seeds,
+                Some(expected_revision),
+            )
+        };
+
+        let mut accounts = f.change_destination_accounts(schedules());
+        assert_eq!(
+            change_destination_at(&mut accounts, 1),
+            Err(VestingError::StaleRevision.into())
+        );
+        change_destination_at(&mut accounts, 0).unwrap();
+
+        // a revoke proposed before the destination moved doesn't go through anymore
+        let mut revoke_accounts = f.revoke_accounts(15, schedules());
+        revoke_accounts[3].data = accounts[0].data.clone();
+        assert_eq!(
+            revoke_at(&mut revoke_accounts, 0),
+            Err(VestingError::StaleRevision.into())
+        );
+        revoke_at(&mut revoke_accounts, 1).unwrap();
+        assert_eq!(
+            unpack_schedules(&revoke_accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 0)]
+        );
+    }
+
+    // ----------------------------------------------------------------------------- activate
+
+    #[test]
+    fn test_relative_create_waits_for_activate() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(150);
+        accounts[1] = f.empty_vesting_account(2);
+        // offsets from the start, so "in the past" doesn't apply
+        f.create_relative(&mut accounts, vec![schedule(0, 100), schedule(30, 50)])
+            .unwrap();
+        let relative_data = accounts[1].data.clone();
+        let header =
+            VestingScheduleHeader::unpack(&relative_data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.state, ContractState::AwaitingActivation);
+        assert_eq!(header.next_release_time, NO_PENDING_RELEASE);
+        assert_eq!(header.activation_time, 0);
+
+        // nothing can be claimed yet
+        let schedules = || vec![vesting_schedule(0, 100), vesting_schedule(30, 50)];
+        let mut unlock_accounts = f.unlock_accounts(1_000, schedules());
+        unlock_accounts[2].data = relative_data.clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(VestingError::AwaitingActivation.into())
+        );
+
+        // Activate takes the clock, granter and vesting account of Revoke
+        let mut accounts = f.revoke_accounts(1_000, schedules());
+        accounts[3].data = relative_data;
+        f.activate(&mut accounts[1..4]).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(1_000, 100), vesting_schedule(1_030, 50)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&accounts[3].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.state, ContractState::Active);
+        assert_eq!(header.activation_time, 1_000);
+        assert_eq!(header.next_release_time, 1_000);
+        assert!(header.has_claimable);
+
+        // only once
+        assert_eq!(
+            f.activate(&mut accounts[1..4]),
+            Err(VestingError::InvalidContractState.into())
+        );
+
+        unlock_accounts[2].data = accounts[3].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(1_000, 0), vesting_schedule(1_030, 50)]
+        );
+    }
+
+    #[test]
+    fn test_revoke_before_activate_takes_everything() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(150);
+        accounts[1] = f.empty_vesting_account(2);
+        f.create_relative(&mut accounts, vec![schedule(0, 100), schedule(30, 50)])
+            .unwrap();
+
+        let mut revoke_accounts = f.revoke_accounts(
+            1_000,
+            vec![vesting_schedule(0, 100), vesting_schedule(30, 50)],
+        );
+        revoke_accounts[3].data = accounts[1].data.clone();
+        f.revoke(&mut revoke_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&revoke_accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(0, 0), vesting_schedule(30, 0)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&revoke_accounts[3].data[..VestingScheduleHeader::LEN])
+                .unwrap();
+        assert_eq!(header.state, ContractState::Exhausted);
+        assert_eq!(
+            f.activate(&mut revoke_accounts[1..4]),
+            Err(VestingError::InvalidContractState.into())
+        );
+    }
+
+    #[test]
+    fn test_activate_checks() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(20, 50)];
+
+        // a contract created with absolute release times has nothing to anchor
+        let mut accounts = f.revoke_accounts(15, schedules.clone());
+        assert_eq!(
+            f.activate(&mut accounts[1..4]),
+            Err(VestingError::InvalidContractState.into())
+        );
+
+        let mut accounts = f.create_accounts(50);
+        f.create_relative(&mut accounts, vec![schedule(20, 50)])
+            .unwrap();
+        let relative_data = accounts[1].data.clone();
+
+        let mut accounts = f.revoke_accounts(15, schedules.clone());
+        accounts[3].data = relative_data.clone();
+        accounts[2].is_signer = false;
+        assert_eq!(
+            f.activate(&mut accounts[1..4]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let mut accounts = f.revoke_accounts(15, schedules);
+        accounts[3].data = relative_data;
+        accounts[2] = TestAccount::wallet(Pubkey::new_unique()).signer();
+        assert_eq!(
+            f.activate(&mut accounts[1..4]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- pause
+
+    #[test]
+    fn test_pause_holds_back_unlock_until_resume() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        // Pause and Resume take the first two accounts of SetRollover
+        let mut accounts = f.set_rollover_accounts(schedules());
+        f.set_paused(&mut accounts[..2], true).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert!(header.paused);
+
+        let mut unlock_accounts = f.unlock_accounts(15, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(VestingError::ContractPaused.into())
+        );
+
+        // the schedules kept vesting in the meantime
+        f.set_paused(&mut accounts[..2], false).unwrap();
+        let mut unlock_accounts = f.unlock_accounts(25, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 0)]
+        );
+    }
+
+    #[test]
+    fn test_pause_checks() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(20, 50)];
+
+        let mut accounts = f.set_rollover_accounts(schedules.clone());
+        accounts[1].is_signer = false;
+        assert_eq!(
+            f.set_paused(&mut accounts[..2], true),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // the destination can't resume a contract the granter paused
+        let mut accounts = f.set_rollover_accounts(schedules.clone());
+        f.set_paused(&mut accounts[..2], true).unwrap();
+        accounts[1] = TestAccount::wallet(f.destination_owner).signer();
+        assert_eq!(
+            f.set_paused(&mut accounts[..2], false),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.set_rollover_accounts(schedules);
+        let mut header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        header.state = ContractState::Exhausted;
+        header.pack_into_slice(&mut accounts[0].data[..VestingScheduleHeader::LEN]);
+        assert_eq!(
+            f.set_paused(&mut accounts[..2], true),
+            Err(VestingError::ContractExhausted.into())
+        );
+    }
+
+    // ----------------------------------------------------------------------------- emergency withdraw
+
+    #[test]
+    fn test_emergency_withdraw_sweeps_everything() {
+        let f = VestingFixture::new();
+        let authority = Pubkey::new_unique();
+        let mut accounts = f.create_accounts(150);
+        accounts[1] = f.empty_vesting_account(2);
+        f.create_with_emergency_authority(
+            &mut accounts,
+            vec![schedule(10, 100), schedule(20, 50)],
+            &authority,
+        )
+        .unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[1].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.emergency_authority, authority);
+
+        // released or not, nothing is left to the destination
+        let mut accounts = f.emergency_withdraw_accounts(accounts[1].data.clone(), authority);
+        f.emergency_withdraw(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[1].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 0)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&accounts[1].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.state, ContractState::Exhausted);
+        assert_eq!(header.next_release_time, NO_PENDING_RELEASE);
+        assert!(!header.has_claimable);
+
+        // and whatever turns up on the account later can be swept too
+        f.emergency_withdraw(&mut accounts).unwrap();
+    }
+
+    #[test]
+    fn test_emergency_withdraw_checks() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(20, 50)];
+        let authority = Pubkey::new_unique();
+        let mut header = f.header(&schedules);
+        header.emergency_authority = authority;
+        let mut vesting_data = f.vesting_account(schedules.clone()).data;
+        header.pack_into_slice(&mut vesting_data[..VestingScheduleHeader::LEN]);
+
+        let mut accounts = f.emergency_withdraw_accounts(vesting_data.clone(), authority);
+        accounts[3].is_signer = false;
+        assert_eq!(
+            f.emergency_withdraw(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // neither the granter nor the destination can sweep it
+        for key in [f.granter, f.destination_owner] {
+            let mut accounts = f.emergency_withdraw_accounts(vesting_data.clone(), key);
+            assert_eq!(
+                f.emergency_withdraw(&mut accounts),
+                Err(ProgramError::InvalidArgument)
+            );
+        }
+
+        let mut accounts = f.emergency_withdraw_accounts(vesting_data, authority);
+        accounts[4] =
+            TestAccount::token_account(Pubkey::new_unique(), Pubkey::new_unique(), authority, 0);
+        assert_eq!(
+            f.emergency_withdraw(&mut accounts),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // a contract created without an authority can't be swept
+        let mut accounts =
+            f.emergency_withdraw_accounts(f.vesting_account(schedules).data, Pubkey::default());
+        assert_eq!(
+            f.emergency_withdraw(&mut accounts),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_emergency_withdraw_with_transfer_fees() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(20, 50)];
+        let authority = Pubkey::new_unique();
+        let mut header = f.header(&schedules);
+        header.emergency_authority = authority;
+        let mut vesting_data = f.vesting_account(schedules).data;
+        header.pack_into_slice(&mut vesting_data[..VestingScheduleHeader::LEN]);
+        let fee_accounts = || {
+            let mut accounts = f.emergency_withdraw_accounts(vesting_data.clone(), authority);
+            accounts[0] = TestAccount::program(spl_token_2022::id());
+            accounts[2] = accounts[2].clone().with_token_extensions(&[
+                ExtensionType::ImmutableOwner,
+                ExtensionType::TransferFeeAmount,
+            ]);
+            // the fee is the current epoch's, off the chain there's no Clock syscall to read it from
+            accounts.push(TestAccount::clock(0));
+            accounts
+        };
+
+        let mut accounts = fee_accounts();
+        assert_eq!(
+            f.emergency_withdraw(&mut accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        let mut accounts = fee_accounts();
+        accounts.push(TestAccount::fee_mint(f.mint, 100, 5));
+        f.emergency_withdraw(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[1].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(20, 0)]
+        );
+    }
+
+    // ----------------------------------------------------------------------------- debug shift schedules
+
+    #[cfg(feature = "devnet-tools")]
+    #[test]
+    fn test_debug_shift_schedules() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(1_000, 100), vesting_schedule(2_000, 50)];
+        // takes the first two accounts of SetRollover, like ChangeGranter
+        let mut accounts = f.set_rollover_accounts(schedules());
+        f.debug_shift_schedules(&mut accounts[..2], 1_500).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[0].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(0, 100), vesting_schedule(500, 50)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.next_release_time, 0);
+
+        // the fast-forwarded schedules can be claimed right away
+        let mut unlock_accounts = f.unlock_accounts(600, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(0, 0), vesting_schedule(500, 0)]
+        );
+
+        // granter only
+        let mut accounts = f.set_rollover_accounts(schedules());
+        accounts[1] = TestAccount::wallet(f.destination_owner).signer();
+        assert_eq!(
+            f.debug_shift_schedules(&mut accounts[..2], 1_500),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // never at the mainnet id
+        let mut accounts = f.set_rollover_accounts(schedules());
+        assert_eq!(
+            Processor::process_debug_shift_schedules(
+                &ids::mainnet(),
+                &account_infos(&mut accounts[..2]),
+                f.seeds,
+                1_500
+            ),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- signers
+
+    /// Who has to sign each instruction, by account index. Spelled out here rather than read off the specs, so a
+    /// spec that stops asking for a signature fails the test below instead of quietly passing it. Signatures that
+    /// depend on the contract, like the destination owner's on a permissioned unlock, are up to each instruction's
+    /// own tests
+    fn required_signers(ix: &VestingInstruction) -> &'static [usize] {
+        match ix {
+            VestingInstruction::Empty { .. }
+            | VestingInstruction::Unlock { .. }
+            | VestingInstruction::UnlockGrant { .. }
+            | VestingInstruction::UnlockWithPermit { .. }
+            | VestingInstruction::ClaimCompressed { .. }
+            | VestingInstruction::VerifySolvency { .. }
+            | VestingInstruction::RefreshClaimable { .. }
+            | VestingInstruction::ReadEmissions { .. } => &[],
+            VestingInstruction::Init { .. }
+            | VestingInstruction::InitChild { .. }
+            | VestingInstruction::InitGrant { .. } => &[2],
+            VestingInstruction::Create { .. }
+            | VestingInstruction::CreateLinear { .. }
+            | VestingInstruction::CreateFromMint { .. } => &[3],
+            VestingInstruction::ChangeDestination { .. }
+            | VestingInstruction::ChangeFutureDestination { .. } => &[2],
+            VestingInstruction::CreateCompressed { .. } => &[2, 6],
+            VestingInstruction::AppendCompressed { .. } => &[1],
+            VestingInstruction::Revoke { .. } | VestingInstruction::RevokeBatch { .. } => &[2],
+            VestingInstruction::Accelerate { .. } | VestingInstruction::Activate { .. } => &[1],
+            VestingInstruction::ChangeGranter { .. }
+            | VestingInstruction::Pause { .. }
+            | VestingInstruction::Resume { .. } => &[1],
+            VestingInstruction::EmergencyWithdraw { .. } => &[3],
+            VestingInstruction::ExportContract { .. } => &[4, 6],
+            VestingInstruction::ImportContract { .. } => &[2],
+            VestingInstruction::TopUpGas { .. } => &[1],
+            VestingInstruction::SetPermissionlessUnlock { .. }
+            | VestingInstruction::SetUnlockPolicy { .. }
+            | VestingInstruction::SetMinClaimAmount { .. } => &[2],
+            VestingInstruction::SetRollover { .. }
+            | VestingInstruction::SetTransferLimit { .. }
+            | VestingInstruction::SetWithholding { .. }
+            | VestingInstruction::SetSplits { .. } => &[1, 3],
+            VestingInstruction::InitConfig { .. } => &[2, 5],
+            VestingInstruction::SetEarlyClaimTolerance { .. }
+            | VestingInstruction::SetCommunityTreasury { .. }
+            | VestingInstruction::SetForbidSelfDealing { .. } => &[1],
+            VestingInstruction::Close { .. } => &[3],
+            VestingInstruction::InitDestinationApprovers { .. } => &[1, 4, 6],
+            VestingInstruction::ApproveDestinationChange { .. } => &[2],
+            VestingInstruction::MigrateLayout => &[1],
+            VestingInstruction::Compact { .. } => &[2],
+            VestingInstruction::TopUp { .. } => &[4],
+            VestingInstruction::Merge { .. } => &[6, 8],
+            VestingInstruction::TransferBeneficiary { .. } => &[2],
+            VestingInstruction::CheckRevision { .. } => &[],
+            VestingInstruction::Resize { .. } => &[1],
+            VestingInstruction::CloseUninitialized { .. } => &[1],
+            #[cfg(feature = "devnet-tools")]
+            VestingInstruction::DebugShiftSchedules { .. } => &[1],
+        }
+    }
+
+    /// One of every instruction
+    fn every_instruction() -> Vec<VestingInstruction> {
+        let seeds = [1; 32];
+        let key = Pubkey::new_unique();
+        let schedules = vec![schedule(10, 100)];
+        let instructions = vec![
+            VestingInstruction::Empty { number: 1 },
+            VestingInstruction::Init {
+                seeds,
+                number_of_schedules: 1,
+                schedule_layout: ScheduleLayout::Plain,
+            },
+            VestingInstruction::InitChild {
+                base_seed: seeds,
+                index: 0,
+                number_of_schedules: 1,
+            },
+            VestingInstruction::Create {
+                seeds,
+                token_mint_addr: key,
+                token_dest_addr: key,
+                allow_immediate: true,
+                gas_rebate: 0,
+                gas_deposit: 0,
+                min_claim_amount: 0,
+                destination_locked: false,
+                forbid_self_dealing: false,
+                revoke_policy: RevokePolicy::ReturnToGranter,
+                relative: false,
+                emergency_authority: Pubkey::default(),
+                revocable: true,
+                schedules: schedules.clone(),
+                categories: vec![],
+            },
+            VestingInstruction::CreateFromMint {
+                seeds,
+                token_mint_addr: key,
+                token_dest_addr: key,
+                allow_immediate: true,
+                max_supply: u64::MAX,
+                schedules: schedules.clone(),
+            },
+            VestingInstruction::CreateLinear {
+                seeds,
+                token_mint_addr: key,
+                token_dest_addr: key,
+                allow_immediate: true,
+                cliff_time: 10,
+                cliff_amount: 10,
+                start_time: 10,
+                end_time: 20,
+                total_amount: 100,
+            },
+            VestingInstruction::CreateCompressed {
+                seeds,
+                token_mint_addr: key,
+                max_depth: 3,
+                max_buffer_size: 8,
+                total_amount: 100,
+            },
+            VestingInstruction::AppendCompressed {
+                seeds,
+                leaves: vec![[0; 32]],
+            },
+            VestingInstruction::Unlock {
+                seeds,
+                max_schedules_per_call: 0,
+                max_amount: 0,
+                start_index: None,
+            },
+            VestingInstruction::UnlockGrant {
+                granter: key,
+                beneficiary: key,
+                mint: key,
+                nonce: 0,
+            },
+            VestingInstruction::UnlockWithPermit { seeds, expiry: 20 },
+            VestingInstruction::ClaimCompressed {
+                seeds,
+                root: [0; 32],
+                index: 0,
+                release_time: 10,
+                amount: 100,
+            },
+            VestingInstruction::ChangeDestination {
+                seeds,
+                expected_revision: None,
+            },
+            VestingInstruction::ChangeFutureDestination { seeds, cutoff: 10 },
+            VestingInstruction::Revoke {
+                seeds,
+                expected_revision: None,
+            },
+            VestingInstruction::RevokeBatch { seeds: vec![seeds] },
+            VestingInstruction::ExportContract {
+                seeds,
+                new_program_id: key,
+            },
+            VestingInstruction::ImportContract { seeds },
+            VestingInstruction::TopUpGas { seeds, amount: 1 },
+            VestingInstruction::TopUp {
+                seeds,
+                allow_immediate: true,
+                schedules,
+            },
+            VestingInstruction::SetPermissionlessUnlock {
+                seeds,
+                enabled: true,
+            },
+            VestingInstruction::SetUnlockPolicy {
+                seeds,
+                policy: UnlockPolicy::DestinationOwnerOnly,
+            },
+            VestingInstruction::SetMinClaimAmount {
+                seeds,
+                min_claim_amount: 1,
+            },
+            VestingInstruction::SetRollover {
+                seeds,
+                enabled: true,
+            },
+            VestingInstruction::SetTransferLimit {
+                seeds,
+                max_transfer_per_unlock: 1,
+                unlock_interval: 1,
+            },
+            VestingInstruction::SetWithholding {
+                seeds,
+                withholding_bps: 1,
+            },
+            VestingInstruction::SetSplits {
+                seeds,
+                splits: vec![SplitDestination {
+                    destination_address: key,
+                    bps: 1,
+                }],
+            },
+            VestingInstruction::VerifySolvency { seeds },
+            VestingInstruction::RefreshClaimable { seeds },
+            VestingInstruction::ReadEmissions {
+                mint_address: key,
+                weeks: 1,
+            },
+            VestingInstruction::Merge {
+                seeds,
+                merged_seeds: [2; 32],
+            },
+            VestingInstruction::TransferBeneficiary {
+                seeds,
+                rent_payer: RentPayer::Beneficiary,
+            },
+            VestingInstruction::CheckRevision {
+                seeds,
+                expected_revision: 1,
+            },
+            VestingInstruction::Resize {
+                seeds,
+                number_of_schedules: 2,
+                schedule_layout: ScheduleLayout::Plain,
+            },
+            VestingInstruction::CloseUninitialized { seeds },
+            VestingInstruction::InitGrant {
+                beneficiary: key,
+                mint: key,
+                nonce: 0,
+                number_of_schedules: 1,
+                schedule_layout: ScheduleLayout::Plain,
+            },
+            VestingInstruction::InitConfig {
+                admin: key,
+                early_claim_tolerance_seconds: 0,
+            },
+            VestingInstruction::SetEarlyClaimTolerance {
+                early_claim_tolerance_seconds: 0,
+            },
+            VestingInstruction::SetCommunityTreasury {
+                community_treasury: key,
+            },
+            VestingInstruction::SetForbidSelfDealing {
+                forbid_self_dealing: true,
+            },
+            VestingInstruction::Close {
+                seeds,
+                dust_policy: DustPolicy::ReturnToGranter,
+            },
+            VestingInstruction::InitDestinationApprovers {
+                seeds,
+                threshold: 1,
+                approvers: vec![key],
+            },
+            VestingInstruction::ApproveDestinationChange {
+                seeds,
+                new_destination: key,
+            },
+            VestingInstruction::MigrateLayout,
+            VestingInstruction::Compact { seeds },
+            VestingInstruction::Accelerate { seeds },
+            VestingInstruction::ChangeGranter {
+                seeds,
+                new_granter: key,
+            },
+            VestingInstruction::Activate { seeds },
+            VestingInstruction::Pause { seeds },
+            VestingInstruction::Resume { seeds },
+            VestingInstruction::EmergencyWithdraw { seeds },
+        ];
+        #[cfg(feature = "devnet-tools")]
+        let instructions = [
+            instructions,
+            vec![VestingInstruction::DebugShiftSchedules { seeds, offset: 60 }],
+        ]
+        .concat();
+        instructions
+    }
+
+    #[test]
+    fn test_every_instruction_requires_its_signers() {
+        let program_id = Pubkey::new_unique();
+        for ix in every_instruction() {
+            let signers = required_signers(&ix);
+            let specs = ix.account_specs();
+            let required = specs.iter().take_while(|s| !s.optional).count();
+            let accounts: Vec<TestAccount> = specs[..required]
+                .iter()
+                .enumerate()
+                .map(|(i, spec)| {
+                    let mut account = TestAccount::wallet(Pubkey::new_unique());
+                    account.is_writable = spec.writable;
+                    account.is_signer = signers.contains(&i);
+                    account
+                })
+                .collect();
+
+            // with everyone signing, whatever the made up accounts fail on, it's not a missing signature
+            assert_ne!(
+                Processor::process_instruction(
+                    &program_id,
+                    &account_infos(&mut accounts.clone()),
+                    &ix.pack()
+                ),
+                Err(ProgramError::MissingRequiredSignature),
+                "{:?}",
+                ix
+            );
+            for &signer in signers {
+                let mut accounts = accounts.clone();
+                accounts[signer].is_signer = false;
+                assert_eq!(
+                    Processor::process_instruction(
+                        &program_id,
+                        &account_infos(&mut accounts),
+                        &ix.pack()
+                    ),
+                    Err(ProgramError::MissingRequiredSignature),
+                    "{:?} without the signature of {}",
+                    ix,
+                    specs[signer].name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_instruction_packs_to_its_packed_len() {
+        for ix in every_instruction() {
+            let packed = ix.pack();
+            assert_eq!(ix.packed_len(), packed.len(), "{:?}", ix);
+            let mut dst = vec![0; packed.len()];
+            assert_eq!(ix.pack_into(&mut dst), Ok(packed.len()), "{:?}", ix);
+            assert_eq!(dst, packed, "{:?}", ix);
+        }
+    }
+
+    /// Goes through the builders and process_checked, which fails the test if an account a builder passes read-only
+    /// gets written to. Only instructions that get past their checks outside the runtime write anything
+    #[test]
+    fn test_builders_pass_written_accounts_writable() {
+        let f = VestingFixture::new();
+        let token_program_id = spl_token::id();
+        let system_program_id = solana_program::system_program::id();
+        let clock_id = sysvar::clock::id();
+
+        // Init, then CloseUninitialized giving the account back
+        let payer = Pubkey::new_unique();
+        let ix = instruction::init(
+            &system_program_id,
+            &sysvar::rent::id(),
+            &f.program_id,
+            &payer,
+            &f.vesting_account_key,
+            f.seeds,
+            1,
+        )
+        .unwrap();
+        let accounts = process_checked(
+            &ix,
+            &[
+                TestAccount::program(system_program_id),
+                TestAccount::rent(),
+                TestAccount::wallet(payer),
+                f.empty_vesting_account(1),
+            ],
+        )
+        .unwrap();
+        let ix = instruction::close_uninitialized(
+            &f.program_id,
+            &f.vesting_account_key,
+            &payer,
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(&ix, &accounts).unwrap();
+
+        let mut accounts = f.create_accounts(100);
+        accounts.extend([
+            TestAccount::mint(f.mint, f.granter, 100),
+            f.destination_token_account(),
+        ]);
+        let ix = instruction::create(
+            &f.program_id,
+            &token_program_id,
+            &f.vesting_account_key,
+            &f.vesting_token_account_key,
+            &f.granter,
+            &f.source_token_account_key,
+            &f.destination_token_account_key,
+            &f.mint,
+            vec![schedule(10, 100)],
+            CreateOptions {
+                allow_immediate: true,
+                ..CreateOptions::default()
+            },
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(&ix, &accounts).unwrap();
+
+        let ix = instruction::unlock(
+            &f.program_id,
+            &token_program_id,
+            &clock_id,
+            &f.vesting_account_key,
+            &f.vesting_token_account_key,
+            &f.destination_token_account_key,
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(&ix, &f.unlock_accounts(20, vec![vesting_schedule(10, 100)])).unwrap();
+
+        let ix = instruction::revoke(
+            &f.program_id,
+            &token_program_id,
+            &clock_id,
+            &f.granter,
+            &f.vesting_account_key,
+            &f.vesting_token_account_key,
+            &f.source_token_account_key,
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(&ix, &f.revoke_accounts(5, vec![vesting_schedule(10, 100)])).unwrap();
+
+        let accounts = f.change_destination_accounts(vec![vesting_schedule(10, 100)]);
+        let ix = instruction::change_destination(
+            &f.program_id,
+            &f.vesting_account_key,
+            &f.destination_owner,
+            &f.destination_token_account_key,
+            &accounts[3].key,
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(&ix, &accounts).unwrap();
+        let ix = instruction::set_permissionless_unlock(
+            &f.program_id,
+            &f.vesting_account_key,
+            &f.destination_token_account_key,
+            &f.destination_owner,
+            false,
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(&ix, &accounts).unwrap();
+
+        // read-only all the way through
+        let ix =
+            instruction::check_revision(&f.program_id, &f.vesting_account_key, f.seeds, 0).unwrap();
+        process_checked(&ix, &accounts).unwrap();
+
+        let new_owner = Pubkey::new_unique();
+        let new_destination = get_associated_token_address(&new_owner, &f.mint);
+        let mut accounts =
+            f.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], new_owner);
+        accounts.extend([
+            f.destination_index_account(&f.destination_token_account_key, f.vesting_account_key),
+            f.destination_index_account(&new_destination, Pubkey::default()),
+        ]);
+        let ix = instruction::transfer_beneficiary(
+            &f.program_id,
+            &token_program_id,
+            &system_program_id,
+            &spl_associated_token_account::id(),
+            &f.vesting_account_key,
+            &f.destination_owner,
+            &f.destination_token_account_key,
+            &new_owner,
+            &f.mint,
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(&ix, &accounts).unwrap();
+
+        let granter_token_account = f.granter_token_account();
+        let ix = instruction::close(
+            &f.program_id,
+            &token_program_id,
+            &f.vesting_account_key,
+            &f.vesting_token_account_key,
+            &f.granter,
+            &granter_token_account.key,
+            DustPolicy::ReturnToGranter,
+            f.seeds,
+        )
+        .unwrap();
+        process_checked(
+            &ix,
+            &f.close_accounts(vec![vesting_schedule(10, 0)], granter_token_account),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "which its builder passes read-only")]
+    fn test_process_checked_catches_read_only_writes() {
+        let f = VestingFixture::new();
+        let new_owner = Pubkey::new_unique();
+        let new_destination = get_associated_token_address(&new_owner, &f.mint);
+        let mut accounts =
+            f.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], new_owner);
+        accounts.extend([
+            f.destination_index_account(&f.destination_token_account_key, f.vesting_account_key),
+            f.destination_index_account(&new_destination, Pubkey::default()),
+        ]);
+        let mut ix = instruction::transfer_beneficiary(
+            &f.program_id,
+            &spl_token::id(),
+            &solana_program::system_program::id(),
+            &spl_associated_token_account::id(),
+            &f.vesting_account_key,
+            &f.destination_owner,
+            &f.destination_token_account_key,
+            &new_owner,
+            &f.mint,
+            f.seeds,
+        )
+        .unwrap();
+        // process_instruction checks the required accounts against their specs, the optional ones are on their own
+        ix.accounts[10].is_writable = false;
+        let _ = process_checked(&ix, &accounts);
+    }
+
+    // ----------------------------------------------------------------------------- transfer limit
+
+    #[test]
+    fn test_unlock_caps_transfer() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        // SetTransferLimit takes the same accounts as SetRollover
+        let mut accounts = f.set_rollover_accounts(schedules());
+        f.set_transfer_limit(&mut accounts, 120, 100).unwrap();
+
+        let mut unlock_accounts = f.unlock_accounts(25, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 30)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&unlock_accounts[2].data[..VestingScheduleHeader::LEN])
+                .unwrap();
+        assert_eq!(header.last_unlock_time, 25);
+        // the rest is still due
+        assert_eq!(header.next_release_time, 20);
+
+        let data = unlock_accounts[2].data.clone();
+        let mut unlock_accounts = f.unlock_accounts(124, schedules());
+        unlock_accounts[2].data = data.clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(VestingError::UnlockRateLimited.into())
+        );
+
+        let mut unlock_accounts = f.unlock_accounts(125, schedules());
+        unlock_accounts[2].data = data;
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 0)]
+        );
+    }
+
+    #[test]
+    fn test_set_transfer_limit_checks() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        for signer in [1, 3] {
+            let mut accounts = f.set_rollover_accounts(schedules());
+            accounts[signer].is_signer = false;
+            assert_eq!(
+                f.set_transfer_limit(&mut accounts, 120, 100),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+
+        // the fixture's minimum claim is 0, so raise it first
+        let mut accounts = f.set_rollover_accounts(schedules());
+        let mut header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        header.min_claim_amount = 200;
+        header.pack_into_slice(&mut accounts[0].data[..VestingScheduleHeader::LEN]);
+        assert_eq!(
+            f.set_transfer_limit(&mut accounts, 120, 100),
+            Err(ProgramError::InvalidArgument)
+        );
+        f.set_transfer_limit(&mut accounts, 200, 100).unwrap();
+
+        let mut unlock_accounts = f.unlock_accounts(25, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        assert_eq!(
+            f.unlock_bounded(&mut unlock_accounts, 1),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- program config
+
+    fn config_account(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        early_claim_tolerance_seconds: u32,
+    ) -> TestAccount {
+        let (config_key, _) = find_program_config(program_id);
+        let mut data = vec![0; ProgramConfig::LEN];
+        ProgramConfig {
+            admin: *admin,
+            early_claim_tolerance_seconds,
+            community_treasury: Pubkey::default(),
+            forbid_self_dealing: false,
+            is_initialized: true,
+        }
+        .pack_into_slice(&mut data);
+        TestAccount::new(config_key, *program_id, data).writable()
+    }
+
+    #[test]
+    fn test_unlock_applies_early_claim_tolerance() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(20, 100), vesting_schedule(40, 50)];
+
+        let mut accounts = f.unlock_accounts(15, schedules());
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        // 5 seconds early is within the tolerance, 25 isn't
+        accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 10));
+        f.unlock(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(20, 0), vesting_schedule(40, 50)]
+        );
+
+        // also after a rebate receiver
+        let mut accounts = f.unlock_accounts(15, schedules());
+        accounts.push(
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+        );
+        accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 10));
+        f.unlock(&mut accounts).unwrap();
+
+        // or before it
+        let mut accounts = f.unlock_accounts(15, schedules());
+        accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 10));
+        accounts.push(
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+        );
+        f.unlock(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(20, 0), vesting_schedule(40, 50)]
+        );
+    }
+
+    #[test]
+    fn test_unlock_rejects_unexpected_accounts() {
+        let f = VestingFixture::new();
+        let rebate_receiver = || {
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable()
+        };
+
+        // a second account nothing recognizes, wherever the config goes
+        let mut accounts = f.unlock_accounts(25, vec![vesting_schedule(20, 100)]);
+        accounts.push(rebate_receiver());
+        accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 10));
+        accounts.push(TestAccount::wallet(Pubkey::new_unique()));
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+        let mut accounts = f.unlock_accounts(25, vec![vesting_schedule(20, 100)]);
+        accounts.push(rebate_receiver());
+        accounts.push(rebate_receiver());
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        // a withholding account the contract doesn't withhold to isn't recognized either
+        let mut accounts = f.unlock_accounts(25, vec![vesting_schedule(20, 100)]);
+        accounts.push(rebate_receiver());
+        accounts.push(TestAccount::new(
+            Pubkey::new_unique(),
+            spl_token::id(),
+            vec![],
+        ));
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_revoke_applies_early_claim_tolerance() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(20, 100), vesting_schedule(40, 50)];
+
+        // without the config, a schedule 10 seconds out is still revocable
+        let mut accounts = f.revoke_accounts(10, schedules());
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(20, 0), vesting_schedule(40, 0)]
+        );
+
+        // with it, one right at the edge of the tolerance is what Unlock would pay out already
+        let mut accounts = f.revoke_accounts(10, schedules());
+        accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 10));
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(20, 100), vesting_schedule(40, 0)]
+        );
+
+        // and one a second past it isn't
+        let mut accounts = f.revoke_accounts(9, schedules());
+        accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 10));
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&accounts[3].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(20, 0), vesting_schedule(40, 0)]
+        );
+    }
+
+    #[test]
+    fn test_unlock_rejects_foreign_config() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(20, 100)]);
+        accounts.push(
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+        );
+        accounts.push(config_account(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            10,
+        ));
+        accounts[6].owner = Pubkey::new_unique();
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+
+        // owned by the program isn't enough, it has to be the config
+        accounts[6] = TestAccount::new(
+            Pubkey::new_unique(),
+            f.program_id,
+            f.vesting_account(vec![vesting_schedule(20, 100)]).data,
+        );
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_set_early_claim_tolerance() {
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let mut accounts = vec![
+            config_account(&program_id, &admin, 0),
+            TestAccount::wallet(Pubkey::new_unique()).signer(),
+        ];
+        let set = |accounts: &mut [TestAccount], seconds: u32| {
+            Processor::process_set_early_claim_tolerance(
+                &program_id,
+                &account_infos(accounts),
+                seconds,
+            )
+        };
+        assert_eq!(
+            set(&mut accounts, 30),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[1].key = admin;
+        assert_eq!(
+            set(&mut accounts, MAX_EARLY_CLAIM_TOLERANCE_SECONDS + 1),
+            Err(VestingError::EarlyClaimToleranceTooLarge.into())
+        );
+
+        set(&mut accounts, 30).unwrap();
+        assert_eq!(
+            ProgramConfig::unpack(&accounts[0].data)
+                .unwrap()
+                .early_claim_tolerance_seconds,
+            30
+        );
+    }
+
+    #[test]
+    fn test_init_config_requires_upgrade_authority() {
+        let program_id = Pubkey::new_unique();
+        let upgrade_authority = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let (program_data_key, _) =
+            Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        let program_data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(upgrade_authority),
+        })
+        .unwrap();
+
+        // CPIs are no-ops here, so the config account starts out the size create_account would give it
+        let mut config = config_account(&program_id, &admin, 0);
+        config.data = vec![0; ProgramConfig::LEN];
+        let mut accounts = vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::rent(),
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+            config,
+            TestAccount::new(program_data_key, bpf_loader_upgradeable::id(), program_data),
+            TestAccount::wallet(Pubkey::new_unique()).signer(),
+        ];
+        let init = |accounts: &mut [TestAccount], seconds: u32| {
+            Processor::process_init_config(&program_id, &account_infos(accounts), &admin, seconds)
+        };
+        assert_eq!(
+            init(&mut accounts, 30),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[5].key = upgrade_authority;
+        assert_eq!(
+            init(&mut accounts, MAX_EARLY_CLAIM_TOLERANCE_SECONDS + 1),
+            Err(VestingError::EarlyClaimToleranceTooLarge.into())
+        );
+
+        init(&mut accounts, 30).unwrap();
+        assert_eq!(
+            ProgramConfig::unpack(&accounts[3].data).unwrap(),
+            ProgramConfig {
+                admin,
+                early_claim_tolerance_seconds: 30,
+                community_treasury: Pubkey::default(),
+                forbid_self_dealing: false,
+                is_initialized: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_withholding_requires_both_parties() {
+        let f = VestingFixture::new();
+        let withholding_key = Pubkey::new_unique();
+        for signer in [1, 3] {
+            let mut accounts =
+                f.set_withholding_accounts(vec![vesting_schedule(10, 100)], withholding_key);
+            accounts[signer].is_signer = false;
+            assert_eq!(
+                f.set_withholding(&mut accounts, 2_500),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+
+        let mut accounts =
+            f.set_withholding_accounts(vec![vesting_schedule(10, 100)], withholding_key);
+        assert_eq!(
+            f.set_withholding(&mut accounts, MAX_WITHHOLDING_BPS + 1),
+            Err(VestingError::WithholdingTooLarge.into())
+        );
+        f.set_withholding(&mut accounts, 2_500).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.withholding_bps, 2_500);
+        assert_eq!(header.withholding_destination, withholding_key);
+
+        // turning it off forgets the account
+        accounts.pop();
+        f.set_withholding(&mut accounts, 0).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.withholding_bps, 0);
+        assert_eq!(header.withholding_destination, Pubkey::default());
+    }
+
+    #[test]
+    fn test_set_withholding_rejects_other_mint() {
+        let f = VestingFixture::new();
+        let mut accounts =
+            f.set_withholding_accounts(vec![vesting_schedule(10, 100)], Pubkey::new_unique());
+        accounts[4] = TestAccount::token_account(
+            accounts[4].key,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            0,
+        );
+        assert_eq!(
+            f.set_withholding(&mut accounts, 2_500),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // nor can the destination withhold from itself
+        let mut accounts = f.set_withholding_accounts(
+            vec![vesting_schedule(10, 100)],
+            f.destination_token_account_key,
+        );
+        assert_eq!(
+            f.set_withholding(&mut accounts, 2_500),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_unlock_requires_withholding_account() {
+        let f = VestingFixture::new();
+        let withholding_key = Pubkey::new_unique();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let mut accounts = f.set_withholding_accounts(schedules(), withholding_key);
+        f.set_withholding(&mut accounts, 2_500).unwrap();
+
+        let mut unlock_accounts = f.unlock_accounts(15, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        // found by its key, even ahead of the config
+        unlock_accounts.push(TestAccount::token_account(
+            withholding_key,
+            f.mint,
+            Pubkey::new_unique(),
+            0,
+        ));
+        unlock_accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 0));
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 50)]
+        );
+    }
+
+    // ----------------------------------------------------------------------------- splits
+
+    fn split(destination_address: Pubkey, bps: u16) -> SplitDestination {
+        SplitDestination {
+            destination_address,
+            bps,
+        }
+    }
+
+    #[test]
+    fn test_set_splits() {
+        let f = VestingFixture::new();
+        let splits = vec![
+            split(Pubkey::new_unique(), 2_000),
+            split(Pubkey::new_unique(), 1_000),
+        ];
+        let mut accounts = f.set_splits_accounts(vec![vesting_schedule(10, 100)], &splits);
+        f.set_splits(&mut accounts, splits.clone()).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.splits, splits);
+
+        // an empty list turns splitting off, without the split accounts
+        f.set_splits(&mut accounts[..4], vec![]).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert!(header.splits.is_empty());
+    }
+
+    #[test]
+    fn test_set_splits_checks() {
+        let f = VestingFixture::new();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let schedules = || vec![vesting_schedule(10, 100)];
+
+        for splits in [
+            // more than there's room for
+            (0..=MAX_SPLIT_DESTINATIONS)
+                .map(|_| split(Pubkey::new_unique(), 100))
+                .collect(),
+            // more than the whole
+            vec![split(a, 6_000), split(b, 5_000)],
+            // a split taking nothing
+            vec![split(a, 1_000), split(b, 0)],
+            // the same account twice, or the destination itself
+            vec![split(a, 1_000), split(a, 1_000)],
+            vec![split(f.destination_token_account_key, 1_000)],
+        ] {
+            let mut accounts = f.set_splits_accounts(schedules(), &splits);
+            assert_eq!(
+                f.set_splits(&mut accounts, splits),
+                Err(VestingError::InvalidSplits.into())
+            );
+        }
+
+        // everything can go to the splits
+        let splits = vec![split(a, 5_000), split(b, 5_000)];
+        let mut accounts = f.set_splits_accounts(schedules(), &splits);
+        f.set_splits(&mut accounts, splits.clone()).unwrap();
+
+        // the split accounts come in order, and hold the contract's mint
+        let mut accounts = f.set_splits_accounts(schedules(), &splits);
+        accounts.swap(4, 5);
+        assert_eq!(
+            f.set_splits(&mut accounts, splits.clone()),
+            Err(ProgramError::InvalidArgument)
+        );
+        let mut accounts = f.set_splits_accounts(schedules(), &splits);
+        accounts[5] = TestAccount::token_account(b, Pubkey::new_unique(), Pubkey::new_unique(), 0);
+        assert_eq!(
+            f.set_splits(&mut accounts, splits.clone()),
+            Err(ProgramError::InvalidArgument)
+        );
+        let mut accounts = f.set_splits_accounts(schedules(), &splits);
+        assert_eq!(
+            f.set_splits(&mut accounts[..5], splits.clone()),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        // only the granter, and not behind the destination owner's back
+        let mut accounts = f.set_splits_accounts(schedules(), &splits);
+        accounts[1] = TestAccount::wallet(f.destination_owner).signer();
+        assert_eq!(
+            f.set_splits(&mut accounts, splits.clone()),
+            Err(ProgramError::InvalidArgument)
+        );
+        let mut accounts = f.set_splits_accounts(schedules(), &splits);
+        accounts[3] = TestAccount::wallet(Pubkey::new_unique()).signer();
+        assert_eq!(
+            f.set_splits(&mut accounts, splits),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_unlock_requires_split_accounts() {
+        let f = VestingFixture::new();
+        let splits = vec![
+            split(Pubkey::new_unique(), 2_000),
+            split(Pubkey::new_unique(), 1_000),
+        ];
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let mut accounts = f.set_splits_accounts(schedules(), &splits);
+        f.set_splits(&mut accounts, splits.clone()).unwrap();
+
+        let mut unlock_accounts = f.unlock_accounts(15, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        unlock_accounts.push(accounts[4].clone().writable());
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        // found by their keys, in any order and even ahead of the config
+        unlock_accounts.insert(5, accounts[5].clone().writable());
+        unlock_accounts.push(config_account(&f.program_id, &Pubkey::new_unique(), 0));
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 50)]
+        );
+    }
+
+    #[test]
+    fn test_unlock_skips_splits_taking_nothing() {
+        let f = VestingFixture::new();
+        let splits = vec![split(Pubkey::new_unique(), 1)];
+        let mut accounts = f.set_splits_accounts(vec![vesting_schedule(10, 100)], &splits);
+        f.set_splits(&mut accounts, splits).unwrap();
+
+        // 1 bps of 100 rounds down to nothing, so the split's account isn't needed
+        let mut unlock_accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+    }
+
+    // ----------------------------------------------------------------------------- emission curve
+
+    fn weekly_amounts(curve: &TestAccount, weeks: usize) -> Vec<u64> {
+        EmissionCurve::unpack(&curve.data).unwrap().weekly_amounts[..weeks].to_vec()
+    }
+
+    fn track(vesting_account: &mut TestAccount) {
+        let mut header =
+            VestingScheduleHeader::unpack(&vesting_account.data[..VestingScheduleHeader::LEN])
+                .unwrap();
+        header.emission_tracked = true;
+        header.pack_into_slice(&mut vesting_account.data[..VestingScheduleHeader::LEN]);
+    }
+
+    #[test]
+    fn test_create_tracks_the_contract_on_the_emission_curve() {
+        let f = VestingFixture::new();
+        let schedules = vec![
+            vesting_schedule(10, 100),
+            vesting_schedule(2 * SECONDS_PER_WEEK, 50),
+        ];
+        let mut accounts = f.create_accounts(150);
+        accounts[1] = f.empty_vesting_account(2);
+        accounts.push(f.emission_curve(&[7]));
+        f.create(
+            &mut accounts,
+            vec![schedule(10, 100), schedule(2 * SECONDS_PER_WEEK, 50)],
+        )
+        .unwrap();
+
+        let header =
+            VestingScheduleHeader::unpack(&accounts[1].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert!(header.emission_tracked);
+        assert_eq!(weekly_amounts(&accounts[7], 4), vec![107, 0, 50, 0]);
+
+        // untracked without the curve
+        let mut untracked = f.create_accounts(150);
+        untracked[1] = f.empty_vesting_account(2);
+        f.create(
+            &mut untracked,
+            vec![schedule(10, 100), schedule(2 * SECONDS_PER_WEEK, 50)],
+        )
+        .unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&untracked[1].data[..VestingScheduleHeader::LEN])
+                .unwrap();
+        assert!(!header.emission_tracked);
+
+        // from then on the curve has to come along, and follows what's claimed
+        let mut unlock_accounts = f.unlock_accounts(15, schedules);
+        unlock_accounts[2].data = accounts[1].data.clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        unlock_accounts.push(accounts[7].clone());
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(weekly_amounts(&unlock_accounts[5], 4), vec![7, 0, 50, 0]);
+    }
+
+    #[test]
+    fn test_create_emission_curve_checks() {
+        let f = VestingFixture::new();
+
+        // only contracts releasing in tranches at absolute times
+        let mut accounts = f.create_accounts(100);
+        accounts.push(f.emission_curve(&[]));
+        assert_eq!(
+            f.create_relative(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+        let mut accounts = f.create_accounts(100);
+        accounts.push(f.emission_curve(&[]));
+        assert_eq!(
+            f.create_linear(&mut accounts, linear(10, 110, 100)),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // and no further out than the curve goes
+        let horizon = EMISSION_CURVE_WEEKS as u64 * SECONDS_PER_WEEK;
+        let mut accounts = f.create_accounts(100);
+        accounts.push(f.emission_curve(&[]));
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(horizon, 100)]),
+            Err(VestingError::BeyondEmissionCurve.into())
+        );
+        let mut accounts = f.create_accounts(100);
+        accounts.push(f.emission_curve(&[]));
+        f.create(&mut accounts, vec![schedule(horizon - 1, 100)])
+            .unwrap();
+        assert_eq!(
+            EmissionCurve::unpack(&accounts[7].data)
+                .unwrap()
+                .weekly_amounts
+                .last(),
+            Some(&100)
+        );
+    }
+
+    #[test]
+    fn test_revoke_takes_revoked_schedules_off_the_emission_curve() {
+        let f = VestingFixture::new();
+        let mut accounts = f.revoke_accounts(
+            15,
+            vec![
+                vesting_schedule(10, 100),
+                vesting_schedule(2 * SECONDS_PER_WEEK, 50),
+            ],
+        );
+        track(&mut accounts[3]);
+        assert_eq!(
+            f.revoke(&mut accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        // the released schedule stays on it until it's claimed
+        accounts.push(f.emission_curve(&[100, 0, 50]));
+        f.revoke(&mut accounts).unwrap();
+        assert_eq!(weekly_amounts(&accounts[6], 3), vec![100, 0, 0]);
+
+        // a curve that isn't the program's is rejected
+        let mut accounts = f.revoke_accounts(15, vec![vesting_schedule(20, 100)]);
+        track(&mut accounts[3]);
+        let mut curve = f.emission_curve(&[100]);
+        curve.owner = Pubkey::new_unique();
+        accounts.push(curve);
+        assert_eq!(
+            f.revoke(&mut accounts),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    #[test]
+    fn test_read_emissions_checks() {
+        let f = VestingFixture::new();
+        let read = |curve: TestAccount, weeks: u8| {
+            let mut accounts = vec![TestAccount::clock(15), curve];
+            Processor::process_read_emissions(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                &f.mint,
+                weeks,
+            )
+        };
+
+        read(f.emission_curve(&[100]), 1).unwrap();
+        read(f.emission_curve(&[100]), MAX_READ_EMISSION_WEEKS).unwrap();
+        // a mint without a curve has nothing coming up
+        let (curve_key, _) = find_emission_curve(&f.program_id, &f.mint);
+        read(TestAccount::wallet(curve_key), 1).unwrap();
+
+        assert_eq!(
+            read(f.emission_curve(&[100]), 0),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read(f.emission_curve(&[100]), MAX_READ_EMISSION_WEEKS + 1),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        let mut curve = f.emission_curve(&[100]);
+        curve.key = Pubkey::new_unique();
+        assert_eq!(read(curve, 1), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_close_returns_dust_to_granter() {
+        let f = VestingFixture::new();
+
+        // released but unclaimed is still owed to the destination
+        let mut accounts = f.close_accounts(
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 50)],
+            f.granter_token_account(),
+        );
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::ReturnToGranter),
+            Err(VestingError::ContractNotFinished.into())
+        );
+
+        let schedules = || vec![vesting_schedule(10, 0), vesting_schedule(20, 0)];
+        let mut accounts = f.close_accounts(
+            schedules(),
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 0),
+        );
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::ReturnToGranter),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.close_accounts(schedules(), f.granter_token_account());
+        accounts[3].is_signer = false;
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::ReturnToGranter),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let mut accounts = f.close_accounts(schedules(), f.granter_token_account());
+        let lamports = accounts[1].lamports + accounts[3].lamports;
+        f.close(&mut accounts, DustPolicy::ReturnToGranter).unwrap();
+        assert_eq!(accounts[1].lamports, 0);
+        assert_eq!(accounts[3].lamports, lamports);
+        // only the header is left, so that nothing can Create over the account before the runtime drops it
+        assert_eq!(
+            VestingScheduleHeader::unpack(&accounts[1].data[..VestingScheduleHeader::LEN])
+                .unwrap()
+                .state,
+            ContractState::Closed
+        );
+        assert!(accounts[1].data[VestingScheduleHeader::LEN..]
+            .iter()
+            .all(|b| *b == 0));
+        let closed_data = accounts[1].data.clone();
+        let mut accounts = f.create_accounts(100);
+        accounts[1].data = closed_data;
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_close_donates_dust_to_community_treasury() {
+        let f = VestingFixture::new();
+        let treasury = Pubkey::new_unique();
+        let schedules = || vec![vesting_schedule(10, 0)];
+        let treasury_token_account =
+            || TestAccount::token_account(Pubkey::new_unique(), f.mint, treasury, 0);
+        let config = |community_treasury: Pubkey| {
+            let mut config = config_account(&f.program_id, &Pubkey::new_unique(), 0);
+            let mut state = ProgramConfig::unpack(&config.data).unwrap();
+            state.community_treasury = community_treasury;
+            state.pack_into_slice(&mut config.data);
+            config
+        };
+
+        // nothing configured
+        let mut accounts = f.close_accounts(schedules(), treasury_token_account());
+        accounts.push(config(Pubkey::default()));
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::CommunityTreasury),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // the granter can't pass their own account off as the treasury
+        let mut accounts = f.close_accounts(schedules(), f.granter_token_account());
+        accounts.push(config(treasury));
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::CommunityTreasury),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.close_accounts(schedules(), treasury_token_account());
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::CommunityTreasury),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        accounts.push(config(treasury));
+        f.close(&mut accounts, DustPolicy::CommunityTreasury)
+            .unwrap();
+        assert_eq!(accounts[1].lamports, 0);
+    }
+
+    #[test]
+    fn test_close_burns_dust() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 0)];
+
+        let mut accounts = f.close_accounts(schedules(), f.granter_token_account());
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::Burn),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.close_accounts(schedules(), TestAccount::wallet(f.mint));
+        f.close(&mut accounts, DustPolicy::Burn).unwrap();
+        assert_eq!(accounts[1].lamports, 0);
+    }
+
+    #[test]
+    fn test_set_community_treasury() {
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let mut accounts = vec![
+            config_account(&program_id, &admin, 0),
+            TestAccount::wallet(admin),
+        ];
+        let set = |accounts: &mut [TestAccount]| {
+            Processor::process_set_community_treasury(
+                &program_id,
+                &account_infos(accounts),
+                &treasury,
+            )
+        };
+        assert_eq!(
+            set(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[1].is_signer = true;
+        set(&mut accounts).unwrap();
+        assert_eq!(
+            ProgramConfig::unpack(&accounts[0].data)
+                .unwrap()
+                .community_treasury,
+            treasury
+        );
+    }
+
+    #[test]
+    fn test_set_forbid_self_dealing() {
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let mut accounts = vec![
+            config_account(&program_id, &admin, 0),
+            TestAccount::wallet(admin),
+        ];
+        let set = |accounts: &mut [TestAccount]| {
+            Processor::process_set_forbid_self_dealing(&program_id, &account_infos(accounts), true)
+        };
+        assert_eq!(
+            set(&mut accounts),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        accounts[1].is_signer = true;
+        set(&mut accounts).unwrap();
+        assert!(
+            ProgramConfig::unpack(&accounts[0].data)
+                .unwrap()
+                .forbid_self_dealing
+        );
+    }
+
+    #[test]
+    fn test_verify_solvency_flags_shortfall() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let mut accounts = vec![f.vesting_account(schedules()), f.vesting_token_account(149)];
+        let verify = |accounts: &mut [TestAccount]| {
+            Processor::process_verify_solvency(&f.program_id, &account_infos(accounts), f.seeds)
+        };
+        let insolvent = |accounts: &[TestAccount]| {
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN])
+                .unwrap()
+                .insolvent
+        };
+
+        verify(&mut accounts).unwrap();
+        assert!(insolvent(&accounts));
+
+        // topped back up - and then some
+        accounts[1] = f.vesting_token_account(200);
+        verify(&mut accounts).unwrap();
+        assert!(!insolvent(&accounts));
+    }
+
+    #[test]
+    fn test_verify_solvency_rejects_foreign_token_account() {
+        let f = VestingFixture::new();
+        let mut accounts = vec![
+            f.vesting_account(vec![vesting_schedule(10, 100)]),
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 100),
+        ];
+        assert_eq!(
+            Processor::process_verify_solvency(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                f.seeds
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_refresh_claimable() {
+        let f = VestingFixture::new();
+        let refresh = |unix_timestamp: i64, schedules: Vec<VestingSchedule>| {
+            let mut accounts = vec![
+                TestAccount::clock(unix_timestamp),
+                f.vesting_account(schedules),
+            ];
+            Processor::process_refresh_claimable(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                f.seeds,
+            )
+            .unwrap();
+            VestingScheduleHeader::unpack(&accounts[1].data[..VestingScheduleHeader::LEN])
+                .unwrap()
+                .has_claimable
+        };
+        assert!(!refresh(9, vec![vesting_schedule(10, 100)]));
+        assert!(refresh(10, vec![vesting_schedule(10, 100)]));
+        // fully claimed
+        assert!(!refresh(10, vec![vesting_schedule(10, 0)]));
+    }
+
+    #[test]
+    fn test_unlock_refreshes_has_claimable() {
+        let f = VestingFixture::new();
+        let has_claimable = |accounts: &[TestAccount]| {
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN])
+                .unwrap()
+                .has_claimable
+        };
+        let mut accounts = f.unlock_accounts(
+            15,
+            vec![vesting_schedule(10, 100), vesting_schedule(20, 50)],
+        );
+        f.unlock(&mut accounts).unwrap();
+        assert!(!has_claimable(&accounts));
+
+        // a capped unlock leaves the rest of the release claimable
+        let schedules = vec![vesting_schedule(10, 100)];
+        let mut header = f.header(&schedules);
+        header.max_transfer_per_unlock = 60;
+        let mut accounts = f.unlock_accounts(15, schedules);
+        header.pack_into_slice(&mut accounts[2].data[..VestingScheduleHeader::LEN]);
+        f.unlock(&mut accounts).unwrap();
+        assert!(has_claimable(&accounts));
+    }
+
+    #[test]
+    fn test_top_up_gas_increases_escrow() {
+        let f = VestingFixture::new();
+        let mut accounts = vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+            f.vesting_account(vec![vesting_schedule(10, 100)]),
+        ];
+        Processor::process_top_up_gas(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            f.seeds,
+            10_000,
+        )
+        .unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.gas_escrow, 10_000);
+    }
+
+    #[test]
+    fn test_unlock_rejects_before_release_time() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(5, vec![vesting_schedule(10, 100)]);
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_unlock_rejects_fake_token_program() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[0] = TestAccount::program(Pubkey::new_unique());
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_unlock_rejects_wrong_destination() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[4].key = Pubkey::new_unique();
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_unlock_rejects_truncated_vesting_account() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[2].data.truncate(VestingScheduleHeader::LEN - 1);
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_unlock_rejects_padded_vesting_account() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[2]
+            .data
+            .extend_from_slice(&[0; VestingSchedule::LEN]);
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(VestingError::CorruptState.into())
+        );
+    }
+
+    #[test]
+    fn test_unlock_rejects_foreign_vesting_token_account() {
+        let f = VestingFixture::new();
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[3] = accounts[3].clone().with_token_state(|a| {
+            a.owner = Pubkey::new_unique();
+        });
+        assert_eq!(f.unlock(&mut accounts), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_token_accounts_reject_program_accounts() {
+        let f = VestingFixture::new();
+
+        // token account data, but owned by this program - eg a future state account that happens to be the same size
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[3].owner = f.program_id;
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        let mut accounts = f.create_accounts(100);
+        accounts[4].owner = f.program_id;
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        // the program's own state accounts where Close expects the dust receiver
+        let mut accounts = f.close_accounts(
+            vec![vesting_schedule(10, 0)],
+            f.vesting_account(vec![vesting_schedule(10, 0)]),
+        );
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::ReturnToGranter),
+            Err(ProgramError::IncorrectProgramId)
+        );
+        let mut accounts = f.close_accounts(
+            vec![vesting_schedule(10, 0)],
+            config_account(&f.program_id, &f.granter, 0),
+        );
+        assert_eq!(
+            f.close(&mut accounts, DustPolicy::ReturnToGranter),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    #[test]
+    fn test_token_accounts_reject_other_programs_accounts() {
+        let f = VestingFixture::new();
+        let other_program = Pubkey::new_unique();
+
+        // the vesting token account's exact data, but kept by a program that can write whatever balance it likes
+        let mut accounts = f.unlock_accounts(15, vec![vesting_schedule(10, 100)]);
+        accounts[3].owner = other_program;
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        let mut accounts = f.create_accounts(100);
+        accounts[4].owner = other_program;
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        let mut accounts = vec![
+            f.vesting_account(vec![vesting_schedule(10, 100)]),
+            f.vesting_token_account(100),
+        ];
+        accounts[1].owner = other_program;
+        assert_eq!(
+            Processor::process_verify_solvency(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                f.seeds
+            ),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- change destination
+
+    #[test]
+    fn test_change_destination_updates_header() {
+        let f = VestingFixture::new();
+        let mut accounts = f.change_destination_accounts(vec![vesting_schedule(10, 100)]);
+        let new_destination = accounts[3].key;
+        f.change_destination(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.destination_address, new_destination);
+        assert_eq!(header.destination_change_count, 1);
+    }
+
+    #[test]
+    fn test_change_future_destination_splits_unlocks() {
+        let f = VestingFixture::new();
+        let schedules = || vec![vesting_schedule(10, 100), vesting_schedule(20, 50)];
+        let mut accounts = f.change_destination_accounts(schedules());
+        let new_destination = accounts[3].key;
+        f.change_future_destination(&mut accounts, 20).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.destination_address, new_destination);
+        assert_eq!(
+            header.legacy_destination_address,
+            f.destination_token_account_key
+        );
+        assert_eq!(header.legacy_cutoff, 20);
+
+        // both schedules have matured, but the old destination only gets the one before the cutoff
+        let mut unlock_accounts = f.unlock_accounts(25, schedules());
+        unlock_accounts[2].data = accounts[0].data.clone();
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 50)]
+        );
+        let header =
+            VestingScheduleHeader::unpack(&unlock_accounts[2].data[..VestingScheduleHeader::LEN])
+                .unwrap();
+        assert_eq!(header.legacy_cutoff, 0);
+
+        // now only the new destination can claim
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(ProgramError::InvalidArgument)
+        );
+        unlock_accounts[4] =
+            TestAccount::token_account(new_destination, f.mint, Pubkey::new_unique(), 0);
+        f.unlock(&mut unlock_accounts).unwrap();
+        assert_eq!(
+            unpack_schedules(&unlock_accounts[2].data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(10, 0), vesting_schedule(20, 0)]
+        );
+    }
+
+    #[test]
+    fn test_change_future_destination_without_earlier_schedules_is_a_plain_change() {
+        let f = VestingFixture::new();
+        let mut accounts =
+            f.change_destination_accounts(vec![vesting_schedule(10, 0), vesting_schedule(20, 50)]);
+        f.change_future_destination(&mut accounts, 20).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.destination_address, accounts[3].key);
+        assert_eq!(header.legacy_cutoff, 0);
+    }
+
+    #[test]
+    fn test_change_destination_requires_owner_signature() {
+        let f = VestingFixture::new();
+        let mut accounts = f.change_destination_accounts(vec![vesting_schedule(10, 100)]);
+        accounts[2].is_signer = false;
+        assert_eq!(
+            f.change_destination(&mut accounts),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_change_destination_rejects_another_mint() {
+        let f = VestingFixture::new();
+        let mut accounts = f.change_destination_accounts(vec![vesting_schedule(10, 100)]);
+        accounts[3] = TestAccount::token_account(
+            accounts[3].key,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            0,
+        );
+        assert_eq!(
+            f.change_destination(&mut accounts),
+            Err(VestingError::DestinationMintMismatch.into())
+        );
+        assert_eq!(
+            f.change_future_destination(&mut accounts, 20),
+            Err(VestingError::DestinationMintMismatch.into())
+        );
+
+        // nor anything that isn't a token account
+        accounts[3] = TestAccount::wallet(accounts[3].key);
+        assert_eq!(
+            f.change_destination(&mut accounts),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    #[test]
+    fn test_change_destination_rejects_locked_destination() {
+        let f = VestingFixture::new();
+        let schedules = vec![vesting_schedule(10, 100)];
+        let mut accounts = f.change_destination_accounts(vec![vesting_schedule(10, 100)]);
+        let mut header = f.header(&schedules);
+        header.destination_locked = true;
+        header.pack_into_slice(&mut accounts[0].data[..VestingScheduleHeader::LEN]);
+
+        assert_eq!(
+            f.change_destination(&mut accounts),
+            Err(VestingError::DestinationLocked.into())
+        );
+        assert_eq!(
+            f.change_future_destination(&mut accounts, 20),
+            Err(VestingError::DestinationLocked.into())
+        );
+    }
+
+    #[test]
+    fn test_change_destination_rejects_exhausted_contract() {
+        let f = VestingFixture::new();
+        let mut accounts = f.change_destination_accounts(vec![vesting_schedule(10, 0)]);
+        assert_eq!(
+            f.change_destination(&mut accounts),
+            Err(VestingError::ContractExhausted.into())
+        );
+    }
+
+    // ----------------------------------------------------------------------------- destination approvers
+
+    #[test]
+    fn test_init_destination_approvers_locks_destination() {
+        let f = VestingFixture::new();
+        let approvers = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut accounts = f.init_approvers_accounts();
+        f.init_approvers(&mut accounts, 2, approvers.clone())
+            .unwrap();
+
+        let approvers_state = DestinationApprovers::unpack(&accounts[3].data).unwrap();
+        assert_eq!(approvers_state.vesting_account, f.vesting_account_key);
+        assert_eq!(approvers_state.threshold, 2);
+        assert_eq!(approvers_state.approvers, approvers);
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert!(header.destination_locked);
+
+        // the owner alone can't move the destination anymore, and the approvers can't be swapped out
+        let mut change_accounts = f.change_destination_accounts(vec![vesting_schedule(10, 100)]);
+        change_accounts[0].data = accounts[2].data.clone();
+        assert_eq!(
+            f.change_destination(&mut change_accounts),
+            Err(VestingError::DestinationLocked.into())
+        );
+        assert_eq!(
+            f.init_approvers(&mut accounts, 1, vec![Pubkey::new_unique()]),
+            Err(VestingError::DestinationLocked.into())
+        );
+    }
+
+    #[test]
+    fn test_init_destination_approvers_checks() {
+        let f = VestingFixture::new();
+        let approvers = || vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let mut accounts = f.init_approvers_accounts();
+        accounts[4].is_signer = false;
+        assert_eq!(
+            f.init_approvers(&mut accounts, 1, approvers()),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        let mut accounts = f.init_approvers_accounts();
+        accounts[6].is_signer = false;
+        assert_eq!(
+            f.init_approvers(&mut accounts, 1, approvers()),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let duplicate = Pubkey::new_unique();
+        for (threshold, approvers) in [
+            (0, approvers()),
+            (3, approvers()),
+            (1, vec![]),
+            (2, vec![duplicate, duplicate]),
+            (
+                1,
+                (0..=MAX_DESTINATION_APPROVERS)
+                    .map(|_| Pubkey::new_unique())
+                    .collect(),
+            ),
+        ] {
+            let mut accounts = f.init_approvers_accounts();
+            assert_eq!(
+                f.init_approvers(&mut accounts, threshold, approvers),
+                Err(VestingError::InvalidApprovers.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_approve_destination_change_needs_threshold() {
+        let f = VestingFixture::new();
+        let approvers = vec![
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut accounts = f.init_approvers_accounts();
+        f.init_approvers(&mut accounts, 2, approvers.clone())
+            .unwrap();
+        let destination = |accounts: &[TestAccount]| {
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN])
+                .unwrap()
+                .destination_address
+        };
+        let first_choice =
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 0);
+        let second_choice =
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 0);
+
+        // approving twice doesn't count twice
+        f.approve(&mut accounts, approvers[0], &first_choice)
+            .unwrap();
+        f.approve(&mut accounts, approvers[0], &first_choice)
+            .unwrap();
+        assert_eq!(destination(&accounts), f.destination_token_account_key);
+
+        // a vote for another destination doesn't take anyone else's back
+        f.approve(&mut accounts, approvers[1], &second_choice)
+            .unwrap();
+        let third_choice =
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 0);
+        f.approve(&mut accounts, approvers[0], &third_choice)
+            .unwrap();
+        let approvers_state = DestinationApprovers::unpack(&accounts[3].data).unwrap();
+        assert_eq!(
+            approvers_state.votes,
+            vec![third_choice.key, second_choice.key, Pubkey::default()]
+        );
+        assert_eq!(destination(&accounts), f.destination_token_account_key);
+
+        f.approve(&mut accounts, approvers[2], &second_choice)
+            .unwrap();
+        assert_eq!(destination(&accounts), second_choice.key);
+        let header =
+            VestingScheduleHeader::unpack(&accounts[2].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.destination_change_count, 1);
+        let approvers_state = DestinationApprovers::unpack(&accounts[3].data).unwrap();
+        assert_eq!(approvers_state.votes, vec![Pubkey::default(); 3]);
+    }
+
+    #[test]
+    fn test_approve_destination_change_checks() {
+        let f = VestingFixture::new();
+        let approvers = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut accounts = f.init_approvers_accounts();
+        f.init_approvers(&mut accounts, 1, approvers.clone())
+            .unwrap();
+        let new_destination =
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 0);
+
+        assert_eq!(
+            f.approve(&mut accounts, Pubkey::new_unique(), &new_destination),
+            Err(ProgramError::InvalidArgument)
+        );
+        let other_mint = TestAccount::token_account(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            0,
+        );
+        assert_eq!(
+            f.approve(&mut accounts, approvers[0], &other_mint),
+            Err(VestingError::DestinationMintMismatch.into())
+        );
+        assert_eq!(
+            f.approve(&mut accounts, approvers[0], &f.destination_token_account()),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut approve_accounts = vec![
+            accounts[2].clone(),
+            accounts[3].clone(),
+            TestAccount::wallet(approvers[0]),
+            new_destination.clone(),
+        ];
+        assert_eq!(
+            Processor::process_approve_destination_change(
+                &f.program_id,
+                &account_infos(&mut approve_accounts),
+                f.seeds,
+                &new_destination.key,
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // a contract without approvers
+        let mut accounts = f.init_approvers_accounts();
+        assert!(f
+            .approve(&mut accounts, approvers[0], &new_destination)
+            .is_err());
+    }
+
+    #[test]
+    fn test_approve_destination_change_moves_destination_index() {
+        let f = VestingFixture::new();
+        let approvers = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut accounts = f.init_approvers_accounts();
+        f.init_approvers(&mut accounts, 2, approvers.clone())
+            .unwrap();
+        let new_destination =
+            TestAccount::token_account(Pubkey::new_unique(), f.mint, Pubkey::new_unique(), 0);
+
+        let mut approve_accounts = vec![
+            accounts[2].clone(),
+            accounts[3].clone(),
+            new_destination.clone(),
+            TestAccount::program(solana_program::system_program::id()),
+            f.destination_index_account(&f.destination_token_account_key, f.vesting_account_key),
+            f.destination_index_account(&new_destination.key, Pubkey::default()),
+        ];
+        let approve_moving_index = |approver: &Pubkey, accounts: &mut Vec<TestAccount>| {
+            let instruction = instruction::with_moved_destination_index_on_approval(
+                &f.program_id,
+                &solana_program::system_program::id(),
+                instruction::approve_destination_change(
+                    &f.program_id,
+                    &f.vesting_account_key,
+                    approver,
+                    &new_destination.key,
+                    f.seeds,
+                )
+                .unwrap(),
+                &f.mint,
+                &f.destination_token_account_key,
+            );
+            let mut with_approver = accounts.clone();
+            with_approver.push(TestAccount::wallet(*approver));
+            let after = process_checked(&instruction, &with_approver).unwrap();
+            accounts[0] = after[0].clone();
+            accounts[1] = after[1].clone();
+            accounts[4] = after[5].clone();
+            accounts[5] = after[6].clone();
+        };
+
+        // a vote short of the threshold leaves the indexes alone
+        approve_moving_index(&approvers[0], &mut approve_accounts);
+        assert_eq!(
+            destination_index(&approve_accounts[4]),
+            f.vesting_account_key
+        );
+        assert_eq!(destination_index(&approve_accounts[5]), Pubkey::default());
+
+        // the one moving the destination moves the contract to the new destination's index
+        approve_moving_index(&approvers[1], &mut approve_accounts);
+        let header =
+            VestingScheduleHeader::unpack(&approve_accounts[0].data[..VestingScheduleHeader::LEN])
+                .unwrap();
+        assert_eq!(header.destination_address, new_destination.key);
+        assert_eq!(header.destination_change_count, 1);
+        assert_eq!(destination_index(&approve_accounts[4]), Pubkey::default());
+        assert_eq!(
+            destination_index(&approve_accounts[5]),
+            f.vesting_account_key
+        );
+    }
+
+    // ----------------------------------------------------------------------------- compaction
+
+    #[test]
+    fn test_compact_checks() {
+        let f = VestingFixture::new();
+        let compact = |schedules: Vec<VestingSchedule>, granter: TestAccount| {
+            let mut accounts = vec![
+                TestAccount::rent(),
+                f.vesting_account(schedules),
+                granter,
+                TestAccount::wallet(Pubkey::new_unique()).writable(),
+            ];
+            Processor::process_compact(&f.program_id, &account_infos(&mut accounts), f.seeds)
+        };
+        let granter = || TestAccount::wallet(f.granter).signer();
+
+        assert_eq!(
+            compact(
+                vec![vesting_schedule(10, 0), vesting_schedule(20, 100)],
+                TestAccount::wallet(f.granter)
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            compact(
+                vec![vesting_schedule(10, 0), vesting_schedule(20, 100)],
+                TestAccount::wallet(Pubkey::new_unique()).signer()
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            compact(
+                vec![vesting_schedule(10, 50), vesting_schedule(20, 100)],
+                granter()
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            compact(
+                vec![vesting_schedule(10, 0), vesting_schedule(20, 0)],
+                granter()
+            ),
+            Err(VestingError::ContractExhausted.into())
+        );
+    }
+
+    #[test]
+    fn test_top_up_checks() {
+        let f = VestingFixture::new();
+        let top_up = |accounts: &mut [TestAccount], schedules: Vec<Schedule>| {
+            Processor::process_top_up(
+                &f.program_id,
+                &account_infos(accounts),
+                f.seeds,
+                true,
+                schedules,
+            )
+        };
+
+        let mut accounts = f.top_up_accounts(vec![vesting_schedule(10, 100)], 50);
+        accounts[4].is_signer = false;
+        assert_eq!(
+            top_up(&mut accounts, vec![schedule(20, 50)]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        // only the granter
+        accounts[4] = TestAccount::wallet(Pubkey::new_unique())
+            .signer()
+            .writable();
+        assert_eq!(
+            top_up(&mut accounts, vec![schedule(20, 50)]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut accounts = f.top_up_accounts(vec![vesting_schedule(10, 100)], 50);
+        assert_eq!(
+            top_up(&mut accounts, vec![]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            top_up(&mut accounts, vec![schedule(20, 51)]),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(
+            top_up(&mut accounts, vec![schedule(20, u64::MAX - 99)]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        // a finished contract stays finished
+        let mut accounts = f.top_up_accounts(vec![vesting_schedule(10, 0)], 50);
+        assert_eq!(
+            top_up(&mut accounts, vec![schedule(20, 50)]),
+            Err(VestingError::ContractExhausted.into())
+        );
+
+        // the LinearVesting would end up in the middle of the schedules
+        let mut accounts = f.top_up_accounts(vec![vesting_schedule(10, 100)], 50);
+        accounts[2] = f.linear_vesting_account(vesting_schedule(10, 100), linear(10, 20, 100));
+        assert_eq!(
+            top_up(&mut accounts, vec![schedule(20, 50)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    // ----------------------------------------------------------------------------- merge
+
+    #[test]
+    fn test_merge_checks() {
+        let f = VestingFixture::new();
+        let g = f.merged();
+        let merge = |accounts: &mut [TestAccount], merged_seeds: Seeds| {
+            Processor::process_merge(
+                &f.program_id,
+                &account_infos(accounts),
+                f.seeds,
+                merged_seeds,
+            )
+        };
+        let merge_accounts = || {
+            f.merge_accounts(
+                &g,
+                vec![vesting_schedule(10, 100)],
+                vec![vesting_schedule(20, 50)],
+            )
+        };
+
+        let mut self_merge = merge_accounts();
+        self_merge[4] = f.vesting_account(vec![vesting_schedule(10, 100)]);
+        assert_eq!(
+            merge(&mut self_merge, f.seeds),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        for signer in [6, 8] {
+            let mut accounts = merge_accounts();
+            accounts[signer].is_signer = false;
+            assert_eq!(
+                merge(&mut accounts, g.seeds),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+
+        // another granter's contract
+        let other = VestingFixture {
+            granter: Pubkey::new_unique(),
+            ..g
+        };
+        let mut accounts = merge_accounts();
+        accounts[4] = other.vesting_account(vec![vesting_schedule(20, 50)]);
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // another destination's contract
+        let other = VestingFixture {
+            destination_token_account_key: Pubkey::new_unique(),
+            ..g
+        };
+        let mut accounts = merge_accounts();
+        accounts[4] = other.vesting_account(vec![vesting_schedule(20, 50)]);
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // another mint's contract
+        let other = VestingFixture {
+            mint: Pubkey::new_unique(),
+            ..g
+        };
+        let mut accounts = merge_accounts();
+        accounts[4] = other.vesting_account(vec![vesting_schedule(20, 50)]);
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // the LinearVesting would end up in the middle of the schedules
+        let mut accounts = merge_accounts();
+        accounts[4] = g.linear_vesting_account(vesting_schedule(20, 50), linear(10, 20, 50));
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // the merged schedules would be on the curve without ever having been added to it
+        let mut accounts = merge_accounts();
+        track(&mut accounts[4]);
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // the merged schedules would be paid out under other terms, whichever contract had them - a paused or locked
+        // contract can't be merged into one that isn't, nor the other way around
+        let other_terms: [fn(&mut VestingScheduleHeader); 13] = [
+            |h| h.legacy_cutoff = 1,
+            |h| h.withholding_bps = 100,
+            |h| h.withholding_destination = Pubkey::new_unique(),
+            |h| {
+                h.splits = vec![SplitDestination {
+                    destination_address: Pubkey::new_unique(),
+                    bps: 100,
+                }]
+            },
+            |h| h.emergency_authority = Pubkey::new_unique(),
+            |h| h.paused = true,
+            |h| h.unlock_policy = UnlockPolicy::DelegateOnly,
+            |h| h.max_transfer_per_unlock = 10,
+            |h| h.min_claim_amount = 10,
+            |h| h.roll_into_final = true,
+            |h| h.revocable = !h.revocable,
+            |h| {
+                h.revoke_policy = match h.revoke_policy {
+                    RevokePolicy::Burn => RevokePolicy::ReturnToGranter,
+                    RevokePolicy::ReturnToGranter => RevokePolicy::Burn,
+                }
+            },
+            |h| h.destination_locked = true,
+        ];
+        for edit in other_terms {
+            for contract in [2, 4] {
+                let mut accounts = merge_accounts();
+                let mut header = VestingScheduleHeader::unpack(
+                    &accounts[contract].data[..VestingScheduleHeader::LEN],
+                )
+                .unwrap();
+                edit(&mut header);
+                header.pack_into_slice(&mut accounts[contract].data[..VestingScheduleHeader::LEN]);
+                assert_eq!(
+                    merge(&mut accounts, g.seeds),
+                    Err(ProgramError::InvalidArgument)
+                );
+            }
+        }
+
+        let mut accounts = merge_accounts();
+        accounts[5] = g.vesting_token_account(49);
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(ProgramError::InsufficientFunds)
+        );
+
+        let mut accounts = f.merge_accounts(
+            &g,
+            vec![vesting_schedule(10, u64::MAX - 48)],
+            vec![vesting_schedule(20, 50)],
+        );
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // a finished contract stays finished
+        let mut accounts = f.merge_accounts(
+            &g,
+            vec![vesting_schedule(10, 0)],
+            vec![vesting_schedule(20, 50)],
+        );
+        assert_eq!(
+            merge(&mut accounts, g.seeds),
+            Err(VestingError::ContractExhausted.into())
+        );
+    }
+
+    #[test]
+    fn test_move_destination_index() {
+        let f = VestingFixture::new();
+        let g = f.merged();
+        let destination = f.destination_token_account_key;
+        let move_index = |account: &mut TestAccount| {
+            Processor::move_destination_index(
+                &f.program_id,
+                &account.account_info(),
+                &f.mint,
+                &destination,
+                &g.vesting_account_key,
+                &f.vesting_account_key,
+            )
+        };
+
+        let mut index = f.destination_index_account(&destination, g.vesting_account_key);
+        move_index(&mut index).unwrap();
+        assert_eq!(destination_index(&index), f.vesting_account_key);
+
+        // the index has moved on to a contract that isn't part of the merge
+        let other = Pubkey::new_unique();
+        let mut index = f.destination_index_account(&destination, other);
+        move_index(&mut index).unwrap();
+        assert_eq!(destination_index(&index), other);
+
+        // never created
+        let (index_key, _) = find_destination_index(&f.program_id, &f.mint, &destination);
+        move_index(&mut TestAccount::wallet(index_key)).unwrap();
+
+        let mut index = f.destination_index_account(&Pubkey::new_unique(), g.vesting_account_key);
+        assert_eq!(move_index(&mut index), Err(ProgramError::InvalidArgument));
+    }
+
+    // ----------------------------------------------------------------------------- layout migration
+
+    #[test]
+    fn test_migrate_layout_checks() {
+        let f = VestingFixture::new();
+        let migrate = |account: TestAccount| {
+            let mut accounts = vec![
+                TestAccount::program(solana_program::system_program::id()),
+                TestAccount::wallet(Pubkey::new_unique())
+                    .signer()
+                    .writable(),
+                account,
+            ];
+            Processor::process_migrate_layout(&f.program_id, &account_infos(&mut accounts))
+        };
+
+        // already on the current layout
+        assert_eq!(
+            migrate(f.vesting_account(vec![vesting_schedule(10, 100)])),
+            Err(ProgramError::InvalidArgument)
+        );
+        // someone else's account that happens to look like a baseline contract
+        let mut baseline = f.baseline_vesting_account(vec![vesting_schedule(10, 100)]);
+        baseline.owner = Pubkey::new_unique();
+        assert_eq!(migrate(baseline), Err(ProgramError::InvalidArgument));
+        // a baseline account Init-ed but never Created holds nothing to migrate
+        assert_eq!(
+            migrate(TestAccount::new(
+                f.vesting_account_key,
+                f.program_id,
+                vec![0; BASELINE_HEADER_LEN + VestingSchedule::LEN]
+            )),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // baseline contracts can't be used until they're migrated
+        let mut accounts = f.unlock_accounts(20, vec![vesting_schedule(10, 100)]);
+        accounts[2] = f.baseline_vesting_account(vec![vesting_schedule(10, 100)]);
+        assert_eq!(
+            f.unlock(&mut accounts),
+            Err(VestingError::LegacyAccountLayout.into())
+        );
+    }
+
+    // ----------------------------------------------------------------------------- migration
+
+    // ----------------------------------------------------------------------------- destination index
+
+    fn destination_index(account: &TestAccount) -> Pubkey {
+        DestinationIndex::unpack(&account.data)
+            .unwrap()
+            .vesting_account
+    }
+
+    #[test]
+    fn test_create_writes_destination_index() {
+        let f = VestingFixture::new();
+        let mut accounts = f.create_accounts(100);
+        accounts.push(TestAccount::program(solana_program::system_program::id()));
+        // an index left behind by an earlier contract for the same pair
+        accounts
+            .push(f.destination_index_account(&f.destination_token_account_key, Pubkey::default()));
+        f.create(&mut accounts, vec![schedule(10, 100)]).unwrap();
+        assert_eq!(destination_index(&accounts[8]), f.vesting_account_key);
+
+        // the index of another destination
+        let mut accounts = f.create_accounts(100);
+        accounts.push(TestAccount::program(solana_program::system_program::id()));
+        accounts.push(f.destination_index_account(&Pubkey::new_unique(), Pubkey::default()));
+        assert_eq!(
+            f.create(&mut accounts, vec![schedule(10, 100)]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_create_keeps_destination_index_of_a_paying_contract() {
+        let f = VestingFixture::new();
+        let other = f.merged();
+        let create_with_index = |indexed_contract: Option<TestAccount>| {
+            let mut accounts = f.create_accounts(100);
+            accounts.push(TestAccount::program(solana_program::system_program::id()));
+            accounts.push(f.destination_index_account(
+                &f.destination_token_account_key,
+                other.vesting_account_key,
+            ));
+            accounts.extend(indexed_contract);
+            let result = f.create(&mut accounts, vec![schedule(10, 100)]);
+            result.map(|()| destination_index(&accounts[8]))
+        };
+
+        // the contract the index points at isn't passed, or still has something to pay the destination
+        assert_eq!(
+            create_with_index(None),
+            Err(VestingError::DestinationIndexTaken.into())
+        );
+        assert_eq!(
+            create_with_index(Some(other.vesting_account(vec![vesting_schedule(20, 50)]))),
+            Err(VestingError::DestinationIndexTaken.into())
+        );
+        // some other contract that has finished
+        let unrelated = VestingFixture {
+            vesting_account_key: Pubkey::new_unique(),
+            ..other
+        };
+        assert_eq!(
+            create_with_index(Some(
+                unrelated.vesting_account(vec![vesting_schedule(20, 0)])
+            )),
+            Err(VestingError::DestinationIndexTaken.into())
+        );
+
+        // paid out, closed, or moved on to another destination
+        assert_eq!(
+            create_with_index(Some(other.vesting_account(vec![vesting_schedule(20, 0)]))),
+            Ok(f.vesting_account_key)
+        );
+        assert_eq!(
+            create_with_index(Some(TestAccount::wallet(other.vesting_account_key))),
+            Ok(f.vesting_account_key)
+        );
+        let moved_on = VestingFixture {
+            destination_token_account_key: Pubkey::new_unique(),
+            ..other
+        };
+        assert_eq!(
+            create_with_index(Some(
+                moved_on.vesting_account(vec![vesting_schedule(20, 50)])
+            )),
+            Ok(f.vesting_account_key)
+        );
+    }
+
+    #[test]
+    fn test_change_destination_moves_destination_index() {
+        let f = VestingFixture::new();
+        let mut accounts =
+            f.change_destination_accounts_with_indexes(vec![vesting_schedule(10, 100)]);
+        f.change_destination(&mut accounts).unwrap();
+        assert_eq!(destination_index(&accounts[5]), Pubkey::default());
+        assert_eq!(destination_index(&accounts[6]), f.vesting_account_key);
+
+        // the current destination's index has moved on to another contract in the meantime
+        let other_contract = Pubkey::new_unique();
+        let mut accounts =
+            f.change_destination_accounts_with_indexes(vec![vesting_schedule(10, 100)]);
+        accounts[5] = f.destination_index_account(&f.destination_token_account_key, other_contract);
+        f.change_destination(&mut accounts).unwrap();
+        assert_eq!(destination_index(&accounts[5]), other_contract);
+    }
+
+    #[test]
+    fn test_change_destination_keeps_index_of_a_paying_contract() {
+        let f = VestingFixture::new();
+        let with_other_index = || {
+            let mut accounts =
+                f.change_destination_accounts_with_indexes(vec![vesting_schedule(10, 100)]);
+            let other = VestingFixture {
+                destination_token_account_key: accounts[3].key,
+                ..f.merged()
+            };
+            accounts[6] = f.destination_index_account(&accounts[3].key, other.vesting_account_key);
+            (accounts, other)
+        };
+
+        let (mut accounts, _) = with_other_index();
+        assert_eq!(
+            f.change_destination(&mut accounts),
+            Err(VestingError::DestinationIndexTaken.into())
+        );
+
+        let (mut accounts, other) = with_other_index();
+        accounts.push(other.vesting_account(vec![vesting_schedule(20, 0)]));
+        f.change_destination(&mut accounts).unwrap();
+        assert_eq!(destination_index(&accounts[6]), f.vesting_account_key);
+    }
+
+    #[test]
+    fn test_change_future_destination_keeps_legacy_index() {
+        let f = VestingFixture::new();
+        let mut accounts = f.change_destination_accounts_with_indexes(vec![
+            vesting_schedule(10, 100),
+            vesting_schedule(20, 50),
+        ]);
+        f.change_future_destination(&mut accounts, 20).unwrap();
+        assert_eq!(destination_index(&accounts[5]), f.vesting_account_key);
+        assert_eq!(destination_index(&accounts[6]), f.vesting_account_key);
+    }
+
+    // ----------------------------------------------------------------------------- transfer beneficiary
+
+    #[test]
+    fn test_transfer_beneficiary_moves_to_associated_token_account() {
+        let f = VestingFixture::new();
+        let new_owner = Pubkey::new_unique();
+        let new_destination = get_associated_token_address(&new_owner, &f.mint);
+        let mut accounts =
+            f.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], new_owner);
+        accounts.extend(vec![
+            f.destination_index_account(&f.destination_token_account_key, f.vesting_account_key),
+            f.destination_index_account(&new_destination, Pubkey::default()),
+        ]);
+        f.transfer_beneficiary(&mut accounts).unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.destination_address, new_destination);
+        assert_eq!(header.destination_change_count, 1);
+        assert_eq!(destination_index(&accounts[9]), Pubkey::default());
+        assert_eq!(destination_index(&accounts[10]), f.vesting_account_key);
+    }
+
+    #[test]
+    fn test_transfer_beneficiary_checks() {
+        let f = VestingFixture::new();
+        let new_owner = Pubkey::new_unique();
+        let accounts =
+            || f.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], new_owner);
+
+        let mut not_signed = accounts();
+        not_signed[2].is_signer = false;
+        assert_eq!(
+            f.transfer_beneficiary(&mut not_signed),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // any other token account of the new owner
+        let mut not_associated = accounts();
+        not_associated[3] = TestAccount::token_account(Pubkey::new_unique(), f.mint, new_owner, 0);
+        assert_eq!(
+            f.transfer_beneficiary(&mut not_associated),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut other_mint = accounts();
+        other_mint[5] = TestAccount::mint(Pubkey::new_unique(), f.granter, 0);
+        assert_eq!(
+            f.transfer_beneficiary(&mut other_mint),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut other_program = accounts();
+        other_program[8] = TestAccount::program(Pubkey::new_unique());
+        assert_eq!(
+            f.transfer_beneficiary(&mut other_program),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // the current owner's own associated token account is already where the tokens go
+        let g = VestingFixture {
+            destination_token_account_key: get_associated_token_address(
+                &f.destination_owner,
+                &f.mint,
+            ),
+            ..f
+        };
+        let mut same_owner =
+            g.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], g.destination_owner);
+        assert_eq!(
+            g.transfer_beneficiary(&mut same_owner),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // ChangeDestination's own checks still apply
+        let mut locked = accounts();
+        let mut header = f.header(&[vesting_schedule(10, 100)]);
+        header.destination_locked = true;
+        header.pack_into_slice(&mut locked[0].data[..VestingScheduleHeader::LEN]);
+        assert_eq!(
+            f.transfer_beneficiary(&mut locked),
+            Err(VestingError::DestinationLocked.into())
+        );
+    }
+
+    #[test]
+    fn test_transfer_beneficiary_rent_payer() {
+        let f = VestingFixture::new();
+        let new_owner = Pubkey::new_unique();
+        let new_destination = get_associated_token_address(&new_owner, &f.mint);
+        let accounts = |rent_payer: TestAccount| {
+            let mut accounts =
+                f.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], new_owner);
+            accounts.push(rent_payer);
+            accounts
+        };
+        let cranker = || {
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable()
+        };
+        let granter = || TestAccount::wallet(f.granter).signer().writable();
+
+        let mut no_payer =
+            f.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], new_owner);
+        assert_eq!(
+            f.transfer_beneficiary_paid_by(&mut no_payer, RentPayer::Cranker),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        let mut not_signed = accounts(cranker());
+        not_signed[9].is_signer = false;
+        assert_eq!(
+            f.transfer_beneficiary_paid_by(&mut not_signed, RentPayer::Cranker),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // the cranker pays, but the owner still decides
+        let mut owner_not_signed = accounts(cranker());
+        owner_not_signed[2].is_signer = false;
+        assert_eq!(
+            f.transfer_beneficiary_paid_by(&mut owner_not_signed, RentPayer::Cranker),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // the escrow is the granter's to spend
+        let mut not_granter = accounts(cranker());
+        assert_eq!(
+            f.transfer_beneficiary_paid_by(&mut not_granter, RentPayer::GranterEscrow),
+            Err(ProgramError::InvalidArgument)
+        );
+        let mut granter_not_signed = accounts(granter());
+        granter_not_signed[9].is_signer = false;
+        assert_eq!(
+            f.transfer_beneficiary_paid_by(&mut granter_not_signed, RentPayer::GranterEscrow),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // the payer comes after the indexes, which are still moved over
+        let mut with_indexes =
+            f.transfer_beneficiary_accounts(vec![vesting_schedule(10, 100)], new_owner);
+        with_indexes.extend(vec![
+            f.destination_index_account(&f.destination_token_account_key, f.vesting_account_key),
+            f.destination_index_account(&new_destination, Pubkey::default()),
+            cranker(),
+        ]);
+        f.transfer_beneficiary_paid_by(&mut with_indexes, RentPayer::Cranker)
+            .unwrap();
+        assert_eq!(destination_index(&with_indexes[10]), f.vesting_account_key);
+
+        // an existing account costs the escrow nothing
+        let mut accounts = accounts(granter());
+        let mut header = f.header(&[vesting_schedule(10, 100)]);
+        header.gas_escrow = 5_000;
+        header.pack_into_slice(&mut accounts[0].data[..VestingScheduleHeader::LEN]);
+        let lamports = accounts[0].lamports;
+        f.transfer_beneficiary_paid_by(&mut accounts, RentPayer::GranterEscrow)
+            .unwrap();
+        let header =
+            VestingScheduleHeader::unpack(&accounts[0].data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.destination_address, new_destination);
+        assert_eq!(header.gas_escrow, 5_000);
+        assert_eq!(accounts[0].lamports, lamports);
+    }
+
+    #[test]
+    fn test_associated_token_account_len() {
+        let f = VestingFixture::new();
+        let mut accounts = vec![
+            TestAccount::program(spl_token::id()),
+            TestAccount::mint(f.mint, f.granter, 0),
+        ];
+        let infos = account_infos(&mut accounts);
+        assert_eq!(
+            associated_token_account_len(&infos[0], &infos[1]),
+            Ok(Account::LEN)
+        );
+
+        // Token-2022 always adds ImmutableOwner
+        let mut accounts = vec![
+            TestAccount::program(spl_token_2022::id()),
+            TestAccount::mint(f.mint, f.granter, 0),
+        ];
+        accounts[1].owner = spl_token_2022::id();
+        let infos = account_infos(&mut accounts);
+        assert_eq!(
+            associated_token_account_len(&infos[0], &infos[1]),
+            Ok(ExtensionType::get_account_len::<Account>(&[
+                ExtensionType::ImmutableOwner
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_export_marks_contract_migrated() {
+        let f = VestingFixture::new();
+        let new_program_id = Pubkey::new_unique();
+        let mut accounts = f.export_accounts(&new_program_id);
+        Processor::process_export_contract(
+            &f.program_id,
+            &account_infos(&mut accounts),
+            f.seeds,
+            &new_program_id,
+        )
+        .unwrap();
+
+        let data = &accounts[1].data;
+        assert_eq!(
+            data[ACCOUNT_TYPE_OFFSET],
+            AccountType::MigratedVestingSchedule as u8
+        );
+        assert!(
+            VestingScheduleHeader::unpack_migrated(&data[..VestingScheduleHeader::LEN]).is_ok()
+        );
+
+        // can't be unlocked (or exported) on the old deployment anymore
+        let mut unlock_accounts = f.unlock_accounts(30, vec![]);
+        unlock_accounts[2] = accounts[1].clone();
+        assert_eq!(
+            f.unlock(&mut unlock_accounts),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_export_requires_both_parties() {
+        let f = VestingFixture::new();
+        let new_program_id = Pubkey::new_unique();
+        for signer in [4, 6].iter() {
+            let mut accounts = f.export_accounts(&new_program_id);
+            accounts[*signer].is_signer = false;
+            assert_eq!(
+                Processor::process_export_contract(
+                    &f.program_id,
+                    &account_infos(&mut accounts),
+                    f.seeds,
+                    &new_program_id,
+                ),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+    }
+
+    #[test]
+    fn test_export_rejects_foreign_escrow() {
+        let f = VestingFixture::new();
+        let new_program_id = Pubkey::new_unique();
+        let mut accounts = f.export_accounts(&new_program_id);
+        accounts[3] = accounts[3].clone().with_token_state(|a| {
+            a.owner = Pubkey::new_unique();
+        });
+        assert_eq!(
+            Processor::process_export_contract(
+                &f.program_id,
+                &account_infos(&mut accounts),
+                f.seeds,
+                &new_program_id,
+            ),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_import_recreates_remaining_schedules() {
+        let old = VestingFixture::new();
+        let mut new = VestingFixture::new();
+        new.mint = old.mint;
+
+        let schedules = vec![vesting_schedule(10, 0), vesting_schedule(20, 50)];
+        let mut old_vesting_account = old.vesting_account(schedules);
+        old_vesting_account.owner = ids::mainnet();
+        let header = VestingScheduleHeader::unpack_from_slice(&old_vesting_account.data).unwrap();
+        header
+            .pack_migrated_into_slice(&mut old_vesting_account.data[..VestingScheduleHeader::LEN]);
+
+        let (migration_authority, _) =
+            find_migration_authority(&new.program_id, &old.vesting_account_key);
+        let mut accounts = vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::rent(),
+            TestAccount::wallet(Pubkey::new_unique()).signer(),
+            TestAccount::program(spl_token::id()),
+            old_vesting_account,
+            TestAccount::wallet(migration_authority),
+            TestAccount::token_account(Pubkey::new_unique(), old.mint, migration_authority, 50),
+            // create_account is a no-op outside the runtime, so the account has to exist already
+            new.empty_vesting_account(1),
+            new.vesting_token_account(0),
+        ];
+        Processor::process_import_contract(
+            &new.program_id,
+            &account_infos(&mut accounts),
+            new.seeds,
+        )
+        .unwrap();
+
+        let data = &accounts[7].data;
+        let header = VestingScheduleHeader::unpack(&data[..VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(
+            header.destination_address,
+            old.destination_token_account_key
+        );
+        assert_eq!(header.granter_address, old.granter);
+        assert_eq!(header.next_release_time, 20);
+        assert_eq!(
+            unpack_schedules(&data[VestingScheduleHeader::LEN..]).unwrap(),
+            vec![vesting_schedule(20, 50)]
+        );
+
+        let import = |accounts: &mut Vec<TestAccount>| {
+            accounts[7] = new.empty_vesting_account(1);
+            Processor::process_import_contract(&new.program_id, &account_infos(accounts), new.seeds)
+        };
+
+        // only contracts exported by a known deployment, anyone can lay out an account of their own the same way
+        let mut foreign = accounts.clone();
+        foreign[4].owner = Pubkey::new_unique();
+        assert_eq!(import(&mut foreign), Err(ProgramError::IncorrectProgramId));
+
+        // laid out as ExportContract leaves it
+        let mut not_migrated = accounts.clone();
+        not_migrated[4].data[ACCOUNT_TYPE_OFFSET] = AccountType::VestingSchedule as u8;
+        assert_eq!(
+            import(&mut not_migrated),
+            Err(ProgramError::InvalidAccountData)
+        );
+        let mut truncated = accounts.clone();
+        truncated[4]
+            .data
+            .truncate(VestingScheduleHeader::LEN + VestingSchedule::LEN);
+        assert_eq!(
+            import(&mut truncated),
+            Err(VestingError::CorruptState.into())
+        );
+        let mut linear_contract = accounts.clone();
+        linear_contract[4] =
+            old.linear_vesting_account(vesting_schedule(20, 50), linear(10, 30, 50));
+        linear_contract[4].owner = ids::mainnet();
+        let linear_header =
+            VestingScheduleHeader::unpack_from_slice(&linear_contract[4].data).unwrap();
+        linear_header
+            .pack_migrated_into_slice(&mut linear_contract[4].data[..VestingScheduleHeader::LEN]);
+        assert_eq!(
+            import(&mut linear_contract),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // an escrow that wasn't funded by an export can't back an import
+        accounts[6] = accounts[6].clone().with_token_state(|a| a.amount = 49);
+        assert_eq!(import(&mut accounts), Err(ProgramError::InsufficientFunds));
+    }
+
+    // ----------------------------------------------------------------------------- children
+
+    #[test]
+    fn test_init_child_checks_derived_account() {
+        let program_id = Pubkey::new_unique();
+        let base_seed = [3u8; 32];
+        let (child_key, _) = find_child_vesting_account(&program_id, &base_seed, 1);
+
+        // the vesting account of child 0 passed in for child 1
+        let (wrong_key, _) = find_child_vesting_account(&program_id, &base_seed, 0);
+        let mut accounts = vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::rent(),
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+            TestAccount::wallet(wrong_key).writable(),
+        ];
+        let ix = VestingInstruction::InitChild {
+            base_seed,
+            index: 1,
+            number_of_schedules: 1,
+        };
+        assert_eq!(
+            Processor::process_instruction(&program_id, &account_infos(&mut accounts), &ix.pack()),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // create_account is a no-op here, so the account has to be there already
+        accounts[3] = TestAccount::new(
+            child_key,
+            program_id,
+            vec![0; VestingScheduleHeader::account_len(1, ScheduleLayout::Plain)],
+        )
+        .writable();
+        Processor::process_instruction(&program_id, &account_infos(&mut accounts), &ix.pack())
+            .unwrap();
+        assert_eq!(
+            accounts[3].data[CONTRACT_STATE_OFFSET],
+            ContractState::Initialized as u8
+        );
+    }
+
+    #[test]
+    fn test_append_compressed() {
+        use crate::compression::{hash_leaf, MerkleTree};
+
+        let f = VestingFixture::new();
+        let tree = MerkleTree::from_leaves(vec![], 3).unwrap();
+        let (state, accounts) = f.compressed_accounts(&tree, 6);
+        let append = |granter: &Pubkey, merkle_tree: &Pubkey, leaves: usize| {
+            instruction::append_compressed(
+                &f.program_id,
+                &f.vesting_account_key,
+                granter,
+                merkle_tree,
+                vec![hash_leaf(&f.destination_token_account_key, 10, 100); leaves],
+                f.seeds,
+            )
+            .unwrap()
+        };
+
+        // only the granter appends, and only to the contract's own tree
+        let mut other_accounts = accounts.clone();
+        other_accounts.push(TestAccount::wallet(f.destination_owner));
+        assert_eq!(
+            process_checked(
+                &append(&f.destination_owner, &state.merkle_tree, 1),
+                &other_accounts
+            )
+            .err(),
+            Some(ProgramError::InvalidArgument)
+        );
+        let other_tree = Pubkey::new_unique();
+        other_accounts.push(TestAccount::new(other_tree, accounts[6].key, vec![]));
+        assert_eq!(
+            process_checked(&append(&f.granter, &other_tree, 1), &other_accounts).err(),
+            Some(ProgramError::InvalidArgument)
+        );
+
+        // the tree holds 8 leaves
+        assert_eq!(
+            process_checked(&append(&f.granter, &state.merkle_tree, 3), &accounts).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        let accounts =
+            process_checked(&append(&f.granter, &state.merkle_tree, 2), &accounts).unwrap();
+        assert_eq!(
+            CompressedVestingTree::unpack(&accounts[0].data)
+                .unwrap()
+                .leaf_count,
+            8
+        );
+    }
+
+    #[test]
+    fn test_claim_compressed() {
+        use crate::{
+            compression::{CompressedSchedule, MerkleTree},
+            instruction::ClaimCompressedParams,
+        };
+
+        let f = VestingFixture::new();
+        let schedules = (0..4)
+            .map(|i| CompressedSchedule {
+                destination_address: f.destination_token_account_key,
+                release_time: 10 + i * 10,
+                amount: 100,
+            })
+            .collect::<Vec<CompressedSchedule>>();
+        let tree = MerkleTree::new(&schedules).unwrap();
+        let (state, accounts) = f.compressed_accounts(&tree, 4);
+        let claim = |index: u32, merkle_tree: &Pubkey, proof: &[Pubkey]| {
+            instruction::claim_compressed(
+                &f.program_id,
+                &spl_token::id(),
+                &sysvar::clock::id(),
+                &f.vesting_account_key,
+                &f.vesting_token_account_key,
+                &f.destination_token_account_key,
+                merkle_tree,
+                ClaimCompressedParams {
+                    root: tree.root(),
+                    index,
+                    release_time: schedules.get(index as usize).map_or(10, |s| s.release_time),
+                    amount: 100,
+                    proof: proof.to_vec(),
+                },
+                f.seeds,
+            )
+            .unwrap()
+        };
+
+        // the destination has to hold the tree's mint
+        let mut other_mint_accounts = accounts.clone();
+        other_mint_accounts[4] = TestAccount::token_account(
+            f.destination_token_account_key,
+            Pubkey::new_unique(),
+            f.destination_owner,
+            0,
+        );
+        assert_eq!(
+            process_checked(
+                &claim(0, &state.merkle_tree, &tree.proof_accounts(0)),
+                &other_mint_accounts
+            )
+            .err(),
+            Some(ProgramError::InvalidArgument)
+        );
+
+        // the tree has to be the contract's, owned by the program called
+        let mut other_tree_accounts = accounts.clone();
+        let other_tree = Pubkey::new_unique();
+        other_tree_accounts.push(TestAccount::new(other_tree, accounts[6].key, vec![]));
+        assert_eq!(
+            process_checked(
+                &claim(0, &other_tree, &tree.proof_accounts(0)),
+                &other_tree_accounts
+            )
+            .err(),
+            Some(ProgramError::InvalidArgument)
+        );
+        let mut other_owner_accounts = accounts.clone();
+        other_owner_accounts[5].owner = Pubkey::new_unique();
+        assert_eq!(
+            process_checked(
+                &claim(0, &state.merkle_tree, &tree.proof_accounts(0)),
+                &other_owner_accounts
+            )
+            .err(),
+            Some(ProgramError::IncorrectProgramId)
+        );
+
+        // and that program has to be spl-account-compression, as any other could accept whatever proof
+        let other_program = Pubkey::new_unique();
+        let mut other_program_accounts = accounts.clone();
+        other_program_accounts[5].owner = other_program;
+        other_program_accounts.push(TestAccount::program(other_program));
+        let mut other_program_claim = claim(0, &state.merkle_tree, &tree.proof_accounts(0));
+        other_program_claim.accounts[6].pubkey = other_program;
+        assert_eq!(
+            process_checked(&other_program_claim, &other_program_accounts).err(),
+            Some(ProgramError::InvalidArgument)
+        );
+
+        // a proof short of a node, or for a leaf past the ones appended
+        assert_eq!(
+            process_checked(
+                &claim(0, &state.merkle_tree, &tree.proof_accounts(0)[1..]),
+                &accounts
+            )
+            .err(),
+            Some(VestingError::InvalidMerkleProof.into())
+        );
+        assert_eq!(
+            process_checked(
+                &claim(4, &state.merkle_tree, &tree.proof_accounts(4)),
+                &accounts
+            )
+            .err(),
+            Some(VestingError::InvalidMerkleProof.into())
+        );
+
+        // the clock is at 15
+        assert_eq!(
+            process_checked(
+                &claim(1, &state.merkle_tree, &tree.proof_accounts(1)),
+                &accounts
+            )
+            .err(),
+            Some(ProgramError::InvalidArgument)
+        );
+        process_checked(
+            &claim(0, &state.merkle_tree, &tree.proof_accounts(0)),
+            &accounts,
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,2741 @@
+pub use crate::core::{next_release_time, LinearVesting, VestingSchedule, NO_PENDING_RELEASE};
+use crate::{error::VestingError, math::rolled_next_release_time, pda::LEGACY_BUMP};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use num_traits::FromPrimitive;
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// First bytes of every account owned by the program, so that raw account dumps and explorers can tell them apart
+/// from anything else without knowing the program id. Contracts created on the baseline layout have none of it, see
+/// is_baseline_layout
+pub const ACCOUNT_MAGIC: [u8; 4] = *b"TVST";
+
+/// Byte offset of the account type, right after ACCOUNT_MAGIC
+pub const ACCOUNT_TYPE_OFFSET: usize = 4;
+
+/// Follows ACCOUNT_MAGIC in every account owned by the program, so clients can tell account types apart.
+/// Accounts straight out of Init are all zeroes (magic included) and so read as Uninitialized
+#[derive(Clone, Copy, Debug, Eq, PartialEq, num_derive::FromPrimitive)]
+pub enum AccountType {
+    Uninitialized = 0,
+    VestingSchedule = 1,
+    CompressedVestingTree = 2,
+    /// A vesting account whose funds were moved to a new deployment by ExportContract. Kept as a tombstone
+    MigratedVestingSchedule = 3,
+    /// The program-wide settings. Only ever written to the config PDA, see find_program_config
+    ProgramConfig = 4,
+    /// Points from a (mint, destination) pair to a vesting account, see find_destination_index
+    DestinationIndex = 5,
+    /// The m-of-n approvers of a vesting account's destination changes, see find_destination_approvers
+    DestinationApprovers = 6,
+    /// What the tracked contracts of a mint release week by week, see find_emission_curve
+    EmissionCurve = 7,
+}
+
+impl AccountType {
+    /// The type byte expected for a given is_initialized flag
+    fn for_state(is_initialized: bool, initialized_type: AccountType) -> Self {
+        if is_initialized {
+            initialized_type
+        } else {
+            AccountType::Uninitialized
+        }
+    }
+}
+
+/// Accounts straight out of Init are all zeroes, magic included. Anything else has to start with ACCOUNT_MAGIC
+fn check_magic(src_magic: &[u8], src_account_type: u8) -> Result<(), ProgramError> {
+    if src_magic == ACCOUNT_MAGIC
+        || (src_magic == [0; 4] && src_account_type == AccountType::Uninitialized as u8)
+    {
+        return Ok(());
+    }
+    msg!("account doesn't start with ACCOUNT_MAGIC");
+    Err(ProgramError::InvalidAccountData)
+}
+
+/// The type of an account on the current layout. None for baseline contracts and anything that isn't ours
+pub fn account_type(data: &[u8]) -> Option<AccountType> {
+    if is_baseline_layout(data) {
+        return None;
+    }
+    let src_account_type = *data.get(ACCOUNT_TYPE_OFFSET)?;
+    check_magic(&data[..ACCOUNT_TYPE_OFFSET], src_account_type).ok()?;
+    AccountType::from_u8(src_account_type)
+}
+
+/// Size of the header of the baseline layout, before the header was versioned: the destination, the mint and an
+/// is_initialized bool - no magic, type or version. The schedules follow it, as they still do
+pub const BASELINE_HEADER_LEN: usize = 65;
+
+/// Whether `data` is a contract Created on the baseline layout. Baseline accounts Init-ed but never Created are all
+/// zeroes, which reads as Uninitialized on any version
+pub fn is_baseline_layout(data: &[u8]) -> bool {
+    data.len() >= BASELINE_HEADER_LEN
+        && (data.len() - BASELINE_HEADER_LEN) % VestingSchedule::LEN == 0
+        && data[..ACCOUNT_TYPE_OFFSET] != ACCOUNT_MAGIC
+        && data[BASELINE_HEADER_LEN - 1] == 1
+}
+
+/// Size a baseline contract of `baseline_len` bytes grows to on the current header version
+pub fn upgraded_baseline_len(baseline_len: usize) -> usize {
+    let schedule_count = baseline_len.saturating_sub(BASELINE_HEADER_LEN) / VestingSchedule::LEN;
+    VestingScheduleHeader::account_len(schedule_count, ScheduleLayout::Plain)
+}
+
+/// Moves a baseline contract onto the current header version in place. `data` has to be the baseline account grown
+/// to upgraded_baseline_len - the schedules move up behind the new header. Everything the baseline didn't have is
+/// what the baseline program did: anyone could submit Unlock, and there was no granter to revoke anything
+pub fn upgrade_baseline_layout(data: &mut [u8]) -> Result<(), ProgramError> {
+    let schedule_count =
+        data.len().saturating_sub(VestingScheduleHeader::LEN) / VestingSchedule::LEN;
+    let baseline_len = BASELINE_HEADER_LEN + schedule_count * VestingSchedule::LEN;
+    if data.len() != VestingScheduleHeader::account_len(schedule_count, ScheduleLayout::Plain)
+        || !is_baseline_layout(&data[..baseline_len])
+    {
+        msg!("account isn't a baseline contract grown to the current layout");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let schedules = unpack_schedules(&data[BASELINE_HEADER_LEN..baseline_len])?;
+    let mut header = VestingScheduleHeader {
+        destination_address: Pubkey::new_from_array(*array_ref!(data, 0, 32)),
+        mint_address: Pubkey::new_from_array(*array_ref!(data, 32, 32)),
+        granter_address: Pubkey::default(),
+        next_release_time: next_release_time(&schedules),
+        gas_rebate: 0,
+        gas_escrow: 0,
+        schedule_count: schedule_count as u32,
+        unlock_policy: UnlockPolicy::Permissionless,
+        legacy_destination_address: Pubkey::default(),
+        legacy_cutoff: 0,
+        insolvent: false,
+        roll_into_final: false,
+        min_claim_amount: 0,
+        destination_locked: false,
+        unlock_cursor: 0,
+        sweep_next_release_time: NO_PENDING_RELEASE,
+        permit_nonce: 0,
+        withholding_bps: 0,
+        withholding_destination: Pubkey::default(),
+        max_transfer_per_unlock: 0,
+        unlock_interval: 0,
+        last_unlock_time: 0,
+        revoke_policy: RevokePolicy::ReturnToGranter,
+        unlock_count: 0,
+        destination_change_count: 0,
+        revoke_attempt_count: 0,
+        has_claimable: false,
+        activation_time: 0,
+        paused: false,
+        emergency_authority: Pubkey::default(),
+        revocable: false,
+        splits: vec![],
+        emission_tracked: false,
+        unlocked_gross: 0,
+        unlocked_net: 0,
+        revision: 0,
+        // baseline seeds end in their own bump
+        bump: LEGACY_BUMP,
+        schedule_layout: ScheduleLayout::Plain,
+        state: ContractState::Active,
+    };
+    header.settle_state();
+
+    data.fill(0);
+    header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+    pack_schedules_into_slice(schedules, &mut data[VestingScheduleHeader::LEN..]);
+    Ok(())
+}
+
+/// How the schedules of a vesting account are laid out, recorded in its header
+#[derive(Clone, Copy, Debug, Eq, PartialEq, num_derive::FromPrimitive)]
+pub enum ScheduleLayout {
+    /// Schedules only - every contract created before categories existed
+    Plain = 0,
+    /// The schedules are followed by a table of one ScheduleCategory byte per schedule, in the same order. Keeping
+    /// the table after the schedules leaves every schedule at the same offset as in the plain layout
+    Categorized = 1,
+    /// A single schedule releasing an optional cliff and then continuously, as described by the LinearVesting that
+    /// follows it. The schedule's amount is what hasn't been claimed yet, like any other schedule's
+    Linear = 2,
+}
+
+/// Where a vesting contract is in its life: Uninitialized → Initialized → Active → Exhausted → Closed, with a stop at
+/// AwaitingActivation between Initialized and Active for a Create with relative release times. Stored in the
+/// last byte of the header, which used to be the is_initialized bool - the values are versioned so that the bool's
+/// `true` (LEGACY_INITIALIZED_STATE) can still be told apart from the states that replaced it
+#[derive(Clone, Copy, Debug, Eq, PartialEq, num_derive::FromPrimitive)]
+pub enum ContractState {
+    /// No contract yet - all zeroes, as left by Init before it marked accounts Initialized
+    Uninitialized = 0,
+    /// Allocated by Init, waiting for Create
+    Initialized = 2,
+    /// Created, with schedules left to pay out
+    Active = 3,
+    /// Every schedule has been paid out or revoked, only Close is left
+    Exhausted = 4,
+    /// Closed and drained of its lamports, so it only lives until the end of the transaction that closed it
+    Closed = 5,
+    /// Created with release times relative to a start that isn't known yet. Nothing is released until Activate
+    /// anchors them, only the granter's Revoke can take everything back in the meantime
+    AwaitingActivation = 6,
+}
+
+/// What the last header byte held for a created contract while it was still the is_initialized bool. Read as Active
+/// or Exhausted, depending on whether anything is left to pay out, and written back as that
+pub const LEGACY_INITIALIZED_STATE: u8 = 1;
+
+/// What a handler does to a contract, as far as its state is concerned
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContractEvent {
+    Init,
+    Create,
+    /// Create with relative release times
+    CreateRelative,
+    /// Anchors the release times of a CreateRelative contract
+    Activate,
+    /// Anything that needs a contract with something left to pay out, without ending it
+    Modify,
+    /// The last schedule was paid out or revoked
+    Exhaust,
+    Close,
+    /// Gives back an account that never got a contract
+    CloseUninitialized,
+}
+
+impl ContractState {
+    /// The transition table - the only moves a contract can make, and which events each state accepts
+    pub fn apply(self, event: ContractEvent) -> Result<ContractState, VestingError> {
+        use ContractEvent::*;
+        use ContractState::*;
+        match (self, event) {
+            (Uninitialized, Init) => Ok(Initialized),
+            (Uninitialized | Initialized, Create) => Ok(Active),
+            (Uninitialized | Initialized, CreateRelative) => Ok(AwaitingActivation),
+            (AwaitingActivation, Activate) => Ok(Active),
+            (Active, Modify) => Ok(Active),
+            (Active | AwaitingActivation, Exhaust) => Ok(Exhausted),
+            (Exhausted, Close) => Ok(Closed),
+            (Initialized, CloseUninitialized) => Ok(Closed),
+            (Active | AwaitingActivation, Close) => Err(VestingError::ContractNotFinished),
+            (Exhausted, Modify | Exhaust) => Err(VestingError::ContractExhausted),
+            (AwaitingActivation, Modify) => Err(VestingError::AwaitingActivation),
+            _ => Err(VestingError::InvalidContractState),
+        }
+    }
+
+    /// Whether Create has written a contract, so the rest of the header means something
+    pub fn is_created(self) -> bool {
+        !matches!(
+            self,
+            ContractState::Uninitialized | ContractState::Initialized
+        )
+    }
+
+    /// Decodes the state byte. A legacy `true` needs the contract's next_release_time to tell Active from Exhausted
+    pub fn from_byte(byte: u8, next_release_time: u64) -> Option<Self> {
+        match byte {
+            LEGACY_INITIALIZED_STATE if next_release_time == NO_PENDING_RELEASE => {
+                Some(ContractState::Exhausted)
+            }
+            LEGACY_INITIALIZED_STATE => Some(ContractState::Active),
+            _ => ContractState::from_u8(byte),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContractState::Uninitialized => "uninitialized",
+            ContractState::Initialized => "initialized",
+            ContractState::Active => "active",
+            ContractState::Exhausted => "exhausted",
+            ContractState::AwaitingActivation => "awaiting activation",
+            ContractState::Closed => "closed",
+        }
+    }
+}
+
+/// Where Close sends whatever is left in a finished contract's vesting token account - rounding dust, or tokens
+/// sent there by mistake
+#[derive(Clone, Copy, Debug, Eq, PartialEq, num_derive::FromPrimitive)]
+pub enum DustPolicy {
+    /// To a token account of the granter
+    ReturnToGranter = 0,
+    /// To a token account of ProgramConfig::community_treasury
+    CommunityTreasury = 1,
+    /// Burned, taking it out of the mint's supply
+    Burn = 2,
+}
+
+/// Who pays the rent of an associated token account an instruction creates along the way
+#[derive(Clone, Copy, Debug, Eq, PartialEq, num_derive::FromPrimitive)]
+pub enum RentPayer {
+    /// The destination owner, who signs the instruction anyway
+    Beneficiary = 0,
+    /// A signer of its own, eg a relayer submitting on the beneficiary's behalf
+    Cranker = 1,
+    /// The contract's gas escrow, with the granter's signature - it's the granter's deposit
+    GranterEscrow = 2,
+}
+
+/// What Revoke does with the unvested remainder of a contract, set at Create
+#[derive(Clone, Copy, Debug, Eq, PartialEq, num_derive::FromPrimitive)]
+pub enum RevokePolicy {
+    /// Sent back to a token account of the granter
+    ReturnToGranter = 0,
+    /// Burned, taking it out of the mint's supply - for deflationary tokens, where revoked tokens shouldn't go back
+    /// into circulation
+    Burn = 1,
+}
+
+impl RevokePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevokePolicy::ReturnToGranter => "return-to-granter",
+            RevokePolicy::Burn => "burn",
+        }
+    }
+}
+
+/// Who can submit an Unlock, set by the destination owner with SetUnlockPolicy. The tokens only ever go to the
+/// stored destination whatever the policy - it's about who gets to decide when a claim happens
+#[derive(Clone, Copy, Debug, Eq, PartialEq, num_derive::FromPrimitive)]
+pub enum UnlockPolicy {
+    /// The destination owner signs, or hands out a permit
+    DestinationOwnerOnly = 0,
+    /// Anyone, eg a crank going through due contracts
+    Permissionless = 1,
+    /// The delegate approved on the destination spl-token account signs, eg a custodian's hot wallet. The delegated
+    /// amount doesn't matter, only who the delegate is
+    DelegateOnly = 2,
+}
+
+impl UnlockPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnlockPolicy::DestinationOwnerOnly => "destination-owner-only",
+            UnlockPolicy::Permissionless => "permissionless",
+            UnlockPolicy::DelegateOnly => "delegate-only",
+        }
+    }
+}
+
+/// What a schedule pays for, so that DAOs can report emissions by bucket. Only read by clients - the program
+/// releases every category the same way
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, num_derive::FromPrimitive)]
+pub enum ScheduleCategory {
+    Uncategorized = 0,
+    Salary = 1,
+    Bonus = 2,
+    Advisor = 3,
+    Investor = 4,
+}
+
+impl ScheduleCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduleCategory::Uncategorized => "uncategorized",
+            ScheduleCategory::Salary => "salary",
+            ScheduleCategory::Bonus => "bonus",
+            ScheduleCategory::Advisor => "advisor",
+            ScheduleCategory::Investor => "investor",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VestingScheduleHeader {
+    pub destination_address: Pubkey,
+    pub mint_address: Pubkey,
+    pub granter_address: Pubkey, //whoever signed Create, until ChangeGranter hands it over - the only one allowed to revoke
+    pub next_release_time: u64, //earliest release time that still has tokens behind it, see next_release_time()
+    pub gas_rebate: u64, //lamports paid to whoever submits a successful Unlock, 0 = no rebate
+    pub gas_escrow: u64, //lamports on the account on top of rent, set aside for rebates
+    pub schedule_count: u32, //number of schedules after the header, checked against the account size on every load
+    pub unlock_policy: UnlockPolicy, //who can submit Unlock, Permissionless unless the destination owner changed it
+    pub legacy_destination_address: Pubkey, //still gets the schedules released before legacy_cutoff
+    pub legacy_cutoff: u64, //0 = no split and everything goes to destination_address, see ChangeFutureDestination
+    pub insolvent: bool, //set by VerifySolvency when the vesting token account can't cover the remaining schedules
+    pub roll_into_final: bool, //matured tranches move into the final schedule instead of being claimable, see SetRollover
+    pub min_claim_amount: u64, //Unlock refuses smaller transfers, unless they are the claimant's last. 0 = no minimum
+    pub destination_locked: bool, //set at Create for grants that can't be re-assigned, or once DestinationApprovers take over changes
+    pub unlock_cursor: u32, //index of the first schedule the next bounded Unlock looks at, 0 outside of a sweep
+    pub sweep_next_release_time: u64, //earliest unpaid release time among the schedules before unlock_cursor
+    pub permit_nonce: u64, //covered by claim permits and bumped by every UnlockWithPermit, so each permit works once
+    pub withholding_bps: u16, //share of every Unlock going to withholding_destination instead, in basis points. 0 = none
+    pub withholding_destination: Pubkey, //token account receiving the withheld share, see SetWithholding
+    pub max_transfer_per_unlock: u64, //Unlock pays out at most this much and leaves the rest released. 0 = no cap
+    pub unlock_interval: u32, //seconds an Unlock has to wait after the previous one while the cap is on
+    pub last_unlock_time: u64, //when the last Unlock went through, only tracked while the cap is on
+    pub revoke_policy: RevokePolicy, //whether Revoke returns the unvested remainder to the granter or burns it, set at Create
+    pub unlock_count: u32, //Unlocks that paid something out. Like the other counters it saturates, and is only read off-chain
+    pub destination_change_count: u32, //times the destination moved, through ChangeDestination, ChangeFutureDestination or approvals
+    pub revoke_attempt_count: u32, //Revokes of the contract, counting the ones that found nothing left to revoke
+    pub has_claimable: bool, //hint that something was released as of the last Create, Unlock or RefreshClaimable. Goes stale as time passes, see refresh_has_claimable
+    pub activation_time: u64, //anchor Activate added to the relative release times of a Create with `relative` set. 0 = absolute release times
+    pub paused: bool, //set by the granter's Pause, Unlock refuses to pay out until Resume clears it
+    pub emergency_authority: Pubkey, //set at Create, can sweep the vesting token account with EmergencyWithdraw. Pubkey::default() = none
+    pub revocable: bool, //set at Create, Revoke and RevokeBatch always fail on a contract without it
+    pub splits: Vec<SplitDestination>, //at most MAX_SPLIT_DESTINATIONS token accounts sharing what Unlock pays the destination, see SetSplits
+    pub emission_tracked: bool, //set at Create when passed the mint's EmissionCurve, which every change to the schedules has to update from then on
+    pub unlocked_gross: u64,    //what Unlock has taken out of the vesting token account so far
+    pub unlocked_net: u64, //what of unlocked_gross reached the destination, splits and withholding account, short of Token-2022 transfer fees
+    pub revision: u32, //bumped by every change the granter or the destination owner makes after Create, see CheckRevision. Unlike the counters above it never saturates
+    pub bump: u8, //set at Init: the bump of a vesting account at find_program_address(&[seeds]) (behind VESTING_SEED_PREFIX for InitGrant's), or LEGACY_BUMP for one whose seeds end in their own bump, see pda::VestingSigner
+    pub schedule_layout: ScheduleLayout, //whether a category table follows the schedules, set at Create
+    pub state: ContractState,            //only ever changed through apply() and settle_state()
+}
+
+/// Version of the vesting header's layout, written right after the type byte. Contracts created before the header
+/// was versioned are on the baseline layout, see is_baseline_layout
+pub const HEADER_VERSION: u8 = 1;
+
+/// Byte offset of the header version within the account data
+pub const HEADER_VERSION_OFFSET: usize = 5;
+
+/// Byte offset of the granter within the account data, for listing a granter's contracts
+pub const GRANTER_OFFSET: usize = 70;
+
+/// Until Create writes the granter there, the granter's bytes hold whoever paid for Init - for CloseUninitialized
+/// to refund. Listings by granter don't see them, as they only match created contracts
+pub const INIT_PAYER_OFFSET: usize = GRANTER_OFFSET;
+
+/// Who paid for Init, as recorded in an account waiting for Create. The default pubkey for accounts Init-ed before
+/// the payer was recorded
+pub fn init_payer(data: &[u8]) -> Pubkey {
+    Pubkey::new_from_array(*array_ref!(data, INIT_PAYER_OFFSET, 32))
+}
+
+pub fn write_init_payer(data: &mut [u8], payer: &Pubkey) {
+    data[INIT_PAYER_OFFSET..INIT_PAYER_OFFSET + 32].copy_from_slice(payer.as_ref());
+}
+
+/// Byte offset of next_release_time within the account data, for cranks that only want to read those 8 bytes
+pub const NEXT_RELEASE_TIME_OFFSET: usize = 102;
+
+/// Byte offset of the unlock policy within the account data, for cranks skipping contracts that aren't
+/// UnlockPolicy::Permissionless
+pub const UNLOCK_POLICY_OFFSET: usize = 130;
+
+/// Byte offset of the insolvent flag within the account data, for monitors filtering on it
+pub const INSOLVENT_OFFSET: usize = 171;
+
+/// Byte offset of the has_claimable hint within the account data, for cranks pre-selecting contracts with a memcmp
+/// filter before looking at next_release_time
+pub const HAS_CLAIMABLE_OFFSET: usize = 269;
+
+/// Byte offset of the paused flag within the account data, for cranks skipping paused contracts
+pub const PAUSED_OFFSET: usize = 278;
+
+/// Byte offset of the contract state within the account data, the last byte of the header
+pub const CONTRACT_STATE_OFFSET: usize = 472;
+
+/// Byte offset of the destination within the account data, right after the magic, the type byte and the version
+pub const DESTINATION_OFFSET: usize = 6;
+
+/// Byte offset of destination_change_count within the account data
+pub const DESTINATION_CHANGE_COUNT_OFFSET: usize = 261;
+
+/// Points the header in `data` at `destination` and counts the change in destination_change_count, as every
+/// destination change does - without unpacking and repacking the rest of the header
+pub fn write_destination(data: &mut [u8], destination: &Pubkey) {
+    data[DESTINATION_OFFSET..DESTINATION_OFFSET + 32].copy_from_slice(destination.as_ref());
+    let count = array_mut_ref!(data, DESTINATION_CHANGE_COUNT_OFFSET, 4);
+    *count = u32::from_le_bytes(*count).saturating_add(1).to_le_bytes();
+}
+
+/// Byte offset of revision within the account data
+pub const REVISION_OFFSET: usize = 466;
+
+/// Counts a change to the contract in the header's revision, in place like write_destination. Returns the new
+/// revision, or fails once it would wrap, which would let a stale CheckRevision pass again
+pub fn bump_revision(data: &mut [u8]) -> Result<u32, ProgramError> {
+    let revision = array_mut_ref!(data, REVISION_OFFSET, 4);
+    let bumped = u32::from_le_bytes(*revision)
+        .checked_add(1)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    *revision = bumped.to_le_bytes();
+    Ok(bumped)
+}
+
+/// Byte offset of bump within the account data. Instructions read it before the header gets unpacked, to check the
+/// vesting account against its seeds
+pub const BUMP_OFFSET: usize = 470;
+
+/// Writes the contract state of the header in `data`, along with the type byte that goes with it. The magic isn't
+/// written, so moving an account that was never packed to a created state is up to pack_into_slice
+pub fn write_state(data: &mut [u8], state: ContractState) {
+    data[ACCOUNT_TYPE_OFFSET] =
+        AccountType::for_state(state.is_created(), AccountType::VestingSchedule) as u8;
+    data[CONTRACT_STATE_OFFSET] = state as u8;
+}
+
+/// First of the sol_log_data fields Revoke and RevokeBatch emit per revoked contract, followed by the vesting account,
+/// the amount revoked (u64 le) and the RevokePolicy byte it was handled with
+pub const REVOKE_EVENT: &[u8] = b"revoke";
+
+/// First of the sol_log_data fields Accelerate emits, followed by the vesting account and the amount it released
+/// early (u64 le)
+pub const ACCELERATE_EVENT: &[u8] = b"accelerate";
+
+/// First of the sol_log_data fields emitted whenever a contract's revision is bumped, followed by the vesting account
+/// and the new revision (u32 le)
+pub const REVISION_EVENT: &[u8] = b"revision";
+
+/// Header of a compressed vesting account. Instead of storing each schedule, the leaves go in an
+/// spl-account-compression merkle tree with this account as its authority, so only the vesting program can append to
+/// it or empty its leaves
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedVestingTree {
+    pub mint_address: Pubkey,
+    pub merkle_tree: Pubkey, //its owner is the account-compression program the contract calls
+    pub granter: Pubkey,     //the only one who can append leaves
+    pub max_depth: u32,
+    pub leaf_count: u32, //leaves appended so far, the next one goes at this index
+    pub is_initialized: bool,
+}
+
+/// Program-wide settings, in a single PDA per deployment. Set up by the program's upgrade authority with InitConfig,
+/// and changed by `admin` from then on
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramConfig {
+    pub admin: Pubkey,
+    pub early_claim_tolerance_seconds: u32, //Unlock treats schedules releasing this much later than the clock as released
+    pub community_treasury: Pubkey, //owner of the token accounts Close can donate dust to, Pubkey::default() = none
+    pub forbid_self_dealing: bool, //Create rejects a granter owning the destination when it's passed this config
+    pub is_initialized: bool,
+}
+
+/// A PDA per (mint, destination token account) pointing at the vesting account paying that destination, so wallets
+/// can find it with a single fetch. Kept up to date by Create and ChangeDestination when they're passed the index.
+/// With several contracts for the same pair it sticks to the first one until that one stops paying the destination,
+/// so that nobody can point a wallet's lookup at a dust contract of their own
+#[derive(Clone, Debug, PartialEq)]
+pub struct DestinationIndex {
+    pub vesting_account: Pubkey, //Pubkey::default() once that contract has moved on to another destination
+    pub is_initialized: bool,
+}
+
+/// The approvers a foundation-held contract's destination changes go through, in a PDA per vesting account (see
+/// find_destination_approvers). Installing them locks the destination, so ChangeDestination fails from then on and
+/// only `threshold` approvers voting for the same new destination with ApproveDestinationChange can move it. Each
+/// approver has a single vote, so none of them can take back anyone else's
+#[derive(Clone, Debug, PartialEq)]
+pub struct DestinationApprovers {
+    pub vesting_account: Pubkey,
+    pub threshold: u8,
+    pub approvers: Vec<Pubkey>, //at most MAX_DESTINATION_APPROVERS, no duplicates
+    pub votes: Vec<Pubkey>, //votes[i] = the destination approvers[i] last approved, Pubkey::default() = none
+    pub is_initialized: bool,
+}
+
+/// Most approvers a DestinationApprovers account holds
+pub const MAX_DESTINATION_APPROVERS: usize = 10;
+
+/// What the tracked contracts of a mint still have to release, week by week, in a PDA per mint (see
+/// find_emission_curve) - so that on-chain consumers can read upcoming emissions with ReadEmissions instead of adding
+/// up every contract. Create tracks a contract when it's passed the curve, and every instruction changing a tracked
+/// contract's schedules has to be passed it from then on. Linear and relative contracts can't be tracked
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmissionCurve {
+    pub mint_address: Pubkey,
+    pub start_week: u64, //week of weekly_amounts[0], moved up to the current week whenever the curve is written
+    pub past_due: u64,   //released in the weeks before start_week but not claimed yet
+    pub weekly_amounts: Vec<u64>, //always EMISSION_CURVE_WEEKS of them, weekly_amounts[i] releasing in week start_week + i
+    pub is_initialized: bool,
+}
+
+/// Weeks an EmissionCurve has an amount for, from the current one on. A tracked contract can't have schedules
+/// releasing any later than that
+pub const EMISSION_CURVE_WEEKS: usize = 260;
+
+/// Length of an EmissionCurve week. Weeks are counted from the Unix epoch, so they start on Thursdays at 00:00 UTC
+pub const SECONDS_PER_WEEK: u64 = 7 * 24 * 60 * 60;
+
+/// Upper bound of ProgramConfig::early_claim_tolerance_seconds. The tolerance is there to absorb a lagging validator
+/// clock, not to release anything meaningfully early
+pub const MAX_EARLY_CLAIM_TOLERANCE_SECONDS: u32 = 300;
+
+/// VestingScheduleHeader::withholding_bps of a contract withholding everything it releases
+pub const MAX_WITHHOLDING_BPS: u16 = 10_000;
+
+/// A token account taking `bps` basis points of everything Unlock pays the destination of a split contract. The
+/// destination keeps what the splits leave, along with the rounding dust
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SplitDestination {
+    pub destination_address: Pubkey,
+    pub bps: u16,
+}
+
+/// Most splits a contract can have, on top of its destination - as many as the header has room for
+pub const MAX_SPLIT_DESTINATIONS: usize = 4;
+
+/// What all the splits of a contract can add up to, leaving the destination with nothing but the rounding dust
+pub const MAX_SPLIT_BPS: u16 = 10_000;
+
+// https://docs.rs/solana-program/1.7.4/solana_program/program_pack/index.html
+// there are 3 standard traits that we have to define as per program_pack module:
+// 1)is_initialized = check if state has been initialized
+// 2)pack = de/serialize state
+// 3)sealed = solana's version of size
+
+// ----------------------------------------------------------------------------- 1)
+// just take the default implementation
+impl Sealed for VestingSchedule {}
+
+impl Sealed for LinearVesting {}
+
+impl Sealed for VestingScheduleHeader {}
+
+impl Sealed for CompressedVestingTree {}
+
+impl Sealed for ProgramConfig {}
+
+impl Sealed for DestinationIndex {}
+
+impl Sealed for DestinationApprovers {}
+
+impl Sealed for EmissionCurve {}
+
+// ----------------------------------------------------------------------------- 2)
+// interesting, so you DONT HAVE TO implement it for each struct... the Bonfida guys didnt impl for the second one
+impl IsInitialized for VestingScheduleHeader {
+    fn is_initialized(&self) -> bool {
+        self.state.is_created()
+    }
+}
+
+impl IsInitialized for CompressedVestingTree {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for ProgramConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for DestinationIndex {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for DestinationApprovers {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for EmissionCurve {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+// ----------------------------------------------------------------------------- 3)
+// the layouts themselves live in core, for clients that can't pull in solana-program
+impl Pack for VestingSchedule {
+    const LEN: usize = 16;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[..VestingSchedule::LEN].copy_from_slice(&self.to_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        VestingSchedule::from_bytes(src).ok_or_else(|| {
+            msg!("passed slice is shorter than 16 bytes");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl Pack for LinearVesting {
+    const LEN: usize = 40;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[..LinearVesting::LEN].copy_from_slice(&self.to_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        LinearVesting::from_bytes(src).ok_or_else(|| {
+            msg!("passed slice is shorter than 40 bytes");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl Pack for VestingScheduleHeader {
+    //magic + account type + version + each pubkey = 32x6 + u64x12 + u32x7 + u16 + bool x9 + revoke policy +
+    //split count + (pubkey 32 + u16) x MAX_SPLIT_DESTINATIONS + bump + schedule layout
+    const LEN: usize = 473;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref!(dst, 0, VestingScheduleHeader::LEN); //gen mutable ref to a subset of a slice
+
+        // prepare the byte slices we'll be filling in
+        let (
+            dst_magic,
+            dst_account_type,
+            dst_version,
+            dst_destination_address,
+            dst_mint_address,
+            dst_granter_address,
+            dst_next_release_time,
+            dst_gas_rebate,
+            dst_gas_escrow,
+            dst_schedule_count,
+            dst_unlock_policy,
+            dst_legacy_destination_address,
+            dst_legacy_cutoff,
+            dst_insolvent,
+            dst_roll_into_final,
+            dst_min_claim_amount,
+            dst_destination_locked,
+            dst_unlock_cursor,
+            dst_sweep_next_release_time,
+            dst_permit_nonce,
+            dst_withholding_bps,
+            dst_withholding_destination,
+            dst_max_transfer_per_unlock,
+            dst_unlock_interval,
+            dst_last_unlock_time,
+            dst_revoke_policy,
+            dst_unlock_count,
+            dst_destination_change_count,
+            dst_revoke_attempt_count,
+            dst_has_claimable,
+            dst_activation_time,
+            dst_paused,
+            dst_emergency_authority,
+            dst_revocable,
+            dst_split_count,
+            dst_splits,
+            dst_emission_tracked,
+            dst_unlocked_gross,
+            dst_unlocked_net,
+            dst_revision,
+            dst_bump,
+            dst_schedule_layout,
+            dst_state,
+        ) = mut_array_refs![
+            dst, 4, 1, 1, 32, 32, 32, 8, 8, 8, 4, 1, 32, 8, 1, 1, 8, 1, 4, 8, 8, 2, 32, 8, 4, 8, 1,
+            4, 4, 4, 1, 8, 1, 32, 1, 1, 136, 1, 8, 8, 4, 1, 1, 1
+        ]; //get multiple mutable refs to subsets of a slice
+
+        *dst_magic = ACCOUNT_MAGIC;
+
+        // fill in the byte fields from self
+        dst_account_type[0] =
+            AccountType::for_state(self.state.is_created(), AccountType::VestingSchedule) as u8;
+        dst_version[0] = HEADER_VERSION;
+        dst_destination_address.copy_from_slice(self.destination_address.as_ref());
+        dst_mint_address.copy_from_slice(self.mint_address.as_ref());
+        dst_granter_address.copy_from_slice(self.granter_address.as_ref());
+        *dst_next_release_time = self.next_release_time.to_le_bytes();
+        *dst_gas_rebate = self.gas_rebate.to_le_bytes();
+        *dst_gas_escrow = self.gas_escrow.to_le_bytes();
+        *dst_schedule_count = self.schedule_count.to_le_bytes();
+        dst_unlock_policy[0] = self.unlock_policy as u8;
+        dst_legacy_destination_address.copy_from_slice(self.legacy_destination_address.as_ref());
+        *dst_legacy_cutoff = self.legacy_cutoff.to_le_bytes();
+        dst_insolvent[0] = self.insolvent as u8;
+        dst_roll_into_final[0] = self.roll_into_final as u8;
+        *dst_min_claim_amount = self.min_claim_amount.to_le_bytes();
+        dst_destination_locked[0] = self.destination_locked as u8;
+        *dst_unlock_cursor = self.unlock_cursor.to_le_bytes();
+        *dst_sweep_next_release_time = self.sweep_next_release_time.to_le_bytes();
+        *dst_permit_nonce = self.permit_nonce.to_le_bytes();
+        *dst_withholding_bps = self.withholding_bps.to_le_bytes();
+        dst_withholding_destination.copy_from_slice(self.withholding_destination.as_ref());
+        *dst_max_transfer_per_unlock = self.max_transfer_per_unlock.to_le_bytes();
+        *dst_unlock_interval = self.unlock_interval.to_le_bytes();
+        *dst_last_unlock_time = self.last_unlock_time.to_le_bytes();
+        dst_revoke_policy[0] = self.revoke_policy as u8;
+        *dst_unlock_count = self.unlock_count.to_le_bytes();
+        *dst_destination_change_count = self.destination_change_count.to_le_bytes();
+        *dst_revoke_attempt_count = self.revoke_attempt_count.to_le_bytes();
+        dst_has_claimable[0] = self.has_claimable as u8;
+        *dst_activation_time = self.activation_time.to_le_bytes();
+        dst_paused[0] = self.paused as u8;
+        dst_emergency_authority.copy_from_slice(self.emergency_authority.as_ref());
+        dst_revocable[0] = self.revocable as u8;
+        dst_split_count[0] = self.splits.len() as u8;
+        dst_splits.fill(0);
+        for (dst_split, split) in dst_splits.chunks_exact_mut(34).zip(&self.splits) {
+            dst_split[..32].copy_from_slice(split.destination_address.as_ref());
+            dst_split[32..].copy_from_slice(&split.bps.to_le_bytes());
+        }
+        dst_emission_tracked[0] = self.emission_tracked as u8;
+        *dst_unlocked_gross = self.unlocked_gross.to_le_bytes();
+        *dst_unlocked_net = self.unlocked_net.to_le_bytes();
+        *dst_revision = self.revision.to_le_bytes();
+        dst_bump[0] = self.bump;
+        dst_schedule_layout[0] = self.schedule_layout as u8;
+        dst_state[0] = self.state as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::unpack_as(src, AccountType::VestingSchedule)
+    }
+}
+
+impl VestingScheduleHeader {
+    /// Unpacks the header from a whole vesting account, which has to hold exactly schedule_count schedules.
+    /// A truncated or padded account (eg after a buggy realloc) fails with CorruptState instead of reading garbage
+    pub fn unpack_account(data: &[u8]) -> Result<Self, ProgramError> {
+        if is_baseline_layout(data) {
+            msg!("account is on the baseline layout and has to go through MigrateLayout first");
+            return Err(VestingError::LegacyAccountLayout.into());
+        }
+        let header = Self::unpack(
+            data.get(..Self::LEN)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
+        header.check_schedule_count(data.len())?;
+        Ok(header)
+    }
+
+    /// Same as unpack_account, for a contract that has been exported to a new deployment
+    pub fn unpack_migrated_account(data: &[u8]) -> Result<Self, ProgramError> {
+        let header = Self::unpack_migrated(
+            data.get(..Self::LEN)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?;
+        header.check_schedule_count(data.len())?;
+        Ok(header)
+    }
+
+    /// Size of a vesting account holding `schedule_count` schedules laid out as `layout`
+    pub fn account_len(schedule_count: usize, layout: ScheduleLayout) -> usize {
+        let trailer_len = match layout {
+            ScheduleLayout::Plain => 0,
+            ScheduleLayout::Categorized => schedule_count,
+            ScheduleLayout::Linear => LinearVesting::LEN,
+        };
+        Self::LEN + schedule_count * VestingSchedule::LEN + trailer_len
+    }
+
+    /// Where the schedules end within the account data, and the category table of a categorized contract (or the
+    /// LinearVesting of a linear one) starts
+    pub fn schedules_end(&self) -> usize {
+        Self::LEN + self.schedule_count as usize * VestingSchedule::LEN
+    }
+
+    fn check_schedule_count(&self, data_len: usize) -> Result<(), ProgramError> {
+        let expected_len = Self::account_len(self.schedule_count as usize, self.schedule_layout);
+        if data_len != expected_len {
+            msg!(
+                "vesting account holds {:?} bytes, {:?} schedules need {:?}",
+                data_len,
+                self.schedule_count,
+                expected_len
+            );
+            return Err(VestingError::CorruptState.into());
+        }
+        Ok(())
+    }
+
+    /// Whether a schedule is paid to legacy_destination_address instead of destination_address
+    pub fn pays_legacy_destination(&self, schedule: &VestingSchedule) -> bool {
+        schedule.release_time < self.legacy_cutoff
+    }
+
+    /// Drops the legacy destination once it has nothing left to claim
+    pub fn settle_legacy_destination(&mut self, schedules: &[VestingSchedule]) {
+        if !schedules
+            .iter()
+            .any(|s| s.amount > 0 && self.pays_legacy_destination(s))
+        {
+            self.legacy_destination_address = Pubkey::default();
+            self.legacy_cutoff = 0;
+        }
+    }
+
+    /// Sets next_release_time from the schedules. With roll_into_final, nothing can be claimed before the final schedule,
+    /// and nothing at all before Activate. Having looked at every schedule, this also ends any sweep of bounded Unlocks
+    /// in progress
+    pub fn update_next_release_time(&mut self, schedules: &[VestingSchedule]) {
+        self.next_release_time = if self.state == ContractState::AwaitingActivation {
+            NO_PENDING_RELEASE
+        } else if self.roll_into_final {
+            rolled_next_release_time(schedules)
+        } else {
+            next_release_time(schedules)
+        };
+        self.unlock_cursor = 0;
+        self.sweep_next_release_time = NO_PENDING_RELEASE;
+        self.settle_state();
+    }
+
+    /// Moves the contract along the transition table, see ContractState::apply
+    pub fn apply(&mut self, event: ContractEvent) -> Result<(), ProgramError> {
+        match self.state.apply(event) {
+            Ok(state) => {
+                self.state = state;
+                Ok(())
+            }
+            Err(e) => {
+                msg!("A {:?} contract can't take {:?}", self.state, event);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Marks an Active contract Exhausted once next_release_time says there's nothing left to pay out, which also drops
+    /// the has_claimable hint
+    pub fn settle_state(&mut self) {
+        if self.next_release_time == NO_PENDING_RELEASE && self.state == ContractState::Active {
+            self.state = ContractState::Exhausted;
+        }
+        if self.next_release_time == NO_PENDING_RELEASE {
+            self.has_claimable = false;
+        }
+    }
+
+    /// Sets the has_claimable hint from next_release_time as of `now`. Only as precise as next_release_time: a bounded
+    /// Unlock can leave it early, a linear contract keeps releasing past it
+    pub fn refresh_has_claimable(&mut self, now: u64) {
+        self.has_claimable = self.next_release_time <= now;
+    }
+
+    /// Moves the cursor of a bounded Unlock past `window`, the schedules it just went through, which end at index `end`.
+    /// next_release_time is only recomputed once a sweep has been through every schedule - until then it can be too
+    /// early, but never too late
+    pub fn advance_unlock_cursor(&mut self, window: &[VestingSchedule], end: u32) {
+        let sweep_next_release_time = self.sweep_next_release_time.min(next_release_time(window));
+        if end >= self.schedule_count {
+            self.next_release_time = sweep_next_release_time;
+            self.unlock_cursor = 0;
+            self.sweep_next_release_time = NO_PENDING_RELEASE;
+            self.settle_state();
+        } else {
+            self.unlock_cursor = end;
+            self.sweep_next_release_time = sweep_next_release_time;
+        }
+    }
+
+    /// Header of a contract that has been exported to a new deployment
+    pub fn unpack_migrated(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::unpack_as(src, AccountType::MigratedVestingSchedule)
+    }
+
+    /// Same as pack_into_slice, but tags the account as exported so that it no longer reads as a live contract
+    pub fn pack_migrated_into_slice(&self, dst: &mut [u8]) {
+        self.pack_into_slice(dst);
+        dst[ACCOUNT_TYPE_OFFSET] = AccountType::for_state(
+            self.state.is_created(),
+            AccountType::MigratedVestingSchedule,
+        ) as u8;
+    }
+
+    fn unpack_as(src: &[u8], initialized_type: AccountType) -> Result<Self, ProgramError> {
+        if src.len() < VestingScheduleHeader::LEN {
+            msg!(
+                "passed slice is shorter than {} bytes",
+                VestingScheduleHeader::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref!(src, 0, VestingScheduleHeader::LEN); //gen an array ref to a subset of a slice
+
+        // get refs to each slice we're interested in
+        let (
+            src_magic,
+            src_account_type,
+            src_version,
+            src_destination_address,
+            src_mint_address,
+            src_granter_address,
+            src_next_release_time,
+            src_gas_rebate,
+            src_gas_escrow,
+            src_schedule_count,
+            src_unlock_policy,
+            src_legacy_destination_address,
+            src_legacy_cutoff,
+            src_insolvent,
+            src_roll_into_final,
+            src_min_claim_amount,
+            src_destination_locked,
+            src_unlock_cursor,
+            src_sweep_next_release_time,
+            src_permit_nonce,
+            src_withholding_bps,
+            src_withholding_destination,
+            src_max_transfer_per_unlock,
+            src_unlock_interval,
+            src_last_unlock_time,
+            src_revoke_policy,
+            src_unlock_count,
+            src_destination_change_count,
+            src_revoke_attempt_count,
+            src_has_claimable,
+            src_activation_time,
+            src_paused,
+            src_emergency_authority,
+            src_revocable,
+            src_split_count,
+            src_splits,
+            src_emission_tracked,
+            src_unlocked_gross,
+            src_unlocked_net,
+            src_revision,
+            src_bump,
+            src_schedule_layout,
+            src_state,
+        ) = array_refs![
+            src, 4, 1, 1, 32, 32, 32, 8, 8, 8, 4, 1, 32, 8, 1, 1, 8, 1, 4, 8, 8, 2, 32, 8, 4, 8, 1,
+            4, 4, 4, 1, 8, 1, 32, 1, 1, 136, 1, 8, 8, 4, 1, 1, 1
+        ]; //get multiple refs to multiple subsets of a slice
+
+        check_magic(src_magic, src_account_type[0])?;
+        // the version goes in along with the magic, Init leaves both zeroed
+        if *src_magic == ACCOUNT_MAGIC && src_version[0] != HEADER_VERSION {
+            msg!("unknown header version {}", src_version[0]);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let next_release_time = u64::from_le_bytes(*src_next_release_time);
+        let state = ContractState::from_byte(src_state[0], next_release_time)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        let insolvent = match src_insolvent {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let has_claimable = match src_has_claimable {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let paused = match src_paused {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let revocable = match src_revocable {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let split_count = src_split_count[0] as usize;
+        if split_count > MAX_SPLIT_DESTINATIONS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let emission_tracked = match src_emission_tracked {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let roll_into_final = match src_roll_into_final {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let destination_locked = match src_destination_locked {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let unlock_policy =
+            UnlockPolicy::from_u8(src_unlock_policy[0]).ok_or(ProgramError::InvalidAccountData)?;
+        let revoke_policy =
+            RevokePolicy::from_u8(src_revoke_policy[0]).ok_or(ProgramError::InvalidAccountData)?;
+        let schedule_layout = ScheduleLayout::from_u8(src_schedule_layout[0])
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        if src_account_type[0] != AccountType::for_state(state.is_created(), initialized_type) as u8
+        {
+            msg!("account is not a vesting account");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            destination_address: Pubkey::new_from_array(*src_destination_address),
+            mint_address: Pubkey::new_from_array(*src_mint_address),
+            granter_address: Pubkey::new_from_array(*src_granter_address),
+            next_release_time,
+            gas_rebate: u64::from_le_bytes(*src_gas_rebate),
+            gas_escrow: u64::from_le_bytes(*src_gas_escrow),
+            schedule_count: u32::from_le_bytes(*src_schedule_count),
+            unlock_policy,
+            legacy_destination_address: Pubkey::new_from_array(*src_legacy_destination_address),
+            legacy_cutoff: u64::from_le_bytes(*src_legacy_cutoff),
+            insolvent,
+            roll_into_final,
+            min_claim_amount: u64::from_le_bytes(*src_min_claim_amount),
+            destination_locked,
+            unlock_cursor: u32::from_le_bytes(*src_unlock_cursor),
+            sweep_next_release_time: u64::from_le_bytes(*src_sweep_next_release_time),
+            permit_nonce: u64::from_le_bytes(*src_permit_nonce),
+            withholding_bps: u16::from_le_bytes(*src_withholding_bps),
+            withholding_destination: Pubkey::new_from_array(*src_withholding_destination),
+            max_transfer_per_unlock: u64::from_le_bytes(*src_max_transfer_per_unlock),
+            unlock_interval: u32::from_le_bytes(*src_unlock_interval),
+            last_unlock_time: u64::from_le_bytes(*src_last_unlock_time),
+            revoke_policy,
+            unlock_count: u32::from_le_bytes(*src_unlock_count),
+            destination_change_count: u32::from_le_bytes(*src_destination_change_count),
+            revoke_attempt_count: u32::from_le_bytes(*src_revoke_attempt_count),
+            has_claimable,
+            activation_time: u64::from_le_bytes(*src_activation_time),
+            paused,
+            emergency_authority: Pubkey::new_from_array(*src_emergency_authority),
+            revocable,
+            splits: src_splits
+                .chunks_exact(34)
+                .take(split_count)
+                .map(|split| SplitDestination {
+                    destination_address: Pubkey::new_from_array(*array_ref!(split, 0, 32)),
+                    bps: u16::from_le_bytes(*array_ref!(split, 32, 2)),
+                })
+                .collect(),
+            emission_tracked,
+            unlocked_gross: u64::from_le_bytes(*src_unlocked_gross),
+            unlocked_net: u64::from_le_bytes(*src_unlocked_net),
+            revision: u32::from_le_bytes(*src_revision),
+            bump: src_bump[0],
+            schedule_layout,
+            state,
+        })
+    }
+}
+
+impl Pack for CompressedVestingTree {
+    //magic + account type + pubkey 32x3 + u32x2 + bool
+    const LEN: usize = 110;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref!(dst, 0, CompressedVestingTree::LEN);
+
+        let (
+            dst_magic,
+            dst_account_type,
+            dst_mint_address,
+            dst_merkle_tree,
+            dst_granter,
+            dst_max_depth,
+            dst_leaf_count,
+            dst_is_initialized,
+        ) = mut_array_refs![dst, 4, 1, 32, 32, 32, 4, 4, 1];
+
+        *dst_magic = ACCOUNT_MAGIC;
+
+        dst_account_type[0] =
+            AccountType::for_state(self.is_initialized, AccountType::CompressedVestingTree) as u8;
+        dst_mint_address.copy_from_slice(self.mint_address.as_ref());
+        dst_merkle_tree.copy_from_slice(self.merkle_tree.as_ref());
+        dst_granter.copy_from_slice(self.granter.as_ref());
+        *dst_max_depth = self.max_depth.to_le_bytes();
+        *dst_leaf_count = self.leaf_count.to_le_bytes();
+        dst_is_initialized[0] = self.is_initialized as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < CompressedVestingTree::LEN {
+            msg!(
+                "passed slice is shorter than {} bytes",
+                CompressedVestingTree::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref!(src, 0, CompressedVestingTree::LEN);
+
+        let (
+            src_magic,
+            src_account_type,
+            src_mint_address,
+            src_merkle_tree,
+            src_granter,
+            src_max_depth,
+            src_leaf_count,
+            src_is_initialized,
+        ) = array_refs![src, 4, 1, 32, 32, 32, 4, 4, 1];
+
+        check_magic(src_magic, src_account_type[0])?;
+
+        let is_initialized = match src_is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        if src_account_type[0]
+            != AccountType::for_state(is_initialized, AccountType::CompressedVestingTree) as u8
+        {
+            msg!("account is not a compressed vesting account");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            mint_address: Pubkey::new_from_array(*src_mint_address),
+            merkle_tree: Pubkey::new_from_array(*src_merkle_tree),
+            granter: Pubkey::new_from_array(*src_granter),
+            max_depth: u32::from_le_bytes(*src_max_depth),
+            leaf_count: u32::from_le_bytes(*src_leaf_count),
+            is_initialized,
+        })
+    }
+}
+
+impl Pack for ProgramConfig {
+    //magic + account type + pubkey 32x2 + u32 + bool x2
+    const LEN: usize = 75;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref!(dst, 0, ProgramConfig::LEN);
+
+        let (
+            dst_magic,
+            dst_account_type,
+            dst_admin,
+            dst_early_claim_tolerance_seconds,
+            dst_community_treasury,
+            dst_forbid_self_dealing,
+            dst_is_initialized,
+        ) = mut_array_refs![dst, 4, 1, 32, 4, 32, 1, 1];
+
+        *dst_magic = ACCOUNT_MAGIC;
+
+        dst_account_type[0] =
+            AccountType::for_state(self.is_initialized, AccountType::ProgramConfig) as u8;
+        dst_admin.copy_from_slice(self.admin.as_ref());
+        *dst_early_claim_tolerance_seconds = self.early_claim_tolerance_seconds.to_le_bytes();
+        dst_community_treasury.copy_from_slice(self.community_treasury.as_ref());
+        dst_forbid_self_dealing[0] = self.forbid_self_dealing as u8;
+        dst_is_initialized[0] = self.is_initialized as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < 75 {
+            msg!("passed slice is shorter than 75 bytes");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref!(src, 0, ProgramConfig::LEN);
+
+        let (
+            src_magic,
+            src_account_type,
+            src_admin,
+            src_early_claim_tolerance_seconds,
+            src_community_treasury,
+            src_forbid_self_dealing,
+            src_is_initialized,
+        ) = array_refs![src, 4, 1, 32, 4, 32, 1, 1];
+
+        check_magic(src_magic, src_account_type[0])?;
+
+        let forbid_self_dealing = match src_forbid_self_dealing {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let is_initialized = match src_is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        if src_account_type[0]
+            != AccountType::for_state(is_initialized, AccountType::ProgramConfig) as u8
+        {
+            msg!("account is not the program config");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            admin: Pubkey::new_from_array(*src_admin),
+            early_claim_tolerance_seconds: u32::from_le_bytes(*src_early_claim_tolerance_seconds),
+            community_treasury: Pubkey::new_from_array(*src_community_treasury),
+            forbid_self_dealing,
+            is_initialized,
+        })
+    }
+}
+
+impl Pack for DestinationIndex {
+    //magic + account type + pubkey 32 + bool
+    const LEN: usize = 38;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref!(dst, 0, DestinationIndex::LEN);
+
+        let (dst_magic, dst_account_type, dst_vesting_account, dst_is_initialized) =
+            mut_array_refs![dst, 4, 1, 32, 1];
+
+        *dst_magic = ACCOUNT_MAGIC;
+
+        dst_account_type[0] =
+            AccountType::for_state(self.is_initialized, AccountType::DestinationIndex) as u8;
+        dst_vesting_account.copy_from_slice(self.vesting_account.as_ref());
+        dst_is_initialized[0] = self.is_initialized as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < 38 {
+            msg!("passed slice is shorter than 38 bytes");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref!(src, 0, DestinationIndex::LEN);
+
+        let (src_magic, src_account_type, src_vesting_account, src_is_initialized) =
+            array_refs![src, 4, 1, 32, 1];
+
+        check_magic(src_magic, src_account_type[0])?;
+
+        let is_initialized = match src_is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        if src_account_type[0]
+            != AccountType::for_state(is_initialized, AccountType::DestinationIndex) as u8
+        {
+            msg!("account is not a destination index");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            vesting_account: Pubkey::new_from_array(*src_vesting_account),
+            is_initialized,
+        })
+    }
+}
+
+impl Pack for DestinationApprovers {
+    //magic + account type + pubkey 32 + u8 + approver count + pubkey 32 x MAX_DESTINATION_APPROVERS x 2 + bool
+    const LEN: usize = 680;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref!(dst, 0, DestinationApprovers::LEN);
+
+        let (
+            dst_magic,
+            dst_account_type,
+            dst_vesting_account,
+            dst_threshold,
+            dst_approver_count,
+            dst_approvers,
+            dst_votes,
+            dst_is_initialized,
+        ) = mut_array_refs![dst, 4, 1, 32, 1, 1, 320, 320, 1];
+
+        *dst_magic = ACCOUNT_MAGIC;
+
+        dst_account_type[0] =
+            AccountType::for_state(self.is_initialized, AccountType::DestinationApprovers) as u8;
+        dst_vesting_account.copy_from_slice(self.vesting_account.as_ref());
+        dst_threshold[0] = self.threshold;
+        dst_approver_count[0] = self.approvers.len() as u8;
+        dst_approvers.fill(0);
+        for (dst_approver, approver) in dst_approvers.chunks_exact_mut(32).zip(&self.approvers) {
+            dst_approver.copy_from_slice(approver.as_ref());
+        }
+        dst_votes.fill(0);
+        for (dst_vote, vote) in dst_votes.chunks_exact_mut(32).zip(&self.votes) {
+            dst_vote.copy_from_slice(vote.as_ref());
+        }
+        dst_is_initialized[0] = self.is_initialized as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < 680 {
+            msg!("passed slice is shorter than 680 bytes");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref!(src, 0, DestinationApprovers::LEN);
+
+        let (
+            src_magic,
+            src_account_type,
+            src_vesting_account,
+            src_threshold,
+            src_approver_count,
+            src_approvers,
+            src_votes,
+            src_is_initialized,
+        ) = array_refs![src, 4, 1, 32, 1, 1, 320, 320, 1];
+
+        check_magic(src_magic, src_account_type[0])?;
+
+        let is_initialized = match src_is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        if src_account_type[0]
+            != AccountType::for_state(is_initialized, AccountType::DestinationApprovers) as u8
+        {
+            msg!("account is not a destination approvers account");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let approver_count = src_approver_count[0] as usize;
+        if approver_count > MAX_DESTINATION_APPROVERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            vesting_account: Pubkey::new_from_array(*src_vesting_account),
+            threshold: src_threshold[0],
+            approvers: src_approvers
+                .chunks_exact(32)
+                .take(approver_count)
+                .map(|approver| Pubkey::new_from_array(*array_ref!(approver, 0, 32)))
+                .collect(),
+            votes: src_votes
+                .chunks_exact(32)
+                .take(approver_count)
+                .map(|vote| Pubkey::new_from_array(*array_ref!(vote, 0, 32)))
+                .collect(),
+            is_initialized,
+        })
+    }
+}
+
+impl DestinationApprovers {
+    /// Records the vote of `approvers[approver_index]` for `destination`, replacing whatever it voted for before.
+    /// Returns whether `threshold` approvers now agree on it, clearing every vote if they do - they were all cast
+    /// against the destination being replaced
+    pub fn vote(&mut self, approver_index: usize, destination: Pubkey) -> bool {
+        self.votes.resize(self.approvers.len(), Pubkey::default());
+        self.votes[approver_index] = destination;
+        let agreeing = self
+            .votes
+            .iter()
+            .filter(|vote| **vote == destination)
+            .count();
+        if agreeing < self.threshold as usize {
+            return false;
+        }
+        self.votes.fill(Pubkey::default());
+        true
+    }
+}
+
+impl Pack for EmissionCurve {
+    //magic + account type + pubkey 32 + u64x2 + u64 x EMISSION_CURVE_WEEKS + bool
+    const LEN: usize = 2134;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref!(dst, 0, EmissionCurve::LEN);
+
+        let (
+            dst_magic,
+            dst_account_type,
+            dst_mint_address,
+            dst_start_week,
+            dst_past_due,
+            dst_weekly_amounts,
+            dst_is_initialized,
+        ) = mut_array_refs![dst, 4, 1, 32, 8, 8, 2080, 1];
+
+        *dst_magic = ACCOUNT_MAGIC;
+
+        dst_account_type[0] =
+            AccountType::for_state(self.is_initialized, AccountType::EmissionCurve) as u8;
+        dst_mint_address.copy_from_slice(self.mint_address.as_ref());
+        *dst_start_week = self.start_week.to_le_bytes();
+        *dst_past_due = self.past_due.to_le_bytes();
+        dst_weekly_amounts.fill(0);
+        for (dst_amount, amount) in dst_weekly_amounts
+            .chunks_exact_mut(8)
+            .zip(&self.weekly_amounts)
+        {
+            dst_amount.copy_from_slice(&amount.to_le_bytes());
+        }
+        dst_is_initialized[0] = self.is_initialized as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < 2134 {
+            msg!("passed slice is shorter than 2134 bytes");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref!(src, 0, EmissionCurve::LEN);
+
+        let (
+            src_magic,
+            src_account_type,
+            src_mint_address,
+            src_start_week,
+            src_past_due,
+            src_weekly_amounts,
+            src_is_initialized,
+        ) = array_refs![src, 4, 1, 32, 8, 8, 2080, 1];
+
+        check_magic(src_magic, src_account_type[0])?;
+
+        let is_initialized = match src_is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        if src_account_type[0]
+            != AccountType::for_state(is_initialized, AccountType::EmissionCurve) as u8
+        {
+            msg!("account is not an emission curve");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            mint_address: Pubkey::new_from_array(*src_mint_address),
+            start_week: u64::from_le_bytes(*src_start_week),
+            past_due: u64::from_le_bytes(*src_past_due),
+            weekly_amounts: src_weekly_amounts
+                .chunks_exact(8)
+                .map(|amount| u64::from_le_bytes(*array_ref!(amount, 0, 8)))
+                .collect(),
+            is_initialized,
+        })
+    }
+}
+
+impl EmissionCurve {
+    /// An empty curve for `mint_address`, starting with the week of `now`
+    pub fn new(mint_address: Pubkey, now: u64) -> Self {
+        Self {
+            mint_address,
+            start_week: Self::week(now),
+            past_due: 0,
+            weekly_amounts: vec![0; EMISSION_CURVE_WEEKS],
+            is_initialized: true,
+        }
+    }
+
+    /// The week `time` falls in
+    pub fn week(time: u64) -> u64 {
+        time / SECONDS_PER_WEEK
+    }
+
+    /// Moves the curve up to the week of `now`, what was releasing in the weeks it leaves behind becoming past due.
+    /// Never moves it back, so a `now` of 0 leaves it where it is
+    pub fn roll(&mut self, now: u64) {
+        let week = Self::week(now);
+        if week <= self.start_week {
+            return;
+        }
+        let passed = (week - self.start_week).min(EMISSION_CURVE_WEEKS as u64) as usize;
+        self.past_due = self
+            .weekly_amounts
+            .drain(..passed)
+            .fold(self.past_due, u64::saturating_add);
+        self.weekly_amounts.resize(EMISSION_CURVE_WEEKS, 0);
+        self.start_week = week;
+    }
+
+    /// The amount schedules releasing at `release_time` add up in. None past the last week of the curve
+    fn amount_at(&mut self, release_time: u64) -> Option<&mut u64> {
+        let week = Self::week(release_time);
+        if week < self.start_week {
+            return Some(&mut self.past_due);
+        }
+        let index = week - self.start_week;
+        if index >= EMISSION_CURVE_WEEKS as u64 {
+            return None;
+        }
+        self.weekly_amounts.get_mut(index as usize)
+    }
+
+    /// Takes a contract's schedules off the curve and puts what they've become in their place. None, with the curve
+    /// left halfway, if one of `after` releases past the last week
+    pub fn replace(&mut self, before: &[VestingSchedule], after: &[VestingSchedule]) -> Option<()> {
+        // the curve holds at least what was put on it for every tracked contract, so taking a contract off only
+        // saturates on a curve that went wrong - which shouldn't keep anyone from claiming
+        for s in before {
+            if let Some(amount) = self.amount_at(s.release_time) {
+                *amount = amount.saturating_sub(s.amount);
+            }
+        }
+        // a paid out schedule doesn't need a week of its own
+        for s in after.iter().filter(|s| s.amount > 0) {
+            let amount = self.amount_at(s.release_time)?;
+            *amount = amount.saturating_add(s.amount);
+        }
+        Some(())
+    }
+
+    /// The week of `now` and the `weeks` weekly amounts from there on. The first one counts in what was already
+    /// released earlier that week
+    pub fn upcoming(mut self, now: u64, weeks: usize) -> (u64, Vec<u64>) {
+        self.roll(now);
+        self.weekly_amounts.truncate(weeks);
+        (self.start_week, self.weekly_amounts)
+    }
+}
+
+// ----------------------------------------------------------------------------- other
+
+pub fn unpack_schedules(input: &[u8]) -> Result<Vec<VestingSchedule>, ProgramError> {
+    let number_of_schedules = input.len() / VestingSchedule::LEN;
+    let mut output: Vec<VestingSchedule> = Vec::with_capacity(number_of_schedules);
+    let mut offset = 0;
+    for _ in 0..number_of_schedules {
+        output.push(VestingSchedule::unpack_from_slice(
+            &input[offset..offset + VestingSchedule::LEN],
+        )?);
+        offset += VestingSchedule::LEN;
+    }
+    Ok(output)
+}
+
+/// Category of every schedule of a vesting account, given its whole data. Schedules of a plain or linear contract are
+/// Uncategorized
+pub fn unpack_categories(
+    header: &VestingScheduleHeader,
+    data: &[u8],
+) -> Result<Vec<ScheduleCategory>, ProgramError> {
+    let count = header.schedule_count as usize;
+    match header.schedule_layout {
+        ScheduleLayout::Plain | ScheduleLayout::Linear => {
+            Ok(vec![ScheduleCategory::Uncategorized; count])
+        }
+        ScheduleLayout::Categorized => data
+            .get(header.schedules_end()..header.schedules_end() + count)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .iter()
+            .map(|c| ScheduleCategory::from_u8(*c).ok_or(ProgramError::InvalidAccountData))
+            .collect(),
+    }
+}
+
+/// The LinearVesting of a linear contract given its whole data, None for any other layout
+pub fn unpack_linear(
+    header: &VestingScheduleHeader,
+    data: &[u8],
+) -> Result<Option<LinearVesting>, ProgramError> {
+    match header.schedule_layout {
+        ScheduleLayout::Linear => Ok(Some(LinearVesting::unpack_from_slice(
+            data.get(header.schedules_end()..)
+                .ok_or(ProgramError::InvalidAccountData)?,
+        )?)),
+        ScheduleLayout::Plain | ScheduleLayout::Categorized => Ok(None),
+    }
+}
+
+pub fn pack_categories_into_slice(categories: &[ScheduleCategory], target: &mut [u8]) {
+    for (dst, category) in target.iter_mut().zip(categories.iter()) {
+        *dst = *category as u8;
+    }
+}
+
+/// Any account owned by the program, decoded according to its type byte. Baseline contracts decode too, as they would
+/// after MigrateLayout
+#[derive(Debug, PartialEq)]
+pub enum ProgramAccount {
+    Uninitialized,
+    Vesting {
+        header: VestingScheduleHeader,
+        schedules: Vec<VestingSchedule>,
+        categories: Vec<ScheduleCategory>,
+    },
+    CompressedVestingTree(CompressedVestingTree),
+    MigratedVesting {
+        header: VestingScheduleHeader,
+        schedules: Vec<VestingSchedule>,
+        categories: Vec<ScheduleCategory>,
+    },
+    ProgramConfig(ProgramConfig),
+    DestinationIndex(DestinationIndex),
+    DestinationApprovers(DestinationApprovers),
+    EmissionCurve(EmissionCurve),
+}
+
+impl ProgramAccount {
+    pub fn unpack_any(data: &[u8]) -> Result<Self, ProgramError> {
+        if is_baseline_layout(data) {
+            let mut upgraded = data.to_vec();
+            upgraded.resize(upgraded_baseline_len(data.len()), 0);
+            upgrade_baseline_layout(&mut upgraded)?;
+            return Self::unpack_any(&upgraded);
+        }
+
+        let account_type = account_type(data).ok_or(ProgramError::InvalidAccountData)?;
+
+        match account_type {
+            AccountType::Uninitialized => Ok(Self::Uninitialized),
+            AccountType::VestingSchedule => {
+                let header = VestingScheduleHeader::unpack_account(data)?;
+                Ok(Self::Vesting {
+                    schedules: unpack_schedules(
+                        &data[VestingScheduleHeader::LEN..header.schedules_end()],
+                    )?,
+                    categories: unpack_categories(&header, data)?,
+                    header,
+                })
+            }
+            AccountType::CompressedVestingTree => Ok(Self::CompressedVestingTree(
+                CompressedVestingTree::unpack(data)?,
+            )),
+            AccountType::MigratedVestingSchedule => {
+                let header = VestingScheduleHeader::unpack_migrated_account(data)?;
+                Ok(Self::MigratedVesting {
+                    schedules: unpack_schedules(
+                        &data[VestingScheduleHeader::LEN..header.schedules_end()],
+                    )?,
+                    categories: unpack_categories(&header, data)?,
+                    header,
+                })
+            }
+            AccountType::ProgramConfig => Ok(Self::ProgramConfig(ProgramConfig::unpack(data)?)),
+            AccountType::DestinationIndex => {
+                Ok(Self::DestinationIndex(DestinationIndex::unpack(data)?))
+            }
+            AccountType::DestinationApprovers => Ok(Self::DestinationApprovers(
+                DestinationApprovers::unpack(data)?,
+            )),
+            AccountType::EmissionCurve => Ok(Self::EmissionCurve(EmissionCurve::unpack(data)?)),
+        }
+    }
+}
+
+/// How many schedules log_contract_summary prints
+pub const SUMMARY_SCHEDULES: usize = 3;
+
+/// Logs the gist of a contract - who it pays, in what, and its first few schedules - to make failed checks easier
+/// to make sense of. `schedules_prefix` is the account data after the header, only its start is read
+pub fn log_contract_summary(header: &VestingScheduleHeader, schedules_prefix: &[u8]) {
+    msg!(
+        "contract: destination {}, mint {}, granter {}",
+        header.destination_address,
+        header.mint_address,
+        header.granter_address
+    );
+    msg!(
+        "contract: {} schedules, next release {}, unlock policy {}",
+        header.schedule_count,
+        header.next_release_time,
+        header.unlock_policy.as_str()
+    );
+    for (i, chunk) in schedules_prefix
+        .chunks_exact(VestingSchedule::LEN)
+        .take(SUMMARY_SCHEDULES)
+        .enumerate()
+    {
+        if let Ok(s) = VestingSchedule::unpack_from_slice(chunk) {
+            msg!("schedule {}: {} at {}", i, s.amount, s.release_time);
+        }
+    }
+}
+
+/// Drops the schedules that have been paid out (amount 0) from a vesting account's data, moving the remaining
+/// schedules - and their categories - down. Updates the header to match, and returns the length the account has to
+/// be shrunk to: whatever is left past it is stale
+pub fn compact_schedules(
+    header: &mut VestingScheduleHeader,
+    data: &mut [u8],
+) -> Result<usize, ProgramError> {
+    let schedules = unpack_schedules(&data[VestingScheduleHeader::LEN..header.schedules_end()])?;
+    let categories = unpack_categories(header, data)?;
+    let (kept_schedules, kept_categories): (Vec<VestingSchedule>, Vec<ScheduleCategory>) =
+        schedules
+            .into_iter()
+            .zip(categories)
+            .filter(|(s, _)| s.amount > 0)
+            .unzip();
+
+    header.schedule_count = kept_schedules.len() as u32;
+    // the indexes of a bounded sweep in progress no longer hold, this starts it over
+    header.update_next_release_time(&kept_schedules);
+    header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+
+    let schedules_end = header.schedules_end();
+    pack_schedules_into_slice(
+        kept_schedules,
+        &mut data[VestingScheduleHeader::LEN..schedules_end],
+    );
+    if header.schedule_layout == ScheduleLayout::Categorized {
+        pack_categories_into_slice(&kept_categories, &mut data[schedules_end..]);
+    }
+
+    Ok(VestingScheduleHeader::account_len(
+        header.schedule_count as usize,
+        header.schedule_layout,
+    ))
+}
+
+pub fn pack_schedules_into_slice(schedules: Vec<VestingSchedule>, target: &mut [u8]) {
+    let mut offset = 0;
+    for s in schedules.iter() {
+        s.pack_into_slice(&mut target[offset..]);
+        offset += VestingSchedule::LEN;
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_packing() {
+        // create the 3 structures
+        let header = VestingScheduleHeader {
+            destination_address: Pubkey::new_unique(), //nice function for testing
+            mint_address: Pubkey::new_unique(),
+            granter_address: Pubkey::new_unique(),
+            next_release_time: 1,
+            gas_rebate: 5000,
+            gas_escrow: 50000,
+            schedule_count: 2,
+            unlock_policy: UnlockPolicy::DestinationOwnerOnly,
+            legacy_destination_address: Pubkey::new_unique(),
+            legacy_cutoff: 50,
+            insolvent: true,
+            roll_into_final: true,
+            min_claim_amount: 1000,
+            destination_locked: true,
+            unlock_cursor: 1,
+            sweep_next_release_time: 7,
+            permit_nonce: 3,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 500,
+            unlock_interval: 3600,
+            last_unlock_time: 40,
+            revoke_policy: RevokePolicy::Burn,
+            unlock_count: 4,
+            destination_change_count: 2,
+            revoke_attempt_count: 1,
+            has_claimable: true,
+            activation_time: 1_700_000_000,
+            paused: true,
+            emergency_authority: Pubkey::new_unique(),
+            revocable: false,
+            splits: vec![
+                SplitDestination {
+                    destination_address: Pubkey::new_unique(),
+                    bps: 2_000,
+                },
+                SplitDestination {
+                    destination_address: Pubkey::new_unique(),
+                    bps: 1_000,
+                },
+            ],
+            emission_tracked: true,
+            unlocked_gross: 1_000,
+            unlocked_net: 990,
+            revision: 7,
+            bump: 254,
+            schedule_layout: ScheduleLayout::Categorized,
+            state: ContractState::Active,
+        };
+        let schedule_1 = VestingSchedule {
+            release_time: 1,
+            amount: 333,
+        };
+        let schedule_2 = VestingSchedule {
+            release_time: 99999,
+            amount: 111,
+        };
+
+        // pack them into a slice
+        const SIZE: usize = VestingScheduleHeader::LEN + 2 * VestingSchedule::LEN;
+        let mut state_array = [0_u8; SIZE];
+
+        header.pack_into_slice(&mut state_array[..VestingScheduleHeader::LEN]);
+        schedule_1.pack_into_slice(
+            &mut state_array
+                [VestingScheduleHeader::LEN..VestingScheduleHeader::LEN + VestingSchedule::LEN],
+        );
+        schedule_2
+            .pack_into_slice(&mut state_array[VestingScheduleHeader::LEN + VestingSchedule::LEN..]);
+        let packed = Vec::from(state_array);
+
+        // create an empty vector of same size
+        let mut expected = Vec::<u8>::with_capacity(SIZE);
+        // use extend_from_slice and to_le_bytes() to pack it
+        expected.extend_from_slice(b"TVST");
+        expected.push(AccountType::VestingSchedule as u8);
+        expected.push(HEADER_VERSION);
+        expected.extend_from_slice(&header.destination_address.to_bytes());
+        expected.extend_from_slice(&header.mint_address.to_bytes());
+        expected.extend_from_slice(&header.granter_address.to_bytes());
+        expected.extend_from_slice(&header.next_release_time.to_le_bytes());
+        expected.extend_from_slice(&header.gas_rebate.to_le_bytes());
+        expected.extend_from_slice(&header.gas_escrow.to_le_bytes());
+        expected.extend_from_slice(&header.schedule_count.to_le_bytes());
+        expected.extend_from_slice(&[header.unlock_policy as u8]);
+        expected.extend_from_slice(&header.legacy_destination_address.to_bytes());
+        expected.extend_from_slice(&header.legacy_cutoff.to_le_bytes());
+        expected.extend_from_slice(&[header.insolvent as u8]);
+        expected.extend_from_slice(&[header.roll_into_final as u8]);
+        expected.extend_from_slice(&header.min_claim_amount.to_le_bytes());
+        expected.extend_from_slice(&[header.destination_locked as u8]);
+        expected.extend_from_slice(&header.unlock_cursor.to_le_bytes());
+        expected.extend_from_slice(&header.sweep_next_release_time.to_le_bytes());
+        expected.extend_from_slice(&header.permit_nonce.to_le_bytes());
+        expected.extend_from_slice(&header.withholding_bps.to_le_bytes());
+        expected.extend_from_slice(&header.withholding_destination.to_bytes());
+        expected.extend_from_slice(&header.max_transfer_per_unlock.to_le_bytes());
+        expected.extend_from_slice(&header.unlock_interval.to_le_bytes());
+        expected.extend_from_slice(&header.last_unlock_time.to_le_bytes());
+        expected.extend_from_slice(&[header.revoke_policy as u8]);
+        expected.extend_from_slice(&header.unlock_count.to_le_bytes());
+        expected.extend_from_slice(&header.destination_change_count.to_le_bytes());
+        expected.extend_from_slice(&header.revoke_attempt_count.to_le_bytes());
+        expected.extend_from_slice(&[header.has_claimable as u8]);
+        expected.extend_from_slice(&header.activation_time.to_le_bytes());
+        expected.extend_from_slice(&[header.paused as u8]);
+        expected.extend_from_slice(&header.emergency_authority.to_bytes());
+        expected.extend_from_slice(&[header.revocable as u8]);
+        expected.push(header.splits.len() as u8);
+        for split in &header.splits {
+            expected.extend_from_slice(&split.destination_address.to_bytes());
+            expected.extend_from_slice(&split.bps.to_le_bytes());
+        }
+        expected.extend_from_slice(&[0; 2 * 34]);
+        expected.extend_from_slice(&[header.emission_tracked as u8]);
+        expected.extend_from_slice(&header.unlocked_gross.to_le_bytes());
+        expected.extend_from_slice(&header.unlocked_net.to_le_bytes());
+        expected.extend_from_slice(&header.revision.to_le_bytes());
+        expected.push(header.bump);
+        expected.extend_from_slice(&[header.schedule_layout as u8]);
+        expected.extend_from_slice(&[header.state as u8]);
+        expected.extend_from_slice(&schedule_1.release_time.to_le_bytes());
+        expected.extend_from_slice(&schedule_1.amount.to_le_bytes());
+        expected.extend_from_slice(&schedule_2.release_time.to_le_bytes());
+        expected.extend_from_slice(&schedule_2.amount.to_le_bytes());
+
+        // test packing
+        assert_eq!(packed, expected);
+
+        // test unpacking
+        let unpacked_header =
+            VestingScheduleHeader::unpack_from_slice(&packed[..VestingScheduleHeader::LEN])
+                .unwrap();
+        assert_eq!(header, unpacked_header);
+        let unpacked_s1 = VestingSchedule::unpack_from_slice(
+            &packed[VestingScheduleHeader::LEN..VestingScheduleHeader::LEN + VestingSchedule::LEN],
+        )
+        .unwrap();
+        assert_eq!(schedule_1, unpacked_s1);
+        let unpacked_s2 = VestingSchedule::unpack_from_slice(
+            &packed[VestingScheduleHeader::LEN + VestingSchedule::LEN..],
+        )
+        .unwrap();
+        assert_eq!(schedule_2, unpacked_s2);
+
+        assert_eq!(
+            packed[NEXT_RELEASE_TIME_OFFSET..NEXT_RELEASE_TIME_OFFSET + 8],
+            header.next_release_time.to_le_bytes()
+        );
+        assert_eq!(packed[INSOLVENT_OFFSET], header.insolvent as u8);
+        assert_eq!(packed[HAS_CLAIMABLE_OFFSET], header.has_claimable as u8);
+        assert_eq!(packed[PAUSED_OFFSET], header.paused as u8);
+        assert_eq!(packed[CONTRACT_STATE_OFFSET], header.state as u8);
+        assert_eq!(packed[BUMP_OFFSET], header.bump);
+        assert_eq!(packed[UNLOCK_POLICY_OFFSET], header.unlock_policy as u8);
+        assert_eq!(
+            packed[GRANTER_OFFSET..GRANTER_OFFSET + 32],
+            header.granter_address.to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_next_release_time() {
+        let schedule = |release_time, amount| VestingSchedule {
+            release_time,
+            amount,
+        };
+        assert_eq!(
+            next_release_time(&[schedule(30, 1), schedule(10, 0), schedule(20, 5)]),
+            20
+        );
+        assert_eq!(
+            next_release_time(&[schedule(10, 0), schedule(20, 0)]),
+            NO_PENDING_RELEASE
+        );
+        assert_eq!(next_release_time(&[]), NO_PENDING_RELEASE);
+    }
+
+    #[test]
+    fn test_unpack_any() {
+        let header = VestingScheduleHeader {
+            destination_address: Pubkey::new_unique(),
+            mint_address: Pubkey::new_unique(),
+            granter_address: Pubkey::new_unique(),
+            next_release_time: 1,
+            gas_rebate: 5000,
+            gas_escrow: 50000,
+            schedule_count: 1,
+            unlock_policy: UnlockPolicy::DestinationOwnerOnly,
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount: 0,
+            destination_locked: false,
+            unlock_cursor: 0,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            splits: vec![],
+            emission_tracked: false,
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: 0,
+            schedule_layout: ScheduleLayout::Plain,
+            state: ContractState::Active,
+        };
+        let schedule = VestingSchedule {
+            release_time: 1,
+            amount: 333,
+        };
+        let mut vesting_data = [0_u8; VestingScheduleHeader::LEN + VestingSchedule::LEN];
+        header.pack_into_slice(&mut vesting_data[..VestingScheduleHeader::LEN]);
+        schedule.pack_into_slice(&mut vesting_data[VestingScheduleHeader::LEN..]);
+
+        let tree = CompressedVestingTree {
+            mint_address: Pubkey::new_unique(),
+            merkle_tree: Pubkey::new_unique(),
+            granter: Pubkey::new_unique(),
+            max_depth: 5,
+            leaf_count: 3,
+            is_initialized: true,
+        };
+        let mut tree_data = [0_u8; CompressedVestingTree::LEN];
+        tree.pack_into_slice(&mut tree_data);
+
+        assert_eq!(
+            ProgramAccount::unpack_any(&vesting_data).unwrap(),
+            ProgramAccount::Vesting {
+                header,
+                schedules: vec![schedule],
+                categories: vec![ScheduleCategory::Uncategorized],
+            }
+        );
+        assert_eq!(
+            ProgramAccount::unpack_any(&tree_data).unwrap(),
+            ProgramAccount::CompressedVestingTree(tree)
+        );
+
+        let config = ProgramConfig {
+            admin: Pubkey::new_unique(),
+            early_claim_tolerance_seconds: 30,
+            community_treasury: Pubkey::new_unique(),
+            forbid_self_dealing: true,
+            is_initialized: true,
+        };
+        let mut config_data = [0_u8; ProgramConfig::LEN];
+        config.pack_into_slice(&mut config_data);
+        assert_eq!(config_data[37..41], 30_u32.to_le_bytes());
+        assert_eq!(config_data[41..73], config.community_treasury.to_bytes());
+        assert_eq!(config_data[73], 1);
+        assert_eq!(
+            ProgramAccount::unpack_any(&config_data).unwrap(),
+            ProgramAccount::ProgramConfig(config)
+        );
+        assert!(ProgramConfig::unpack(&vesting_data[..ProgramConfig::LEN]).is_err());
+
+        let index = DestinationIndex {
+            vesting_account: Pubkey::new_unique(),
+            is_initialized: true,
+        };
+        let mut index_data = [0_u8; DestinationIndex::LEN];
+        index.pack_into_slice(&mut index_data);
+        assert_eq!(
+            ProgramAccount::unpack_any(&index_data).unwrap(),
+            ProgramAccount::DestinationIndex(index)
+        );
+        assert!(DestinationIndex::unpack(&config_data[..DestinationIndex::LEN]).is_err());
+
+        let approvers = DestinationApprovers {
+            vesting_account: Pubkey::new_unique(),
+            threshold: 2,
+            approvers: vec![
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ],
+            votes: vec![
+                Pubkey::new_unique(),
+                Pubkey::default(),
+                Pubkey::new_unique(),
+            ],
+            is_initialized: true,
+        };
+        let mut approvers_data = [0_u8; DestinationApprovers::LEN];
+        approvers.pack_into_slice(&mut approvers_data);
+        assert_eq!(approvers_data[38], 3);
+        assert_eq!(
+            ProgramAccount::unpack_any(&approvers_data).unwrap(),
+            ProgramAccount::DestinationApprovers(approvers)
+        );
+        approvers_data[38] = MAX_DESTINATION_APPROVERS as u8 + 1;
+        assert!(DestinationApprovers::unpack(&approvers_data).is_err());
+
+        let mut curve = EmissionCurve::new(Pubkey::new_unique(), 3 * SECONDS_PER_WEEK);
+        curve.past_due = 7;
+        curve.weekly_amounts[EMISSION_CURVE_WEEKS - 1] = 9;
+        let mut curve_data = vec![0_u8; EmissionCurve::LEN];
+        curve.pack_into_slice(&mut curve_data);
+        assert_eq!(
+            ProgramAccount::unpack_any(&curve_data).unwrap(),
+            ProgramAccount::EmissionCurve(curve)
+        );
+        assert!(EmissionCurve::unpack(&approvers_data).is_err());
+        // fresh out of Init
+        assert_eq!(
+            ProgramAccount::unpack_any(&[0; 100]).unwrap(),
+            ProgramAccount::Uninitialized
+        );
+
+        // a tree can't be read as a vesting header and the other way around
+        let mut padded_tree_data = [0_u8; VestingScheduleHeader::LEN];
+        padded_tree_data[..CompressedVestingTree::LEN].copy_from_slice(&tree_data);
+        assert!(VestingScheduleHeader::unpack(&padded_tree_data).is_err());
+        assert!(
+            CompressedVestingTree::unpack(&vesting_data[..CompressedVestingTree::LEN]).is_err()
+        );
+        vesting_data[ACCOUNT_TYPE_OFFSET] = 42;
+        assert!(ProgramAccount::unpack_any(&vesting_data).is_err());
+    }
+
+    #[test]
+    fn test_emission_curve() {
+        let schedule = |week: u64, amount| VestingSchedule {
+            release_time: week * SECONDS_PER_WEEK + 100,
+            amount,
+        };
+        let mut curve = EmissionCurve::new(Pubkey::new_unique(), 10 * SECONDS_PER_WEEK);
+        let contract = vec![schedule(8, 5), schedule(10, 10), schedule(12, 20)];
+        curve.replace(&[], &contract).unwrap();
+        // released before the curve started
+        assert_eq!(curve.past_due, 5);
+        assert_eq!(curve.weekly_amounts[..4], [10, 0, 20, 0]);
+
+        // a week later, what releases in week 10 is past due too
+        assert_eq!(
+            curve.clone().upcoming(11 * SECONDS_PER_WEEK, 3),
+            (11, vec![0, 20, 0])
+        );
+        curve.roll(11 * SECONDS_PER_WEEK);
+        assert_eq!((curve.start_week, curve.past_due), (11, 15));
+        assert_eq!(curve.weekly_amounts.len(), EMISSION_CURVE_WEEKS);
+        // never back
+        curve.roll(0);
+        assert_eq!(curve.start_week, 11);
+
+        // weeks 8 and 10 claimed, week 12 accelerated to now
+        let after = vec![schedule(8, 0), schedule(10, 0), schedule(11, 20)];
+        curve.replace(&contract, &after).unwrap();
+        assert_eq!(curve.past_due, 0);
+        assert_eq!(curve.weekly_amounts[..2], [20, 0]);
+
+        // up to the last week, and no further
+        let last_week = 11 + EMISSION_CURVE_WEEKS as u64 - 1;
+        curve.replace(&[], &[schedule(last_week, 1)]).unwrap();
+        assert_eq!(curve.weekly_amounts[EMISSION_CURVE_WEEKS - 1], 1);
+        assert_eq!(curve.replace(&[], &[schedule(last_week + 1, 1)]), None);
+        // unless there's nothing left of it
+        curve.replace(&[], &[schedule(last_week + 1, 0)]).unwrap();
+
+        // rolling past every week moves everything to past_due
+        curve.roll((last_week + 10) * SECONDS_PER_WEEK);
+        assert_eq!(curve.past_due, 21);
+        assert!(curve.weekly_amounts.iter().all(|a| *a == 0));
+    }
+
+    #[test]
+    fn test_contract_state() {
+        use ContractEvent::*;
+        use ContractState::*;
+        assert_eq!(Uninitialized.apply(Init), Ok(Initialized));
+        assert_eq!(Initialized.apply(Create), Ok(Active));
+        assert_eq!(Uninitialized.apply(Create), Ok(Active));
+        assert_eq!(Active.apply(Modify), Ok(Active));
+        assert_eq!(Active.apply(Exhaust), Ok(Exhausted));
+        assert_eq!(Exhausted.apply(Close), Ok(Closed));
+        assert_eq!(Active.apply(Close), Err(VestingError::ContractNotFinished));
+        assert_eq!(
+            Exhausted.apply(Modify),
+            Err(VestingError::ContractExhausted)
+        );
+        for state in [Active, Exhausted, Closed, AwaitingActivation] {
+            assert_eq!(state.apply(Create), Err(VestingError::InvalidContractState));
+        }
+
+        // only an account that never got a contract can be given back without one
+        assert_eq!(Initialized.apply(CloseUninitialized), Ok(Closed));
+        for state in [Uninitialized, Active, Exhausted, Closed, AwaitingActivation] {
+            assert_eq!(
+                state.apply(CloseUninitialized),
+                Err(VestingError::InvalidContractState)
+            );
+        }
+
+        // a relative contract only moves on through Activate, or a Revoke of everything
+        assert_eq!(Initialized.apply(CreateRelative), Ok(AwaitingActivation));
+        assert_eq!(AwaitingActivation.apply(Activate), Ok(Active));
+        assert_eq!(AwaitingActivation.apply(Exhaust), Ok(Exhausted));
+        assert_eq!(
+            AwaitingActivation.apply(Modify),
+            Err(VestingError::AwaitingActivation)
+        );
+        assert_eq!(
+            AwaitingActivation.apply(Close),
+            Err(VestingError::ContractNotFinished)
+        );
+        assert_eq!(
+            Active.apply(Activate),
+            Err(VestingError::InvalidContractState)
+        );
+        assert_eq!(
+            Closed.apply(Modify),
+            Err(VestingError::InvalidContractState)
+        );
+
+        // a legacy is_initialized = true is told apart by next_release_time, and written back as what it was read as
+        assert_eq!(
+            ContractState::from_byte(LEGACY_INITIALIZED_STATE, 10),
+            Some(Active)
+        );
+        assert_eq!(
+            ContractState::from_byte(LEGACY_INITIALIZED_STATE, NO_PENDING_RELEASE),
+            Some(Exhausted)
+        );
+        assert_eq!(ContractState::from_byte(7, 10), None);
+
+        let mut header =
+            VestingScheduleHeader::unpack_unchecked(&[0; VestingScheduleHeader::LEN]).unwrap();
+        assert_eq!(header.state, Uninitialized);
+        header.state = Active;
+        header.next_release_time = 10;
+        let mut data = [0_u8; VestingScheduleHeader::LEN];
+        header.pack_into_slice(&mut data);
+        data[CONTRACT_STATE_OFFSET] = LEGACY_INITIALIZED_STATE;
+        assert_eq!(VestingScheduleHeader::unpack(&data).unwrap().state, Active);
+
+        header.update_next_release_time(&[VestingSchedule {
+            release_time: 10,
+            amount: 0,
+        }]);
+        assert_eq!(header.state, Exhausted);
+        header.pack_into_slice(&mut data);
+        assert_eq!(data[CONTRACT_STATE_OFFSET], Exhausted as u8);
+    }
+
+    #[test]
+    fn test_header_setters() {
+        let mut header =
+            VestingScheduleHeader::unpack_unchecked(&[0; VestingScheduleHeader::LEN]).unwrap();
+        header.destination_address = Pubkey::new_unique();
+        header.mint_address = Pubkey::new_unique();
+        header.destination_change_count = 2;
+        header.next_release_time = 10;
+        header.state = ContractState::Active;
+        let mut data = [0_u8; VestingScheduleHeader::LEN];
+        header.pack_into_slice(&mut data);
+
+        let destination = Pubkey::new_unique();
+        write_destination(&mut data, &destination);
+        header.destination_address = destination;
+        header.destination_change_count = 3;
+        assert_eq!(VestingScheduleHeader::unpack(&data).unwrap(), header);
+
+        write_state(&mut data, ContractState::Exhausted);
+        header.state = ContractState::Exhausted;
+        assert_eq!(VestingScheduleHeader::unpack(&data).unwrap(), header);
+
+        assert_eq!(bump_revision(&mut data), Ok(1));
+        header.revision = 1;
+        assert_eq!(VestingScheduleHeader::unpack(&data).unwrap(), header);
+        let mut packed = [0_u8; VestingScheduleHeader::LEN];
+        header.pack_into_slice(&mut packed);
+        assert_eq!(data, packed);
+
+        // a revision that would wrap is refused
+        data[REVISION_OFFSET..REVISION_OFFSET + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            bump_revision(&mut data),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // what Init leaves behind is all zeroes but the state
+        let mut data = [0_u8; VestingScheduleHeader::LEN];
+        write_state(&mut data, ContractState::Initialized);
+        assert_eq!(account_type(&data), Some(AccountType::Uninitialized));
+        assert_eq!(
+            VestingScheduleHeader::unpack_unchecked(&data)
+                .unwrap()
+                .state,
+            ContractState::Initialized
+        );
+    }
+
+    #[test]
+    fn test_baseline_layout() {
+        // a contract as the baseline program packed it: destination, mint and is_initialized, then the schedules
+        let destination = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let schedules = vec![
+            VestingSchedule {
+                release_time: 10,
+                amount: 0,
+            },
+            VestingSchedule {
+                release_time: 20,
+                amount: 100,
+            },
+        ];
+        let mut baseline = Vec::new();
+        baseline.extend_from_slice(&destination.to_bytes());
+        baseline.extend_from_slice(&mint.to_bytes());
+        baseline.push(1);
+        for schedule in &schedules {
+            baseline.extend_from_slice(&schedule.release_time.to_le_bytes());
+            baseline.extend_from_slice(&schedule.amount.to_le_bytes());
+        }
+        assert_eq!(
+            baseline.len(),
+            BASELINE_HEADER_LEN + 2 * VestingSchedule::LEN
+        );
+        assert!(is_baseline_layout(&baseline));
+        assert_eq!(account_type(&baseline), None);
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&baseline),
+            Err(VestingError::LegacyAccountLayout.into())
+        );
+
+        // what MigrateLayout does after growing the account
+        let mut migrated = baseline.clone();
+        migrated.resize(upgraded_baseline_len(baseline.len()), 0);
+        upgrade_baseline_layout(&mut migrated).unwrap();
+        assert_eq!(migrated[..4], *b"TVST");
+        assert_eq!(migrated[HEADER_VERSION_OFFSET], HEADER_VERSION);
+        assert!(!is_baseline_layout(&migrated));
+        let header = VestingScheduleHeader::unpack_account(&migrated).unwrap();
+        assert_eq!(header.destination_address, destination);
+        assert_eq!(header.mint_address, mint);
+        assert_eq!(header.granter_address, Pubkey::default());
+        assert_eq!(header.schedule_count, 2);
+        assert_eq!(header.next_release_time, 20);
+        assert_eq!(header.unlock_policy, UnlockPolicy::Permissionless);
+        assert!(!header.revocable);
+        assert_eq!(header.bump, LEGACY_BUMP);
+        assert_eq!(header.state, ContractState::Active);
+        assert_eq!(
+            unpack_schedules(&migrated[VestingScheduleHeader::LEN..]).unwrap(),
+            schedules
+        );
+        // readers get the same without waiting for it
+        assert_eq!(
+            ProgramAccount::unpack_any(&baseline).unwrap(),
+            ProgramAccount::Vesting {
+                header,
+                schedules,
+                categories: vec![ScheduleCategory::Uncategorized; 2],
+            }
+        );
+        // once is enough
+        assert_eq!(
+            upgrade_baseline_layout(&mut migrated.clone()),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // a baseline contract with nothing left to pay out
+        let mut exhausted = baseline.clone();
+        exhausted[BASELINE_HEADER_LEN + 24..].fill(0);
+        exhausted.resize(upgraded_baseline_len(baseline.len()), 0);
+        upgrade_baseline_layout(&mut exhausted).unwrap();
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&exhausted)
+                .unwrap()
+                .state,
+            ContractState::Exhausted
+        );
+
+        // a version this program doesn't know
+        let mut unknown_version = migrated.clone();
+        unknown_version[HEADER_VERSION_OFFSET] = HEADER_VERSION + 1;
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&unknown_version),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // fresh out of Init, on either layout
+        assert!(!is_baseline_layout(&[0; BASELINE_HEADER_LEN + 16]));
+        assert_eq!(
+            account_type(&[0; BASELINE_HEADER_LEN + 16]),
+            Some(AccountType::Uninitialized)
+        );
+        // not ours at all
+        assert_eq!(account_type(&[42; 100]), None);
+        assert!(ProgramAccount::unpack_any(&[42; 100]).is_err());
+    }
+
+    #[test]
+    fn test_compact_schedules() {
+        let schedules = vec![
+            VestingSchedule {
+                release_time: 10,
+                amount: 0,
+            },
+            VestingSchedule {
+                release_time: 20,
+                amount: 100,
+            },
+            VestingSchedule {
+                release_time: 30,
+                amount: 0,
+            },
+            VestingSchedule {
+                release_time: 40,
+                amount: 50,
+            },
+        ];
+        let mut header = VestingScheduleHeader {
+            destination_address: Pubkey::new_unique(),
+            mint_address: Pubkey::new_unique(),
+            granter_address: Pubkey::new_unique(),
+            next_release_time: 20,
+            gas_rebate: 0,
+            gas_escrow: 0,
+            schedule_count: 4,
+            unlock_policy: UnlockPolicy::DestinationOwnerOnly,
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount: 0,
+            destination_locked: false,
+            unlock_cursor: 2,
+            sweep_next_release_time: 40,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            splits: vec![],
+            emission_tracked: false,
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: 0,
+            schedule_layout: ScheduleLayout::Categorized,
+            state: ContractState::Active,
+        };
+        let mut data =
+            vec![0_u8; VestingScheduleHeader::account_len(4, ScheduleLayout::Categorized)];
+        header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(schedules, &mut data[VestingScheduleHeader::LEN..]);
+        pack_categories_into_slice(
+            &[
+                ScheduleCategory::Salary,
+                ScheduleCategory::Bonus,
+                ScheduleCategory::Advisor,
+                ScheduleCategory::Investor,
+            ],
+            &mut data[header.schedules_end()..],
+        );
+
+        let new_len = compact_schedules(&mut header, &mut data).unwrap();
+        assert_eq!(
+            new_len,
+            VestingScheduleHeader::account_len(2, ScheduleLayout::Categorized)
+        );
+        assert_eq!(header.schedule_count, 2);
+        assert_eq!(header.unlock_cursor, 0);
+        assert_eq!(header.sweep_next_release_time, NO_PENDING_RELEASE);
+        assert_eq!(
+            ProgramAccount::unpack_any(&data[..new_len]).unwrap(),
+            ProgramAccount::Vesting {
+                header,
+                schedules: vec![
+                    VestingSchedule {
+                        release_time: 20,
+                        amount: 100,
+                    },
+                    VestingSchedule {
+                        release_time: 40,
+                        amount: 50,
+                    },
+                ],
+                categories: vec![ScheduleCategory::Bonus, ScheduleCategory::Investor],
+            }
+        );
+    }
+
+    #[test]
+    fn test_log_contract_summary_tolerates_short_data() {
+        let header = VestingScheduleHeader {
+            destination_address: Pubkey::new_unique(),
+            mint_address: Pubkey::new_unique(),
+            granter_address: Pubkey::new_unique(),
+            next_release_time: NO_PENDING_RELEASE,
+            gas_rebate: 0,
+            gas_escrow: 0,
+            schedule_count: 5,
+            unlock_policy: UnlockPolicy::Permissionless,
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount: 0,
+            destination_locked: false,
+            unlock_cursor: 0,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            splits: vec![],
+            emission_tracked: false,
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: 0,
+            schedule_layout: ScheduleLayout::Plain,
+            state: ContractState::Active,
+        };
+        log_contract_summary(&header, &[1; 5 * VestingSchedule::LEN]);
+        // only part of a schedule left
+        log_contract_summary(&header, &[1; VestingSchedule::LEN + 3]);
+        log_contract_summary(&header, &[]);
+    }
+
+    #[test]
+    fn test_unpack_short_header() {
+        let src = [0; VestingScheduleHeader::LEN - 1];
+        assert_eq!(
+            VestingScheduleHeader::unpack_from_slice(&src),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            VestingScheduleHeader::unpack_migrated(&src),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_unpack_account_checks_schedule_count() {
+        let header = VestingScheduleHeader {
+            destination_address: Pubkey::new_unique(),
+            mint_address: Pubkey::new_unique(),
+            granter_address: Pubkey::new_unique(),
+            next_release_time: 1,
+            gas_rebate: 0,
+            gas_escrow: 0,
+            schedule_count: 2,
+            unlock_policy: UnlockPolicy::Permissionless,
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount: 0,
+            destination_locked: false,
+            unlock_cursor: 0,
+            schedule_layout: ScheduleLayout::Plain,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            splits: vec![],
+            emission_tracked: false,
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: 0,
+            state: ContractState::Active,
+        };
+        let mut data = vec![0_u8; VestingScheduleHeader::LEN + 2 * VestingSchedule::LEN];
+        header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&data).unwrap(),
+            header
+        );
+
+        let corrupt: ProgramError = VestingError::CorruptState.into();
+        // truncated, by a whole schedule or a single byte
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&data[..data.len() - VestingSchedule::LEN])
+                .unwrap_err(),
+            corrupt
+        );
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&data[..data.len() - 1]).unwrap_err(),
+            corrupt
+        );
+        // padded
+        data.push(0);
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&data).unwrap_err(),
+            corrupt
+        );
+        assert_eq!(ProgramAccount::unpack_any(&data).unwrap_err(), corrupt);
+        // too short to even hold the header
+        assert_eq!(
+            VestingScheduleHeader::unpack_account(&data[..VestingScheduleHeader::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_unpack_categorized_account() {
+        let header = VestingScheduleHeader {
+            destination_address: Pubkey::new_unique(),
+            mint_address: Pubkey::new_unique(),
+            granter_address: Pubkey::new_unique(),
+            next_release_time: 1,
+            gas_rebate: 0,
+            gas_escrow: 0,
+            schedule_count: 2,
+            unlock_policy: UnlockPolicy::Permissionless,
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount: 0,
+            destination_locked: false,
+            unlock_cursor: 0,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            splits: vec![],
+            emission_tracked: false,
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: 0,
+            schedule_layout: ScheduleLayout::Categorized,
+            state: ContractState::Active,
+        };
+        let schedules = vec![
+            VestingSchedule {
+                release_time: 1,
+                amount: 333,
+            },
+            VestingSchedule {
+                release_time: 2,
+                amount: 111,
+            },
+        ];
+        let categories = vec![ScheduleCategory::Salary, ScheduleCategory::Investor];
+        let mut data =
+            vec![0_u8; VestingScheduleHeader::account_len(2, ScheduleLayout::Categorized)];
+        assert_eq!(
+            data.len(),
+            VestingScheduleHeader::LEN + 2 * VestingSchedule::LEN + 2
+        );
+        header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(schedules, &mut data[VestingScheduleHeader::LEN..]);
+        pack_categories_into_slice(&categories, &mut data[header.schedules_end()..]);
+
+        match ProgramAccount::unpack_any(&data).unwrap() {
+            ProgramAccount::Vesting {
+                schedules,
+                categories: unpacked,
+                ..
+            } => {
+                // the category table isn't mistaken for another schedule
+                assert_eq!(schedules.len(), 2);
+                assert_eq!(unpacked, categories);
+            }
+            other => panic!("unexpected account {:?}", other),
+        }
+
+        // a plain account of the same size is padded, and the table has to hold known categories
+        let plain = VestingScheduleHeader {
+            schedule_layout: ScheduleLayout::Plain,
+            ..header.clone()
+        };
+        plain.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        assert!(ProgramAccount::unpack_any(&data).is_err());
+        header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        let end = data.len();
+        data[end - 1] = 42;
+        assert_eq!(
+            ProgramAccount::unpack_any(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
@@ -0,0 +1,1213 @@
+//! Synthetic account sets for unit testing the processor without spinning up a ProgramTest bank.
+//! Outside the runtime, CPIs (invoke / invoke_signed) are no-ops that return Ok, so handlers run right up to
+//! the state update and only the effects on our own accounts can be asserted on.
+
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::{
+        self,
+        instructions::{construct_instructions_data, store_current_index, BorrowedInstruction},
+    },
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account, AccountState, Mint};
+use spl_token_2022::extension::{
+    transfer_fee::TransferFeeConfig, AccountType, ExtensionType, StateWithExtensionsMut,
+};
+
+use crate::{
+    compression::{spl_account_compression, spl_noop},
+    instruction::{
+        claim_permit_message, ed25519_verify, find_destination_approvers, find_destination_index,
+        find_emission_curve, find_migration_authority, unlock_with_permit, CreateOptions, Schedule,
+        Seeds, VestingInstruction,
+    },
+    pda::vesting_address,
+    processor::Processor,
+    state::{
+        next_release_time, pack_schedules_into_slice, CompressedVestingTree, ContractState,
+        DestinationApprovers, DestinationIndex, DustPolicy, EmissionCurve, LinearVesting,
+        RentPayer, RevokePolicy, ScheduleLayout, SplitDestination, UnlockPolicy, VestingSchedule,
+        VestingScheduleHeader, BASELINE_HEADER_LEN, NO_PENDING_RELEASE,
+    },
+};
+
+// ----------------------------------------------------------------------------- accounts
+
+/// Owns everything an AccountInfo borrows, so a whole set can be turned into AccountInfos at once
+#[derive(Clone, Debug)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self {
+            key,
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            is_signer: false,
+            is_writable: false,
+            executable: false,
+        }
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn writable(mut self) -> Self {
+        self.is_writable = true;
+        self
+    }
+
+    /// A plain system account, eg a wallet
+    pub fn wallet(key: Pubkey) -> Self {
+        Self::new(key, solana_program::system_program::id(), vec![])
+    }
+
+    pub fn program(key: Pubkey) -> Self {
+        let mut account = Self::new(key, bpf_loader::id(), vec![]);
+        account.executable = true;
+        account
+    }
+
+    pub fn clock(unix_timestamp: i64) -> Self {
+        let clock = Clock {
+            unix_timestamp,
+            ..Clock::default()
+        };
+        Self::new(
+            sysvar::clock::id(),
+            sysvar::id(),
+            bincode::serialize(&clock).unwrap(),
+        )
+    }
+
+    pub fn rent() -> Self {
+        Self::new(
+            sysvar::rent::id(),
+            sysvar::id(),
+            bincode::serialize(&Rent::default()).unwrap(),
+        )
+    }
+
+    /// The instructions sysvar of a transaction made of `instructions`, currently executing `current_index`
+    pub fn instructions(instructions: &[Instruction], current_index: u16) -> Self {
+        let borrowed = instructions
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: vec![],
+                data: &ix.data,
+            })
+            .collect::<Vec<_>>();
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, current_index);
+        Self::new(sysvar::instructions::id(), sysvar::id(), data)
+    }
+
+    pub fn token_account(key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        let account = Account {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Account::default()
+        };
+        let mut data = vec![0; Account::LEN];
+        Account::pack(account, &mut data).unwrap();
+        Self::new(key, spl_token::id(), data).writable()
+    }
+
+    pub fn mint(key: Pubkey, mint_authority: Pubkey, supply: u64) -> Self {
+        let mint = Mint {
+            mint_authority: COption::Some(mint_authority),
+            supply,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(mint, &mut data).unwrap();
+        Self::new(key, spl_token::id(), data).writable()
+    }
+
+    /// A Token-2022 mint charging `transfer_fee_basis_points` of every transfer, up to `maximum_fee`
+    pub fn fee_mint(key: Pubkey, transfer_fee_basis_points: u16, maximum_fee: u64) -> Self {
+        let mut data = vec![
+            0;
+            ExtensionType::get_account_len::<spl_token_2022::state::Mint>(&[
+                ExtensionType::TransferFeeConfig
+            ])
+        ];
+        let mut mint =
+            StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+                .unwrap();
+        mint.base.is_initialized = true;
+        mint.base.decimals = 6;
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        let transfer_fee_config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+        transfer_fee_config
+            .newer_transfer_fee
+            .transfer_fee_basis_points = transfer_fee_basis_points.into();
+        transfer_fee_config.newer_transfer_fee.maximum_fee = maximum_fee.into();
+        Self::new(key, spl_token_2022::id(), data)
+    }
+
+    /// Unpacks, edits and repacks a token account created with token_account()
+    pub fn with_token_state(mut self, edit: impl FnOnce(&mut Account)) -> Self {
+        let mut account = Account::unpack(&self.data[..Account::LEN]).unwrap();
+        edit(&mut account);
+        Account::pack(account, &mut self.data[..Account::LEN]).unwrap();
+        self
+    }
+
+    /// Moves a token account created with token_account() over to Token-2022, carrying `extensions` - all zeroes
+    pub fn with_token_extensions(mut self, extensions: &[ExtensionType]) -> Self {
+        self.owner = spl_token_2022::id();
+        // without extensions the layout is spl-token's
+        if extensions.is_empty() {
+            return self;
+        }
+        self.data.resize(
+            ExtensionType::get_account_len::<spl_token_2022::state::Account>(extensions),
+            0,
+        );
+        self.data[Account::LEN] = AccountType::Account.into();
+        let mut tlv_start = Account::LEN + 1;
+        for extension in extensions {
+            let len = extension.get_type_len();
+            self.data[tlv_start..tlv_start + 2].copy_from_slice(&<[u8; 2]>::from(*extension));
+            self.data[tlv_start + 2..tlv_start + 4].copy_from_slice(&(len as u16).to_le_bytes());
+            tlv_start += 4 + len;
+        }
+        self
+    }
+
+    pub fn account_info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+pub fn account_infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+    accounts.iter_mut().map(|a| a.account_info()).collect()
+}
+
+/// Runs `instruction` through Processor::process_instruction the way the runtime would: the accounts are picked out
+/// of `accounts` by key, in the order of its AccountMetas and with their signer and writable flags. Panics if the
+/// program changed the data, lamports or owner of one passed read-only - the runtime fails the transaction for that,
+/// so a builder leaving out the writable flag of an account its instruction writes to shows up here rather than on
+/// a cluster. Returns the accounts as the program left them, in the order of the AccountMetas
+pub fn process_checked(
+    instruction: &Instruction,
+    accounts: &[TestAccount],
+) -> Result<Vec<TestAccount>, ProgramError> {
+    // a key passed more than once is a single account to the runtime, writable if any of its metas says so
+    let flag = |key: &Pubkey, f: fn(&AccountMeta) -> bool| {
+        instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == *key && f(meta))
+    };
+    let mut metas_accounts: Vec<TestAccount> = instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            let mut account = accounts
+                .iter()
+                .find(|a| a.key == meta.pubkey)
+                .unwrap_or_else(|| panic!("no account for {}", meta.pubkey))
+                .clone();
+            account.is_signer = flag(&meta.pubkey, |m| m.is_signer);
+            account.is_writable = flag(&meta.pubkey, |m| m.is_writable);
+            account
+        })
+        .collect();
+    let before = metas_accounts.clone();
+
+    let result = Processor::process_instruction(
+        &instruction.program_id,
+        &account_infos(&mut metas_accounts),
+        &instruction.data,
+    );
+
+    for (before, after) in before.iter().zip(&metas_accounts) {
+        if !before.is_writable {
+            assert!(
+                before.data == after.data
+                    && before.lamports == after.lamports
+                    && before.owner == after.owner,
+                "instruction {} changed {}, which its builder passes read-only",
+                instruction.data[0],
+                before.key
+            );
+        }
+    }
+    result.map(|()| metas_accounts)
+}
+
+// ----------------------------------------------------------------------------- vesting contract
+
+/// Keys of a single vesting contract, plus builders for the account sets each instruction expects
+pub struct VestingFixture {
+    pub program_id: Pubkey,
+    pub seeds: Seeds,
+    pub vesting_account_key: Pubkey,
+    pub vesting_token_account_key: Pubkey,
+    pub mint: Pubkey,
+    pub granter: Pubkey,
+    pub source_token_account_key: Pubkey,
+    pub destination_owner: Pubkey,
+    pub destination_token_account_key: Pubkey,
+}
+
+impl Default for VestingFixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VestingFixture {
+    pub fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        // same as the integration tests - 31 bytes of seed plus the bump
+        let (vesting_account_key, seeds) = vesting_address(&[7; 31], &program_id);
+
+        Self {
+            program_id,
+            seeds,
+            vesting_account_key,
+            vesting_token_account_key: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            granter: Pubkey::new_unique(),
+            source_token_account_key: Pubkey::new_unique(),
+            destination_owner: Pubkey::new_unique(),
+            destination_token_account_key: Pubkey::new_unique(),
+        }
+    }
+
+    pub fn header(&self, schedules: &[VestingSchedule]) -> VestingScheduleHeader {
+        let mut header = VestingScheduleHeader {
+            destination_address: self.destination_token_account_key,
+            mint_address: self.mint,
+            granter_address: self.granter,
+            next_release_time: next_release_time(schedules),
+            gas_rebate: 0,
+            gas_escrow: 0,
+            schedule_count: schedules.len() as u32,
+            unlock_policy: UnlockPolicy::Permissionless,
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount: 0,
+            destination_locked: false,
+            unlock_cursor: 0,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            splits: vec![],
+            emission_tracked: false,
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: 0,
+            schedule_layout: ScheduleLayout::Plain,
+            state: ContractState::Active,
+        };
+        header.settle_state();
+        header
+    }
+
+    /// An initialized vesting account, as it looks after Create
+    pub fn vesting_account(&self, schedules: Vec<VestingSchedule>) -> TestAccount {
+        let mut data = vec![0; VestingScheduleHeader::LEN + schedules.len() * VestingSchedule::LEN];
+        self.header(&schedules)
+            .pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        pack_schedules_into_slice(schedules, &mut data[VestingScheduleHeader::LEN..]);
+        TestAccount::new(self.vesting_account_key, self.program_id, data).writable()
+    }
+
+    /// A contract as the baseline program packed it: destination, mint and is_initialized, then the schedules - no
+    /// magic, type or version
+    pub fn baseline_vesting_account(&self, schedules: Vec<VestingSchedule>) -> TestAccount {
+        let mut data =
+            Vec::with_capacity(BASELINE_HEADER_LEN + schedules.len() * VestingSchedule::LEN);
+        data.extend_from_slice(self.destination_token_account_key.as_ref());
+        data.extend_from_slice(self.mint.as_ref());
+        data.push(1);
+        data.resize(data.capacity(), 0);
+        pack_schedules_into_slice(schedules, &mut data[BASELINE_HEADER_LEN..]);
+        TestAccount::new(self.vesting_account_key, self.program_id, data).writable()
+    }
+
+    /// An initialized linear vesting account, with `schedule.amount` of `linear.total_amount` left to claim
+    pub fn linear_vesting_account(
+        &self,
+        schedule: VestingSchedule,
+        linear: LinearVesting,
+    ) -> TestAccount {
+        let mut header = self.header(std::slice::from_ref(&schedule));
+        header.schedule_layout = ScheduleLayout::Linear;
+        let mut data = vec![0; VestingScheduleHeader::account_len(1, ScheduleLayout::Linear)];
+        header.pack_into_slice(&mut data[..VestingScheduleHeader::LEN]);
+        schedule.pack_into_slice(&mut data[VestingScheduleHeader::LEN..]);
+        linear.pack_into_slice(&mut data[header.schedules_end()..]);
+        TestAccount::new(self.vesting_account_key, self.program_id, data).writable()
+    }
+
+    /// A vesting account straight out of Init - all zeroes
+    pub fn empty_vesting_account(&self, number_of_schedules: usize) -> TestAccount {
+        let data = vec![0; VestingScheduleHeader::LEN + number_of_schedules * VestingSchedule::LEN];
+        TestAccount::new(self.vesting_account_key, self.program_id, data).writable()
+    }
+
+    pub fn vesting_token_account(&self, amount: u64) -> TestAccount {
+        TestAccount::token_account(
+            self.vesting_token_account_key,
+            self.mint,
+            self.vesting_account_key,
+            amount,
+        )
+    }
+
+    pub fn destination_token_account(&self) -> TestAccount {
+        TestAccount::token_account(
+            self.destination_token_account_key,
+            self.mint,
+            self.destination_owner,
+            0,
+        )
+    }
+
+    /// Accounts in the order Create expects them
+    pub fn create_accounts(&self, source_amount: u64) -> Vec<TestAccount> {
+        vec![
+            TestAccount::program(spl_token::id()),
+            self.empty_vesting_account(1),
+            self.vesting_token_account(0),
+            TestAccount::wallet(self.granter).signer(),
+            TestAccount::token_account(
+                self.source_token_account_key,
+                self.mint,
+                self.granter,
+                source_amount,
+            ),
+            TestAccount::mint(self.mint, Pubkey::new_unique(), 0),
+            self.destination_token_account(),
+        ]
+    }
+
+    /// Accounts in the order CreateFromMint expects them, the granter being the mint authority
+    pub fn create_from_mint_accounts(&self, supply: u64) -> Vec<TestAccount> {
+        vec![
+            TestAccount::program(spl_token::id()),
+            self.empty_vesting_account(1),
+            self.vesting_token_account(0),
+            TestAccount::wallet(self.granter).signer(),
+            TestAccount::mint(self.mint, self.granter, supply),
+            self.destination_token_account(),
+        ]
+    }
+
+    /// Accounts in the order Unlock expects them
+    pub fn unlock_accounts(
+        &self,
+        unix_timestamp: i64,
+        schedules: Vec<VestingSchedule>,
+    ) -> Vec<TestAccount> {
+        let total = schedules.iter().map(|s| s.amount).sum();
+        vec![
+            TestAccount::program(spl_token::id()),
+            TestAccount::clock(unix_timestamp),
+            self.vesting_account(schedules),
+            self.vesting_token_account(total),
+            self.destination_token_account(),
+        ]
+    }
+
+    /// Accounts in the order Revoke expects them, the granter getting the tokens back in its source token account
+    pub fn revoke_accounts(
+        &self,
+        unix_timestamp: i64,
+        schedules: Vec<VestingSchedule>,
+    ) -> Vec<TestAccount> {
+        let total = schedules.iter().map(|s| s.amount).sum();
+        vec![
+            TestAccount::program(spl_token::id()),
+            TestAccount::clock(unix_timestamp),
+            TestAccount::wallet(self.granter).signer(),
+            self.vesting_account(schedules),
+            self.vesting_token_account(total),
+            TestAccount::token_account(self.source_token_account_key, self.mint, self.granter, 0),
+        ]
+    }
+
+    /// Accounts in the order ChangeDestination expects them
+    pub fn change_destination_accounts(&self, schedules: Vec<VestingSchedule>) -> Vec<TestAccount> {
+        vec![
+            self.vesting_account(schedules),
+            self.destination_token_account(),
+            TestAccount::wallet(self.destination_owner).signer(),
+            TestAccount::token_account(Pubkey::new_unique(), self.mint, Pubkey::new_unique(), 0),
+        ]
+    }
+
+    /// Accounts in the order TopUp expects them, the granter holding `source_amount` to add
+    pub fn top_up_accounts(
+        &self,
+        schedules: Vec<VestingSchedule>,
+        source_amount: u64,
+    ) -> Vec<TestAccount> {
+        let total = schedules.iter().map(|s| s.amount).sum();
+        vec![
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(solana_program::system_program::id()),
+            self.vesting_account(schedules),
+            self.vesting_token_account(total),
+            TestAccount::wallet(self.granter).signer().writable(),
+            TestAccount::token_account(
+                self.source_token_account_key,
+                self.mint,
+                self.granter,
+                source_amount,
+            ),
+        ]
+    }
+
+    /// Accounts in the order TransferBeneficiary expects them, without the indexes. The new owner's associated token
+    /// account already exists, as creating it is a CPI
+    pub fn transfer_beneficiary_accounts(
+        &self,
+        schedules: Vec<VestingSchedule>,
+        new_owner: Pubkey,
+    ) -> Vec<TestAccount> {
+        vec![
+            self.vesting_account(schedules),
+            self.destination_token_account(),
+            TestAccount::wallet(self.destination_owner)
+                .signer()
+                .writable(),
+            TestAccount::token_account(
+                get_associated_token_address(&new_owner, &self.mint),
+                self.mint,
+                new_owner,
+                0,
+            ),
+            TestAccount::wallet(new_owner),
+            TestAccount::mint(self.mint, self.granter, 0),
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(spl_associated_token_account::id()),
+        ]
+    }
+
+    /// Another contract of the same granter, destination and mint, to merge into this one
+    pub fn merged(&self) -> Self {
+        let (vesting_account_key, seeds) = vesting_address(&[8; 31], &self.program_id);
+        Self {
+            seeds,
+            vesting_account_key,
+            vesting_token_account_key: Pubkey::new_unique(),
+            ..*self
+        }
+    }
+
+    /// Accounts in the order Merge expects them, `merged` being folded into this contract
+    pub fn merge_accounts(
+        &self,
+        merged: &VestingFixture,
+        schedules: Vec<VestingSchedule>,
+        merged_schedules: Vec<VestingSchedule>,
+    ) -> Vec<TestAccount> {
+        let total = schedules.iter().map(|s| s.amount).sum();
+        let merged_total = merged_schedules.iter().map(|s| s.amount).sum();
+        vec![
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(solana_program::system_program::id()),
+            self.vesting_account(schedules),
+            self.vesting_token_account(total),
+            merged.vesting_account(merged_schedules),
+            merged.vesting_token_account(merged_total),
+            TestAccount::wallet(self.granter).signer().writable(),
+            self.destination_token_account(),
+            TestAccount::wallet(self.destination_owner).signer(),
+        ]
+    }
+
+    /// Unlock accounts for a contract paying a 5000 lamport rebate, with a relayer appended
+    pub fn unlock_accounts_with_rebate(&self, gas_escrow: u64) -> Vec<TestAccount> {
+        let schedules = vec![vesting_schedule(10, 100)];
+        let mut header = self.header(&schedules);
+        header.gas_rebate = 5000;
+        header.gas_escrow = gas_escrow;
+
+        let mut accounts = self.unlock_accounts(15, schedules);
+        header.pack_into_slice(&mut accounts[2].data[..VestingScheduleHeader::LEN]);
+        accounts[2].lamports += gas_escrow;
+        accounts.push(
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+        );
+        accounts
+    }
+
+    /// Unlock accounts for a contract with permissionless unlock turned off
+    pub fn permissioned_unlock_accounts(&self) -> Vec<TestAccount> {
+        let schedules = vec![vesting_schedule(10, 100)];
+        let mut header = self.header(&schedules);
+        header.unlock_policy = UnlockPolicy::DestinationOwnerOnly;
+
+        let mut accounts = self.unlock_accounts(15, schedules);
+        header.pack_into_slice(&mut accounts[2].data[..VestingScheduleHeader::LEN]);
+        accounts
+    }
+
+    /// Same as permissioned_unlock_accounts, with the instructions sysvar of a transaction where `signer` signed
+    /// a permit expiring at 20 just before UnlockWithPermit
+    pub fn permit_unlock_accounts(&self, signer: &Pubkey, nonce: u64) -> Vec<TestAccount> {
+        let message = claim_permit_message(&self.program_id, &self.vesting_account_key, 20, nonce);
+        let verify_ix = ed25519_verify(signer, &[0; 64], &message);
+        let unlock_ix = unlock_with_permit(
+            &self.program_id,
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &sysvar::instructions::id(),
+            &self.vesting_account_key,
+            &self.vesting_token_account_key,
+            &self.destination_token_account_key,
+            None,
+            20,
+            self.seeds,
+        )
+        .unwrap();
+
+        let mut accounts = self.permissioned_unlock_accounts();
+        accounts.push(TestAccount::instructions(&[verify_ix, unlock_ix], 1));
+        accounts
+    }
+
+    pub fn set_rollover_accounts(&self, schedules: Vec<VestingSchedule>) -> Vec<TestAccount> {
+        vec![
+            self.vesting_account(schedules),
+            TestAccount::wallet(self.granter).signer(),
+            self.destination_token_account(),
+            TestAccount::wallet(self.destination_owner).signer(),
+        ]
+    }
+
+    /// Accounts in the order EmergencyWithdraw expects them, for a contract holding `vesting_data`
+    pub fn emergency_withdraw_accounts(
+        &self,
+        vesting_data: Vec<u8>,
+        emergency_authority: Pubkey,
+    ) -> Vec<TestAccount> {
+        vec![
+            TestAccount::program(spl_token::id()),
+            TestAccount::new(self.vesting_account_key, self.program_id, vesting_data).writable(),
+            self.vesting_token_account(150),
+            TestAccount::wallet(emergency_authority).signer(),
+            TestAccount::token_account(Pubkey::new_unique(), self.mint, emergency_authority, 0),
+        ]
+    }
+
+    pub fn set_withholding_accounts(
+        &self,
+        schedules: Vec<VestingSchedule>,
+        withholding_token_account_key: Pubkey,
+    ) -> Vec<TestAccount> {
+        vec![
+            self.vesting_account(schedules),
+            TestAccount::wallet(self.granter).signer(),
+            self.destination_token_account(),
+            TestAccount::wallet(self.destination_owner).signer(),
+            TestAccount::token_account(
+                withholding_token_account_key,
+                self.mint,
+                Pubkey::new_unique(),
+                0,
+            ),
+        ]
+    }
+
+    /// The accounts of SetSplits, with a token account of the contract's mint for every split
+    pub fn set_splits_accounts(
+        &self,
+        schedules: Vec<VestingSchedule>,
+        splits: &[SplitDestination],
+    ) -> Vec<TestAccount> {
+        let mut accounts = self.set_rollover_accounts(schedules);
+        accounts.extend(splits.iter().map(|s| {
+            TestAccount::token_account(s.destination_address, self.mint, Pubkey::new_unique(), 0)
+        }));
+        accounts
+    }
+
+    /// The fixture mint's emission curve, starting with week 0 and holding `weekly_amounts` from there on
+    pub fn emission_curve(&self, weekly_amounts: &[u64]) -> TestAccount {
+        let (curve_key, _) = find_emission_curve(&self.program_id, &self.mint);
+        let mut curve = EmissionCurve::new(self.mint, 0);
+        curve.weekly_amounts[..weekly_amounts.len()].copy_from_slice(weekly_amounts);
+        let mut data = vec![0; EmissionCurve::LEN];
+        curve.pack_into_slice(&mut data);
+        TestAccount::new(curve_key, self.program_id, data).writable()
+    }
+
+    pub fn close_accounts(
+        &self,
+        schedules: Vec<VestingSchedule>,
+        dust_receiver: TestAccount,
+    ) -> Vec<TestAccount> {
+        vec![
+            TestAccount::program(spl_token::id()),
+            self.vesting_account(schedules),
+            self.vesting_token_account(3),
+            TestAccount::wallet(self.granter).signer().writable(),
+            dust_receiver.writable(),
+        ]
+    }
+
+    pub fn granter_token_account(&self) -> TestAccount {
+        TestAccount::token_account(Pubkey::new_unique(), self.mint, self.granter, 0)
+    }
+
+    /// InitDestinationApprovers accounts, with the approvers PDA already owned by the program since CPIs are no-ops
+    pub fn init_approvers_accounts(&self) -> Vec<TestAccount> {
+        let (approvers_key, _) =
+            find_destination_approvers(&self.program_id, &self.vesting_account_key);
+        vec![
+            TestAccount::program(solana_program::system_program::id()),
+            TestAccount::wallet(Pubkey::new_unique())
+                .signer()
+                .writable(),
+            self.vesting_account(vec![vesting_schedule(10, 100)]),
+            TestAccount::new(
+                approvers_key,
+                self.program_id,
+                vec![0; DestinationApprovers::LEN],
+            )
+            .writable(),
+            TestAccount::wallet(self.granter).signer(),
+            self.destination_token_account(),
+            TestAccount::wallet(self.destination_owner).signer(),
+        ]
+    }
+
+    /// A destination index that already exists, pointing at `vesting_account`. CPIs are no-ops here, so the
+    /// create_account path can't be exercised
+    pub fn destination_index_account(
+        &self,
+        destination: &Pubkey,
+        vesting_account: Pubkey,
+    ) -> TestAccount {
+        let (index_key, _) = find_destination_index(&self.program_id, &self.mint, destination);
+        let mut data = vec![0; DestinationIndex::LEN];
+        DestinationIndex {
+            vesting_account,
+            is_initialized: true,
+        }
+        .pack_into_slice(&mut data);
+        TestAccount::new(index_key, self.program_id, data).writable()
+    }
+
+    /// ChangeDestination accounts with both indexes, the current one pointing at the contract
+    pub fn change_destination_accounts_with_indexes(
+        &self,
+        schedules: Vec<VestingSchedule>,
+    ) -> Vec<TestAccount> {
+        let mut accounts = self.change_destination_accounts(schedules);
+        let new_destination = accounts[3].key;
+        accounts.extend(vec![
+            TestAccount::program(solana_program::system_program::id()),
+            self.destination_index_account(
+                &self.destination_token_account_key,
+                self.vesting_account_key,
+            ),
+            self.destination_index_account(&new_destination, Pubkey::default()),
+        ]);
+        accounts
+    }
+
+    pub fn export_accounts(&self, new_program_id: &Pubkey) -> Vec<TestAccount> {
+        let (migration_authority, _) =
+            find_migration_authority(new_program_id, &self.vesting_account_key);
+        vec![
+            TestAccount::program(spl_token::id()),
+            self.vesting_account(vec![vesting_schedule(10, 0), vesting_schedule(20, 50)]),
+            self.vesting_token_account(50),
+            TestAccount::token_account(Pubkey::new_unique(), self.mint, migration_authority, 0),
+            TestAccount::wallet(self.granter).signer(),
+            self.destination_token_account(),
+            TestAccount::wallet(self.destination_owner).signer(),
+        ]
+    }
+
+    /// A compressed contract of `tree`'s leaves, with the accounts ClaimCompressed takes in front of the proof and
+    /// then every node of the tree, so that any proof can be passed. The tree account itself stays zeroed, since
+    /// spl-account-compression isn't there to check proofs outside the runtime
+    pub fn compressed_accounts(
+        &self,
+        tree: &crate::compression::MerkleTree,
+        leaf_count: u32,
+    ) -> (CompressedVestingTree, Vec<TestAccount>) {
+        let state = CompressedVestingTree {
+            mint_address: self.mint,
+            merkle_tree: Pubkey::new_unique(),
+            granter: self.granter,
+            max_depth: tree.depth(),
+            leaf_count,
+            is_initialized: true,
+        };
+        let mut data = vec![0_u8; CompressedVestingTree::LEN];
+        state.pack_into_slice(&mut data);
+
+        let account_compression_program_id = spl_account_compression::id();
+        let tree_size = crate::compression::merkle_tree_account_size(
+            tree.depth(),
+            tree.max_buffer_size().unwrap(),
+        );
+        let mut accounts = vec![
+            TestAccount::program(spl_token::id()),
+            TestAccount::clock(15),
+            TestAccount::new(self.vesting_account_key, self.program_id, data),
+            self.vesting_token_account(400),
+            self.destination_token_account(),
+            TestAccount::new(
+                state.merkle_tree,
+                account_compression_program_id,
+                vec![0; tree_size],
+            ),
+            TestAccount::program(account_compression_program_id),
+            TestAccount::program(spl_noop::id()),
+            TestAccount::wallet(self.granter),
+        ];
+        for index in 0..1 << tree.depth() {
+            accounts.extend(
+                tree.proof_accounts(index)
+                    .into_iter()
+                    .map(TestAccount::wallet),
+            );
+        }
+        (state, accounts)
+    }
+}
+
+// ----------------------------------------------------------------------------- instructions
+
+/// Runs each instruction for the fixture's contract, with the options most tests want
+impl VestingFixture {
+    pub fn create(&self, accounts: &mut [TestAccount], schedules: Vec<Schedule>) -> ProgramResult {
+        Processor::process_create(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            &self.mint,
+            &self.destination_token_account_key,
+            schedules,
+            None,
+            CreateOptions {
+                allow_immediate: true,
+                ..CreateOptions::default()
+            },
+        )
+    }
+
+    pub fn unlock(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        Processor::process_unlock(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            None,
+            0,
+            0,
+            None,
+        )
+    }
+
+    pub fn unlock_partial(&self, accounts: &mut [TestAccount], max_amount: u64) -> ProgramResult {
+        Processor::process_unlock(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            None,
+            0,
+            max_amount,
+            None,
+        )
+    }
+
+    pub fn change_destination(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        Processor::process_change_destination(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            None,
+            None,
+        )
+    }
+
+    pub fn create_from_mint(&self, accounts: &mut [TestAccount], max_supply: u64) -> ProgramResult {
+        let ix = VestingInstruction::CreateFromMint {
+            seeds: self.seeds,
+            token_mint_addr: self.mint,
+            token_dest_addr: self.destination_token_account_key,
+            allow_immediate: true,
+            max_supply,
+            schedules: vec![schedule(10, 100)],
+        };
+        Processor::process_instruction(&self.program_id, &account_infos(accounts), &ix.pack())
+    }
+
+    pub fn permit_unlock(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        Processor::process_unlock(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            Some(20),
+            0,
+            0,
+            None,
+        )
+    }
+
+    pub fn set_rollover(&self, accounts: &mut [TestAccount], enabled: bool) -> ProgramResult {
+        Processor::process_set_rollover(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            enabled,
+        )
+    }
+
+    pub fn unlock_bounded(
+        &self,
+        accounts: &mut [TestAccount],
+        max_schedules_per_call: u32,
+    ) -> ProgramResult {
+        self.unlock_range(accounts, None, max_schedules_per_call)
+    }
+
+    pub fn unlock_range(
+        &self,
+        accounts: &mut [TestAccount],
+        start_index: Option<u32>,
+        max_schedules_per_call: u32,
+    ) -> ProgramResult {
+        Processor::process_unlock(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            None,
+            max_schedules_per_call,
+            0,
+            start_index,
+        )
+    }
+
+    pub fn revoke(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        Processor::process_revoke(&self.program_id, &account_infos(accounts), self.seeds, None)
+    }
+
+    pub fn create_linear(
+        &self,
+        accounts: &mut [TestAccount],
+        linear: LinearVesting,
+    ) -> ProgramResult {
+        Processor::process_create(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            &self.mint,
+            &self.destination_token_account_key,
+            vec![],
+            Some(linear),
+            CreateOptions {
+                allow_immediate: true,
+                ..CreateOptions::default()
+            },
+        )
+    }
+
+    pub fn accelerate(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        Processor::process_accelerate(&self.program_id, &account_infos(accounts), self.seeds)
+    }
+
+    pub fn change_granter(
+        &self,
+        accounts: &mut [TestAccount],
+        new_granter: &Pubkey,
+    ) -> ProgramResult {
+        Processor::process_change_granter(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            new_granter,
+        )
+    }
+
+    pub fn check_revision(
+        &self,
+        accounts: &mut [TestAccount],
+        expected_revision: u32,
+    ) -> ProgramResult {
+        Processor::process_check_revision(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            expected_revision,
+        )
+    }
+
+    pub fn create_relative(
+        &self,
+        accounts: &mut [TestAccount],
+        schedules: Vec<Schedule>,
+    ) -> ProgramResult {
+        Processor::process_create(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            &self.mint,
+            &self.destination_token_account_key,
+            schedules,
+            None,
+            CreateOptions {
+                relative: true,
+                ..CreateOptions::default()
+            },
+        )
+    }
+
+    pub fn activate(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        Processor::process_activate(&self.program_id, &account_infos(accounts), self.seeds)
+    }
+
+    pub fn set_paused(&self, accounts: &mut [TestAccount], paused: bool) -> ProgramResult {
+        Processor::process_set_paused(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            paused,
+        )
+    }
+
+    pub fn create_with_emergency_authority(
+        &self,
+        accounts: &mut [TestAccount],
+        schedules: Vec<Schedule>,
+        emergency_authority: &Pubkey,
+    ) -> ProgramResult {
+        Processor::process_create(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            &self.mint,
+            &self.destination_token_account_key,
+            schedules,
+            None,
+            CreateOptions {
+                allow_immediate: true,
+                emergency_authority: *emergency_authority,
+                ..CreateOptions::default()
+            },
+        )
+    }
+
+    pub fn emergency_withdraw(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        Processor::process_emergency_withdraw(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+        )
+    }
+
+    #[cfg(feature = "devnet-tools")]
+    pub fn debug_shift_schedules(
+        &self,
+        accounts: &mut [TestAccount],
+        offset: u64,
+    ) -> ProgramResult {
+        Processor::process_debug_shift_schedules(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            offset,
+        )
+    }
+
+    pub fn set_transfer_limit(
+        &self,
+        accounts: &mut [TestAccount],
+        max_transfer_per_unlock: u64,
+        unlock_interval: u32,
+    ) -> ProgramResult {
+        Processor::process_set_transfer_limit(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            max_transfer_per_unlock,
+            unlock_interval,
+        )
+    }
+
+    pub fn set_withholding(
+        &self,
+        accounts: &mut [TestAccount],
+        withholding_bps: u16,
+    ) -> ProgramResult {
+        Processor::process_set_withholding(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            withholding_bps,
+        )
+    }
+
+    pub fn set_splits(
+        &self,
+        accounts: &mut [TestAccount],
+        splits: Vec<SplitDestination>,
+    ) -> ProgramResult {
+        Processor::process_set_splits(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            splits,
+        )
+    }
+
+    pub fn close(&self, accounts: &mut [TestAccount], dust_policy: DustPolicy) -> ProgramResult {
+        Processor::process_close(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            dust_policy,
+        )
+    }
+
+    pub fn change_future_destination(
+        &self,
+        accounts: &mut [TestAccount],
+        cutoff: u64,
+    ) -> ProgramResult {
+        Processor::process_change_destination(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            Some(cutoff),
+            None,
+        )
+    }
+
+    pub fn init_approvers(
+        &self,
+        accounts: &mut [TestAccount],
+        threshold: u8,
+        approvers: Vec<Pubkey>,
+    ) -> ProgramResult {
+        Processor::process_init_destination_approvers(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            threshold,
+            approvers,
+        )
+    }
+
+    pub fn approve(
+        &self,
+        accounts: &mut [TestAccount],
+        approver: Pubkey,
+        new_destination: &TestAccount,
+    ) -> ProgramResult {
+        let mut approve_accounts = vec![
+            accounts[2].clone(),
+            accounts[3].clone(),
+            TestAccount::wallet(approver).signer(),
+            new_destination.clone(),
+        ];
+        Processor::process_approve_destination_change(
+            &self.program_id,
+            &account_infos(&mut approve_accounts),
+            self.seeds,
+            &new_destination.key,
+        )?;
+        accounts[2] = approve_accounts[0].clone();
+        accounts[3] = approve_accounts[1].clone();
+        Ok(())
+    }
+
+    pub fn transfer_beneficiary(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        self.transfer_beneficiary_paid_by(accounts, RentPayer::Beneficiary)
+    }
+
+    pub fn transfer_beneficiary_paid_by(
+        &self,
+        accounts: &mut [TestAccount],
+        rent_payer: RentPayer,
+    ) -> ProgramResult {
+        Processor::process_transfer_beneficiary(
+            &self.program_id,
+            &account_infos(accounts),
+            self.seeds,
+            rent_payer,
+        )
+    }
+}
+
+/// Shorthand for the instruction-side schedule type
+pub fn schedule(release_time: u64, amount: u64) -> Schedule {
+    Schedule {
+        release_time,
+        amount,
+    }
+}
+
+/// Shorthand for the state-side schedule type
+pub fn vesting_schedule(release_time: u64, amount: u64) -> VestingSchedule {
+    VestingSchedule {
+        release_time,
+        amount,
+    }
+}
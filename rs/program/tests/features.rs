@@ -0,0 +1,90 @@
+//! Builds both crates once per feature combination and runs their unit tests, so that code behind a feature no default
+//! build turns on doesn't rot. Every combination is a build of its own, so this only runs with:
+//!
+//!     cargo test --package token-vesting-program --features feature-matrix --test features
+//!
+//! Every new feature gets a row in MATRIX, on its own and next to the features whose code it touches.
+#![cfg(feature = "feature-matrix")]
+
+use std::{path::Path, process::Command};
+
+const PROGRAM: &str = "token-vesting-program";
+const SDK: &str = "token-vesting-sdk";
+
+/// Package and feature combinations to build and test, on top of --no-default-features
+const MATRIX: &[(&str, &[&str])] = &[
+    (PROGRAM, &[]),
+    (PROGRAM, &["no-entrypoint"]),
+    (PROGRAM, &["debug-logs"]),
+    (PROGRAM, &["paranoid"]),
+    (PROGRAM, &["paranoid", "bonfida-compat"]),
+    (PROGRAM, &["bonfida-compat"]),
+    (PROGRAM, &["chrono"]),
+    (PROGRAM, &["fuzz"]),
+    (PROGRAM, &["fuzz", "bonfida-compat"]),
+    (PROGRAM, &["anchor"]),
+    (PROGRAM, &["anchor", "no-entrypoint"]),
+    (PROGRAM, &["test-utils"]),
+    (PROGRAM, &["core-shims"]),
+    (PROGRAM, &["devnet-tools"]),
+    (
+        PROGRAM,
+        &[
+            "no-entrypoint",
+            "debug-logs",
+            "paranoid",
+            "bonfida-compat",
+            "chrono",
+            "fuzz",
+            "anchor",
+            "test-utils",
+            "core-shims",
+            "devnet-tools",
+        ],
+    ),
+    (SDK, &[]),
+    (SDK, &["chrono"]),
+    (SDK, &["bonfida-compat"]),
+    (SDK, &["crank-store"]),
+    // what an off-chain consumer usually turns on
+    (SDK, &["chrono", "bonfida-compat"]),
+];
+
+#[test]
+fn test_feature_matrix() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    // a target dir of its own, so the nested builds don't wait on the lock held by the build running this test
+    let target_dir = Path::new(manifest_dir)
+        .join("..")
+        .join("target")
+        .join("feature-matrix");
+
+    let failed = MATRIX
+        .iter()
+        .map(|(package, features)| (*package, features.join(",")))
+        .filter(|(package, features)| {
+            println!("feature matrix: {} [{}]", package, features);
+            let status = Command::new(env!("CARGO"))
+                .current_dir(manifest_dir)
+                .env("CARGO_TARGET_DIR", &target_dir)
+                .args([
+                    "test",
+                    "--package",
+                    package,
+                    "--lib",
+                    "--no-default-features",
+                    "--features",
+                    features,
+                ])
+                .status()
+                .expect("failed to run cargo");
+            !status.success()
+        })
+        .map(|(package, features)| format!("{} [{}]", package, features))
+        .collect::<Vec<String>>();
+    assert!(
+        failed.is_empty(),
+        "failed feature combinations: {:?}",
+        failed
+    );
+}
@@ -0,0 +1,1701 @@
+// this prevents the test to be run outside of cargo test-bpf
+#![cfg(feature = "test-bpf")]
+
+use std::{borrow::Borrow, collections::HashMap, convert::TryInto};
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{create_account, transfer},
+    system_program,
+    sysvar::{self},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::solana_program::program_pack::Pack;
+use token_vesting_program::{
+    error::VestingError,
+    ids,
+    instruction::{
+        change_destination, create, create_from_mint, init, resize, revoke, revoke_batch,
+        set_permissionless_unlock, top_up, unlock, unlock_with_rebate, CreateOptions,
+        RevokeBatchEntry, Schedule, Seeds, VestingInstruction,
+    },
+    pda::vesting_address,
+    processor::Processor,
+    state::{
+        unpack_schedules, ContractState, RevokePolicy, ScheduleLayout, VestingSchedule,
+        VestingScheduleHeader, NO_PENDING_RELEASE,
+    },
+};
+
+const SEED: &str = "11111111yayayayayyayayayayyayayayayyayayayayyayayayay";
+// const TOKEN_MINT_ADDR: &str = "5e48G9KL813hkT9LRCHs6uGFdrhAihP8Jbk1QfScE78R";
+
+async fn setup_test_env() -> (BanksClient, Keypair, Hash, Pubkey) {
+    let program_id = ids::devnet();
+    let (mut banks_client, payer, recent_blockhash) = ProgramTest::new(
+        "token_vesting_program", //must match crate name or cargo test-bpf won't work
+        program_id,
+        processor!(Processor::process_instruction),
+    )
+    .start()
+    .await;
+
+    (banks_client, payer, recent_blockhash, program_id)
+}
+
+#[tokio::test]
+async fn test_empty_ix() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    // ----------------------------------------------------------------------------- 1a manual
+    // let z = vec![4_u8, 4, 0, 0, 0];
+    // let mut tx = Transaction::new_with_payer(
+    //     &[Instruction::new_with_bytes(program_id, &z2, vec![])],
+    //     Some(&payer.pubkey()),
+    // );
+
+    // ----------------------------------------------------------------------------- 1a semi-manual
+    let mut z = vec![4_u8];
+    let x = 32_u32.to_le_bytes();
+    z.extend(&x);
+    let mut tx = Transaction::new_with_payer(
+        &[Instruction::new_with_bytes(program_id, &z, vec![])],
+        Some(&payer.pubkey()),
+    );
+
+    // ----------------------------------------------------------------------------- 2 automatic - bincode
+    // requires deserialization with bincode on the other side
+
+    // let mut tx = Transaction::new_with_payer(
+    //     &[Instruction::new_with_bincode(
+    //         program_id,
+    //         &VestingInstruction::Empty { number: 5 },
+    //         vec![],
+    //     )],
+    //     Some(&payer.pubkey()),
+    // );
+
+    // ----------------------------------------------------------------------------- 3 automatic - borsh
+    // (!) requires deserialization with borsh on the other side
+
+    // let mut tx = Transaction::new_with_payer(
+    //     &[Instruction::new_with_borsh(
+    //         program_id,
+    //         &VestingInstruction::Empty { number: 5 },
+    //         vec![],
+    //     )],
+    //     Some(&payer.pubkey()),
+    // );
+
+    tx.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_init_create_unlock_flow() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    // ----------------------------------------------------------------------------- 1 call init
+
+    // LOL I packed the data here manually, but actually there wasn't any need for this - I could have just used pub fn init() from instruction.rs
+    let mut data = vec![0_u8];
+    let num_schedules = 1_u32.to_le_bytes();
+    data.extend(&*SEED[..32].as_bytes());
+    data.extend(&num_schedules);
+
+    let vesting_account_key =
+        Pubkey::create_program_address(&[&SEED[..32].as_bytes()], &program_id).unwrap();
+
+    let mut tx = Transaction::new_with_payer(
+        &[Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                //   0. `[]` The system program account
+                AccountMeta::new_readonly(system_program::id(), false),
+                //   1. `[]` The sysvar Rent account
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                //   1. `[signer]` The fee payer account
+                AccountMeta::new_readonly(payer.pubkey(), true),
+                //   1. `[writable]` The vesting account
+                AccountMeta::new(vesting_account_key, false),
+            ],
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    tx.sign(&[&payer], recent_blockhash);
+    //in a sense this .unwrap() is the first assert!()
+    //if there was any error while executing the contract, this would also throw an error
+    banks_client.process_transaction(tx).await.unwrap();
+
+    // ----------------------------------------------------------------------------- 2 interm step - create assoc token acc
+
+    // step 1 - we need to create a new token. We can't use existing because the payer, which is randomly derived in this test, needs to have the right to mint tokens
+    // 1.1 we'll need a new keypair
+    let mint_keypair = solana_sdk::signature::Keypair::new();
+
+    // 1.2 so that we can create a new account
+    let rent = banks_client.get_rent().await.unwrap();
+    let mint_rent = rent.minimum_balance(spl_token::state::Mint::LEN);
+    let create_account_ix = create_account(
+        &payer.pubkey(),
+        &mint_keypair.pubkey(),
+        mint_rent,
+        spl_token::state::Mint::LEN as u64,
+        &spl_token::id(), //we're making the spl_token the owner
+    );
+
+    // 1.3 which we will initialize as token mint account
+    let init_token_mint_acc_ix = spl_token::instruction::initialize_mint(
+        &spl_token::id(),
+        &mint_keypair.pubkey(),
+        &payer.pubkey(),
+        Some(&payer.pubkey()),
+        0,
+    )
+    .unwrap();
+
+    let mut create_token_tx = Transaction::new_signed_with_payer(
+        &[create_account_ix, init_token_mint_acc_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &mint_keypair], //&[&b"escrow"[..], &[bump_seed]]
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_token_tx)
+        .await
+        .unwrap();
+
+    // step 2 - create an associated token account
+    // this consists of 2 sub-steps:
+    // step 2.1: we find the associated address, because we're going to pass it in - https://docs.rs/spl-associated-token-account/1.0.2/spl_associated_token_account/fn.get_associated_token_address.html
+    // - note that the wallet address is the vesting_account, because we want the vesting_token_account to be owned by the vesting_account
+
+    let vesting_token_account_key = spl_associated_token_account::get_associated_token_address(
+        &vesting_account_key,
+        &mint_keypair.pubkey(),
+    );
+
+    // step 2.2: we issue a call to create that address to the associated token program - https://docs.rs/spl-associated-token-account/1.0.2/spl_associated_token_account/fn.create_associated_token_account.html
+    // - note that the program only has 1 instruction, which is why we don't need to send any data. we only need to pass in the right accounts
+    let accounts = vec![
+        //   pubkey: payer, isSigner, isWritable
+        AccountMeta::new(payer.pubkey(), true),
+        //   pubkey: vesting_token_account, isWritable
+        AccountMeta::new(vesting_token_account_key, false),
+        //   pubkey: vesting_account,
+        AccountMeta::new_readonly(vesting_account_key, false),
+        //   pubkey: splTokenMintAddress,
+        AccountMeta::new_readonly(mint_keypair.pubkey(), false),
+        //   pubkey: systemProgramId,
+        AccountMeta::new_readonly(system_program::id(), false),
+        //   pubkey: TOKEN_PROGRAM_ID,
+        AccountMeta::new_readonly(spl_token::id(), false),
+        //   pubkey: SYSVAR_RENT_PUBKEY,
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    let mut token_tx = Transaction::new_with_payer(
+        &[Instruction::new_with_bytes(
+            spl_associated_token_account::id(),
+            &[], //no data because this program only executes 1 instruction
+            accounts,
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    // (!) COULD HAVE JUST USED THE BELOW, BUT WOULD STILL NEED TO RUN "GET" AS WE NEED THE ADDR FURTHER
+    // let ix_to_create_assoc_acc = spl_associated_token_account::create_associated_token_account(
+    //     &payer.pubkey(),
+    //     &vesting_account_key,
+    //     &mint_keypair.pubkey(),
+    // );
+    //
+    // println!("ix is: {:?}", ix_to_create_assoc_acc);
+
+    // let mut token_tx =
+    //     Transaction::new_with_payer(&[ix_to_create_assoc_acc], Some(&payer.pubkey()));
+
+    token_tx.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(token_tx).await.unwrap();
+
+    // ----------------------------------------------------------------------------- 3 create source & mint some tokens
+
+    // create an associated token account from main payer's account
+    let create_source_token_acc_ix = spl_associated_token_account::create_associated_token_account(
+        &payer.pubkey(),
+        &payer.pubkey(),
+        &mint_keypair.pubkey(),
+    );
+
+    // get the key so that we can use it minter ix below
+    let source_token_acc_key = spl_associated_token_account::get_associated_token_address(
+        &payer.pubkey(),
+        &mint_keypair.pubkey(),
+    );
+
+    //note how this time we're using a helper function instead of manually building up the tx data
+    let mint_to_source_acc_ix = spl_token::instruction::mint_to(
+        &spl_token::id(),
+        &mint_keypair.pubkey(),
+        &source_token_acc_key,
+        &payer.pubkey(),
+        &[&payer.pubkey()],
+        1000,
+    )
+    .unwrap();
+
+    let mint_tx = Transaction::new_signed_with_payer(
+        &[create_source_token_acc_ix, mint_to_source_acc_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(mint_tx).await.unwrap();
+
+    // ----------------------------------------------------------------------------- 4 create dest & call create
+
+    let dest_keypair = solana_sdk::signature::Keypair::new();
+
+    // let create_dest_acc_ix = solana_program::system_instruction::create_account(
+    //     &dest_keypair
+    // )
+
+    let create_dest_token_acc_ix = spl_associated_token_account::create_associated_token_account(
+        &payer.pubkey(),
+        &dest_keypair.pubkey(),
+        &mint_keypair.pubkey(),
+    );
+
+    let dest_token_acc_key = spl_associated_token_account::get_associated_token_address(
+        &dest_keypair.pubkey(),
+        &mint_keypair.pubkey(),
+    );
+
+    let s = token_vesting_program::instruction::Schedule {
+        release_time: 1,
+        amount: 111,
+    };
+    let schedules = vec![s];
+
+    // try_into() instead of into() because forcing an arb-sized array into a fixed size might fail
+    // https://users.rust-lang.org/t/why-from-u8-is-not-implemented-for-u8-x/35590
+    let seeds: [u8; 32] = (&*SEED[..32].as_bytes()).try_into().unwrap();
+
+    let create_vesting_contract_ix = create(
+        &program_id,
+        &spl_token::id(),
+        &vesting_account_key,
+        &vesting_token_account_key,
+        &payer.pubkey(),
+        &source_token_acc_key,
+        &dest_token_acc_key,
+        &mint_keypair.pubkey(),
+        schedules,
+        CreateOptions {
+            allow_immediate: true,
+            ..CreateOptions::default()
+        },
+        seeds,
+    )
+    .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[create_dest_token_acc_ix, create_vesting_contract_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    let token_accounts = [source_token_acc_key, vesting_token_account_key];
+    let before = snapshot(&mut banks_client, &token_accounts).await;
+    banks_client.process_transaction(tx).await.unwrap();
+    let after = snapshot(&mut banks_client, &token_accounts).await;
+    assert_account_changes(
+        &before,
+        &after,
+        &[
+            Change::TokenBalance(source_token_acc_key, -111),
+            Change::TokenBalance(vesting_token_account_key, 111),
+        ],
+    );
+
+    // ----------------------------------------------------------------------------- 5 test unlock
+
+    let unlock_contract_ix = unlock(
+        &program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        &vesting_account_key,
+        &vesting_token_account_key,
+        &dest_token_acc_key,
+        seeds,
+    )
+    .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_contract_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    let touched = [
+        vesting_account_key,
+        vesting_token_account_key,
+        source_token_acc_key,
+        dest_token_acc_key,
+    ];
+    let before = snapshot(&mut banks_client, &touched).await;
+    banks_client.process_transaction(tx).await.unwrap();
+    let after = snapshot(&mut banks_client, &touched).await;
+    // the only schedule is paid out, which exhausts the contract. The source is left alone
+    assert_account_changes(
+        &before,
+        &after,
+        &[
+            Change::Header(
+                vesting_account_key,
+                Box::new(|h: &mut VestingScheduleHeader| {
+                    h.next_release_time = NO_PENDING_RELEASE;
+                    h.state = ContractState::Exhausted;
+                    h.unlock_count = 1;
+                    h.has_claimable = false;
+                    h.unlocked_gross = 111;
+                    h.unlocked_net = 111;
+                }),
+            ),
+            Change::ScheduleAmount(vesting_account_key, 0, 0),
+            Change::TokenBalance(vesting_token_account_key, -111),
+            Change::TokenBalance(dest_token_acc_key, 111),
+        ],
+    );
+
+    // ----------------------------------------------------------------------------- verify state on the blockchain
+
+    // against a live cluster instead of the test bank (needs token-vesting-sdk):
+    // let client = token_vesting_sdk::client::ClientConfig::default().rpc_client();
+    // let dest_acc = client.get_account(&dest_token_acc_key).unwrap();
+
+    let dest_acc = banks_client
+        .get_account(dest_token_acc_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let dest_token_acc_state = spl_token::state::Account::unpack(&dest_acc.data.borrow()).unwrap();
+    assert_eq!(dest_token_acc_state.amount, 111);
+
+    let source_acc = banks_client
+        .get_account(source_token_acc_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let source_token_acc_state =
+        spl_token::state::Account::unpack_from_slice(&source_acc.data).unwrap();
+    assert_eq!(source_token_acc_state.amount, 1000 - 111);
+}
+
+// ----------------------------------------------------------------------------- helpers for the tests below
+
+/// Everything a test needs to poke at a freshly created vesting contract
+struct VestingContract {
+    seeds: Seeds,
+    mint: Keypair,
+    vesting_account_key: Pubkey,
+    vesting_token_account_key: Pubkey,
+    source_token_account_key: Pubkey,
+    destination_owner: Keypair,
+    destination_token_account_key: Pubkey,
+}
+
+/// Valid vesting seeds - the last byte is the bump, same trick as in the fuzzer
+fn vesting_seeds(program_id: &Pubkey, n: u8) -> Seeds {
+    vesting_address(&[n; 31], program_id).1
+}
+
+/// Same steps as test_init_create_unlock_flow, but using the builders from instruction.rs.
+/// The payer is the source token account owner and gets 1000 tokens minted to it
+async fn create_vesting_contract(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    program_id: Pubkey,
+    seeds: Seeds,
+    schedules: Vec<Schedule>,
+) -> VestingContract {
+    let contract = setup_vesting_contract(
+        banks_client,
+        payer,
+        recent_blockhash,
+        program_id,
+        seeds,
+        schedules.len() as u32,
+    )
+    .await;
+    let tx = Transaction::new_signed_with_payer(
+        &[create_ix(program_id, payer, &contract, schedules, true)],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    contract
+}
+
+/// Everything up to (but excluding) Create - the vesting account is initialized and the payer funded
+async fn setup_vesting_contract(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    program_id: Pubkey,
+    seeds: Seeds,
+    number_of_schedules: u32,
+) -> VestingContract {
+    let vesting_account_key = Pubkey::create_program_address(&[&seeds], &program_id).unwrap();
+    let mint = Keypair::new();
+    let destination_owner = Keypair::new();
+
+    let vesting_token_account_key = spl_associated_token_account::get_associated_token_address(
+        &vesting_account_key,
+        &mint.pubkey(),
+    );
+    let source_token_account_key =
+        spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &mint.pubkey());
+    let destination_token_account_key = spl_associated_token_account::get_associated_token_address(
+        &destination_owner.pubkey(),
+        &mint.pubkey(),
+    );
+
+    // init + mint
+    let rent = banks_client.get_rent().await.unwrap();
+    let setup_ixs = vec![
+        init(
+            &system_program::id(),
+            &sysvar::rent::id(),
+            &program_id,
+            &payer.pubkey(),
+            &vesting_account_key,
+            seeds,
+            number_of_schedules,
+        )
+        .unwrap(),
+        create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            &mint.pubkey(),
+            &payer.pubkey(),
+            None,
+            0,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &setup_ixs,
+        Some(&payer.pubkey()),
+        &[payer, &mint],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    // token accounts + funding
+    let token_ixs = vec![
+        spl_associated_token_account::create_associated_token_account(
+            &payer.pubkey(),
+            &vesting_account_key,
+            &mint.pubkey(),
+        ),
+        spl_associated_token_account::create_associated_token_account(
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &mint.pubkey(),
+        ),
+        spl_associated_token_account::create_associated_token_account(
+            &payer.pubkey(),
+            &destination_owner.pubkey(),
+            &mint.pubkey(),
+        ),
+        spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &mint.pubkey(),
+            &source_token_account_key,
+            &payer.pubkey(),
+            &[],
+            1000,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &token_ixs,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    VestingContract {
+        seeds,
+        mint,
+        vesting_account_key,
+        vesting_token_account_key,
+        source_token_account_key,
+        destination_owner,
+        destination_token_account_key,
+    }
+}
+
+fn create_ix(
+    program_id: Pubkey,
+    payer: &Keypair,
+    contract: &VestingContract,
+    schedules: Vec<Schedule>,
+    allow_immediate: bool,
+) -> Instruction {
+    create(
+        &program_id,
+        &spl_token::id(),
+        &contract.vesting_account_key,
+        &contract.vesting_token_account_key,
+        &payer.pubkey(),
+        &contract.source_token_account_key,
+        &contract.destination_token_account_key,
+        &contract.mint.pubkey(),
+        schedules,
+        CreateOptions {
+            allow_immediate,
+            ..CreateOptions::default()
+        },
+        contract.seeds,
+    )
+    .unwrap()
+}
+
+fn unlock_ix(program_id: Pubkey, contract: &VestingContract) -> Instruction {
+    unlock(
+        &program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        &contract.vesting_account_key,
+        &contract.vesting_token_account_key,
+        &contract.destination_token_account_key,
+        contract.seeds,
+    )
+    .unwrap()
+}
+
+/// Creates a second token account of the contract's mint, owned by a new keypair
+async fn create_token_account(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    mint: &Pubkey,
+) -> (Keypair, Pubkey) {
+    let owner = Keypair::new();
+    let ix = spl_associated_token_account::create_associated_token_account(
+        &payer.pubkey(),
+        &owner.pubkey(),
+        mint,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    let key = spl_associated_token_account::get_associated_token_address(&owner.pubkey(), mint);
+    (owner, key)
+}
+
+fn custom_error(index: u8, e: VestingError) -> TransactionError {
+    TransactionError::InstructionError(index, InstructionError::Custom(e as u32))
+}
+
+// ----------------------------------------------------------------------------- change destination
+
+#[tokio::test]
+async fn test_change_destination_while_locked() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+    let contract = create_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        vec![Schedule {
+            release_time: u64::MAX - 1,
+            amount: 100,
+        }],
+    )
+    .await;
+    let (_, new_destination_key) = create_token_account(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        &contract.mint.pubkey(),
+    )
+    .await;
+
+    let ix = change_destination(
+        &program_id,
+        &contract.vesting_account_key,
+        &contract.destination_owner.pubkey(),
+        &contract.destination_token_account_key,
+        &new_destination_key,
+        contract.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer, &contract.destination_owner],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    let vesting_account = banks_client
+        .get_account(contract.vesting_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let header = token_vesting_program::state::VestingScheduleHeader::unpack(
+        &vesting_account.data[..token_vesting_program::state::VestingScheduleHeader::LEN],
+    )
+    .unwrap();
+    assert_eq!(header.destination_address, new_destination_key);
+}
+
+#[tokio::test]
+async fn test_change_destination_rejected_when_exhausted() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+    let contract = create_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        vec![Schedule {
+            release_time: 1,
+            amount: 100,
+        }],
+    )
+    .await;
+    let (_, new_destination_key) = create_token_account(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        &contract.mint.pubkey(),
+    )
+    .await;
+
+    // claim everything
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix(program_id, &contract)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    let ix = change_destination(
+        &program_id,
+        &contract.vesting_account_key,
+        &contract.destination_owner.pubkey(),
+        &contract.destination_token_account_key,
+        &new_destination_key,
+        contract.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer, &contract.destination_owner],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        custom_error(0, VestingError::ContractExhausted)
+    );
+}
+
+#[tokio::test]
+async fn test_create_rejects_past_release_time() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+    let contract = setup_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        1,
+    )
+    .await;
+    let schedules = vec![Schedule {
+        release_time: 1,
+        amount: 100,
+    }];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[create_ix(
+            program_id,
+            &payer,
+            &contract,
+            schedules.clone(),
+            false,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        custom_error(0, VestingError::ReleaseTimeInPast)
+    );
+
+    // same schedule goes through once immediate release is explicitly allowed
+    let tx = Transaction::new_signed_with_payer(
+        &[create_ix(program_id, &payer, &contract, schedules, true)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+}
+
+// ----------------------------------------------------------------------------- account diffs
+
+/// Accounts as they were at some point of a test. None for an account that didn't exist then
+type Snapshot = HashMap<Pubkey, Option<Account>>;
+
+async fn snapshot(banks_client: &mut BanksClient, keys: &[Pubkey]) -> Snapshot {
+    let mut snapshot = HashMap::new();
+    for key in keys {
+        snapshot.insert(*key, banks_client.get_account(*key).await.unwrap());
+    }
+    snapshot
+}
+
+/// What a transaction is expected to have done to one of the snapshotted accounts
+enum Change {
+    /// A token account's balance moved by this much
+    TokenBalance(Pubkey, i128),
+    /// A vesting account's header, as an edit of the one before
+    Header(Pubkey, Box<dyn Fn(&mut VestingScheduleHeader)>),
+    /// Schedule `index` of a vesting account holds this amount afterwards
+    ScheduleAmount(Pubkey, usize, u64),
+}
+
+impl Change {
+    fn key(&self) -> &Pubkey {
+        match self {
+            Change::TokenBalance(key, _)
+            | Change::Header(key, _)
+            | Change::ScheduleAmount(key, ..) => key,
+        }
+    }
+}
+
+/// Header and schedules of a plain vesting account
+fn vesting_state(data: &[u8]) -> (VestingScheduleHeader, Vec<VestingSchedule>) {
+    let header = VestingScheduleHeader::unpack_account(data).unwrap();
+    let schedules_end =
+        VestingScheduleHeader::LEN + header.schedule_count as usize * VestingSchedule::LEN;
+    let schedules = unpack_schedules(&data[VestingScheduleHeader::LEN..schedules_end]).unwrap();
+    (header, schedules)
+}
+
+/// Checks every account of `before` against `after`, field by field: an account with expectations has to differ by
+/// exactly those, any other has to be left untouched
+fn assert_account_changes(before: &Snapshot, after: &Snapshot, expectations: &[Change]) {
+    for (key, old) in before {
+        let new = &after[key];
+        let changes: Vec<&Change> = expectations.iter().filter(|c| c.key() == key).collect();
+        if changes.is_empty() {
+            assert_eq!(new, old, "{} wasn't expected to change", key);
+            continue;
+        }
+        let old = old
+            .as_ref()
+            .unwrap_or_else(|| panic!("{} didn't exist before", key));
+        let new = new.as_ref().unwrap_or_else(|| panic!("{} is gone", key));
+
+        if let Change::TokenBalance(..) = changes[0] {
+            let mut expected = spl_token::state::Account::unpack(&old.data).unwrap();
+            for change in changes {
+                match change {
+                    Change::TokenBalance(_, delta) => {
+                        expected.amount = (expected.amount as i128 + delta).try_into().unwrap()
+                    }
+                    _ => panic!("{} is a token account", key),
+                }
+            }
+            assert_eq!(
+                spl_token::state::Account::unpack(&new.data).unwrap(),
+                expected,
+                "token account {}",
+                key
+            );
+        } else {
+            let (mut expected_header, mut expected_schedules) = vesting_state(&old.data);
+            for change in changes {
+                match change {
+                    Change::Header(_, edit) => edit(&mut expected_header),
+                    Change::ScheduleAmount(_, index, amount) => {
+                        expected_schedules[*index].amount = *amount
+                    }
+                    Change::TokenBalance(..) => panic!("{} is a vesting account", key),
+                }
+            }
+            let (header, schedules) = vesting_state(&new.data);
+            assert_eq!(header, expected_header, "header of {}", key);
+            assert_eq!(schedules, expected_schedules, "schedules of {}", key);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------- revoke
+
+fn token_balance(account: &solana_sdk::account::Account) -> u64 {
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+#[tokio::test]
+async fn test_revoke_batch() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    // one released and one locked schedule per contract
+    let schedules = vec![
+        Schedule {
+            release_time: 1,
+            amount: 100,
+        },
+        Schedule {
+            release_time: u64::MAX,
+            amount: 200,
+        },
+    ];
+    let mut contracts = vec![];
+    for n in 0..2 {
+        contracts.push(
+            create_vesting_contract(
+                &mut banks_client,
+                &payer,
+                recent_blockhash,
+                program_id,
+                vesting_seeds(&program_id, n),
+                schedules.clone(),
+            )
+            .await,
+        );
+    }
+
+    let entries = contracts
+        .iter()
+        .map(|c| RevokeBatchEntry {
+            vesting_account_key: c.vesting_account_key,
+            vesting_token_account_key: c.vesting_token_account_key,
+            granter_token_account_key: c.source_token_account_key,
+            seeds: c.seeds,
+        })
+        .collect::<Vec<RevokeBatchEntry>>();
+
+    // only the granter can revoke
+    let impostor = Keypair::new();
+    let ix = revoke_batch(
+        &program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        &impostor.pubkey(),
+        &entries,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer, &impostor],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let ix = revoke_batch(
+        &program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        &payer.pubkey(),
+        &entries,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    for c in contracts.iter() {
+        // locked amount went back to the granter, released amount stays put
+        let source = banks_client
+            .get_account(c.source_token_account_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(token_balance(&source), 1000 - 300 + 200);
+        let vesting = banks_client
+            .get_account(c.vesting_token_account_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(token_balance(&vesting), 100);
+
+        // and the destination can still claim it
+        let tx = Transaction::new_signed_with_payer(
+            &[unlock_ix(program_id, c)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            recent_blockhash,
+        );
+        banks_client.process_transaction(tx).await.unwrap();
+        let destination = banks_client
+            .get_account(c.destination_token_account_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(token_balance(&destination), 100);
+    }
+}
+
+#[tokio::test]
+async fn test_revoke() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    let schedules = vec![
+        Schedule {
+            release_time: 1,
+            amount: 100,
+        },
+        Schedule {
+            release_time: u64::MAX,
+            amount: 200,
+        },
+    ];
+    let c = create_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        schedules,
+    )
+    .await;
+
+    let ix = revoke(
+        &program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        &payer.pubkey(),
+        &c.vesting_account_key,
+        &c.vesting_token_account_key,
+        &c.source_token_account_key,
+        c.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    // locked amount went back to the granter, released amount stays put
+    let source = banks_client
+        .get_account(c.source_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&source), 1000 - 300 + 200);
+    let vesting = banks_client
+        .get_account(c.vesting_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&vesting), 100);
+}
+
+#[tokio::test]
+async fn test_revoke_burns() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    let c = setup_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        2,
+    )
+    .await;
+    let create_ix = create(
+        &program_id,
+        &spl_token::id(),
+        &c.vesting_account_key,
+        &c.vesting_token_account_key,
+        &payer.pubkey(),
+        &c.source_token_account_key,
+        &c.destination_token_account_key,
+        &c.mint.pubkey(),
+        vec![
+            Schedule {
+                release_time: 1,
+                amount: 100,
+            },
+            Schedule {
+                release_time: u64::MAX,
+                amount: 200,
+            },
+        ],
+        CreateOptions {
+            allow_immediate: true,
+            revoke_policy: RevokePolicy::Burn,
+            ..CreateOptions::default()
+        },
+        c.seeds,
+    )
+    .unwrap();
+    // the mint goes where the granter's token account would
+    let revoke_ix = revoke(
+        &program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        &payer.pubkey(),
+        &c.vesting_account_key,
+        &c.vesting_token_account_key,
+        &c.mint.pubkey(),
+        c.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[create_ix, revoke_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    let source = banks_client
+        .get_account(c.source_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&source), 1000 - 300);
+    let vesting = banks_client
+        .get_account(c.vesting_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&vesting), 100);
+    let mint = banks_client
+        .get_account(c.mint.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Mint::unpack(&mint.data).unwrap().supply,
+        1000 - 200
+    );
+}
+
+#[tokio::test]
+async fn test_top_up() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    let c = create_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        vec![Schedule {
+            release_time: u64::MAX - 1,
+            amount: 100,
+        }],
+    )
+    .await;
+
+    let ix = top_up(
+        &program_id,
+        &spl_token::id(),
+        &system_program::id(),
+        &c.vesting_account_key,
+        &c.vesting_token_account_key,
+        &payer.pubkey(),
+        &c.source_token_account_key,
+        vec![
+            Schedule {
+                release_time: 1,
+                amount: 200,
+            },
+            Schedule {
+                release_time: u64::MAX,
+                amount: 300,
+            },
+        ],
+        true,
+        c.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix, unlock_ix(program_id, &c)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    // the new schedules are paid for and unlock like the original one
+    let vesting_account = banks_client
+        .get_account(c.vesting_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        vesting_account.data.len(),
+        VestingScheduleHeader::account_len(3, ScheduleLayout::Plain)
+    );
+    assert!(Rent::default().is_exempt(vesting_account.lamports, vesting_account.data.len()));
+    let vesting = banks_client
+        .get_account(c.vesting_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&vesting), 100 + 300);
+    let destination = banks_client
+        .get_account(c.destination_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&destination), 200);
+}
+
+#[tokio::test]
+async fn test_resize() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    // initialized for a single schedule, then grown to fit three
+    let c = setup_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        1,
+    )
+    .await;
+    let schedules = vec![
+        Schedule {
+            release_time: 1,
+            amount: 100,
+        },
+        Schedule {
+            release_time: u64::MAX - 1,
+            amount: 200,
+        },
+        Schedule {
+            release_time: u64::MAX,
+            amount: 300,
+        },
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            resize(
+                &program_id,
+                &system_program::id(),
+                &payer.pubkey(),
+                &c.vesting_account_key,
+                c.seeds,
+                3,
+                ScheduleLayout::Plain,
+            )
+            .unwrap(),
+            create_ix(program_id, &payer, &c, schedules, true),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    let vesting_account = banks_client
+        .get_account(c.vesting_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        vesting_account.data.len(),
+        VestingScheduleHeader::account_len(3, ScheduleLayout::Plain)
+    );
+    assert!(Rent::default().is_exempt(vesting_account.lamports, vesting_account.data.len()));
+    let (header, _) = vesting_state(&vesting_account.data);
+    assert_eq!(header.schedule_count, 3);
+
+    // a created contract grows with TopUp only
+    let tx = Transaction::new_signed_with_payer(
+        &[resize(
+            &program_id,
+            &system_program::id(),
+            &payer.pubkey(),
+            &c.vesting_account_key,
+            c.seeds,
+            4,
+            ScheduleLayout::Plain,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+// ----------------------------------------------------------------------------- permissionless unlock
+
+#[tokio::test]
+async fn test_permissionless_unlock() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+    let schedules = || {
+        vec![Schedule {
+            release_time: 1,
+            amount: 100,
+        }]
+    };
+    let open = create_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        schedules(),
+    )
+    .await;
+    let opted_out = create_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 1),
+        schedules(),
+    )
+    .await;
+
+    // a crank with nothing to do with either contract pays for its own transactions
+    let crank = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer(&payer.pubkey(), &crank.pubkey(), 1_000_000_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    let ix = set_permissionless_unlock(
+        &program_id,
+        &opted_out.vesting_account_key,
+        &opted_out.destination_token_account_key,
+        &opted_out.destination_owner.pubkey(),
+        false,
+        opted_out.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer, &opted_out.destination_owner],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    // the crank can't point the tokens anywhere but the stored destination
+    let mut redirected = unlock_ix(program_id, &open);
+    redirected.accounts[4].pubkey = open.source_token_account_key;
+    let tx = Transaction::new_signed_with_payer(
+        &[redirected],
+        Some(&crank.pubkey()),
+        &[&crank],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix(program_id, &open)],
+        Some(&crank.pubkey()),
+        &[&crank],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    let destination = banks_client
+        .get_account(open.destination_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&destination), 100);
+
+    // the opted out contract waits for its destination owner
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix(program_id, &opted_out)],
+        Some(&crank.pubkey()),
+        &[&crank],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let ix = unlock_with_rebate(
+        &program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        &opted_out.vesting_account_key,
+        &opted_out.vesting_token_account_key,
+        &opted_out.destination_token_account_key,
+        &opted_out.destination_owner.pubkey(),
+        opted_out.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&crank.pubkey()),
+        &[&crank, &opted_out.destination_owner],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    let destination = banks_client
+        .get_account(opted_out.destination_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&destination), 100);
+}
+
+#[tokio::test]
+async fn test_create_from_mint() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    // the payer is the mint authority, the 1000 tokens in its source token account stay untouched
+    let c = setup_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        1,
+    )
+    .await;
+    let ix = create_from_mint(
+        &program_id,
+        &spl_token::id(),
+        &c.vesting_account_key,
+        &c.vesting_token_account_key,
+        &payer.pubkey(),
+        &c.destination_token_account_key,
+        &c.mint.pubkey(),
+        vec![Schedule {
+            release_time: u64::MAX,
+            amount: 300,
+        }],
+        CreateOptions {
+            allow_immediate: true,
+            ..CreateOptions::default()
+        },
+        c.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    let vesting = banks_client
+        .get_account(c.vesting_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&vesting), 300);
+    let source = banks_client
+        .get_account(c.source_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&source), 1000);
+    let mint = banks_client
+        .get_account(c.mint.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Mint::unpack(&mint.data).unwrap().supply,
+        1300
+    );
+}
+
+#[tokio::test]
+async fn test_create_from_mint_over_max_supply() {
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_env().await;
+
+    // 1000 tokens are already minted, so 300 more overshoots a cap of 1200
+    let c = setup_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        1,
+    )
+    .await;
+    let ix = create_from_mint(
+        &program_id,
+        &spl_token::id(),
+        &c.vesting_account_key,
+        &c.vesting_token_account_key,
+        &payer.pubkey(),
+        &c.destination_token_account_key,
+        &c.mint.pubkey(),
+        vec![Schedule {
+            release_time: u64::MAX,
+            amount: 300,
+        }],
+        CreateOptions {
+            allow_immediate: true,
+            max_supply: Some(1200),
+            ..CreateOptions::default()
+        },
+        c.seeds,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        custom_error(0, VestingError::MintSupplyCapExceeded)
+    );
+    let mint = banks_client
+        .get_account(c.mint.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Mint::unpack(&mint.data).unwrap().supply,
+        1000
+    );
+}
+
+// ----------------------------------------------------------------------------- PDA destination
+
+const VAULT_SEED: &[u8] = b"vault";
+
+/// Stands in for a protocol collecting vesting proceeds in its own vault: a PDA of this program owns the destination
+/// token account, and the program moves the destination by signing ChangeDestination with invoke_signed.
+///
+/// Accounts: the vesting program, the vesting account, the current destination, the vault authority, the new
+/// destination. Data: the vesting seeds
+fn vault_process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (vault_authority, bump) = Pubkey::find_program_address(&[VAULT_SEED], program_id);
+    let seeds: Seeds = data
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let ix = change_destination(
+        accounts[0].key,
+        accounts[1].key,
+        &vault_authority,
+        accounts[2].key,
+        accounts[4].key,
+        seeds,
+    )?;
+    invoke_signed(&ix, accounts, &[&[VAULT_SEED, &[bump]]])
+}
+
+#[tokio::test]
+async fn test_pda_owned_destination() {
+    let program_id = ids::devnet();
+    let vault_program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "token_vesting_program",
+        program_id,
+        processor!(Processor::process_instruction),
+    );
+    // there's no .so for the vault, so it always runs natively. Spelling out the type keeps the processor closure
+    // generic over the vm's lifetime, which unwrapping the Option alone doesn't
+    let vault_builtin: Option<fn(*mut EbpfVm<InvokeContext<'static>>, u64, u64, u64, u64, u64)> =
+        processor!(vault_process);
+    program_test.add_builtin_program("vault_program", vault_program_id, vault_builtin.unwrap());
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let contract = create_vesting_contract(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        program_id,
+        vesting_seeds(&program_id, 0),
+        vec![
+            Schedule {
+                release_time: 1,
+                amount: 100,
+            },
+            Schedule {
+                release_time: u64::MAX - 1,
+                amount: 100,
+            },
+        ],
+    )
+    .await;
+
+    // hand the contract over to the vault
+    let (vault_authority, _) = Pubkey::find_program_address(&[VAULT_SEED], &vault_program_id);
+    let vault_token_account_key = spl_associated_token_account::get_associated_token_address(
+        &vault_authority,
+        &contract.mint.pubkey(),
+    );
+    let ixs = vec![
+        spl_associated_token_account::create_associated_token_account(
+            &payer.pubkey(),
+            &vault_authority,
+            &contract.mint.pubkey(),
+        ),
+        change_destination(
+            &program_id,
+            &contract.vesting_account_key,
+            &contract.destination_owner.pubkey(),
+            &contract.destination_token_account_key,
+            &vault_token_account_key,
+            contract.seeds,
+        )
+        .unwrap(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer.pubkey()),
+        &[&payer, &contract.destination_owner],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    // proceeds flow straight into the vault
+    let contract = VestingContract {
+        destination_token_account_key: vault_token_account_key,
+        ..contract
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix(program_id, &contract)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    let vault = banks_client
+        .get_account(vault_token_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_balance(&vault), 100);
+
+    // and the vault program can move the destination on, signing for its PDA
+    let (_, new_destination_key) = create_token_account(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        &contract.mint.pubkey(),
+    )
+    .await;
+    let ix = Instruction::new_with_bytes(
+        vault_program_id,
+        &contract.seeds,
+        vec![
+            AccountMeta::new_readonly(program_id, false),
+            AccountMeta::new(contract.vesting_account_key, false),
+            AccountMeta::new_readonly(vault_token_account_key, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new_readonly(new_destination_key, false),
+        ],
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+
+    let vesting_account = banks_client
+        .get_account(contract.vesting_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let header = token_vesting_program::state::VestingScheduleHeader::unpack(
+        &vesting_account.data[..token_vesting_program::state::VestingScheduleHeader::LEN],
+    )
+    .unwrap();
+    assert_eq!(header.destination_address, new_destination_key);
+}
@@ -0,0 +1,89 @@
+//! Emission statistics over outstanding schedules, for vesting-cli's `show --analytics` and dashboards.
+//! Both helpers take plain schedules, so the schedules of many contracts can be chained together to characterize
+//! a whole mint. Claimed schedules have an amount of 0 and drop out on their own.
+
+use crate::state::VestingSchedule;
+
+/// Cumulative amount released by each distinct release time, in time order - the points of a step chart of what
+/// hits the market when. Schedules releasing at the same time share a point, and empty schedules don't get one
+pub fn vesting_curve(schedules: &[VestingSchedule]) -> Vec<(u64, u64)> {
+    let mut sorted: Vec<&VestingSchedule> = schedules.iter().filter(|s| s.amount > 0).collect();
+    sorted.sort_by_key(|s| s.release_time);
+
+    let mut curve: Vec<(u64, u64)> = Vec::with_capacity(sorted.len());
+    let mut cumulative_amount: u64 = 0;
+    for s in sorted {
+        cumulative_amount = cumulative_amount.saturating_add(s.amount);
+        match curve.last_mut() {
+            Some((release_time, amount)) if *release_time == s.release_time => {
+                *amount = cumulative_amount
+            }
+            _ => curve.push((s.release_time, cumulative_amount)),
+        }
+    }
+    curve
+}
+
+/// Release time averaged over every token still to be claimed - the earlier it is, the sooner most of the supply
+/// unlocks. None when nothing is outstanding
+pub fn weighted_avg_unlock_time(schedules: &[VestingSchedule]) -> Option<u64> {
+    // a mint's whole supply fits in a u64, so neither sum can overflow a u128
+    let (weighted_sum, total) =
+        schedules
+            .iter()
+            .fold((0u128, 0u128), |(weighted_sum, total), s| {
+                (
+                    weighted_sum + s.release_time as u128 * s.amount as u128,
+                    total + s.amount as u128,
+                )
+            });
+    if total == 0 {
+        return None;
+    }
+    Some((weighted_sum / total) as u64)
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::vesting_schedule;
+
+    #[test]
+    fn test_vesting_curve() {
+        let schedules = vec![
+            vesting_schedule(30, 50),
+            vesting_schedule(10, 100),
+            vesting_schedule(20, 0),
+            vesting_schedule(30, 25),
+        ];
+        assert_eq!(vesting_curve(&schedules), vec![(10, 100), (30, 175)]);
+        assert_eq!(vesting_curve(&[]), vec![]);
+        assert_eq!(
+            vesting_curve(&[vesting_schedule(10, u64::MAX), vesting_schedule(20, 1)]),
+            vec![(10, u64::MAX), (20, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_weighted_avg_unlock_time() {
+        assert_eq!(
+            weighted_avg_unlock_time(&[vesting_schedule(10, 300), vesting_schedule(50, 100)]),
+            Some(20)
+        );
+        // claimed schedules don't pull the average
+        assert_eq!(
+            weighted_avg_unlock_time(&[vesting_schedule(10, 0), vesting_schedule(50, 100)]),
+            Some(50)
+        );
+        assert_eq!(weighted_avg_unlock_time(&[vesting_schedule(10, 0)]), None);
+        assert_eq!(
+            weighted_avg_unlock_time(&[
+                vesting_schedule(u64::MAX, u64::MAX / 2),
+                vesting_schedule(u64::MAX, u64::MAX / 2)
+            ]),
+            Some(u64::MAX)
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Splits batches into instructions and transactions that fit the runtime's limits, so that an oversized batch is
+//! caught while it's being built instead of when the cluster rejects it.
+//!
+//! A transaction can be at most MAX_TRANSACTION_SIZE bytes on the wire and reference at most
+//! MAX_TRANSACTION_ACCOUNTS accounts. Chunks are always measured together with the payer they'll be sent with, since
+//! a payer that isn't already part of the batch adds a key and a signature of its own.
+
+use std::{fmt, ops::Range};
+
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::{packet::PACKET_DATA_SIZE, transaction::Transaction};
+
+use crate::instruction::{revoke_batch, RevokeBatchEntry};
+
+pub const MAX_TRANSACTION_SIZE: usize = PACKET_DATA_SIZE;
+/// The runtime's account lock limit
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+
+/// What a transaction takes up of the limits
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Footprint {
+    /// Serialized size in bytes, signatures included
+    pub size: usize,
+    pub accounts: usize,
+}
+
+impl Footprint {
+    pub fn of(payer: &Pubkey, instructions: &[Instruction]) -> Self {
+        let transaction = Transaction::new_with_payer(instructions, Some(payer));
+        Self {
+            size: bincode::serialized_size(&transaction).unwrap() as usize,
+            accounts: transaction.message.account_keys.len(),
+        }
+    }
+
+    pub fn fits(&self) -> bool {
+        self.size <= MAX_TRANSACTION_SIZE && self.accounts <= MAX_TRANSACTION_ACCOUNTS
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BatchError {
+    /// The item at `index` of the batch doesn't fit in a transaction even on its own
+    Oversized {
+        index: usize,
+        footprint: Footprint,
+    },
+    Instruction(ProgramError),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Oversized { index, footprint } => write!(
+                f,
+                "item {} of the batch doesn't fit in a transaction: {} bytes (max {}), {} accounts (max {})",
+                index, footprint.size, MAX_TRANSACTION_SIZE, footprint.accounts, MAX_TRANSACTION_ACCOUNTS
+            ),
+            Self::Instruction(e) => write!(f, "building the batch failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl From<ProgramError> for BatchError {
+    fn from(e: ProgramError) -> Self {
+        Self::Instruction(e)
+    }
+}
+
+/// A batch split into transactions. `chunks[i]` is the range of the batch that `transactions[i]` covers, so the
+/// outcome of a transaction can be traced back to the items in it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchPlan {
+    pub transactions: Vec<Vec<Instruction>>,
+    pub chunks: Vec<Range<usize>>,
+}
+
+/// Packs instructions (eg one unlock per contract) into as few transactions as they fit in, keeping their order
+pub fn plan_instructions(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<BatchPlan, BatchError> {
+    plan(payer, instructions.len(), |chunk| {
+        Ok(instructions[chunk].to_vec())
+    })
+}
+
+/// Splits a RevokeBatch into as few RevokeBatch instructions as the contracts fit in, one per transaction, keeping
+/// their order. The granter signs every one of them, so it's usually the payer as well
+pub fn plan_revoke_batch(
+    vesting_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    clock_sysvar_id: &Pubkey,
+    payer: &Pubkey,
+    granter_key: &Pubkey,
+    contracts: &[RevokeBatchEntry],
+) -> Result<BatchPlan, BatchError> {
+    plan(payer, contracts.len(), |chunk| {
+        Ok(vec![revoke_batch(
+            vesting_program_id,
+            token_program_id,
+            clock_sysvar_id,
+            granter_key,
+            &contracts[chunk],
+        )?])
+    })
+}
+
+/// Grows each chunk one item at a time for as long as the transaction `build` makes of it still fits
+fn plan(
+    payer: &Pubkey,
+    len: usize,
+    build: impl Fn(Range<usize>) -> Result<Vec<Instruction>, BatchError>,
+) -> Result<BatchPlan, BatchError> {
+    let mut plan = BatchPlan::default();
+    let mut start = 0;
+    while start < len {
+        let mut transaction = build(start..start + 1)?;
+        let footprint = Footprint::of(payer, &transaction);
+        if !footprint.fits() {
+            return Err(BatchError::Oversized {
+                index: start,
+                footprint,
+            });
+        }
+
+        let mut end = start + 1;
+        while end < len {
+            let bigger = build(start..end + 1)?;
+            if !Footprint::of(payer, &bigger).fits() {
+                break;
+            }
+            transaction = bigger;
+            end += 1;
+        }
+        plan.transactions.push(transaction);
+        plan.chunks.push(start..end);
+        start = end;
+    }
+    Ok(plan)
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::unlock;
+    use solana_program::{instruction::AccountMeta, sysvar};
+
+    fn unlock_ix() -> Instruction {
+        unlock(
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            [0; 32],
+        )
+        .unwrap()
+    }
+
+    fn revoke_entry() -> RevokeBatchEntry {
+        RevokeBatchEntry {
+            vesting_account_key: Pubkey::new_unique(),
+            vesting_token_account_key: Pubkey::new_unique(),
+            granter_token_account_key: Pubkey::new_unique(),
+            seeds: [7; 32],
+        }
+    }
+
+    #[test]
+    fn test_footprint() {
+        let payer = Pubkey::new_unique();
+        let ix = unlock_ix();
+        let footprint = Footprint::of(&payer, std::slice::from_ref(&ix));
+        // payer, the instruction's accounts and the program
+        assert_eq!(footprint.accounts, 1 + ix.accounts.len() + 1);
+        assert!(footprint.fits());
+
+        let huge = Instruction {
+            program_id: ix.program_id,
+            accounts: vec![],
+            data: vec![0; MAX_TRANSACTION_SIZE],
+        };
+        assert!(!Footprint::of(&payer, &[huge]).fits());
+    }
+
+    #[test]
+    fn test_plan_instructions() {
+        let payer = Pubkey::new_unique();
+        let unlocks: Vec<Instruction> = (0..40).map(|_| unlock_ix()).collect();
+        let plan = plan_instructions(&payer, &unlocks).unwrap();
+
+        assert!(plan.transactions.len() > 1);
+        assert_eq!(plan.chunks.first().unwrap().start, 0);
+        assert_eq!(plan.chunks.last().unwrap().end, unlocks.len());
+        for (transaction, chunk) in plan.transactions.iter().zip(plan.chunks.iter()) {
+            assert_eq!(transaction[..], unlocks[chunk.clone()]);
+            assert!(Footprint::of(&payer, transaction).fits());
+        }
+        // chunks are as big as they can be
+        for (chunk, next) in plan.chunks.iter().zip(plan.chunks.iter().skip(1)) {
+            assert_eq!(chunk.end, next.start);
+            assert!(!Footprint::of(&payer, &unlocks[chunk.start..next.start + 1]).fits());
+        }
+
+        assert_eq!(
+            plan_instructions(&payer, &[]).unwrap(),
+            BatchPlan::default()
+        );
+    }
+
+    #[test]
+    fn test_plan_instructions_oversized() {
+        let payer = Pubkey::new_unique();
+        let mut instructions = vec![unlock_ix(), unlock_ix()];
+        instructions[1].accounts.extend(
+            (0..MAX_TRANSACTION_ACCOUNTS).map(|_| AccountMeta::new(Pubkey::new_unique(), false)),
+        );
+
+        match plan_instructions(&payer, &instructions) {
+            Err(BatchError::Oversized { index, footprint }) => {
+                assert_eq!(index, 1);
+                assert!(footprint.accounts > MAX_TRANSACTION_ACCOUNTS);
+            }
+            other => panic!("expected Oversized, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plan_revoke_batch() {
+        let program_id = Pubkey::new_unique();
+        let granter = Pubkey::new_unique();
+        let contracts: Vec<RevokeBatchEntry> = (0..30).map(|_| revoke_entry()).collect();
+        let plan = plan_revoke_batch(
+            &program_id,
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &granter,
+            &granter,
+            &contracts,
+        )
+        .unwrap();
+
+        assert!(plan.transactions.len() > 1);
+        let mut covered = 0;
+        for (transaction, chunk) in plan.transactions.iter().zip(plan.chunks.iter()) {
+            assert_eq!(chunk.start, covered);
+            covered = chunk.end;
+            // one RevokeBatch per transaction, with the chunk's contracts in order
+            assert_eq!(transaction.len(), 1);
+            assert_eq!(
+                transaction[0],
+                revoke_batch(
+                    &program_id,
+                    &spl_token::id(),
+                    &sysvar::clock::id(),
+                    &granter,
+                    &contracts[chunk.clone()],
+                )
+                .unwrap()
+            );
+            assert!(Footprint::of(&granter, transaction).fits());
+        }
+        assert_eq!(covered, contracts.len());
+    }
+}
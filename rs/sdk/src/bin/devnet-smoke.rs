@@ -0,0 +1,68 @@
+//! Runs smoke::run_smoke_test against a cluster and exits with 1 unless every step passed. Replaces the hand-run
+//! lifecycle of the release checklist:
+//!
+//! ```text
+//! devnet-smoke --keypair ~/.config/solana/id.json [--url devnet] [--program-id <pubkey>]
+//! ```
+//!
+//! The keypair has to hold enough SOL for a mint, three token accounts and a vesting account. The program id defaults
+//! to ids::devnet()
+
+use std::{process, str::FromStr};
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::read_keypair_file;
+use token_vesting_sdk::{
+    client::{ClientConfig, Cluster},
+    ids,
+    smoke::{run_smoke_test, SmokeConfig},
+};
+
+const USAGE: &str =
+    "usage: devnet-smoke --keypair <path> [--url <cluster or url>] [--program-id <pubkey>]";
+
+fn main() {
+    let mut keypair_path = None;
+    let mut cluster = Cluster::Devnet;
+    let mut program_id = ids::devnet();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| exit_with(&format!("{} needs a value", flag)));
+        match flag.as_str() {
+            "--keypair" => keypair_path = Some(value),
+            "--url" => cluster = Cluster::from_str(&value).unwrap_or_else(|e| exit_with(&e)),
+            "--program-id" => {
+                program_id = Pubkey::from_str(&value).unwrap_or_else(|e| exit_with(&e.to_string()))
+            }
+            _ => exit_with(&format!("unknown flag {}", flag)),
+        }
+    }
+    let keypair_path = keypair_path.unwrap_or_else(|| exit_with("--keypair is required"));
+    let payer = read_keypair_file(&keypair_path)
+        .unwrap_or_else(|e| exit_with(&format!("reading {}: {}", keypair_path, e)));
+
+    let config = ClientConfig {
+        cluster,
+        ..ClientConfig::default()
+    };
+    println!("smoke testing {} on {}", program_id, config.cluster.url());
+    let report = run_smoke_test(
+        &config.rpc_client(),
+        &config,
+        &program_id,
+        &payer,
+        &SmokeConfig::default(),
+    );
+    print!("{}", report.render());
+    if !report.passed() {
+        process::exit(1);
+    }
+}
+
+fn exit_with(error: &str) -> ! {
+    eprintln!("{}\n{}", error, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,161 @@
+//! Command line client of the vesting program. See token_vesting_sdk::cli for the flags and commands:
+//!
+//! ```text
+//! vesting-cli --url mainnet show <vesting account> --analytics
+//! vesting-cli --json due
+//! vesting-cli localnet-bootstrap
+//! vesting-cli report --grantor <pubkey> --from 2024-01-01 --to 2025-01-01 --history --csv > ledger.csv
+//! vesting-cli --url mainnet revoke-batch --file cohort.txt --keypair granter.json
+//! ```
+//!
+//! Results go to stdout, errors to stderr. Exits with 2 on a bad command line and 1 when the command itself failed
+
+use std::process;
+
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    rpc_client::RpcClient,
+};
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use token_vesting_sdk::{
+    cli::{parse_args, parse_contracts_file, CliArgs, Command, USAGE},
+    client::{
+        category_totals, cluster_time, due_contracts, fetch_contract, granter_report,
+        localnet_bootstrap, revoke_contracts, ClientConfig,
+    },
+    output::{
+        render, render_csv, render_list, CategoryTotalOutput, ContractOutput, DueContractOutput,
+        LedgerEntryOutput, LocalnetBootstrapOutput, RevokeOutput,
+    },
+    state::ScheduleLayout,
+};
+
+fn main() {
+    let args = parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let config = ClientConfig {
+        cluster: args.cluster.clone(),
+        ..ClientConfig::default()
+    };
+
+    match run(&config.rpc_client(), &config, &args) {
+        Ok(output) => println!("{}", output),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run(rpc_client: &RpcClient, config: &ClientConfig, args: &CliArgs) -> ClientResult<String> {
+    match &args.command {
+        Command::Show {
+            vesting_account,
+            analytics,
+        } => {
+            let (header, schedules, categories) =
+                fetch_contract(rpc_client, config, &args.program_id, vesting_account)?;
+            let mut contract = ContractOutput::new(vesting_account, &header)
+                .with_schedules(&schedules)
+                .with_cluster_time(cluster_time(rpc_client, config)?);
+            if header.schedule_layout == ScheduleLayout::Categorized {
+                contract = contract.with_categories(&categories);
+            }
+            if *analytics {
+                contract = contract.with_analytics(&schedules);
+            }
+            Ok(render(&contract, args.format))
+        }
+        Command::Due => {
+            let due: Vec<DueContractOutput> = due_contracts(rpc_client, config, &args.program_id)?
+                .iter()
+                .map(|(key, next_release_time)| DueContractOutput::new(key, *next_release_time))
+                .collect();
+            Ok(render_list(&due, args.format))
+        }
+        Command::LocalnetBootstrap {
+            keypair,
+            beneficiary,
+        } => {
+            let payer = read_keypair(keypair)?;
+            let bootstrap = localnet_bootstrap(
+                rpc_client,
+                config,
+                &args.program_id,
+                &payer,
+                &Keypair::new(),
+                &beneficiary.unwrap_or_else(|| payer.pubkey()),
+            )?;
+            Ok(render(
+                &LocalnetBootstrapOutput::from(&bootstrap),
+                args.format,
+            ))
+        }
+        Command::Report {
+            grantor,
+            from,
+            to,
+            history,
+            totals,
+            csv,
+        } => {
+            let entries = granter_report(
+                rpc_client,
+                config,
+                &args.program_id,
+                grantor,
+                *from,
+                *to,
+                *history,
+            )?;
+            if *totals {
+                let totals: Vec<CategoryTotalOutput> = category_totals(&entries)
+                    .iter()
+                    .map(CategoryTotalOutput::from)
+                    .collect();
+                return Ok(if *csv {
+                    render_csv(&totals)
+                } else {
+                    render_list(&totals, args.format)
+                });
+            }
+            let entries: Vec<LedgerEntryOutput> =
+                entries.iter().map(LedgerEntryOutput::from).collect();
+            Ok(if *csv {
+                render_csv(&entries)
+            } else {
+                render_list(&entries, args.format)
+            })
+        }
+        Command::RevokeBatch { file, keypair } => {
+            let granter = read_keypair(keypair)?;
+            let contents = std::fs::read_to_string(file)
+                .map_err(|e| ClientErrorKind::Custom(format!("reading {}: {}", file, e)))?;
+            let contracts = parse_contracts_file(&contents)
+                .map_err(|e| ClientErrorKind::Custom(format!("{}: {}", file, e)))?;
+            let results =
+                revoke_contracts(rpc_client, config, &args.program_id, &granter, &contracts);
+            let revoked: Vec<RevokeOutput> = contracts
+                .iter()
+                .zip(&results)
+                .map(|((vesting_account, _), result)| RevokeOutput::new(vesting_account, result))
+                .collect();
+            Ok(render_list(&revoked, args.format))
+        }
+    }
+}
+
+/// The keypair at `path`, or the solana CLI's default one
+fn read_keypair(path: &Option<String>) -> ClientResult<Keypair> {
+    let path = match path {
+        Some(path) => path.clone(),
+        None => format!(
+            "{}/.config/solana/id.json",
+            std::env::var("HOME").unwrap_or_default()
+        ),
+    };
+    read_keypair_file(&path)
+        .map_err(|e| ClientErrorKind::Custom(format!("reading {}: {}", path, e)).into())
+}
@@ -0,0 +1,197 @@
+//! Runs the crank as a service: every --interval it unlocks what client::due_contracts finds, keeps what happened in a
+//! store::CrankStore so that failing contracts stay backed off across restarts, and serves metrics::CrankMetrics for
+//! Prometheus to scrape:
+//!
+//! ```text
+//! vesting-crank --keypair ~/.config/solana/id.json --store ./crank-db [--contracts <path>] [--url devnet]
+//!     [--program-id <pubkey>] [--metrics 127.0.0.1:9100] [--interval 60]
+//! ```
+//!
+//! Unlock needs a contract's seeds, which its account doesn't hold. They're taken from the --contracts file (the
+//! revoke-batch format, see cli::parse_contracts_file), or else recovered with client::fetch_recovered_seed for
+//! contracts set up by InitGrant. Due contracts whose seeds can't be found are skipped with a warning. Built with the
+//! crank-store feature only
+
+use std::{
+    collections::HashMap, error::Error, fs, net::TcpListener, process, str::FromStr, sync::Arc,
+    thread, time::Duration,
+};
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use token_vesting_sdk::{
+    cli::parse_contracts_file,
+    client::{cluster_time, due_contracts, fetch_recovered_seed, ClientConfig, Cluster},
+    crank::{fetch_unlock_job, run_unlocks, ClusterRpc, CrankConfig},
+    ids,
+    instruction::Seeds,
+    metrics::{CrankMetrics, DEFAULT_LAMPORTS_PER_SIGNATURE},
+    store::{CrankStore, FailureBackoff},
+};
+
+const USAGE: &str = "usage: vesting-crank --keypair <path> --store <path> [--contracts <path>] \
+                     [--url <cluster or url>] [--program-id <pubkey>] [--metrics <address>] [--interval <seconds>]";
+
+/// Nonces fetch_recovered_seed tries for a contract missing from --contracts
+const MAX_RECOVERED_NONCE: u64 = 16;
+
+/// Seeds per vesting account, None for the ones that couldn't be recovered - so that they're only tried once
+type SeedCache = HashMap<Pubkey, Option<Seeds>>;
+
+fn main() {
+    let mut keypair_path = None;
+    let mut store_path = None;
+    let mut contracts_path = None;
+    let mut cluster = Cluster::Devnet;
+    let mut program_id = ids::devnet();
+    let mut metrics_address = None;
+    let mut interval = Duration::from_secs(60);
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| exit_with(&format!("{} needs a value", flag)));
+        match flag.as_str() {
+            "--keypair" => keypair_path = Some(value),
+            "--store" => store_path = Some(value),
+            "--contracts" => contracts_path = Some(value),
+            "--url" => cluster = Cluster::from_str(&value).unwrap_or_else(|e| exit_with(&e)),
+            "--program-id" => {
+                program_id = Pubkey::from_str(&value).unwrap_or_else(|e| exit_with(&e.to_string()))
+            }
+            "--metrics" => metrics_address = Some(value),
+            "--interval" => {
+                interval = value
+                    .parse()
+                    .map(Duration::from_secs)
+                    .unwrap_or_else(|e| exit_with(&format!("--interval {}: {}", value, e)))
+            }
+            _ => exit_with(&format!("unknown flag {}", flag)),
+        }
+    }
+    let keypair_path = keypair_path.unwrap_or_else(|| exit_with("--keypair is required"));
+    let payer = read_keypair_file(&keypair_path)
+        .unwrap_or_else(|e| exit_with(&format!("reading {}: {}", keypair_path, e)));
+    let store_path = store_path.unwrap_or_else(|| exit_with("--store is required"));
+    let store = CrankStore::open(&store_path).unwrap_or_else(|e| exit_with(&e.to_string()));
+
+    let mut seeds = SeedCache::new();
+    if let Some(path) = contracts_path {
+        let contents = fs::read_to_string(&path)
+            .unwrap_or_else(|e| exit_with(&format!("reading {}: {}", path, e)));
+        let contracts = parse_contracts_file(&contents)
+            .unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)));
+        seeds.extend(contracts.into_iter().map(|(key, s)| (key, Some(s))));
+    }
+
+    let metrics = Arc::new(CrankMetrics::default());
+    if let Some(address) = metrics_address {
+        let listener = TcpListener::bind(&address)
+            .unwrap_or_else(|e| exit_with(&format!("binding {}: {}", address, e)));
+        metrics.serve(listener);
+    }
+
+    let config = ClientConfig {
+        cluster,
+        ..ClientConfig::default()
+    };
+    let rpc_client = config.rpc_client();
+    println!("cranking {} on {}", program_id, config.cluster.url());
+    loop {
+        // a run that fails (eg the RPC node is down) is just tried again at the next interval
+        if let Err(e) = crank(
+            &rpc_client,
+            &config,
+            &program_id,
+            &payer,
+            &store,
+            &metrics,
+            &mut seeds,
+        ) {
+            eprintln!("crank run failed: {}", e);
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// One run: the due contracts that aren't backed off are unlocked, and the outcome goes to the store and the metrics
+fn crank(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    store: &CrankStore,
+    metrics: &CrankMetrics,
+    seeds: &mut SeedCache,
+) -> Result<(), Box<dyn Error>> {
+    let due = due_contracts(rpc_client, config, program_id)?;
+    let now = cluster_time(rpc_client, config)?;
+    metrics.observe_due(&due, now);
+
+    let mut jobs = Vec::with_capacity(due.len());
+    for (vesting_account_key, _) in &due {
+        let contract_seeds = match seeds.get(vesting_account_key) {
+            Some(contract_seeds) => *contract_seeds,
+            None => {
+                let recovered = fetch_recovered_seed(
+                    rpc_client,
+                    config,
+                    program_id,
+                    vesting_account_key,
+                    MAX_RECOVERED_NONCE,
+                )?
+                .map(|(_, contract_seeds)| contract_seeds);
+                seeds.insert(*vesting_account_key, recovered);
+                recovered
+            }
+        };
+        let contract_seeds = match contract_seeds {
+            Some(contract_seeds) => contract_seeds,
+            None => {
+                eprintln!("skipping {}: seeds unknown", vesting_account_key);
+                continue;
+            }
+        };
+        match fetch_unlock_job(
+            rpc_client,
+            config,
+            program_id,
+            vesting_account_key,
+            contract_seeds,
+        ) {
+            Ok(job) => jobs.push(job),
+            Err(e) => eprintln!("skipping {}: {}", vesting_account_key, e),
+        }
+    }
+
+    let jobs = store.ready_jobs(jobs, now, &FailureBackoff::default())?;
+    if jobs.is_empty() {
+        return Ok(());
+    }
+    let report = run_unlocks(
+        &ClusterRpc { rpc_client, config },
+        payer,
+        jobs,
+        &CrankConfig::default(),
+    )?;
+    store.record(&report, now)?;
+    metrics.record(&report, DEFAULT_LAMPORTS_PER_SIGNATURE);
+
+    println!(
+        "unlocked {} contracts in {} waves, {} failed",
+        report.unlocked.len(),
+        report.waves,
+        report.failed.len()
+    );
+    for (vesting_account_key, error) in &report.failed {
+        eprintln!("{} failed: {}", vesting_account_key, error);
+    }
+    Ok(())
+}
+
+fn exit_with(error: &str) -> ! {
+    eprintln!("{}\n{}", error, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,484 @@
+//! Command line of the vesting-cli binary, parsed here rather than in the binary so that it can be tested:
+//!
+//! ```text
+//! vesting-cli [--url <cluster or url>] [--program-id <pubkey>] [--json] <command> [<args>]
+//! ```
+//!
+//! Global flags can go anywhere on the line. Every command prints its result through the output module: for humans,
+//! or with `--json` as JSON whose shape scripts can rely on
+
+use std::{collections::VecDeque, str::FromStr};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    client::Cluster,
+    ids,
+    instruction::Seeds,
+    output::{utc_date, OutputFormat},
+};
+
+pub const USAGE: &str =
+    "usage: vesting-cli [--url <cluster or url>] [--program-id <pubkey>] [--json] <command>
+
+commands:
+  show <vesting account> [--analytics]
+                            a contract's header and schedules, --analytics adds its emission curve and weighted
+                            average unlock time
+  due                       contracts with something to unlock at cluster time
+  localnet-bootstrap [--keypair <path>] [--beneficiary <pubkey>]
+                            seeds a local validator with a mint and demo contracts at varied maturities, paid by
+                            --keypair (~/.config/solana/id.json) and vesting to --beneficiary (the payer). --url
+                            defaults to localnet for this one
+  report --grantor <pubkey> --from <date> --to <date> [--history] [--totals] [--csv]
+                            what the grantor's contracts saw from --from up to (not including) --to, dates being
+                            yyyy-mm-dd (UTC) or unix timestamps. --history walks the signature history for claims,
+                            revokes and fees, --totals adds the ledger up by category, --csv exports it
+  revoke-batch --file <path> [--keypair <path>]
+                            revokes every contract in the file, one `<vesting account> <seeds>` line each (base58,
+                            as localnet-bootstrap prints them), each in a transaction of its own signed by the
+                            granter's --keypair (~/.config/solana/id.json). Reports which ones failed";
+
+/// Flags that don't take a value
+const SWITCHES: &[&str] = &["--json", "--analytics", "--history", "--totals", "--csv"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CliArgs {
+    /// Devnet unless --url says otherwise
+    pub cluster: Cluster,
+    /// ids::devnet() unless --program-id says otherwise
+    pub program_id: Pubkey,
+    pub format: OutputFormat,
+    pub command: Command,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// A contract's header and schedules, see client::fetch_contract
+    Show {
+        vesting_account: Pubkey,
+        analytics: bool,
+    },
+    /// Contracts anyone can submit Unlock for, see client::due_contracts
+    Due,
+    /// A mint and demo contracts on a local validator, see client::localnet_bootstrap
+    LocalnetBootstrap {
+        /// The solana CLI's default keypair when not given
+        keypair: Option<String>,
+        /// The payer when not given
+        beneficiary: Option<Pubkey>,
+    },
+    /// The grantor's ledger between two dates, see client::granter_report
+    Report {
+        grantor: Pubkey,
+        from: u64,
+        to: u64,
+        history: bool,
+        totals: bool,
+        csv: bool,
+    },
+    /// The contracts listed in a file revoked one by one, see client::revoke_contracts and parse_contracts_file
+    RevokeBatch {
+        file: String,
+        /// The solana CLI's default keypair when not given
+        keypair: Option<String>,
+    },
+}
+
+/// Parses the arguments following the binary's name
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliArgs, String> {
+    let mut line = CommandLine::split(args)?;
+    let url = line.value("--url");
+    let program_id = match line.value("--program-id") {
+        Some(key) => parse_pubkey(&key)?,
+        None => ids::devnet(),
+    };
+    let format = OutputFormat::from_json_flag(line.switch("--json"));
+
+    let command = match line.argument("command")?.as_str() {
+        "show" => Command::Show {
+            vesting_account: parse_pubkey(&line.argument("vesting account")?)?,
+            analytics: line.switch("--analytics"),
+        },
+        "due" => Command::Due,
+        "localnet-bootstrap" => Command::LocalnetBootstrap {
+            keypair: line.value("--keypair"),
+            beneficiary: match line.value("--beneficiary") {
+                Some(key) => Some(parse_pubkey(&key)?),
+                None => None,
+            },
+        },
+        "report" => {
+            let grantor = parse_pubkey(&line.required_value("--grantor")?)?;
+            let from = parse_date(&line.required_value("--from")?)?;
+            let to = parse_date(&line.required_value("--to")?)?;
+            if from >= to {
+                return Err("--from must come before --to".to_string());
+            }
+            Command::Report {
+                grantor,
+                from,
+                to,
+                history: line.switch("--history"),
+                totals: line.switch("--totals"),
+                csv: line.switch("--csv"),
+            }
+        }
+        "revoke-batch" => Command::RevokeBatch {
+            file: line.required_value("--file")?,
+            keypair: line.value("--keypair"),
+        },
+        command => return Err(format!("unknown command {}", command)),
+    };
+    line.finish()?;
+
+    let cluster = match (url, &command) {
+        (Some(url), _) => Cluster::from_str(&url)?,
+        (None, Command::LocalnetBootstrap { .. }) => Cluster::Localnet,
+        (None, _) => Cluster::Devnet,
+    };
+    Ok(CliArgs {
+        cluster,
+        program_id,
+        format,
+        command,
+    })
+}
+
+/// The contracts of a revoke-batch or vesting-crank --contracts file: a vesting account and its seeds per line, both
+/// base58. Blank lines and lines starting with # are skipped
+pub fn parse_contracts_file(contents: &str) -> Result<Vec<(Pubkey, Seeds)>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let at_line = |e: String| format!("line {}: {}", number, e);
+            match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [vesting_account, seeds] => Ok((
+                    parse_pubkey(vesting_account).map_err(at_line)?,
+                    parse_pubkey(seeds).map_err(at_line)?.to_bytes(),
+                )),
+                _ => Err(at_line(format!(
+                    "expected `<vesting account> <seeds>`, got {:?}",
+                    line
+                ))),
+            }
+        })
+        .collect()
+}
+
+fn parse_pubkey(key: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(key).map_err(|e| format!("{}: {}", key, e))
+}
+
+/// A yyyy-mm-dd date (midnight UTC) or a unix timestamp, as a unix timestamp
+fn parse_date(date: &str) -> Result<u64, String> {
+    if let Ok(timestamp) = date.parse::<u64>() {
+        return Ok(timestamp);
+    }
+    let invalid = || format!("{}: expected yyyy-mm-dd or a unix timestamp", date);
+    let parts = date
+        .split('-')
+        .map(|p| p.parse::<i64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<i64>, String>>()?;
+    let (year, month, day) = match parts[..] {
+        [year, month, day] => (year, month, day),
+        _ => return Err(invalid()),
+    };
+
+    // civil date to days, from http://howardhinnant.github.io/date_algorithms.html
+    let y = year - (month <= 2) as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    // out of range months and days (or dates before 1970) don't come back the same
+    if days < 0 {
+        return Err(invalid());
+    }
+    let timestamp = days as u64 * 86_400;
+    if utc_date(timestamp) != format!("{:04}-{:02}-{:02}", year, month, day) {
+        return Err(invalid());
+    }
+    Ok(timestamp)
+}
+
+/// A command line split into flags and positional arguments, which get taken out as they're interpreted - whatever
+/// is left at the end wasn't expected
+struct CommandLine {
+    values: Vec<(String, String)>,
+    switches: Vec<String>,
+    arguments: VecDeque<String>,
+}
+
+impl CommandLine {
+    fn split<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut line = CommandLine {
+            values: vec![],
+            switches: vec![],
+            arguments: VecDeque::new(),
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if SWITCHES.contains(&arg.as_str()) {
+                line.switches.push(arg);
+            } else if arg.starts_with("--") {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", arg))?;
+                line.values.push((arg, value));
+            } else {
+                line.arguments.push_back(arg);
+            }
+        }
+        Ok(line)
+    }
+
+    fn value(&mut self, flag: &str) -> Option<String> {
+        let position = self.values.iter().position(|(f, _)| f == flag)?;
+        Some(self.values.remove(position).1)
+    }
+
+    fn required_value(&mut self, flag: &str) -> Result<String, String> {
+        self.value(flag)
+            .ok_or_else(|| format!("{} is required", flag))
+    }
+
+    fn switch(&mut self, flag: &str) -> bool {
+        let before = self.switches.len();
+        self.switches.retain(|s| s != flag);
+        self.switches.len() < before
+    }
+
+    fn argument(&mut self, name: &str) -> Result<String, String> {
+        self.arguments
+            .pop_front()
+            .ok_or_else(|| format!("missing {}", name))
+    }
+
+    fn finish(self) -> Result<(), String> {
+        let unexpected = self
+            .values
+            .into_iter()
+            .map(|(flag, _)| flag)
+            .chain(self.switches)
+            .chain(self.arguments)
+            .next();
+        match unexpected {
+            Some(arg) => Err(format!("unexpected {}", arg)),
+            None => Ok(()),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<CliArgs, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let vesting_account = Pubkey::new_unique();
+        assert_eq!(
+            parse(&format!("show {}", vesting_account)),
+            Ok(CliArgs {
+                cluster: Cluster::Devnet,
+                program_id: ids::devnet(),
+                format: OutputFormat::Display,
+                command: Command::Show {
+                    vesting_account,
+                    analytics: false,
+                },
+            })
+        );
+        assert_eq!(
+            parse(&format!("show --analytics {}", vesting_account))
+                .unwrap()
+                .command,
+            Command::Show {
+                vesting_account,
+                analytics: true,
+            }
+        );
+
+        // global flags go anywhere
+        let program_id = Pubkey::new_unique();
+        for line in [
+            format!("--json --url localnet due --program-id {}", program_id),
+            format!("due --program-id {} --json --url localnet", program_id),
+        ] {
+            assert_eq!(
+                parse(&line),
+                Ok(CliArgs {
+                    cluster: Cluster::Localnet,
+                    program_id,
+                    format: OutputFormat::Json,
+                    command: Command::Due,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_args_report() {
+        let grantor = Pubkey::new_unique();
+        assert_eq!(
+            parse(&format!(
+                "report --grantor {} --from 2024-01-01 --to 1735689600 --csv --history",
+                grantor
+            ))
+            .unwrap()
+            .command,
+            Command::Report {
+                grantor,
+                from: 1_704_067_200,
+                to: 1_735_689_600,
+                history: true,
+                totals: false,
+                csv: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_args_localnet_bootstrap() {
+        let args = parse("localnet-bootstrap").unwrap();
+        assert_eq!(args.cluster, Cluster::Localnet);
+        assert_eq!(
+            args.command,
+            Command::LocalnetBootstrap {
+                keypair: None,
+                beneficiary: None,
+            }
+        );
+
+        let beneficiary = Pubkey::new_unique();
+        let args = parse(&format!(
+            "localnet-bootstrap --url localhost --keypair payer.json --beneficiary {}",
+            beneficiary
+        ))
+        .unwrap();
+        assert_eq!(args.cluster, Cluster::Localnet);
+        assert_eq!(
+            args.command,
+            Command::LocalnetBootstrap {
+                keypair: Some("payer.json".to_string()),
+                beneficiary: Some(beneficiary),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_args_revoke_batch() {
+        assert_eq!(
+            parse("revoke-batch --file cohort.txt").unwrap().command,
+            Command::RevokeBatch {
+                file: "cohort.txt".to_string(),
+                keypair: None,
+            }
+        );
+        assert_eq!(
+            parse("--url mainnet revoke-batch --keypair granter.json --file cohort.txt")
+                .unwrap()
+                .command,
+            Command::RevokeBatch {
+                file: "cohort.txt".to_string(),
+                keypair: Some("granter.json".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_contracts_file() {
+        let contracts = [
+            (Pubkey::new_unique(), [1; 32]),
+            (Pubkey::new_unique(), [2; 32]),
+        ];
+        let file = format!(
+            "# terminated cohort\n{} {}\n\n  {}\t{}  \n",
+            contracts[0].0,
+            Pubkey::new_from_array(contracts[0].1),
+            contracts[1].0,
+            Pubkey::new_from_array(contracts[1].1),
+        );
+        assert_eq!(parse_contracts_file(&file), Ok(contracts.to_vec()));
+        assert_eq!(parse_contracts_file(""), Ok(vec![]));
+
+        for (file, error) in [
+            (format!("{}", contracts[0].0), "line 1: expected"),
+            (
+                format!("\n{} {} extra", contracts[0].0, contracts[1].0),
+                "line 2: expected",
+            ),
+            (format!("{} seeds", contracts[0].0), "line 1: seeds:"),
+        ] {
+            let e = parse_contracts_file(&file).unwrap_err();
+            assert!(e.starts_with(error), "{:?} failed with {:?}", file, e);
+        }
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(parse_date("2000-02-29"), Ok(951_782_400));
+        assert_eq!(parse_date("2024-12-31"), Ok(1_735_603_200));
+        assert_eq!(parse_date("1700000000"), Ok(1_700_000_000));
+        for date in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-00-10",
+            "24-1-1",
+            "yesterday",
+            "",
+        ] {
+            assert!(parse_date(date).is_err(), "{:?}", date);
+        }
+    }
+
+    #[test]
+    fn test_parse_args_rejects() {
+        for (line, error) in [
+            ("", "missing command"),
+            ("close", "unknown command close"),
+            ("show", "missing vesting account"),
+            ("due --url", "--url needs a value"),
+            ("due --url ftp://cluster", "unknown cluster"),
+            ("due extra", "unexpected extra"),
+            ("due --limit 3", "unexpected --limit"),
+            ("due --url devnet --url testnet", "unexpected --url"),
+            ("due --csv", "unexpected --csv"),
+            ("due --analytics", "unexpected --analytics"),
+            (
+                "report --from 2024-01-01 --to 2025-01-01",
+                "--grantor is required",
+            ),
+            ("revoke-batch", "--file is required"),
+        ] {
+            let e = parse(line).unwrap_err();
+            assert!(e.starts_with(error), "{:?} failed with {:?}", line, e);
+        }
+
+        for (from, error) in [
+            ("2024-02-30", "2024-02-30: expected yyyy-mm-dd"),
+            ("1969-12-31", "1969-12-31: expected yyyy-mm-dd"),
+            ("2025-01-01", "--from must come before --to"),
+        ] {
+            let line = format!(
+                "report --grantor {} --from {} --to 2025-01-01",
+                Pubkey::new_unique(),
+                from
+            );
+            let e = parse(&line).unwrap_err();
+            assert!(e.starts_with(error), "{:?} failed with {:?}", line, e);
+        }
+    }
+}
@@ -0,0 +1,1832 @@
+//! Off-chain helpers for finding and reading vesting accounts over RPC.
+//! Lives in token-vesting-sdk so that the on-chain program doesn't pull in solana-client.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryInto,
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+use num_traits::FromPrimitive;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig,
+        RpcTransactionConfig,
+    },
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_program::{
+    clock::Clock, instruction::Instruction, message::Message, native_token::lamports_to_sol,
+    program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, system_program, sysvar,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use spl_token::state::Mint;
+
+use crate::{
+    instruction::{
+        check_revision, child_seeds, create, export_contract, find_destination_index,
+        find_migration_authority, find_vesting_address, import_contract, init, init_categorized,
+        init_linear, required_lamports_for, revoke, ContractMigration, CreateOptions, Schedule,
+        Seeds, VestingInstruction, SCHEDULE_SIZE,
+    },
+    state::{
+        unpack_categories, unpack_schedules, AccountType, CompressedVestingTree, ContractState,
+        DestinationIndex, ProgramAccount, RevokePolicy, ScheduleCategory, ScheduleLayout,
+        UnlockPolicy, VestingSchedule, VestingScheduleHeader, ACCOUNT_MAGIC, ACCOUNT_TYPE_OFFSET,
+        BASELINE_HEADER_LEN, CONTRACT_STATE_OFFSET, GRANTER_OFFSET, HAS_CLAIMABLE_OFFSET,
+        INIT_PAYER_OFFSET, INSOLVENT_OFFSET, NEXT_RELEASE_TIME_OFFSET, PAUSED_OFFSET,
+        UNLOCK_POLICY_OFFSET,
+    },
+};
+
+/// getMultipleAccounts refuses more than 100 keys per call
+pub const MAX_ACCOUNTS_PER_PAGE: usize = 100;
+
+// ----------------------------------------------------------------------------- config
+
+/// Where to send requests. Parses from what a `--url` flag takes: a cluster name or a custom http(s) url
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Cluster {
+    Localnet,
+    Devnet,
+    Testnet,
+    Mainnet,
+    Custom(String),
+}
+
+impl Cluster {
+    pub fn url(&self) -> &str {
+        match self {
+            Cluster::Localnet => "http://localhost:8899",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Custom(url) => url,
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "localnet" | "localhost" => Ok(Cluster::Localnet),
+            "devnet" => Ok(Cluster::Devnet),
+            "testnet" => Ok(Cluster::Testnet),
+            "mainnet" | "mainnet-beta" => Ok(Cluster::Mainnet),
+            _ if s.starts_with("http://") || s.starts_with("https://") => {
+                Ok(Cluster::Custom(s.to_string()))
+            }
+            _ => Err(format!(
+                "unknown cluster {:?}, expected localnet, devnet, testnet, mainnet or an http(s) url",
+                s
+            )),
+        }
+    }
+}
+
+/// Settings shared by every client helper, so that none of them assumes a cluster or a commitment level
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    /// Used for every read, and as the level transactions are confirmed at
+    pub commitment: CommitmentConfig,
+    pub cluster: Cluster,
+    /// Per RPC request
+    pub timeout: Duration,
+    /// Sends transactions without simulating them first - faster, but failures only show up on-chain
+    pub skip_preflight: bool,
+    /// Pins what cluster_time returns instead of reading the Clock sysvar, for tests and dry runs
+    pub clock_override: Option<u64>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            commitment: CommitmentConfig::confirmed(),
+            cluster: Cluster::Localnet,
+            timeout: Duration::from_secs(30),
+            skip_preflight: false,
+            clock_override: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    pub fn rpc_client(&self) -> RpcClient {
+        RpcClient::new_with_timeout_and_commitment(
+            self.cluster.url().to_string(),
+            self.timeout,
+            self.commitment,
+        )
+    }
+
+    pub fn send_config(&self) -> RpcSendTransactionConfig {
+        RpcSendTransactionConfig {
+            skip_preflight: self.skip_preflight,
+            preflight_commitment: Some(self.commitment.commitment),
+            ..RpcSendTransactionConfig::default()
+        }
+    }
+}
+
+/// Sends a signed transaction and waits until it reaches the configured commitment
+pub fn send_transaction(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    transaction: &Transaction,
+) -> ClientResult<Signature> {
+    rpc_client.send_and_confirm_transaction_with_spinner_and_config(
+        transaction,
+        config.commitment,
+        config.send_config(),
+    )
+}
+
+// ----------------------------------------------------------------------------- gPA filters
+
+/// Matches the magic and type byte every program account starts with
+pub fn account_type_filter(account_type: AccountType) -> RpcFilterType {
+    let mut prefix = ACCOUNT_MAGIC.to_vec();
+    prefix.push(account_type as u8);
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, prefix))
+}
+
+/// Contracts still on the baseline layout, which need a MigrateLayout. The filter only matches their is_initialized
+/// byte, which other accounts can have set at that offset too - check what comes back with is_baseline_layout
+pub fn baseline_vesting_account_filters() -> Vec<RpcFilterType> {
+    vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+        BASELINE_HEADER_LEN - 1,
+        vec![1],
+    ))]
+}
+
+/// Vesting accounts that went through Create. Their size depends on the number of schedules, so no dataSize filter
+pub fn vesting_account_filters() -> Vec<RpcFilterType> {
+    vec![account_type_filter(AccountType::VestingSchedule)]
+}
+
+/// Vesting accounts anyone can submit Unlock for, ie whose unlock policy is still UnlockPolicy::Permissionless and
+/// that the granter hasn't paused
+pub fn permissionless_vesting_account_filters() -> Vec<RpcFilterType> {
+    vec![
+        account_type_filter(AccountType::VestingSchedule),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            UNLOCK_POLICY_OFFSET,
+            vec![UnlockPolicy::Permissionless as u8],
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(PAUSED_OFFSET, vec![0])),
+    ]
+}
+
+/// Vesting accounts whose has_claimable hint is set - candidates only, since the hint goes stale as time passes.
+/// Contracts that only became claimable since their last write show up once RefreshClaimable has been through them
+pub fn has_claimable_vesting_account_filters() -> Vec<RpcFilterType> {
+    vec![
+        account_type_filter(AccountType::VestingSchedule),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(HAS_CLAIMABLE_OFFSET, vec![1])),
+    ]
+}
+
+/// Vesting accounts that VerifySolvency last found short of tokens
+pub fn insolvent_vesting_account_filters() -> Vec<RpcFilterType> {
+    vec![
+        account_type_filter(AccountType::VestingSchedule),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(INSOLVENT_OFFSET, vec![1])),
+    ]
+}
+
+/// Vesting accounts in `state`. Contracts nothing has been written to since ContractState replaced is_initialized
+/// still hold LEGACY_INITIALIZED_STATE and match neither Active nor Exhausted
+pub fn contract_state_vesting_account_filters(state: ContractState) -> Vec<RpcFilterType> {
+    vec![
+        account_type_filter(AccountType::VestingSchedule),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            CONTRACT_STATE_OFFSET,
+            vec![state as u8],
+        )),
+    ]
+}
+
+/// Vesting accounts created by `granter`
+pub fn granter_vesting_account_filters(granter: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        account_type_filter(AccountType::VestingSchedule),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            GRANTER_OFFSET,
+            granter.to_bytes().to_vec(),
+        )),
+    ]
+}
+
+/// Vesting accounts `payer` Init-ed that never got a contract, which CloseUninitialized can give the rent of back.
+/// They start with zeroes where the magic and the type byte go
+pub fn uninitialized_vesting_account_filters(payer: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![0; ACCOUNT_TYPE_OFFSET + 1])),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            CONTRACT_STATE_OFFSET,
+            vec![ContractState::Initialized as u8],
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            INIT_PAYER_OFFSET,
+            payer.to_bytes().to_vec(),
+        )),
+    ]
+}
+
+pub fn compressed_vesting_tree_filters() -> Vec<RpcFilterType> {
+    vec![
+        account_type_filter(AccountType::CompressedVestingTree),
+        RpcFilterType::DataSize(CompressedVestingTree::LEN as u64),
+    ]
+}
+
+// ----------------------------------------------------------------------------- clock
+
+/// The cluster's unix_timestamp, as the program will see it when comparing against release times. The local clock
+/// can be off from the validators' by minutes, so anything predicting claimability goes by this instead.
+/// Returns config.clock_override without a request when it's set
+pub fn cluster_time(rpc_client: &RpcClient, config: &ClientConfig) -> ClientResult<u64> {
+    if let Some(now) = config.clock_override {
+        return Ok(now);
+    }
+    let clock_account = rpc_client
+        .get_account_with_commitment(&sysvar::clock::id(), config.commitment)?
+        .value
+        .ok_or_else(|| custom_error("clock sysvar not found"))?;
+    let clock: Clock = bincode::deserialize(&clock_account.data).map_err(custom_error)?;
+    Ok(clock.unix_timestamp.max(0) as u64)
+}
+
+// ----------------------------------------------------------------------------- contracts
+
+/// Header, schedules and categories of the vesting contract at `vesting_account_key`. A contract still on the baseline
+/// layout comes back the way MigrateLayout would rewrite it
+pub fn fetch_contract(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+) -> ClientResult<(
+    VestingScheduleHeader,
+    Vec<VestingSchedule>,
+    Vec<ScheduleCategory>,
+)> {
+    let account = rpc_client
+        .get_account_with_commitment(vesting_account_key, config.commitment)?
+        .value
+        .ok_or_else(|| custom_error(format!("no account at {}", vesting_account_key)))?;
+    if account.owner != *program_id {
+        return Err(custom_error(format!(
+            "{} isn't owned by {}",
+            vesting_account_key, program_id
+        )));
+    }
+    match ProgramAccount::unpack_any(&account.data).map_err(custom_error)? {
+        ProgramAccount::Vesting {
+            header,
+            schedules,
+            categories,
+        }
+        | ProgramAccount::MigratedVesting {
+            header,
+            schedules,
+            categories,
+        } => Ok((header, schedules, categories)),
+        _ => Err(custom_error(format!(
+            "{} isn't a vesting contract",
+            vesting_account_key
+        ))),
+    }
+}
+
+// ----------------------------------------------------------------------------- crank
+
+/// Vesting accounts with something to unlock at cluster_time, along with their next_release_time.
+///
+/// Memcmp filters can only match exact bytes, so the comparison against the clock happens here - but the RPC node
+/// only sends back the 8 bytes of next_release_time per account, which keeps a full scan cheap.
+/// Fully claimed contracts hold NO_PENDING_RELEASE and never show up, nor do contracts that opted out of permissionless
+/// unlock with SetUnlockPolicy or that the granter paused - a crank's Unlock would only fail on them
+pub fn due_contracts(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+) -> ClientResult<Vec<(Pubkey, u64)>> {
+    let now = cluster_time(rpc_client, config)?;
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(permissionless_vesting_account_filters()),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: NEXT_RELEASE_TIME_OFFSET,
+                    length: 8,
+                }),
+                commitment: Some(config.commitment),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(key, account)| {
+            let next_release_time = u64::from_le_bytes(account.data.as_slice().try_into().ok()?);
+            (next_release_time <= now).then_some((key, next_release_time))
+        })
+        .collect())
+}
+
+// ----------------------------------------------------------------------------- destination index
+
+/// The vesting account the destination index of (mint, destination) points at - a single getAccountInfo instead
+/// of a gPA scan. None if the index was never written, or its contract has moved on to another destination
+pub fn find_contract_by_destination(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    destination_token_account: &Pubkey,
+) -> ClientResult<Option<Pubkey>> {
+    let (index_key, _) = find_destination_index(program_id, mint, destination_token_account);
+    let account = rpc_client
+        .get_account_with_commitment(&index_key, config.commitment)?
+        .value;
+    Ok(account
+        .filter(|a| a.owner == *program_id)
+        .and_then(|a| DestinationIndex::unpack(&a.data).ok())
+        .map(|index| index.vesting_account)
+        .filter(|key| *key != Pubkey::default()))
+}
+
+// ----------------------------------------------------------------------------- seed recovery
+
+/// Nonce and seeds of a contract from InitGrant, tried against each of `beneficiaries` (the wallet the granter picked
+/// at InitGrant, which the header doesn't show) and nonces below `max_nonce`. None if the contract's seeds weren't
+/// derived that way, eg picked at random
+pub fn recover_seed(
+    program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    header: &VestingScheduleHeader,
+    beneficiaries: &[Pubkey],
+    max_nonce: u64,
+) -> Option<(u64, Seeds)> {
+    beneficiaries.iter().find_map(|beneficiary| {
+        (0..max_nonce).find_map(|nonce| {
+            let (key, seeds) = find_vesting_address(
+                program_id,
+                &header.granter_address,
+                beneficiary,
+                &header.mint_address,
+                nonce,
+            );
+            (key == *vesting_account_key).then_some((nonce, seeds))
+        })
+    })
+}
+
+/// recover_seed for a live contract, trying the owners of its current destination and of the one before the last
+/// ChangeFutureDestination. A beneficiary that has moved on from both has to go through recover_seed directly
+pub fn fetch_recovered_seed(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    max_nonce: u64,
+) -> ClientResult<Option<(u64, Seeds)>> {
+    let account = rpc_client
+        .get_account_with_commitment(vesting_account_key, config.commitment)?
+        .value
+        .filter(|a| a.owner == *program_id)
+        .ok_or_else(|| custom_error("vesting account not found"))?;
+    let header = VestingScheduleHeader::unpack_account(&account.data).map_err(custom_error)?;
+    let mut destinations = vec![header.destination_address];
+    if header.legacy_destination_address != Pubkey::default() {
+        destinations.push(header.legacy_destination_address);
+    }
+    // the base of a Token-2022 account is laid out the same, extensions go after it
+    let beneficiaries: Vec<Pubkey> = rpc_client
+        .get_multiple_accounts(&destinations)?
+        .into_iter()
+        .flatten()
+        .filter_map(|account| {
+            spl_token::state::Account::unpack(account.data.get(..spl_token::state::Account::LEN)?)
+                .ok()
+        })
+        .map(|token_account| token_account.owner)
+        .collect();
+    Ok(recover_seed(
+        program_id,
+        vesting_account_key,
+        &header,
+        &beneficiaries,
+        max_nonce,
+    ))
+}
+
+// ----------------------------------------------------------------------------- preflight
+
+/// A payer that can't fund what it's about to sign for
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InsufficientLamports {
+    pub payer: Pubkey,
+    pub balance: u64,
+    /// Lamports the transaction moves out of the payer, eg rent for a new account
+    pub required: u64,
+    pub fee: u64,
+}
+
+impl std::fmt::Display for InsufficientLamports {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "payer {} has {} SOL, needs {} SOL for rent plus {} SOL in fees",
+            self.payer,
+            lamports_to_sol(self.balance),
+            lamports_to_sol(self.required),
+            lamports_to_sol(self.fee)
+        )
+    }
+}
+
+/// Whether `balance` covers `required` plus `fee`, without going to the cluster
+pub fn check_lamports(
+    payer: &Pubkey,
+    balance: u64,
+    required: u64,
+    fee: u64,
+) -> Result<(), InsufficientLamports> {
+    match required.checked_add(fee) {
+        Some(total) if balance >= total => Ok(()),
+        _ => Err(InsufficientLamports {
+            payer: *payer,
+            balance,
+            required,
+            fee,
+        }),
+    }
+}
+
+/// Fails with an InsufficientLamports message if `payer` can't cover `required` lamports (eg from
+/// required_lamports_for) plus the fee of `instructions`. Otherwise a payer short of lamports only shows up as a
+/// transport error, which looks like any other failed send
+pub fn check_payer_balance(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    required: u64,
+) -> ClientResult<()> {
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let message = Message::new_with_blockhash(instructions, Some(payer), &blockhash);
+    let fee = rpc_client.get_fee_for_message(&message)?;
+    let balance = rpc_client
+        .get_balance_with_commitment(payer, config.commitment)?
+        .value;
+    check_lamports(payer, balance, required, fee).map_err(custom_error)
+}
+
+/// Builds an Init for `number_of_schedules` schedules, once check_payer_balance has found that `payer` can afford it
+pub fn checked_init(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    seeds: Seeds,
+    number_of_schedules: u32,
+    schedule_layout: ScheduleLayout,
+) -> ClientResult<Instruction> {
+    let vesting_account_key =
+        Pubkey::create_program_address(&[&seeds], program_id).map_err(custom_error)?;
+    let (system_program_id, rent_sysvar_id) = (system_program::id(), sysvar::rent::id());
+    let instruction = match schedule_layout {
+        ScheduleLayout::Plain => init(
+            &system_program_id,
+            &rent_sysvar_id,
+            program_id,
+            payer,
+            &vesting_account_key,
+            seeds,
+            number_of_schedules,
+        ),
+        ScheduleLayout::Categorized => init_categorized(
+            &system_program_id,
+            &rent_sysvar_id,
+            program_id,
+            payer,
+            &vesting_account_key,
+            seeds,
+            number_of_schedules,
+        ),
+        // always a single schedule
+        ScheduleLayout::Linear => init_linear(
+            &system_program_id,
+            &rent_sysvar_id,
+            program_id,
+            payer,
+            &vesting_account_key,
+            seeds,
+        ),
+    }
+    .map_err(custom_error)?;
+
+    let rent_account = rpc_client
+        .get_account_with_commitment(&sysvar::rent::id(), config.commitment)?
+        .value
+        .ok_or_else(|| custom_error("rent sysvar not found"))?;
+    let rent: Rent = bincode::deserialize(&rent_account.data).map_err(custom_error)?;
+    check_payer_balance(
+        rpc_client,
+        config,
+        payer,
+        std::slice::from_ref(&instruction),
+        required_lamports_for(number_of_schedules, schedule_layout, &rent),
+    )?;
+    Ok(instruction)
+}
+
+/// `instructions` changing a contract, behind a CheckRevision against `expected_revision` - the revision they were
+/// built from (ContractOutput::revision). If the granter or the destination owner changed the contract in the
+/// meantime, the transaction fails with StaleRevision instead of going through on top of that change. Revoke and
+/// ChangeDestination can carry the revision themselves, see instruction::revoke_at_revision
+pub fn with_expected_revision(
+    program_id: &Pubkey,
+    seeds: Seeds,
+    expected_revision: u32,
+    instructions: Vec<Instruction>,
+) -> ClientResult<Vec<Instruction>> {
+    let vesting_account_key =
+        Pubkey::create_program_address(&[&seeds], program_id).map_err(custom_error)?;
+    let mut checked =
+        vec![
+            check_revision(program_id, &vesting_account_key, seeds, expected_revision)
+                .map_err(custom_error)?,
+        ];
+    checked.extend(instructions);
+    Ok(checked)
+}
+
+// ----------------------------------------------------------------------------- revoke
+
+/// Revokes each of `contracts` (vesting account and seeds) in a transaction of its own, signed and paid for by the
+/// granter, so that one failing doesn't hold the others back. Comes back with a result per contract, in order.
+///
+/// The unvested tokens go to the granter's associated token account, or get burned for a contract created with
+/// RevokePolicy::Burn. Contracts another granter set up fail here, without a transaction
+pub fn revoke_contracts(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    granter: &Keypair,
+    contracts: &[(Pubkey, Seeds)],
+) -> Vec<ClientResult<Signature>> {
+    contracts
+        .iter()
+        .map(|(vesting_account_key, seeds)| {
+            let (header, _, _) =
+                fetch_contract(rpc_client, config, program_id, vesting_account_key)?;
+            if header.granter_address != granter.pubkey() {
+                return Err(custom_error(format!(
+                    "{} was granted by {}",
+                    vesting_account_key, header.granter_address
+                )));
+            }
+            let mint = header.mint_address;
+            let granter_token_account_key = match header.revoke_policy {
+                RevokePolicy::Burn => mint,
+                RevokePolicy::ReturnToGranter => {
+                    spl_associated_token_account::get_associated_token_address(
+                        &granter.pubkey(),
+                        &mint,
+                    )
+                }
+            };
+            let instruction = revoke(
+                program_id,
+                &spl_token::id(),
+                &sysvar::clock::id(),
+                &granter.pubkey(),
+                vesting_account_key,
+                &spl_associated_token_account::get_associated_token_address(
+                    vesting_account_key,
+                    &mint,
+                ),
+                &granter_token_account_key,
+                *seeds,
+            )
+            .map_err(custom_error)?;
+            send_signed(rpc_client, config, &[instruction], &[granter])
+        })
+        .collect()
+}
+
+// ----------------------------------------------------------------------------- migration
+
+/// Instructions moving a live contract from `old_program_id` to `new_program_id`. They're meant to go out in a single
+/// transaction, so the contract never sits half-way (exported but not yet imported) and neither side sees any downtime:
+/// 1. escrow token account owned by the new deployment's migration authority, and the new vesting token account
+/// 2. ExportContract on the old deployment
+/// 3. ImportContract on the new deployment
+///
+/// The transaction has to be signed by the payer, the granter and the destination token account owner. ImportContract
+/// only takes contracts from the deployments in ids::migration_sources, so `old_program_id` has to be one of them
+pub fn migrate_contract_instructions(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    old_program_id: &Pubkey,
+    new_program_id: &Pubkey,
+    payer_key: &Pubkey,
+    destination_token_account_owner_key: &Pubkey,
+    old_seeds: Seeds,
+    new_seeds: Seeds,
+) -> ClientResult<Vec<Instruction>> {
+    let old_vesting_account_key =
+        Pubkey::create_program_address(&[&old_seeds], old_program_id).map_err(custom_error)?;
+    let new_vesting_account_key =
+        Pubkey::create_program_address(&[&new_seeds], new_program_id).map_err(custom_error)?;
+
+    let data = rpc_client
+        .get_account_with_commitment(&old_vesting_account_key, config.commitment)?
+        .value
+        .ok_or_else(|| custom_error(format!("{} not found", old_vesting_account_key)))?
+        .data;
+    let header = VestingScheduleHeader::unpack_account(&data).map_err(custom_error)?;
+    let mint = header.mint_address;
+
+    let (migration_authority_key, _) =
+        find_migration_authority(new_program_id, &old_vesting_account_key);
+    let escrow_token_account_key =
+        spl_associated_token_account::get_associated_token_address(&migration_authority_key, &mint);
+    let old_vesting_token_account_key =
+        spl_associated_token_account::get_associated_token_address(&old_vesting_account_key, &mint);
+    let new_vesting_token_account_key =
+        spl_associated_token_account::get_associated_token_address(&new_vesting_account_key, &mint);
+
+    let migration = ContractMigration {
+        new_program_id: *new_program_id,
+        old_vesting_account_key,
+        escrow_token_account_key,
+    };
+
+    Ok(vec![
+        spl_associated_token_account::create_associated_token_account(
+            payer_key,
+            &migration_authority_key,
+            &mint,
+        ),
+        spl_associated_token_account::create_associated_token_account(
+            payer_key,
+            &new_vesting_account_key,
+            &mint,
+        ),
+        export_contract(
+            old_program_id,
+            &spl_token::id(),
+            &old_vesting_token_account_key,
+            &header.granter_address,
+            &header.destination_address,
+            destination_token_account_owner_key,
+            &migration,
+            old_seeds,
+        )
+        .map_err(custom_error)?,
+        import_contract(
+            &spl_token::id(),
+            &system_program::id(),
+            &sysvar::rent::id(),
+            payer_key,
+            &new_vesting_account_key,
+            &new_vesting_token_account_key,
+            &migration,
+            new_seeds,
+        )
+        .map_err(custom_error)?,
+    ])
+}
+
+pub(crate) fn custom_error(e: impl std::fmt::Display) -> ClientError {
+    ClientErrorKind::Custom(e.to_string()).into()
+}
+
+// ----------------------------------------------------------------------------- index
+
+#[derive(Clone, Debug)]
+pub struct ScanConfig {
+    /// How many accounts to fetch per getMultipleAccounts call. Capped at MAX_ACCOUNTS_PER_PAGE
+    pub page_size: usize,
+    /// How many pages are fetched at the same time
+    pub concurrency: usize,
+    /// Sleep between two pages on the same worker - the main knob for staying under public RPC rate limits
+    pub page_delay: Duration,
+    /// Cached headers fetched less than this many slots ago are not re-fetched on refresh()
+    pub max_age_slots: u64,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            page_size: MAX_ACCOUNTS_PER_PAGE,
+            concurrency: 4,
+            page_delay: Duration::from_millis(100),
+            max_age_slots: 150, //~1 minute
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct IndexedContract {
+    pub header: VestingScheduleHeader,
+    /// Slot at which the header was last fetched
+    pub slot: u64,
+}
+
+/// A locally cached view of every vesting account owned by the program.
+///
+/// Instead of one getProgramAccounts call returning every byte of every account, refresh():
+/// 1. lists the program's vesting accounts with a zero-length dataSlice (keys only)
+/// 2. fetches only the header bytes of new or stale accounts, in pages, on a few worker threads
+pub struct VestingIndex<'a> {
+    rpc_client: &'a RpcClient,
+    commitment: CommitmentConfig,
+    program_id: Pubkey,
+    config: ScanConfig,
+    contracts: HashMap<Pubkey, IndexedContract>,
+    last_refresh_slot: u64,
+}
+
+impl<'a> VestingIndex<'a> {
+    pub fn new(
+        rpc_client: &'a RpcClient,
+        client_config: &ClientConfig,
+        program_id: Pubkey,
+        config: ScanConfig,
+    ) -> Self {
+        Self {
+            rpc_client,
+            commitment: client_config.commitment,
+            program_id,
+            config,
+            contracts: HashMap::new(),
+            last_refresh_slot: 0,
+        }
+    }
+
+    pub fn contracts(&self) -> &HashMap<Pubkey, IndexedContract> {
+        &self.contracts
+    }
+
+    pub fn get(&self, vesting_account_key: &Pubkey) -> Option<&IndexedContract> {
+        self.contracts.get(vesting_account_key)
+    }
+
+    pub fn last_refresh_slot(&self) -> u64 {
+        self.last_refresh_slot
+    }
+
+    /// Brings the index up to date. Returns the number of headers that were (re)fetched
+    pub fn refresh(&mut self) -> ClientResult<usize> {
+        let current_slot = self.rpc_client.get_slot_with_commitment(self.commitment)?;
+
+        // ----------------------------------------------------------------------------- 1 keys only
+        let keys = self
+            .rpc_client
+            .get_program_accounts_with_config(
+                &self.program_id,
+                RpcProgramAccountsConfig {
+                    filters: Some(vesting_account_filters()),
+                    account_config: self.account_config(0),
+                    ..RpcProgramAccountsConfig::default()
+                },
+            )?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<HashSet<Pubkey>>();
+
+        // closed accounts drop out of the index
+        self.contracts.retain(|key, _| keys.contains(key));
+
+        // ----------------------------------------------------------------------------- 2 stale headers
+        let max_age_slots = self.config.max_age_slots;
+        let stale = keys
+            .into_iter()
+            .filter(|key| match self.contracts.get(key) {
+                Some(c) => current_slot.saturating_sub(c.slot) >= max_age_slots,
+                None => true,
+            })
+            .collect::<Vec<Pubkey>>();
+
+        let fetched = self.fetch_headers(&stale)?;
+        let refreshed = fetched.len();
+        for (key, contract) in fetched {
+            self.contracts.insert(key, contract);
+        }
+
+        self.last_refresh_slot = current_slot;
+        Ok(refreshed)
+    }
+
+    fn fetch_headers(&self, keys: &[Pubkey]) -> ClientResult<Vec<(Pubkey, IndexedContract)>> {
+        let page_size = self.config.page_size.clamp(1, MAX_ACCOUNTS_PER_PAGE);
+        let pages = keys.chunks(page_size).collect::<Vec<&[Pubkey]>>();
+        let concurrency = self.config.concurrency.max(1);
+
+        // each worker takes every n-th page, so pages are spread evenly without a shared queue
+        let results = thread::scope(|s| {
+            let workers = (0..concurrency)
+                .map(|worker| {
+                    let pages = &pages;
+                    s.spawn(move || {
+                        let mut out = vec![];
+                        for page in pages.iter().skip(worker).step_by(concurrency) {
+                            out.append(&mut self.fetch_page(page)?);
+                            thread::sleep(self.config.page_delay);
+                        }
+                        Ok(out)
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .map(|w| w.join().expect("vesting index worker panicked"))
+                .collect::<Vec<ClientResult<Vec<(Pubkey, IndexedContract)>>>>()
+        });
+
+        let mut fetched = vec![];
+        for r in results {
+            fetched.append(&mut r?);
+        }
+        Ok(fetched)
+    }
+
+    fn fetch_page(&self, page: &[Pubkey]) -> ClientResult<Vec<(Pubkey, IndexedContract)>> {
+        let response = self.rpc_client.get_multiple_accounts_with_config(
+            page,
+            self.account_config(VestingScheduleHeader::LEN),
+        )?;
+        let slot = response.context.slot;
+
+        Ok(page
+            .iter()
+            .zip(response.value)
+            .filter_map(|(key, account)| {
+                // the account could have been closed since it was listed
+                let header = VestingScheduleHeader::unpack(&account?.data).ok()?;
+                Some((*key, IndexedContract { header, slot }))
+            })
+            .collect())
+    }
+
+    fn account_config(&self, length: usize) -> RpcAccountInfoConfig {
+        RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig { offset: 0, length }),
+            commitment: Some(self.commitment),
+            ..RpcAccountInfoConfig::default()
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------- report
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum LedgerKind {
+    /// Tokens moved into a contract by Create
+    Grant,
+    /// Released tokens paid out to the destination
+    Claim,
+    /// Unvested tokens sent back to the granter by Revoke or RevokeBatch
+    Revoke,
+    /// Lamports a transaction the granter paid for cost
+    Fee,
+    /// Lamports the granter put into a contract's gas escrow, at Create or with TopUpGas
+    GasDeposit,
+    /// A schedule releasing in the report's window that hasn't been claimed yet. Only ever comes from current state
+    Pending,
+}
+
+impl LedgerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerKind::Grant => "grant",
+            LedgerKind::Claim => "claim",
+            LedgerKind::Revoke => "revoke",
+            LedgerKind::Fee => "fee",
+            LedgerKind::GasDeposit => "gas_deposit",
+            LedgerKind::Pending => "pending",
+        }
+    }
+}
+
+/// One line of a granter's report
+#[derive(Clone, Debug, PartialEq)]
+pub struct LedgerEntry {
+    /// Block time of the transaction, or the release time of a pending schedule
+    pub time: u64,
+    pub kind: LedgerKind,
+    pub amount: u64,
+    /// None for amounts in lamports
+    pub mint: Option<Pubkey>,
+    pub contract: Pubkey,
+    /// None for pending schedules
+    pub signature: Option<Signature>,
+    /// What the amount vests for, where that's known: pending schedules, and grants to categorized contracts. Claims
+    /// and revokes don't say which schedules they touched, so they have none
+    pub category: Option<ScheduleCategory>,
+}
+
+/// What a report needs from one transaction touching a vesting account
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportTransaction {
+    pub signature: Signature,
+    pub time: u64,
+    pub fee_payer: Pubkey,
+    pub fee: u64,
+    /// Data of every instruction addressed to the vesting program
+    pub vesting_instructions: Vec<Vec<u8>>,
+    /// Change of the vesting spl-token account's balance
+    pub token_delta: i128,
+}
+
+impl ReportTransaction {
+    pub fn ledger_entries(
+        &self,
+        contract: &Pubkey,
+        header: &VestingScheduleHeader,
+    ) -> Vec<LedgerEntry> {
+        let entry = |kind, amount, mint| LedgerEntry {
+            time: self.time,
+            kind,
+            amount,
+            mint,
+            contract: *contract,
+            signature: Some(self.signature),
+            category: None,
+        };
+        let mut entries = vec![];
+
+        // anyone can crank an Unlock, only count lamports that came out of the granter's wallet
+        if self.fee_payer == header.granter_address {
+            entries.push(entry(LedgerKind::Fee, self.fee, None));
+            for data in self.vesting_instructions.iter() {
+                if let Some(deposit) = gas_deposit(data).filter(|d| *d > 0) {
+                    entries.push(entry(LedgerKind::GasDeposit, deposit, None));
+                }
+            }
+        }
+
+        let amount = self.token_delta.unsigned_abs().min(u64::MAX as u128) as u64;
+        let revoked = self
+            .vesting_instructions
+            .iter()
+            .any(|data| matches!(data.first(), Some(7) | Some(30)));
+        let kind = match self.token_delta {
+            0 => None,
+            d if d > 0 => Some(LedgerKind::Grant),
+            _ if revoked => Some(LedgerKind::Revoke),
+            _ => Some(LedgerKind::Claim),
+        };
+        match (kind, granted_categories(&self.vesting_instructions)) {
+            // a categorized Create says how much went into each bucket
+            (Some(LedgerKind::Grant), Some(granted)) => {
+                for (category, amount) in granted {
+                    entries.push(LedgerEntry {
+                        category: Some(category),
+                        ..entry(LedgerKind::Grant, amount, Some(header.mint_address))
+                    });
+                }
+            }
+            (Some(kind), _) => entries.push(entry(kind, amount, Some(header.mint_address))),
+            (None, _) => {}
+        }
+        entries
+    }
+}
+
+/// Amount granted per category by the categorized Create among `instructions`, if there is one. Reads the bytes
+/// directly, like gas_deposit
+fn granted_categories(instructions: &[Vec<u8>]) -> Option<Vec<(ScheduleCategory, u64)>> {
+    const CREATE_FIXED_LEN: usize = 158;
+    let rest = instructions
+        .iter()
+        .find(|data| data.first() == Some(&18))
+        .map(|data| &data[1..])?;
+    let item_len = SCHEDULE_SIZE + 1;
+    let count = rest.len().checked_sub(CREATE_FIXED_LEN)? / item_len;
+    if rest.len() != CREATE_FIXED_LEN + count * item_len {
+        return None;
+    }
+    let categories_start = CREATE_FIXED_LEN + count * SCHEDULE_SIZE;
+
+    let mut granted = BTreeMap::new();
+    for i in 0..count {
+        let amount =
+            VestingInstruction::unpack_u64(rest, CREATE_FIXED_LEN + i * SCHEDULE_SIZE + 8).ok()?;
+        let category = ScheduleCategory::from_u8(rest[categories_start + i])?;
+        let total: &mut u64 = granted.entry(category).or_default();
+        *total = total.saturating_add(amount);
+    }
+    Some(granted.into_iter().collect())
+}
+
+/// Lamports a Create or TopUpGas instruction moves into the gas escrow. Reads the bytes directly rather than going
+/// through VestingInstruction::unpack, which logs
+fn gas_deposit(data: &[u8]) -> Option<u64> {
+    const CREATE_FIXED_LEN: usize = 158;
+    let (tag, rest) = data.split_first()?;
+    match tag {
+        // the upstream Create layout (bonfida-compat) has no gas fields, and never has this length
+        1 if rest.len() >= CREATE_FIXED_LEN
+            && rest.len() % SCHEDULE_SIZE == CREATE_FIXED_LEN % SCHEDULE_SIZE =>
+        {
+            VestingInstruction::unpack_u64(rest, 105).ok()
+        }
+        // a categorized Create, same fixed part
+        18 if rest.len() >= CREATE_FIXED_LEN => VestingInstruction::unpack_u64(rest, 105).ok(),
+        11 => VestingInstruction::unpack_u64(rest, 32).ok(),
+        _ => None,
+    }
+}
+
+/// Schedules of a contract releasing in [from, to) that are still waiting to be claimed
+pub fn pending_entries(
+    contract: &Pubkey,
+    header: &VestingScheduleHeader,
+    schedules: &[VestingSchedule],
+    categories: &[ScheduleCategory],
+    from: u64,
+    to: u64,
+) -> Vec<LedgerEntry> {
+    schedules
+        .iter()
+        .zip(categories.iter())
+        .filter(|(s, _)| s.amount > 0 && s.release_time >= from && s.release_time < to)
+        .map(|(s, category)| LedgerEntry {
+            time: s.release_time,
+            kind: LedgerKind::Pending,
+            amount: s.amount,
+            mint: Some(header.mint_address),
+            contract: *contract,
+            signature: None,
+            category: Some(*category),
+        })
+        .collect()
+}
+
+/// Entries of a report added up by kind, asset and category
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryTotal {
+    pub kind: LedgerKind,
+    pub mint: Option<Pubkey>,
+    pub category: Option<ScheduleCategory>,
+    pub amount: u64,
+    /// How many entries went into the total
+    pub entries: usize,
+}
+
+/// Adds up a report by kind, asset and category, eg for a DAO reporting emissions by bucket. Comes out sorted, so
+/// that each bucket lands in the same place from one report to the next
+pub fn category_totals(entries: &[LedgerEntry]) -> Vec<CategoryTotal> {
+    let mut totals: BTreeMap<_, (u64, usize)> = BTreeMap::new();
+    for e in entries {
+        let total = totals.entry((e.kind, e.mint, e.category)).or_default();
+        total.0 = total.0.saturating_add(e.amount);
+        total.1 += 1;
+    }
+    totals
+        .into_iter()
+        .map(
+            |((kind, mint, category), (amount, entries))| CategoryTotal {
+                kind,
+                mint,
+                category,
+                amount,
+                entries,
+            },
+        )
+        .collect()
+}
+
+/// Everything the contracts created by `granter` saw between `from` (inclusive) and `to` (exclusive), sorted by time.
+///
+/// Current state alone only says what is still pending, since claimed schedules are zeroed out. With `with_history`,
+/// the signature history of every vesting account is walked as well, which costs a getTransaction per transaction in
+/// the window. Either way, contracts whose vesting account has been closed are left out.
+/// A transaction touching several of the granter's contracts (eg RevokeBatch) only has its fee counted once
+pub fn granter_report(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    granter: &Pubkey,
+    from: u64,
+    to: u64,
+    with_history: bool,
+) -> ClientResult<Vec<LedgerEntry>> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(granter_vesting_account_filters(granter)),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(config.commitment),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    let mut entries = vec![];
+    let mut fees_seen = HashSet::new();
+    for (contract, account) in accounts {
+        let header = VestingScheduleHeader::unpack_account(&account.data).map_err(custom_error)?;
+        let schedules =
+            unpack_schedules(&account.data[VestingScheduleHeader::LEN..header.schedules_end()])
+                .map_err(custom_error)?;
+        let categories = unpack_categories(&header, &account.data).map_err(custom_error)?;
+        entries.append(&mut pending_entries(
+            &contract,
+            &header,
+            &schedules,
+            &categories,
+            from,
+            to,
+        ));
+
+        if with_history {
+            for entry in
+                contract_history(rpc_client, config, program_id, &contract, &header, from, to)?
+            {
+                if entry.kind != LedgerKind::Fee || fees_seen.insert(entry.signature) {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+
+    entries.sort_by_key(|e| e.time);
+    Ok(entries)
+}
+
+/// Walks the signature history of one vesting account, newest first, down to `from`
+fn contract_history(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    contract: &Pubkey,
+    header: &VestingScheduleHeader,
+    from: u64,
+    to: u64,
+) -> ClientResult<Vec<LedgerEntry>> {
+    let mut entries = vec![];
+    let mut before = None;
+    loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            contract,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: None,
+                commitment: Some(config.commitment),
+            },
+        )?;
+        if page.is_empty() {
+            return Ok(entries);
+        }
+
+        for status in page.iter() {
+            let signature = Signature::from_str(&status.signature).map_err(custom_error)?;
+            before = Some(signature);
+            // not every node keeps block times for old slots
+            let time = match status.block_time {
+                Some(t) => t.max(0) as u64,
+                None => continue,
+            };
+            if time < from {
+                return Ok(entries);
+            }
+            if time >= to {
+                continue;
+            }
+            let transaction = fetch_report_transaction(
+                rpc_client, config, program_id, contract, header, signature, time,
+            )?;
+            entries.append(&mut transaction.ledger_entries(contract, header));
+        }
+    }
+}
+
+fn fetch_report_transaction(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    contract: &Pubkey,
+    header: &VestingScheduleHeader,
+    signature: Signature,
+    time: u64,
+) -> ClientResult<ReportTransaction> {
+    let confirmed = rpc_client.get_transaction_with_config(
+        &signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(config.commitment),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let meta = confirmed
+        .transaction
+        .meta
+        .ok_or_else(|| custom_error(format!("{} has no status meta", signature)))?;
+    let transaction = confirmed
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| custom_error(format!("can't decode {}", signature)))?;
+
+    let keys = transaction.message.static_account_keys();
+    let vesting_instructions = transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(program_id))
+        .map(|ix| ix.data.clone())
+        .collect();
+    let token_delta = vesting_token_balance(&meta.post_token_balances, contract, header)
+        - vesting_token_balance(&meta.pre_token_balances, contract, header);
+
+    Ok(ReportTransaction {
+        signature,
+        time,
+        fee_payer: keys.first().copied().unwrap_or_default(),
+        fee: meta.fee,
+        vesting_instructions,
+        token_delta,
+    })
+}
+
+/// Balance of the token accounts of the contract's mint owned by the vesting account - in practice just its
+/// vesting token account
+fn vesting_token_balance(
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+    contract: &Pubkey,
+    header: &VestingScheduleHeader,
+) -> i128 {
+    let (contract, mint) = (contract.to_string(), header.mint_address.to_string());
+    Option::<&Vec<UiTransactionTokenBalance>>::from(balances.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|b| b.mint == mint && Option::from(b.owner.as_ref()) == Some(&contract))
+        .filter_map(|b| b.ui_token_amount.amount.parse::<u64>().ok())
+        .map(i128::from)
+        .sum()
+}
+
+// ----------------------------------------------------------------------------- localnet bootstrap
+
+/// Decimals of the mint localnet_bootstrap creates
+pub const DEMO_MINT_DECIMALS: u8 = 6;
+
+const DAY: u64 = 24 * 60 * 60;
+const DEMO_TOKEN: u64 = 10u64.pow(DEMO_MINT_DECIMALS as u32);
+
+/// One contract seeded by localnet_bootstrap
+#[derive(Clone, Debug, PartialEq)]
+pub struct DemoContract {
+    pub label: &'static str,
+    pub seeds: Seeds,
+    pub vesting_account_key: Pubkey,
+    pub schedules: Vec<Schedule>,
+}
+
+/// What localnet_bootstrap left on the cluster
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalnetBootstrap {
+    pub mint: Pubkey,
+    /// The beneficiary's associated token account, the destination of every demo contract
+    pub destination_token_account_key: Pubkey,
+    pub contracts: Vec<DemoContract>,
+}
+
+/// Schedules of the demo contracts, relative to `now`: one fully released, one halfway through, one before its cliff
+/// and a monthly grant that has just started
+pub fn demo_schedules(now: u64) -> Vec<(&'static str, Vec<Schedule>)> {
+    let at = |offset_days: i64, tokens: u64| Schedule {
+        release_time: if offset_days < 0 {
+            now.saturating_sub(offset_days.unsigned_abs() * DAY)
+        } else {
+            now + offset_days as u64 * DAY
+        },
+        amount: tokens * DEMO_TOKEN,
+    };
+    vec![
+        ("vested", vec![at(-90, 500), at(-30, 500)]),
+        (
+            "halfway",
+            vec![at(-180, 250), at(-90, 250), at(90, 250), at(180, 250)],
+        ),
+        ("cliff", vec![at(365, 1_000)]),
+        (
+            "monthly",
+            (0..12).map(|month| at(month * 30 - 1, 100)).collect(),
+        ),
+    ]
+}
+
+/// Seeds a local validator with a fresh mint and the demo_schedules contracts, all paying out to `beneficiary`'s
+/// associated token account - realistic data for frontend development in one call. The program has to be deployed
+/// at `program_id` already. `payer` funds everything and becomes the mint authority and the granter.
+///
+/// Refuses to run against anything but localnet, since it mints tokens out of thin air
+pub fn localnet_bootstrap(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    mint: &Keypair,
+    beneficiary: &Pubkey,
+) -> ClientResult<LocalnetBootstrap> {
+    if config.cluster != Cluster::Localnet {
+        return Err(custom_error(format!(
+            "localnet_bootstrap only runs against localnet, not {}",
+            config.cluster.url()
+        )));
+    }
+    let deployed = rpc_client
+        .get_account_with_commitment(program_id, config.commitment)?
+        .value
+        .is_some_and(|program| program.executable);
+    if !deployed {
+        return Err(custom_error(format!(
+            "no program deployed at {}",
+            program_id
+        )));
+    }
+
+    let schedules = demo_schedules(cluster_time(rpc_client, config)?);
+    let total: u64 = schedules
+        .iter()
+        .flat_map(|(_, schedules)| schedules)
+        .map(|s| s.amount)
+        .sum();
+
+    let payer_key = payer.pubkey();
+    let mint_key = mint.pubkey();
+    let source_token_account_key =
+        spl_associated_token_account::get_associated_token_address(&payer_key, &mint_key);
+    let destination_token_account_key =
+        spl_associated_token_account::get_associated_token_address(beneficiary, &mint_key);
+
+    // ----------------------------------------------------------------------------- mint
+    let mint_rent = rpc_client.get_minimum_balance_for_rent_exemption(Mint::LEN)?;
+    let mut instructions = vec![
+        system_instruction::create_account(
+            &payer_key,
+            &mint_key,
+            mint_rent,
+            Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            &mint_key,
+            &payer_key,
+            None,
+            DEMO_MINT_DECIMALS,
+        )
+        .map_err(custom_error)?,
+        spl_associated_token_account::create_associated_token_account(
+            &payer_key, &payer_key, &mint_key,
+        ),
+        spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &mint_key,
+            &source_token_account_key,
+            &payer_key,
+            &[],
+            total,
+        )
+        .map_err(custom_error)?,
+    ];
+    if *beneficiary != payer_key {
+        instructions.push(
+            spl_associated_token_account::create_associated_token_account(
+                &payer_key,
+                beneficiary,
+                &mint_key,
+            ),
+        );
+    }
+    send_signed(rpc_client, config, &instructions, &[payer, mint])?;
+
+    // ----------------------------------------------------------------------------- contracts
+    // seeds derived from the mint, so that every bootstrap gets its own contracts
+    let base_seed = mint_key.to_bytes();
+    let mut contracts = Vec::with_capacity(schedules.len());
+    for (index, (label, schedules)) in schedules.into_iter().enumerate() {
+        let seeds = child_seeds(program_id, &base_seed, index as u32);
+        let vesting_account_key =
+            Pubkey::create_program_address(&[&seeds], program_id).map_err(custom_error)?;
+        let vesting_token_account_key = spl_associated_token_account::get_associated_token_address(
+            &vesting_account_key,
+            &mint_key,
+        );
+        let instructions = vec![
+            checked_init(
+                rpc_client,
+                config,
+                program_id,
+                &payer_key,
+                seeds,
+                schedules.len() as u32,
+                ScheduleLayout::Plain,
+            )?,
+            spl_associated_token_account::create_associated_token_account(
+                &payer_key,
+                &vesting_account_key,
+                &mint_key,
+            ),
+            // the first schedules are meant to be in the past
+            create(
+                program_id,
+                &spl_token::id(),
+                &vesting_account_key,
+                &vesting_token_account_key,
+                &payer_key,
+                &source_token_account_key,
+                &destination_token_account_key,
+                &mint_key,
+                schedules.clone(),
+                CreateOptions {
+                    allow_immediate: true,
+                    ..CreateOptions::default()
+                },
+                seeds,
+            )
+            .map_err(custom_error)?,
+        ];
+        send_signed(rpc_client, config, &instructions, &[payer])?;
+        contracts.push(DemoContract {
+            label,
+            seeds,
+            vesting_account_key,
+            schedules,
+        });
+    }
+
+    Ok(LocalnetBootstrap {
+        mint: mint_key,
+        destination_token_account_key,
+        contracts,
+    })
+}
+
+pub(crate) fn send_signed(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> ClientResult<Signature> {
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        blockhash,
+    );
+    send_transaction(rpc_client, config, &transaction)
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::top_up_gas,
+        test_utils::{schedule, vesting_schedule, VestingFixture},
+    };
+
+    #[test]
+    fn test_check_lamports() {
+        let payer = Pubkey::new_unique();
+        assert_eq!(
+            check_lamports(&payer, 2_000_005_000, 2_000_000_000, 5000),
+            Ok(())
+        );
+        let err = check_lamports(&payer, 1_500_000_000, 2_000_000_000, 5000).unwrap_err();
+        assert_eq!(err.balance, 1_500_000_000);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "payer {} has 1.5 SOL, needs 2 SOL for rent plus 0.000005 SOL in fees",
+                payer
+            )
+        );
+        // the fee alone can be what's missing, and the sum doesn't wrap around
+        assert!(check_lamports(&payer, 2_000_000_000, 2_000_000_000, 5000).is_err());
+        assert!(check_lamports(&payer, u64::MAX, u64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_with_expected_revision() {
+        let f = VestingFixture::new();
+        let instructions = with_expected_revision(
+            &f.program_id,
+            f.seeds,
+            3,
+            vec![top_up_gas(
+                &f.program_id,
+                &system_program::id(),
+                &f.granter,
+                &f.vesting_account_key,
+                1,
+                f.seeds,
+            )
+            .unwrap()],
+        )
+        .unwrap();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].accounts[0].pubkey, f.vesting_account_key);
+        assert_eq!(
+            VestingInstruction::unpack(&instructions[0].data).unwrap(),
+            VestingInstruction::CheckRevision {
+                seeds: f.seeds,
+                expected_revision: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_recover_seed() {
+        let f = VestingFixture::new();
+        let header = f.header(&[vesting_schedule(10, 100)]);
+        let (vesting_account_key, seeds) =
+            find_vesting_address(&f.program_id, &f.granter, &f.destination_owner, &f.mint, 3);
+
+        let original = [f.destination_owner];
+        assert_eq!(
+            recover_seed(&f.program_id, &vesting_account_key, &header, &original, 10),
+            Some((3, seeds))
+        );
+        // not among the nonces tried
+        assert_eq!(
+            recover_seed(&f.program_id, &vesting_account_key, &header, &original, 3),
+            None
+        );
+        // hand-picked seeds can't be recovered
+        assert_eq!(
+            recover_seed(
+                &f.program_id,
+                &f.vesting_account_key,
+                &header,
+                &original,
+                10
+            ),
+            None
+        );
+        // the beneficiary has moved on since InitGrant
+        let moved = [Pubkey::new_unique(), f.destination_owner];
+        assert_eq!(
+            recover_seed(&f.program_id, &vesting_account_key, &header, &moved, 10),
+            Some((3, seeds))
+        );
+    }
+
+    #[test]
+    fn test_cluster_from_str() {
+        assert_eq!("devnet".parse(), Ok(Cluster::Devnet));
+        assert_eq!("mainnet-beta".parse(), Ok(Cluster::Mainnet));
+        assert_eq!(
+            "https://rpc.example.com".parse::<Cluster>().unwrap().url(),
+            "https://rpc.example.com"
+        );
+        assert!("mainnet-alpha".parse::<Cluster>().is_err());
+    }
+
+    fn report_transaction(
+        fee_payer: Pubkey,
+        data: Vec<Vec<u8>>,
+        token_delta: i128,
+    ) -> ReportTransaction {
+        ReportTransaction {
+            signature: Signature::new_unique(),
+            time: 100,
+            fee_payer,
+            fee: 5000,
+            vesting_instructions: data,
+            token_delta,
+        }
+    }
+
+    #[test]
+    fn test_ledger_entries() {
+        let f = VestingFixture::new();
+        let header = f.header(&[]);
+        let kinds = |tx: ReportTransaction| {
+            tx.ledger_entries(&f.vesting_account_key, &header)
+                .into_iter()
+                .map(|e| (e.kind, e.amount))
+                .collect::<Vec<(LedgerKind, u64)>>()
+        };
+
+        // a claim cranked by someone else costs the granter nothing
+        assert_eq!(
+            kinds(report_transaction(
+                Pubkey::new_unique(),
+                vec![vec![2; 33]],
+                -40
+            )),
+            vec![(LedgerKind::Claim, 40)]
+        );
+        assert_eq!(
+            kinds(report_transaction(f.granter, vec![vec![7; 33]], -60)),
+            vec![(LedgerKind::Fee, 5000), (LedgerKind::Revoke, 60)]
+        );
+        assert_eq!(
+            kinds(report_transaction(f.granter, vec![vec![30; 33]], -60)),
+            vec![(LedgerKind::Fee, 5000), (LedgerKind::Revoke, 60)]
+        );
+        let top_up = top_up_gas(
+            &f.program_id,
+            &system_program::id(),
+            &f.granter,
+            &f.vesting_account_key,
+            20,
+            f.seeds,
+        )
+        .unwrap();
+        assert_eq!(
+            kinds(report_transaction(f.granter, vec![top_up.data], 0)),
+            vec![(LedgerKind::Fee, 5000), (LedgerKind::GasDeposit, 20)]
+        );
+    }
+
+    #[test]
+    fn test_cluster_time_override() {
+        // the mock fails every request, so only the override can answer
+        let rpc_client = RpcClient::new_mock("fails".to_string());
+        let config = ClientConfig {
+            clock_override: Some(1_700_000_000),
+            ..ClientConfig::default()
+        };
+        assert_eq!(cluster_time(&rpc_client, &config).unwrap(), 1_700_000_000);
+        assert!(cluster_time(&rpc_client, &ClientConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_demo_schedules() {
+        let now = 1_700_000_000;
+        let contracts = demo_schedules(now);
+        let released =
+            |schedules: &[Schedule]| schedules.iter().filter(|s| s.release_time <= now).count();
+        let maturities: Vec<_> = contracts
+            .iter()
+            .map(|(label, schedules)| (*label, released(schedules), schedules.len()))
+            .collect();
+        assert_eq!(
+            maturities,
+            vec![
+                ("vested", 2, 2),
+                ("halfway", 2, 4),
+                ("cliff", 0, 1),
+                ("monthly", 1, 12),
+            ]
+        );
+
+        // a young localnet clock doesn't underflow
+        assert!(demo_schedules(0)
+            .iter()
+            .flat_map(|(_, schedules)| schedules)
+            .all(|s| s.amount > 0));
+    }
+
+    #[test]
+    fn test_gas_deposit() {
+        let f = VestingFixture::new();
+        let create = create(
+            &f.program_id,
+            &spl_token::id(),
+            &f.vesting_account_key,
+            &f.vesting_token_account_key,
+            &f.granter,
+            &f.source_token_account_key,
+            &f.destination_token_account_key,
+            &f.mint,
+            vec![schedule(1, 1)],
+            CreateOptions {
+                gas_rebate: 5,
+                gas_deposit: 30,
+                ..CreateOptions::default()
+            },
+            f.seeds,
+        )
+        .unwrap();
+        assert_eq!(gas_deposit(&create.data), Some(30));
+        assert_eq!(gas_deposit(&[2; 33]), None);
+    }
+
+    #[test]
+    fn test_pending_entries() {
+        let f = VestingFixture::new();
+        let schedules = vec![
+            vesting_schedule(10, 100),
+            vesting_schedule(20, 0),
+            vesting_schedule(30, 50),
+            vesting_schedule(40, 50),
+        ];
+        let categories = vec![
+            ScheduleCategory::Salary,
+            ScheduleCategory::Salary,
+            ScheduleCategory::Bonus,
+            ScheduleCategory::Bonus,
+        ];
+        let entries = pending_entries(
+            &f.vesting_account_key,
+            &f.header(&schedules),
+            &schedules,
+            &categories,
+            10,
+            40,
+        );
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (e.time, e.amount, e.category))
+                .collect::<Vec<_>>(),
+            vec![
+                (10, 100, Some(ScheduleCategory::Salary)),
+                (30, 50, Some(ScheduleCategory::Bonus))
+            ]
+        );
+        assert!(entries.iter().all(|e| e.kind == LedgerKind::Pending));
+    }
+
+    #[test]
+    fn test_categorized_grant() {
+        let f = VestingFixture::new();
+        let header = f.header(&[]);
+        let create = create(
+            &f.program_id,
+            &spl_token::id(),
+            &f.vesting_account_key,
+            &f.vesting_token_account_key,
+            &f.granter,
+            &f.source_token_account_key,
+            &f.destination_token_account_key,
+            &f.mint,
+            vec![schedule(1, 10), schedule(2, 20), schedule(3, 5)],
+            CreateOptions {
+                categories: vec![
+                    ScheduleCategory::Advisor,
+                    ScheduleCategory::Investor,
+                    ScheduleCategory::Advisor,
+                ],
+                ..CreateOptions::default()
+            },
+            f.seeds,
+        )
+        .unwrap();
+        assert_eq!(gas_deposit(&create.data), Some(0));
+
+        let entries = report_transaction(f.granter, vec![create.data], 35)
+            .ledger_entries(&f.vesting_account_key, &header);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (e.kind, e.amount, e.category))
+                .collect::<Vec<_>>(),
+            vec![
+                (LedgerKind::Fee, 5000, None),
+                (LedgerKind::Grant, 15, Some(ScheduleCategory::Advisor)),
+                (LedgerKind::Grant, 20, Some(ScheduleCategory::Investor)),
+            ]
+        );
+
+        let totals = category_totals(&[entries.clone(), entries].concat());
+        assert_eq!(totals.len(), 3);
+        assert_eq!(
+            totals[0],
+            CategoryTotal {
+                kind: LedgerKind::Grant,
+                mint: Some(f.mint),
+                category: Some(ScheduleCategory::Advisor),
+                amount: 30,
+                entries: 2,
+            }
+        );
+    }
+}
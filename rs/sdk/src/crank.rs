@@ -0,0 +1,602 @@
+//! Submits unlocks for many contracts at once, for cranks going through thousands of due contracts (see
+//! `client::due_contracts`). Unlocks are sent in waves: every transaction of a wave goes out back to back, so no two
+//! of them may write to the same account - otherwise the runtime would serialize them, or one would fail on state
+//! the other just changed. Jobs that conflict with the current wave wait for the next one.
+//!
+//! Each wave's signatures are then polled until they land, fail, or give up. Failed batches are split so that one
+//! bad contract doesn't hold back the others, and jobs are retried up to `CrankConfig::max_attempts` times.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    thread,
+    time::Duration,
+};
+
+use solana_client::{client_error::Result as ClientResult, rpc_client::RpcClient};
+use solana_program::{hash::Hash, instruction::Instruction, pubkey::Pubkey, sysvar};
+use solana_sdk::{
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+use crate::{
+    batch::{BatchError, Footprint},
+    client::{custom_error, fetch_contract, ClientConfig},
+    instruction::{unlock, Seeds},
+};
+
+/// The RPC calls the crank makes, so that the scheduling can be tested against a fake cluster
+pub trait CrankRpc {
+    fn latest_blockhash(&self) -> ClientResult<Hash>;
+    /// Sends without waiting for confirmation
+    fn send(&self, transaction: &Transaction) -> ClientResult<Signature>;
+    /// One entry per signature: None while it hasn't reached the configured commitment (or never will)
+    fn statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<Vec<Option<Result<(), TransactionError>>>>;
+}
+
+/// CrankRpc for a real cluster
+pub struct ClusterRpc<'a> {
+    pub rpc_client: &'a RpcClient,
+    pub config: &'a ClientConfig,
+}
+
+impl CrankRpc for ClusterRpc<'_> {
+    fn latest_blockhash(&self) -> ClientResult<Hash> {
+        self.rpc_client.get_latest_blockhash()
+    }
+
+    fn send(&self, transaction: &Transaction) -> ClientResult<Signature> {
+        self.rpc_client
+            .send_transaction_with_config(transaction, self.config.send_config())
+    }
+
+    fn statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<Vec<Option<Result<(), TransactionError>>>> {
+        Ok(self
+            .rpc_client
+            .get_signature_statuses(signatures)?
+            .value
+            .into_iter()
+            .map(|status| {
+                status
+                    .filter(|s| s.satisfies_commitment(self.config.commitment))
+                    .map(|s| s.status)
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CrankConfig {
+    /// Unlocks batched into one transaction. Unlocks share the token program, clock and vesting program, so 4 fit
+    /// comfortably in a transaction even with rebate receivers. A batch is closed early anyway if one more unlock
+    /// would take the transaction over the size or account limit, see batch::Footprint
+    pub max_unlocks_per_transaction: usize,
+    /// Transactions sent per wave
+    pub max_transactions_per_wave: usize,
+    /// How often a wave's signatures are polled before the ones still missing are taken as dropped
+    pub max_status_polls: usize,
+    pub poll_interval: Duration,
+    /// Sends per job before it's reported as failed. Being split out of a failed batch doesn't count
+    pub max_attempts: u32,
+}
+
+impl Default for CrankConfig {
+    fn default() -> Self {
+        Self {
+            max_unlocks_per_transaction: 4,
+            max_transactions_per_wave: 16,
+            // a blockhash expires after ~150 slots, about a minute
+            max_status_polls: 150,
+            poll_interval: Duration::from_millis(400),
+            max_attempts: 3,
+        }
+    }
+}
+
+/// An unlock instruction (any of the `unlock` builders) for one contract
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnlockJob {
+    pub vesting_account_key: Pubkey,
+    pub instruction: Instruction,
+}
+
+impl UnlockJob {
+    fn writable_accounts(&self) -> impl Iterator<Item = &Pubkey> {
+        self.instruction
+            .accounts
+            .iter()
+            .filter(|a| a.is_writable)
+            .map(|a| &a.pubkey)
+    }
+}
+
+/// The UnlockJob of a live contract: a plain unlock from its associated vesting token account to its current
+/// destination. The account doesn't hold its seeds, so they have to come from whoever set the contract up, or from
+/// client::fetch_recovered_seed
+pub fn fetch_unlock_job(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    vesting_account_key: &Pubkey,
+    seeds: Seeds,
+) -> ClientResult<UnlockJob> {
+    let (header, _, _) = fetch_contract(rpc_client, config, program_id, vesting_account_key)?;
+    let instruction = unlock(
+        program_id,
+        &spl_token::id(),
+        &sysvar::clock::id(),
+        vesting_account_key,
+        &spl_associated_token_account::get_associated_token_address(
+            vesting_account_key,
+            &header.mint_address,
+        ),
+        &header.destination_address,
+        seeds,
+    )
+    .map_err(custom_error)?;
+    Ok(UnlockJob {
+        vesting_account_key: *vesting_account_key,
+        instruction,
+    })
+}
+
+/// A job waiting to be sent, with what happened to it so far
+#[derive(Clone, Debug)]
+struct QueuedJob {
+    job: UnlockJob,
+    attempts: u32,
+    /// Was in a batch that failed, so it goes out on its own from now on
+    solo: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrankReport {
+    pub unlocked: Vec<(Pubkey, Signature)>,
+    /// With the last error seen
+    pub failed: Vec<(Pubkey, String)>,
+    pub waves: usize,
+    /// Transactions that made it into a block, failed ones included - each of them cost the payer its fee
+    pub transactions_landed: usize,
+}
+
+/// Unlocks every job, see the module docs. Only returns an error when the RPC itself fails - contracts that can't
+/// be unlocked end up in CrankReport::failed
+pub fn run_unlocks(
+    rpc: &impl CrankRpc,
+    payer: &Keypair,
+    jobs: Vec<UnlockJob>,
+    config: &CrankConfig,
+) -> ClientResult<CrankReport> {
+    let mut queue: VecDeque<QueuedJob> = VecDeque::with_capacity(jobs.len());
+    let mut report = CrankReport::default();
+    for (index, job) in jobs.into_iter().enumerate() {
+        // no point sending what the cluster is going to reject whatever happens
+        let footprint = Footprint::of(&payer.pubkey(), std::slice::from_ref(&job.instruction));
+        if !footprint.fits() {
+            let error = BatchError::Oversized { index, footprint };
+            report
+                .failed
+                .push((job.vesting_account_key, error.to_string()));
+            continue;
+        }
+        queue.push_back(QueuedJob {
+            job,
+            attempts: 0,
+            solo: false,
+        });
+    }
+
+    while !queue.is_empty() {
+        let wave = plan_wave(&mut queue, &payer.pubkey(), config);
+        report.waves += 1;
+
+        let blockhash = rpc.latest_blockhash()?;
+        let mut in_flight: Vec<(Signature, Vec<QueuedJob>)> = Vec::with_capacity(wave.len());
+        for batch in wave {
+            let instructions: Vec<Instruction> =
+                batch.iter().map(|q| q.job.instruction.clone()).collect();
+            let transaction = Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer.pubkey()),
+                &[payer],
+                blockhash,
+            );
+            match rpc.send(&transaction) {
+                Ok(signature) => in_flight.push((signature, batch)),
+                // a failed preflight is as good as a failed transaction
+                Err(e) => reconcile_failure(batch, e.to_string(), &mut queue, &mut report, config),
+            }
+        }
+
+        let signatures: Vec<Signature> = in_flight.iter().map(|(s, _)| *s).collect();
+        let outcomes = wait_for(rpc, &signatures, config)?;
+        for ((signature, batch), outcome) in in_flight.into_iter().zip(outcomes) {
+            if outcome.is_some() {
+                report.transactions_landed += 1;
+            }
+            match outcome {
+                Some(Ok(())) => report.unlocked.extend(
+                    batch
+                        .into_iter()
+                        .map(|q| (q.job.vesting_account_key, signature)),
+                ),
+                Some(Err(e)) => {
+                    reconcile_failure(batch, e.to_string(), &mut queue, &mut report, config)
+                }
+                None => {
+                    for mut q in batch {
+                        q.attempts += 1;
+                        requeue(
+                            q,
+                            "transaction dropped".to_string(),
+                            &mut queue,
+                            &mut report,
+                            config,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Takes the next wave off the front of the queue: batches of jobs, none of them writing to an account another
+/// job of the wave writes to. Whatever doesn't fit stays queued, in order. Never returns an empty wave for a
+/// non-empty queue, since the first job always fits
+fn plan_wave(
+    queue: &mut VecDeque<QueuedJob>,
+    payer: &Pubkey,
+    config: &CrankConfig,
+) -> Vec<Vec<QueuedJob>> {
+    let mut locked: HashSet<Pubkey> = HashSet::new();
+    let mut wave: Vec<Vec<QueuedJob>> = Vec::new();
+    let mut deferred: VecDeque<QueuedJob> = VecDeque::new();
+
+    while let Some(q) = queue.pop_front() {
+        if q.job.writable_accounts().any(|key| locked.contains(key)) {
+            deferred.push_back(q);
+            continue;
+        }
+        let open_batch = if q.solo {
+            None
+        } else {
+            wave.iter().position(|b| {
+                !b[0].solo
+                    && b.len() < config.max_unlocks_per_transaction
+                    && fits_with(payer, b, &q)
+            })
+        };
+        let batch = match open_batch {
+            Some(i) => i,
+            None if wave.len() < config.max_transactions_per_wave => {
+                wave.push(vec![]);
+                wave.len() - 1
+            }
+            None => {
+                deferred.push_back(q);
+                continue;
+            }
+        };
+        locked.extend(q.job.writable_accounts().copied());
+        wave[batch].push(q);
+    }
+
+    *queue = deferred;
+    wave
+}
+
+/// Whether the batch's transaction still fits the limits with one more job in it
+fn fits_with(payer: &Pubkey, batch: &[QueuedJob], q: &QueuedJob) -> bool {
+    let instructions: Vec<Instruction> = batch
+        .iter()
+        .chain(Some(q))
+        .map(|q| q.job.instruction.clone())
+        .collect();
+    Footprint::of(payer, &instructions).fits()
+}
+
+/// Polls until every signature has an outcome or max_status_polls runs out. None = never landed
+fn wait_for(
+    rpc: &impl CrankRpc,
+    signatures: &[Signature],
+    config: &CrankConfig,
+) -> ClientResult<Vec<Option<Result<(), TransactionError>>>> {
+    let mut outcomes = vec![None; signatures.len()];
+    let mut pending: Vec<usize> = (0..signatures.len()).collect();
+
+    for _ in 0..config.max_status_polls {
+        if pending.is_empty() {
+            break;
+        }
+        let pending_signatures: Vec<Signature> = pending.iter().map(|i| signatures[*i]).collect();
+        let statuses = rpc.statuses(&pending_signatures)?;
+        for (i, status) in pending.iter().zip(statuses) {
+            outcomes[*i] = status;
+        }
+        pending.retain(|i| outcomes[*i].is_none());
+        if !pending.is_empty() {
+            thread::sleep(config.poll_interval);
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// A batch of several jobs fails as a whole when any of them does, so its jobs are retried on their own to find
+/// out which. A job that fails on its own uses up an attempt
+fn reconcile_failure(
+    batch: Vec<QueuedJob>,
+    error: String,
+    queue: &mut VecDeque<QueuedJob>,
+    report: &mut CrankReport,
+    config: &CrankConfig,
+) {
+    let split = batch.len() > 1;
+    for mut q in batch {
+        if split {
+            q.solo = true;
+        } else {
+            q.attempts += 1;
+        }
+        requeue(q, error.clone(), queue, report, config);
+    }
+}
+
+fn requeue(
+    q: QueuedJob,
+    error: String,
+    queue: &mut VecDeque<QueuedJob>,
+    report: &mut CrankReport,
+    config: &CrankConfig,
+) {
+    if q.attempts >= config.max_attempts {
+        report.failed.push((q.job.vesting_account_key, error));
+    } else {
+        queue.push_back(q);
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::instruction::InstructionError;
+    use std::{cell::RefCell, collections::HashMap};
+
+    /// A cluster that lands every transaction on the first poll, except for those touching `failing` (which fail)
+    /// or `dropped_once` (whose first transaction never lands)
+    #[derive(Default)]
+    struct MockRpc {
+        failing: HashSet<Pubkey>,
+        dropped_once: RefCell<HashSet<Pubkey>>,
+        outcomes: RefCell<HashMap<Signature, Option<Result<(), TransactionError>>>>,
+        /// Writable accounts of every transaction sent, by wave (= blockhash fetched)
+        waves: RefCell<Vec<Vec<Vec<Pubkey>>>>,
+    }
+
+    impl CrankRpc for MockRpc {
+        fn latest_blockhash(&self) -> ClientResult<Hash> {
+            self.waves.borrow_mut().push(vec![]);
+            Ok(Hash::new_unique())
+        }
+
+        fn send(&self, transaction: &Transaction) -> ClientResult<Signature> {
+            let message = &transaction.message;
+            let writable: Vec<Pubkey> = (1..message.account_keys.len())
+                .filter(|i| message.is_writable(*i))
+                .map(|i| message.account_keys[i])
+                .collect();
+            let outcome = if writable.iter().any(|k| self.failing.contains(k)) {
+                Some(Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(0),
+                )))
+            } else if writable
+                .iter()
+                .any(|k| self.dropped_once.borrow_mut().remove(k))
+            {
+                None
+            } else {
+                Some(Ok(()))
+            };
+            self.waves.borrow_mut().last_mut().unwrap().push(writable);
+            let signature = transaction.signatures[0];
+            self.outcomes.borrow_mut().insert(signature, outcome);
+            Ok(signature)
+        }
+
+        fn statuses(
+            &self,
+            signatures: &[Signature],
+        ) -> ClientResult<Vec<Option<Result<(), TransactionError>>>> {
+            let outcomes = self.outcomes.borrow();
+            Ok(signatures.iter().map(|s| outcomes[s].clone()).collect())
+        }
+    }
+
+    fn job(destination: &Pubkey) -> UnlockJob {
+        let vesting_account_key = Pubkey::new_unique();
+        UnlockJob {
+            vesting_account_key,
+            instruction: unlock(
+                &Pubkey::new_unique(),
+                &spl_token::id(),
+                &sysvar::clock::id(),
+                &vesting_account_key,
+                &Pubkey::new_unique(),
+                destination,
+                [0; 32],
+            )
+            .unwrap(),
+        }
+    }
+
+    fn queued(jobs: &[UnlockJob]) -> VecDeque<QueuedJob> {
+        jobs.iter()
+            .map(|job| QueuedJob {
+                job: job.clone(),
+                attempts: 0,
+                solo: false,
+            })
+            .collect()
+    }
+
+    fn test_config() -> CrankConfig {
+        CrankConfig {
+            max_unlocks_per_transaction: 2,
+            max_transactions_per_wave: 2,
+            poll_interval: Duration::from_millis(0),
+            max_status_polls: 3,
+            ..CrankConfig::default()
+        }
+    }
+
+    fn keys(batch: &[QueuedJob]) -> Vec<Pubkey> {
+        batch.iter().map(|q| q.job.vesting_account_key).collect()
+    }
+
+    #[test]
+    fn test_plan_wave() {
+        let shared_destination = Pubkey::new_unique();
+        let jobs = vec![
+            job(&shared_destination),
+            job(&Pubkey::new_unique()),
+            job(&shared_destination),
+            job(&Pubkey::new_unique()),
+            job(&Pubkey::new_unique()),
+            job(&Pubkey::new_unique()),
+        ];
+        let mut queue = queued(&jobs);
+
+        // the second unlock into the shared destination has to wait, as does whatever is over 2 x 2
+        let wave = plan_wave(&mut queue, &Pubkey::new_unique(), &test_config());
+        assert_eq!(
+            wave.iter().map(|b| keys(b)).collect::<Vec<_>>(),
+            vec![
+                vec![jobs[0].vesting_account_key, jobs[1].vesting_account_key],
+                vec![jobs[3].vesting_account_key, jobs[4].vesting_account_key],
+            ]
+        );
+        assert_eq!(
+            keys(&queue.iter().cloned().collect::<Vec<_>>()),
+            vec![jobs[2].vesting_account_key, jobs[5].vesting_account_key]
+        );
+
+        let wave = plan_wave(&mut queue, &Pubkey::new_unique(), &test_config());
+        assert_eq!(
+            keys(&wave[0]),
+            vec![jobs[2].vesting_account_key, jobs[5].vesting_account_key]
+        );
+        assert!(queue.is_empty());
+
+        // solo jobs get a transaction each
+        let mut queue = queued(&jobs[3..5]);
+        queue[0].solo = true;
+        let wave = plan_wave(&mut queue, &Pubkey::new_unique(), &test_config());
+        assert_eq!(wave.len(), 2);
+    }
+
+    #[test]
+    fn test_run_unlocks_keeps_waves_conflict_free() {
+        let destinations = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let jobs: Vec<UnlockJob> = (0..10).map(|i| job(&destinations[i % 2])).collect();
+        let rpc = MockRpc::default();
+        let report = run_unlocks(&rpc, &Keypair::new(), jobs.clone(), &test_config()).unwrap();
+
+        assert_eq!(report.unlocked.len(), 10);
+        assert!(report.failed.is_empty());
+        // two destinations shared by every job, so two unlocks per wave at most
+        assert_eq!(report.waves, 5);
+        assert_eq!(report.transactions_landed, 5);
+        for wave in rpc.waves.borrow().iter() {
+            let mut seen = HashSet::new();
+            for writable in wave {
+                // the payer is left out, it's writable in every transaction
+                assert!(writable.iter().all(|k| seen.insert(*k)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_unlocks_reconciles_failures() {
+        let jobs: Vec<UnlockJob> = (0..4).map(|_| job(&Pubkey::new_unique())).collect();
+        let rpc = MockRpc {
+            failing: vec![jobs[0].vesting_account_key].into_iter().collect(),
+            dropped_once: RefCell::new(vec![jobs[2].vesting_account_key].into_iter().collect()),
+            ..MockRpc::default()
+        };
+        let report = run_unlocks(&rpc, &Keypair::new(), jobs.clone(), &test_config()).unwrap();
+
+        // jobs[1] was held back by jobs[0]'s failure only until the split, jobs[3] by jobs[2]'s drop until the retry
+        let mut unlocked: Vec<Pubkey> = report.unlocked.iter().map(|(k, _)| *k).collect();
+        unlocked.sort();
+        let mut expected = vec![
+            jobs[1].vesting_account_key,
+            jobs[2].vesting_account_key,
+            jobs[3].vesting_account_key,
+        ];
+        expected.sort();
+        assert_eq!(unlocked, expected);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, jobs[0].vesting_account_key);
+        // one send as part of a batch, then max_attempts on its own
+        let sends_of_failing: usize = rpc
+            .waves
+            .borrow()
+            .iter()
+            .flatten()
+            .filter(|writable| writable.contains(&jobs[0].vesting_account_key))
+            .count();
+        assert_eq!(sends_of_failing, 1 + test_config().max_attempts as usize);
+        // the dropped transaction is the only one that didn't cost a fee
+        let sent: usize = rpc.waves.borrow().iter().map(|wave| wave.len()).sum();
+        assert_eq!(report.transactions_landed, sent - 1);
+    }
+
+    #[test]
+    fn test_plan_wave_respects_transaction_limits() {
+        let payer = Pubkey::new_unique();
+        let jobs: Vec<UnlockJob> = (0..20).map(|_| job(&Pubkey::new_unique())).collect();
+        let config = CrankConfig {
+            max_unlocks_per_transaction: 20,
+            ..test_config()
+        };
+        let mut queue = queued(&jobs);
+
+        // 20 unlocks don't fit in one transaction, so the first batch gets closed early
+        let wave = plan_wave(&mut queue, &payer, &config);
+        assert!(wave[0].len() < 20);
+        for batch in wave.iter() {
+            let instructions: Vec<Instruction> =
+                batch.iter().map(|q| q.job.instruction.clone()).collect();
+            assert!(Footprint::of(&payer, &instructions).fits());
+        }
+    }
+
+    #[test]
+    fn test_run_unlocks_fails_oversized_jobs_without_sending() {
+        let mut jobs: Vec<UnlockJob> = (0..2).map(|_| job(&Pubkey::new_unique())).collect();
+        jobs[0].instruction.data = vec![0; crate::batch::MAX_TRANSACTION_SIZE];
+        let rpc = MockRpc::default();
+        let report = run_unlocks(&rpc, &Keypair::new(), jobs.clone(), &test_config()).unwrap();
+
+        assert_eq!(report.unlocked.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, jobs[0].vesting_account_key);
+        assert!(rpc
+            .waves
+            .borrow()
+            .iter()
+            .flatten()
+            .all(|writable| !writable.contains(&jobs[0].vesting_account_key)));
+    }
+}
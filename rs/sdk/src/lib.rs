@@ -0,0 +1,18 @@
+// the program's own modules, so off-chain code only ever needs to depend on this crate
+#[cfg(feature = "bonfida-compat")]
+pub use token_vesting_program::bonfida;
+pub use token_vesting_program::{compression, core, error, ids, instruction, math, pda, state};
+
+pub mod analytics;
+pub mod batch;
+pub mod cli;
+pub mod client;
+pub mod crank;
+pub mod metrics;
+pub mod output;
+pub mod signing;
+pub mod smoke;
+#[cfg(feature = "crank-store")]
+pub mod store;
+#[cfg(test)]
+use token_vesting_program::test_utils;
@@ -0,0 +1,250 @@
+//! Counters for a crank running as a service, served in the Prometheus text format so that claims, fees and how far
+//! behind the crank is can be scraped and alerted on. Plain std: the endpoint answers GET /metrics and nothing else.
+
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::crank::CrankReport;
+
+/// What the runtime charges per signature. A crank transaction only has the payer's
+pub const DEFAULT_LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// How long a scraper gets to send its request line. Scrapes are answered one at a time, so without it a connection
+/// that never sends anything would stall the endpoint for good
+pub const SCRAPE_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Totals since the crank started, plus gauges describing its last run. Shared between the crank loop and the
+/// endpoint, so everything is atomic
+#[derive(Debug, Default)]
+pub struct CrankMetrics {
+    claims_submitted: AtomicU64,
+    claims_failed: AtomicU64,
+    transactions_landed: AtomicU64,
+    lamports_spent: AtomicU64,
+    waves: AtomicU64,
+    runs: AtomicU64,
+    due_contracts: AtomicU64,
+    lag_seconds: AtomicU64,
+}
+
+impl CrankMetrics {
+    /// Adds a run_unlocks report to the totals. Fees are counted for every transaction that landed, at
+    /// `lamports_per_signature` each
+    pub fn record(&self, report: &CrankReport, lamports_per_signature: u64) {
+        let transactions_landed = report.transactions_landed as u64;
+        self.claims_submitted
+            .fetch_add(report.unlocked.len() as u64, Ordering::Relaxed);
+        self.claims_failed
+            .fetch_add(report.failed.len() as u64, Ordering::Relaxed);
+        self.transactions_landed
+            .fetch_add(transactions_landed, Ordering::Relaxed);
+        self.lamports_spent.fetch_add(
+            transactions_landed.saturating_mul(lamports_per_signature),
+            Ordering::Relaxed,
+        );
+        self.waves.fetch_add(report.waves as u64, Ordering::Relaxed);
+        self.runs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sets the gauges from what client::due_contracts found at `now`: how many contracts are due, and for how long
+    /// the oldest of them has been - 0 once the crank has caught up
+    pub fn observe_due(&self, due: &[(Pubkey, u64)], now: u64) {
+        let lag = due
+            .iter()
+            .map(|(_, next_release_time)| now.saturating_sub(*next_release_time))
+            .max()
+            .unwrap_or(0);
+        self.due_contracts
+            .store(due.len() as u64, Ordering::Relaxed);
+        self.lag_seconds.store(lag, Ordering::Relaxed);
+    }
+
+    /// The Prometheus text exposition of every metric
+    pub fn render(&self) -> String {
+        let metrics: [(&str, &str, &str, &AtomicU64); 8] = [
+            (
+                "claims_submitted_total",
+                "counter",
+                "Unlocks that landed",
+                &self.claims_submitted,
+            ),
+            (
+                "claims_failed_total",
+                "counter",
+                "Unlocks given up on after max_attempts",
+                &self.claims_failed,
+            ),
+            (
+                "transactions_landed_total",
+                "counter",
+                "Transactions that made it into a block, failed ones included",
+                &self.transactions_landed,
+            ),
+            (
+                "lamports_spent_total",
+                "counter",
+                "Fees paid for the landed transactions",
+                &self.lamports_spent,
+            ),
+            ("waves_total", "counter", "Waves sent", &self.waves),
+            ("runs_total", "counter", "Crank runs", &self.runs),
+            (
+                "due_contracts",
+                "gauge",
+                "Contracts with something to unlock at the last scan",
+                &self.due_contracts,
+            ),
+            (
+                "lag_seconds",
+                "gauge",
+                "How long the oldest due contract has been waiting at the last scan",
+                &self.lag_seconds,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics.iter() {
+            // writing to a String can't fail
+            let _ = writeln!(out, "# HELP token_vesting_crank_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE token_vesting_crank_{} {}", name, kind);
+            let _ = writeln!(
+                out,
+                "token_vesting_crank_{} {}",
+                name,
+                value.load(Ordering::Relaxed)
+            );
+        }
+        out
+    }
+
+    /// Answers scrapes on `listener` from a thread of its own, for as long as the listener lives. Taking a bound
+    /// listener rather than an address lets the caller pick port 0 and read the actual one back
+    pub fn serve(self: &Arc<Self>, listener: TcpListener) -> thread::JoinHandle<()> {
+        let metrics = Arc::clone(self);
+        thread::spawn(move || {
+            // a scraper that hangs up early is its own problem, the next one gets served all the same
+            for stream in listener.incoming().flatten() {
+                let _ = metrics.respond(stream);
+            }
+        })
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(SCRAPE_READ_TIMEOUT))?;
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+        let (status, body) = match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["GET", "/metrics", ..] => ("200 OK", self.render()),
+            _ => ("404 Not Found", String::new()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signature;
+    use std::io::Read;
+
+    fn report() -> CrankReport {
+        CrankReport {
+            unlocked: vec![
+                (Pubkey::new_unique(), Signature::default()),
+                (Pubkey::new_unique(), Signature::default()),
+            ],
+            failed: vec![(Pubkey::new_unique(), "custom program error".to_string())],
+            waves: 2,
+            transactions_landed: 3,
+        }
+    }
+
+    fn value(rendered: &str, name: &str) -> u64 {
+        let prefix = format!("token_vesting_crank_{} ", name);
+        rendered
+            .lines()
+            .find_map(|line| line.strip_prefix(&prefix))
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record_and_render() {
+        let metrics = CrankMetrics::default();
+        metrics.record(&report(), DEFAULT_LAMPORTS_PER_SIGNATURE);
+        metrics.record(&report(), DEFAULT_LAMPORTS_PER_SIGNATURE);
+        metrics.observe_due(
+            &[(Pubkey::new_unique(), 100), (Pubkey::new_unique(), 40)],
+            130,
+        );
+
+        let rendered = metrics.render();
+        assert_eq!(value(&rendered, "claims_submitted_total"), 4);
+        assert_eq!(value(&rendered, "claims_failed_total"), 2);
+        assert_eq!(value(&rendered, "transactions_landed_total"), 6);
+        assert_eq!(value(&rendered, "lamports_spent_total"), 30_000);
+        assert_eq!(value(&rendered, "runs_total"), 2);
+        assert_eq!(value(&rendered, "due_contracts"), 2);
+        assert_eq!(value(&rendered, "lag_seconds"), 90);
+        assert!(rendered.contains("# TYPE token_vesting_crank_lag_seconds gauge\n"));
+
+        // caught up
+        metrics.observe_due(&[], 200);
+        assert_eq!(value(&metrics.render(), "lag_seconds"), 0);
+    }
+
+    fn get(address: &std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_serve() {
+        let metrics = Arc::new(CrankMetrics::default());
+        metrics.record(&report(), DEFAULT_LAMPORTS_PER_SIGNATURE);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        metrics.serve(listener);
+
+        let response = get(&address, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&metrics.render()));
+
+        // what the endpoint serves follows the crank
+        metrics.record(&report(), DEFAULT_LAMPORTS_PER_SIGNATURE);
+        let response = get(&address, "/metrics");
+        assert_eq!(
+            value(response.split("\r\n\r\n").nth(1).unwrap(), "runs_total"),
+            2
+        );
+
+        assert!(get(&address, "/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        // a connection that never sends its request only holds up the next scrape until it times out
+        let _silent = TcpStream::connect(address).unwrap();
+        assert!(get(&address, "/metrics").starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+}
@@ -0,0 +1,980 @@
+//! Formatter layer for anything that prints client results, eg vesting-cli (see the cli module). Every result type is
+//! both Serialize (for `--json`) and Display (for humans), and render() picks one based on the OutputFormat.
+//!
+//! The JSON shape is meant to be parsed by scripts, so field names are part of the interface: add fields, don't rename
+//! them. Keys are base58 strings and times are unix timestamps in seconds.
+
+use std::{fmt, str::FromStr};
+
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::{
+    analytics::{vesting_curve, weighted_avg_unlock_time},
+    client::{CategoryTotal, DemoContract, IndexedContract, LedgerEntry, LocalnetBootstrap},
+    state::{
+        ContractState, RevokePolicy, ScheduleCategory, UnlockPolicy, VestingSchedule,
+        VestingScheduleHeader, NO_PENDING_RELEASE,
+    },
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    Display,
+    Json,
+}
+
+impl OutputFormat {
+    /// What a `--json` flag maps to
+    pub fn from_json_flag(json: bool) -> Self {
+        if json {
+            OutputFormat::Json
+        } else {
+            OutputFormat::Display
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "display" => Ok(OutputFormat::Display),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "unknown output format {:?}, expected display or json",
+                s
+            )),
+        }
+    }
+}
+
+/// Renders a single result. JSON comes out on one line, so that a stream of results can be read line by line
+pub fn render<T: Serialize + fmt::Display>(value: &T, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Display => value.to_string(),
+        // only fails for maps with non-string keys, which none of the output types have
+        OutputFormat::Json => serde_json::to_string(value).expect("output types always serialize"),
+    }
+}
+
+/// Same as render, for a list of results. JSON is a single array, the display format one result per paragraph
+pub fn render_list<T: Serialize + fmt::Display>(values: &[T], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Display => values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join("\n\n"),
+        OutputFormat::Json => serde_json::to_string(values).expect("output types always serialize"),
+    }
+}
+
+/// A list of results as CSV, with a header line. None of the fields can hold a comma or a quote, so nothing is quoted
+pub fn render_csv<T: CsvRow>(values: &[T]) -> String {
+    let mut csv = T::HEADER.join(",");
+    for v in values {
+        csv.push('\n');
+        csv.push_str(&v.csv_fields().join(","));
+    }
+    csv
+}
+
+/// Output types that can also be exported as CSV, eg for accounting software
+pub trait CsvRow {
+    const HEADER: &'static [&'static str];
+
+    fn csv_fields(&self) -> Vec<String>;
+}
+
+/// yyyy-mm-dd (UTC) of a unix timestamp, for spreadsheets that don't take timestamps
+pub fn utc_date(unix_timestamp: u64) -> String {
+    // days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = (unix_timestamp / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// ----------------------------------------------------------------------------- output types
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScheduleOutput {
+    pub release_time: u64,
+    pub amount: u64,
+    /// Only for categorized contracts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+impl From<&VestingSchedule> for ScheduleOutput {
+    fn from(s: &VestingSchedule) -> Self {
+        Self {
+            release_time: s.release_time,
+            amount: s.amount,
+            category: None,
+        }
+    }
+}
+
+/// A vesting contract. Schedules are left out when only the header was fetched (eg from a VestingIndex)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ContractOutput {
+    pub address: String,
+    pub destination: String,
+    pub mint: String,
+    pub granter: String,
+    /// See ContractState, eg "active" or "exhausted"
+    pub state: String,
+    /// None once everything has been claimed
+    pub next_release_time: Option<u64>,
+    /// Whether next_release_time has passed by cluster time, see with_cluster_time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<bool>,
+    pub gas_rebate: u64,
+    pub gas_escrow: u64,
+    /// See UnlockPolicy, "permissionless", "destination-owner-only" or "delegate-only"
+    pub unlock_policy: String,
+    pub insolvent: bool,
+    pub roll_into_final: bool,
+    pub min_claim_amount: u64,
+    pub destination_locked: bool,
+    /// What the next claim permit has to be signed over
+    pub permit_nonce: u64,
+    pub withholding_bps: u16,
+    /// Only while withholding_bps > 0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub withholding_destination: Option<String>,
+    /// Token accounts sharing every Unlock with the destination, see SetSplits
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<SplitOutput>,
+    /// 0 = no cap
+    pub max_transfer_per_unlock: u64,
+    pub unlock_interval: u32,
+    /// See RevokePolicy, "return-to-granter" or "burn"
+    pub revoke_policy: String,
+    pub unlock_count: u32,
+    pub destination_change_count: u32,
+    /// Revokes that found nothing left count too
+    pub revoke_attempt_count: u32,
+    /// Hint only, see VestingScheduleHeader::has_claimable
+    pub has_claimable: bool,
+    /// When Activate anchored the release times of a contract created with relative ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activation_time: Option<u64>,
+    /// Set by Pause, Unlock pays nothing out until Resume
+    pub paused: bool,
+    /// Only for contracts created with one, see EmergencyWithdraw
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emergency_authority: Option<String>,
+    /// Unset for contracts created irrevocable, whose grant can never be clawed back
+    pub revocable: bool,
+    /// Set for contracts created onto their mint's emission curve, see ReadEmissions
+    pub emission_tracked: bool,
+    /// What Unlock has paid out so far, and what of it arrived after Token-2022 transfer fees
+    pub unlocked_gross: u64,
+    pub unlocked_net: u64,
+    /// Bumped by every change the granter or the destination owner makes, see client::with_expected_revision
+    pub revision: u32,
+    pub schedule_count: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedules: Option<Vec<ScheduleOutput>>,
+    /// Only when asked for, see with_analytics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analytics: Option<AnalyticsOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+}
+
+impl ContractOutput {
+    pub fn new(address: &Pubkey, header: &VestingScheduleHeader) -> Self {
+        Self {
+            address: address.to_string(),
+            destination: header.destination_address.to_string(),
+            mint: header.mint_address.to_string(),
+            granter: header.granter_address.to_string(),
+            state: header.state.as_str().to_string(),
+            next_release_time: Some(header.next_release_time).filter(|t| *t != NO_PENDING_RELEASE),
+            due: None,
+            gas_rebate: header.gas_rebate,
+            gas_escrow: header.gas_escrow,
+            unlock_policy: header.unlock_policy.as_str().to_string(),
+            insolvent: header.insolvent,
+            roll_into_final: header.roll_into_final,
+            min_claim_amount: header.min_claim_amount,
+            destination_locked: header.destination_locked,
+            permit_nonce: header.permit_nonce,
+            withholding_bps: header.withholding_bps,
+            withholding_destination: Some(header.withholding_destination.to_string())
+                .filter(|_| header.withholding_bps > 0),
+            splits: header
+                .splits
+                .iter()
+                .map(|s| SplitOutput {
+                    destination: s.destination_address.to_string(),
+                    bps: s.bps,
+                })
+                .collect(),
+            max_transfer_per_unlock: header.max_transfer_per_unlock,
+            unlock_interval: header.unlock_interval,
+            revoke_policy: header.revoke_policy.as_str().to_string(),
+            unlock_count: header.unlock_count,
+            destination_change_count: header.destination_change_count,
+            revoke_attempt_count: header.revoke_attempt_count,
+            has_claimable: header.has_claimable,
+            activation_time: Some(header.activation_time).filter(|t| *t > 0),
+            paused: header.paused,
+            emergency_authority: Some(header.emergency_authority)
+                .filter(|a| *a != Pubkey::default())
+                .map(|a| a.to_string()),
+            revocable: header.revocable,
+            emission_tracked: header.emission_tracked,
+            unlocked_gross: header.unlocked_gross,
+            unlocked_net: header.unlocked_net,
+            revision: header.revision,
+            schedule_count: header.schedule_count,
+            schedules: None,
+            analytics: None,
+            slot: None,
+        }
+    }
+
+    pub fn with_schedules(mut self, schedules: &[VestingSchedule]) -> Self {
+        self.schedules = Some(schedules.iter().map(ScheduleOutput::from).collect());
+        self
+    }
+
+    /// Tags the schedules (see with_schedules) with the categories of a categorized contract
+    pub fn with_categories(mut self, categories: &[ScheduleCategory]) -> Self {
+        for (s, category) in self.schedules.iter_mut().flatten().zip(categories) {
+            s.category = Some(category.as_str().to_string());
+        }
+        self
+    }
+
+    /// Marks whether the contract has something to unlock at `now`, which should come from client::cluster_time
+    /// rather than the local clock
+    pub fn with_cluster_time(mut self, now: u64) -> Self {
+        self.due = Some(self.next_release_time.is_some_and(|t| t <= now));
+        self
+    }
+
+    /// Adds the emission curve and weighted average unlock time of the outstanding schedules
+    pub fn with_analytics(mut self, schedules: &[VestingSchedule]) -> Self {
+        self.analytics = Some(AnalyticsOutput::from(schedules));
+        self
+    }
+
+    pub fn from_indexed(address: &Pubkey, contract: &IndexedContract) -> Self {
+        Self {
+            slot: Some(contract.slot),
+            ..Self::new(address, &contract.header)
+        }
+    }
+}
+
+impl fmt::Display for ContractOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Vesting contract {}", self.address)?;
+        writeln!(f, "  destination:    {}", self.destination)?;
+        writeln!(f, "  mint:           {}", self.mint)?;
+        writeln!(f, "  granter:        {}", self.granter)?;
+        writeln!(f, "  state:          {}", self.state)?;
+        if self.revision > 0 {
+            writeln!(f, "  revision:       {}", self.revision)?;
+        }
+        match self.next_release_time {
+            Some(t) if self.due == Some(true) => {
+                writeln!(f, "  next release:   {} (due by cluster time)", t)?
+            }
+            Some(t) => writeln!(f, "  next release:   {}", t)?,
+            None if self.state == ContractState::AwaitingActivation.as_str() => {
+                writeln!(f, "  next release:   - (awaiting activation)")?
+            }
+            None => writeln!(f, "  next release:   - (fully claimed)")?,
+        }
+        if let Some(t) = self.activation_time {
+            writeln!(f, "  activated:      {}", t)?;
+        }
+        if self.gas_rebate > 0 {
+            writeln!(
+                f,
+                "  gas rebate:     {} lamports ({} in escrow)",
+                self.gas_rebate, self.gas_escrow
+            )?;
+        }
+        if !self.revocable {
+            writeln!(
+                f,
+                "  irrevocable:    the granter can never revoke the contract"
+            )?;
+        }
+        if self.emission_tracked {
+            writeln!(f, "  emissions:      tracked on the mint's emission curve")?;
+        }
+        if self.unlocked_net < self.unlocked_gross {
+            writeln!(
+                f,
+                "  unlocked:       {} ({} after transfer fees)",
+                self.unlocked_gross, self.unlocked_net
+            )?;
+        }
+        if let Some(authority) = &self.emergency_authority {
+            writeln!(f, "  emergency:      {} can sweep the contract", authority)?;
+        }
+        if self.paused {
+            writeln!(
+                f,
+                "  PAUSED:         the granter holds back unlocks until Resume"
+            )?;
+        }
+        if self.insolvent {
+            writeln!(
+                f,
+                "  INSOLVENT:      vesting token account can't cover the remaining schedules"
+            )?;
+        }
+        if self.unlock_policy == UnlockPolicy::DestinationOwnerOnly.as_str() {
+            writeln!(f, "  unlock:         destination owner or permit only")?;
+        } else if self.unlock_policy == UnlockPolicy::DelegateOnly.as_str() {
+            writeln!(f, "  unlock:         destination delegate only")?;
+        }
+        if self.roll_into_final {
+            writeln!(
+                f,
+                "  rollover:       matured schedules roll into the final one"
+            )?;
+        }
+        if self.min_claim_amount > 0 {
+            writeln!(f, "  min claim:      {}", self.min_claim_amount)?;
+        }
+        if self.destination_locked {
+            writeln!(f, "  destination:    locked")?;
+        }
+        if let Some(withholding_destination) = &self.withholding_destination {
+            writeln!(
+                f,
+                "  withholding:    {} bps to {}",
+                self.withholding_bps, withholding_destination
+            )?;
+        }
+        for split in &self.splits {
+            writeln!(
+                f,
+                "  split:          {} bps to {}",
+                split.bps, split.destination
+            )?;
+        }
+        if self.max_transfer_per_unlock > 0 {
+            writeln!(
+                f,
+                "  transfer cap:   {} per unlock, every {}s",
+                self.max_transfer_per_unlock, self.unlock_interval
+            )?;
+        }
+        if self.revoke_policy == RevokePolicy::Burn.as_str() {
+            writeln!(f, "  on revoke:      unvested tokens are burned")?;
+        }
+        if self.unlock_count > 0
+            || self.destination_change_count > 0
+            || self.revoke_attempt_count > 0
+        {
+            writeln!(
+                f,
+                "  activity:       {} unlocks, {} destination changes, {} revoke attempts",
+                self.unlock_count, self.destination_change_count, self.revoke_attempt_count
+            )?;
+        }
+        if let Some(slot) = self.slot {
+            writeln!(f, "  as of slot:     {}", slot)?;
+        }
+        write!(f, "  schedules:      {}", self.schedule_count)?;
+        for s in self.schedules.iter().flatten() {
+            write!(f, "\n    {:>12} at {}", s.amount, s.release_time)?;
+            if let Some(category) = &s.category {
+                write!(f, " ({})", category)?;
+            }
+        }
+        if let Some(analytics) = &self.analytics {
+            write!(f, "\n{}", analytics)?;
+        }
+        Ok(())
+    }
+}
+
+/// A split of a contract's unlocks, see ContractOutput::splits
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SplitOutput {
+    pub destination: String,
+    pub bps: u16,
+}
+
+/// Emission statistics of outstanding schedules, see the analytics module
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AnalyticsOutput {
+    /// None when nothing is outstanding
+    pub weighted_avg_unlock_time: Option<u64>,
+    pub curve: Vec<CurvePointOutput>,
+}
+
+/// Everything released by `release_time`, outstanding schedules only
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CurvePointOutput {
+    pub release_time: u64,
+    pub cumulative_amount: u64,
+}
+
+impl From<&[VestingSchedule]> for AnalyticsOutput {
+    fn from(schedules: &[VestingSchedule]) -> Self {
+        Self {
+            weighted_avg_unlock_time: weighted_avg_unlock_time(schedules),
+            curve: vesting_curve(schedules)
+                .into_iter()
+                .map(|(release_time, cumulative_amount)| CurvePointOutput {
+                    release_time,
+                    cumulative_amount,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for AnalyticsOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.weighted_avg_unlock_time {
+            Some(t) => write!(f, "  avg unlock:     {} ({})", t, utc_date(t))?,
+            None => write!(f, "  avg unlock:     - (nothing outstanding)")?,
+        }
+        for point in &self.curve {
+            write!(
+                f,
+                "\n    {:>12} by {}",
+                point.cumulative_amount, point.release_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// One entry of client::due_contracts
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DueContractOutput {
+    pub address: String,
+    pub next_release_time: u64,
+}
+
+impl DueContractOutput {
+    pub fn new(address: &Pubkey, next_release_time: u64) -> Self {
+        Self {
+            address: address.to_string(),
+            next_release_time,
+        }
+    }
+}
+
+impl fmt::Display for DueContractOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} due since {}", self.address, self.next_release_time)
+    }
+}
+
+/// How revoking one contract of a revoke-batch went, see client::revoke_contracts
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RevokeOutput {
+    pub address: String,
+    /// Set when the revoke went through
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Set when it didn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RevokeOutput {
+    pub fn new<E: fmt::Display>(address: &Pubkey, result: &Result<Signature, E>) -> Self {
+        Self {
+            address: address.to_string(),
+            signature: result.as_ref().ok().map(Signature::to_string),
+            error: result.as_ref().err().map(E::to_string),
+        }
+    }
+}
+
+impl fmt::Display for RevokeOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.signature, &self.error) {
+            (Some(signature), _) => write!(f, "{} revoked in {}", self.address, signature),
+            (None, error) => write!(
+                f,
+                "{} failed: {}",
+                self.address,
+                error.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// One contract seeded by client::localnet_bootstrap
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DemoContractOutput {
+    pub label: String,
+    pub address: String,
+    /// base58, like the keys - the frontend needs them for Unlock
+    pub seeds: String,
+    pub total_amount: u64,
+    pub schedule_count: usize,
+}
+
+impl From<&DemoContract> for DemoContractOutput {
+    fn from(contract: &DemoContract) -> Self {
+        Self {
+            label: contract.label.to_string(),
+            address: contract.vesting_account_key.to_string(),
+            seeds: Pubkey::new_from_array(contract.seeds).to_string(),
+            total_amount: contract.schedules.iter().map(|s| s.amount).sum(),
+            schedule_count: contract.schedules.len(),
+        }
+    }
+}
+
+impl fmt::Display for DemoContractOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<8} {} ({} over {} schedules)",
+            self.label, self.address, self.total_amount, self.schedule_count
+        )
+    }
+}
+
+/// What client::localnet_bootstrap seeded, for a frontend's .env
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LocalnetBootstrapOutput {
+    pub mint: String,
+    pub destination_token_account: String,
+    pub contracts: Vec<DemoContractOutput>,
+}
+
+impl From<&LocalnetBootstrap> for LocalnetBootstrapOutput {
+    fn from(bootstrap: &LocalnetBootstrap) -> Self {
+        Self {
+            mint: bootstrap.mint.to_string(),
+            destination_token_account: bootstrap.destination_token_account_key.to_string(),
+            contracts: bootstrap
+                .contracts
+                .iter()
+                .map(DemoContractOutput::from)
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for LocalnetBootstrapOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Mint:        {}", self.mint)?;
+        write!(f, "Destination: {}", self.destination_token_account)?;
+        for contract in &self.contracts {
+            write!(f, "\n  {}", contract)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sent transaction
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TransactionOutput {
+    pub signature: String,
+}
+
+impl fmt::Display for TransactionOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Signature: {}", self.signature)
+    }
+}
+
+/// One line of client::granter_report. Amounts are in base units of `asset`, which is either a mint or "lamports"
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LedgerEntryOutput {
+    pub time: u64,
+    pub kind: String,
+    pub amount: u64,
+    pub asset: String,
+    pub contract: String,
+    /// None for pending schedules, which haven't happened yet
+    pub signature: Option<String>,
+    /// None where the report can't tell, see LedgerEntry
+    pub category: Option<String>,
+}
+
+impl From<&LedgerEntry> for LedgerEntryOutput {
+    fn from(e: &LedgerEntry) -> Self {
+        Self {
+            time: e.time,
+            kind: e.kind.as_str().to_string(),
+            amount: e.amount,
+            asset: e
+                .mint
+                .map_or_else(|| "lamports".to_string(), |m| m.to_string()),
+            contract: e.contract.to_string(),
+            signature: e.signature.map(|s| s.to_string()),
+            category: e.category.map(|c| c.as_str().to_string()),
+        }
+    }
+}
+
+impl fmt::Display for LedgerEntryOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:<11} {:>20} {} ({})",
+            utc_date(self.time),
+            self.kind,
+            self.amount,
+            self.asset,
+            self.contract
+        )?;
+        if let Some(category) = &self.category {
+            write!(f, " [{}]", category)?;
+        }
+        if let Some(signature) = &self.signature {
+            write!(f, "\n  {}", signature)?;
+        }
+        Ok(())
+    }
+}
+
+impl CsvRow for LedgerEntryOutput {
+    const HEADER: &'static [&'static str] = &[
+        "date",
+        "time",
+        "kind",
+        "amount",
+        "asset",
+        "contract",
+        "signature",
+        "category",
+    ];
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            utc_date(self.time),
+            self.time.to_string(),
+            self.kind.clone(),
+            self.amount.to_string(),
+            self.asset.clone(),
+            self.contract.clone(),
+            self.signature.clone().unwrap_or_default(),
+            self.category.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// One line of client::category_totals
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CategoryTotalOutput {
+    pub kind: String,
+    pub asset: String,
+    pub category: Option<String>,
+    pub amount: u64,
+    pub entries: usize,
+}
+
+impl From<&CategoryTotal> for CategoryTotalOutput {
+    fn from(t: &CategoryTotal) -> Self {
+        Self {
+            kind: t.kind.as_str().to_string(),
+            asset: t
+                .mint
+                .map_or_else(|| "lamports".to_string(), |m| m.to_string()),
+            category: t.category.map(|c| c.as_str().to_string()),
+            amount: t.amount,
+            entries: t.entries,
+        }
+    }
+}
+
+impl fmt::Display for CategoryTotalOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<11} {:<13} {:>20} {} ({} entries)",
+            self.kind,
+            self.category.as_deref().unwrap_or("-"),
+            self.amount,
+            self.asset,
+            self.entries
+        )
+    }
+}
+
+impl CsvRow for CategoryTotalOutput {
+    const HEADER: &'static [&'static str] = &["kind", "category", "amount", "asset", "entries"];
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.kind.clone(),
+            self.category.clone().unwrap_or_default(),
+            self.amount.to_string(),
+            self.asset.clone(),
+            self.entries.to_string(),
+        ]
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::LedgerKind,
+        state::{ContractState, ScheduleLayout, SplitDestination},
+    };
+
+    fn header() -> VestingScheduleHeader {
+        VestingScheduleHeader {
+            destination_address: Pubkey::new_unique(),
+            mint_address: Pubkey::new_unique(),
+            granter_address: Pubkey::new_unique(),
+            next_release_time: NO_PENDING_RELEASE,
+            gas_rebate: 0,
+            gas_escrow: 0,
+            schedule_count: 1,
+            unlock_policy: UnlockPolicy::Permissionless,
+            legacy_destination_address: Pubkey::default(),
+            legacy_cutoff: 0,
+            insolvent: false,
+            roll_into_final: false,
+            min_claim_amount: 0,
+            destination_locked: false,
+            unlock_cursor: 0,
+            sweep_next_release_time: NO_PENDING_RELEASE,
+            permit_nonce: 0,
+            withholding_bps: 0,
+            withholding_destination: Pubkey::default(),
+            max_transfer_per_unlock: 0,
+            unlock_interval: 0,
+            last_unlock_time: 0,
+            revoke_policy: RevokePolicy::ReturnToGranter,
+            unlock_count: 0,
+            destination_change_count: 0,
+            revoke_attempt_count: 0,
+            has_claimable: false,
+            activation_time: 0,
+            paused: false,
+            emergency_authority: Pubkey::default(),
+            revocable: true,
+            splits: vec![],
+            emission_tracked: false,
+            unlocked_gross: 0,
+            unlocked_net: 0,
+            revision: 0,
+            bump: 0,
+            schedule_layout: ScheduleLayout::Plain,
+            state: ContractState::Exhausted,
+        }
+    }
+
+    #[test]
+    fn test_contract_json_is_stable() {
+        let address = Pubkey::new_unique();
+        let header = header();
+        let output = ContractOutput::new(&address, &header).with_schedules(&[VestingSchedule {
+            release_time: 10,
+            amount: 0,
+        }]);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&output, OutputFormat::Json)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "address": address.to_string(),
+                "destination": header.destination_address.to_string(),
+                "mint": header.mint_address.to_string(),
+                "granter": header.granter_address.to_string(),
+                "state": "exhausted",
+                "next_release_time": null,
+                "gas_rebate": 0,
+                "gas_escrow": 0,
+                "unlock_policy": "permissionless",
+                "insolvent": false,
+                "roll_into_final": false,
+                "min_claim_amount": 0,
+                "destination_locked": false,
+                "permit_nonce": 0,
+                "withholding_bps": 0,
+                "max_transfer_per_unlock": 0,
+                "unlock_interval": 0,
+                "revoke_policy": "return-to-granter",
+                "unlock_count": 0,
+                "destination_change_count": 0,
+                "revoke_attempt_count": 0,
+                "has_claimable": false,
+                "paused": false,
+                "revocable": true,
+                "emission_tracked": false,
+                "unlocked_gross": 0,
+                "unlocked_net": 0,
+                "revision": 0,
+                "schedule_count": 1,
+                "schedules": [{ "release_time": 10, "amount": 0 }],
+            })
+        );
+
+        let categorized = ContractOutput::new(&address, &header)
+            .with_schedules(&[VestingSchedule {
+                release_time: 10,
+                amount: 0,
+            }])
+            .with_categories(&[ScheduleCategory::Investor]);
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&categorized, OutputFormat::Json)).unwrap();
+        assert_eq!(
+            json["schedules"],
+            serde_json::json!([{ "release_time": 10, "amount": 0, "category": "investor" }])
+        );
+
+        let analytics = ContractOutput::new(&address, &header).with_analytics(&[
+            VestingSchedule {
+                release_time: 10,
+                amount: 300,
+            },
+            VestingSchedule {
+                release_time: 50,
+                amount: 100,
+            },
+        ]);
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&analytics, OutputFormat::Json)).unwrap();
+        assert_eq!(
+            json["analytics"],
+            serde_json::json!({
+                "weighted_avg_unlock_time": 20,
+                "curve": [
+                    { "release_time": 10, "cumulative_amount": 300 },
+                    { "release_time": 50, "cumulative_amount": 400 },
+                ],
+            })
+        );
+
+        let destination = Pubkey::new_unique();
+        let split = ContractOutput::new(
+            &address,
+            &VestingScheduleHeader {
+                splits: vec![SplitDestination {
+                    destination_address: destination,
+                    bps: 2_000,
+                }],
+                ..header.clone()
+            },
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&split, OutputFormat::Json)).unwrap();
+        assert_eq!(
+            json["splits"],
+            serde_json::json!([{ "destination": destination.to_string(), "bps": 2_000 }])
+        );
+        assert!(render(&split, OutputFormat::Display)
+            .contains(&format!("split:          2000 bps to {}", destination)));
+
+        let exhausted = ContractOutput::new(&address, &header).with_cluster_time(100);
+        assert_eq!(exhausted.due, Some(false));
+        let due = ContractOutput::new(
+            &address,
+            &VestingScheduleHeader {
+                next_release_time: 100,
+                ..header
+            },
+        )
+        .with_cluster_time(100);
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&due, OutputFormat::Json)).unwrap();
+        assert_eq!(json["due"], true);
+        assert!(render(&due, OutputFormat::Display).contains("100 (due by cluster time)"));
+    }
+
+    #[test]
+    fn test_render_list() {
+        let due = vec![
+            DueContractOutput::new(&Pubkey::new_unique(), 5),
+            DueContractOutput::new(&Pubkey::new_unique(), 6),
+        ];
+        assert_eq!(render_list(&due, OutputFormat::Display).lines().count(), 3);
+        let json: Vec<serde_json::Value> =
+            serde_json::from_str(&render_list(&due, OutputFormat::Json)).unwrap();
+        assert_eq!(json[1]["next_release_time"], 6);
+
+        assert_eq!("json".parse(), Ok(OutputFormat::Json));
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_revoke_output() {
+        let address = Pubkey::new_unique();
+        let revoked = RevokeOutput::new::<String>(&address, &Ok(Signature::default()));
+        assert!(render(&revoked, OutputFormat::Display).contains(" revoked in "));
+        let failed = RevokeOutput::new(&address, &Err("not the granter".to_string()));
+        assert_eq!(
+            render(&failed, OutputFormat::Display),
+            format!("{} failed: not the granter", address)
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&failed, OutputFormat::Json)).unwrap();
+        assert_eq!(json["error"], "not the granter");
+        assert!(json.get("signature").is_none());
+    }
+
+    #[test]
+    fn test_utc_date() {
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(951_782_400), "2000-02-29");
+        assert_eq!(utc_date(1_735_689_599), "2024-12-31");
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let contract = Pubkey::new_unique();
+        let entries = vec![
+            LedgerEntryOutput::from(&LedgerEntry {
+                time: 86_400,
+                kind: LedgerKind::Fee,
+                amount: 5000,
+                mint: None,
+                contract,
+                signature: None,
+                category: None,
+            }),
+            LedgerEntryOutput::from(&LedgerEntry {
+                time: 0,
+                kind: LedgerKind::Pending,
+                amount: 10,
+                mint: Some(contract),
+                contract,
+                signature: None,
+                category: Some(ScheduleCategory::Bonus),
+            }),
+        ];
+        let csv = render_csv(&entries);
+        let lines = csv.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines[0],
+            "date,time,kind,amount,asset,contract,signature,category"
+        );
+        assert_eq!(
+            lines[1],
+            format!("1970-01-02,86400,fee,5000,lamports,{},,", contract)
+        );
+        assert_eq!(lines[2].split(',').count(), 8);
+        assert!(lines[2].ends_with(",bonus"));
+        assert_eq!(render_csv::<LedgerEntryOutput>(&[]).lines().count(), 1);
+    }
+}
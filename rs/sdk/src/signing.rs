@@ -0,0 +1,180 @@
+//! Transactions signed in more than one go, for flows where the fee payer isn't at hand when everyone else signs: an
+//! exchange paying for its users' claims, or a payer behind a multisig that only signs once the others have. The
+//! transaction is built with partially_signed, passed around (bincode, like the wire format), each party adds theirs
+//! with add_signatures, and signature_status says who is still missing before it gets sent.
+
+use solana_program::{hash::Hash, instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{
+    signature::Signature,
+    signer::{signers::Signers, SignerError},
+    transaction::Transaction,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignatureStatus {
+    /// Every required signature is there and verifies, the transaction can be sent
+    Complete,
+    Incomplete {
+        /// Haven't signed yet, the fee payer first if it's among them
+        missing: Vec<Pubkey>,
+        /// Signed something else - another message, or the same one with another blockhash
+        invalid: Vec<Pubkey>,
+    },
+}
+
+impl SignatureStatus {
+    pub fn is_complete(&self) -> bool {
+        *self == Self::Complete
+    }
+}
+
+/// A transaction paid for by `fee_payer`, signed by `signers` only. Nothing has to sign yet: `signers` can be empty,
+/// for a transaction that only fixes the message and the blockhash every party then signs
+pub fn partially_signed<T: Signers + ?Sized>(
+    instructions: &[Instruction],
+    fee_payer: &Pubkey,
+    signers: &T,
+    recent_blockhash: Hash,
+) -> Result<Transaction, SignerError> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(fee_payer));
+    transaction.try_partial_sign(signers, recent_blockhash)?;
+    Ok(transaction)
+}
+
+/// Adds the signatures of `signers`, keeping the ones already there. Unlike Transaction::partial_sign this never
+/// touches the blockhash, which would wipe the other parties' signatures. Fails with KeypairPubkeyMismatch if any of
+/// `signers` isn't asked to sign
+pub fn add_signatures<T: Signers + ?Sized>(
+    transaction: &mut Transaction,
+    signers: &T,
+) -> Result<(), SignerError> {
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction.try_partial_sign(signers, recent_blockhash)
+}
+
+/// Who still has to sign, checking the signatures that are there against the message
+pub fn signature_status(transaction: &Transaction) -> SignatureStatus {
+    let mut missing = vec![];
+    let mut invalid = vec![];
+    let verified = transaction.verify_with_results();
+    for ((signature, key), verified) in transaction
+        .signatures
+        .iter()
+        .zip(&transaction.message.account_keys)
+        .zip(verified)
+    {
+        if *signature == Signature::default() {
+            missing.push(*key);
+        } else if !verified {
+            invalid.push(*key);
+        }
+    }
+
+    if missing.is_empty() && invalid.is_empty() {
+        SignatureStatus::Complete
+    } else {
+        SignatureStatus::Incomplete { missing, invalid }
+    }
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::system_instruction;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    /// A transfer out of `from`, so it has to sign next to whoever pays
+    fn transfer(from: &Keypair) -> Vec<Instruction> {
+        vec![system_instruction::transfer(
+            &from.pubkey(),
+            &Pubkey::new_unique(),
+            1,
+        )]
+    }
+
+    #[test]
+    fn test_fee_payer_signs_last() {
+        let (owner, fee_payer) = (Keypair::new(), Keypair::new());
+        let blockhash = Hash::new_unique();
+        let mut transaction =
+            partially_signed(&transfer(&owner), &fee_payer.pubkey(), &[&owner], blockhash).unwrap();
+        assert_eq!(transaction.message.account_keys[0], fee_payer.pubkey());
+        assert_eq!(
+            signature_status(&transaction),
+            SignatureStatus::Incomplete {
+                missing: vec![fee_payer.pubkey()],
+                invalid: vec![],
+            }
+        );
+
+        // as it would reach the fee payer
+        let mut transaction: Transaction =
+            bincode::deserialize(&bincode::serialize(&transaction).unwrap()).unwrap();
+        add_signatures(&mut transaction, &[&fee_payer]).unwrap();
+        assert!(signature_status(&transaction).is_complete());
+        assert_eq!(transaction.message.recent_blockhash, blockhash);
+        transaction.verify().unwrap();
+
+        // signing again changes nothing
+        let signatures = transaction.signatures.clone();
+        add_signatures(&mut transaction, &[&owner]).unwrap();
+        assert_eq!(transaction.signatures, signatures);
+    }
+
+    #[test]
+    fn test_nobody_signs_first() {
+        let (owner, fee_payer) = (Keypair::new(), Keypair::new());
+        let no_signers: [&Keypair; 0] = [];
+        let mut transaction = partially_signed(
+            &transfer(&owner),
+            &fee_payer.pubkey(),
+            &no_signers,
+            Hash::new_unique(),
+        )
+        .unwrap();
+        assert_eq!(
+            signature_status(&transaction),
+            SignatureStatus::Incomplete {
+                missing: vec![fee_payer.pubkey(), owner.pubkey()],
+                invalid: vec![],
+            }
+        );
+
+        // in any order
+        add_signatures(&mut transaction, &[&fee_payer]).unwrap();
+        add_signatures(&mut transaction, &[&owner]).unwrap();
+        assert!(signature_status(&transaction).is_complete());
+    }
+
+    #[test]
+    fn test_stale_and_foreign_signatures() {
+        let (owner, fee_payer) = (Keypair::new(), Keypair::new());
+        let mut transaction = partially_signed(
+            &transfer(&owner),
+            &fee_payer.pubkey(),
+            &[&owner],
+            Hash::new_unique(),
+        )
+        .unwrap();
+
+        // someone who isn't asked to sign
+        assert_eq!(
+            add_signatures(&mut transaction, &[&Keypair::new()]),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+
+        // a blockhash swapped under the owner's signature
+        transaction.message.recent_blockhash = Hash::new_unique();
+        add_signatures(&mut transaction, &[&fee_payer]).unwrap();
+        assert_eq!(
+            signature_status(&transaction),
+            SignatureStatus::Incomplete {
+                missing: vec![],
+                invalid: vec![owner.pubkey()],
+            }
+        );
+        assert!(!signature_status(&transaction).is_complete());
+    }
+}
@@ -0,0 +1,368 @@
+//! The release checklist's lifecycle run, against a real cluster: a fresh mint, Init, Create with a single schedule a
+//! few seconds out, waiting for the cluster clock to get there, Unlock, checking where the tokens ended up, Close.
+//! Every step is reported on its own, so a broken deployment shows where it broke. See the devnet-smoke binary.
+
+use std::{fmt::Write as _, thread, time::Duration, time::Instant};
+
+use solana_client::{client_error::Result as ClientResult, rpc_client::RpcClient};
+use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction, sysvar};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use spl_token::state::Mint;
+
+use crate::{
+    client::{checked_init, cluster_time, custom_error, send_signed, ClientConfig},
+    instruction::{close, create, find_child_vesting_account, unlock, CreateOptions, Schedule},
+    state::{DustPolicy, ScheduleLayout},
+};
+
+/// Every step of a run, in order
+pub const SMOKE_STEPS: [&str; 7] = [
+    "create mint",
+    "init",
+    "create",
+    "wait",
+    "unlock",
+    "verify balances",
+    "close",
+];
+
+#[derive(Clone, Debug)]
+pub struct SmokeConfig {
+    /// Tokens the contract vests, in base units of a 0 decimals mint
+    pub amount: u64,
+    /// How far past the cluster time at Create the schedule releases
+    pub release_delay: Duration,
+    /// How long to wait for the cluster clock to reach the release time before giving up
+    pub wait_timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for SmokeConfig {
+    fn default() -> Self {
+        Self {
+            amount: 1_000,
+            release_delay: Duration::from_secs(10),
+            wait_timeout: Duration::from_secs(120),
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepOutcome {
+    Passed,
+    Failed(String),
+    /// An earlier step failed
+    Skipped,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepResult {
+    pub step: &'static str,
+    pub outcome: StepOutcome,
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SmokeReport {
+    pub steps: Vec<StepResult>,
+}
+
+impl SmokeReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|s| s.outcome == StepOutcome::Passed)
+    }
+
+    /// One line per step, then the verdict
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for result in &self.steps {
+            // writing to a String can't fail
+            let _ = match &result.outcome {
+                StepOutcome::Passed => writeln!(
+                    out,
+                    "PASS {} ({:.1}s)",
+                    result.step,
+                    result.elapsed.as_secs_f64()
+                ),
+                StepOutcome::Failed(error) => writeln!(
+                    out,
+                    "FAIL {} ({:.1}s): {}",
+                    result.step,
+                    result.elapsed.as_secs_f64(),
+                    error
+                ),
+                StepOutcome::Skipped => writeln!(out, "SKIP {}", result.step),
+            };
+        }
+        let _ = writeln!(out, "{}", if self.passed() { "passed" } else { "failed" });
+        out
+    }
+
+    /// Runs `step` unless an earlier one failed
+    fn run(&mut self, step: &'static str, f: impl FnOnce() -> ClientResult<()>) {
+        if !self.passed() {
+            self.steps.push(StepResult {
+                step,
+                outcome: StepOutcome::Skipped,
+                elapsed: Duration::default(),
+            });
+            return;
+        }
+        let started = Instant::now();
+        let outcome = match f() {
+            Ok(()) => StepOutcome::Passed,
+            Err(e) => StepOutcome::Failed(e.to_string()),
+        };
+        self.steps.push(StepResult {
+            step,
+            outcome,
+            elapsed: started.elapsed(),
+        });
+    }
+}
+
+/// Runs every one of SMOKE_STEPS against the deployment at `program_id`. `payer` pays for everything, and is the mint
+/// authority and the granter. The beneficiary is a fresh keypair, so every run starts from nothing
+pub fn run_smoke_test(
+    rpc_client: &RpcClient,
+    config: &ClientConfig,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    smoke: &SmokeConfig,
+) -> SmokeReport {
+    let payer_key = payer.pubkey();
+    let mint = Keypair::new();
+    let mint_key = mint.pubkey();
+    let beneficiary = Keypair::new().pubkey();
+    let source_token_account_key = get_associated_token_address(&payer_key, &mint_key);
+    let destination_token_account_key = get_associated_token_address(&beneficiary, &mint_key);
+    // seeds derived from the mint, so that every run gets a contract of its own
+    let (vesting_account_key, seeds) =
+        find_child_vesting_account(program_id, &mint_key.to_bytes(), 0);
+    let vesting_token_account_key = get_associated_token_address(&vesting_account_key, &mint_key);
+    let mut release_time = 0;
+
+    let mut report = SmokeReport::default();
+    report.run(SMOKE_STEPS[0], || {
+        let mint_rent = rpc_client.get_minimum_balance_for_rent_exemption(Mint::LEN)?;
+        let instructions = vec![
+            system_instruction::create_account(
+                &payer_key,
+                &mint_key,
+                mint_rent,
+                Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint_key,
+                &payer_key,
+                None,
+                0,
+            )
+            .map_err(custom_error)?,
+            create_associated_token_account(&payer_key, &payer_key, &mint_key, &spl_token::id()),
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &mint_key,
+                &source_token_account_key,
+                &payer_key,
+                &[],
+                smoke.amount,
+            )
+            .map_err(custom_error)?,
+            create_associated_token_account(&payer_key, &beneficiary, &mint_key, &spl_token::id()),
+        ];
+        send_signed(rpc_client, config, &instructions, &[payer, &mint])?;
+        Ok(())
+    });
+
+    report.run(SMOKE_STEPS[1], || {
+        let instructions = vec![
+            checked_init(
+                rpc_client,
+                config,
+                program_id,
+                &payer_key,
+                seeds,
+                1,
+                ScheduleLayout::Plain,
+            )?,
+            create_associated_token_account(
+                &payer_key,
+                &vesting_account_key,
+                &mint_key,
+                &spl_token::id(),
+            ),
+        ];
+        send_signed(rpc_client, config, &instructions, &[payer])?;
+        Ok(())
+    });
+
+    report.run(SMOKE_STEPS[2], || {
+        release_time = cluster_time(rpc_client, config)? + smoke.release_delay.as_secs();
+        let instruction = create(
+            program_id,
+            &spl_token::id(),
+            &vesting_account_key,
+            &vesting_token_account_key,
+            &payer_key,
+            &source_token_account_key,
+            &destination_token_account_key,
+            &mint_key,
+            vec![Schedule {
+                release_time,
+                amount: smoke.amount,
+            }],
+            CreateOptions::default(),
+            seeds,
+        )
+        .map_err(custom_error)?;
+        send_signed(rpc_client, config, &[instruction], &[payer])?;
+        Ok(())
+    });
+
+    report.run(SMOKE_STEPS[3], || {
+        let started = Instant::now();
+        loop {
+            let now = cluster_time(rpc_client, config)?;
+            if now >= release_time {
+                return Ok(());
+            }
+            if started.elapsed() > smoke.wait_timeout {
+                return Err(custom_error(format!(
+                    "cluster time {} still short of release time {} after {}s",
+                    now,
+                    release_time,
+                    smoke.wait_timeout.as_secs()
+                )));
+            }
+            thread::sleep(smoke.poll_interval);
+        }
+    });
+
+    report.run(SMOKE_STEPS[4], || {
+        let instruction = unlock(
+            program_id,
+            &spl_token::id(),
+            &sysvar::clock::id(),
+            &vesting_account_key,
+            &vesting_token_account_key,
+            &destination_token_account_key,
+            seeds,
+        )
+        .map_err(custom_error)?;
+        send_signed(rpc_client, config, &[instruction], &[payer])?;
+        Ok(())
+    });
+
+    report.run(SMOKE_STEPS[5], || {
+        let balance = |key: &Pubkey| -> ClientResult<u64> {
+            rpc_client
+                .get_token_account_balance_with_commitment(key, config.commitment)?
+                .value
+                .amount
+                .parse()
+                .map_err(custom_error)
+        };
+        let expected = [
+            ("destination", destination_token_account_key, smoke.amount),
+            ("vesting token account", vesting_token_account_key, 0),
+        ];
+        for (name, key, expected) in expected.iter() {
+            let actual = balance(key)?;
+            if actual != *expected {
+                return Err(custom_error(format!(
+                    "{} {} holds {}, expected {}",
+                    name, key, actual, expected
+                )));
+            }
+        }
+        Ok(())
+    });
+
+    report.run(SMOKE_STEPS[6], || {
+        let instruction = close(
+            program_id,
+            &spl_token::id(),
+            &vesting_account_key,
+            &vesting_token_account_key,
+            &payer_key,
+            &source_token_account_key,
+            DustPolicy::ReturnToGranter,
+            seeds,
+        )
+        .map_err(custom_error)?;
+        send_signed(rpc_client, config, &[instruction], &[payer])?;
+        let left = rpc_client
+            .get_account_with_commitment(&vesting_account_key, config.commitment)?
+            .value;
+        if left.is_some() {
+            return Err(custom_error(format!(
+                "vesting account {} still exists",
+                vesting_account_key
+            )));
+        }
+        Ok(())
+    });
+
+    report
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(step: &'static str, outcome: StepOutcome) -> StepResult {
+        StepResult {
+            step,
+            outcome,
+            elapsed: Duration::from_millis(1_500),
+        }
+    }
+
+    #[test]
+    fn test_steps_after_a_failure_are_skipped() {
+        let mut report = SmokeReport::default();
+        report.run("first", || Ok(()));
+        report.run("second", || Err(custom_error("boom")));
+        report.run("third", || panic!("should have been skipped"));
+
+        let outcomes: Vec<_> = report.steps.iter().map(|s| s.outcome.clone()).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                StepOutcome::Passed,
+                StepOutcome::Failed("Custom: boom".to_string()),
+                StepOutcome::Skipped,
+            ]
+        );
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_render() {
+        let report = SmokeReport {
+            steps: vec![
+                step("init", StepOutcome::Passed),
+                step("create", StepOutcome::Failed("boom".to_string())),
+                step("wait", StepOutcome::Skipped),
+            ],
+        };
+        assert_eq!(
+            report.render(),
+            "PASS init (1.5s)\nFAIL create (1.5s): boom\nSKIP wait\nfailed\n"
+        );
+
+        let report = SmokeReport {
+            steps: vec![step("init", StepOutcome::Passed)],
+        };
+        assert!(report.render().ends_with("\npassed\n"));
+    }
+}
@@ -0,0 +1,317 @@
+//! What a crank running as a service remembers between runs and restarts, in a sled database: for every contract it
+//! went through, when it last unlocked it and how it has been failing since. Contracts that keep failing (eg an
+//! insolvent one, or a destination account that was closed) are backed off exponentially instead of costing a
+//! transaction on every run.
+
+use std::{fmt, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+
+use crate::crank::{CrankReport, UnlockJob};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContractRecord {
+    /// Cluster time of the last unlock that landed
+    pub last_unlock_time: Option<u64>,
+    pub last_signature: Option<String>,
+    /// Runs in a row the contract ended up in CrankReport::failed, reset by an unlock that lands
+    pub consecutive_failures: u32,
+    pub last_failure_time: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl ContractRecord {
+    /// Until when the contract is left alone, None if it isn't
+    pub fn backed_off_until(&self, backoff: &FailureBackoff) -> Option<u64> {
+        let last_failure_time = self.last_failure_time?;
+        if self.consecutive_failures == 0 {
+            return None;
+        }
+        // doubles with every failure, 2^31 times the base is already far past any sensible max
+        let exponent = (self.consecutive_failures - 1).min(31);
+        let delay = backoff
+            .base
+            .as_secs()
+            .saturating_mul(1 << exponent)
+            .min(backoff.max.as_secs());
+        Some(last_failure_time.saturating_add(delay))
+    }
+}
+
+/// How long a failing contract is skipped for: `base` after its first failure, twice as long after every further
+/// one, never more than `max`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FailureBackoff {
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl Default for FailureBackoff {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(60),
+            max: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Db(sled::Error),
+    /// A record that doesn't deserialize, eg written by an incompatible version
+    Corrupt {
+        key: Pubkey,
+        error: String,
+    },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Db(e) => write!(f, "crank store: {}", e),
+            Self::Corrupt { key, error } => {
+                write!(f, "crank store: corrupt record for {}: {}", key, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<sled::Error> for StoreError {
+    fn from(e: sled::Error) -> Self {
+        Self::Db(e)
+    }
+}
+
+/// Records keyed by vesting account
+pub struct CrankStore {
+    db: sled::Db,
+}
+
+impl CrankStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Ok(Self {
+            db: sled::open(path)?,
+        })
+    }
+
+    /// A store that's gone once dropped, for dry runs
+    pub fn temporary() -> Result<Self, StoreError> {
+        Ok(Self {
+            db: sled::Config::new().temporary(true).open()?,
+        })
+    }
+
+    pub fn get(&self, vesting_account_key: &Pubkey) -> Result<Option<ContractRecord>, StoreError> {
+        self.db
+            .get(vesting_account_key)?
+            .map(|bytes| decode(vesting_account_key, &bytes))
+            .transpose()
+    }
+
+    fn put(&self, vesting_account_key: &Pubkey, record: &ContractRecord) -> Result<(), StoreError> {
+        // serializing a plain struct into a Vec can't fail
+        let bytes = bincode::serialize(record).unwrap();
+        self.db.insert(vesting_account_key, bytes)?;
+        Ok(())
+    }
+
+    /// Every contract the crank went through, in key order
+    pub fn records(&self) -> Result<Vec<(Pubkey, ContractRecord)>, StoreError> {
+        self.db
+            .iter()
+            .map(|entry| {
+                let (key, bytes) = entry?;
+                let key = Pubkey::new(&key);
+                Ok((key, decode(&key, &bytes)?))
+            })
+            .collect()
+    }
+
+    /// Updates the records of every contract in a run_unlocks report that ended at cluster time `now`, and flushes
+    /// them to disk - what landed shouldn't be forgotten on a crash
+    pub fn record(&self, report: &CrankReport, now: u64) -> Result<(), StoreError> {
+        for (key, signature) in &report.unlocked {
+            let mut record = self.get(key)?.unwrap_or_default();
+            record.last_unlock_time = Some(now);
+            record.last_signature = Some(signature.to_string());
+            record.consecutive_failures = 0;
+            self.put(key, &record)?;
+        }
+        for (key, error) in &report.failed {
+            let mut record = self.get(key)?.unwrap_or_default();
+            record.consecutive_failures = record.consecutive_failures.saturating_add(1);
+            record.last_failure_time = Some(now);
+            record.last_error = Some(error.clone());
+            self.put(key, &record)?;
+        }
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// The jobs whose contracts aren't backed off at `now`, in order
+    pub fn ready_jobs(
+        &self,
+        jobs: Vec<UnlockJob>,
+        now: u64,
+        backoff: &FailureBackoff,
+    ) -> Result<Vec<UnlockJob>, StoreError> {
+        let mut ready = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            let backed_off = self
+                .get(&job.vesting_account_key)?
+                .and_then(|record| record.backed_off_until(backoff))
+                .is_some_and(|until| now < until);
+            if !backed_off {
+                ready.push(job);
+            }
+        }
+        Ok(ready)
+    }
+}
+
+fn decode(key: &Pubkey, bytes: &[u8]) -> Result<ContractRecord, StoreError> {
+    bincode::deserialize(bytes).map_err(|e| StoreError::Corrupt {
+        key: *key,
+        error: e.to_string(),
+    })
+}
+
+// ----------------------------------------------------------------------------- tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::instruction::Instruction;
+    use solana_sdk::signature::Signature;
+
+    fn job(vesting_account_key: Pubkey) -> UnlockJob {
+        UnlockJob {
+            vesting_account_key,
+            instruction: Instruction {
+                program_id: Pubkey::new_unique(),
+                accounts: vec![],
+                data: vec![],
+            },
+        }
+    }
+
+    fn backoff() -> FailureBackoff {
+        FailureBackoff {
+            base: Duration::from_secs(10),
+            max: Duration::from_secs(35),
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let store = CrankStore::temporary().unwrap();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let signature = Signature::new_unique();
+        let report = |unlocked: Vec<Pubkey>, failed: Vec<Pubkey>| CrankReport {
+            unlocked: unlocked.into_iter().map(|k| (k, signature)).collect(),
+            failed: failed
+                .into_iter()
+                .map(|k| (k, "failed".to_string()))
+                .collect(),
+            ..CrankReport::default()
+        };
+
+        store.record(&report(vec![a], vec![b]), 100).unwrap();
+        store.record(&report(vec![], vec![b]), 200).unwrap();
+        assert_eq!(
+            store.get(&a).unwrap(),
+            Some(ContractRecord {
+                last_unlock_time: Some(100),
+                last_signature: Some(signature.to_string()),
+                ..ContractRecord::default()
+            })
+        );
+        assert_eq!(
+            store.get(&b).unwrap(),
+            Some(ContractRecord {
+                consecutive_failures: 2,
+                last_failure_time: Some(200),
+                last_error: Some("failed".to_string()),
+                ..ContractRecord::default()
+            })
+        );
+        assert_eq!(store.get(&Pubkey::new_unique()).unwrap(), None);
+        assert_eq!(store.records().unwrap().len(), 2);
+
+        // an unlock that lands clears the streak, the last error stays for reference
+        store.record(&report(vec![b], vec![]), 300).unwrap();
+        let record = store.get(&b).unwrap().unwrap();
+        assert_eq!(record.consecutive_failures, 0);
+        assert_eq!(record.last_error, Some("failed".to_string()));
+    }
+
+    #[test]
+    fn test_backed_off_until() {
+        let failing = |consecutive_failures| ContractRecord {
+            consecutive_failures,
+            last_failure_time: Some(1_000),
+            ..ContractRecord::default()
+        };
+        assert_eq!(ContractRecord::default().backed_off_until(&backoff()), None);
+        assert_eq!(failing(0).backed_off_until(&backoff()), None);
+        assert_eq!(failing(1).backed_off_until(&backoff()), Some(1_010));
+        assert_eq!(failing(2).backed_off_until(&backoff()), Some(1_020));
+        assert_eq!(failing(3).backed_off_until(&backoff()), Some(1_035));
+        assert_eq!(failing(u32::MAX).backed_off_until(&backoff()), Some(1_035));
+    }
+
+    #[test]
+    fn test_ready_jobs() {
+        let store = CrankStore::temporary().unwrap();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        store
+            .record(
+                &CrankReport {
+                    failed: vec![(a, "failed".to_string())],
+                    ..CrankReport::default()
+                },
+                100,
+            )
+            .unwrap();
+
+        let keys = |jobs: Vec<UnlockJob>| -> Vec<Pubkey> {
+            jobs.into_iter().map(|j| j.vesting_account_key).collect()
+        };
+        let ready = store
+            .ready_jobs(vec![job(a), job(b)], 105, &backoff())
+            .unwrap();
+        assert_eq!(keys(ready), vec![b]);
+        let ready = store
+            .ready_jobs(vec![job(a), job(b)], 110, &backoff())
+            .unwrap();
+        assert_eq!(keys(ready), vec![a, b]);
+    }
+
+    #[test]
+    fn test_reopen_keeps_records() {
+        let path = std::env::temp_dir().join(format!("crank-store-{}", Pubkey::new_unique()));
+        let key = Pubkey::new_unique();
+        {
+            let store = CrankStore::open(&path).unwrap();
+            store
+                .record(
+                    &CrankReport {
+                        unlocked: vec![(key, Signature::default())],
+                        ..CrankReport::default()
+                    },
+                    100,
+                )
+                .unwrap();
+        }
+        let store = CrankStore::open(&path).unwrap();
+        assert_eq!(
+            store.get(&key).unwrap().unwrap().last_unlock_time,
+            Some(100)
+        );
+        drop(store);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -1,7 +0,0 @@
-#[cfg(not(feature = "no-entrypoint"))]
-pub mod entrypoint;
-
-pub mod error;
-pub mod instruction;
-pub mod processor;
-pub mod state;